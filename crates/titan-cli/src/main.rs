@@ -1,6 +1,7 @@
 use anyhow::{Context, Result, bail};
 use clap::{Parser, Subcommand};
 use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use serenity::all::{GatewayIntents, Message, Ready};
 use serenity::async_trait;
@@ -13,34 +14,65 @@ use std::process::Command as ProcessCommand;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-use titan_common::config::{AutonomyMode, ModelProvider, TitanConfig};
+use titan_common::config::{
+    AutonomyMode, BackupConfig, EmbeddingProvider, ModelProvider, TitanConfig,
+};
 use titan_common::{APP_NAME, logging};
+use titan_comms::telegram::{TelegramBot, approval_keyboard};
 use titan_comms::{ChannelKind, channel_send, channel_status};
 use titan_connectors::{
-    CompositeSecretResolver, ConnectorType, execute_connector_tool_after_approval, test_connector,
+    CompositeSecretResolver, ConnectorAddPayload, ConnectorType, SecretResolver, add_connector,
+    apply_connector_change_approval, check_connector_config, connector_config_schema,
+    connector_config_with_fields, connector_event_kinds, connector_tools_for,
+    default_connector_config, execute_connector_tool_after_approval, parse_config_field,
+    test_connector,
 };
 use titan_core::{
-    Goal, GoalAttemptBehavior, GoalExecutionConfig, GoalJob, GoalStatus, Runtime, SubagentConfig,
-    SubagentOrchestrator, SubagentTask, SubmitOutcome, TraceEvent,
+    Goal, GoalAttemptBehavior, GoalExecutionConfig, GoalJob, GoalStatus, ReflectionGenerator,
+    Runtime, StructuredReflection, SubagentConfig, SubagentOrchestrator, SubagentTask,
+    SubmitOutcome, TaskRunResult, TraceEvent, TraceVerbosity,
 };
 use titan_discord::DiscordGateway;
-use titan_gateway::{Channel as GatewayChannel, InboundEvent, TitanGatewayRuntime};
-use titan_memory::{MemoryStore, NewJobRecord, RiskMode};
+use titan_gateway::{
+    Channel as GatewayChannel, CrashReporter, EventBus, InboundEvent, NOTIFICATION_EVENT_KINDS,
+    TitanGatewayRuntime, WEBHOOK_EVENT_KINDS, WriterLease, approval_edit_refusal,
+    approval_notice_targets, approval_quorum_shortfall, approver_refusal, complete_slash_command,
+    deliver_outbox, dispatch_notification, dispatch_webhooks, notify_approval_resolved,
+    parse_quiet_hours, process_writer_id, remote_writer, route_telegram_update, scrub_diagnostic,
+    sync_remote_goals,
+};
+use titan_memory::approval_payload::{
+    AllowlistAction, ConfigAllowlistPayload, SkillExecGrantPayload, decode_approval_payload,
+    encode_approval_payload,
+};
+use titan_memory::s3::{S3Client, S3Credentials, S3Object, parse_s3_url};
+use titan_memory::{
+    ApprovalRecord, GATEWAY_WRITER_LEASE, GOAL_LABEL_USER, GoalFilter, MaintenanceReport,
+    MemoryStore, NewJobRecord, NotificationPrefRecord, RiskMode, TraceView, WriterLeaseRecord,
+};
 use titan_secrets::{SecretsStatus, SecretsStore};
 use titan_skills::{
-    LocalRegistryAdapter, SkillPackage, SkillRegistryAdapter, SkillRunState,
-    approval_payload_for_stage, deny_unsigned_risky_install, deserialize_approval_payload,
-    finalize_install_from_payload, inspect_registry_v1, list_installed_skills_v1,
-    remove_installed_skill_v1, run_skill_v1, search_registry_v1, serialize_approval_payload,
-    stage_install_v1,
+    NamedRegistry, PublishOptions, SkillEntrypointType, SkillPackage, SkillRunState,
+    SkillScaffoldOptions, WasmLimits, approval_payload_for_stage, configured_registries,
+    deny_unsigned_risky_install, deserialize_approval_payload, finalize_install_from_payload,
+    inspect_registry_v1, list_installed_skills_v1, publish_to_local_registry,
+    registry_adapter_from_source, remove_installed_skill_v1, run_skill_v1, scaffold_skill_v1,
+    search_registry_v1, serialize_approval_payload, sign_skill_bundle_v1,
+    stage_install_from_registries, test_skill_v1,
+};
+use titan_tools::{
+    LintLevel, PolicyEngine, PolicyFile, ToolExecutionContext, ToolExecutor, ToolRegistry,
+    ToolRiskMode,
 };
-use titan_tools::{PolicyEngine, ToolExecutionContext, ToolExecutor, ToolRegistry, ToolRiskMode};
 use titan_web as web_runtime;
 use uuid::Uuid;
 
 #[derive(Debug, Parser)]
 #[command(name = "titan", about = "TITAN agent platform CLI", version)]
 struct Cli {
+    /// Print one JSON document instead of text (status, goal, approval, skill, connector).
+    #[arg(long, global = true)]
+    json: bool,
     #[command(subcommand)]
     command: Option<Command>,
 }
@@ -49,6 +81,13 @@ struct Cli {
 enum Command {
     /// Validate local setup and generate default config if missing.
     Doctor,
+    /// Show mode, risk state, queue depth, and the writer lease.
+    Status,
+    /// Complete a partial chat slash command, e.g. `titan complete /sk`.
+    Complete {
+        /// Partial input; quote it to complete arguments (`"/mode co"`).
+        input: String,
+    },
     /// Run core runtime services (Discord loop + web UI).
     Run {
         #[arg(long, default_value = "127.0.0.1:3000")]
@@ -121,6 +160,41 @@ enum Command {
         #[command(subcommand)]
         command: CommCommand,
     },
+    /// Per-operator notification routing.
+    Notify {
+        #[command(subcommand)]
+        command: NotifyCommand,
+    },
+    /// Trace verbosity levels and sampling stats.
+    Trace {
+        #[command(subcommand)]
+        command: TraceCommand,
+    },
+    /// Outbound webhooks for lifecycle events.
+    Webhook {
+        #[command(subcommand)]
+        command: WebhookCommand,
+    },
+    /// Database maintenance (checkpoint, vacuum, integrity).
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+    /// Queued channel messages (notifications and run replies).
+    Outbox {
+        #[command(subcommand)]
+        command: OutboxCommand,
+    },
+    /// Goals forwarded to federation peers (`[[federation]]` in config).
+    Federation {
+        #[command(subcommand)]
+        command: FederationCommand,
+    },
+    /// Single-writer lease held by the running gateway.
+    Lease {
+        #[command(subcommand)]
+        command: LeaseCommand,
+    },
     /// LLM model configuration commands.
     Model {
         #[command(subcommand)]
@@ -133,12 +207,23 @@ enum Command {
     },
     /// Set risk mode quickly.
     Mode { risk_mode: String },
+    /// Local crash reports from panics and failed commands.
+    Crash {
+        #[command(subcommand)]
+        command: CrashCommand,
+    },
+    /// Safe mode: supervised execution with schedules and connector triggers paused.
+    SafeMode {
+        #[command(subcommand)]
+        command: SafeModeCommand,
+    },
     /// Encrypted local secrets store operations.
     Secrets {
         #[command(subcommand)]
         command: SecretsCommand,
     },
     /// Connector lifecycle and health commands.
+    #[command(alias = "connectors")]
     Connector {
         #[command(subcommand)]
         command: ConnectorCommand,
@@ -158,6 +243,61 @@ enum Command {
         #[command(subcommand)]
         command: AgentCommand,
     },
+    /// Inspect and validate the approval policy file (`policy.toml`).
+    Policy {
+        #[command(subcommand)]
+        command: PolicyCommand,
+    },
+    /// Declarative instance setup from `titan.bootstrap.toml`.
+    Bootstrap {
+        #[command(subcommand)]
+        command: BootstrapCommand,
+    },
+    /// Export DB, config, installed skills, and trust keys to a `.tar.zst` bundle.
+    Export {
+        #[arg(long)]
+        out: PathBuf,
+        /// Include `secrets.enc` and the Discord token from config.
+        #[arg(long, default_value_t = false)]
+        include_secrets: bool,
+    },
+    /// Encrypted state bundles in an S3-compatible bucket (`[backup]` in config).
+    Backup {
+        #[command(subcommand)]
+        command: BackupCommand,
+    },
+    /// Restore a bundle written by `titan export`.
+    Import {
+        bundle: PathBuf,
+        /// Replace existing state in the workspace.
+        #[arg(long, default_value_t = false)]
+        force: bool,
+        /// Leave the local secrets store untouched even if the bundle has one.
+        #[arg(long, default_value_t = false)]
+        skip_secrets: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum BackupCommand {
+    /// Export state, encrypt it, upload it, and prune to `backup.keep_last`.
+    Push,
+    /// List backups in the target, newest first.
+    List,
+    /// Delete all but the newest `backup.keep_last` backups.
+    Prune,
+    /// Download, decrypt, and import a backup.
+    Restore {
+        /// `s3://<bucket>/<key>`, or `latest` for the newest backup in the target.
+        #[arg(long)]
+        from: String,
+        /// Replace existing state in the workspace.
+        #[arg(long, default_value_t = false)]
+        force: bool,
+        /// Leave the local secrets store untouched even if the backup has one.
+        #[arg(long, default_value_t = false)]
+        skip_secrets: bool,
+    },
 }
 
 #[derive(Debug, Subcommand)]
@@ -178,6 +318,50 @@ enum GoalCommand {
     Show { goal_id: String },
     /// Cancel a goal by id.
     Cancel { goal_id: String },
+    /// List recent goals with their labels, newest first.
+    List {
+        #[arg(long)]
+        status: Option<String>,
+        /// Repeat to require several labels.
+        #[arg(long = "label")]
+        labels: Vec<String>,
+        /// Start from a saved filter of `--operator`.
+        #[arg(long, requires = "operator")]
+        filter: Option<String>,
+        #[arg(long)]
+        operator: Option<String>,
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Add labels to a goal, or remove them with `--remove`.
+    Label {
+        goal_id: String,
+        labels: Vec<String>,
+        #[arg(long)]
+        remove: Vec<String>,
+    },
+    /// Saved goal filters, kept per operator.
+    Filter {
+        #[command(subcommand)]
+        command: GoalFilterCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum GoalFilterCommand {
+    /// Save a status and label filter under a name, replacing one of the same name.
+    Save {
+        operator: String,
+        name: String,
+        #[arg(long)]
+        status: Option<String>,
+        #[arg(long = "label")]
+        labels: Vec<String>,
+    },
+    /// List an operator's saved filters.
+    List { operator: String },
+    /// Remove a saved filter.
+    Remove { operator: String, name: String },
 }
 
 #[derive(Debug, Subcommand)]
@@ -187,9 +371,12 @@ enum ToolCommand {
         tool_name: String,
         #[arg(long)]
         input: Option<String>,
-        #[arg(long, default_value_t = 300_000)]
-        approval_ttl_ms: u64,
+        /// Defaults to `[approvals]` in config.toml for the tool's capability class.
+        #[arg(long)]
+        approval_ttl_ms: Option<u64>,
     },
+    /// Print the full stored output of a tool run, fetching offloaded output from the blob store.
+    Output { run_id: String },
 }
 
 #[derive(Debug, Subcommand)]
@@ -209,12 +396,21 @@ enum ApprovalCommand {
         approval_id: String,
         #[arg(long)]
         reason: Option<String>,
+        /// Approver name; counts toward policy quorums.
+        #[arg(long, default_value = "cli")]
+        by: String,
+        /// Run this input instead of the requested one; the original is kept on the request.
+        #[arg(long)]
+        input: Option<String>,
     },
     /// Deny a pending request.
     Deny {
         approval_id: String,
         #[arg(long)]
         reason: Option<String>,
+        /// Approver name; checked against the capability's approver allowlist.
+        #[arg(long, default_value = "cli")]
+        by: String,
     },
 }
 
@@ -230,6 +426,8 @@ enum MemoryCommand {
     Backup { path: PathBuf },
     /// Restore sqlite memory DB from a backup file.
     Restore { path: PathBuf },
+    /// Embed memories that have no vector yet and list vector sizes per namespace.
+    Embed,
 }
 
 #[derive(Debug, Subcommand)]
@@ -251,6 +449,22 @@ enum SessionCommand {
     },
     /// Stop a session run queue.
     Stop { session_id: String },
+    /// Pin a fact, or a message by `#<id>`, so it stays in context through compaction.
+    Pin { session_id: String, target: String },
+    /// Remove a pin.
+    Unpin { session_id: String, pin_id: i64 },
+    /// Show the session as it stood at a past moment (epoch milliseconds).
+    AsOf {
+        session_id: String,
+        #[arg(long)]
+        at_ms: i64,
+    },
+    /// Compact and close sessions idle for a number of days, summarizing them into memory.
+    CloseIdle {
+        /// Defaults to `chat.session_idle_close_days`.
+        #[arg(long)]
+        older_than_days: Option<u64>,
+    },
 }
 
 #[derive(Debug, Subcommand)]
@@ -308,6 +522,157 @@ enum CommCommand {
     },
 }
 
+#[derive(Debug, Subcommand)]
+enum PolicyCommand {
+    /// Validate a policy file; exits non-zero when it has errors.
+    Lint {
+        /// Defaults to the configured policy path.
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+    /// Show the policy currently in force.
+    Show,
+}
+
+#[derive(Debug, Subcommand)]
+enum BootstrapCommand {
+    /// Reconcile connectors, skills, allowlist, personas, and scheduled goals with the file.
+    Apply {
+        #[arg(long, default_value = "titan.bootstrap.toml")]
+        file: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum NotifyCommand {
+    /// Route an event kind (or `*`) for an operator to a channel target.
+    Set {
+        operator: String,
+        event_kind: String,
+        #[arg(long)]
+        channel: String,
+        #[arg(long)]
+        target: String,
+        /// UTC window during which nothing is sent, e.g. `22:00-07:00`.
+        #[arg(long)]
+        quiet_hours: Option<String>,
+    },
+    /// List notification preferences.
+    List,
+    /// Remove an operator's preference for an event kind.
+    Remove {
+        operator: String,
+        event_kind: String,
+    },
+    /// Show recent notification deliveries.
+    Log {
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Send a digest of pending approvals and recent goals.
+    Digest,
+}
+
+#[derive(Debug, Subcommand)]
+enum TraceCommand {
+    /// Set trace verbosity (minimal|normal|debug) globally, per channel, or per goal.
+    Level {
+        level: String,
+        #[arg(long, conflicts_with = "goal")]
+        channel: Option<String>,
+        #[arg(long)]
+        goal: Option<String>,
+    },
+    /// Remove a verbosity override so the next scope applies.
+    Clear {
+        #[arg(long, conflicts_with = "goal")]
+        channel: Option<String>,
+        #[arg(long)]
+        goal: Option<String>,
+    },
+    /// List configured verbosity levels.
+    Levels,
+    /// Show how many events of each type a goal produced and how many were kept.
+    Stats { goal_id: String },
+    /// Align two runs of a recurring goal and show where plan, steps, timing, or outcome differ.
+    Diff { goal_a: String, goal_b: String },
+}
+
+#[derive(Debug, Subcommand)]
+enum WebhookCommand {
+    /// List configured webhooks and the events they receive.
+    List,
+    /// Show recent webhook deliveries.
+    Deliveries {
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Queue new lifecycle events and send due deliveries once.
+    Dispatch,
+}
+
+#[derive(Debug, Subcommand)]
+enum DbCommand {
+    /// Show database size, free pages, and the last maintenance run.
+    Status,
+    /// Checkpoint the WAL, ANALYZE, check index integrity, and VACUUM when worthwhile.
+    Maintain {
+        /// VACUUM even if free pages are below the configured ratio.
+        #[arg(long)]
+        vacuum: bool,
+    },
+    /// Move finished goals' traces and steps into a compressed archive (see `[archive]`).
+    Archive {
+        /// Overrides `archive.after_days`.
+        #[arg(long)]
+        older_than_days: Option<u64>,
+    },
+    /// Print an archived goal's stored rows as JSON.
+    ShowArchived { goal_id: String },
+    /// Find rows that point at missing approvals, plans, or messages; `--fix` repairs them.
+    Repair {
+        /// Apply the repairs in one transaction instead of only reporting.
+        #[arg(long)]
+        fix: bool,
+        /// Goals and sessions untouched for less than this are treated as in flight.
+        #[arg(long, default_value_t = 60)]
+        stale_minutes: u64,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum OutboxCommand {
+    /// Show recent outbox rows.
+    List {
+        /// Only rows with this status (pending, delivered, failed).
+        #[arg(long)]
+        status: Option<String>,
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Send every due row once.
+    Deliver,
+}
+
+#[derive(Debug, Subcommand)]
+enum FederationCommand {
+    /// List configured peers and recently forwarded goals.
+    Status {
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Pull status and traces for forwarded goals that have not finished.
+    Sync,
+}
+
+#[derive(Debug, Subcommand)]
+enum LeaseCommand {
+    /// Show which process holds the writer lease.
+    Show,
+    /// Clear a stale lease left by a process that did not exit cleanly.
+    Release,
+}
+
 #[derive(Debug, Subcommand)]
 enum ModelCommand {
     /// Show the currently configured model provider and id.
@@ -331,14 +696,56 @@ enum ModelCommand {
 #[derive(Debug, Subcommand)]
 enum YoloCommand {
     Status,
-    Arm,
+    /// Show arm/enable/disable/expiry history, newest first.
+    History {
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    Arm {
+        /// Recorded in risk-state history.
+        #[arg(long)]
+        reason: Option<String>,
+    },
     Enable {
         code: String,
         phrase: String,
         #[arg(long, default_value_t = 15)]
         ttl: i64,
+        /// Recorded in risk-state history.
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    Disable {
+        /// Recorded in risk-state history.
+        #[arg(long)]
+        reason: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum CrashCommand {
+    List {
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Print one report as JSON.
+    Show { id: String },
+    /// Post one report, or every unsubmitted one, to `crash_reports.endpoint`.
+    Submit { id: Option<String> },
+}
+
+#[derive(Debug, Subcommand)]
+enum SafeModeCommand {
+    Status,
+    Enter {
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Leave safe mode; failures before this point no longer count toward the breaker.
+    Resume {
+        #[arg(long)]
+        reason: Option<String>,
     },
-    Disable,
 }
 
 #[derive(Debug, Subcommand)]
@@ -349,22 +756,34 @@ enum SecretsCommand {
     Unlock,
     /// Lock encrypted secrets store for this process.
     Lock,
+    /// Store a secret value (prompted) under `key_id`, e.g. `backup:passphrase`.
+    Set { key_id: String },
 }
 
 #[derive(Debug, Subcommand)]
 enum ConnectorCommand {
     /// List configured connectors.
     List,
-    /// Add a connector row.
+    /// Add a connector row; its config is validated against the type's schema.
     Add {
         connector_type: String,
         #[arg(long)]
         name: Option<String>,
+        /// Config field to set over the type's defaults, as `field=value` (repeatable).
+        #[arg(long = "field", value_name = "FIELD=VALUE")]
+        fields: Vec<String>,
+        /// Prompt for each config field, re-asking until the value is valid.
+        #[arg(long, default_value_t = false, conflicts_with = "fields")]
+        interactive: bool,
     },
     /// Configure connector fields and secret material.
     Configure { id: String },
+    /// List connector types, built-in and registered, with their tools.
+    Types,
     /// Run connector health check and persist last test status.
     Test { id: String },
+    /// List tools exposed by a connector with scopes and risk class.
+    Tools { id: String },
     /// Remove connector by id.
     Remove { id: String },
 }
@@ -377,13 +796,16 @@ enum SkillCommand {
         #[arg(long, default_value = "local")]
         source: String,
     },
-    /// Install a skill from a registry.
+    /// Install a skill from a registry (configured registries in priority order by default).
     Install {
         skill: String,
-        #[arg(long, default_value = "local")]
-        source: String,
+        #[arg(long)]
+        source: Option<String>,
         #[arg(long, default_value_t = false)]
         force: bool,
+        /// Allow moving a locked skill to a different registry (always requires approval).
+        #[arg(long, default_value_t = false)]
+        allow_source_switch: bool,
     },
     /// List installed skills.
     List,
@@ -398,21 +820,67 @@ enum SkillCommand {
         #[arg(long, default_value_t = false)]
         all: bool,
         slug: Option<String>,
-        #[arg(long, default_value = "local")]
-        source: String,
+        #[arg(long)]
+        source: Option<String>,
         #[arg(long, default_value_t = false)]
         force: bool,
+        #[arg(long, default_value_t = false)]
+        allow_source_switch: bool,
     },
     /// Remove installed skill by slug.
     Remove { slug: String },
     /// Validate installed skill against lock/hash/signature policy.
     Doctor { slug: String },
+    /// Lift a scope-violation quarantine from a skill.
+    Release { slug: String },
     /// Run an installed skill through broker + policy.
     Run {
         slug: String,
         #[arg(long)]
         input: Option<String>,
     },
+    /// Scaffold a new skill bundle, optionally signing and registering it locally.
+    New {
+        slug: String,
+        #[arg(long, default_value = "prompt")]
+        entrypoint_type: String,
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long, default_value = "0.1.0")]
+        version: String,
+        #[arg(long)]
+        description: Option<String>,
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// File holding a base64 ed25519 secret key used to sign the bundle.
+        #[arg(long)]
+        sign_key: Option<PathBuf>,
+        #[arg(long, default_value = "local")]
+        key_id: String,
+        #[arg(long, default_value_t = false)]
+        register: bool,
+    },
+    /// Validate a bundle and publish it into a local registry index.
+    Publish {
+        bundle_dir: PathBuf,
+        /// Registry root (defaults to the local registry).
+        #[arg(long)]
+        registry: Option<PathBuf>,
+        /// Point the index at the bundle in place instead of copying it.
+        #[arg(long, default_value_t = false)]
+        no_copy: bool,
+        /// Replace an already-published version whose hash differs.
+        #[arg(long, default_value_t = false)]
+        overwrite: bool,
+    },
+    /// Dry-run an installed skill in a sandbox copy of the workspace and report violations.
+    Test {
+        slug: String,
+        #[arg(long)]
+        input: Option<String>,
+        #[arg(long, default_value_t = false)]
+        keep_sandbox: bool,
+    },
     /// Validate skill manifest and wasm binary.
     Validate { skill_dir: PathBuf },
 }
@@ -423,6 +891,9 @@ enum WebCommand {
     Serve {
         #[arg(long, default_value = "127.0.0.1:3000")]
         bind: String,
+        /// Read-only dashboard for non-admin viewers: trace details are redacted.
+        #[arg(long)]
+        viewer: bool,
     },
 }
 
@@ -440,8 +911,42 @@ enum AgentCommand {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let label = cli.command.as_ref().map(command_label).unwrap_or_default();
+    let reporter = configured_crash_reporter();
+    if let Some(reporter) = &reporter {
+        reporter.clone().install_panic_hook();
+    }
+    let result = dispatch(cli);
+    if let (Err(err), Some(reporter)) = (&result, &reporter) {
+        let _ = reporter.capture("error", &format!("titan {label}"), &format!("{err:#}"));
+    }
+    result
+}
+
+/// Top-level subcommand name only; the rest of the Debug output can carry arguments.
+fn command_label(command: &Command) -> String {
+    format!("{command:?}")
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// Reporter for an existing config with crash reports enabled. Never creates a config.
+fn configured_crash_reporter() -> Option<CrashReporter> {
+    let config = TitanConfig::load(&TitanConfig::resolve_path()).ok()?;
+    config
+        .crash_reports
+        .enabled
+        .then(|| CrashReporter::new(&config.workspace_dir, config.crash_reports.trace_events))
+}
+
+fn dispatch(cli: Cli) -> Result<()> {
+    let json = cli.json;
     match cli.command {
         Some(Command::Doctor) => doctor(),
+        Some(Command::Status) => status(json),
+        Some(Command::Complete { input }) => complete(&input, json),
         Some(Command::Run {
             bind,
             poll_interval_ms,
@@ -458,22 +963,43 @@ fn main() -> Result<()> {
             install_daemon,
             yes,
         }) => onboard(install_daemon, yes),
-        Some(Command::Goal { command }) => goal(command),
+        Some(Command::Goal { command }) => goal(command, json),
         Some(Command::Tool { command }) => tool(command),
-        Some(Command::Approval { command }) => approval(command),
+        Some(Command::Approval { command }) => approval(command, json),
         Some(Command::Memory { command }) => memory(command),
         Some(Command::Session { command }) => session(command),
         Some(Command::Job { command }) => job(command),
         Some(Command::Discord { command }) => discord(command),
         Some(Command::Comm { command }) => comm(command),
+        Some(Command::Notify { command }) => notify(command),
+        Some(Command::Trace { command }) => trace(command),
+        Some(Command::Webhook { command }) => webhook(command),
+        Some(Command::Db { command }) => db(command),
+        Some(Command::Federation { command }) => federation(command),
+        Some(Command::Lease { command }) => lease(command),
+        Some(Command::Outbox { command }) => outbox(command),
         Some(Command::Model { command }) => model(command),
         Some(Command::Yolo { command }) => yolo(command),
         Some(Command::Mode { risk_mode }) => mode_risk(&risk_mode),
+        Some(Command::SafeMode { command }) => safe_mode(command),
+        Some(Command::Crash { command }) => crash(command),
         Some(Command::Secrets { command }) => secrets(command),
-        Some(Command::Connector { command }) => connector(command),
-        Some(Command::Skill { command }) => skill(command),
+        Some(Command::Connector { command }) => connector(command, json),
+        Some(Command::Skill { command }) => skill(command, json),
         Some(Command::Web { command }) => web(command),
         Some(Command::Agent { command }) => agent(command),
+        Some(Command::Policy { command }) => policy(command),
+        Some(Command::Bootstrap { command }) => bootstrap(command),
+        Some(Command::Export {
+            out,
+            include_secrets,
+        }) => export_command(&out, include_secrets),
+        Some(Command::Import {
+            bundle,
+            force,
+            skip_secrets,
+        }) => import_command(&bundle, force, skip_secrets),
+        Some(Command::Backup { command }) => backup(command),
         None => {
             println!("{APP_NAME} CLI bootstrap complete.");
             println!("Run `titan doctor` to generate and validate local config.");
@@ -482,10 +1008,192 @@ fn main() -> Result<()> {
     }
 }
 
+/// Command output as ordered `key: value` lines, or with `--json` as one JSON object with
+/// the same keys. Keys are part of the scripting contract documented in `docs/API.md`.
+struct Output {
+    json: bool,
+    entries: Vec<OutputEntry>,
+}
+
+enum OutputEntry {
+    Field(String, serde_json::Value),
+    /// Printed in text mode only, e.g. list rows already covered by a JSON field.
+    Text(String),
+    /// Emitted in JSON mode only.
+    Json(String, serde_json::Value),
+}
+
+impl Output {
+    fn new(json: bool) -> Self {
+        Self {
+            json,
+            entries: Vec::new(),
+        }
+    }
+
+    fn field(&mut self, key: &str, value: impl Into<serde_json::Value>) -> &mut Self {
+        self.entries
+            .push(OutputEntry::Field(key.to_string(), value.into()));
+        self
+    }
+
+    fn text(&mut self, line: impl Into<String>) -> &mut Self {
+        self.entries.push(OutputEntry::Text(line.into()));
+        self
+    }
+
+    fn json_field(&mut self, key: &str, value: impl Into<serde_json::Value>) -> &mut Self {
+        self.entries
+            .push(OutputEntry::Json(key.to_string(), value.into()));
+        self
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        let rendered = self.render()?;
+        if !rendered.is_empty() {
+            println!("{rendered}");
+        }
+        Ok(())
+    }
+
+    fn render(&mut self) -> Result<String> {
+        let entries = std::mem::take(&mut self.entries);
+        if self.json {
+            let object: serde_json::Map<String, serde_json::Value> = entries
+                .into_iter()
+                .filter_map(|entry| match entry {
+                    OutputEntry::Field(key, value) | OutputEntry::Json(key, value) => {
+                        Some((key, value))
+                    }
+                    OutputEntry::Text(_) => None,
+                })
+                .collect();
+            return Ok(serde_json::to_string_pretty(&object)?);
+        }
+        let lines = entries
+            .into_iter()
+            .filter_map(|entry| match entry {
+                OutputEntry::Field(key, serde_json::Value::String(value)) => {
+                    Some(format!("{key}: {value}"))
+                }
+                OutputEntry::Field(key, serde_json::Value::Null) => Some(format!("{key}: <none>")),
+                OutputEntry::Field(key, value) => Some(format!("{key}: {value}")),
+                OutputEntry::Text(line) => Some(line),
+                OutputEntry::Json(..) => None,
+            })
+            .collect::<Vec<_>>();
+        Ok(lines.join("\n"))
+    }
+}
+
+fn approval_json(approval: &ApprovalRecord) -> serde_json::Value {
+    serde_json::json!({
+        "approval_id": approval.id,
+        "tool_name": approval.tool_name,
+        "capability": approval.capability,
+        "status": approval.status,
+        "requested_by": approval.requested_by,
+        "resolved_by": approval.resolved_by,
+        "expires_at_ms": approval.expires_at_ms,
+        "remaining_ms": approval_remaining_ms(approval),
+        "input": approval.input,
+        "decision_reason": approval.decision_reason,
+    })
+}
+
+fn approval_remaining_ms(approval: &ApprovalRecord) -> i64 {
+    approval
+        .expires_at_ms
+        .saturating_sub(current_epoch_ms())
+        .max(0)
+}
+
+fn format_remaining(ms: i64) -> String {
+    let secs = ms / 1000;
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{secs}s")
+    }
+}
+
+fn complete(input: &str, json: bool) -> Result<()> {
+    let completions = complete_slash_command(input);
+    let mut out = Output::new(json);
+    for completion in &completions {
+        out.text(
+            format!("{} {}", completion.value, completion.hint)
+                .trim_end()
+                .to_string(),
+        );
+    }
+    out.json_field(
+        "completions",
+        completions
+            .iter()
+            .map(|completion| {
+                serde_json::json!({
+                    "value": completion.value,
+                    "hint": completion.hint,
+                    "summary": completion.summary,
+                })
+            })
+            .collect::<Vec<_>>(),
+    );
+    out.finish()
+}
+
+fn status(json: bool) -> Result<()> {
+    let config = load_initialized_config()?;
+    let db_path = config.workspace_dir.join("titan.db");
+    let store = MemoryStore::open(&db_path)?;
+    store.apply_yolo_expiry("cli")?;
+    let risk = store.get_runtime_risk_state()?;
+    let writer = store.live_writer_lease(GATEWAY_WRITER_LEASE)?;
+    let provider = store.model_provider_state()?;
+
+    let mut out = Output::new(json);
+    out.field("mode", autonomy_mode_name(&config.mode))
+        .field("risk_mode", risk.risk_mode.as_str())
+        .field("yolo_expires_at_ms", risk.yolo_expires_at_ms)
+        .field(
+            "model_provider",
+            if provider.degraded { "degraded" } else { "ok" },
+        )
+        .field(
+            "model_error",
+            provider.last_error.filter(|_| provider.degraded),
+        )
+        .field("waiting_for_model", store.count_goals_waiting_for_model()?)
+        .field("active_goals", store.count_active_goals()?)
+        .field("pending_approvals", store.list_pending_approvals()?.len())
+        .field("writer", writer.as_ref().map(|lease| lease.holder.clone()))
+        .field("writer_endpoint", writer.and_then(|lease| lease.endpoint))
+        .field("db", db_path.display().to_string());
+    out.finish()
+}
+
 fn load_initialized_config() -> Result<TitanConfig> {
     let (config, _, _) = TitanConfig::load_or_create()?;
     config.validate_and_prepare()?;
     logging::init(&config.log_level);
+    PolicyEngine::configure_approvals(&config.approvals);
+    PolicyEngine::configure_egress(&config.egress)?;
+    MemoryStore::configure_blob_storage(&config.storage);
+    let mut embeddings = config.embeddings.clone();
+    if embeddings.provider == EmbeddingProvider::Ollama
+        && embeddings.endpoint.is_none()
+        && matches!(config.model.provider, ModelProvider::Ollama)
+    {
+        embeddings.endpoint = config.model.endpoint.clone();
+    }
+    MemoryStore::configure_embeddings(&embeddings);
+    let policy_path = titan_tools::policy_path_for(&config);
+    if policy_path.exists() {
+        PolicyEngine::load_policy(&policy_path)?;
+    }
     Ok(config)
 }
 
@@ -525,14 +1233,476 @@ fn comm(command: CommCommand) -> Result<()> {
     Ok(())
 }
 
-fn model(command: ModelCommand) -> Result<()> {
+fn notify(command: NotifyCommand) -> Result<()> {
+    let config = load_initialized_config()?;
+    let store = MemoryStore::open(&config.workspace_dir.join("titan.db"))?;
+
+    match command {
+        NotifyCommand::Set {
+            operator,
+            event_kind,
+            channel,
+            target,
+            quiet_hours,
+        } => {
+            if event_kind != "*" && !NOTIFICATION_EVENT_KINDS.contains(&event_kind.as_str()) {
+                bail!(
+                    "unknown event kind '{event_kind}' (expected * or one of {})",
+                    NOTIFICATION_EVENT_KINDS.join(", ")
+                );
+            }
+            let channel_kind = ChannelKind::parse(&channel)
+                .ok_or_else(|| anyhow::anyhow!("unsupported channel: {channel}"))?;
+            if target.trim().is_empty() {
+                bail!("--target is required");
+            }
+            let quiet = quiet_hours.as_deref().map(parse_quiet_hours).transpose()?;
+            store.upsert_notification_pref(&NotificationPrefRecord {
+                operator: operator.trim().to_string(),
+                event_kind: event_kind.clone(),
+                channel: channel_kind.as_str().to_string(),
+                target: target.trim().to_string(),
+                quiet_start_minute: quiet.map(|(start, _)| start),
+                quiet_end_minute: quiet.map(|(_, end)| end),
+            })?;
+            println!("notification_pref_set: {} {}", operator.trim(), event_kind);
+        }
+        NotifyCommand::List => {
+            let prefs = store.list_notification_prefs()?;
+            if prefs.is_empty() {
+                println!("no notification preferences");
+            }
+            for pref in prefs {
+                let quiet = match (pref.quiet_start_minute, pref.quiet_end_minute) {
+                    (Some(start), Some(end)) => format!(
+                        "{:02}:{:02}-{:02}:{:02}",
+                        start / 60,
+                        start % 60,
+                        end / 60,
+                        end % 60
+                    ),
+                    _ => "-".to_string(),
+                };
+                println!(
+                    "{} event={} channel={} target={} quiet_hours={}",
+                    pref.operator, pref.event_kind, pref.channel, pref.target, quiet
+                );
+            }
+        }
+        NotifyCommand::Remove {
+            operator,
+            event_kind,
+        } => {
+            if store.remove_notification_pref(&operator, &event_kind)? {
+                println!("notification_pref_removed: {operator} {event_kind}");
+            } else {
+                println!("notification_pref_not_found: {operator} {event_kind}");
+            }
+        }
+        NotifyCommand::Log { limit } => {
+            for row in store.list_notification_log(limit)? {
+                println!(
+                    "#{} {} event={} channel={} status={} detail={}",
+                    row.id, row.operator, row.event_kind, row.channel, row.status, row.detail
+                );
+            }
+        }
+        NotifyCommand::Digest => {
+            let pending = store.list_pending_approvals()?;
+            let goals = store.list_goals(10)?;
+            let mut message = format!(
+                "TITAN digest: {} pending approvals, {} recent goals",
+                pending.len(),
+                goals.len()
+            );
+            for goal in &goals {
+                message.push_str(&format!("\n- {} [{}]", goal.description, goal.status));
+            }
+            let sent = dispatch_notification(&store, "digest", &message)?;
+            println!("digest_routes: {}", sent.len());
+            for row in sent {
+                println!("- {} {} {}", row.operator, row.channel, row.status);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn trace_scope(channel: Option<String>, goal: Option<String>) -> String {
+    match (channel, goal) {
+        (Some(channel), _) => format!("channel:{channel}"),
+        (None, Some(goal)) => format!("goal:{goal}"),
+        (None, None) => "default".to_string(),
+    }
+}
+
+fn trace(command: TraceCommand) -> Result<()> {
+    let config = load_initialized_config()?;
+    let store = MemoryStore::open(&config.workspace_dir.join("titan.db"))?;
+
+    match command {
+        TraceCommand::Level {
+            level,
+            channel,
+            goal,
+        } => {
+            let verbosity = TraceVerbosity::parse(&level)
+                .ok_or_else(|| anyhow::anyhow!("level must be minimal, normal, or debug"))?;
+            let scope = trace_scope(channel, goal);
+            store.set_trace_level(&scope, verbosity)?;
+            println!("trace_level: {} {}", scope, verbosity.as_str());
+        }
+        TraceCommand::Clear { channel, goal } => {
+            let scope = trace_scope(channel, goal);
+            let removed = store.clear_trace_level(&scope)?;
+            println!("trace_level_cleared: {} removed={}", scope, removed);
+        }
+        TraceCommand::Levels => {
+            for (scope, level) in store.list_trace_levels()? {
+                println!("{scope}: {level}");
+            }
+        }
+        TraceCommand::Stats { goal_id } => {
+            println!(
+                "verbosity: {}",
+                store.trace_verbosity_for_goal(&goal_id)?.as_str()
+            );
+            for (event_type, seen, kept) in store.trace_sampling_stats(&goal_id)? {
+                println!("- {event_type} seen={seen} kept={kept}");
+            }
+        }
+        TraceCommand::Diff { goal_a, goal_b } => {
+            let diff = store
+                .diff_goal_runs(&goal_a, &goal_b)?
+                .with_context(|| format!("goal not found: {goal_a} or {goal_b}"))?;
+            for (label, run) in [("a", &diff.a), ("b", &diff.b)] {
+                println!(
+                    "{label}: {} status={} intent={} candidate={} duration_secs={} {}",
+                    run.goal_id,
+                    run.status,
+                    run.intent.as_deref().unwrap_or("<none>"),
+                    run.selected_candidate_id.as_deref().unwrap_or("<none>"),
+                    run.duration_secs,
+                    run.description
+                );
+            }
+            println!("same_family: {}", diff.same_family());
+            println!("plan_changed: {}", diff.plan_changed());
+            println!("outcome_changed: {}", diff.outcome_changed());
+            println!("duration_delta_secs: {:+}", diff.duration_delta_secs());
+            println!("steps:");
+            for row in &diff.steps {
+                let marker = match row.kind() {
+                    "same" => "=",
+                    "changed" => "~",
+                    "only_a" => "-",
+                    _ => "+",
+                };
+                let (a, b) = (row.a.as_ref(), row.b.as_ref());
+                let step = a.or(b).expect("aligned row has a step");
+                println!(
+                    "{marker} {} {} a={} b={}",
+                    step.step_id,
+                    step.tool_name,
+                    a.map_or("-", |step| step.status.as_str()),
+                    b.map_or("-", |step| step.status.as_str())
+                );
+                if row.kind() == "changed" {
+                    for (label, step) in [("a", a), ("b", b)] {
+                        let output = step.map_or("", |step| step.output.as_str());
+                        let first_line = output.lines().next().unwrap_or_default();
+                        println!(
+                            "    {label}: {}",
+                            first_line.chars().take(120).collect::<String>()
+                        );
+                    }
+                }
+            }
+            let events = diff.event_count_changes();
+            if !events.is_empty() {
+                println!("events:");
+                for (event_type, a, b) in events {
+                    println!("- {event_type} a={a} b={b}");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn webhook(command: WebhookCommand) -> Result<()> {
+    let config = load_initialized_config()?;
+    let store = MemoryStore::open(&config.workspace_dir.join("titan.db"))?;
+
+    match command {
+        WebhookCommand::List => {
+            println!("webhooks: {}", config.webhooks.len());
+            for hook in &config.webhooks {
+                let events = if hook.events.is_empty() {
+                    WEBHOOK_EVENT_KINDS.join(",")
+                } else {
+                    hook.events.join(",")
+                };
+                println!(
+                    "- {} url={} events={} signed={} max_attempts={}",
+                    hook.name,
+                    hook.url,
+                    events,
+                    hook.secret_env.is_some(),
+                    hook.max_attempts
+                );
+            }
+        }
+        WebhookCommand::Deliveries { limit } => {
+            for row in store.list_webhook_deliveries(limit)? {
+                println!(
+                    "#{} webhook={} event={} status={} attempts={} code={} error={}",
+                    row.id,
+                    row.webhook,
+                    row.event_id,
+                    row.status,
+                    row.attempts,
+                    row.last_status_code
+                        .map(|code| code.to_string())
+                        .unwrap_or_else(|| "-".to_string()),
+                    row.last_error.as_deref().unwrap_or("-")
+                );
+            }
+        }
+        WebhookCommand::Dispatch => {
+            let summary = dispatch_webhooks(&store, &config.webhooks, current_epoch_ms())?;
+            println!("enqueued: {}", summary.enqueued);
+            println!("delivered: {}", summary.delivered);
+            println!("retrying: {}", summary.retrying);
+            println!("failed: {}", summary.failed);
+        }
+    }
+
+    Ok(())
+}
+
+/// Ids listed per check by `titan db repair`; the count always covers them all.
+const REPAIR_IDS_SHOWN: usize = 20;
+
+fn db(command: DbCommand) -> Result<()> {
+    let config = load_initialized_config()?;
+    let store = MemoryStore::open(&config.workspace_dir.join("titan.db"))?;
+
+    match command {
+        DbCommand::Status => {
+            let stats = store.size_stats()?;
+            println!("db_bytes: {}", stats.file_bytes);
+            println!("wal_bytes: {}", stats.wal_bytes);
+            println!("journal_mode: {}", stats.journal_mode);
+            println!(
+                "free_pages: {}/{} ({:.1}%)",
+                stats.freelist_pages,
+                stats.page_count,
+                stats.free_ratio() * 100.0
+            );
+            println!(
+                "last_maintenance_ms: {}",
+                store
+                    .last_maintenance_at_ms()?
+                    .map(|ms| ms.to_string())
+                    .unwrap_or_else(|| "never".to_string())
+            );
+            let cache = store.model_cache_stats(current_epoch_ms())?;
+            println!(
+                "model_cache: entries={} live={} hits={}",
+                cache.entries, cache.live, cache.hits
+            );
+        }
+        DbCommand::Maintain { vacuum } => {
+            let report =
+                store.run_maintenance("cli", vacuum, config.maintenance.vacuum_free_ratio)?;
+            print_maintenance_report(&report);
+        }
+        DbCommand::Archive { older_than_days } => {
+            let days = older_than_days.unwrap_or(config.archive.after_days);
+            let cutoff_ms = current_epoch_ms() - days as i64 * 86_400_000;
+            let report = store.archive_goals(cutoff_ms, &config.archive)?;
+            println!("archived_goals: {}", report.goals);
+            println!("archived_rows: {}", report.rows);
+            println!("archive_bytes: {}", report.bytes);
+            println!(
+                "archive: {}",
+                report.archive_ref.as_deref().unwrap_or("<none>")
+            );
+        }
+        DbCommand::ShowArchived { goal_id } => match store.archived_goal(&goal_id)? {
+            Some(entry) => println!("{}", serde_json::to_string_pretty(&entry)?),
+            None => println!("goal_not_archived: {goal_id}"),
+        },
+        DbCommand::Repair { fix, stale_minutes } => {
+            let stale_before_ms = current_epoch_ms() - stale_minutes as i64 * 60_000;
+            let report = store.repair_references(stale_before_ms, fix)?;
+            println!("mode: {}", if report.fixed { "fix" } else { "dry_run" });
+            for finding in &report.findings {
+                println!(
+                    "{}: {} ({})",
+                    finding.check,
+                    finding.ids.len(),
+                    finding.action
+                );
+                for id in finding.ids.iter().take(REPAIR_IDS_SHOWN) {
+                    println!("  - {id}");
+                }
+                if finding.ids.len() > REPAIR_IDS_SHOWN {
+                    println!("  ... {} more", finding.ids.len() - REPAIR_IDS_SHOWN);
+                }
+            }
+            println!("total: {}", report.total());
+            if !report.fixed && report.total() > 0 {
+                println!("run `titan db repair --fix` to repair");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn outbox(command: OutboxCommand) -> Result<()> {
+    let config = load_initialized_config()?;
+    let store = MemoryStore::open(&config.workspace_dir.join("titan.db"))?;
+
+    match command {
+        OutboxCommand::List { status, limit } => {
+            for row in store.list_outbox(status.as_deref(), limit)? {
+                println!(
+                    "#{} {} subject={} {}:{} status={} attempts={} error={}",
+                    row.id,
+                    row.event_kind,
+                    row.subject_id,
+                    row.channel,
+                    row.target,
+                    row.status,
+                    row.attempts,
+                    row.last_error.as_deref().unwrap_or("-")
+                );
+            }
+        }
+        OutboxCommand::Deliver => {
+            let summary = deliver_outbox(&store, current_epoch_ms())?;
+            println!("delivered: {}", summary.delivered);
+            println!("retrying: {}", summary.retrying);
+            println!("failed: {}", summary.failed);
+        }
+    }
+
+    Ok(())
+}
+
+fn federation(command: FederationCommand) -> Result<()> {
+    let config = load_initialized_config()?;
+    let store = MemoryStore::open(&config.workspace_dir.join("titan.db"))?;
+
+    match command {
+        FederationCommand::Status { limit } => {
+            println!("peers: {}", config.federation.len());
+            for peer in &config.federation {
+                println!(
+                    "- {} | {} | intents={} | paths={}",
+                    peer.name,
+                    peer.url,
+                    peer.intents.join(","),
+                    peer.paths.join(",")
+                );
+            }
+            let rows = store.list_remote_goals(limit.min(200))?;
+            println!("forwarded_goals: {}", rows.len());
+            for row in rows {
+                println!(
+                    "- {} -> {}:{} | {} | last_trace_id={}",
+                    row.goal_id, row.peer, row.remote_goal_id, row.remote_status, row.last_trace_id
+                );
+            }
+        }
+        FederationCommand::Sync => {
+            let summary = sync_remote_goals(&store, &config.federation)?;
+            println!("polled: {}", summary.polled);
+            println!("traces_merged: {}", summary.traces_merged);
+            println!("finished: {}", summary.finished);
+            for error in &summary.errors {
+                println!("error: {error}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn lease(command: LeaseCommand) -> Result<()> {
+    let config = load_initialized_config()?;
+    let store = MemoryStore::open(&config.workspace_dir.join("titan.db"))?;
+
+    match command {
+        LeaseCommand::Show => match store.writer_lease(GATEWAY_WRITER_LEASE)? {
+            Some(lease) => {
+                println!("holder: {}", lease.holder);
+                println!("endpoint: {}", lease.endpoint.as_deref().unwrap_or("-"));
+                println!("live: {}", lease.is_live(current_epoch_ms()));
+                println!("acquired_at_ms: {}", lease.acquired_at_ms);
+                println!("expires_at_ms: {}", lease.expires_at_ms);
+            }
+            None => println!("holder: none"),
+        },
+        LeaseCommand::Release => {
+            let released = store.release_writer_lease(GATEWAY_WRITER_LEASE, None)?;
+            println!("lease_released: {released}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends an execution-critical write to the process holding the writer lease.
+fn proxy_to_writer(
+    writer: &WriterLeaseRecord,
+    path: &str,
+    body: serde_json::Value,
+) -> Result<serde_json::Value> {
+    let Some(endpoint) = writer.endpoint.as_deref() else {
+        bail!(
+            "writer lease held by {} with no web endpoint; retry once it exits",
+            writer.holder
+        );
+    };
+    let url = format!("{}{}", endpoint.trim_end_matches('/'), path);
+    let response = Client::new()
+        .post(&url)
+        .json(&body)
+        .send()
+        .with_context(|| format!("failed to reach writer at {url}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        bail!("writer at {url} returned {status}: {}", response.text()?);
+    }
+    println!("proxied_to: {endpoint}");
+    Ok(response.json()?)
+}
+
+fn print_maintenance_report(report: &MaintenanceReport) {
+    println!("bytes_before: {}", report.before.total_bytes());
+    println!("bytes_after: {}", report.after.total_bytes());
+    println!("reclaimed_bytes: {}", report.reclaimed_bytes());
+    println!("wal_checkpointed: {}", report.wal_checkpointed);
+    println!("vacuumed: {}", report.vacuumed);
+    println!("indexes: {}", report.index_count);
+    println!("integrity: {}", report.integrity.join("; "));
+    println!("duration_ms: {}", report.duration_ms);
+}
+
+fn model(command: ModelCommand) -> Result<()> {
     let (mut config, path, _) = TitanConfig::load_or_create()?;
     config.validate_and_prepare()?;
     logging::init(&config.log_level);
 
     match command {
         ModelCommand::Show => {
-            println!("provider: {}", model_provider_name(&config.model.provider));
+            println!("provider: {}", config.model.provider.as_str());
             println!("model: {}", config.model.model_id);
             println!(
                 "endpoint: {}",
@@ -569,7 +1739,7 @@ fn model(command: ModelCommand) -> Result<()> {
             config.validate_and_prepare()?;
             config.save(&path)?;
             println!("model_config_saved: true");
-            println!("provider: {}", model_provider_name(&config.model.provider));
+            println!("provider: {}", config.model.provider.as_str());
             println!("model: {}", config.model.model_id);
         }
         ModelCommand::ListOllama { endpoint } => {
@@ -587,6 +1757,93 @@ fn model(command: ModelCommand) -> Result<()> {
 
 const YOLO_ENABLE_PHRASE: &str = "I_ACCEPT_UNBOUNDED_AUTONOMY";
 
+fn crash(command: CrashCommand) -> Result<()> {
+    let config = load_initialized_config()?;
+    let reporter = CrashReporter::new(&config.workspace_dir, config.crash_reports.trace_events);
+    match command {
+        CrashCommand::List { limit } => {
+            let reports = reporter.list()?;
+            println!("crash_reports: {}", reports.len());
+            for report in reports.iter().take(limit) {
+                println!(
+                    "- {} | {} | {} | submitted={} | {}",
+                    report.id,
+                    report.kind,
+                    report.module,
+                    report.submitted_at_ms.is_some(),
+                    report.message.lines().next().unwrap_or_default()
+                );
+            }
+        }
+        CrashCommand::Show { id } => {
+            let report = reporter
+                .get(&id)?
+                .ok_or_else(|| anyhow::anyhow!("crash report not found: {id}"))?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        CrashCommand::Submit { id } => {
+            let endpoint = config.crash_reports.endpoint.as_deref().ok_or_else(|| {
+                anyhow::anyhow!("crash_reports.endpoint is not set in config.toml")
+            })?;
+            let reports: Vec<_> = reporter
+                .list()?
+                .into_iter()
+                .filter(|report| match &id {
+                    Some(id) => &report.id == id,
+                    None => report.submitted_at_ms.is_none(),
+                })
+                .collect();
+            if let Some(id) = &id
+                && reports.is_empty()
+            {
+                bail!("crash report not found: {id}");
+            }
+            for mut report in reports {
+                let code = reporter.submit(&mut report, endpoint)?;
+                println!("submitted: {} http_status={code}", report.id);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn safe_mode(command: SafeModeCommand) -> Result<()> {
+    let config = load_initialized_config()?;
+    let store = MemoryStore::open(&config.workspace_dir.join("titan.db"))?;
+    match command {
+        SafeModeCommand::Status => {
+            let state = store.safe_mode_state()?;
+            println!("safe_mode: {}", if state.active { "active" } else { "off" });
+            println!("reason: {}", state.reason.as_deref().unwrap_or("<none>"));
+            println!(
+                "changed_by: {}",
+                state.changed_by.as_deref().unwrap_or("<none>")
+            );
+            println!(
+                "consecutive_failures: {} threshold={}",
+                store.consecutive_failed_goals()?,
+                config.safe_mode.failure_threshold
+            );
+            if let Some(detail) = state.last_panic_detail.as_deref() {
+                println!("last_panic: {detail}");
+            }
+        }
+        SafeModeCommand::Enter { reason } => {
+            let reason = reason.unwrap_or_else(|| "entered by operator".to_string());
+            store.enter_safe_mode("cli", &reason)?;
+            println!("safe_mode: active");
+        }
+        SafeModeCommand::Resume { reason } => {
+            if store.resume_from_safe_mode("cli", reason.as_deref())? {
+                println!("safe_mode: off");
+            } else {
+                println!("safe_mode: off (was not active)");
+            }
+        }
+    }
+    Ok(())
+}
+
 fn yolo(command: YoloCommand) -> Result<()> {
     let config = load_initialized_config()?;
     let store = MemoryStore::open(&config.workspace_dir.join("titan.db"))?;
@@ -610,13 +1867,38 @@ fn yolo(command: YoloCommand) -> Result<()> {
                 state.last_changed_at_ms, state.last_changed_by
             );
         }
-        YoloCommand::Arm => {
-            let code = store.arm_yolo("cli")?;
+        YoloCommand::History { limit } => {
+            let rows = store.list_risk_state_history(limit)?;
+            println!("risk_state_history: {}", rows.len());
+            for row in rows {
+                println!(
+                    "- {} | {} | risk_mode={} | by={} | expires_at_ms={} | reason={}",
+                    row.created_at_ms,
+                    row.action,
+                    row.risk_mode.as_str(),
+                    row.actor,
+                    row.expires_at_ms
+                        .map(|v| v.to_string())
+                        .unwrap_or_else(|| "<none>".to_string()),
+                    row.reason.as_deref().unwrap_or("<none>")
+                );
+            }
+        }
+        YoloCommand::Arm { reason } => {
+            if !PolicyEngine::yolo_guardrails().enabled {
+                bail!("yolo is disabled by policy ([yolo] enabled = false)");
+            }
+            let code = store.arm_yolo("cli", reason.as_deref())?;
             println!("yolo_armed_code: {}", code);
             println!("required_phrase: {}", YOLO_ENABLE_PHRASE);
             println!("default_ttl_minutes: 15");
         }
-        YoloCommand::Enable { code, phrase, ttl } => {
+        YoloCommand::Enable {
+            code,
+            phrase,
+            ttl,
+            reason,
+        } => {
             let state = store.get_runtime_risk_state()?;
             let armed = state
                 .yolo_armed_token
@@ -627,7 +1909,16 @@ fn yolo(command: YoloCommand) -> Result<()> {
             if phrase != YOLO_ENABLE_PHRASE {
                 bail!("invalid yolo enable phrase");
             }
-            store.enable_yolo("cli", ttl)?;
+            let guardrails = PolicyEngine::yolo_guardrails();
+            if !guardrails.enabled {
+                bail!("yolo is disabled by policy ([yolo] enabled = false)");
+            }
+            if let Some(max) = guardrails.max_minutes
+                && ttl.max(0) as u64 > max
+            {
+                bail!("yolo ttl {ttl}m exceeds policy max_minutes={max}");
+            }
+            store.enable_yolo("cli", ttl, reason.as_deref())?;
             let new_state = store.get_runtime_risk_state()?;
             println!("risk_mode: {}", new_state.risk_mode.as_str());
             println!(
@@ -638,20 +1929,73 @@ fn yolo(command: YoloCommand) -> Result<()> {
                     .unwrap_or_else(|| "<none>".to_string())
             );
         }
-        YoloCommand::Disable => {
-            store.set_risk_mode_secure("cli")?;
+        YoloCommand::Disable { reason } => {
+            store.set_risk_mode_secure("cli", reason.as_deref())?;
             println!("risk_mode: secure");
         }
     }
     Ok(())
 }
 
+fn policy(command: PolicyCommand) -> Result<()> {
+    let config = load_initialized_config()?;
+    match command {
+        PolicyCommand::Lint { file } => {
+            let path = file.unwrap_or_else(|| titan_tools::policy_path_for(&config));
+            let policy = PolicyFile::load(&path)?;
+            let issues = policy.lint();
+            let errors = issues
+                .iter()
+                .filter(|issue| issue.level == LintLevel::Error)
+                .count();
+            println!("policy: {}", path.display());
+            for issue in &issues {
+                let level = match issue.level {
+                    LintLevel::Error => "error",
+                    LintLevel::Warning => "warning",
+                };
+                println!("{level}: {}", issue.message);
+            }
+            println!("errors: {errors}");
+            println!("warnings: {}", issues.len() - errors);
+            if errors > 0 {
+                bail!("policy lint failed with {errors} error(s)");
+            }
+        }
+        PolicyCommand::Show => match PolicyEngine::policy_status() {
+            Some(status) => {
+                println!("policy: {}", status.path.display());
+                println!("rules: {}", status.rules);
+                println!("standing_grants: {}", status.standing_grants);
+                let yolo = PolicyEngine::yolo_guardrails();
+                println!("yolo_enabled: {}", yolo.enabled);
+                println!(
+                    "yolo_max_minutes: {}",
+                    yolo.max_minutes
+                        .map(|v| v.to_string())
+                        .unwrap_or_else(|| "<none>".to_string())
+                );
+                if let Some(err) = status.last_reload_error {
+                    println!("last_reload_error: {err}");
+                }
+            }
+            None => {
+                println!(
+                    "policy: <builtin> ({} not found)",
+                    titan_tools::policy_path_for(&config).display()
+                );
+            }
+        },
+    }
+    Ok(())
+}
+
 fn mode_risk(risk_mode: &str) -> Result<()> {
     let requested = RiskMode::parse(risk_mode);
     let config = load_initialized_config()?;
     let store = MemoryStore::open(&config.workspace_dir.join("titan.db"))?;
     if matches!(requested, RiskMode::Secure) {
-        store.set_risk_mode_secure("cli")?;
+        store.set_risk_mode_secure("cli", None)?;
         println!("risk_mode: secure");
         return Ok(());
     }
@@ -687,41 +2031,87 @@ fn secrets(command: SecretsCommand) -> Result<()> {
             store.lock();
             println!("status: locked");
         }
+        SecretsCommand::Set { key_id } => {
+            let passphrase = match std::env::var("TITAN_SECRETS_PASSPHRASE") {
+                Ok(passphrase) if !passphrase.trim().is_empty() => passphrase,
+                _ => prompt_with_default("Secrets passphrase", "")?,
+            };
+            let mut store = SecretsStore::open_default();
+            store.unlock(passphrase.trim())?;
+            let value = prompt_with_default(&format!("Value for {key_id}"), "")?;
+            store.set_secret(&key_id, value.trim())?;
+            println!("secret_set: {key_id}");
+        }
     }
     Ok(())
 }
 
-fn connector(command: ConnectorCommand) -> Result<()> {
+fn connector(command: ConnectorCommand, json: bool) -> Result<()> {
     let config = load_initialized_config()?;
     let store = MemoryStore::open(&config.workspace_dir.join("titan.db"))?;
+    let mut out = Output::new(json);
     match command {
         ConnectorCommand::List => {
             let rows = store.list_connectors()?;
-            println!("connectors: {}", rows.len());
-            for row in rows {
-                println!(
+            out.text(format!("connectors: {}", rows.len()));
+            for row in &rows {
+                out.text(format!(
                     "- {} | {} | {} | last_test={}",
                     row.id,
                     row.connector_type,
                     row.display_name,
-                    row.last_test_status
-                        .unwrap_or_else(|| "<never>".to_string())
-                );
+                    row.last_test_status.as_deref().unwrap_or("<never>")
+                ));
             }
+            out.json_field(
+                "connectors",
+                rows.iter()
+                    .map(|row| {
+                        serde_json::json!({
+                            "connector_id": row.id,
+                            "type": row.connector_type,
+                            "display_name": row.display_name,
+                            "last_test_status": row.last_test_status,
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            );
         }
         ConnectorCommand::Add {
             connector_type,
             name,
+            fields,
+            interactive,
         } => {
             let parsed = ConnectorType::parse(&connector_type)
                 .ok_or_else(|| anyhow::anyhow!("unsupported connector type: {connector_type}"))?;
-            let id = Uuid::new_v4().to_string();
-            let display_name = name.unwrap_or_else(|| parsed.as_str().to_string());
-            let config_json = default_connector_config(parsed)?.to_string();
-            store.add_connector(&id, parsed.as_str(), &display_name, &config_json)?;
-            println!("connector_added: {id}");
-            println!("type: {}", parsed.as_str());
-            println!("display_name: {display_name}");
+            let mut display_name = name.unwrap_or_else(|| parsed.as_str().to_string());
+            let config = if interactive {
+                display_name = prompt_with_default("Display name", &display_name)?;
+                prompt_connector_config(parsed)?
+            } else {
+                let fields = fields
+                    .iter()
+                    .map(|field| {
+                        field.split_once('=').ok_or_else(|| {
+                            anyhow::anyhow!("--field expects FIELD=VALUE, got '{field}'")
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                connector_config_with_fields(parsed, &fields)
+                    .context("set fields with --field or use --interactive")?
+            };
+            let id = add_connector(
+                &store,
+                &ConnectorAddPayload {
+                    connector_type: parsed,
+                    display_name: display_name.clone(),
+                    config,
+                },
+            )?;
+            out.field("connector_added", id)
+                .field("type", parsed.as_str())
+                .field("display_name", display_name);
         }
         ConnectorCommand::Configure { id } => {
             let row = store
@@ -763,12 +2153,62 @@ fn connector(command: ConnectorCommand) -> Result<()> {
                             bail!("secrets store is locked; unlock to persist connector token");
                         }
                     }
-                    (
-                        display_name,
-                        serde_json::json!({
-                            "owner": owner,
+                    let existing_subscriptions = existing_cfg
+                        .get("subscriptions")
+                        .and_then(Value::as_object)
+                        .cloned()
+                        .unwrap_or_default();
+                    let kinds = connector_event_kinds(&store, &id)?;
+                    let events = prompt_with_default(
+                        &format!(
+                            "Webhook events to turn into goals ({})",
+                            kinds
+                                .iter()
+                                .map(|kind| kind.event.as_str())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                        &existing_subscriptions
+                            .keys()
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join(","),
+                    )?;
+                    let mut subscriptions = serde_json::Map::new();
+                    for event in events.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                        if !kinds.iter().any(|kind| kind.event == event) {
+                            bail!("unsupported webhook event: {event}");
+                        }
+                        // Keep a custom template across reconfiguration; "" means the default.
+                        let template = existing_subscriptions
+                            .get(event)
+                            .cloned()
+                            .unwrap_or_else(|| Value::String(String::new()));
+                        subscriptions.insert(event.to_string(), template);
+                    }
+                    if !subscriptions.is_empty() {
+                        let secret = prompt_with_default(
+                            "Webhook secret (blank to keep existing or env-only)",
+                            "",
+                        )?;
+                        if !secret.trim().is_empty() {
+                            if let Some(secrets) = &mut store_secrets {
+                                secrets.set_secret(
+                                    &format!("connector:{id}:github_webhook_secret"),
+                                    secret.trim(),
+                                )?;
+                            } else {
+                                bail!("secrets store is locked; unlock to persist webhook secret");
+                            }
+                        }
+                    }
+                    (
+                        display_name,
+                        serde_json::json!({
+                            "owner": owner,
                             "repo": repo,
                             "base_url": base_url,
+                            "subscriptions": subscriptions,
                         }),
                     )
                 }
@@ -809,26 +2249,146 @@ fn connector(command: ConnectorCommand) -> Result<()> {
                         }),
                     )
                 }
+                ConnectorType::Email => {
+                    let display_name = prompt_with_default("Display name", &row.display_name)?;
+                    let config = prompt_connector_config(parsed)?;
+                    let password =
+                        prompt_with_default("Mail password (blank to keep env-only)", "")?;
+                    if !password.trim().is_empty() {
+                        if let Some(secrets) = &mut store_secrets {
+                            secrets.set_secret(
+                                &format!("connector:{id}:email_password"),
+                                password.trim(),
+                            )?;
+                        } else {
+                            bail!("secrets store is locked; unlock to persist mail password");
+                        }
+                    }
+                    (display_name, config)
+                }
+                ConnectorType::Webhook => {
+                    let display_name = prompt_with_default("Display name", &row.display_name)?;
+                    let mut config = prompt_connector_config(parsed)?;
+                    // Secret headers are an object, so they carry over from the stored config.
+                    if let Some(headers) = existing_cfg.get("headers").and_then(Value::as_object) {
+                        for header in headers.keys() {
+                            let value = prompt_with_default(
+                                &format!("{header} value (blank to keep env-only)"),
+                                "",
+                            )?;
+                            if value.trim().is_empty() {
+                                continue;
+                            }
+                            let Some(secrets) = &mut store_secrets else {
+                                bail!("secrets store is locked; unlock to persist header secrets");
+                            };
+                            secrets.set_secret(
+                                &format!("connector:{id}:header:{}", header.to_ascii_lowercase()),
+                                value.trim(),
+                            )?;
+                        }
+                        config["headers"] = Value::Object(headers.clone());
+                    }
+                    (display_name, config)
+                }
+                ConnectorType::Registered(_) => (
+                    prompt_with_default("Display name", &row.display_name)?,
+                    prompt_connector_config(parsed)?,
+                ),
             };
 
+            check_connector_config(parsed, &config_json)?;
             let updated = store.update_connector(&id, &display_name, &config_json.to_string())?;
-            println!("connector_config_updated: {updated}");
-            println!("connector_id: {id}");
+            out.field("connector_config_updated", updated)
+                .field("connector_id", id);
+        }
+        ConnectorCommand::Types => {
+            let types = ConnectorType::all();
+            out.text(format!("connector types: {}", types.len()));
+            for connector_type in &types {
+                let tools = connector_type.factory().tools();
+                out.text(format!(
+                    "- {} | tools={}",
+                    connector_type.as_str(),
+                    tools.len()
+                ));
+                for tool in &tools {
+                    out.text(format!(
+                        "  - {} | risk={} | scopes={} | {}",
+                        tool.name,
+                        tool.risk_class.as_str(),
+                        tool.required_scopes.labels().join(","),
+                        tool.description
+                    ));
+                }
+            }
+            out.json_field(
+                "types",
+                types
+                    .iter()
+                    .map(|connector_type| {
+                        serde_json::json!({
+                            "type": connector_type.as_str(),
+                            "tools": connector_type
+                                .factory()
+                                .tools()
+                                .iter()
+                                .map(|tool| {
+                                    serde_json::json!({
+                                        "name": tool.name,
+                                        "risk": tool.risk_class.as_str(),
+                                        "scopes": tool.required_scopes.labels(),
+                                        "description": tool.description,
+                                    })
+                                })
+                                .collect::<Vec<_>>(),
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            );
         }
         ConnectorCommand::Test { id } => {
             let resolver = CompositeSecretResolver::from_env()?;
             let health = test_connector(&store, &id, &resolver)?;
-            println!("connector_id: {id}");
-            println!("health_ok: {}", health.ok);
-            println!("detail: {}", health.detail);
+            out.field("connector_id", id)
+                .field("health_ok", health.ok)
+                .field("detail", health.detail);
+        }
+        ConnectorCommand::Tools { id } => {
+            let tools = connector_tools_for(&store, &id)?;
+            out.field("connector_id", id)
+                .text(format!("tools: {}", tools.len()));
+            for tool in &tools {
+                out.text(format!(
+                    "- {} | risk={} | scopes={} | {}",
+                    tool.name,
+                    tool.risk_class.as_str(),
+                    tool.required_scopes.labels().join(","),
+                    tool.description
+                ));
+            }
+            out.json_field(
+                "tools",
+                tools
+                    .iter()
+                    .map(|tool| {
+                        serde_json::json!({
+                            "name": tool.name,
+                            "risk": tool.risk_class.as_str(),
+                            "scopes": tool.required_scopes.labels(),
+                            "description": tool.description,
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            );
         }
         ConnectorCommand::Remove { id } => {
             let removed = store.remove_connector(&id)?;
-            println!("connector_removed: {removed}");
-            println!("connector_id: {id}");
+            out.field("connector_removed", removed)
+                .field("connector_id", id);
         }
     }
-    Ok(())
+    out.finish()
 }
 
 fn onboard(install_daemon: bool, accept_defaults: bool) -> Result<()> {
@@ -945,10 +2505,7 @@ fn onboard(install_daemon: bool, accept_defaults: bool) -> Result<()> {
     println!("onboarding_status: complete");
     println!("workspace: {}", config.workspace_dir.display());
     println!("mode: {:?}", config.mode);
-    println!(
-        "model_provider: {}",
-        model_provider_name(&config.model.provider)
-    );
+    println!("model_provider: {}", config.model.provider.as_str());
     println!("model_id: {}", config.model.model_id);
     println!("discord_enabled: {}", config.discord.enabled);
     if config.discord.enabled {
@@ -1146,14 +2703,36 @@ fn doctor() -> Result<()> {
     println!("web_bind_default: {}", bind_addr);
     println!("created_config: {created}");
 
+    let reporter = CrashReporter::new(&config.workspace_dir, config.crash_reports.trace_events);
+    let reports = reporter.list()?;
+    println!(
+        "crash_reports: {} unsubmitted={} dir={}",
+        reports.len(),
+        reports
+            .iter()
+            .filter(|report| report.submitted_at_ms.is_none())
+            .count(),
+        reporter.reports_dir().display()
+    );
+    for report in reports.iter().take(3) {
+        println!(
+            "- {} | {} | {} | {}",
+            report.id,
+            report.kind,
+            report.module,
+            report.message.lines().next().unwrap_or_default()
+        );
+    }
+
     Ok(())
 }
 
-fn goal(command: GoalCommand) -> Result<()> {
+fn goal(command: GoalCommand, json: bool) -> Result<()> {
     let config = load_initialized_config()?;
 
     let db_path = config.workspace_dir.join("titan.db");
     let store = MemoryStore::open(&db_path)?;
+    let mut out = Output::new(json);
 
     match command {
         GoalCommand::Submit {
@@ -1166,11 +2745,11 @@ fn goal(command: GoalCommand) -> Result<()> {
             if let Some(key) = &dedupe_key {
                 // Persistent idempotency for external callers that may retry submissions.
                 if let Some(existing) = store.find_goal_by_dedupe_key(key)? {
-                    println!("dedupe_hit: true");
-                    println!("goal_id: {}", existing.id);
-                    println!("status: {}", existing.status);
-                    println!("description: {}", existing.description);
-                    return Ok(());
+                    out.field("dedupe_hit", true)
+                        .field("goal_id", existing.id)
+                        .field("status", existing.status)
+                        .field("description", existing.description);
+                    return out.finish();
                 }
             }
 
@@ -1189,8 +2768,8 @@ fn goal(command: GoalCommand) -> Result<()> {
                 behavior,
             };
             if !matches!(runtime.submit(job), SubmitOutcome::Accepted) {
-                println!("submit_status: duplicate");
-                return Ok(());
+                out.field("submit_status", "duplicate");
+                return out.finish();
             }
 
             let result = runtime
@@ -1212,42 +2791,94 @@ fn goal(command: GoalCommand) -> Result<()> {
             }
             store.update_goal_status(&result.goal.id, result.goal.status)?;
 
-            println!("goal_id: {}", result.goal.id);
-            println!("status: {}", result.goal.status.as_str());
-            println!("attempts: {}", result.attempts);
-            println!("db: {}", db_path.display());
+            out.json_field("dedupe_hit", false)
+                .field("goal_id", result.goal.id)
+                .field("status", result.goal.status.as_str())
+                .field("attempts", result.attempts)
+                .field("db", db_path.display().to_string());
         }
         GoalCommand::Show { goal_id } => {
             if let Some(goal) = store.get_goal(&goal_id)? {
-                println!("goal_id: {}", goal.id);
-                println!("status: {}", goal.status);
-                println!("description: {}", goal.description);
-                println!(
-                    "dedupe_key: {}",
-                    goal.dedupe_key.unwrap_or_else(|| "<none>".to_string())
-                );
-                println!("traces:");
+                let archive_ref = store.goal_archive_ref(&goal.id)?;
+                out.field("goal_id", goal.id)
+                    .field("status", goal.status)
+                    .field("description", goal.description)
+                    .field("dedupe_key", goal.dedupe_key);
+                if let Some(archive_ref) = archive_ref {
+                    // Traces and steps now live in the archive; `titan db show-archived` reads them.
+                    out.field("archive", archive_ref);
+                }
+                out.text("traces:");
                 // Ordered traces provide a minimal execution timeline for this goal.
-                for event in store.get_traces(&goal_id)? {
-                    println!("- {}: {}", event.event_type, event.detail);
+                let traces = store.get_traces(&goal_id)?;
+                for event in &traces {
+                    out.text(format!("- {}: {}", event.event_type, event.detail));
                 }
+                out.json_field(
+                    "traces",
+                    traces
+                        .iter()
+                        .map(|event| {
+                            serde_json::json!({
+                                "event_type": event.event_type,
+                                "detail": event.detail,
+                            })
+                        })
+                        .collect::<Vec<_>>(),
+                );
+                let changes = store.list_workspace_changes(&goal_id)?;
+                if !changes.is_empty() {
+                    out.text("workspace_changes:");
+                }
+                for change in &changes {
+                    out.text(format!(
+                        "- [{}] {} {} via={} {}",
+                        change.change_kind.trim(),
+                        change.path,
+                        change.diff_stat,
+                        change.tool_name,
+                        if change.merged_at_ms.is_some() {
+                            "merged"
+                        } else {
+                            "unmerged"
+                        }
+                    ));
+                }
+                out.json_field(
+                    "workspace_changes",
+                    changes
+                        .iter()
+                        .map(|change| {
+                            serde_json::json!({
+                                "path": change.path,
+                                "change_kind": change.change_kind,
+                                "diff_stat": change.diff_stat,
+                                "tool_name": change.tool_name,
+                                "merged_at_ms": change.merged_at_ms,
+                                "created_at_ms": change.created_at_ms,
+                            })
+                        })
+                        .collect::<Vec<_>>(),
+                );
             } else {
-                println!("goal not found: {goal_id}");
+                out.text(format!("goal not found: {goal_id}"))
+                    .json_field("goal_not_found", goal_id);
             }
         }
         GoalCommand::Cancel { goal_id } => {
             let Some(existing) = store.get_goal(&goal_id)? else {
-                println!("goal not found: {goal_id}");
-                return Ok(());
+                out.text(format!("goal not found: {goal_id}"))
+                    .json_field("goal_not_found", goal_id);
+                return out.finish();
             };
             if matches!(
                 existing.status.as_str(),
                 "completed" | "failed" | "cancelled"
             ) {
-                println!("cancel_rejected: terminal_status");
-                println!("goal_id: {}", goal_id);
-                println!("status: {}", existing.status);
-                return Ok(());
+                out.field("cancel_rejected", "terminal_status")
+                    .field("goal_id", goal_id)
+                    .field("status", existing.status);
+                return out.finish();
             }
             store.update_goal_status(&goal_id, GoalStatus::Cancelled)?;
             store.add_trace_event(&TraceEvent::new(
@@ -1260,11 +2891,114 @@ fn goal(command: GoalCommand) -> Result<()> {
                 "reflection_recorded",
                 "Cancellation recorded for future planning context",
             ))?;
-            println!("goal_id: {}", goal_id);
-            println!("status: {}", GoalStatus::Cancelled.as_str());
+            out.field("goal_id", goal_id)
+                .field("status", GoalStatus::Cancelled.as_str());
+        }
+        GoalCommand::List {
+            status,
+            labels,
+            filter,
+            operator,
+            limit,
+        } => {
+            let mut terms = Vec::new();
+            if let (Some(name), Some(operator)) = (&filter, &operator) {
+                let saved = store
+                    .get_goal_filter(operator, name)?
+                    .with_context(|| format!("goal filter not found: {operator} {name}"))?;
+                terms.push(saved.terms());
+            }
+            terms.extend(status.map(|status| format!("status={status}")));
+            terms.extend(labels.iter().map(|label| format!("label={label}")));
+            let filter = GoalFilter::parse(&terms.join(" "))?;
+            let mut rows = Vec::new();
+            for goal in store.list_goals_filtered(&filter, limit)? {
+                let labels = store.goal_labels(&goal.id)?;
+                out.text(format!(
+                    "- {} {} [{}] {}",
+                    goal.id,
+                    goal.status,
+                    labels.join(","),
+                    goal.description
+                ));
+                rows.push(serde_json::json!({
+                    "goal_id": goal.id,
+                    "status": goal.status,
+                    "priority": goal.priority,
+                    "description": goal.description,
+                    "labels": labels,
+                }));
+            }
+            out.field("filter", filter.terms())
+                .field("count", rows.len())
+                .json_field("goals", rows);
         }
+        GoalCommand::Label {
+            goal_id,
+            labels,
+            remove,
+        } => {
+            if store.get_goal(&goal_id)?.is_none() {
+                out.text(format!("goal not found: {goal_id}"))
+                    .json_field("goal_not_found", goal_id);
+                return out.finish();
+            }
+            for label in &remove {
+                store.remove_goal_label(&goal_id, label)?;
+            }
+            store.add_goal_labels(&goal_id, &labels, GOAL_LABEL_USER)?;
+            out.field("goal_id", goal_id.clone())
+                .field("labels", store.goal_labels(&goal_id)?.join(","));
+        }
+        GoalCommand::Filter { command } => match command {
+            GoalFilterCommand::Save {
+                operator,
+                name,
+                status,
+                labels,
+            } => {
+                let terms = status
+                    .map(|status| format!("status={status}"))
+                    .into_iter()
+                    .chain(labels.iter().map(|label| format!("label={label}")))
+                    .collect::<Vec<_>>();
+                if terms.is_empty() {
+                    bail!("a filter needs --status or --label");
+                }
+                let filter = GoalFilter::parse(&terms.join(" "))?;
+                store.save_goal_filter(&operator, &name, &filter)?;
+                out.field("goal_filter_saved", name)
+                    .field("operator", operator)
+                    .field("terms", filter.terms());
+            }
+            GoalFilterCommand::List { operator } => {
+                let saved = store.list_goal_filters(&operator)?;
+                for preset in &saved {
+                    out.text(format!("- {}: {}", preset.name, preset.filter.terms()));
+                }
+                out.json_field(
+                    "filters",
+                    saved
+                        .iter()
+                        .map(|preset| {
+                            serde_json::json!({
+                                "name": preset.name,
+                                "terms": preset.filter.terms(),
+                            })
+                        })
+                        .collect::<Vec<_>>(),
+                );
+            }
+            GoalFilterCommand::Remove { operator, name } => {
+                out.field(
+                    "goal_filter_removed",
+                    store.remove_goal_filter(&operator, &name)?,
+                )
+                .field("name", name);
+            }
+        },
     }
-    Ok(())
+    out.finish()
 }
 
 fn tool(command: ToolCommand) -> Result<()> {
@@ -1297,13 +3031,19 @@ fn tool(command: ToolCommand) -> Result<()> {
             } else {
                 ToolRiskMode::Secure
             };
-            if PolicyEngine::requires_approval_with_risk(config.mode.clone(), risk, tool.class) {
+            if PolicyEngine::requires_approval_for_tool(
+                config.mode.clone(),
+                risk,
+                tool.class,
+                Some(&tool.name),
+            ) {
                 let approval = store.create_approval_request(
                     &tool.name,
                     tool.class.as_str(),
                     input.as_deref().unwrap_or_default(),
                     Some("cli"),
-                    approval_ttl_ms,
+                    approval_ttl_ms
+                        .unwrap_or_else(|| PolicyEngine::approval_ttl_ms(tool.class.as_str())),
                 )?;
                 println!("approval_required: true");
                 println!("approval_id: {}", approval.id);
@@ -1320,66 +3060,94 @@ fn tool(command: ToolCommand) -> Result<()> {
             exec_ctx.bypass_path_guard = matches!(risk_state.risk_mode, RiskMode::Yolo)
                 && risk_state.yolo_bypass_path_guard
                 && config.security.yolo_bypass_path_guard;
+            if let Some(profile) = config
+                .sandbox
+                .profile_for(&config.mode, risk_state.risk_mode.as_str())
+            {
+                exec_ctx.apply_sandbox(profile);
+            }
             let result = ToolExecutor::execute(tool, input.as_deref(), &exec_ctx)?;
-            store.record_tool_run(None, &tool.name, &result.status, &result.output)?;
+            let run = store.record_tool_run(None, &tool.name, &result.status, &result.output)?;
             println!("approval_required: false");
+            println!("tool_run_id: {}", run.id);
             println!("tool_name: {}", tool.name);
             println!("status: {}", result.status);
             println!("output: {}", result.output);
         }
+        ToolCommand::Output { run_id } => match store.get_tool_run_output(&run_id)? {
+            Some(output) => println!("{output}"),
+            None => println!("tool_run_not_found: {run_id}"),
+        },
     }
     Ok(())
 }
 
-fn approval(command: ApprovalCommand) -> Result<()> {
+fn approval(command: ApprovalCommand, json: bool) -> Result<()> {
     let config = load_initialized_config()?;
 
     let db_path = config.workspace_dir.join("titan.db");
     let store = MemoryStore::open(&db_path)?;
     store.apply_yolo_expiry("cli")?;
     let registry = ToolRegistry::with_defaults();
+    let mut out = Output::new(json);
 
     match command {
         ApprovalCommand::List => {
             let approvals = store.list_pending_approvals()?;
-            if approvals.is_empty() {
-                println!("pending_approvals: 0");
-                return Ok(());
-            }
-            println!("pending_approvals: {}", approvals.len());
-            for approval in approvals {
-                println!(
-                    "- {} | {} | {} | {}",
-                    approval.id, approval.tool_name, approval.capability, approval.status
-                );
+            out.field("pending_approvals", approvals.len());
+            for approval in &approvals {
+                out.text(format!(
+                    "- {} | expires_in={} | {} | {} | {}",
+                    approval.id,
+                    format_remaining(approval_remaining_ms(approval)),
+                    approval.tool_name,
+                    approval.capability,
+                    approval.status
+                ));
             }
+            out.json_field(
+                "approvals",
+                approvals.iter().map(approval_json).collect::<Vec<_>>(),
+            );
         }
         ApprovalCommand::Show { approval_id } => {
             if let Some(approval) = store.get_approval_request(&approval_id)? {
-                println!("approval_id: {}", approval.id);
-                println!("tool_name: {}", approval.tool_name);
-                println!("capability: {}", approval.capability);
-                println!("status: {}", approval.status);
-                println!(
-                    "requested_by: {}",
-                    approval
-                        .requested_by
-                        .unwrap_or_else(|| "<unknown>".to_string())
-                );
-                println!(
-                    "resolved_by: {}",
-                    approval.resolved_by.unwrap_or_else(|| "<none>".to_string())
-                );
-                println!("expires_at_ms: {}", approval.expires_at_ms);
-                println!("input: {}", approval.input);
-                println!(
-                    "decision_reason: {}",
-                    approval
-                        .decision_reason
-                        .unwrap_or_else(|| "<none>".to_string())
-                );
+                let requested_by = approval.requested_by.clone();
+                let remaining_ms = approval_remaining_ms(&approval);
+                out.field("approval_id", approval.id)
+                    .field("tool_name", approval.tool_name)
+                    .field("capability", approval.capability)
+                    .field("status", approval.status);
+                match requested_by {
+                    Some(requested_by) => out.field("requested_by", requested_by),
+                    None => out
+                        .text("requested_by: <unknown>")
+                        .json_field("requested_by", serde_json::Value::Null),
+                };
+                out.field("resolved_by", approval.resolved_by)
+                    .field("expires_at_ms", approval.expires_at_ms)
+                    .text(format!("expires_in: {}", format_remaining(remaining_ms)))
+                    .json_field("remaining_ms", remaining_ms)
+                    .field("input", approval.input);
+                if let Some(original_input) = approval.original_input {
+                    out.field("original_input", original_input);
+                }
+                out.field("decision_reason", approval.decision_reason);
+                let mut decisions = Vec::new();
+                for row in store.approval_decisions(&approval_id)? {
+                    out.text(format!(
+                        "- {} {} at {}",
+                        row.approver, row.decision, row.created_at_ms
+                    ));
+                    decisions.push(serde_json::json!({
+                        "approver": row.approver,
+                        "decision": row.decision,
+                        "created_at_ms": row.created_at_ms,
+                    }));
+                }
+                out.json_field("decisions", decisions);
             } else {
-                println!("approval_not_found: {}", approval_id);
+                out.field("approval_not_found", approval_id);
             }
         }
         ApprovalCommand::Wait {
@@ -1390,75 +3158,123 @@ fn approval(command: ApprovalCommand) -> Result<()> {
             loop {
                 if let Some(approval) = store.get_approval_request(&approval_id)? {
                     if approval.status != "pending" {
-                        println!("approval_id: {}", approval.id);
-                        println!("status: {}", approval.status);
-                        println!(
-                            "resolved_by: {}",
-                            approval.resolved_by.unwrap_or_else(|| "<none>".to_string())
-                        );
-                        println!(
-                            "decision_reason: {}",
-                            approval
-                                .decision_reason
-                                .unwrap_or_else(|| "<none>".to_string())
-                        );
+                        out.field("approval_id", approval.id)
+                            .field("status", approval.status)
+                            .field("resolved_by", approval.resolved_by)
+                            .field("decision_reason", approval.decision_reason);
                         break;
                     }
                     if Instant::now() >= deadline {
-                        println!("wait_status: timeout");
-                        println!("approval_id: {}", approval_id);
+                        out.field("wait_status", "timeout")
+                            .field("approval_id", approval_id.as_str());
                         break;
                     }
                     thread::sleep(Duration::from_millis(300));
                     continue;
                 }
 
-                println!("approval_not_found: {}", approval_id);
+                out.field("approval_not_found", approval_id.as_str());
                 break;
             }
         }
         ApprovalCommand::Approve {
             approval_id,
             reason,
+            by,
+            input: edited_input,
         } => {
-            let Some(approval) = store.get_approval_request(&approval_id)? else {
-                println!("approval_not_found: {}", approval_id);
-                return Ok(());
+            if let Some(writer) = remote_writer(&store)? {
+                let output = proxy_to_writer(
+                    &writer,
+                    &format!("/api/approvals/{approval_id}/approve"),
+                    serde_json::json!({
+                        "reason": reason,
+                        "resolved_by": by,
+                        "input": edited_input,
+                    }),
+                )?;
+                out.field("approval_status", output["status"].clone())
+                    .field("detail", output["detail"].clone());
+                return out.finish();
+            }
+            let Some(mut approval) = store.get_approval_request(&approval_id)? else {
+                out.field("approval_not_found", approval_id);
+                return out.finish();
             };
 
             if store.approval_has_tool_run(&approval_id)? {
-                println!("approval_status: replay_blocked");
-                println!("approval_id: {}", approval_id);
-                return Ok(());
+                out.field("approval_status", "replay_blocked")
+                    .field("approval_id", approval_id);
+                return out.finish();
+            }
+
+            if let Some(refusal) = approver_refusal(&store, &approval, &by)? {
+                out.field("approval_status", "approver_not_allowed")
+                    .field("detail", refusal);
+                return out.finish();
+            }
+
+            if let Some(refusal) = approval_edit_refusal(
+                &store,
+                &config.mode,
+                &approval,
+                true,
+                edited_input.as_deref(),
+            )? {
+                out.field("approval_status", "edit_not_allowed")
+                    .field("detail", refusal);
+                return out.finish();
+            }
+
+            if let Some((votes, quorum)) =
+                approval_quorum_shortfall(&store, &config.mode, &approval, &by)?
+            {
+                out.field("approval_status", "awaiting_quorum")
+                    .field("votes", format!("{votes}/{quorum}"));
+                return out.finish();
             }
 
             let resolved = store.resolve_approval_request(
                 &approval_id,
                 true,
-                Some("cli"),
+                Some(&by),
                 reason.as_deref(),
+                edited_input.as_deref(),
             )?;
             if !resolved {
-                println!("approval_not_pending: {}", approval_id);
-                return Ok(());
+                out.field("approval_not_pending", approval_id);
+                return out.finish();
             }
+            if let Some(edited) = edited_input {
+                approval.original_input = Some(std::mem::replace(&mut approval.input, edited));
+            }
+            notify_approval_resolved(&store, &approval_id, &by, "approved")?;
 
             if approval.tool_name == "skill_install" {
                 let payload = deserialize_approval_payload(&approval.input)?;
                 let installed = finalize_install_from_payload(&payload)?;
                 persist_installed_skill(&store, &installed)?;
-                println!("approval_status: approved");
-                println!("install_status: finalized");
-                println!("slug: {}", installed.manifest.slug);
-                println!("version: {}", installed.manifest.version);
-                return Ok(());
+                out.field("approval_status", "approved")
+                    .field("install_status", "finalized")
+                    .field("slug", installed.manifest.slug)
+                    .field("version", installed.manifest.version);
+                return out.finish();
             }
 
             if approval.tool_name == "skill_exec_grant" {
-                println!("approval_status: approved");
-                println!("grant: skill_exec");
-                println!("slug: {}", approval.input);
-                return Ok(());
+                let grant: SkillExecGrantPayload = decode_approval_payload(&approval.input)?;
+                out.field("approval_status", "approved")
+                    .field("grant", "skill_exec")
+                    .field("slug", grant.slug);
+                return out.finish();
+            }
+
+            if let Some(change) =
+                apply_connector_change_approval(&store, &approval.tool_name, &approval.input)?
+            {
+                out.field("approval_status", "approved")
+                    .field("connector_change", change);
+                return out.finish();
             }
 
             if approval.tool_name == "connector_tool" {
@@ -1469,17 +3285,17 @@ fn approval(command: ApprovalCommand) -> Result<()> {
                     &approval.input,
                     &resolver,
                 )?;
-                println!("approval_status: approved");
-                println!("execution_status: {}", outcome.result_status);
-                println!("goal_id: {}", outcome.goal_id);
-                return Ok(());
+                out.field("approval_status", "approved")
+                    .field("execution_status", outcome.result_status)
+                    .field("goal_id", outcome.goal_id);
+                return out.finish();
             }
 
             // Approving triggers execution immediately to keep operator workflow single-step.
             let Some(tool) = registry.get(&approval.tool_name) else {
-                println!("approval_status: approved");
-                println!("execution_status: skipped_unknown_tool");
-                return Ok(());
+                out.field("approval_status", "approved")
+                    .field("execution_status", "skipped_unknown_tool");
+                return out.finish();
             };
             let input = if approval.input.trim().is_empty() {
                 None
@@ -1492,6 +3308,12 @@ fn approval(command: ApprovalCommand) -> Result<()> {
             exec_ctx.bypass_path_guard = matches!(risk_state.risk_mode, RiskMode::Yolo)
                 && risk_state.yolo_bypass_path_guard
                 && config.security.yolo_bypass_path_guard;
+            if let Some(profile) = config
+                .sandbox
+                .profile_for(&config.mode, risk_state.risk_mode.as_str())
+            {
+                exec_ctx.apply_sandbox(profile);
+            }
             let result = ToolExecutor::execute(tool, input, &exec_ctx)?;
             store.record_tool_run(
                 Some(&approval_id),
@@ -1500,31 +3322,51 @@ fn approval(command: ApprovalCommand) -> Result<()> {
                 &result.output,
             )?;
 
-            println!("approval_status: approved");
-            println!("tool_name: {}", tool.name);
-            println!("execution_status: {}", result.status);
-            println!("output: {}", result.output);
+            out.field("approval_status", "approved")
+                .field("tool_name", tool.name.as_str())
+                .field("execution_status", result.status)
+                .field("output", result.output);
         }
         ApprovalCommand::Deny {
             approval_id,
             reason,
+            by,
         } => {
+            if let Some(writer) = remote_writer(&store)? {
+                let output = proxy_to_writer(
+                    &writer,
+                    &format!("/api/approvals/{approval_id}/deny"),
+                    serde_json::json!({ "reason": reason, "resolved_by": by }),
+                )?;
+                out.field("approval_status", output["status"].clone())
+                    .field("approval_id", approval_id);
+                return out.finish();
+            }
+            if let Some(approval) = store.get_approval_request(&approval_id)?
+                && let Some(refusal) = approver_refusal(&store, &approval, &by)?
+            {
+                out.field("approval_status", "approver_not_allowed")
+                    .field("detail", refusal);
+                return out.finish();
+            }
             let resolved = store.resolve_approval_request(
                 &approval_id,
                 false,
-                Some("cli"),
+                Some(&by),
                 reason.as_deref(),
+                None,
             )?;
             if !resolved {
-                println!("approval_not_pending: {}", approval_id);
-                return Ok(());
+                out.field("approval_not_pending", approval_id);
+                return out.finish();
             }
-            println!("approval_status: denied");
-            println!("approval_id: {}", approval_id);
+            notify_approval_resolved(&store, &approval_id, &by, "denied")?;
+            out.field("approval_status", "denied")
+                .field("approval_id", approval_id);
         }
     }
 
-    Ok(())
+    out.finish()
 }
 
 fn memory(command: MemoryCommand) -> Result<()> {
@@ -1548,6 +3390,15 @@ fn memory(command: MemoryCommand) -> Result<()> {
             store.restore_from(&path)?;
             println!("restore_applied: {}", path.display());
         }
+        MemoryCommand::Embed => {
+            println!("embedded: {}", store.embed_pending_memories()?);
+            for namespace in store.embedding_namespaces()? {
+                println!(
+                    "- {} | {} | dimensions={}",
+                    namespace.namespace, namespace.embedder, namespace.dimensions
+                );
+            }
+        }
     }
     Ok(())
 }
@@ -1563,14 +3414,19 @@ fn session(command: SessionCommand) -> Result<()> {
             println!("sessions: {}", rows.len());
             for row in rows {
                 println!(
-                    "- {} | {}:{} | queue={} | compactions={} | activation={} | usage={}",
+                    "- {} | {}:{} | queue={} | compactions={} | activation={} | usage={}{}",
                     row.id,
                     row.channel,
                     row.peer_id,
                     row.queue_depth,
                     row.compactions_count,
                     row.activation_mode,
-                    row.usage_mode
+                    row.usage_mode,
+                    if row.closed_at_ms.is_some() {
+                        " | closed"
+                    } else {
+                        ""
+                    }
                 );
             }
         }
@@ -1588,12 +3444,31 @@ fn session(command: SessionCommand) -> Result<()> {
                     .unwrap_or_else(|| "<default>".to_string())
             );
             println!("usage_mode: {}", row.usage_mode);
+            let usage = store.session_usage(&row.id)?;
+            println!("usage_tokens: {}", usage.tokens);
+            println!("usage_cost_usd: {:.4}", usage.cost_usd);
             println!("activation_mode: {}", row.activation_mode);
             println!("compactions_count: {}", row.compactions_count);
             println!("queue_depth: {}", row.queue_depth);
             println!("stop_requested: {}", row.stop_requested);
+            if let Some(closed_at_ms) = row.closed_at_ms {
+                println!("closed_at_ms: {closed_at_ms}");
+            }
             let messages = store.list_session_messages(&session_id, 20)?;
             println!("recent_messages: {}", messages.len());
+            let pins = store.list_session_pins(&session_id)?;
+            println!("pins: {}", pins.len());
+            for pin in pins {
+                println!(
+                    "- pin#{} | message={} | by={} | {}",
+                    pin.id,
+                    pin.message_id
+                        .map(|id| format!("#{id}"))
+                        .unwrap_or_else(|| "<none>".to_string()),
+                    pin.pinned_by,
+                    pin.content
+                );
+            }
         }
         SessionCommand::Reset { session_id } => {
             let deleted = store.reset_session(&session_id)?;
@@ -1612,6 +3487,74 @@ fn session(command: SessionCommand) -> Result<()> {
             store.mark_session_stop(&session_id)?;
             println!("session_stop_requested: {}", session_id);
         }
+        SessionCommand::CloseIdle { older_than_days } => {
+            let Some(days) = older_than_days.or(config.chat.session_idle_close_days) else {
+                bail!("pass --older-than-days or set chat.session_idle_close_days");
+            };
+            let cutoff_ms = current_epoch_ms() - days as i64 * 86_400_000;
+            let report = store.close_idle_sessions(cutoff_ms)?;
+            println!("sessions_closed: {}", report.closed.len());
+            println!("sessions_summarized: {}", report.summarized);
+            for session_id in report.closed {
+                println!("- {session_id}");
+            }
+        }
+        SessionCommand::Pin { session_id, target } => {
+            if store.get_session(&session_id)?.is_none() {
+                bail!("session not found: {session_id}");
+            }
+            let pin = store.pin_session_item(&session_id, &target, "cli")?;
+            println!("pinned: {}", pin.id);
+            println!(
+                "message_id: {}",
+                pin.message_id
+                    .map(|id| id.to_string())
+                    .unwrap_or_else(|| "<none>".to_string())
+            );
+        }
+        SessionCommand::Unpin { session_id, pin_id } => {
+            let removed = store.unpin_session_item(&session_id, pin_id)?;
+            println!("unpinned: {removed}");
+            println!("pin_id: {pin_id}");
+        }
+        SessionCommand::AsOf { session_id, at_ms } => {
+            if store.get_session(&session_id)?.is_none() {
+                bail!("session not found: {session_id}");
+            }
+            let snapshot = store.session_snapshot(&session_id, at_ms)?;
+            println!("session_id: {}", snapshot.session_id);
+            println!("as_of_ms: {}", snapshot.as_of_ms);
+            println!("risk_mode: {}", snapshot.risk_mode.as_str());
+            println!("pins: {}", snapshot.pins.len());
+            for pin in snapshot.pins {
+                println!("- pin#{} | by={} | {}", pin.id, pin.pinned_by, pin.content);
+            }
+            println!("messages: {}", snapshot.messages.len());
+            for message in snapshot.messages {
+                println!(
+                    "- #{} | {}{} | {}",
+                    message.id,
+                    message.role,
+                    if message.compacted {
+                        " (compacted)"
+                    } else {
+                        ""
+                    },
+                    message.content
+                );
+            }
+            println!("open_goals: {}", snapshot.open_goals.len());
+            for goal in snapshot.open_goals {
+                println!("- {} | {} | {}", goal.id, goal.status, goal.description);
+            }
+            println!("open_approvals: {}", snapshot.open_approvals.len());
+            for approval in snapshot.open_approvals {
+                println!(
+                    "- {} | {} | {} | {}",
+                    approval.id, approval.tool_name, approval.capability, approval.status
+                );
+            }
+        }
     }
     Ok(())
 }
@@ -1723,6 +3666,17 @@ fn job(command: JobCommand) -> Result<()> {
             println!("job_id: {job_id}");
         }
         JobCommand::RunNow { job_id } => {
+            if let Some(writer) = remote_writer(&store)? {
+                let output = proxy_to_writer(
+                    &writer,
+                    &format!("/api/jobs/{job_id}/run-now"),
+                    serde_json::json!({}),
+                )?;
+                println!("job_run_now: {job_id}");
+                println!("goal_id: {}", output["goal_id"].as_str().unwrap_or("-"));
+                println!("status: {}", output["status"].as_str().unwrap_or("-"));
+                return Ok(());
+            }
             let outcome = execute_job_now(&db_path, &workspace, &job_id)?;
             println!("job_run_now: {job_id}");
             println!("goal_id: {}", outcome.goal_id);
@@ -1821,125 +3775,143 @@ fn discord(command: DiscordCommand) -> Result<()> {
     Ok(())
 }
 
-fn skill(command: SkillCommand) -> Result<()> {
+fn skill(command: SkillCommand, json: bool) -> Result<()> {
     let config = load_initialized_config()?;
     let workspace_root = config.workspace_dir.clone();
     let store = MemoryStore::open(&workspace_root.join("titan.db"))?;
+    let mut out = Output::new(json);
 
     match command {
         SkillCommand::Search { query, source } => {
-            let adapter = registry_adapter_from_source(&source)?;
+            let adapter = registry_adapter_from_source(&workspace_root, &source)?;
             let hits = search_registry_v1(adapter.as_ref(), &query)?;
-            println!("results: {}", hits.len());
-            for item in hits {
-                println!("{} {} latest={}", item.slug, item.name, item.latest);
+            out.text(format!("results: {}", hits.len()));
+            for item in &hits {
+                out.text(format!(
+                    "{} {} latest={}",
+                    item.slug, item.name, item.latest
+                ));
             }
+            out.json_field(
+                "results",
+                hits.iter()
+                    .map(|item| {
+                        serde_json::json!({
+                            "slug": item.slug,
+                            "name": item.name,
+                            "latest": item.latest,
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            );
         }
         SkillCommand::Install {
             skill,
             source,
             force,
+            allow_source_switch,
         } => {
-            let (slug, version) = parse_slug_and_version(&skill);
-            let adapter = registry_adapter_from_source(&source)?;
-            let staged = stage_install_v1(
-                adapter.as_ref(),
-                &workspace_root,
-                &slug,
-                version.as_deref(),
+            let registries = skill_registries(&config, &workspace_root, source.as_deref())?;
+            let request = request_skill_install(
+                &config,
+                &store,
+                &registries,
+                &skill,
                 force,
+                allow_source_switch,
+                "cli",
             )?;
-            deny_unsigned_risky_install(&staged)?;
-            let payload = approval_payload_for_stage(&staged);
-            let payload_json = serialize_approval_payload(&payload)?;
-            let approval = store.create_approval_request(
-                "skill_install",
-                "write",
-                &payload_json,
-                Some("cli"),
-                300_000,
-            )?;
-
-            let read_only = staged
-                .manifest
-                .permissions
-                .scopes
-                .iter()
-                .all(|scope| matches!(scope, titan_skills::SkillScope::Read));
-            let auto_finalize = matches!(config.mode, AutonomyMode::Autonomous)
-                || (matches!(config.mode, AutonomyMode::Supervised) && read_only);
-
-            if auto_finalize {
-                store.resolve_approval_request(
-                    &approval.id,
-                    true,
-                    Some("cli-auto"),
-                    Some("auto-approved by mode policy"),
-                )?;
-                let installed = finalize_install_from_payload(&payload)?;
-                persist_installed_skill(&store, &installed)?;
-                println!(
-                    "installed: {}@{}",
-                    installed.manifest.slug, installed.manifest.version
-                );
-                println!("approval_id: {}", approval.id);
-                println!("signature_status: {}", installed.signature_status);
-                println!(
-                    "scopes: {}",
-                    format_skill_scopes(&installed.manifest.permissions.scopes)
+            let approval = &request.approval;
+            let payload = &request.payload;
+
+            if let Some(installed) = &request.installed {
+                out.field(
+                    "installed",
+                    format!("{}@{}", installed.manifest.slug, installed.manifest.version),
+                )
+                .field("approval_id", approval.id.as_str())
+                .field("signature_status", installed.signature_status.to_string())
+                .field(
+                    "scopes",
+                    format_skill_scopes(&installed.manifest.permissions.scopes),
                 );
             } else {
-                println!("approval_required: true");
-                println!("approval_id: {}", approval.id);
-                println!("slug: {}", payload.slug);
-                println!("version: {}", payload.version);
-                println!("signature_status: {}", payload.signature_status);
-                println!("scopes: {}", payload.scopes.join(","));
+                out.field("approval_required", true)
+                    .field("approval_id", approval.id.as_str())
+                    .field("slug", payload.slug.as_str())
+                    .field("version", payload.version.as_str())
+                    .field("source", payload.source.as_str());
+                if let Some(previous) = &payload.previous_source {
+                    out.field(
+                        "source_switch",
+                        format!("{} -> {}", previous, payload.source),
+                    );
+                }
+                out.field("signature_status", payload.signature_status.to_string())
+                    .field("scopes", payload.scopes.join(","));
             }
         }
         SkillCommand::List => {
             let items = list_installed_skills_v1(&workspace_root)?;
-            println!("installed_skills: {}", items.len());
-            for skill in items {
-                println!(
+            out.text(format!("installed_skills: {}", items.len()));
+            for skill in &items {
+                out.text(format!(
                     "{} {} signed={} scopes={}",
                     skill.manifest.slug,
                     skill.manifest.version,
                     skill.signature_status,
                     format_skill_scopes(&skill.manifest.permissions.scopes)
-                );
+                ));
             }
+            out.json_field(
+                "installed_skills",
+                items
+                    .iter()
+                    .map(|skill| {
+                        serde_json::json!({
+                            "slug": skill.manifest.slug,
+                            "version": skill.manifest.version,
+                            "signature_status": skill.signature_status.to_string(),
+                            "scopes": format_skill_scopes(&skill.manifest.permissions.scopes),
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            );
         }
         SkillCommand::Inspect { slug, source } => {
             if let Some(local) = list_installed_skills_v1(&workspace_root)?
                 .into_iter()
                 .find(|s| s.manifest.slug == slug)
             {
-                println!("slug: {}", local.manifest.slug);
-                println!("name: {}", local.manifest.name);
-                println!("version: {}", local.manifest.version);
-                println!("entrypoint_type: {:?}", local.manifest.entrypoint_type);
-                println!("entrypoint: {}", local.manifest.entrypoint);
-                println!("signature_status: {}", local.signature_status);
-                println!(
-                    "scopes: {}",
-                    format_skill_scopes(&local.manifest.permissions.scopes)
-                );
-                return Ok(());
+                out.field("slug", local.manifest.slug.as_str())
+                    .field("name", local.manifest.name.as_str())
+                    .field("version", local.manifest.version.as_str())
+                    .field(
+                        "entrypoint_type",
+                        format!("{:?}", local.manifest.entrypoint_type),
+                    )
+                    .field("entrypoint", local.manifest.entrypoint.as_str())
+                    .field("signature_status", local.signature_status.to_string())
+                    .field(
+                        "scopes",
+                        format_skill_scopes(&local.manifest.permissions.scopes),
+                    );
+                return out.finish();
             }
-            let adapter = registry_adapter_from_source(&source)?;
+            let adapter = registry_adapter_from_source(&workspace_root, &source)?;
             let resolved = inspect_registry_v1(adapter.as_ref(), &slug, None)?;
-            println!("slug: {}", resolved.slug);
-            println!("name: {}", resolved.name);
-            println!("version: {}", resolved.version);
-            println!("sha256: {}", resolved.sha256);
-            println!("download_url: {}", resolved.download_url);
+            out.field("slug", resolved.slug)
+                .field("name", resolved.name)
+                .field("version", resolved.version)
+                .field("sha256", resolved.sha256)
+                .field("download_url", resolved.download_url);
         }
         SkillCommand::Update {
             all,
             slug,
             source,
             force,
+            allow_source_switch,
         } => {
             let targets = if all {
                 list_installed_skills_v1(&workspace_root)?
@@ -1950,11 +3922,15 @@ fn skill(command: SkillCommand) -> Result<()> {
                 vec![slug.ok_or_else(|| anyhow::anyhow!("provide <slug> or --all"))?]
             };
             for item in targets {
-                skill(SkillCommand::Install {
-                    skill: item,
-                    source: source.clone(),
-                    force,
-                })?;
+                skill(
+                    SkillCommand::Install {
+                        skill: item,
+                        source: source.clone(),
+                        force,
+                        allow_source_switch,
+                    },
+                    json,
+                )?;
             }
         }
         SkillCommand::Remove { slug } => {
@@ -1988,11 +3964,24 @@ fn skill(command: SkillCommand) -> Result<()> {
                     .map(|entry| entry.hash == skill.hash && entry.version == skill.manifest.version)
                     .unwrap_or(false)
             );
+            match store.get_skill_quarantine(&skill.manifest.slug)? {
+                Some(quarantine) => println!(
+                    "quarantined: true violations={} reason={}",
+                    quarantine.violation_count, quarantine.reason
+                ),
+                None => println!("quarantined: false"),
+            }
+        }
+        SkillCommand::Release { slug } => {
+            let released = store.release_skill_quarantine(&slug)?;
+            println!("released: {}", released);
+            println!("slug: {}", slug);
         }
         SkillCommand::Run { slug, input } => {
             let outcome = run_skill_v1(
                 &store,
                 &workspace_root,
+                WasmLimits::from_config(&config.skills),
                 config.mode.clone(),
                 "cli",
                 &slug,
@@ -2012,6 +4001,116 @@ fn skill(command: SkillCommand) -> Result<()> {
                 }
             }
         }
+        SkillCommand::New {
+            slug,
+            entrypoint_type,
+            name,
+            version,
+            description,
+            dir,
+            sign_key,
+            key_id,
+            register,
+        } => {
+            let entrypoint_type = match entrypoint_type.trim().to_ascii_lowercase().as_str() {
+                "prompt" => SkillEntrypointType::Prompt,
+                "http" => SkillEntrypointType::Http,
+                "wasm" => SkillEntrypointType::Wasm,
+                other => bail!("unsupported entrypoint type: {other} (use prompt|http|wasm)"),
+            };
+            let target = dir.unwrap_or_else(|| PathBuf::from(&slug));
+            let scaffolded = scaffold_skill_v1(
+                &target,
+                &SkillScaffoldOptions {
+                    name: name.unwrap_or_else(|| slug.clone()),
+                    description: description.unwrap_or_else(|| format!("{slug} skill")),
+                    slug,
+                    version,
+                    entrypoint_type,
+                },
+            )?;
+            println!("skill_scaffolded: {}", scaffolded.bundle_dir.display());
+            println!(
+                "skill: {}@{}",
+                scaffolded.manifest.slug, scaffolded.manifest.version
+            );
+            if let Some(key_path) = sign_key {
+                let secret = std::fs::read_to_string(&key_path)
+                    .with_context(|| format!("failed to read {}", key_path.display()))?;
+                let signature = sign_skill_bundle_v1(&scaffolded.bundle_dir, &key_id, &secret)?;
+                println!("signed_with: {}", signature.public_key_id);
+            }
+            println!(
+                "hash: {}",
+                titan_skills::compute_bundle_hash(&scaffolded.bundle_dir)?
+            );
+            if register {
+                let registry_root = titan_skills::default_registry_root();
+                let published = publish_to_local_registry(
+                    &registry_root,
+                    &scaffolded.bundle_dir,
+                    &PublishOptions {
+                        copy_bundle: true,
+                        overwrite: true,
+                    },
+                )?;
+                println!(
+                    "registered: {}",
+                    registry_root.join(published.entry.download_url).display()
+                );
+            }
+        }
+        SkillCommand::Publish {
+            bundle_dir,
+            registry,
+            no_copy,
+            overwrite,
+        } => {
+            let registry_root = registry.unwrap_or_else(titan_skills::default_registry_root);
+            let published = publish_to_local_registry(
+                &registry_root,
+                &bundle_dir,
+                &PublishOptions {
+                    copy_bundle: !no_copy,
+                    overwrite,
+                },
+            )?;
+            println!("published: {}@{}", published.slug, published.entry.version);
+            println!("sha256: {}", published.entry.sha256);
+            println!("download_url: {}", published.entry.download_url);
+            println!("latest: {}", published.latest);
+            println!("replaced: {}", published.replaced);
+            println!("index: {}", registry_root.join("index.json").display());
+        }
+        SkillCommand::Test {
+            slug,
+            input,
+            keep_sandbox,
+        } => {
+            let report = test_skill_v1(&workspace_root, &slug, input.as_deref(), keep_sandbox)?;
+            println!("skill: {}@{}", report.slug, report.version);
+            println!("tool: {}", report.tool_name);
+            println!(
+                "tool_input: {}",
+                report.tool_input.as_deref().unwrap_or("<none>")
+            );
+            println!("status: {}", report.status);
+            println!("violations: {}", report.violations.len());
+            for violation in &report.violations {
+                println!("- {violation}");
+            }
+            println!("sandbox_changes: {}", report.changed_paths.len());
+            for path in &report.changed_paths {
+                println!("- {path}");
+            }
+            if let Some(root) = &report.sandbox_root {
+                println!("sandbox_root: {}", root.display());
+            }
+            println!("output:\n{}", report.output);
+            if !report.passed() {
+                bail!("skill test failed for {}", report.slug);
+            }
+        }
         SkillCommand::Validate { skill_dir } => {
             let package = SkillPackage::load(&skill_dir)?;
             println!("skill_valid: true");
@@ -2020,25 +4119,91 @@ fn skill(command: SkillCommand) -> Result<()> {
             println!("entrypoint: {}", package.wasm_path.display());
         }
     }
-    Ok(())
+    out.finish()
 }
 
-fn registry_adapter_from_source(source: &str) -> Result<Box<dyn SkillRegistryAdapter>> {
-    let trimmed = source.trim();
-    if trimmed.eq_ignore_ascii_case("local") {
-        let root = titan_skills::default_registry_root();
-        return Ok(Box::new(LocalRegistryAdapter::new(root)));
-    }
-    if let Some(path) = trimmed.strip_prefix("local:") {
-        return Ok(Box::new(LocalRegistryAdapter::new(PathBuf::from(path))));
-    }
-    if let Some(url) = trimmed.strip_prefix("git:") {
-        return Ok(Box::new(titan_skills::GitRegistryAdapter::new(url)));
+struct SkillInstallRequest {
+    approval: titan_memory::ApprovalRecord,
+    payload: titan_skills::SkillApprovalPayload,
+    // Set when mode policy finalized the install without waiting for an operator.
+    installed: Option<titan_skills::InstalledSkillV1>,
+}
+
+fn request_skill_install(
+    config: &TitanConfig,
+    store: &MemoryStore,
+    registries: &[NamedRegistry],
+    skill: &str,
+    force: bool,
+    allow_source_switch: bool,
+    requested_by: &str,
+) -> Result<SkillInstallRequest> {
+    let (slug, version) = parse_slug_and_version(skill);
+    let staged = stage_install_from_registries(
+        registries,
+        &config.workspace_dir,
+        &slug,
+        version.as_deref(),
+        force,
+        allow_source_switch,
+        &titan_skills::default_trust_root(),
+    )?;
+    deny_unsigned_risky_install(&staged)?;
+    let payload = approval_payload_for_stage(&staged);
+    let payload_json = serialize_approval_payload(&payload)?;
+    let approval = store.create_approval_request(
+        "skill_install",
+        "write",
+        &payload_json,
+        Some(requested_by),
+        PolicyEngine::approval_ttl_ms("write"),
+    )?;
+
+    let read_only = staged
+        .manifest
+        .permissions
+        .scopes
+        .iter()
+        .all(|scope| matches!(scope, titan_skills::SkillScope::Read));
+    let auto_finalize = payload.previous_source.is_none()
+        && (matches!(config.mode, AutonomyMode::Autonomous)
+            || (matches!(config.mode, AutonomyMode::Supervised) && read_only));
+    if !auto_finalize {
+        return Ok(SkillInstallRequest {
+            approval,
+            payload,
+            installed: None,
+        });
     }
-    if let Some(url) = trimmed.strip_prefix("http:") {
-        return Ok(Box::new(titan_skills::HttpRegistryAdapter::new(url)));
+    store.resolve_approval_request(
+        &approval.id,
+        true,
+        Some(&format!("{requested_by}-auto")),
+        Some("auto-approved by mode policy"),
+        None,
+    )?;
+    let installed = finalize_install_from_payload(&payload)?;
+    persist_installed_skill(store, &installed)?;
+    Ok(SkillInstallRequest {
+        approval,
+        payload,
+        installed: Some(installed),
+    })
+}
+
+fn skill_registries(
+    config: &TitanConfig,
+    workspace_root: &Path,
+    source: Option<&str>,
+) -> Result<Vec<NamedRegistry>> {
+    if let Some(source) = source {
+        return Ok(vec![NamedRegistry {
+            name: source.trim().to_string(),
+            priority: 0,
+            adapter: registry_adapter_from_source(workspace_root, source)?,
+        }]);
     }
-    bail!("unsupported skill registry source: {source}");
+    configured_registries(&config.skills.registries, workspace_root)
 }
 
 fn parse_slug_and_version(input: &str) -> (String, Option<String>) {
@@ -2048,32 +4213,797 @@ fn parse_slug_and_version(input: &str) -> (String, Option<String>) {
     }
 }
 
-fn format_skill_scopes(scopes: &[titan_skills::SkillScope]) -> String {
-    scopes
-        .iter()
-        .map(|scope| scope.as_str())
-        .collect::<Vec<_>>()
-        .join(",")
-}
-
-fn persist_installed_skill(
-    store: &MemoryStore,
-    installed: &titan_skills::InstalledSkillV1,
-) -> Result<()> {
-    let record = titan_memory::InstalledSkillRecord {
-        slug: installed.manifest.slug.clone(),
-        name: installed.manifest.name.clone(),
-        version: installed.manifest.version.clone(),
-        description: installed.manifest.description.clone(),
-        source: installed.source.clone(),
-        hash: installed.hash.clone(),
-        signature_status: installed.signature_status.clone(),
-        scopes: format_skill_scopes(&installed.manifest.permissions.scopes),
-        allowed_paths: installed.manifest.permissions.allowed_paths.join(","),
-        allowed_hosts: installed.manifest.permissions.allowed_hosts.join(","),
-        last_run_goal_id: None,
-    };
-    store.upsert_installed_skill(&record)?;
+fn format_skill_scopes(scopes: &[titan_skills::SkillScope]) -> String {
+    scopes
+        .iter()
+        .map(|scope| scope.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn persist_installed_skill(
+    store: &MemoryStore,
+    installed: &titan_skills::InstalledSkillV1,
+) -> Result<()> {
+    let record = titan_memory::InstalledSkillRecord {
+        slug: installed.manifest.slug.clone(),
+        name: installed.manifest.name.clone(),
+        version: installed.manifest.version.clone(),
+        description: installed.manifest.description.clone(),
+        source: installed.source.clone(),
+        hash: installed.hash.clone(),
+        signature_status: installed.signature_status.clone(),
+        scopes: format_skill_scopes(&installed.manifest.permissions.scopes),
+        allowed_paths: installed.manifest.permissions.allowed_paths.join(","),
+        allowed_hosts: installed.manifest.permissions.allowed_hosts.join(","),
+        last_run_goal_id: None,
+    };
+    store.upsert_installed_skill(&record)?;
+    Ok(())
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BootstrapSpec {
+    #[serde(default)]
+    allowlist: Vec<String>,
+    #[serde(default)]
+    connectors: Vec<BootstrapConnector>,
+    #[serde(default)]
+    skills: Vec<String>,
+    #[serde(default)]
+    personas: Vec<BootstrapPersona>,
+    #[serde(default)]
+    goals: Vec<BootstrapGoal>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BootstrapConnector {
+    name: String,
+    #[serde(rename = "type")]
+    connector_type: String,
+    config: Option<toml::Table>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BootstrapPersona {
+    name: String,
+    description: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BootstrapGoal {
+    name: String,
+    interval: Option<String>,
+    cron: Option<String>,
+    template: String,
+    mode: Option<String>,
+    #[serde(default = "default_allowed_scopes")]
+    allowed_scopes: String,
+}
+
+fn default_allowed_scopes() -> String {
+    "[]".to_string()
+}
+
+#[derive(Debug)]
+struct BootstrapAction {
+    kind: &'static str,
+    name: String,
+    outcome: String,
+}
+
+fn bootstrap(command: BootstrapCommand) -> Result<()> {
+    let config = load_initialized_config()?;
+    let store = MemoryStore::open(&config.workspace_dir.join("titan.db"))?;
+
+    match command {
+        BootstrapCommand::Apply { file } => {
+            let raw = fs::read_to_string(&file)
+                .with_context(|| format!("failed to read {}", file.display()))?;
+            let spec: BootstrapSpec = toml::from_str(&raw)
+                .with_context(|| format!("invalid bootstrap file {}", file.display()))?;
+            let actions = apply_bootstrap(&config, &TitanConfig::resolve_path(), &store, &spec)?;
+            println!("bootstrap_applied: {}", file.display());
+            for action in actions {
+                println!("{} {} {}", action.kind, action.name, action.outcome);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Reconciliation only adds or updates; entries missing from the file are left alone so a
+// partial bootstrap file cannot delete live state. Re-running with the same file is a no-op.
+fn apply_bootstrap(
+    config: &TitanConfig,
+    config_path: &Path,
+    store: &MemoryStore,
+    spec: &BootstrapSpec,
+) -> Result<Vec<BootstrapAction>> {
+    for connector in &spec.connectors {
+        let Some(parsed) = ConnectorType::parse(&connector.connector_type) else {
+            bail!(
+                "connector '{}' has unsupported type: {}",
+                connector.name,
+                connector.connector_type
+            );
+        };
+        if let Some(table) = &connector.config {
+            check_connector_config(parsed, &serde_json::to_value(table)?)
+                .with_context(|| format!("connector '{}'", connector.name))?;
+        }
+    }
+    for goal in &spec.goals {
+        if goal.interval.is_some() == goal.cron.is_some() {
+            bail!("goal '{}' needs exactly one of interval or cron", goal.name);
+        }
+        if goal.mode.is_some() && normalize_job_mode(goal.mode.as_deref()).is_none() {
+            bail!("goal '{}' has unknown mode", goal.name);
+        }
+    }
+
+    let mut actions = Vec::new();
+    let pending = store.list_pending_approvals()?;
+
+    let mut allowlist_added = Vec::new();
+    for id in spec.allowlist.iter().map(|id| id.trim()) {
+        if config.chat.allowlist.iter().any(|existing| existing == id) {
+            actions.push(bootstrap_action("allowlist", id, "unchanged"));
+            continue;
+        }
+        if !titan_gateway::requires_config_approval(config.mode.clone()) {
+            allowlist_added.push(id.to_string());
+            actions.push(bootstrap_action("allowlist", id, "added"));
+            continue;
+        }
+        let payload = ConfigAllowlistPayload {
+            action: AllowlistAction::Add,
+            id: id.to_string(),
+        };
+        let input = encode_approval_payload(&payload)?;
+        let approval_id = match pending.iter().find(|a| {
+            a.tool_name == "config_allowlist"
+                && decode_approval_payload::<ConfigAllowlistPayload>(&a.input)
+                    .is_ok_and(|existing| existing == payload)
+        }) {
+            Some(existing) => existing.id.clone(),
+            None => {
+                store
+                    .create_approval_request(
+                        "config_allowlist",
+                        "write",
+                        &input,
+                        Some("bootstrap"),
+                        PolicyEngine::approval_ttl_ms("write"),
+                    )?
+                    .id
+            }
+        };
+        actions.push(bootstrap_action(
+            "allowlist",
+            id,
+            &format!("approval_required approval_id={approval_id}"),
+        ));
+    }
+    if !allowlist_added.is_empty() {
+        let mut updated = config.clone();
+        updated.chat.allowlist.extend(allowlist_added);
+        updated.save(config_path)?;
+    }
+
+    let connectors = store.list_connectors()?;
+    for connector in &spec.connectors {
+        let parsed = ConnectorType::parse(&connector.connector_type).expect("validated above");
+        let config_value = match &connector.config {
+            Some(table) => serde_json::to_value(table)?,
+            None => default_connector_config(parsed),
+        };
+        match connectors
+            .iter()
+            .find(|row| row.display_name == connector.name)
+        {
+            Some(row) if row.connector_type != parsed.as_str() => bail!(
+                "connector '{}' already exists with type {}",
+                connector.name,
+                row.connector_type
+            ),
+            Some(row) => {
+                let current: Value = serde_json::from_str(&row.config_json).unwrap_or_default();
+                if current == config_value {
+                    actions.push(bootstrap_action("connector", &connector.name, "unchanged"));
+                } else {
+                    store.update_connector(&row.id, &connector.name, &config_value.to_string())?;
+                    actions.push(bootstrap_action("connector", &connector.name, "updated"));
+                }
+            }
+            None => {
+                let id = Uuid::new_v4().to_string();
+                store.add_connector(
+                    &id,
+                    parsed.as_str(),
+                    &connector.name,
+                    &config_value.to_string(),
+                )?;
+                actions.push(bootstrap_action("connector", &connector.name, "created"));
+            }
+        }
+    }
+
+    if !spec.skills.is_empty() {
+        let installed = list_installed_skills_v1(&config.workspace_dir)?;
+        let registries = skill_registries(config, &config.workspace_dir, None)?;
+        for skill in &spec.skills {
+            let (slug, version) = parse_slug_and_version(skill);
+            let matches_version =
+                |candidate: &str| version.as_deref().is_none_or(|v| v == candidate);
+            if installed
+                .iter()
+                .any(|s| s.manifest.slug == slug && matches_version(&s.manifest.version))
+            {
+                actions.push(bootstrap_action("skill", skill, "unchanged"));
+                continue;
+            }
+            let already_requested = pending.iter().find(|a| {
+                a.tool_name == "skill_install"
+                    && deserialize_approval_payload(&a.input).is_ok_and(|payload| {
+                        payload.slug == slug && matches_version(&payload.version)
+                    })
+            });
+            if let Some(approval) = already_requested {
+                actions.push(bootstrap_action(
+                    "skill",
+                    skill,
+                    &format!("approval_required approval_id={}", approval.id),
+                ));
+                continue;
+            }
+            let request = request_skill_install(
+                config,
+                store,
+                &registries,
+                skill,
+                false,
+                false,
+                "bootstrap",
+            )?;
+            let outcome = match &request.installed {
+                Some(installed) => format!("installed version={}", installed.manifest.version),
+                None => format!("approval_required approval_id={}", request.approval.id),
+            };
+            actions.push(bootstrap_action("skill", skill, &outcome));
+        }
+    }
+
+    for persona in &spec.personas {
+        let current = store.get_semantic_fact("persona", &persona.name)?;
+        if current.as_deref() == Some(persona.description.as_str()) {
+            actions.push(bootstrap_action("persona", &persona.name, "unchanged"));
+            continue;
+        }
+        store.upsert_semantic_fact("persona", &persona.name, &persona.description, "bootstrap")?;
+        let outcome = if current.is_some() {
+            "updated"
+        } else {
+            "created"
+        };
+        actions.push(bootstrap_action("persona", &persona.name, outcome));
+    }
+
+    let jobs = store.list_jobs()?;
+    for goal in &spec.goals {
+        let (schedule_kind, schedule_value) = match (&goal.interval, &goal.cron) {
+            (Some(v), None) => ("interval", v.trim()),
+            (None, Some(v)) => ("cron", v.trim()),
+            _ => unreachable!("validated above"),
+        };
+        let mode = normalize_job_mode(goal.mode.as_deref()).unwrap_or_else(|| {
+            normalize_job_mode(Some(autonomy_mode_name(&config.mode))).expect("valid mode")
+        });
+        let existing = jobs.iter().find(|job| job.name == goal.name);
+        let job_id = existing
+            .map(|job| job.job_id.clone())
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let record = NewJobRecord {
+            job_id: &job_id,
+            name: &goal.name,
+            schedule_kind,
+            schedule_value,
+            goal_template: goal.template.trim(),
+            mode,
+            allowed_scopes: goal.allowed_scopes.trim(),
+        };
+        let outcome = match existing {
+            Some(job)
+                if job.schedule_kind == record.schedule_kind
+                    && job.schedule_value == record.schedule_value
+                    && job.goal_template == record.goal_template
+                    && job.mode == record.mode
+                    && job.allowed_scopes == record.allowed_scopes =>
+            {
+                "unchanged"
+            }
+            Some(_) => {
+                store.update_job_definition(record)?;
+                "updated"
+            }
+            None => {
+                store.add_job(record)?;
+                "created"
+            }
+        };
+        actions.push(bootstrap_action("goal", &goal.name, outcome));
+    }
+
+    Ok(actions)
+}
+
+fn bootstrap_action(kind: &'static str, name: &str, outcome: &str) -> BootstrapAction {
+    BootstrapAction {
+        kind,
+        name: name.to_string(),
+        outcome: outcome.to_string(),
+    }
+}
+
+const STATE_BUNDLE_FORMAT: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StateBundleManifest {
+    format_version: u32,
+    titan_version: String,
+    schema_version: i64,
+    created_at_ms: i64,
+    secrets_included: bool,
+    entries: Vec<String>,
+}
+
+// Host locations that make up a TITAN install; tests point these at temp dirs.
+struct StatePaths {
+    config_path: PathBuf,
+    workspace_dir: PathBuf,
+    trust_root: PathBuf,
+    secrets_path: PathBuf,
+}
+
+impl StatePaths {
+    fn for_host(config: &TitanConfig) -> Self {
+        Self {
+            config_path: TitanConfig::resolve_path(),
+            workspace_dir: config.workspace_dir.clone(),
+            trust_root: titan_skills::default_trust_root(),
+            secrets_path: SecretsStore::default_path(),
+        }
+    }
+}
+
+fn export_command(out: &Path, include_secrets: bool) -> Result<()> {
+    let config = load_initialized_config()?;
+    let manifest = export_state(&StatePaths::for_host(&config), out, include_secrets)?;
+    println!("export_written: {}", out.display());
+    println!("schema_version: {}", manifest.schema_version);
+    println!("secrets_included: {}", manifest.secrets_included);
+    println!("entries: {}", manifest.entries.join(","));
+    Ok(())
+}
+
+fn import_command(bundle: &Path, force: bool, skip_secrets: bool) -> Result<()> {
+    let config = load_initialized_config()?;
+    let manifest = import_state(&StatePaths::for_host(&config), bundle, force, skip_secrets)?;
+    println!("import_applied: {}", bundle.display());
+    println!("exported_by: titan {}", manifest.titan_version);
+    println!("schema_version: {}", manifest.schema_version);
+    println!("entries: {}", manifest.entries.join(","));
+    Ok(())
+}
+
+fn export_state(
+    paths: &StatePaths,
+    out: &Path,
+    include_secrets: bool,
+) -> Result<StateBundleManifest> {
+    let staging = std::env::temp_dir().join(format!("titan-export-{}", Uuid::new_v4()));
+    fs::create_dir_all(&staging)?;
+    let result = write_state_bundle(paths, out, include_secrets, &staging);
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+fn write_state_bundle(
+    paths: &StatePaths,
+    out: &Path,
+    include_secrets: bool,
+    staging: &Path,
+) -> Result<StateBundleManifest> {
+    let store = MemoryStore::open(&paths.workspace_dir.join("titan.db"))?;
+    store.backup_to(&staging.join("titan.db"))?;
+    let mut files = vec![("titan.db".to_string(), staging.join("titan.db"))];
+    if paths.config_path.exists() {
+        let mut config = TitanConfig::load(&paths.config_path)?;
+        if !include_secrets {
+            config.discord.token = None;
+        }
+        config.save(&staging.join("config.toml"))?;
+        files.push(("config.toml".to_string(), staging.join("config.toml")));
+    }
+    let lock_path = titan_skills::skills_lock_path(&paths.workspace_dir);
+    if lock_path.exists() {
+        files.push(("skills.lock".to_string(), lock_path));
+    }
+    // Readable audit copy of the risk-mode timeline; import restores it from titan.db.
+    let risk_history = staging.join("risk_state_history.jsonl");
+    let mut lines = String::new();
+    for row in store
+        .list_risk_state_history(i64::MAX as usize)?
+        .into_iter()
+        .rev()
+    {
+        lines.push_str(&serde_json::to_string(&serde_json::json!({
+            "id": row.id,
+            "action": row.action,
+            "risk_mode": row.risk_mode.as_str(),
+            "actor": row.actor,
+            "reason": row.reason,
+            "expires_at_ms": row.expires_at_ms,
+            "created_at_ms": row.created_at_ms,
+        }))?);
+        lines.push('\n');
+    }
+    fs::write(&risk_history, lines)?;
+    files.push(("audit/risk_state_history.jsonl".to_string(), risk_history));
+    if include_secrets && paths.secrets_path.exists() {
+        files.push(("secrets.enc".to_string(), paths.secrets_path.clone()));
+    }
+    let mut dirs = Vec::new();
+    let skills_dir = paths.workspace_dir.join("skills");
+    if skills_dir.is_dir() {
+        dirs.push(("skills".to_string(), skills_dir));
+    }
+    // Large outputs referenced from titan.db; custom blob dirs and S3 buckets are not bundled.
+    let blobs_dir = paths.workspace_dir.join("blobs");
+    if blobs_dir.is_dir() {
+        dirs.push(("blobs".to_string(), blobs_dir));
+    }
+    if paths.trust_root.is_dir() {
+        dirs.push(("trust/keys".to_string(), paths.trust_root.clone()));
+    }
+
+    let manifest = StateBundleManifest {
+        format_version: STATE_BUNDLE_FORMAT,
+        titan_version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version: store.schema_version()?,
+        created_at_ms: current_epoch_ms(),
+        secrets_included: include_secrets,
+        entries: files
+            .iter()
+            .chain(dirs.iter())
+            .map(|(name, _)| name.clone())
+            .collect(),
+    };
+    if let Some(parent) = out.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = fs::File::create(out)
+        .with_context(|| format!("failed to create bundle {}", out.display()))?;
+    let mut builder = tar::Builder::new(zstd::Encoder::new(file, 3)?);
+    let manifest_raw = serde_json::to_vec_pretty(&manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest_raw.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, "manifest.json", manifest_raw.as_slice())?;
+    for (name, path) in &files {
+        builder.append_path_with_name(path, name)?;
+    }
+    for (name, path) in &dirs {
+        builder.append_dir_all(name, path)?;
+    }
+    builder.into_inner()?.finish()?;
+    Ok(manifest)
+}
+
+fn import_state(
+    paths: &StatePaths,
+    bundle: &Path,
+    force: bool,
+    skip_secrets: bool,
+) -> Result<StateBundleManifest> {
+    let staging = std::env::temp_dir().join(format!("titan-import-{}", Uuid::new_v4()));
+    fs::create_dir_all(&staging)?;
+    let result = apply_state_bundle(paths, bundle, force, skip_secrets, &staging);
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+fn apply_state_bundle(
+    paths: &StatePaths,
+    bundle: &Path,
+    force: bool,
+    skip_secrets: bool,
+    staging: &Path,
+) -> Result<StateBundleManifest> {
+    let file = fs::File::open(bundle)
+        .with_context(|| format!("failed to open bundle {}", bundle.display()))?;
+    tar::Archive::new(zstd::Decoder::new(file)?)
+        .unpack(staging)
+        .with_context(|| format!("failed to unpack bundle {}", bundle.display()))?;
+    let manifest: StateBundleManifest = serde_json::from_slice(
+        &fs::read(staging.join("manifest.json")).context("bundle has no manifest.json")?,
+    )
+    .context("invalid bundle manifest")?;
+    if manifest.format_version != STATE_BUNDLE_FORMAT {
+        bail!(
+            "unsupported bundle format {} (this titan reads format {})",
+            manifest.format_version,
+            STATE_BUNDLE_FORMAT
+        );
+    }
+
+    let db_path = paths.workspace_dir.join("titan.db");
+    if db_path.exists() && !force {
+        bail!(
+            "workspace already has state at {}; pass --force to replace it",
+            db_path.display()
+        );
+    }
+    let mut store = MemoryStore::open(&db_path)?;
+    let supported = store.schema_version()?;
+    if manifest.schema_version > supported {
+        bail!(
+            "bundle schema version {} is newer than this titan supports ({}); upgrade titan first",
+            manifest.schema_version,
+            supported
+        );
+    }
+    store.restore_from(&staging.join("titan.db"))?;
+
+    let bundled_config = staging.join("config.toml");
+    if bundled_config.exists() {
+        let mut config = TitanConfig::load(&bundled_config)?;
+        // Paths belong to the new host; a redacted token keeps whatever the host already has.
+        config.workspace_dir = paths.workspace_dir.clone();
+        if config.discord.token.is_none() && paths.config_path.exists() {
+            config.discord.token = TitanConfig::load(&paths.config_path)?.discord.token;
+        }
+        config.save(&paths.config_path)?;
+    }
+    let bundled_lock = staging.join("skills.lock");
+    if bundled_lock.exists() {
+        fs::copy(
+            &bundled_lock,
+            titan_skills::skills_lock_path(&paths.workspace_dir),
+        )?;
+    }
+    let bundled_skills = staging.join("skills");
+    if bundled_skills.is_dir() {
+        let target = paths.workspace_dir.join("skills");
+        if target.exists() {
+            fs::remove_dir_all(&target)?;
+        }
+        copy_tree(&bundled_skills, &target)?;
+    }
+    let bundled_blobs = staging.join("blobs");
+    if bundled_blobs.is_dir() {
+        copy_tree(&bundled_blobs, &paths.workspace_dir.join("blobs"))?;
+    }
+    let bundled_keys = staging.join("trust/keys");
+    if bundled_keys.is_dir() {
+        copy_tree(&bundled_keys, &paths.trust_root)?;
+    }
+    let bundled_secrets = staging.join("secrets.enc");
+    if bundled_secrets.exists() && !skip_secrets {
+        if let Some(parent) = paths.secrets_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&bundled_secrets, &paths.secrets_path)?;
+    }
+    Ok(manifest)
+}
+
+const BACKUP_SUFFIX: &str = ".tar.zst.enc";
+
+// A backup bucket with its credentials already resolved from the secrets store.
+struct BackupTarget {
+    client: S3Client,
+    prefix: String,
+    credentials: S3Credentials,
+}
+
+impl BackupTarget {
+    fn open(config: &BackupConfig, secrets: &dyn SecretResolver) -> Result<Self> {
+        let Some(s3) = &config.s3 else {
+            bail!("no backup target configured; add a [backup.s3] section to config.toml");
+        };
+        Ok(Self {
+            client: S3Client::new(&s3.endpoint, &s3.bucket, &s3.region),
+            prefix: s3.prefix.clone(),
+            credentials: S3Credentials {
+                access_key: required_secret(secrets, &s3.access_key_secret)?,
+                secret_key: required_secret(secrets, &s3.secret_key_secret)?,
+            },
+        })
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("s3://{}/{key}", self.client.bucket())
+    }
+
+    // Oldest first; keys embed a zero-padded timestamp, so key order is age order.
+    fn backups(&self) -> Result<Vec<S3Object>> {
+        let own = format!("{}titan-", self.prefix);
+        Ok(self
+            .client
+            .list(&self.credentials, &self.prefix)?
+            .into_iter()
+            .filter(|object| object.key.starts_with(&own) && object.key.ends_with(BACKUP_SUFFIX))
+            .collect())
+    }
+
+    fn prune(&self, keep_last: usize) -> Result<Vec<String>> {
+        let backups = self.backups()?;
+        let excess = backups.len().saturating_sub(keep_last);
+        let mut removed = Vec::new();
+        for object in backups.into_iter().take(excess) {
+            self.client.delete(&self.credentials, &object.key)?;
+            removed.push(object.key);
+        }
+        Ok(removed)
+    }
+}
+
+fn required_secret(secrets: &dyn SecretResolver, key_id: &str) -> Result<String> {
+    secrets.get_secret(key_id)?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "secret {key_id} is not set; run `titan secrets set {key_id}` and export TITAN_SECRETS_PASSPHRASE"
+        )
+    })
+}
+
+#[derive(Debug)]
+struct BackupPushReport {
+    url: String,
+    size_bytes: usize,
+    manifest: StateBundleManifest,
+    pruned: Vec<String>,
+}
+
+fn push_backup(
+    paths: &StatePaths,
+    config: &BackupConfig,
+    secrets: &dyn SecretResolver,
+) -> Result<BackupPushReport> {
+    let target = BackupTarget::open(config, secrets)?;
+    let passphrase = required_secret(secrets, &config.passphrase_secret)?;
+    let staging = std::env::temp_dir().join(format!("titan-backup-{}", Uuid::new_v4()));
+    fs::create_dir_all(&staging)?;
+    let bundle = staging.join("bundle.tar.zst");
+    let exported = export_state(paths, &bundle, config.include_secrets)
+        .and_then(|manifest| Ok((manifest, fs::read(&bundle)?)));
+    let _ = fs::remove_dir_all(&staging);
+    let (manifest, raw) = exported?;
+    let sealed = titan_secrets::seal_with_passphrase(&passphrase, &raw)?;
+    let key = format!(
+        "{}titan-{:013}{BACKUP_SUFFIX}",
+        target.prefix, manifest.created_at_ms
+    );
+    target.client.put(&target.credentials, &key, &sealed)?;
+    let pruned = target.prune(config.keep_last)?;
+    Ok(BackupPushReport {
+        url: target.url(&key),
+        size_bytes: sealed.len(),
+        manifest,
+        pruned,
+    })
+}
+
+/// Restores `from` (`s3://<bucket>/<key>` or `latest`) through the regular import path.
+fn restore_backup(
+    paths: &StatePaths,
+    config: &BackupConfig,
+    secrets: &dyn SecretResolver,
+    from: &str,
+    force: bool,
+    skip_secrets: bool,
+) -> Result<(String, StateBundleManifest)> {
+    let mut target = BackupTarget::open(config, secrets)?;
+    let key = if from == "latest" {
+        target
+            .backups()?
+            .pop()
+            .map(|object| object.key)
+            .ok_or_else(|| {
+                anyhow::anyhow!("no backups found under {}", target.url(&target.prefix))
+            })?
+    } else {
+        let Some((bucket, key)) = parse_s3_url(from) else {
+            bail!("--from must be s3://<bucket>/<key> or latest");
+        };
+        if bucket != target.client.bucket()
+            && let Some(s3) = &config.s3
+        {
+            target.client = S3Client::new(&s3.endpoint, bucket, &s3.region);
+        }
+        key.to_string()
+    };
+    let sealed = target.client.get(&target.credentials, &key)?;
+    let passphrase = required_secret(secrets, &config.passphrase_secret)?;
+    let raw = titan_secrets::open_with_passphrase(&passphrase, &sealed)
+        .with_context(|| format!("failed to decrypt {}", target.url(&key)))?;
+    let staging = std::env::temp_dir().join(format!("titan-restore-{}", Uuid::new_v4()));
+    fs::create_dir_all(&staging)?;
+    let bundle = staging.join("bundle.tar.zst");
+    let restored = fs::write(&bundle, raw)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| import_state(paths, &bundle, force, skip_secrets));
+    let _ = fs::remove_dir_all(&staging);
+    Ok((target.url(&key), restored?))
+}
+
+fn backup(command: BackupCommand) -> Result<()> {
+    let config = load_initialized_config()?;
+    let paths = StatePaths::for_host(&config);
+    let secrets = CompositeSecretResolver::from_env()?;
+
+    match command {
+        BackupCommand::Push => {
+            let report = push_backup(&paths, &config.backup, &secrets)?;
+            println!("backup_pushed: {}", report.url);
+            println!("bytes: {}", report.size_bytes);
+            println!("schema_version: {}", report.manifest.schema_version);
+            println!("secrets_included: {}", report.manifest.secrets_included);
+            println!("pruned: {}", report.pruned.len());
+        }
+        BackupCommand::List => {
+            let target = BackupTarget::open(&config.backup, &secrets)?;
+            let backups = target.backups()?;
+            println!("backups: {}", backups.len());
+            for object in backups.iter().rev() {
+                println!(
+                    "- {} | {} bytes",
+                    target.url(&object.key),
+                    object.size_bytes
+                );
+            }
+        }
+        BackupCommand::Prune => {
+            let target = BackupTarget::open(&config.backup, &secrets)?;
+            let removed = target.prune(config.backup.keep_last)?;
+            println!("pruned: {}", removed.len());
+            for key in removed {
+                println!("- {}", target.url(&key));
+            }
+        }
+        BackupCommand::Restore {
+            from,
+            force,
+            skip_secrets,
+        } => {
+            let (url, manifest) =
+                restore_backup(&paths, &config.backup, &secrets, &from, force, skip_secrets)?;
+            println!("restore_applied: {url}");
+            println!("exported_by: titan {}", manifest.titan_version);
+            println!("schema_version: {}", manifest.schema_version);
+            println!("entries: {}", manifest.entries.join(","));
+        }
+    }
+    Ok(())
+}
+
+fn copy_tree(source: &Path, target: &Path) -> Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let destination = target.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_tree(&entry.path(), &destination)?;
+        } else {
+            fs::copy(entry.path(), &destination)?;
+        }
+    }
     Ok(())
 }
 
@@ -2081,21 +5011,42 @@ fn web(command: WebCommand) -> Result<()> {
     let config = load_initialized_config()?;
 
     match command {
-        WebCommand::Serve { bind } => {
+        WebCommand::Serve { bind, viewer } => {
             let db_path = config.workspace_dir.join("titan.db");
             let runtime = tokio::runtime::Builder::new_multi_thread()
                 .enable_all()
                 .build()?;
+            let trace_view = if viewer {
+                TraceView::Redacted
+            } else {
+                TraceView::Full
+            };
             println!("web_status: starting");
             println!("bind: {}", bind);
             println!("db: {}", db_path.display());
-            runtime.block_on(web_runtime::serve(
-                &bind,
-                db_path,
-                config.workspace_dir.clone(),
-                autonomy_mode_name(&config.mode).to_string(),
-                config.security.yolo_bypass_path_guard,
-            ))?;
+            println!("trace_view: {}", trace_view.as_str());
+            runtime.block_on(async {
+                let events = EventBus::default();
+                events.spawn_pump(db_path.clone(), Duration::from_millis(500));
+                if !viewer {
+                    WriterLease::new(db_path.clone(), Some(format!("http://{bind}")))
+                        .spawn_heartbeat();
+                }
+                web_runtime::serve(
+                    &bind,
+                    db_path,
+                    config.workspace_dir.clone(),
+                    autonomy_mode_name(&config.mode).to_string(),
+                    config.skills.registries.clone(),
+                    events,
+                    trace_view,
+                    config.approvals.link_secret(),
+                    config.approvals.instance_name.clone(),
+                    config.approvals.peers.clone(),
+                    approval_notice_targets(&config.approvals, None),
+                )
+                .await
+            })?;
         }
     }
     Ok(())
@@ -2105,11 +5056,14 @@ fn run_services(bind: String, poll_interval_ms: u64) -> Result<()> {
     let config = load_initialized_config()?;
     let db_path = config.workspace_dir.join("titan.db");
     let _store = MemoryStore::open(&db_path)?;
-    let runtime = TitanGatewayRuntime::new(
+    let mut runtime = TitanGatewayRuntime::new(
         config.mode.clone(),
         config.workspace_dir.clone(),
         db_path.clone(),
     );
+    if let Some(generator) = reflection_generator(&config) {
+        runtime = runtime.with_reflection_generator(generator);
+    }
 
     let rt = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
@@ -2126,6 +5080,7 @@ fn run_services(bind: String, poll_interval_ms: u64) -> Result<()> {
 
 struct DiscordHandler {
     runtime: Arc<Mutex<TitanGatewayRuntime>>,
+    db_path: PathBuf,
     default_channel_id: Option<u64>,
 }
 
@@ -2178,31 +5133,143 @@ impl EventHandler for DiscordHandler {
             || normalized.contains("write ")
             || normalized.contains("read "))
         {
-            return;
+            let runtime = Arc::clone(&self.runtime);
+            let actor_id = msg.author.id.to_string();
+            let awaiting = tokio::task::spawn_blocking(move || {
+                let lock = runtime
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("runtime lock poisoned"))?;
+                lock.awaiting_reply(GatewayChannel::Discord, &actor_id)
+            })
+            .await;
+            if !matches!(awaiting, Ok(Ok(true))) {
+                return;
+            }
         }
 
         let runtime = Arc::clone(&self.runtime);
         let actor_id = msg.author.id.to_string();
         let content_copy = content.clone();
+        let reply_target = msg.channel_id.get().to_string();
         let run_result = tokio::task::spawn_blocking(move || {
             let lock = runtime
                 .lock()
                 .map_err(|_| anyhow::anyhow!("runtime lock poisoned"))?;
-            lock.process_chat_input(InboundEvent::new(
-                GatewayChannel::Discord,
-                actor_id,
-                content_copy,
-            ))
+            lock.process_chat_input(
+                InboundEvent::new(GatewayChannel::Discord, actor_id, content_copy)
+                    .with_reply_target(reply_target),
+            )
         })
         .await;
 
-        let response = match run_result {
-            Ok(Ok(outcome)) => outcome.response,
-            Ok(Err(err)) => format!("run_error: {err}"),
-            Err(err) => format!("runtime_join_error: {err}"),
+        let (response, outbox_id) = match run_result {
+            Ok(Ok(outcome)) => (outcome.response, outcome.reply_outbox_id),
+            Ok(Err(err)) => (format!("run_error: {err}"), None),
+            Err(err) => (format!("runtime_join_error: {err}"), None),
         };
-        let _ = msg.channel_id.say(&ctx.http, response).await;
+        // The reply is already in the outbox; sending it here is the fast path, and the
+        // outbox worker resends it if this process dies before marking it delivered.
+        if msg.channel_id.say(&ctx.http, response).await.is_ok()
+            && let Some(outbox_id) = outbox_id
+        {
+            let db_path = self.db_path.clone();
+            let _ = tokio::task::spawn_blocking(move || {
+                MemoryStore::open(&db_path)?.mark_outbox_delivered(outbox_id)
+            })
+            .await;
+        }
+    }
+}
+
+/// Long-polls the Telegram bot while this process holds the writer lease, so a standby never
+/// consumes updates meant for the writer.
+fn spawn_telegram_bot(
+    config: &TitanConfig,
+    runtime: Arc<Mutex<TitanGatewayRuntime>>,
+    db_path: PathBuf,
+    lease: WriterLease,
+) -> Result<()> {
+    let token = std::env::var("TELEGRAM_BOT_TOKEN")
+        .with_context(|| "telegram.enabled is set but TELEGRAM_BOT_TOKEN is missing")?;
+    let poll_timeout_secs = config.telegram.poll_timeout_secs;
+    let bot = TelegramBot::new(&token, poll_timeout_secs)?;
+    let allowed_user_ids = config.telegram.allowed_user_ids.clone();
+    println!("telegram_enabled: true");
+    if allowed_user_ids.is_empty() {
+        println!(
+            "telegram_allowed_user_ids: none (set telegram.allowed_user_ids to answer anyone)"
+        );
     }
+    tokio::spawn(async move {
+        let mut offset: Option<i64> = None;
+        loop {
+            if !lease.is_leader() {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+            let bot = bot.clone();
+            let runtime = Arc::clone(&runtime);
+            let allowed_user_ids = allowed_user_ids.clone();
+            let db_path = db_path.clone();
+            let result = tokio::task::spawn_blocking(move || -> Result<Option<i64>> {
+                let mut next = offset;
+                for update in bot.get_updates(offset, poll_timeout_secs)? {
+                    next = Some(update.update_id + 1);
+                    let reply = {
+                        let runtime = runtime
+                            .lock()
+                            .map_err(|_| anyhow::anyhow!("runtime lock poisoned"))?;
+                        route_telegram_update(&runtime, &allowed_user_ids, &update)
+                    };
+                    let reply = match reply {
+                        Ok(Some(reply)) => reply,
+                        Ok(None) => continue,
+                        Err(err) => {
+                            eprintln!("telegram_route_error: {err}");
+                            continue;
+                        }
+                    };
+                    if let Some(callback_query_id) = &reply.callback_query_id {
+                        let _ = bot.answer_callback_query(callback_query_id, &reply.text);
+                    }
+                    let keyboard = reply.approval_id.as_deref().map(approval_keyboard);
+                    // As with Discord, the outbox resends the reply if this send does not land.
+                    if bot
+                        .send_message(&reply.chat_id, &reply.text, keyboard)
+                        .is_ok()
+                        && let Some(outbox_id) = reply.reply_outbox_id
+                    {
+                        MemoryStore::open(&db_path)?.mark_outbox_delivered(outbox_id)?;
+                    }
+                }
+                Ok(next)
+            })
+            .await;
+            match result {
+                Ok(Ok(next)) => offset = next,
+                Ok(Err(err)) => {
+                    eprintln!("telegram_poll_error: {err}");
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+                Err(err) => {
+                    eprintln!("telegram_poll_join_error: {err}");
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Records panics so the next start comes up in safe mode.
+fn install_safe_mode_panic_hook(db_path: PathBuf) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Ok(store) = MemoryStore::open(&db_path) {
+            let _ = store.record_runtime_panic(&scrub_diagnostic(&info.to_string()));
+        }
+        previous(info);
+    }));
 }
 
 async fn run_services_async(
@@ -2212,48 +5279,343 @@ async fn run_services_async(
     runtime: TitanGatewayRuntime,
     poll_interval_ms: u64,
 ) -> Result<()> {
+    install_safe_mode_panic_hook(db_path.clone());
+    if let Some(reason) = MemoryStore::open(&db_path)?
+        .evaluate_safe_mode(config.safe_mode.failure_threshold, "startup")?
+    {
+        println!("safe_mode: active reason={reason}");
+        println!("resume with `titan safe-mode resume` once the cause is understood");
+    }
     let web_bind = bind.clone();
     let web_db = db_path.clone();
     let web_workspace = config.workspace_dir.clone();
     let web_mode = autonomy_mode_name(&config.mode).to_string();
-    let web_yolo_bypass = config.security.yolo_bypass_path_guard;
+    let web_registries = config.skills.registries.clone();
+    let web_link_secret = config.approvals.link_secret();
+    let web_instance_name = config.approvals.instance_name.clone();
+    let web_approval_peers = config.approvals.peers.clone();
+    let web_approval_notices = approval_notice_targets(&config.approvals, None);
+    let events = EventBus::default();
+    events.spawn_pump(db_path.clone(), Duration::from_millis(500));
+    let web_events = events.clone();
     tokio::spawn(async move {
-        if let Err(err) =
-            web_runtime::serve(&web_bind, web_db, web_workspace, web_mode, web_yolo_bypass).await
+        if let Err(err) = web_runtime::serve(
+            &web_bind,
+            web_db,
+            web_workspace,
+            web_mode,
+            web_registries,
+            web_events,
+            TraceView::Full,
+            web_link_secret,
+            web_instance_name,
+            web_approval_peers,
+            web_approval_notices,
+        )
+        .await
         {
             eprintln!("web runtime stopped: {err}");
         }
     });
 
+    let lease = WriterLease::new(db_path.clone(), Some(format!("http://{bind}")));
+    if !lease.try_acquire()? {
+        let holder = MemoryStore::open(&db_path)?
+            .live_writer_lease(GATEWAY_WRITER_LEASE)?
+            .map(|lease| lease.holder)
+            .unwrap_or_default();
+        println!("writer_role: standby (held by {holder})");
+    }
+    lease.spawn_heartbeat();
+
     println!("run_status: starting");
     println!("workspace: {}", config.workspace_dir.display());
     println!("db: {}", db_path.display());
     println!("web_bind: {}", bind);
     println!("mode: {}", autonomy_mode_name(&config.mode));
     let expiry_db = db_path.clone();
+    let expiry_lease = lease.clone();
     tokio::spawn(async move {
         loop {
-            if let Ok(store) = MemoryStore::open(&expiry_db) {
+            if expiry_lease.is_leader()
+                && let Ok(store) = MemoryStore::open(&expiry_db)
+            {
                 let _ = store.apply_yolo_expiry("run_loop");
             }
             tokio::time::sleep(Duration::from_secs(5)).await;
         }
     });
 
+    if !config.webhooks.is_empty() {
+        let webhook_db = db_path.clone();
+        let webhooks = config.webhooks.clone();
+        let mut wake = events.subscribe();
+        let webhook_lease = lease.clone();
+        tokio::spawn(async move {
+            loop {
+                // New events wake the worker at once; the timer drives backoff retries.
+                tokio::select! {
+                    _ = wake.recv() => {}
+                    _ = tokio::time::sleep(Duration::from_secs(5)) => {}
+                }
+                if !webhook_lease.is_leader() {
+                    continue;
+                }
+                let db = webhook_db.clone();
+                let hooks = webhooks.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    let store = MemoryStore::open(&db)?;
+                    store.record_worker_heartbeat("webhooks", process_writer_id(), 5_000)?;
+                    dispatch_webhooks(&store, &hooks, current_epoch_ms())
+                })
+                .await;
+                match result {
+                    Ok(Ok(summary)) if summary.delivered + summary.failed > 0 => println!(
+                        "webhooks_delivered: {} failed: {}",
+                        summary.delivered, summary.failed
+                    ),
+                    Ok(Ok(_)) => {}
+                    Ok(Err(err)) => eprintln!("webhook_dispatch_error: {err}"),
+                    Err(err) => eprintln!("webhook_dispatch_join_error: {err}"),
+                }
+            }
+        });
+    }
+
+    if !config.federation.is_empty() {
+        let federation_db = db_path.clone();
+        let peers = config.federation.clone();
+        let federation_lease = lease.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                if !federation_lease.is_leader() {
+                    continue;
+                }
+                let db = federation_db.clone();
+                let peers = peers.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    let store = MemoryStore::open(&db)?;
+                    store.record_worker_heartbeat("federation", process_writer_id(), 10_000)?;
+                    sync_remote_goals(&store, &peers)
+                })
+                .await;
+                match result {
+                    Ok(Ok(summary)) => {
+                        if summary.traces_merged + summary.finished > 0 {
+                            println!(
+                                "federation_synced: traces={} finished={}",
+                                summary.traces_merged, summary.finished
+                            );
+                        }
+                        for error in summary.errors {
+                            eprintln!("federation_sync_error: {error}");
+                        }
+                    }
+                    Ok(Err(err)) => eprintln!("federation_sync_error: {err}"),
+                    Err(err) => eprintln!("federation_sync_join_error: {err}"),
+                }
+            }
+        });
+    }
+
+    let runtime = Arc::new(Mutex::new(runtime));
+    let resume_runtime = Arc::clone(&runtime);
+    let resume_lease = lease.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            if !resume_lease.is_leader() {
+                continue;
+            }
+            let runtime = Arc::clone(&resume_runtime);
+            let result = tokio::task::spawn_blocking(move || {
+                let runtime = runtime
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("runtime lock poisoned"))?;
+                let resumed = runtime.resume_paused_goals()?;
+                let started = runtime.start_queued_goals()?;
+                anyhow::Ok((resumed, started))
+            })
+            .await;
+            match result {
+                Ok(Ok((resumed, started))) => {
+                    for (label, processed) in resumed
+                        .iter()
+                        .map(|processed| ("goal_resumed", processed))
+                        .chain(started.iter().map(|processed| ("goal_dequeued", processed)))
+                    {
+                        println!(
+                            "{label}: {} status={}",
+                            processed.goal_id,
+                            processed.goal_status.as_str()
+                        );
+                    }
+                }
+                Ok(Err(err)) => eprintln!("goal_resume_error: {err}"),
+                Err(err) => eprintln!("goal_resume_join_error: {err}"),
+            }
+        }
+    });
+
+    let outbox_db = db_path.clone();
+    let outbox_lease = lease.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            if !outbox_lease.is_leader() {
+                continue;
+            }
+            let db = outbox_db.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                let store = MemoryStore::open(&db)?;
+                store.record_worker_heartbeat("outbox", process_writer_id(), 5_000)?;
+                deliver_outbox(&store, current_epoch_ms())
+            })
+            .await;
+            match result {
+                Ok(Ok(summary)) if summary.delivered + summary.failed > 0 => println!(
+                    "outbox_delivered: {} failed: {}",
+                    summary.delivered, summary.failed
+                ),
+                Ok(Ok(_)) => {}
+                Ok(Err(err)) => eprintln!("outbox_delivery_error: {err}"),
+                Err(err) => eprintln!("outbox_delivery_join_error: {err}"),
+            }
+        }
+    });
+
+    if config.archive.enabled {
+        let archive_db = db_path.clone();
+        let archive = config.archive.clone();
+        let archive_lease = lease.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(3_600)).await;
+                if !archive_lease.is_leader() {
+                    continue;
+                }
+                let db = archive_db.clone();
+                let archive = archive.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    let cutoff_ms = current_epoch_ms() - archive.after_days as i64 * 86_400_000;
+                    MemoryStore::open(&db)?.archive_goals(cutoff_ms, &archive)
+                })
+                .await;
+                match result {
+                    Ok(Ok(report)) if report.goals > 0 => println!(
+                        "goals_archived: {} rows={} archive={}",
+                        report.goals,
+                        report.rows,
+                        report.archive_ref.unwrap_or_default()
+                    ),
+                    Ok(Ok(_)) => {}
+                    Ok(Err(err)) => eprintln!("goal_archive_error: {err}"),
+                    Err(err) => eprintln!("goal_archive_join_error: {err}"),
+                }
+            }
+        });
+    }
+
+    if let Some(idle_days) = config.chat.session_idle_close_days {
+        let sessions_db = db_path.clone();
+        let sessions_lease = lease.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(3_600)).await;
+                if !sessions_lease.is_leader() {
+                    continue;
+                }
+                let db = sessions_db.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    let cutoff_ms = current_epoch_ms() - idle_days as i64 * 86_400_000;
+                    MemoryStore::open(&db)?.close_idle_sessions(cutoff_ms)
+                })
+                .await;
+                match result {
+                    Ok(Ok(report)) if !report.closed.is_empty() => println!(
+                        "sessions_closed: {} summarized={}",
+                        report.closed.len(),
+                        report.summarized
+                    ),
+                    Ok(Ok(_)) => {}
+                    Ok(Err(err)) => eprintln!("session_close_error: {err}"),
+                    Err(err) => eprintln!("session_close_join_error: {err}"),
+                }
+            }
+        });
+    }
+
+    if config.maintenance.enabled {
+        let maintenance_db = db_path.clone();
+        let interval_ms = config.maintenance.interval_hours as i64 * 3_600_000;
+        let free_ratio = config.maintenance.vacuum_free_ratio;
+        let maintenance_lease = lease.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(600)).await;
+                if !maintenance_lease.is_leader() {
+                    continue;
+                }
+                let db = maintenance_db.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    let store = MemoryStore::open(&db)?;
+                    if !store.maintenance_due(current_epoch_ms(), interval_ms, free_ratio)? {
+                        return Ok(None);
+                    }
+                    store
+                        .run_maintenance("schedule", false, free_ratio)
+                        .map(Some)
+                })
+                .await;
+                match result {
+                    Ok(Ok(Some(report))) => println!(
+                        "db_maintenance: reclaimed_bytes={} vacuumed={} integrity={}",
+                        report.reclaimed_bytes(),
+                        report.vacuumed,
+                        report.integrity.join("; ")
+                    ),
+                    Ok(Ok(None)) => {}
+                    Ok(Err(err)) => eprintln!("db_maintenance_error: {err}"),
+                    Err(err) => eprintln!("db_maintenance_join_error: {err}"),
+                }
+            }
+        });
+    }
+
     let scheduler_db = db_path.clone();
     let scheduler_workspace = config.workspace_dir.clone();
     let scheduler_tick_ms = poll_interval_ms.max(500);
+    let scheduler_lease = lease.clone();
     tokio::spawn(async move {
         loop {
-            match scheduler_tick_once(&scheduler_db, &scheduler_workspace).await {
-                Ok(count) if count > 0 => println!("scheduler_triggered_jobs: {count}"),
-                Ok(_) => {}
-                Err(err) => eprintln!("scheduler_tick_error: {err}"),
+            if scheduler_lease.is_leader() {
+                if let Ok(store) = MemoryStore::open(&scheduler_db) {
+                    let _ = store.record_worker_heartbeat(
+                        "scheduler",
+                        process_writer_id(),
+                        scheduler_tick_ms as i64,
+                    );
+                }
+                match scheduler_tick_once(&scheduler_db, &scheduler_workspace).await {
+                    Ok(count) if count > 0 => println!("scheduler_triggered_jobs: {count}"),
+                    Ok(_) => {}
+                    Err(err) => eprintln!("scheduler_tick_error: {err}"),
+                }
             }
             tokio::time::sleep(Duration::from_millis(scheduler_tick_ms)).await;
         }
     });
 
+    if config.telegram.enabled {
+        spawn_telegram_bot(
+            &config,
+            Arc::clone(&runtime),
+            db_path.clone(),
+            lease.clone(),
+        )?;
+    }
+
     if !config.discord.enabled {
         println!("discord_enabled: false");
         println!("runtime: web-only (set discord.enabled=true to enable Discord gateway)");
@@ -2268,6 +5630,10 @@ async fn run_services_async(
         )
     })?;
     let default_channel_id = resolve_discord_channel_id(&config);
+    // Only the writer answers Discord; a standby would otherwise reply to every message twice.
+    while !lease.is_leader() {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
     println!("discord_enabled: true");
     if let Some(channel_id) = default_channel_id {
         println!("discord_channel: {}", channel_id);
@@ -2277,7 +5643,8 @@ async fn run_services_async(
         | GatewayIntents::DIRECT_MESSAGES
         | GatewayIntents::MESSAGE_CONTENT;
     let handler = DiscordHandler {
-        runtime: Arc::new(Mutex::new(runtime)),
+        runtime,
+        db_path: db_path.clone(),
         default_channel_id,
     };
     let mut client = serenity::Client::builder(token, intents)
@@ -2292,6 +5659,9 @@ async fn run_services_async(
 
 async fn scheduler_tick_once(db_path: &Path, workspace_root: &Path) -> Result<usize> {
     let store = MemoryStore::open(db_path)?;
+    if store.safe_mode_state()?.active {
+        return Ok(0);
+    }
     let due_jobs = store.list_due_jobs(current_epoch_ms(), 64)?;
     if due_jobs.is_empty() {
         return Ok(0);
@@ -2562,6 +5932,127 @@ fn discover_ollama_models(endpoint: &str) -> Result<Vec<String>> {
     Ok(models.into_iter().collect())
 }
 
+/// Asks Ollama's `/api/generate` for a run's structured reflection. The blocking client runs
+/// on its own thread because the gateway calls this from inside the services' async runtime.
+struct OllamaReflectionGenerator {
+    endpoint: String,
+    model_id: String,
+    /// Database holding the model response cache, used when `model.cache_ttl_secs` is set.
+    db_path: PathBuf,
+    cache_ttl_secs: Option<u64>,
+}
+
+impl OllamaReflectionGenerator {
+    fn request(&self, prompt: &str) -> Result<String, String> {
+        let url = format!("{}/api/generate", self.endpoint.trim_end_matches('/'));
+        let body = serde_json::json!({
+            "model": self.model_id,
+            "prompt": prompt,
+            "format": "json",
+            "stream": false,
+        });
+        thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let response = Client::builder()
+                        .timeout(Duration::from_secs(30))
+                        .build()
+                        .map_err(|err| err.to_string())?
+                        .post(&url)
+                        .json(&body)
+                        .send()
+                        .map_err(|err| err.to_string())?;
+                    if !response.status().is_success() {
+                        return Err(format!("ollama returned {}", response.status()));
+                    }
+                    let value: Value = response.json().map_err(|err| err.to_string())?;
+                    value
+                        .get("response")
+                        .and_then(Value::as_str)
+                        .map(str::to_string)
+                        .ok_or_else(|| "ollama response missing text".to_string())
+                })
+                .join()
+                .map_err(|_| "reflection request panicked".to_string())?
+        })
+    }
+}
+
+impl ReflectionGenerator for OllamaReflectionGenerator {
+    fn generate(&self, run: &TaskRunResult) -> Result<StructuredReflection, String> {
+        let steps: Vec<String> = run
+            .step_results
+            .iter()
+            .map(|result| format!("{} {} {}", result.step_id, result.tool_name, result.status))
+            .collect();
+        let failures: Vec<&str> = run
+            .traces
+            .iter()
+            .filter(|trace| trace.event_type.ends_with("_failed"))
+            .map(|trace| trace.detail.as_str())
+            .collect();
+        let prompt = format!(
+            "Reflect on this agent run. Reply with JSON only, shaped as \
+             {{\"summary\":string,\"worked\":[string],\"failed\":[string],\
+             \"strategy_changes\":[string]}}.\n\
+             goal: {}\nstatus: {}\noutcome: {}\nsteps: {}\nfailures: {}",
+            run.goal.description,
+            run.goal.status.as_str(),
+            run.reflection,
+            steps.join("; "),
+            failures.join("; ")
+        );
+        let Some(ttl_secs) = self.cache_ttl_secs else {
+            let text = self.request(&prompt)?;
+            return serde_json::from_str(&text).map_err(|err| err.to_string());
+        };
+        // Cache trouble falls back to asking the model.
+        let store = MemoryStore::open(&self.db_path).ok();
+        let identity = format!(
+            "ollama|{}|{}|format=json|reflection",
+            self.model_id, self.endpoint
+        );
+        let key = titan_memory::model_cache_key(&identity, &prompt);
+        let now_ms = current_epoch_ms();
+        let cached = store
+            .as_ref()
+            .and_then(|store| store.cached_model_response(&key, now_ms).ok().flatten());
+        let text = match cached {
+            Some(text) => text,
+            None => {
+                let text = self.request(&prompt)?;
+                if let Some(store) = &store {
+                    let _ = store.cache_model_response(
+                        &key,
+                        &identity,
+                        &text,
+                        now_ms,
+                        now_ms + ttl_secs as i64 * 1000,
+                    );
+                }
+                text
+            }
+        };
+        serde_json::from_str(&text).map_err(|err| err.to_string())
+    }
+}
+
+fn reflection_generator(
+    config: &TitanConfig,
+) -> Option<Arc<dyn ReflectionGenerator + Send + Sync>> {
+    if !config.execution.model_reflections
+        || !matches!(config.model.provider, ModelProvider::Ollama)
+    {
+        return None;
+    }
+    Some(Arc::new(OllamaReflectionGenerator {
+        endpoint: config.model.endpoint.clone()?,
+        model_id: config.model.model_id.clone(),
+        db_path: config.workspace_dir.join("titan.db"),
+        cache_ttl_secs: config.model.cache_ttl_secs,
+    }))
+}
+
 fn collect_ollama_api_models(endpoint: &str, models: &mut BTreeSet<String>) -> Result<()> {
     let base = endpoint.trim_end_matches('/');
     if base.is_empty() {
@@ -2656,22 +6147,6 @@ fn current_epoch_ms() -> i64 {
     i64::try_from(millis).unwrap_or(i64::MAX)
 }
 
-fn default_connector_config(connector_type: ConnectorType) -> Result<Value> {
-    let value = match connector_type {
-        ConnectorType::Github => serde_json::json!({
-            "owner": "",
-            "repo": "",
-            "base_url": "https://api.github.com",
-        }),
-        ConnectorType::GoogleCalendar => serde_json::json!({
-            "calendar_id": "primary",
-            "base_url": "https://www.googleapis.com/calendar/v3",
-            "access_token_env": "GOOGLE_CALENDAR_TOKEN",
-        }),
-    };
-    Ok(value)
-}
-
 fn maybe_unlock_secrets_store_interactive() -> Result<Option<SecretsStore>> {
     let choice = prompt_yes_no("Unlock encrypted secrets store", false)?;
     if !choice {
@@ -2702,6 +6177,46 @@ fn prompt_with_default(label: &str, default: &str) -> Result<String> {
     }
 }
 
+// Asks for every scalar field of the type's schema; a blank answer keeps the default, and an
+// invalid one is reported and asked again.
+fn prompt_connector_config(connector_type: ConnectorType) -> Result<Value> {
+    let mut config = default_connector_config(connector_type);
+    let object = config
+        .as_object_mut()
+        .ok_or_else(|| anyhow::anyhow!("default connector config is not an object"))?;
+    for field in connector_config_schema(connector_type)
+        .iter()
+        .filter(|field| field.kind.is_scalar())
+    {
+        let default = match object.get(field.name) {
+            Some(Value::String(text)) => text.clone(),
+            Some(Value::Array(items)) => items
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join(","),
+            Some(Value::Null) | None => String::new(),
+            Some(other) => other.to_string(),
+        };
+        loop {
+            let answer = prompt_with_default(field.prompt, &default)?;
+            if answer.trim().is_empty() && !field.required {
+                object.remove(field.name);
+                break;
+            }
+            match parse_config_field(field, &answer) {
+                Ok(value) => {
+                    object.insert(field.name.to_string(), value);
+                    break;
+                }
+                Err(message) => println!("  {}: {message}", field.name),
+            }
+        }
+    }
+    check_connector_config(connector_type, &config)?;
+    Ok(config)
+}
+
 fn prompt_yes_no(label: &str, default: bool) -> Result<bool> {
     let prompt = if default { "Y/n" } else { "y/N" };
     print!("{label} [{prompt}]: ");
@@ -2774,15 +6289,6 @@ fn default_api_key_env(provider: &ModelProvider) -> Option<String> {
     }
 }
 
-fn model_provider_name(provider: &ModelProvider) -> &'static str {
-    match provider {
-        ModelProvider::OpenAi => "openai",
-        ModelProvider::Anthropic => "anthropic",
-        ModelProvider::Ollama => "ollama",
-        ModelProvider::Custom => "custom",
-    }
-}
-
 fn autonomy_mode_name(mode: &AutonomyMode) -> &'static str {
     match mode {
         AutonomyMode::Supervised => "supervised",
@@ -2863,6 +6369,47 @@ mod tests {
         (tmp, workspace, db_path, store)
     }
 
+    #[test]
+    fn crash_report_labels_never_include_arguments() {
+        let command = Command::Goal {
+            command: GoalCommand::Submit {
+                description: "deploy with token=abc".to_string(),
+                dedupe_key: None,
+                simulate: "success".to_string(),
+                max_retries: 1,
+                timeout_ms: 10_000,
+            },
+        };
+        assert_eq!(command_label(&command), "goal");
+    }
+
+    #[test]
+    fn output_renders_the_same_keys_as_text_or_json() {
+        let build = |json| {
+            let mut out = Output::new(json);
+            out.field("goal_id", "g1")
+                .field("dedupe_hit", false)
+                .field("approval_id", serde_json::Value::Null)
+                .text("- row one")
+                .json_field("rows", vec!["row one"]);
+            out.render().expect("render")
+        };
+        assert_eq!(
+            build(false),
+            "goal_id: g1\ndedupe_hit: false\napproval_id: <none>\n- row one"
+        );
+        let parsed: serde_json::Value = serde_json::from_str(&build(true)).expect("json");
+        assert_eq!(
+            parsed,
+            serde_json::json!({
+                "goal_id": "g1",
+                "dedupe_hit": false,
+                "approval_id": null,
+                "rows": ["row one"],
+            })
+        );
+    }
+
     #[tokio::test]
     async fn job_scheduler_triggers_goal_and_persists_trace() {
         let (_tmp, workspace, db_path, store) = seed_workspace();
@@ -2922,8 +6469,8 @@ mod tests {
     #[tokio::test]
     async fn yolo_job_write_executes_without_approval() {
         let (_tmp, workspace, db_path, store) = seed_workspace();
-        store.arm_yolo("test").expect("arm yolo");
-        store.enable_yolo("test", 5).expect("enable yolo");
+        store.arm_yolo("test", None).expect("arm yolo");
+        store.enable_yolo("test", 5, None).expect("enable yolo");
         store
             .add_job(NewJobRecord {
                 job_id: "job-write-yolo",
@@ -2952,4 +6499,322 @@ mod tests {
         let traces = store.get_traces(&goal_id).expect("traces");
         assert!(traces.iter().any(|trace| trace.risk_mode == "yolo"));
     }
+
+    fn state_paths(root: &Path) -> StatePaths {
+        StatePaths {
+            config_path: root.join("home/.titan/config.toml"),
+            workspace_dir: root.join("workspace"),
+            trust_root: root.join("home/.titan/trust/keys"),
+            secrets_path: root.join("home/.titan/secrets.enc"),
+        }
+    }
+
+    #[test]
+    fn export_import_roundtrip_excludes_secrets_by_default() {
+        let tmp = tempdir().expect("tempdir");
+        let source = state_paths(&tmp.path().join("old-host"));
+        fs::create_dir_all(source.workspace_dir.join("skills/list-docs/1.0.0")).expect("skills");
+        fs::write(
+            source
+                .workspace_dir
+                .join("skills/list-docs/1.0.0/skill.toml"),
+            "slug = \"list-docs\"",
+        )
+        .expect("skill");
+        fs::write(source.workspace_dir.join("skills.lock"), "{}").expect("lock");
+        fs::create_dir_all(&source.trust_root).expect("trust");
+        fs::write(source.trust_root.join("team.pub"), "key").expect("key");
+        fs::write(&source.secrets_path, "encrypted").expect("secrets");
+        let mut config = TitanConfig {
+            workspace_dir: source.workspace_dir.clone(),
+            ..TitanConfig::default()
+        };
+        config.discord.token = Some("discord-secret".to_string());
+        config.save(&source.config_path).expect("config");
+        let store = MemoryStore::open(&source.workspace_dir.join("titan.db")).expect("store");
+        store
+            .create_approval_request("write_file", "write", "a.txt::x", Some("u1"), 300_000)
+            .expect("approval");
+        store
+            .set_risk_mode_secure("cli", Some("export test"))
+            .expect("risk history");
+
+        let bundle = tmp.path().join("bundle.tar.zst");
+        let manifest = export_state(&source, &bundle, false).expect("export");
+        assert!(!manifest.secrets_included);
+        assert!(!manifest.entries.contains(&"secrets.enc".to_string()));
+        assert!(
+            manifest
+                .entries
+                .contains(&"audit/risk_state_history.jsonl".to_string())
+        );
+
+        let target = state_paths(&tmp.path().join("new-host"));
+        let imported = import_state(&target, &bundle, false, false).expect("import");
+        assert_eq!(imported.schema_version, manifest.schema_version);
+        let store = MemoryStore::open(&target.workspace_dir.join("titan.db")).expect("store");
+        assert_eq!(store.list_pending_approvals().expect("pending").len(), 1);
+        assert!(
+            target
+                .workspace_dir
+                .join("skills/list-docs/1.0.0/skill.toml")
+                .exists()
+        );
+        assert!(target.trust_root.join("team.pub").exists());
+        assert!(!target.secrets_path.exists());
+        let restored = TitanConfig::load(&target.config_path).expect("config");
+        assert_eq!(restored.workspace_dir, target.workspace_dir);
+        assert!(restored.discord.token.is_none());
+
+        let err = import_state(&target, &bundle, false, false).expect_err("existing state");
+        assert!(err.to_string().contains("--force"));
+    }
+
+    // Path-style S3 stand-in: PUT, GET, DELETE, and ListObjectsV2 over an in-memory map.
+    fn spawn_fake_s3() -> String {
+        use std::collections::BTreeMap;
+        use std::io::Read;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        std::thread::spawn(move || {
+            let mut objects: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+            for stream in listener.incoming() {
+                let mut stream = stream.expect("accept");
+                let mut raw = Vec::new();
+                let mut buf = [0_u8; 8192];
+                let (head, body) = loop {
+                    let n = stream.read(&mut buf).expect("read");
+                    raw.extend_from_slice(&buf[..n]);
+                    let Some(split) = raw.windows(4).position(|w| w == b"\r\n\r\n") else {
+                        continue;
+                    };
+                    let head = String::from_utf8_lossy(&raw[..split]).to_string();
+                    let length = head
+                        .lines()
+                        .find_map(|line| {
+                            line.to_ascii_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap_or(0))
+                        })
+                        .unwrap_or(0);
+                    if raw.len() >= split + 4 + length {
+                        break (head, raw[split + 4..split + 4 + length].to_vec());
+                    }
+                };
+                assert!(
+                    head.to_ascii_lowercase()
+                        .contains("authorization: aws4-hmac-sha256")
+                );
+                let mut request = head.lines().next().unwrap_or_default().split(' ');
+                let method = request.next().unwrap_or_default().to_string();
+                let target = request.next().unwrap_or_default().to_string();
+                let (path, query) = target.split_once('?').unwrap_or((target.as_str(), ""));
+                let (status, payload) = match method.as_str() {
+                    "PUT" => {
+                        objects.insert(path.to_string(), body);
+                        ("200 OK", Vec::new())
+                    }
+                    "DELETE" => {
+                        objects.remove(path);
+                        ("204 No Content", Vec::new())
+                    }
+                    "GET" if query.contains("list-type=2") => {
+                        let prefix = query
+                            .split('&')
+                            .find_map(|pair| pair.strip_prefix("prefix="))
+                            .unwrap_or_default()
+                            .replace("%2F", "/");
+                        let mut xml = String::from("<ListBucketResult>");
+                        for (key, bytes) in &objects {
+                            let key = key.splitn(3, '/').nth(2).unwrap_or_default();
+                            if key.starts_with(&prefix) {
+                                xml.push_str(&format!(
+                                    "<Contents><Key>{key}</Key><Size>{}</Size></Contents>",
+                                    bytes.len()
+                                ));
+                            }
+                        }
+                        xml.push_str("<IsTruncated>false</IsTruncated></ListBucketResult>");
+                        ("200 OK", xml.into_bytes())
+                    }
+                    _ => match objects.get(path) {
+                        Some(bytes) => ("200 OK", bytes.clone()),
+                        None => ("404 Not Found", Vec::new()),
+                    },
+                };
+                let mut response = format!(
+                    "HTTP/1.1 {status}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    payload.len()
+                )
+                .into_bytes();
+                response.extend_from_slice(&payload);
+                stream.write_all(&response).expect("respond");
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    #[test]
+    fn backups_push_encrypted_prune_to_retention_and_restore_from_s3() {
+        let tmp = tempdir().expect("tempdir");
+        let source = state_paths(&tmp.path().join("old-host"));
+        let store = MemoryStore::open(&source.workspace_dir.join("titan.db")).expect("store");
+        store
+            .create_approval_request("write_file", "write", "a.txt::x", Some("u1"), 300_000)
+            .expect("approval");
+        let config = BackupConfig {
+            keep_last: 2,
+            s3: Some(titan_common::S3BackupConfig {
+                endpoint: spawn_fake_s3(),
+                bucket: "ops".to_string(),
+                region: "us-east-1".to_string(),
+                prefix: "titan-backups/".to_string(),
+                access_key_secret: "backup:s3:access_key".to_string(),
+                secret_key_secret: "backup:s3:secret_key".to_string(),
+            }),
+            ..BackupConfig::default()
+        };
+        let secrets = titan_connectors::InMemorySecretResolver::new(
+            [
+                ("backup:passphrase", "bundle-pass"),
+                ("backup:s3:access_key", "AKIDEXAMPLE"),
+                ("backup:s3:secret_key", "s3-secret"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        );
+
+        let mut urls = Vec::new();
+        for _ in 0..3 {
+            let report = push_backup(&source, &config, &secrets).expect("push");
+            urls.push(report.url);
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        assert!(urls[0].starts_with("s3://ops/titan-backups/titan-"));
+        let target = BackupTarget::open(&config, &secrets).expect("target");
+        let kept: Vec<String> = target
+            .backups()
+            .expect("list")
+            .into_iter()
+            .map(|object| target.url(&object.key))
+            .collect();
+        assert_eq!(kept, urls[1..].to_vec());
+        let sealed = target
+            .client
+            .get(&target.credentials, urls[2].trim_start_matches("s3://ops/"))
+            .expect("get");
+        assert!(titan_secrets::open_with_passphrase("wrong-pass", &sealed).is_err());
+
+        let restored_host = state_paths(&tmp.path().join("new-host"));
+        let (url, manifest) =
+            restore_backup(&restored_host, &config, &secrets, "latest", false, false)
+                .expect("restore latest");
+        assert_eq!(url, urls[2]);
+        assert!(!manifest.secrets_included);
+        let store =
+            MemoryStore::open(&restored_host.workspace_dir.join("titan.db")).expect("store");
+        assert_eq!(store.list_pending_approvals().expect("pending").len(), 1);
+
+        let (url, _) = restore_backup(&restored_host, &config, &secrets, &urls[1], true, false)
+            .expect("restore by url");
+        assert_eq!(url, urls[1]);
+        let err = restore_backup(&restored_host, &config, &secrets, &urls[0], true, false)
+            .expect_err("pruned backup");
+        assert!(err.to_string().contains("404"));
+    }
+
+    #[test]
+    fn bootstrap_apply_reconciles_and_is_idempotent() {
+        let (tmp, workspace, _db_path, store) = seed_workspace();
+        let config_path = tmp.path().join("config.toml");
+        let config = TitanConfig {
+            workspace_dir: workspace.clone(),
+            ..TitanConfig::default()
+        };
+        config.save(&config_path).expect("config");
+        let spec: BootstrapSpec = toml::from_str(
+            r#"
+            allowlist = ["ops-lead"]
+
+            [[connectors]]
+            name = "team-github"
+            type = "github"
+            config = { owner = "acme", repo = "titan", base_url = "https://api.github.com" }
+
+            [[personas]]
+            name = "reviewer"
+            description = "Terse code reviewer"
+
+            [[goals]]
+            name = "nightly-scan"
+            interval = "24h"
+            template = "scan workspace"
+            "#,
+        )
+        .expect("spec");
+
+        let first = apply_bootstrap(&config, &config_path, &store, &spec).expect("apply");
+        let outcomes: Vec<(&str, &str)> = first
+            .iter()
+            .map(|a| (a.kind, a.outcome.split(' ').next().unwrap_or_default()))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("allowlist", "approval_required"),
+                ("connector", "created"),
+                ("persona", "created"),
+                ("goal", "created"),
+            ]
+        );
+        assert_eq!(store.list_pending_approvals().expect("pending").len(), 1);
+
+        let second = apply_bootstrap(&config, &config_path, &store, &spec).expect("reapply");
+        assert_eq!(second[0].outcome, first[0].outcome);
+        assert!(second[1..].iter().all(|a| a.outcome == "unchanged"));
+        assert_eq!(store.list_pending_approvals().expect("pending").len(), 1);
+        assert_eq!(store.list_connectors().expect("connectors").len(), 1);
+        assert_eq!(store.list_jobs().expect("jobs").len(), 1);
+
+        let autonomous = TitanConfig {
+            mode: AutonomyMode::Autonomous,
+            ..config
+        };
+        let third = apply_bootstrap(&autonomous, &config_path, &store, &spec).expect("apply");
+        assert_eq!(third[0].outcome, "added");
+        let saved = TitanConfig::load(&config_path).expect("config");
+        assert_eq!(saved.chat.allowlist, vec!["ops-lead".to_string()]);
+    }
+
+    #[test]
+    fn bootstrap_apply_rejects_invalid_connector_config_before_changes() {
+        let (tmp, workspace, _db_path, store) = seed_workspace();
+        let config_path = tmp.path().join("config.toml");
+        let config = TitanConfig {
+            workspace_dir: workspace,
+            ..TitanConfig::default()
+        };
+        config.save(&config_path).expect("config");
+        let spec: BootstrapSpec = toml::from_str(
+            r#"
+            allowlist = ["ops-lead"]
+
+            [[connectors]]
+            name = "team-github"
+            type = "github"
+            config = { owner = "acme", repository = "titan" }
+            "#,
+        )
+        .expect("spec");
+
+        let err = apply_bootstrap(&config, &config_path, &store, &spec).expect_err("invalid");
+        let message = format!("{err:#}");
+        assert!(message.starts_with("connector 'team-github': invalid github connector config"));
+        assert!(message.contains("repository: unknown github config field"));
+        assert!(message.contains("repo: is required"));
+        assert!(store.list_pending_approvals().expect("pending").is_empty());
+        assert!(store.list_connectors().expect("connectors").is_empty());
+    }
 }
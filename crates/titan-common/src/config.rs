@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -7,7 +8,7 @@ use thiserror::Error;
 
 const DEFAULT_CONFIG_FILE: &str = ".titan/config.toml";
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum AutonomyMode {
     Supervised,
@@ -26,9 +27,47 @@ pub struct TitanConfig {
     #[serde(default)]
     pub discord: DiscordConfig,
     #[serde(default)]
+    pub telegram: TelegramConfig,
+    #[serde(default)]
     pub chat: ChatConfig,
     #[serde(default)]
     pub security: SecurityConfig,
+    #[serde(default)]
+    pub execution: ExecutionConfig,
+    #[serde(default)]
+    pub skills: SkillsConfig,
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+    #[serde(default)]
+    pub archive: ArchiveConfig,
+    #[serde(default)]
+    pub approvals: ApprovalsConfig,
+    #[serde(default)]
+    pub safe_mode: SafeModeConfig,
+    #[serde(default)]
+    pub crash_reports: CrashReportsConfig,
+    #[serde(default)]
+    pub goal_templates: Vec<GoalTemplateConfig>,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default)]
+    pub federation: Vec<FederationPeerConfig>,
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+    #[serde(default)]
+    pub egress: EgressConfig,
+    #[serde(default)]
+    pub embeddings: EmbeddingsConfig,
+    #[serde(default)]
+    pub usage: UsageConfig,
+    #[serde(default)]
+    pub experiment: ExperimentConfig,
+    #[serde(default)]
+    pub access: AccessConfig,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -41,6 +80,17 @@ pub enum ModelProvider {
     Custom,
 }
 
+impl ModelProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::OpenAi => "openai",
+            Self::Anthropic => "anthropic",
+            Self::Ollama => "ollama",
+            Self::Custom => "custom",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelConfig {
     #[serde(default)]
@@ -48,6 +98,26 @@ pub struct ModelConfig {
     pub model_id: String,
     pub endpoint: Option<String>,
     pub api_key_env: Option<String>,
+    /// Reuse the reply to an identical prompt for this long instead of asking the provider
+    /// again; unset disables the cache.
+    #[serde(default)]
+    pub cache_ttl_secs: Option<u64>,
+    /// Consecutive unreachable-provider errors before the gateway switches to degraded mode:
+    /// rule-based planning only, with model-dependent goals held as `waiting_for_model`.
+    #[serde(default = "default_model_outage_threshold")]
+    pub outage_threshold: u32,
+    /// While degraded, a request still tries the provider once this long has passed since
+    /// the last failure; a success ends degraded mode.
+    #[serde(default = "default_model_outage_probe_secs")]
+    pub outage_probe_secs: u64,
+}
+
+fn default_model_outage_threshold() -> u32 {
+    3
+}
+
+fn default_model_outage_probe_secs() -> u64 {
+    60
 }
 
 impl Default for ModelConfig {
@@ -57,6 +127,9 @@ impl Default for ModelConfig {
             model_id: "llama3.2:latest".to_string(),
             endpoint: Some("http://127.0.0.1:11434".to_string()),
             api_key_env: None,
+            cache_ttl_secs: None,
+            outage_threshold: default_model_outage_threshold(),
+            outage_probe_secs: default_model_outage_probe_secs(),
         }
     }
 }
@@ -69,6 +142,32 @@ pub struct DiscordConfig {
     pub default_channel_id: Option<String>,
 }
 
+/// Long-polling Telegram bot. The token comes from `TELEGRAM_BOT_TOKEN`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelegramConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Telegram user ids the bot answers; updates from anyone else are dropped unread.
+    #[serde(default)]
+    pub allowed_user_ids: Vec<String>,
+    #[serde(default = "default_telegram_poll_timeout_secs")]
+    pub poll_timeout_secs: u64,
+}
+
+fn default_telegram_poll_timeout_secs() -> u64 {
+    30
+}
+
+impl Default for TelegramConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_user_ids: Vec::new(),
+            poll_timeout_secs: default_telegram_poll_timeout_secs(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ActivationMode {
@@ -83,12 +182,730 @@ pub struct ChatConfig {
     pub activation_mode: ActivationMode,
     #[serde(default)]
     pub allowlist: Vec<String>,
+    /// Chat actors allowed to run operator slash commands; empty lets everyone.
+    #[serde(default)]
+    pub operators: Vec<String>,
+    /// Sessions idle this many days are compacted and closed by `titan run`, with their final
+    /// context kept in episodic memory. Unset keeps sessions open.
+    #[serde(default)]
+    pub session_idle_close_days: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SecurityConfig {
     #[serde(default = "default_true")]
     pub yolo_bypass_path_guard: bool,
+    /// Overrides the default `policy.toml` next to this config file.
+    #[serde(default)]
+    pub policy_file: Option<PathBuf>,
+    /// Hold `write_file` and `git commit` steps whose content looks like credentials until an
+    /// operator approves them.
+    #[serde(default = "default_true")]
+    pub scan_writes_for_secrets: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutionConfig {
+    #[serde(default)]
+    pub canary_writes: bool,
+    /// Default per-goal cap on a plan's estimated tokens; unset means no cap.
+    #[serde(default)]
+    pub token_budget: Option<u64>,
+    /// How long a session reuses read tool results across goals; 0 keeps reuse within a run.
+    #[serde(default)]
+    pub step_cache_ttl_ms: u64,
+    /// Ask the configured Ollama model for each run's structured reflection.
+    #[serde(default)]
+    pub model_reflections: bool,
+    /// Ask the configured model for plan candidates instead of the keyword planner. Runs
+    /// fall back to the keyword planner when the model fails or proposes unknown tools.
+    #[serde(default)]
+    pub model_planner: bool,
+    /// Output transform per tool name, e.g. `http_get = "json:.items[].name"`.
+    #[serde(default)]
+    pub output_transforms: BTreeMap<String, String>,
+    /// How long a goal waits for the answer to an `AskUser` step; unset means one hour.
+    #[serde(default)]
+    pub ask_user_ttl_ms: Option<u64>,
+    /// Most goals running at once across every channel; unset means no limit.
+    #[serde(default)]
+    pub max_concurrent_goals: Option<u32>,
+    /// Most goals running at once from one channel (`discord`, `webchat`, ...).
+    #[serde(default)]
+    pub max_concurrent_goals_per_channel: Option<u32>,
+    /// Most goals running at once from one session.
+    #[serde(default)]
+    pub max_concurrent_goals_per_session: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SkillsConfig {
+    #[serde(default)]
+    pub registries: Vec<SkillRegistryConfig>,
+    /// Fuel for each wasm skill run, CLI or in-process. A manifest `[limits]` can only lower it.
+    #[serde(default)]
+    pub wasm_fuel: Option<u64>,
+    /// Memory cap for each wasm skill run, in bytes. A manifest `[limits]` can only lower it.
+    #[serde(default)]
+    pub wasm_max_memory_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillRegistryConfig {
+    pub name: String,
+    pub source: String,
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// Outbound webhook fired on lifecycle events.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub name: String,
+    pub url: String,
+    /// Event kinds to deliver; empty means all.
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Environment variable holding the HMAC-SHA256 signing secret.
+    pub secret_env: Option<String>,
+    #[serde(default = "default_webhook_max_attempts")]
+    pub max_attempts: u32,
+}
+
+fn default_webhook_max_attempts() -> u32 {
+    5
+}
+
+/// Scheduled database upkeep run by the service loop.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_maintenance_interval_hours")]
+    pub interval_hours: u64,
+    /// VACUUM once free pages reach this fraction of the database.
+    #[serde(default = "default_vacuum_free_ratio")]
+    pub vacuum_free_ratio: f64,
+}
+
+fn default_maintenance_interval_hours() -> u64 {
+    24
+}
+
+fn default_vacuum_free_ratio() -> f64 {
+    0.25
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_hours: default_maintenance_interval_hours(),
+            vacuum_free_ratio: default_vacuum_free_ratio(),
+        }
+    }
+}
+
+/// Moves finished goals' traces and steps out of SQLite into zstd-compressed JSONL files,
+/// leaving the goal row as a stub that points at its archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveConfig {
+    /// Archive on a schedule from `titan run`; `titan archive run` works either way.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Completed, failed, or cancelled goals untouched for this long are archived.
+    #[serde(default = "default_archive_after_days")]
+    pub after_days: u64,
+    #[serde(default)]
+    pub target: ArchiveTarget,
+    /// Directory for the `dir` target; defaults to `archive/` next to the database.
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+    /// Most goals written to one archive.
+    #[serde(default = "default_archive_batch_size")]
+    pub batch_size: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveTarget {
+    #[default]
+    Dir,
+    /// The `[storage]` blob backend, local or S3.
+    BlobStore,
+}
+
+fn default_archive_after_days() -> u64 {
+    90
+}
+
+fn default_archive_batch_size() -> usize {
+    500
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            after_days: default_archive_after_days(),
+            target: ArchiveTarget::default(),
+            dir: None,
+            batch_size: default_archive_batch_size(),
+        }
+    }
+}
+
+/// Anomaly rules over the gateway access log. Each hit raises an `access_anomaly` lifecycle
+/// event; with `lock_on_anomaly` it also puts the runtime in safe mode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessConfig {
+    /// Requests one actor may make within `burst_window_secs` before it counts as a burst.
+    #[serde(default = "default_access_burst_limit")]
+    pub burst_limit: u32,
+    #[serde(default = "default_access_burst_window_secs")]
+    pub burst_window_secs: u64,
+    /// Actors first seen this recently are new; their approvals raise an anomaly.
+    #[serde(default = "default_access_new_actor_secs")]
+    pub new_actor_secs: u64,
+    /// Enter safe mode, and so supervised mode, on the first anomaly.
+    #[serde(default)]
+    pub lock_on_anomaly: bool,
+}
+
+fn default_access_burst_limit() -> u32 {
+    30
+}
+
+fn default_access_burst_window_secs() -> u64 {
+    60
+}
+
+fn default_access_new_actor_secs() -> u64 {
+    24 * 60 * 60
+}
+
+impl Default for AccessConfig {
+    fn default() -> Self {
+        Self {
+            burst_limit: default_access_burst_limit(),
+            burst_window_secs: default_access_burst_window_secs(),
+            new_actor_secs: default_access_new_actor_secs(),
+            lock_on_anomaly: false,
+        }
+    }
+}
+
+/// Circuit breaker that boots the runtime into safe mode after repeated failures.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SafeModeConfig {
+    /// Consecutive failed goals that trip the breaker; 0 disables the failure check.
+    #[serde(default = "default_safe_mode_failure_threshold")]
+    pub failure_threshold: u32,
+}
+
+fn default_safe_mode_failure_threshold() -> u32 {
+    5
+}
+
+impl Default for SafeModeConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: default_safe_mode_failure_threshold(),
+        }
+    }
+}
+
+/// Estimated token spend per chat session. The gateway records each request and reply in
+/// `session_usage` and refuses new requests once a budget is used up.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageConfig {
+    /// Most estimated tokens one session may use; unset means no cap.
+    #[serde(default)]
+    pub session_token_budget: Option<u64>,
+    /// Most estimated tokens one channel peer may use per UTC day, across its sessions.
+    #[serde(default)]
+    pub daily_token_budget: Option<u64>,
+    /// Price used for the cost estimate stored with each message.
+    #[serde(default)]
+    pub usd_per_1k_tokens: f64,
+}
+
+/// A planner A/B experiment. `variant_percent` of new goals, picked by goal id, are planned
+/// with the variant's overrides; the rest are the control. Both arms are recorded on the
+/// goal's `run_plans` rows so outcomes and feedback can be compared per arm.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExperimentConfig {
+    /// Recorded with each plan; unset disables the experiment.
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub variant_percent: u8,
+    /// Planner the variant uses instead of `execution.model_planner`.
+    #[serde(default)]
+    pub planner: Option<ExperimentPlanner>,
+    /// Plan candidates the variant builds instead of the default 3 (2 to 5).
+    #[serde(default)]
+    pub candidate_count: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExperimentPlanner {
+    /// Keyword intents and built-in candidates only.
+    Rules,
+    /// The configured model provider.
+    Model,
+}
+
+/// Tool outputs larger than `blob_threshold_bytes` live in a blob store; SQLite keeps a
+/// `blob:sha256:<hex>` reference in their place.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageConfig {
+    #[serde(default = "default_blob_threshold_bytes")]
+    pub blob_threshold_bytes: u64,
+    /// Outputs longer than this are cut down to it, with a note of the original size,
+    /// before they are stored or offloaded. Unset keeps outputs whole.
+    #[serde(default)]
+    pub max_output_bytes: Option<u64>,
+    #[serde(default)]
+    pub blob_backend: BlobBackend,
+    /// Content-addressed directory for the local backend; defaults to `blobs/` next to the
+    /// database.
+    #[serde(default)]
+    pub blob_dir: Option<PathBuf>,
+    /// Required when `blob_backend = "s3"`.
+    #[serde(default)]
+    pub s3: Option<S3BlobConfig>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlobBackend {
+    #[default]
+    Local,
+    S3,
+}
+
+/// An S3-compatible bucket (AWS, MinIO, R2), addressed path-style.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct S3BlobConfig {
+    pub endpoint: String,
+    pub bucket: String,
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    /// Key prefix inside the bucket, e.g. `titan/`.
+    #[serde(default)]
+    pub prefix: String,
+    /// Environment variables holding the access key id and secret access key.
+    pub access_key_env: String,
+    pub secret_key_env: String,
+}
+
+fn default_blob_threshold_bytes() -> u64 {
+    64 * 1024
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            blob_threshold_bytes: default_blob_threshold_bytes(),
+            max_output_bytes: None,
+            blob_backend: BlobBackend::Local,
+            blob_dir: None,
+            s3: None,
+        }
+    }
+}
+
+/// Encrypted state bundles pushed by `titan backup push`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupConfig {
+    /// Newest backups kept in the target; older ones are deleted after each push.
+    #[serde(default = "default_backup_keep_last")]
+    pub keep_last: usize,
+    /// Secret-store key holding the passphrase that encrypts each bundle.
+    #[serde(default = "default_backup_passphrase_secret")]
+    pub passphrase_secret: String,
+    /// Bundle `secrets.enc` and the Discord token, as `titan export --include-secrets` does.
+    #[serde(default)]
+    pub include_secrets: bool,
+    #[serde(default)]
+    pub s3: Option<S3BackupConfig>,
+}
+
+/// An S3-compatible backup bucket. Credentials are secret-store keys, not values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct S3BackupConfig {
+    pub endpoint: String,
+    pub bucket: String,
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    #[serde(default = "default_backup_prefix")]
+    pub prefix: String,
+    #[serde(default = "default_backup_access_key_secret")]
+    pub access_key_secret: String,
+    #[serde(default = "default_backup_secret_key_secret")]
+    pub secret_key_secret: String,
+}
+
+fn default_backup_keep_last() -> usize {
+    7
+}
+
+fn default_backup_passphrase_secret() -> String {
+    "backup:passphrase".to_string()
+}
+
+fn default_backup_prefix() -> String {
+    "titan-backups/".to_string()
+}
+
+fn default_backup_access_key_secret() -> String {
+    "backup:s3:access_key".to_string()
+}
+
+fn default_backup_secret_key_secret() -> String {
+    "backup:s3:secret_key".to_string()
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            keep_last: default_backup_keep_last(),
+            passphrase_secret: default_backup_passphrase_secret(),
+            include_secrets: false,
+            s3: None,
+        }
+    }
+}
+
+/// Another TITAN instance that runs matching goals instead of this one. A goal is forwarded
+/// to the first peer whose `intents` or `paths` match it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederationPeerConfig {
+    pub name: String,
+    /// Base URL of the peer's web API, e.g. `http://homelab:3000`.
+    pub url: String,
+    /// Planner intents: `scan_workspace`, `update_readme`, `read_path`, `generic_recon`, or
+    /// `tool:<name>`.
+    #[serde(default)]
+    pub intents: Vec<String>,
+    /// Workspace-relative prefixes; goals that read or write a path under one are forwarded.
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+/// Tool limits chosen by autonomy mode and risk mode.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SandboxConfig {
+    #[serde(default)]
+    pub profiles: Vec<SandboxProfileConfig>,
+}
+
+impl SandboxConfig {
+    /// The first profile bound to `mode` and `risk_mode` (`secure` or `yolo`).
+    pub fn profile_for(
+        &self,
+        mode: &AutonomyMode,
+        risk_mode: &str,
+    ) -> Option<&SandboxProfileConfig> {
+        self.profiles.iter().find(|profile| {
+            (profile.modes.is_empty() || profile.modes.contains(mode))
+                && (profile.risk_modes.is_empty()
+                    || profile.risk_modes.iter().any(|risk| risk == risk_mode))
+        })
+    }
+}
+
+/// What tools may touch while a profile applies. Approving a step does not widen it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxProfileConfig {
+    pub name: String,
+    /// Autonomy modes the profile applies in; empty means all of them.
+    #[serde(default)]
+    pub modes: Vec<AutonomyMode>,
+    /// `secure` and/or `yolo`; empty means both.
+    #[serde(default)]
+    pub risk_modes: Vec<String>,
+    /// Workspace-relative prefixes file tools may use; empty means the whole workspace.
+    #[serde(default)]
+    pub paths: Vec<String>,
+    #[serde(default)]
+    pub network: NetworkPolicy,
+    /// Hosts `http_get` may reach with `network = "allowlist"`. Subdomains match too.
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+    /// Binaries `run_command` may start; unset keeps the built-in allowlist.
+    #[serde(default)]
+    pub commands: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkPolicy {
+    /// No `http_get` at all.
+    None,
+    /// Any public https host.
+    #[default]
+    Public,
+    /// Only `allowed_hosts`.
+    Allowlist,
+}
+
+/// How semantic and episodic memories are embedded for similarity recall.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingsConfig {
+    #[serde(default)]
+    pub provider: EmbeddingProvider,
+    /// Embedding model for `ollama` and `openai`, e.g. `nomic-embed-text`.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Base URL; defaults to `model.endpoint` for Ollama and `https://api.openai.com/v1`.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Environment variable holding the OpenAI API key.
+    #[serde(default = "default_embeddings_api_key_env")]
+    pub api_key_env: String,
+    /// Vector size of the `hashing` embedder.
+    #[serde(default = "default_embedding_dimensions")]
+    pub dimensions: usize,
+    /// Memories recalled for a goal before it is planned; 0 turns recall off.
+    #[serde(default = "default_recall_top_k")]
+    pub recall_top_k: usize,
+    /// Memories sent to the embedder per request when filling in missing vectors.
+    #[serde(default = "default_embedding_batch_size")]
+    pub batch_size: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingProvider {
+    /// Local feature hashing of words; needs no model.
+    #[default]
+    Hashing,
+    Ollama,
+    #[serde(rename = "openai")]
+    OpenAi,
+}
+
+fn default_embeddings_api_key_env() -> String {
+    "OPENAI_API_KEY".to_string()
+}
+
+fn default_embedding_dimensions() -> usize {
+    256
+}
+
+fn default_recall_top_k() -> usize {
+    3
+}
+
+fn default_embedding_batch_size() -> usize {
+    32
+}
+
+impl Default for EmbeddingsConfig {
+    fn default() -> Self {
+        Self {
+            provider: EmbeddingProvider::default(),
+            model: None,
+            endpoint: None,
+            api_key_env: default_embeddings_api_key_env(),
+            dimensions: default_embedding_dimensions(),
+            recall_top_k: default_recall_top_k(),
+            batch_size: default_embedding_batch_size(),
+        }
+    }
+}
+
+/// Hosts every network tool may reach, on top of sandbox profiles and skill `allowed_hosts`.
+/// Rules are a host (`api.github.com`), a wildcard domain (`*.example.com`, subdomains
+/// only), `*`, an IP address, or a CIDR range (`10.0.0.0/8`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EgressConfig {
+    /// When non-empty, targets matching none of these are blocked.
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Always blocked, even when an allow rule matches too.
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Private, loopback, or link-local addresses (IPs or CIDR ranges) that http tools and
+    /// connectors may still reach. Everything else private is refused after resolution.
+    #[serde(default)]
+    pub allow_private: Vec<String>,
+}
+
+/// Local crash reports written on panics and failed commands.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReportsConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Most recent trace events copied into each report, already redacted.
+    #[serde(default = "default_crash_report_trace_events")]
+    pub trace_events: usize,
+    /// Where `titan crash submit` posts reports. Nothing leaves the machine when unset.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+}
+
+fn default_crash_report_trace_events() -> usize {
+    20
+}
+
+impl Default for CrashReportsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            trace_events: default_crash_report_trace_events(),
+            endpoint: None,
+        }
+    }
+}
+
+/// Named goal run with `/run <name> <params...>`, e.g. `release-checklist 1.4.0`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalTemplateConfig {
+    pub name: String,
+    /// Positional parameter names, filled in order; `{name}` in `description` is replaced.
+    #[serde(default)]
+    pub params: Vec<String>,
+    pub description: String,
+    /// Plan candidate id (e.g. `cand_scan_read_2`) to run when the planner offers it.
+    #[serde(default)]
+    pub plan: Option<String>,
+    /// Overrides `execution.token_budget` for goals run from this template.
+    #[serde(default)]
+    pub token_budget: Option<u64>,
+}
+
+impl GoalTemplateConfig {
+    /// Substitutes `values`, given in `params` order, into the description.
+    pub fn expand(&self, values: &[String]) -> String {
+        self.params
+            .iter()
+            .zip(values)
+            .fold(self.description.clone(), |description, (param, value)| {
+                description.replace(&format!("{{{param}}}"), value)
+            })
+    }
+}
+
+/// How long a new approval request stays open before it expires, and how chat replies link
+/// to it on the web dashboard.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalsConfig {
+    #[serde(default = "default_approval_ttl_ms")]
+    pub default_ttl_ms: u64,
+    /// Per capability class (`read`, `write`, `exec`, `net`), e.g. `exec = 600000`.
+    #[serde(default)]
+    pub ttl_ms: BTreeMap<String, u64>,
+    /// How long after expiry `/rerequest <goal_id>` may still revive the action.
+    #[serde(default = "default_rerequest_grace_ms")]
+    pub rerequest_grace_ms: u64,
+    /// Dashboard base URL, e.g. `http://127.0.0.1:3000`, for `approval_url=` deep links.
+    #[serde(default)]
+    pub dashboard_url: Option<String>,
+    /// Environment variable holding the HMAC secret that signs and checks deep links.
+    #[serde(default)]
+    pub link_secret_env: Option<String>,
+    /// How this instance labels its own approvals in the shared inbox.
+    #[serde(default = "default_approval_instance_name")]
+    pub instance_name: String,
+    /// Other instances whose pending approvals the dashboard's shared inbox lists.
+    #[serde(default)]
+    pub peers: Vec<ApprovalPeerConfig>,
+    /// Post a notice with the approval id, tool, capability, and expiry to the conversation
+    /// whose message queued the approval.
+    #[serde(default = "default_true")]
+    pub notify_origin: bool,
+    /// Conversation that gets the same notice for every approval the gateway queues.
+    #[serde(default)]
+    pub operator_channel: Option<ApprovalChannelConfig>,
+    /// Who may approve or deny, per capability class, e.g. `exec = ["alice", "bob"]`.
+    /// Classes not listed accept any approver the channel lets through.
+    #[serde(default)]
+    pub approvers: BTreeMap<String, Vec<String>>,
+    /// Exec and write approvals need two distinct approvers while the runtime is in secure
+    /// mode, on top of any policy quorum.
+    #[serde(default)]
+    pub two_person_secure: bool,
+}
+
+/// A channel conversation, e.g. `channel = "discord"` and a Discord channel id as `target`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalChannelConfig {
+    pub channel: String,
+    pub target: String,
+}
+
+/// Another TITAN instance polled for the shared approval inbox; decisions made in the inbox
+/// are sent back to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalPeerConfig {
+    pub name: String,
+    /// Base URL of the peer's web API, e.g. `http://laptop:3000`.
+    pub url: String,
+}
+
+pub const APPROVAL_CAPABILITIES: [&str; 4] = ["read", "write", "exec", "net"];
+
+fn default_approval_ttl_ms() -> u64 {
+    300_000
+}
+
+fn default_rerequest_grace_ms() -> u64 {
+    86_400_000
+}
+
+fn default_approval_instance_name() -> String {
+    "local".to_string()
+}
+
+impl ApprovalsConfig {
+    pub fn ttl_ms_for(&self, capability: &str) -> u64 {
+        self.ttl_ms
+            .get(capability)
+            .copied()
+            .unwrap_or(self.default_ttl_ms)
+    }
+
+    /// Whether `approver` may decide an approval for `capability`; true when the class has
+    /// no approver list.
+    pub fn may_decide(&self, capability: &str, approver: &str) -> bool {
+        self.approvers
+            .get(capability)
+            .is_none_or(|approvers| approvers.iter().any(|allowed| allowed == approver))
+    }
+
+    /// The deep-link signing secret, when `link_secret_env` names a set variable.
+    pub fn link_secret(&self) -> Option<String> {
+        self.link_secret_env
+            .as_deref()
+            .and_then(|name| std::env::var(name).ok())
+            .filter(|secret| !secret.is_empty())
+    }
+}
+
+impl Default for ApprovalsConfig {
+    fn default() -> Self {
+        Self {
+            default_ttl_ms: default_approval_ttl_ms(),
+            ttl_ms: BTreeMap::new(),
+            rerequest_grace_ms: default_rerequest_grace_ms(),
+            dashboard_url: None,
+            link_secret_env: None,
+            instance_name: default_approval_instance_name(),
+            peers: Vec::new(),
+            notify_origin: true,
+            operator_channel: None,
+            approvers: BTreeMap::new(),
+            two_person_secure: false,
+        }
+    }
 }
 
 impl Default for ChatConfig {
@@ -96,6 +913,8 @@ impl Default for ChatConfig {
         Self {
             activation_mode: ActivationMode::Always,
             allowlist: Vec::new(),
+            operators: Vec::new(),
+            session_idle_close_days: None,
         }
     }
 }
@@ -104,6 +923,8 @@ impl Default for SecurityConfig {
     fn default() -> Self {
         Self {
             yolo_bypass_path_guard: true,
+            policy_file: None,
+            scan_writes_for_secrets: true,
         }
     }
 }
@@ -120,8 +941,27 @@ impl Default for TitanConfig {
             mode: AutonomyMode::default(),
             model: ModelConfig::default(),
             discord: DiscordConfig::default(),
+            telegram: TelegramConfig::default(),
             chat: ChatConfig::default(),
             security: SecurityConfig::default(),
+            execution: ExecutionConfig::default(),
+            skills: SkillsConfig::default(),
+            webhooks: Vec::new(),
+            maintenance: MaintenanceConfig::default(),
+            archive: ArchiveConfig::default(),
+            approvals: ApprovalsConfig::default(),
+            safe_mode: SafeModeConfig::default(),
+            crash_reports: CrashReportsConfig::default(),
+            goal_templates: Vec::new(),
+            storage: StorageConfig::default(),
+            backup: BackupConfig::default(),
+            federation: Vec::new(),
+            sandbox: SandboxConfig::default(),
+            egress: EgressConfig::default(),
+            embeddings: EmbeddingsConfig::default(),
+            usage: UsageConfig::default(),
+            experiment: ExperimentConfig::default(),
+            access: AccessConfig::default(),
         }
     }
 }
@@ -130,6 +970,23 @@ fn default_true() -> bool {
     true
 }
 
+fn valid_egress_rule(rule: &str) -> bool {
+    let rule = rule.trim();
+    if rule == "*" || rule.parse::<std::net::IpAddr>().is_ok() {
+        return true;
+    }
+    if let Some((addr, bits)) = rule.split_once('/') {
+        let max = match addr.parse::<std::net::IpAddr>() {
+            Ok(std::net::IpAddr::V4(_)) => 32,
+            Ok(std::net::IpAddr::V6(_)) => 128,
+            Err(_) => return false,
+        };
+        return bits.parse::<u8>().is_ok_and(|bits| bits <= max);
+    }
+    let host = rule.strip_prefix("*.").unwrap_or(rule);
+    !host.is_empty() && !host.starts_with('.') && !host.contains(['*', '/', ':', ' '])
+}
+
 #[derive(Debug, Error)]
 pub enum ConfigError {
     #[error("failed to read config at {path}: {source}")]
@@ -220,6 +1077,341 @@ impl TitanConfig {
                 "model.endpoint cannot be empty if set".to_string(),
             ));
         }
+        for webhook in &self.webhooks {
+            if webhook.name.trim().is_empty() {
+                return Err(ConfigError::ValidationFailed(
+                    "webhooks.name cannot be empty".to_string(),
+                ));
+            }
+            if !(webhook.url.starts_with("http://") || webhook.url.starts_with("https://")) {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "webhook {} url must be http(s)",
+                    webhook.name
+                )));
+            }
+            if webhook.max_attempts == 0 {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "webhook {} max_attempts must be at least 1",
+                    webhook.name
+                )));
+            }
+        }
+        if self.maintenance.interval_hours == 0 {
+            return Err(ConfigError::ValidationFailed(
+                "maintenance.interval_hours must be at least 1".to_string(),
+            ));
+        }
+        if !(self.maintenance.vacuum_free_ratio > 0.0 && self.maintenance.vacuum_free_ratio <= 1.0)
+        {
+            return Err(ConfigError::ValidationFailed(
+                "maintenance.vacuum_free_ratio must be in (0, 1]".to_string(),
+            ));
+        }
+        if self.archive.after_days == 0 || self.archive.batch_size == 0 {
+            return Err(ConfigError::ValidationFailed(
+                "archive.after_days and archive.batch_size must be at least 1".to_string(),
+            ));
+        }
+        if self.model.cache_ttl_secs == Some(0) {
+            return Err(ConfigError::ValidationFailed(
+                "model.cache_ttl_secs must be at least 1".to_string(),
+            ));
+        }
+        if self.model.outage_threshold == 0 || self.model.outage_probe_secs == 0 {
+            return Err(ConfigError::ValidationFailed(
+                "model.outage_threshold and model.outage_probe_secs must be at least 1".to_string(),
+            ));
+        }
+        if self.chat.session_idle_close_days == Some(0) {
+            return Err(ConfigError::ValidationFailed(
+                "chat.session_idle_close_days must be at least 1".to_string(),
+            ));
+        }
+        if self.usage.session_token_budget == Some(0) || self.usage.daily_token_budget == Some(0) {
+            return Err(ConfigError::ValidationFailed(
+                "usage.session_token_budget and usage.daily_token_budget must be at least 1"
+                    .to_string(),
+            ));
+        }
+        if !(self.usage.usd_per_1k_tokens.is_finite() && self.usage.usd_per_1k_tokens >= 0.0) {
+            return Err(ConfigError::ValidationFailed(
+                "usage.usd_per_1k_tokens must be a non-negative number".to_string(),
+            ));
+        }
+        if self.access.burst_limit == 0 || self.access.burst_window_secs == 0 {
+            return Err(ConfigError::ValidationFailed(
+                "access.burst_limit and access.burst_window_secs must be at least 1".to_string(),
+            ));
+        }
+        if let Some(name) = &self.experiment.name {
+            if name.trim().is_empty() {
+                return Err(ConfigError::ValidationFailed(
+                    "experiment.name must not be empty".to_string(),
+                ));
+            }
+            if self.experiment.variant_percent > 100 {
+                return Err(ConfigError::ValidationFailed(
+                    "experiment.variant_percent must be between 0 and 100".to_string(),
+                ));
+            }
+            if self
+                .experiment
+                .candidate_count
+                .is_some_and(|count| !(2..=5).contains(&count))
+            {
+                return Err(ConfigError::ValidationFailed(
+                    "experiment.candidate_count must be between 2 and 5".to_string(),
+                ));
+            }
+        }
+        if self.approvals.default_ttl_ms == 0 {
+            return Err(ConfigError::ValidationFailed(
+                "approvals.default_ttl_ms must be at least 1".to_string(),
+            ));
+        }
+        for (capability, ttl_ms) in &self.approvals.ttl_ms {
+            if !APPROVAL_CAPABILITIES.contains(&capability.as_str()) {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "approvals.ttl_ms.{capability} is not a capability class (read, write, exec, net)"
+                )));
+            }
+            if *ttl_ms == 0 {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "approvals.ttl_ms.{capability} must be at least 1"
+                )));
+            }
+        }
+        for (capability, approvers) in &self.approvals.approvers {
+            if !APPROVAL_CAPABILITIES.contains(&capability.as_str()) {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "approvals.approvers.{capability} is not a capability class (read, write, exec, net)"
+                )));
+            }
+            if approvers.iter().all(|approver| approver.trim().is_empty()) {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "approvals.approvers.{capability} must name at least one approver"
+                )));
+            }
+        }
+        if let Some(url) = &self.approvals.dashboard_url
+            && !(url.starts_with("http://") || url.starts_with("https://"))
+        {
+            return Err(ConfigError::ValidationFailed(
+                "approvals.dashboard_url must be http(s)".to_string(),
+            ));
+        }
+        if self.approvals.instance_name.trim().is_empty() {
+            return Err(ConfigError::ValidationFailed(
+                "approvals.instance_name cannot be empty".to_string(),
+            ));
+        }
+        let mut instance_names =
+            std::collections::HashSet::from([self.approvals.instance_name.as_str()]);
+        for peer in &self.approvals.peers {
+            if peer.name.trim().is_empty() || !instance_names.insert(peer.name.as_str()) {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "approvals.peers names must be unique, non-empty, and differ from instance_name: '{}'",
+                    peer.name
+                )));
+            }
+            if !(peer.url.starts_with("http://") || peer.url.starts_with("https://")) {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "approvals peer {} url must be http(s)",
+                    peer.name
+                )));
+            }
+        }
+        if let Some(operator_channel) = &self.approvals.operator_channel
+            && (operator_channel.channel.trim().is_empty()
+                || operator_channel.target.trim().is_empty())
+        {
+            return Err(ConfigError::ValidationFailed(
+                "approvals.operator_channel needs a channel and a target".to_string(),
+            ));
+        }
+        if self.storage.blob_threshold_bytes == 0 {
+            return Err(ConfigError::ValidationFailed(
+                "storage.blob_threshold_bytes must be at least 1".to_string(),
+            ));
+        }
+        if self.storage.max_output_bytes == Some(0) {
+            return Err(ConfigError::ValidationFailed(
+                "storage.max_output_bytes must be at least 1".to_string(),
+            ));
+        }
+        if self.storage.blob_backend == BlobBackend::S3 {
+            let Some(s3) = &self.storage.s3 else {
+                return Err(ConfigError::ValidationFailed(
+                    "storage.blob_backend = \"s3\" needs a [storage.s3] section".to_string(),
+                ));
+            };
+            if !(s3.endpoint.starts_with("http://") || s3.endpoint.starts_with("https://")) {
+                return Err(ConfigError::ValidationFailed(
+                    "storage.s3.endpoint must be http(s)".to_string(),
+                ));
+            }
+            if s3.bucket.trim().is_empty() {
+                return Err(ConfigError::ValidationFailed(
+                    "storage.s3.bucket cannot be empty".to_string(),
+                ));
+            }
+        }
+        let mut peer_names = std::collections::HashSet::new();
+        for peer in &self.federation {
+            if peer.name.trim().is_empty() || !peer_names.insert(peer.name.as_str()) {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "federation peer names must be unique and non-empty: '{}'",
+                    peer.name
+                )));
+            }
+            if !(peer.url.starts_with("http://") || peer.url.starts_with("https://")) {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "federation peer {} url must be http(s)",
+                    peer.name
+                )));
+            }
+            if peer.intents.is_empty() && peer.paths.is_empty() {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "federation peer {} needs intents or paths",
+                    peer.name
+                )));
+            }
+        }
+        let mut profile_names = std::collections::HashSet::new();
+        for profile in &self.sandbox.profiles {
+            if profile.name.trim().is_empty() || !profile_names.insert(profile.name.as_str()) {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "sandbox profile names must be unique and non-empty: '{}'",
+                    profile.name
+                )));
+            }
+            if let Some(risk) = profile
+                .risk_modes
+                .iter()
+                .find(|risk| !matches!(risk.as_str(), "secure" | "yolo"))
+            {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "sandbox profile {} risk_modes must be secure or yolo, got '{risk}'",
+                    profile.name
+                )));
+            }
+            if let Some(path) = profile.paths.iter().find(|path| {
+                Path::new(path).is_absolute()
+                    || Path::new(path)
+                        .components()
+                        .any(|part| matches!(part, std::path::Component::ParentDir))
+            }) {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "sandbox profile {} paths must stay inside the workspace: '{path}'",
+                    profile.name
+                )));
+            }
+            if profile.network == NetworkPolicy::Allowlist && profile.allowed_hosts.is_empty() {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "sandbox profile {} needs allowed_hosts with network = \"allowlist\"",
+                    profile.name
+                )));
+            }
+        }
+        if let Some(rule) = self
+            .egress
+            .allow
+            .iter()
+            .chain(&self.egress.deny)
+            .find(|rule| !valid_egress_rule(rule))
+        {
+            return Err(ConfigError::ValidationFailed(format!(
+                "egress rules must be a host, *.domain, *, IP, or CIDR: '{rule}'"
+            )));
+        }
+        if let Some(rule) = self.egress.allow_private.iter().find(|rule| {
+            let rule = rule.trim();
+            !valid_egress_rule(rule)
+                || (rule.parse::<std::net::IpAddr>().is_err() && !rule.contains('/'))
+        }) {
+            return Err(ConfigError::ValidationFailed(format!(
+                "egress.allow_private entries must be an IP or CIDR: '{rule}'"
+            )));
+        }
+        if self.embeddings.dimensions == 0 {
+            return Err(ConfigError::ValidationFailed(
+                "embeddings.dimensions must be at least 1".to_string(),
+            ));
+        }
+        if self.embeddings.batch_size == 0 {
+            return Err(ConfigError::ValidationFailed(
+                "embeddings.batch_size must be at least 1".to_string(),
+            ));
+        }
+        if self.embeddings.provider != EmbeddingProvider::Hashing
+            && self.embeddings.model.as_deref().is_none_or(str::is_empty)
+        {
+            return Err(ConfigError::ValidationFailed(
+                "embeddings.model is required for ollama and openai embeddings".to_string(),
+            ));
+        }
+        if self.backup.keep_last == 0 {
+            return Err(ConfigError::ValidationFailed(
+                "backup.keep_last must be at least 1".to_string(),
+            ));
+        }
+        if let Some(s3) = &self.backup.s3 {
+            if !(s3.endpoint.starts_with("http://") || s3.endpoint.starts_with("https://")) {
+                return Err(ConfigError::ValidationFailed(
+                    "backup.s3.endpoint must be http(s)".to_string(),
+                ));
+            }
+            if s3.bucket.trim().is_empty() {
+                return Err(ConfigError::ValidationFailed(
+                    "backup.s3.bucket cannot be empty".to_string(),
+                ));
+            }
+        }
+        for (name, limit) in [
+            ("max_concurrent_goals", self.execution.max_concurrent_goals),
+            (
+                "max_concurrent_goals_per_channel",
+                self.execution.max_concurrent_goals_per_channel,
+            ),
+            (
+                "max_concurrent_goals_per_session",
+                self.execution.max_concurrent_goals_per_session,
+            ),
+        ] {
+            if limit == Some(0) {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "execution.{name} must be at least 1"
+                )));
+            }
+        }
+        for (tool, spec) in &self.execution.output_transforms {
+            if !spec.starts_with("json:") && !spec.starts_with("regex:") {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "execution.output_transforms.{tool} must start with 'json:' or 'regex:'"
+                )));
+            }
+        }
+        let mut template_names = std::collections::HashSet::new();
+        for template in &self.goal_templates {
+            if template.name.trim().is_empty() || template.name.contains(char::is_whitespace) {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "goal_templates.name '{}' must be a single non-empty word",
+                    template.name
+                )));
+            }
+            if !template_names.insert(template.name.as_str()) {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "goal template {} is defined twice",
+                    template.name
+                )));
+            }
+            if template.description.trim().is_empty() {
+                return Err(ConfigError::ValidationFailed(format!(
+                    "goal template {} description cannot be empty",
+                    template.name
+                )));
+            }
+        }
         fs::create_dir_all(&self.workspace_dir).map_err(|source| ConfigError::WriteFailed {
             path: self.workspace_dir.clone(),
             source,
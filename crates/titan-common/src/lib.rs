@@ -4,7 +4,20 @@ pub mod path_guard;
 
 pub const APP_NAME: &str = "TITAN";
 
+/// Milliseconds since the Unix epoch; 0 if the clock reads before it.
+pub fn now_epoch_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
 pub use config::{
-    ActivationMode, AutonomyMode, ChatConfig, DiscordConfig, ModelConfig, ModelProvider,
-    SecurityConfig, TitanConfig,
+    AccessConfig, ActivationMode, ApprovalChannelConfig, ApprovalPeerConfig, ApprovalsConfig,
+    ArchiveConfig, ArchiveTarget, AutonomyMode, BackupConfig, BlobBackend, ChatConfig,
+    CrashReportsConfig, DiscordConfig, EgressConfig, EmbeddingProvider, EmbeddingsConfig,
+    ExecutionConfig, ExperimentConfig, ExperimentPlanner, FederationPeerConfig, GoalTemplateConfig,
+    MaintenanceConfig, ModelConfig, ModelProvider, NetworkPolicy, S3BackupConfig, S3BlobConfig,
+    SafeModeConfig, SandboxConfig, SandboxProfileConfig, SecurityConfig, SkillRegistryConfig,
+    SkillsConfig, StorageConfig, TelegramConfig, TitanConfig, UsageConfig, WebhookConfig,
 };
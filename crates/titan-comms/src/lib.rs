@@ -1,10 +1,14 @@
 use anyhow::{Context, Result, bail};
+use hmac::{Hmac, Mac};
 use reqwest::blocking::Client;
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderValue};
 use serde::Deserialize;
 use serde::Serialize;
+use sha2::Sha256;
 use titan_discord::DiscordGateway;
 
+pub mod telegram;
+
 #[derive(Debug, Deserialize)]
 struct OkEnvelope {
     ok: bool,
@@ -376,9 +380,248 @@ fn bridge_send(channel: ChannelKind, target: &str, message: &str) -> Result<Comm
     Ok(send_result(channel.as_str(), format!("bridge {}", bridge)))
 }
 
+/// `sha256=<hex>` HMAC of the raw request body, sent as `X-Titan-Signature`.
+pub fn sign_webhook_body(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(body.as_bytes());
+    let hex: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("sha256={hex}")
+}
+
+/// Hex HMAC over `<approval_id>:<expires_at_ms>`, the `sig` of an approval deep link.
+pub fn sign_approval_link(secret: &str, approval_id: &str, expires_at_ms: i64) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(format!("{approval_id}:{expires_at_ms}").as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Dashboard URL of one approval's detail page, valid until the approval expires.
+pub fn approval_deep_link(
+    dashboard_url: &str,
+    secret: &str,
+    approval_id: &str,
+    expires_at_ms: i64,
+) -> String {
+    format!(
+        "{}/approvals/{approval_id}?exp={expires_at_ms}&sig={}",
+        dashboard_url.trim_end_matches('/'),
+        sign_approval_link(secret, approval_id, expires_at_ms)
+    )
+}
+
+/// Checks a deep link's `exp` and `sig`; links past `exp` are rejected even if signed.
+pub fn verify_approval_link(
+    secret: &str,
+    approval_id: &str,
+    expires_at_ms: i64,
+    signature: &str,
+    now_ms: i64,
+) -> bool {
+    let expected = sign_approval_link(secret, approval_id, expires_at_ms);
+    // Compare every byte so timing does not reveal the matching prefix.
+    let matches = expected.len() == signature.len()
+        && expected
+            .bytes()
+            .zip(signature.bytes())
+            .fold(0_u8, |diff, (a, b)| diff | (a ^ b))
+            == 0;
+    matches && now_ms <= expires_at_ms
+}
+
+/// Posts one JSON webhook body and returns the HTTP status; transport failures are errors.
+pub fn post_webhook(
+    url: &str,
+    event_kind: &str,
+    delivery_id: i64,
+    body: &str,
+    secret: Option<&str>,
+) -> Result<u16> {
+    let client = Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()?;
+    let mut request = client
+        .post(url)
+        .header(CONTENT_TYPE, "application/json")
+        .header("X-Titan-Event", event_kind)
+        .header("X-Titan-Delivery", delivery_id.to_string());
+    if let Some(secret) = secret {
+        request = request.header("X-Titan-Signature", sign_webhook_body(secret, body));
+    }
+    let resp = request.body(body.to_string()).send()?;
+    Ok(resp.status().as_u16())
+}
+
+/// One result of a federation peer's `POST /api/events/batch`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RemoteGoalSubmission {
+    /// `processed`, `duplicate`, `rejected`, or `error`.
+    pub status: String,
+    pub goal_id: Option<String>,
+    pub goal_status: Option<String>,
+    #[serde(default)]
+    pub detail: String,
+}
+
+/// A federation peer's goal with the traces after the requested id, from `GET /api/goals/{id}`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RemoteGoalState {
+    pub id: String,
+    pub status: String,
+    #[serde(default)]
+    pub traces: Vec<RemoteTrace>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RemoteTrace {
+    pub id: i64,
+    pub event_type: String,
+    pub detail: String,
+    pub risk_mode: String,
+}
+
+#[derive(Deserialize)]
+struct RemoteBatchResponse {
+    results: Vec<RemoteGoalSubmission>,
+}
+
+/// Submits `message` as one event to a federation peer. `dedupe_key` makes a retry return the
+/// goal the first attempt created.
+pub fn submit_remote_goal(
+    base_url: &str,
+    actor_id: &str,
+    message: &str,
+    dedupe_key: &str,
+) -> Result<RemoteGoalSubmission> {
+    let url = format!("{}/api/events/batch", base_url.trim_end_matches('/'));
+    let body = serde_json::json!({
+        "events": [{"actor_id": actor_id, "message": message, "dedupe_key": dedupe_key}]
+    });
+    off_runtime(|| {
+        let resp = Client::builder()
+            .timeout(std::time::Duration::from_secs(120))
+            .build()?
+            .post(&url)
+            .json(&body)
+            .send()
+            .with_context(|| format!("failed to reach {url}"))?;
+        if !resp.status().is_success() {
+            bail!("{url} returned {}: {}", resp.status(), resp.text()?);
+        }
+        resp.json::<RemoteBatchResponse>()?
+            .results
+            .into_iter()
+            .next()
+            .context("peer returned no batch result")
+    })
+}
+
+pub fn fetch_remote_goal(base_url: &str, goal_id: &str, after_id: i64) -> Result<RemoteGoalState> {
+    let url = format!(
+        "{}/api/goals/{goal_id}?after_id={after_id}",
+        base_url.trim_end_matches('/')
+    );
+    off_runtime(|| {
+        let resp = Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()?
+            .get(&url)
+            .send()
+            .with_context(|| format!("failed to reach {url}"))?;
+        if !resp.status().is_success() {
+            bail!("{url} returned {}", resp.status());
+        }
+        Ok(resp.json()?)
+    })
+}
+
+/// A pending approval on another instance, from its `GET /api/approvals/pending`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RemoteApproval {
+    pub id: String,
+    pub tool_name: String,
+    pub capability: String,
+    pub status: String,
+    pub requested_by: Option<String>,
+    pub expires_at_ms: i64,
+    pub remaining_ms: i64,
+}
+
+/// The peer's answer to an approve or deny sent from the shared inbox.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteDecision {
+    pub status: String,
+    pub detail: String,
+}
+
+pub fn fetch_remote_approvals(base_url: &str) -> Result<Vec<RemoteApproval>> {
+    let url = format!("{}/api/approvals/pending", base_url.trim_end_matches('/'));
+    off_runtime(|| {
+        let resp = Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()?
+            .get(&url)
+            .send()
+            .with_context(|| format!("failed to reach {url}"))?;
+        if !resp.status().is_success() {
+            bail!("{url} returned {}", resp.status());
+        }
+        Ok(resp.json()?)
+    })
+}
+
+/// Approves or denies `approval_id` on the peer, as `resolved_by`.
+pub fn resolve_remote_approval(
+    base_url: &str,
+    approval_id: &str,
+    approve: bool,
+    resolved_by: &str,
+    reason: Option<&str>,
+) -> Result<RemoteDecision> {
+    let url = format!(
+        "{}/api/approvals/{approval_id}/{}",
+        base_url.trim_end_matches('/'),
+        if approve { "approve" } else { "deny" }
+    );
+    let body = serde_json::json!({"resolved_by": resolved_by, "reason": reason});
+    off_runtime(|| {
+        let resp = Client::builder()
+            .timeout(std::time::Duration::from_secs(120))
+            .build()?
+            .post(&url)
+            .json(&body)
+            .send()
+            .with_context(|| format!("failed to reach {url}"))?;
+        if !resp.status().is_success() {
+            bail!("{url} returned {}: {}", resp.status(), resp.text()?);
+        }
+        Ok(resp.json()?)
+    })
+}
+
+// The blocking client panics on a tokio worker, and chat and web handlers call peers from one.
+fn off_runtime<T: Send>(request: impl FnOnce() -> Result<T> + Send) -> Result<T> {
+    std::thread::scope(|scope| {
+        scope
+            .spawn(request)
+            .join()
+            .unwrap_or_else(|_| bail!("peer request thread panicked"))
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use super::ChannelKind;
+    use super::{ChannelKind, approval_deep_link, sign_webhook_body, verify_approval_link};
 
     #[test]
     fn parses_channel_aliases() {
@@ -397,6 +640,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn webhook_signature_matches_rfc4231_vector() {
+        assert_eq!(
+            sign_webhook_body("Jefe", "what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn approval_links_verify_until_expiry() {
+        let link = approval_deep_link("http://127.0.0.1:3000/", "s3cret", "apr-1", 5_000);
+        let sig = link.rsplit_once("sig=").expect("sig").1;
+        assert!(link.starts_with("http://127.0.0.1:3000/approvals/apr-1?exp=5000&sig="));
+        assert!(verify_approval_link("s3cret", "apr-1", 5_000, sig, 4_999));
+        assert!(!verify_approval_link("s3cret", "apr-1", 5_000, sig, 5_001));
+        assert!(!verify_approval_link("s3cret", "apr-2", 5_000, sig, 4_999));
+        assert!(!verify_approval_link("other", "apr-1", 5_000, sig, 4_999));
+    }
+
     #[test]
     fn includes_full_channel_surface() {
         assert_eq!(ChannelKind::all().len(), 21);
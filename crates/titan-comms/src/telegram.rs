@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+
+const TELEGRAM_API_BASE: &str = "https://api.telegram.org";
+
+/// Bot API client for the long-polling Telegram channel.
+#[derive(Debug, Clone)]
+pub struct TelegramBot {
+    client: Client,
+    base: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelegramUpdate {
+    pub update_id: i64,
+    pub message: Option<TelegramMessage>,
+    pub callback_query: Option<TelegramCallbackQuery>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelegramMessage {
+    pub message_id: i64,
+    pub chat: TelegramChat,
+    pub from: Option<TelegramUser>,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelegramChat {
+    pub id: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelegramUser {
+    pub id: i64,
+    #[serde(default)]
+    pub is_bot: bool,
+    pub username: Option<String>,
+}
+
+/// A press on an inline keyboard button; `data` is the button's `callback_data`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelegramCallbackQuery {
+    pub id: String,
+    pub from: TelegramUser,
+    pub message: Option<TelegramMessage>,
+    pub data: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Envelope<T> {
+    ok: bool,
+    result: Option<T>,
+    description: Option<String>,
+}
+
+impl TelegramBot {
+    /// `poll_timeout_secs` is how long `getUpdates` may hold a request open; the HTTP timeout
+    /// leaves room beyond it.
+    pub fn new(token: &str, poll_timeout_secs: u64) -> Result<Self> {
+        Self::with_api_base(TELEGRAM_API_BASE, token, poll_timeout_secs)
+    }
+
+    pub fn with_api_base(api_base: &str, token: &str, poll_timeout_secs: u64) -> Result<Self> {
+        let token = token.trim();
+        if token.is_empty() {
+            bail!("telegram token is empty");
+        }
+        let client = Client::builder()
+            .timeout(Duration::from_secs(poll_timeout_secs + 10))
+            .build()
+            .with_context(|| "failed to build telegram HTTP client")?;
+        Ok(Self {
+            client,
+            base: format!("{}/bot{token}", api_base.trim_end_matches('/')),
+        })
+    }
+
+    fn call<T: DeserializeOwned>(&self, method: &str, body: &Value) -> Result<T> {
+        let response = self
+            .client
+            .post(format!("{}/{method}", self.base))
+            .json(body)
+            .send()
+            .with_context(|| format!("failed to call telegram {method}"))?;
+        let status = response.status();
+        let envelope: Envelope<T> = response
+            .json()
+            .with_context(|| format!("failed to parse telegram {method} response"))?;
+        match envelope.result {
+            Some(result) if envelope.ok => Ok(result),
+            _ => bail!(
+                "telegram {method} failed: {} {}",
+                status.as_u16(),
+                envelope.description.unwrap_or_default()
+            ),
+        }
+    }
+
+    /// Updates after `offset`, waiting up to `timeout_secs` for the first one to arrive.
+    pub fn get_updates(
+        &self,
+        offset: Option<i64>,
+        timeout_secs: u64,
+    ) -> Result<Vec<TelegramUpdate>> {
+        self.call(
+            "getUpdates",
+            &json!({
+                "offset": offset,
+                "timeout": timeout_secs,
+                "allowed_updates": ["message", "callback_query"],
+            }),
+        )
+    }
+
+    pub fn send_message(
+        &self,
+        chat_id: &str,
+        text: &str,
+        reply_markup: Option<Value>,
+    ) -> Result<TelegramMessage> {
+        if text.trim().is_empty() {
+            bail!("text is required");
+        }
+        let mut body = json!({"chat_id": chat_id, "text": text});
+        if let Some(markup) = reply_markup {
+            body["reply_markup"] = markup;
+        }
+        self.call("sendMessage", &body)
+    }
+
+    /// Stops the button's loading spinner and shows `text` to the user who pressed it.
+    pub fn answer_callback_query(&self, callback_query_id: &str, text: &str) -> Result<()> {
+        let _: bool = self.call(
+            "answerCallbackQuery",
+            &json!({"callback_query_id": callback_query_id, "text": text}),
+        )?;
+        Ok(())
+    }
+}
+
+/// Approve and Deny buttons whose presses come back as `approve:<id>` or `deny:<id>`.
+pub fn approval_keyboard(approval_id: &str) -> Value {
+    json!({
+        "inline_keyboard": [[
+            {"text": "Approve", "callback_data": format!("approve:{approval_id}")},
+            {"text": "Deny", "callback_data": format!("deny:{approval_id}")},
+        ]]
+    })
+}
+
+/// `(approve, approval_id)` from the `callback_data` of an [`approval_keyboard`] button.
+pub fn parse_approval_callback(data: &str) -> Option<(bool, &str)> {
+    let (action, approval_id) = data.split_once(':')?;
+    let approve = match action {
+        "approve" => true,
+        "deny" => false,
+        _ => return None,
+    };
+    (!approval_id.trim().is_empty()).then_some((approve, approval_id.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{approval_keyboard, parse_approval_callback};
+
+    #[test]
+    fn approval_buttons_round_trip_through_callback_data() {
+        let keyboard = approval_keyboard("apr-1");
+        let buttons = keyboard["inline_keyboard"][0]
+            .as_array()
+            .expect("button row");
+        let data: Vec<&str> = buttons
+            .iter()
+            .filter_map(|button| button["callback_data"].as_str())
+            .collect();
+        assert_eq!(data, vec!["approve:apr-1", "deny:apr-1"]);
+        assert_eq!(parse_approval_callback(data[0]), Some((true, "apr-1")));
+        assert_eq!(parse_approval_callback(data[1]), Some((false, "apr-1")));
+        assert_eq!(parse_approval_callback("approve:"), None);
+        assert_eq!(parse_approval_callback("snooze:apr-1"), None);
+    }
+}
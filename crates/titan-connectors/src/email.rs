@@ -0,0 +1,895 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow, bail};
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::Value;
+use titan_memory::ConnectorIoRecord;
+use titan_tools::CapabilityClass;
+use uuid::Uuid;
+
+use super::{
+    ConfigFieldKind, Connector, ConnectorConfigField, ConnectorContext, ConnectorFactory,
+    ConnectorHealth, ConnectorScopes, ConnectorToolDescriptor, ConnectorToolResult, ConnectorType,
+    DEFAULT_CACHE_TTL_SECS, config_field, now_epoch_ms, resolve_secret,
+};
+
+const MAIL_TIMEOUT: Duration = Duration::from_secs(30);
+/// Longest protocol line read before the server is treated as misbehaving.
+const MAX_LINE_BYTES: u64 = 64 * 1024;
+/// Largest IMAP literal accepted; header blocks are far smaller.
+const MAX_LITERAL_BYTES: usize = 1024 * 1024;
+const DEFAULT_LIST_LIMIT: usize = 10;
+const MAX_LIST_LIMIT: usize = 50;
+/// Headers returned for each listed message.
+const FETCHED_HEADERS: &str = "FROM TO SUBJECT DATE";
+
+pub(crate) const EMAIL_CONFIG_SCHEMA: &[ConnectorConfigField] = &[
+    config_field("username", "Mailbox login", ConfigFieldKind::Text, true),
+    config_field(
+        "smtp_server",
+        "SMTP server (host:port, 465 by default)",
+        ConfigFieldKind::Text,
+        false,
+    ),
+    config_field(
+        "imap_server",
+        "IMAP server (host:port, 993 by default)",
+        ConfigFieldKind::Text,
+        false,
+    ),
+    config_field(
+        "from_address",
+        "From address (defaults to the login)",
+        ConfigFieldKind::Text,
+        false,
+    ),
+    config_field("mailbox", "IMAP mailbox", ConfigFieldKind::Text, false),
+    config_field(
+        "password_env",
+        "Password env var name",
+        ConfigFieldKind::EnvVar,
+        false,
+    ),
+    config_field(
+        "security",
+        "Connection security (tls or plain)",
+        ConfigFieldKind::Text,
+        false,
+    ),
+    config_field(
+        "cache_ttl_secs",
+        "Read cache TTL in seconds",
+        ConfigFieldKind::Seconds,
+        false,
+    ),
+    config_field("io_log", "I/O log", ConfigFieldKind::IoLog, false),
+];
+
+pub(crate) struct EmailConnectorFactory;
+
+impl ConnectorFactory for EmailConnectorFactory {
+    fn type_name(&self) -> &'static str {
+        "email"
+    }
+
+    fn tools(&self) -> Vec<ConnectorToolDescriptor> {
+        EmailConnector::tools_static()
+    }
+
+    fn config_schema(&self) -> &'static [ConnectorConfigField] {
+        EMAIL_CONFIG_SCHEMA
+    }
+
+    fn default_config(&self) -> Value {
+        serde_json::json!({
+            "username": "",
+            "mailbox": "INBOX",
+            "password_env": "EMAIL_PASSWORD",
+            "security": "tls",
+        })
+    }
+
+    fn build(&self, id: Uuid, display_name: String) -> Box<dyn Connector> {
+        Box::new(EmailConnector { id, display_name })
+    }
+}
+
+#[derive(Debug)]
+struct EmailConnector {
+    id: Uuid,
+    display_name: String,
+}
+
+impl EmailConnector {
+    fn tools_static() -> Vec<ConnectorToolDescriptor> {
+        vec![
+            ConnectorToolDescriptor {
+                name: "email.send".to_string(),
+                description: "Send a plain-text email over SMTP".to_string(),
+                required_scopes: ConnectorScopes {
+                    read: false,
+                    write: true,
+                    net: true,
+                },
+                risk_class: CapabilityClass::Write,
+                cache_ttl_secs: None,
+            },
+            ConnectorToolDescriptor {
+                name: "email.list_recent".to_string(),
+                description: "List the newest messages in the mailbox".to_string(),
+                required_scopes: ConnectorScopes {
+                    read: true,
+                    write: false,
+                    net: true,
+                },
+                risk_class: CapabilityClass::Read,
+                cache_ttl_secs: Some(DEFAULT_CACHE_TTL_SECS),
+            },
+            ConnectorToolDescriptor {
+                name: "email.search".to_string(),
+                description: "Search the mailbox by text, sender, subject, or date".to_string(),
+                required_scopes: ConnectorScopes {
+                    read: true,
+                    write: false,
+                    net: true,
+                },
+                risk_class: CapabilityClass::Read,
+                cache_ttl_secs: Some(DEFAULT_CACHE_TTL_SECS),
+            },
+        ]
+    }
+}
+
+impl Connector for EmailConnector {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn connector_type(&self) -> ConnectorType {
+        ConnectorType::Email
+    }
+
+    fn display_name(&self) -> &str {
+        &self.display_name
+    }
+
+    fn required_scopes(&self) -> ConnectorScopes {
+        ConnectorScopes {
+            read: true,
+            write: true,
+            net: true,
+        }
+    }
+
+    // Logs in to IMAP when it is configured, otherwise authenticates to SMTP.
+    fn health_check(&self, ctx: &ConnectorContext<'_>) -> Result<ConnectorHealth> {
+        let cfg = EmailConfig::from_value(ctx.config)?;
+        let password = cfg.password(ctx)?;
+        if cfg.imap_server.is_some() {
+            logged(ctx, "email.health", "IMAP", &cfg.mailbox, || {
+                let mut session = ImapSession::login(&cfg, &password)?;
+                session.logout();
+                Ok(((), 0))
+            })?;
+            return Ok(ConnectorHealth {
+                ok: true,
+                detail: "imap_login=ok".to_string(),
+            });
+        }
+        logged(ctx, "email.health", "SMTP", "AUTH", || {
+            let mut session = SmtpSession::login(&cfg, &password)?;
+            session.quit();
+            Ok(((), 0))
+        })?;
+        Ok(ConnectorHealth {
+            ok: true,
+            detail: "smtp_login=ok".to_string(),
+        })
+    }
+
+    fn tools(&self) -> Vec<ConnectorToolDescriptor> {
+        Self::tools_static()
+    }
+
+    fn execute_tool(
+        &self,
+        tool_name: &str,
+        input: &Value,
+        ctx: &ConnectorContext<'_>,
+    ) -> Result<ConnectorToolResult> {
+        let cfg = EmailConfig::from_value(ctx.config)?;
+        match tool_name {
+            "email.send" => {
+                let mail = OutgoingMail::from_input(input, &cfg)?;
+                let password = cfg.password(ctx)?;
+                let message = mail.render();
+                let reply = logged(ctx, tool_name, "SMTP", "DATA", || {
+                    let mut session = SmtpSession::login(&cfg, &password)?;
+                    let reply = session.send(&mail, &message)?;
+                    session.quit();
+                    Ok((reply, message.len() as u64))
+                })?;
+                Ok(ConnectorToolResult {
+                    status: "success".to_string(),
+                    output_json: serde_json::json!({
+                        "sent": true,
+                        "message_id": mail.message_id,
+                    }),
+                    metadata_json: serde_json::json!({
+                        "recipients": mail.to.len() + mail.cc.len(),
+                        "smtp_status": reply.get(..3).and_then(|code| code.parse::<u16>().ok()),
+                    }),
+                })
+            }
+            "email.list_recent" | "email.search" => {
+                let criteria = if tool_name == "email.search" {
+                    search_criteria(input)?
+                } else {
+                    "ALL".to_string()
+                };
+                let limit = input
+                    .get("limit")
+                    .and_then(Value::as_u64)
+                    .map_or(DEFAULT_LIST_LIMIT, |limit| limit as usize)
+                    .clamp(1, MAX_LIST_LIMIT);
+                let password = cfg.password(ctx)?;
+                let (matched, messages) = logged(ctx, tool_name, "IMAP", &cfg.mailbox, || {
+                    let mut session = ImapSession::login(&cfg, &password)?;
+                    session.examine(&cfg.mailbox)?;
+                    let uids = session.search(&criteria)?;
+                    let newest: Vec<u32> = uids.iter().rev().take(limit).copied().collect();
+                    let messages = session.fetch_headers(&newest)?;
+                    session.logout();
+                    let bytes = messages.iter().map(MailSummary::len).sum::<usize>() as u64;
+                    Ok(((uids.len(), messages), bytes))
+                })?;
+                Ok(ConnectorToolResult {
+                    status: "success".to_string(),
+                    output_json: serde_json::json!({
+                        "mailbox": cfg.mailbox,
+                        "messages": messages
+                            .iter()
+                            .map(MailSummary::to_json)
+                            .collect::<Vec<_>>(),
+                    }),
+                    metadata_json: serde_json::json!({
+                        "matched": matched,
+                        "returned": messages.len(),
+                    }),
+                })
+            }
+            _ => bail!("unsupported email tool: {tool_name}"),
+        }
+    }
+}
+
+// Runs one mail exchange and records it in the connector's I/O log, if enabled. `call`
+// returns its result and the bytes it transferred.
+fn logged<T>(
+    ctx: &ConnectorContext<'_>,
+    tool_name: &str,
+    method: &str,
+    path: &str,
+    call: impl FnOnce() -> Result<(T, u64)>,
+) -> Result<T> {
+    let started = Instant::now();
+    let outcome = call();
+    if let Some(log) = ctx.io_log {
+        log.push(ConnectorIoRecord {
+            id: 0,
+            connector_id: ctx.connector_id.to_string(),
+            tool_name: tool_name.to_string(),
+            method: method.to_string(),
+            path: path.to_string(),
+            status: None,
+            response_bytes: outcome.as_ref().map_or(0, |(_, bytes)| *bytes),
+            latency_ms: started.elapsed().as_millis() as u64,
+            error: outcome.as_ref().err().map(|err| err.to_string()),
+            created_at_ms: now_epoch_ms(),
+        });
+    }
+    outcome
+        .map(|(value, _)| value)
+        .with_context(|| format!("{tool_name} failed"))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum MailSecurity {
+    /// Implicit TLS from the first byte, as on ports 465 and 993.
+    #[default]
+    Tls,
+    /// Unencrypted; only for local relays and test servers.
+    Plain,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmailConfig {
+    username: String,
+    #[serde(default)]
+    smtp_server: Option<String>,
+    #[serde(default)]
+    imap_server: Option<String>,
+    #[serde(default)]
+    from_address: Option<String>,
+    #[serde(default = "default_mailbox")]
+    mailbox: String,
+    #[serde(default)]
+    password_env: Option<String>,
+    #[serde(default)]
+    security: MailSecurity,
+}
+
+fn default_mailbox() -> String {
+    "INBOX".to_string()
+}
+
+impl EmailConfig {
+    fn from_value(value: &Value) -> Result<Self> {
+        let cfg: Self = serde_json::from_value(value.clone())
+            .with_context(|| "invalid email connector config")?;
+        if cfg.username.trim().is_empty() {
+            bail!("invalid email connector config: username is required");
+        }
+        Ok(cfg)
+    }
+
+    fn password(&self, ctx: &ConnectorContext<'_>) -> Result<String> {
+        resolve_secret(
+            ctx.secret_resolver,
+            ctx.connector_id,
+            "email_password",
+            self.password_env.as_deref().unwrap_or("EMAIL_PASSWORD"),
+        )
+    }
+
+    fn sender(&self) -> &str {
+        self.from_address.as_deref().unwrap_or(&self.username)
+    }
+}
+
+/// Splits `host:port`, falling back to `default_port`. IPv6 hosts need brackets.
+fn server_address(value: &str, default_port: u16) -> Result<(String, u16)> {
+    let value = value.trim();
+    let (host, port) = match value.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') || host.ends_with(']') => (
+            host,
+            port.parse::<u16>()
+                .map_err(|_| anyhow!("invalid port in mail server {value}"))?,
+        ),
+        _ => (value, default_port),
+    };
+    if host.is_empty() {
+        bail!("mail server is missing a host");
+    }
+    Ok((host.to_string(), port))
+}
+
+trait Transport: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Transport for T {}
+
+/// A line-oriented mail protocol connection, encrypted unless `security = "plain"`.
+struct MailConnection {
+    stream: BufReader<Box<dyn Transport>>,
+}
+
+impl MailConnection {
+    fn open(server: &str, default_port: u16, scheme: &str, security: MailSecurity) -> Result<Self> {
+        let (host, port) = server_address(server, default_port)?;
+        let url = reqwest::Url::parse(&format!("{scheme}://{host}:{port}"))
+            .with_context(|| format!("invalid mail server {server}"))?;
+        let addr = titan_tools::pinned_addr(&url)?;
+        let tcp = TcpStream::connect_timeout(&addr, MAIL_TIMEOUT)
+            .with_context(|| format!("failed to connect to {host}:{port}"))?;
+        tcp.set_read_timeout(Some(MAIL_TIMEOUT))?;
+        tcp.set_write_timeout(Some(MAIL_TIMEOUT))?;
+        let stream: Box<dyn Transport> = match security {
+            MailSecurity::Plain => Box::new(tcp),
+            MailSecurity::Tls => {
+                let roots = rustls::RootCertStore {
+                    roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+                };
+                let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+                    rustls::crypto::ring::default_provider(),
+                ))
+                .with_safe_default_protocol_versions()?
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+                let name = rustls::pki_types::ServerName::try_from(
+                    host.trim_start_matches('[')
+                        .trim_end_matches(']')
+                        .to_string(),
+                )
+                .with_context(|| format!("invalid TLS server name {host}"))?;
+                let session = rustls::ClientConnection::new(Arc::new(config), name)?;
+                Box::new(rustls::StreamOwned::new(session, tcp))
+            }
+        };
+        Ok(Self {
+            stream: BufReader::new(stream),
+        })
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        let stream = self.stream.get_mut();
+        stream.write_all(line.as_bytes())?;
+        stream.write_all(b"\r\n")?;
+        stream.flush()?;
+        Ok(())
+    }
+
+    fn read_line(&mut self) -> Result<String> {
+        let mut line = Vec::new();
+        let read = (&mut self.stream)
+            .take(MAX_LINE_BYTES)
+            .read_until(b'\n', &mut line)?;
+        if read == 0 {
+            bail!("mail server closed the connection");
+        }
+        if !line.ends_with(b"\n") {
+            bail!("mail server sent an overlong line");
+        }
+        Ok(String::from_utf8_lossy(&line)
+            .trim_end_matches(['\r', '\n'])
+            .to_string())
+    }
+}
+
+struct SmtpSession {
+    connection: MailConnection,
+}
+
+impl SmtpSession {
+    fn login(cfg: &EmailConfig, password: &str) -> Result<Self> {
+        let server = cfg
+            .smtp_server
+            .as_deref()
+            .ok_or_else(|| anyhow!("email connector has no smtp_server"))?;
+        let mut session = Self {
+            connection: MailConnection::open(server, 465, "smtps", cfg.security)?,
+        };
+        session.expect(&[220], "greeting")?;
+        session.command("EHLO titan", &[250])?;
+        let credentials = base64::engine::general_purpose::STANDARD
+            .encode(format!("\0{}\0{password}", cfg.username));
+        session
+            .command(&format!("AUTH PLAIN {credentials}"), &[235])
+            .map_err(|_| anyhow!("smtp authentication failed"))?;
+        Ok(session)
+    }
+
+    /// Reads a possibly multi-line reply and fails unless its code is in `accepted`.
+    fn expect(&mut self, accepted: &[u16], step: &str) -> Result<String> {
+        loop {
+            let line = self.connection.read_line()?;
+            let code = line
+                .get(..3)
+                .and_then(|code| code.parse::<u16>().ok())
+                .ok_or_else(|| anyhow!("malformed smtp reply to {step}"))?;
+            if line.as_bytes().get(3) == Some(&b'-') {
+                continue;
+            }
+            if !accepted.contains(&code) {
+                bail!("smtp {step} rejected: {line}");
+            }
+            return Ok(line);
+        }
+    }
+
+    fn command(&mut self, command: &str, accepted: &[u16]) -> Result<String> {
+        self.connection.write_line(command)?;
+        let step = command.split_whitespace().next().unwrap_or(command);
+        self.expect(accepted, step)
+    }
+
+    fn send(&mut self, mail: &OutgoingMail, message: &str) -> Result<String> {
+        self.command(&format!("MAIL FROM:<{}>", mail.from), &[250])?;
+        for recipient in mail.to.iter().chain(&mail.cc) {
+            self.command(&format!("RCPT TO:<{recipient}>"), &[250, 251])?;
+        }
+        self.command("DATA", &[354])?;
+        let stream = self.connection.stream.get_mut();
+        for line in message.split("\r\n") {
+            // Dot-stuffing keeps a body line of "." from ending the message early.
+            if line.starts_with('.') {
+                stream.write_all(b".")?;
+            }
+            stream.write_all(line.as_bytes())?;
+            stream.write_all(b"\r\n")?;
+        }
+        self.command(".", &[250])
+    }
+
+    fn quit(&mut self) {
+        let _ = self.command("QUIT", &[221]);
+    }
+}
+
+/// One untagged IMAP response, with any literals it carried.
+struct ImapResponse {
+    text: String,
+    literals: Vec<Vec<u8>>,
+}
+
+struct ImapSession {
+    connection: MailConnection,
+    next_tag: u32,
+}
+
+impl ImapSession {
+    fn login(cfg: &EmailConfig, password: &str) -> Result<Self> {
+        let server = cfg
+            .imap_server
+            .as_deref()
+            .ok_or_else(|| anyhow!("email connector has no imap_server"))?;
+        let mut session = Self {
+            connection: MailConnection::open(server, 993, "imaps", cfg.security)?,
+            next_tag: 1,
+        };
+        let greeting = session.connection.read_line()?;
+        if !greeting.starts_with("* OK") {
+            bail!("unexpected imap greeting: {greeting}");
+        }
+        session
+            .command(&format!(
+                "LOGIN {} {}",
+                imap_quote(&cfg.username)?,
+                imap_quote(password)?
+            ))
+            .map_err(|_| anyhow!("imap authentication failed"))?;
+        Ok(session)
+    }
+
+    /// Sends a tagged command and collects the untagged responses before its `OK`.
+    fn command(&mut self, command: &str) -> Result<Vec<ImapResponse>> {
+        let tag = format!("t{}", self.next_tag);
+        self.next_tag += 1;
+        self.connection.write_line(&format!("{tag} {command}"))?;
+        let verb = command
+            .split_whitespace()
+            .next()
+            .unwrap_or(command)
+            .to_string();
+        let mut responses = Vec::new();
+        loop {
+            let mut text = self.connection.read_line()?;
+            if let Some(status) = text.strip_prefix(&format!("{tag} ")) {
+                if status.starts_with("OK") {
+                    return Ok(responses);
+                }
+                bail!("imap {verb} failed: {status}");
+            }
+            let mut literals = Vec::new();
+            while let Some(len) = literal_len(&text) {
+                if len > MAX_LITERAL_BYTES {
+                    bail!("imap literal of {len} bytes is too large");
+                }
+                let mut literal = vec![0; len];
+                self.connection.stream.read_exact(&mut literal)?;
+                literals.push(literal);
+                text.push_str(&self.connection.read_line()?);
+            }
+            if text.starts_with("* ") {
+                responses.push(ImapResponse { text, literals });
+            }
+        }
+    }
+
+    fn examine(&mut self, mailbox: &str) -> Result<()> {
+        self.command(&format!("EXAMINE {}", imap_quote(mailbox)?))?;
+        Ok(())
+    }
+
+    /// Matching UIDs, oldest first.
+    fn search(&mut self, criteria: &str) -> Result<Vec<u32>> {
+        let mut uids: Vec<u32> = self
+            .command(&format!("UID SEARCH {criteria}"))?
+            .iter()
+            .filter_map(|response| response.text.strip_prefix("* SEARCH"))
+            .flat_map(|ids| ids.split_whitespace().filter_map(|id| id.parse().ok()))
+            .collect();
+        uids.sort_unstable();
+        Ok(uids)
+    }
+
+    /// Summaries for `uids`, in the order given.
+    fn fetch_headers(&mut self, uids: &[u32]) -> Result<Vec<MailSummary>> {
+        if uids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let set = uids
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        let mut fetched: Vec<MailSummary> = self
+            .command(&format!(
+                "UID FETCH {set} (UID BODY.PEEK[HEADER.FIELDS ({FETCHED_HEADERS})])"
+            ))?
+            .iter()
+            .filter(|response| response.text.contains(" FETCH "))
+            .filter_map(|response| {
+                let uid = response
+                    .text
+                    .split_once("UID ")?
+                    .1
+                    .split(|c: char| !c.is_ascii_digit())
+                    .next()?
+                    .parse()
+                    .ok()?;
+                let headers = response.literals.first()?;
+                Some(MailSummary::parse(uid, &String::from_utf8_lossy(headers)))
+            })
+            .collect();
+        fetched.sort_by_key(|summary| {
+            uids.iter()
+                .position(|uid| *uid == summary.uid)
+                .unwrap_or(usize::MAX)
+        });
+        Ok(fetched)
+    }
+
+    fn logout(&mut self) {
+        let _ = self.command("LOGOUT");
+    }
+}
+
+/// The byte count of a `{N}` literal announced at the end of `line`.
+fn literal_len(line: &str) -> Option<usize> {
+    let open = line.strip_suffix('}')?.rfind('{')?;
+    line[open + 1..line.len() - 1].parse().ok()
+}
+
+/// An IMAP quoted string. Line breaks cannot be quoted and would split the command.
+fn imap_quote(value: &str) -> Result<String> {
+    if value.contains(['\r', '\n']) {
+        bail!("imap strings must not contain line breaks");
+    }
+    Ok(format!(
+        "\"{}\"",
+        value.replace('\\', "\\\\").replace('"', "\\\"")
+    ))
+}
+
+/// `UID SEARCH` criteria from `query` (full text), `from`, `subject`, and `since`
+/// (`YYYY-MM-DD`). At least one is required.
+fn search_criteria(input: &Value) -> Result<String> {
+    let text = |key: &str| {
+        input
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    let mut criteria = Vec::new();
+    for (key, keyword) in [("query", "TEXT"), ("from", "FROM"), ("subject", "SUBJECT")] {
+        if let Some(value) = text(key) {
+            criteria.push(format!("{keyword} {}", imap_quote(value)?));
+        }
+    }
+    if let Some(since) = text("since") {
+        let date = chrono::NaiveDate::parse_from_str(since, "%Y-%m-%d")
+            .map_err(|_| anyhow!("since must be a YYYY-MM-DD date"))?;
+        criteria.push(format!("SINCE {}", date.format("%-d-%b-%Y")));
+    }
+    if criteria.is_empty() {
+        bail!("query, from, subject, or since is required");
+    }
+    Ok(criteria.join(" "))
+}
+
+struct MailSummary {
+    uid: u32,
+    from: String,
+    to: String,
+    subject: String,
+    date: String,
+}
+
+impl MailSummary {
+    /// Reads the fetched header block, unfolding continuation lines.
+    fn parse(uid: u32, headers: &str) -> Self {
+        let mut summary = Self {
+            uid,
+            from: String::new(),
+            to: String::new(),
+            subject: String::new(),
+            date: String::new(),
+        };
+        let mut current: Option<&mut String> = None;
+        for line in headers.split("\r\n").flat_map(|line| line.split('\n')) {
+            if line.starts_with([' ', '\t']) {
+                if let Some(value) = current.as_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+                continue;
+            }
+            let Some((name, value)) = line.split_once(':') else {
+                current = None;
+                continue;
+            };
+            let field = match name.trim().to_ascii_lowercase().as_str() {
+                "from" => &mut summary.from,
+                "to" => &mut summary.to,
+                "subject" => &mut summary.subject,
+                "date" => &mut summary.date,
+                _ => {
+                    current = None;
+                    continue;
+                }
+            };
+            *field = value.trim().to_string();
+            current = Some(field);
+        }
+        summary
+    }
+
+    fn len(&self) -> usize {
+        self.from.len() + self.to.len() + self.subject.len() + self.date.len()
+    }
+
+    fn to_json(&self) -> Value {
+        serde_json::json!({
+            "uid": self.uid,
+            "from": self.from,
+            "to": self.to,
+            "subject": self.subject,
+            "date": self.date,
+        })
+    }
+}
+
+struct OutgoingMail {
+    from: String,
+    to: Vec<String>,
+    cc: Vec<String>,
+    subject: String,
+    body: String,
+    message_id: String,
+}
+
+impl OutgoingMail {
+    fn from_input(input: &Value, cfg: &EmailConfig) -> Result<Self> {
+        let to = recipients(input, "to")?;
+        if to.is_empty() {
+            bail!("to is required");
+        }
+        let subject = input
+            .get("subject")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("subject is required"))?;
+        if subject.contains(['\r', '\n']) {
+            bail!("subject must be a single line");
+        }
+        let from = checked_address(cfg.sender())?;
+        let domain = from
+            .rsplit_once('@')
+            .map_or("titan.local", |(_, domain)| domain);
+        Ok(Self {
+            message_id: format!("<{}@{domain}>", Uuid::new_v4()),
+            from,
+            to,
+            cc: recipients(input, "cc")?,
+            subject: subject.trim().to_string(),
+            body: input
+                .get("body")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+        })
+    }
+
+    /// The RFC 5322 message with CRLF line endings; non-ASCII subjects are encoded words.
+    fn render(&self) -> String {
+        let subject = if self.subject.is_ascii() {
+            self.subject.clone()
+        } else {
+            format!(
+                "=?utf-8?B?{}?=",
+                base64::engine::general_purpose::STANDARD.encode(&self.subject)
+            )
+        };
+        let mut headers = vec![
+            format!("From: {}", self.from),
+            format!("To: {}", self.to.join(", ")),
+        ];
+        if !self.cc.is_empty() {
+            headers.push(format!("Cc: {}", self.cc.join(", ")));
+        }
+        headers.extend([
+            format!("Subject: {subject}"),
+            format!("Date: {}", chrono::Utc::now().to_rfc2822()),
+            format!("Message-ID: {}", self.message_id),
+            "MIME-Version: 1.0".to_string(),
+            "Content-Type: text/plain; charset=utf-8".to_string(),
+            "Content-Transfer-Encoding: 8bit".to_string(),
+        ]);
+        let body = self.body.replace("\r\n", "\n").replace('\n', "\r\n");
+        format!("{}\r\n\r\n{body}", headers.join("\r\n"))
+    }
+}
+
+/// Addresses under `key`, given as an array or a comma-separated string.
+fn recipients(input: &Value, key: &str) -> Result<Vec<String>> {
+    let raw: Vec<&str> = match input.get(key) {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::String(list)) => list.split(',').collect(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .ok_or_else(|| anyhow!("{key} must list address strings"))
+            })
+            .collect::<Result<_>>()?,
+        Some(_) => bail!("{key} must be an address string or array"),
+    };
+    raw.into_iter()
+        .map(str::trim)
+        .filter(|address| !address.is_empty())
+        .map(checked_address)
+        .collect()
+}
+
+/// A bare `local@domain` address with nothing that could break out of an SMTP command or
+/// header.
+fn checked_address(address: &str) -> Result<String> {
+    let address = address.trim();
+    let valid = match address.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.is_empty()
+                && !domain.contains('@')
+                && !address
+                    .chars()
+                    .any(|c| c.is_whitespace() || c.is_control() || "<>,;\"()".contains(c))
+        }
+        None => false,
+    };
+    if !valid {
+        bail!("invalid email address: {address}");
+    }
+    Ok(address.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_search_criteria_and_rejects_injection() {
+        let criteria = search_criteria(&serde_json::json!({
+            "query": "invoice \"Q3\"",
+            "from": "billing@example.com",
+            "since": "2026-03-05",
+        }))
+        .expect("criteria");
+        assert_eq!(
+            criteria,
+            r#"TEXT "invoice \"Q3\"" FROM "billing@example.com" SINCE 5-Mar-2026"#
+        );
+        assert!(search_criteria(&serde_json::json!({"query": "a\r\nt9 DELETE x"})).is_err());
+        assert!(search_criteria(&serde_json::json!({})).is_err());
+        assert!(checked_address("ops@example.com>\r\nRCPT TO:<x@y.z").is_err());
+        assert_eq!(
+            server_address("[::1]:2525", 465).expect("v6"),
+            ("[::1]".to_string(), 2525)
+        );
+        assert_eq!(
+            server_address("smtp.example.com", 465).expect("default port"),
+            ("smtp.example.com".to_string(), 465)
+        );
+        let summary = MailSummary::parse(
+            7,
+            "Subject: Quarterly\r\n report\r\nFrom: a@example.com\r\nX-Other: y\r\n\r\n",
+        );
+        assert_eq!(summary.subject, "Quarterly report");
+        assert_eq!(summary.from, "a@example.com");
+        assert_eq!(literal_len("* 1 FETCH (UID 7 BODY[HEADER] {42}"), Some(42));
+    }
+}
@@ -1,37 +1,169 @@
 use std::collections::BTreeMap;
+use std::sync::{Mutex, RwLock};
+use std::time::Instant;
 
 use anyhow::{Context, Result, anyhow, bail};
+use hmac::{Hmac, Mac};
+use reqwest::StatusCode;
+use reqwest::blocking::RequestBuilder;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use titan_common::AutonomyMode;
+use sha2::{Digest, Sha256};
+use titan_common::{AutonomyMode, now_epoch_ms};
 use titan_core::{Goal, GoalStatus, TraceEvent};
-use titan_memory::{MemoryStore, RiskMode};
+use titan_memory::approval_payload::{
+    ApprovalPayload, decode_approval_payload, encode_approval_payload,
+};
+use titan_memory::{ConnectorCacheStats, ConnectorIoRecord, MemoryStore, RiskMode};
 use titan_secrets::SecretsStore;
-use titan_tools::{CapabilityClass, PolicyEngine, ToolRiskMode};
+use titan_tools::{
+    CapabilityClass, EgressBlocked, PolicyEngine, ToolDescriptor, ToolRegistry, ToolRiskMode,
+};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
+mod email;
+mod webhook;
+
+#[derive(Clone, Copy)]
 pub enum ConnectorType {
     Github,
     GoogleCalendar,
+    Email,
+    Webhook,
+    /// A type another crate added with [`register_connector_factory`].
+    Registered(&'static dyn ConnectorFactory),
 }
 
 impl ConnectorType {
     pub fn as_str(self) -> &'static str {
-        match self {
-            Self::Github => "github",
-            Self::GoogleCalendar => "google_calendar",
-        }
+        self.factory().type_name()
     }
 
     pub fn parse(value: &str) -> Option<Self> {
-        match value.trim().to_ascii_lowercase().as_str() {
+        let value = value.trim().to_ascii_lowercase();
+        Self::parse_builtin(&value).or_else(|| {
+            REGISTERED_CONNECTOR_FACTORIES
+                .read()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .iter()
+                .find(|factory| factory.type_name() == value)
+                .map(|factory| Self::Registered(*factory))
+        })
+    }
+
+    fn parse_builtin(value: &str) -> Option<Self> {
+        match value {
             "github" => Some(Self::Github),
             "google_calendar" | "google-calendar" | "gcal" => Some(Self::GoogleCalendar),
+            "email" => Some(Self::Email),
+            "webhook" => Some(Self::Webhook),
             _ => None,
         }
     }
+
+    /// Built-in types first, then registered ones in registration order.
+    pub fn all() -> Vec<Self> {
+        let registered = REGISTERED_CONNECTOR_FACTORIES
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        [
+            Self::Github,
+            Self::GoogleCalendar,
+            Self::Email,
+            Self::Webhook,
+        ]
+        .into_iter()
+        .chain(registered.iter().map(|factory| Self::Registered(*factory)))
+        .collect()
+    }
+
+    pub fn factory(self) -> &'static dyn ConnectorFactory {
+        match self {
+            Self::Github => &GitHubConnectorFactory,
+            Self::GoogleCalendar => &GoogleCalendarConnectorFactory,
+            Self::Email => &email::EmailConnectorFactory,
+            Self::Webhook => &webhook::WebhookConnectorFactory,
+            Self::Registered(factory) => factory,
+        }
+    }
+}
+
+impl PartialEq for ConnectorType {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for ConnectorType {}
+
+impl std::fmt::Debug for ConnectorType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Github => f.write_str("Github"),
+            Self::GoogleCalendar => f.write_str("GoogleCalendar"),
+            Self::Email => f.write_str("Email"),
+            Self::Webhook => f.write_str("Webhook"),
+            Self::Registered(factory) => f
+                .debug_tuple("Registered")
+                .field(&factory.type_name())
+                .finish(),
+        }
+    }
+}
+
+impl Serialize for ConnectorType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ConnectorType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Self::parse(&value)
+            .ok_or_else(|| serde::de::Error::custom(format!("unsupported connector type: {value}")))
+    }
+}
+
+/// Describes and builds connectors of one type. The built-in types have factories too, so
+/// loading, tool listing, and config checks never match on the type.
+pub trait ConnectorFactory: Send + Sync {
+    /// Stored in the connector row's `connector_type`; lowercase letters, digits, and `_`.
+    fn type_name(&self) -> &'static str;
+    fn tools(&self) -> Vec<ConnectorToolDescriptor>;
+    /// Every field the type's config accepts, in prompt order.
+    fn config_schema(&self) -> &'static [ConnectorConfigField];
+    /// Starting `config_json` for a new connector.
+    fn default_config(&self) -> Value;
+    fn build(&self, id: Uuid, display_name: String) -> Box<dyn Connector>;
+}
+
+static REGISTERED_CONNECTOR_FACTORIES: RwLock<Vec<&'static dyn ConnectorFactory>> =
+    RwLock::new(Vec::new());
+
+/// Makes a connector type available to `ConnectorType::parse` and everything built on it. Fails
+/// when the name is malformed or already taken, built-in aliases included.
+pub fn register_connector_factory(factory: &'static dyn ConnectorFactory) -> Result<()> {
+    let name = factory.type_name();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        bail!("invalid connector type name '{name}'; use lowercase letters, digits, and _");
+    }
+    let mut registered = REGISTERED_CONNECTOR_FACTORIES
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if ConnectorType::parse_builtin(name).is_some()
+        || registered
+            .iter()
+            .any(|existing| existing.type_name() == name)
+    {
+        bail!("connector type already registered: {name}");
+    }
+    registered.push(factory);
+    Ok(())
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -41,6 +173,19 @@ pub struct ConnectorScopes {
     pub net: bool,
 }
 
+impl ConnectorScopes {
+    pub fn labels(&self) -> Vec<&'static str> {
+        [
+            ("read", self.read),
+            ("write", self.write),
+            ("net", self.net),
+        ]
+        .into_iter()
+        .filter_map(|(label, enabled)| enabled.then_some(label))
+        .collect()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectorHealth {
     pub ok: bool,
@@ -53,6 +198,9 @@ pub struct ConnectorToolDescriptor {
     pub description: String,
     pub required_scopes: ConnectorScopes,
     pub risk_class: CapabilityClass,
+    /// How long a result may be reused for the same input; `None` for tools that must always
+    /// reach the API. A connector's `cache_ttl_secs` config overrides it (0 turns caching off).
+    pub cache_ttl_secs: Option<u64>,
 }
 
 #[derive(Debug, Clone)]
@@ -62,10 +210,164 @@ pub struct ConnectorToolResult {
     pub metadata_json: Value,
 }
 
+/// A webhook event kind a connector can turn into a goal.
+#[derive(Debug, Clone)]
+pub struct ConnectorEventKind {
+    pub event: String,
+    pub description: String,
+    /// Goal text used when the subscription does not set its own; `{field}` placeholders are
+    /// filled from the delivery.
+    pub default_template: String,
+}
+
+/// One raw webhook delivery. Header names are lowercase.
+pub struct ConnectorDelivery<'a> {
+    pub headers: &'a BTreeMap<String, String>,
+    pub body: &'a [u8],
+}
+
+#[derive(Debug, Clone)]
+pub struct ConnectorDeliveryEvent {
+    pub event: String,
+    pub delivery_id: Option<String>,
+    pub fields: BTreeMap<String, String>,
+}
+
+/// A subscribed delivery rendered into goal text.
+#[derive(Debug, Clone)]
+pub struct ConnectorEventGoal {
+    pub connector_id: String,
+    pub event: String,
+    pub goal_text: String,
+    pub dedupe_key: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ConnectorDeliveryOutcome {
+    /// The delivery failed authentication and must not be acted on.
+    Unauthorized(String),
+    /// Authentic, but the connector has no subscription for this event.
+    Ignored {
+        event: String,
+    },
+    Goal(ConnectorEventGoal),
+}
+
 pub struct ConnectorContext<'a> {
     pub connector_id: &'a str,
     pub config: &'a Value,
     pub secret_resolver: &'a dyn SecretResolver,
+    /// Collects the call's requests when the connector has its I/O log enabled.
+    pub io_log: Option<&'a ConnectorIoLog>,
+}
+
+impl ConnectorContext<'_> {
+    // Sends `request` and reads the whole body, recording the request in the I/O log.
+    fn send(&self, tool_name: &str, request: RequestBuilder) -> Result<HttpReply> {
+        let (client, request) = request.build_split();
+        let request = request?;
+        let method = request.method().to_string();
+        let path = request.url().path().to_string();
+        let started = Instant::now();
+        let mut status = None;
+        let outcome = client.execute(request).and_then(|response| {
+            status = Some(response.status());
+            response.bytes()
+        });
+        if let Some(log) = self.io_log {
+            log.push(ConnectorIoRecord {
+                id: 0,
+                connector_id: self.connector_id.to_string(),
+                tool_name: tool_name.to_string(),
+                method,
+                path,
+                status: status.map(|status| status.as_u16()),
+                response_bytes: outcome.as_ref().map_or(0, |body| body.len() as u64),
+                latency_ms: started.elapsed().as_millis() as u64,
+                error: outcome.as_ref().err().map(|err| err.to_string()),
+                created_at_ms: now_epoch_ms(),
+            });
+        }
+        let body = outcome.with_context(|| format!("{tool_name} request failed"))?;
+        Ok(HttpReply {
+            status: status.expect("a body implies a response"),
+            body: body.to_vec(),
+        })
+    }
+}
+
+struct HttpReply {
+    status: StatusCode,
+    body: Vec<u8>,
+}
+
+impl HttpReply {
+    fn json(&self) -> Result<Value> {
+        if !self.status.is_success() {
+            bail!("http status {}", self.status);
+        }
+        serde_json::from_slice(&self.body).with_context(|| "invalid json response")
+    }
+}
+
+/// Requests made during one connector call, held until the call ends and then written to the
+/// store. Enabled per connector with an `io_log` object in `config_json`:
+/// `{"enabled": true, "retention_days": 7, "max_entries": 500}`.
+pub struct ConnectorIoLog {
+    settings: IoLogSettings,
+    entries: Mutex<Vec<ConnectorIoRecord>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IoLogSettings {
+    #[serde(default)]
+    enabled: bool,
+    #[serde(default = "default_io_log_retention_days")]
+    retention_days: u64,
+    #[serde(default = "default_io_log_max_entries")]
+    max_entries: usize,
+}
+
+fn default_io_log_retention_days() -> u64 {
+    7
+}
+
+fn default_io_log_max_entries() -> usize {
+    500
+}
+
+impl ConnectorIoLog {
+    /// `None` unless the connector config enables the log.
+    pub fn from_config(config: &Value) -> Result<Option<Self>> {
+        let Some(settings) = config.get("io_log") else {
+            return Ok(None);
+        };
+        let settings: IoLogSettings = serde_json::from_value(settings.clone())
+            .with_context(|| "invalid io_log connector config")?;
+        Ok(settings.enabled.then(|| Self {
+            settings,
+            entries: Mutex::new(Vec::new()),
+        }))
+    }
+
+    fn push(&self, record: ConnectorIoRecord) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(record);
+    }
+
+    /// Writes the collected entries and applies the connector's retention policy.
+    pub fn persist(&self, store: &MemoryStore, connector_id: &str) -> Result<()> {
+        let entries = std::mem::take(&mut *self.entries.lock().unwrap_or_else(|e| e.into_inner()));
+        for entry in &entries {
+            store.add_connector_io(entry)?;
+        }
+        let retention_ms = self.settings.retention_days.saturating_mul(86_400_000);
+        let cutoff = now_epoch_ms().saturating_sub(i64::try_from(retention_ms).unwrap_or(i64::MAX));
+        store.prune_connector_io(connector_id, cutoff, self.settings.max_entries)?;
+        Ok(())
+    }
 }
 
 pub trait SecretResolver {
@@ -134,6 +436,24 @@ pub trait Connector: Send + Sync {
         input: &Value,
         ctx: &ConnectorContext<'_>,
     ) -> Result<ConnectorToolResult>;
+
+    /// Webhook event kinds that subscriptions may select. Empty means no inbound events.
+    fn event_kinds(&self) -> Vec<ConnectorEventKind> {
+        Vec::new()
+    }
+
+    /// Checks that a delivery really came from the remote service.
+    fn verify_delivery(
+        &self,
+        _delivery: &ConnectorDelivery<'_>,
+        _ctx: &ConnectorContext<'_>,
+    ) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn parse_delivery(&self, _delivery: &ConnectorDelivery<'_>) -> Result<ConnectorDeliveryEvent> {
+        bail!("connector does not accept webhook deliveries")
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -145,12 +465,18 @@ pub struct ConnectorActionOutcome {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct ApprovalPayload {
+#[serde(deny_unknown_fields)]
+struct ConnectorToolPayload {
     connector_id: String,
     tool_name: String,
     input: Value,
 }
 
+impl ApprovalPayload for ConnectorToolPayload {
+    const KIND: &'static str = "connector_tool";
+    const VERSION: u32 = 1;
+}
+
 pub fn execute_connector_tool_mediated(
     store: &MemoryStore,
     mode: AutonomyMode,
@@ -173,8 +499,12 @@ pub fn execute_connector_tool_mediated(
     } else {
         ToolRiskMode::Secure
     };
-    let needs_approval =
-        PolicyEngine::requires_approval_with_risk(mode, risk_mode, descriptor.risk_class);
+    let needs_approval = PolicyEngine::requires_approval_for_tool(
+        mode,
+        risk_mode,
+        descriptor.risk_class,
+        Some(&descriptor.name),
+    );
 
     let goal = Goal::new(format!("connector:{}:{}", connector_id, tool_name));
     store.create_goal(&goal)?;
@@ -198,13 +528,13 @@ pub fn execute_connector_tool_mediated(
             Some(&goal.id),
             "connector_tool",
             descriptor.risk_class.as_str(),
-            &serde_json::to_string(&ApprovalPayload {
+            &encode_approval_payload(&ConnectorToolPayload {
                 connector_id: connector_id.to_string(),
                 tool_name: tool_name.to_string(),
                 input,
             })?,
             Some(actor),
-            300_000,
+            PolicyEngine::approval_ttl_ms(descriptor.risk_class.as_str()),
         )?;
         store.update_goal_status(&goal.id, GoalStatus::Planning)?;
         return Ok(ConnectorActionOutcome {
@@ -234,14 +564,46 @@ pub fn execute_connector_tool_mediated(
     })
 }
 
+pub fn connector_approval_payload(
+    connector_id: &str,
+    tool_name: &str,
+    input: Value,
+) -> Result<String> {
+    encode_approval_payload(&ConnectorToolPayload {
+        connector_id: connector_id.to_string(),
+        tool_name: tool_name.to_string(),
+        input,
+    })
+}
+
+// Entry point for planned `connector_tool` steps: same payload as approvals, routed through
+// the mediated path so policy, goals and traces stay identical to direct connector calls.
+pub fn execute_connector_tool_from_payload(
+    store: &MemoryStore,
+    mode: AutonomyMode,
+    actor: &str,
+    payload_json: &str,
+    secret_resolver: &dyn SecretResolver,
+) -> Result<ConnectorActionOutcome> {
+    let payload: ConnectorToolPayload = decode_approval_payload(payload_json)?;
+    execute_connector_tool_mediated(
+        store,
+        mode,
+        actor,
+        &payload.connector_id,
+        &payload.tool_name,
+        payload.input,
+        secret_resolver,
+    )
+}
+
 pub fn execute_connector_tool_after_approval(
     store: &MemoryStore,
     actor: &str,
     payload_json: &str,
     secret_resolver: &dyn SecretResolver,
 ) -> Result<ConnectorActionOutcome> {
-    let payload: ApprovalPayload =
-        serde_json::from_str(payload_json).with_context(|| "invalid connector approval payload")?;
+    let payload: ConnectorToolPayload = decode_approval_payload(payload_json)?;
     let connector = load_connector(store, &payload.connector_id)?;
     let risk = store.get_runtime_risk_state()?;
     let goal = Goal::new(format!(
@@ -269,16 +631,441 @@ pub fn execute_connector_tool_after_approval(
     })
 }
 
+/// Starting `config_json` for a new connector of `connector_type`.
+pub fn default_connector_config(connector_type: ConnectorType) -> Value {
+    connector_type.factory().default_config()
+}
+
+/// What a connector config field holds and how its value is checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFieldKind {
+    /// A non-empty string.
+    Text,
+    /// An `http` or `https` URL.
+    Url,
+    /// An `http` or `https` URL that may hold `{name}` placeholders.
+    UrlTemplate,
+    /// A list of host names; typed comma-separated.
+    Hosts,
+    /// Header name to the environment variable holding its value.
+    SecretHeaders,
+    /// The name of an environment variable.
+    EnvVar,
+    /// A whole number of seconds.
+    Seconds,
+    /// Webhook event kind to goal template, see [`route_connector_delivery`].
+    Subscriptions,
+    /// See [`ConnectorIoLog`].
+    IoLog,
+}
+
+impl ConfigFieldKind {
+    /// Kinds an operator can type at a prompt or as `field=value`.
+    pub fn is_scalar(self) -> bool {
+        matches!(
+            self,
+            Self::Text | Self::Url | Self::UrlTemplate | Self::Hosts | Self::EnvVar | Self::Seconds
+        )
+    }
+}
+
+/// One field a connector type accepts in `config_json`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectorConfigField {
+    pub name: &'static str,
+    pub prompt: &'static str,
+    pub kind: ConfigFieldKind,
+    pub required: bool,
+}
+
+const fn config_field(
+    name: &'static str,
+    prompt: &'static str,
+    kind: ConfigFieldKind,
+    required: bool,
+) -> ConnectorConfigField {
+    ConnectorConfigField {
+        name,
+        prompt,
+        kind,
+        required,
+    }
+}
+
+const GITHUB_CONFIG_SCHEMA: &[ConnectorConfigField] = &[
+    config_field("owner", "GitHub owner", ConfigFieldKind::Text, true),
+    config_field("repo", "GitHub repo", ConfigFieldKind::Text, true),
+    config_field(
+        "base_url",
+        "GitHub API base URL",
+        ConfigFieldKind::Url,
+        false,
+    ),
+    config_field(
+        "cache_ttl_secs",
+        "Read cache TTL in seconds",
+        ConfigFieldKind::Seconds,
+        false,
+    ),
+    config_field(
+        "subscriptions",
+        "Webhook subscriptions",
+        ConfigFieldKind::Subscriptions,
+        false,
+    ),
+    config_field("io_log", "I/O log", ConfigFieldKind::IoLog, false),
+];
+
+const GOOGLE_CALENDAR_CONFIG_SCHEMA: &[ConnectorConfigField] = &[
+    config_field("calendar_id", "Calendar ID", ConfigFieldKind::Text, true),
+    config_field(
+        "base_url",
+        "Google Calendar API base URL",
+        ConfigFieldKind::Url,
+        false,
+    ),
+    config_field(
+        "access_token_env",
+        "Access token env var name",
+        ConfigFieldKind::EnvVar,
+        false,
+    ),
+    config_field(
+        "cache_ttl_secs",
+        "Read cache TTL in seconds",
+        ConfigFieldKind::Seconds,
+        false,
+    ),
+    config_field("io_log", "I/O log", ConfigFieldKind::IoLog, false),
+];
+
+/// Every field `connector_type` accepts, in prompt order.
+pub fn connector_config_schema(connector_type: ConnectorType) -> &'static [ConnectorConfigField] {
+    connector_type.factory().config_schema()
+}
+
+/// A config field that failed validation; `field` is `config` when the value is not an object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigFieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigFieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Parses what an operator typed for a scalar field into its config value.
+pub fn parse_config_field(field: &ConnectorConfigField, raw: &str) -> Result<Value, String> {
+    let raw = raw.trim();
+    let value = match field.kind {
+        ConfigFieldKind::Seconds => match raw.parse::<u64>() {
+            Ok(secs) => Value::from(secs),
+            Err(_) => return Err("must be a whole number of seconds".to_string()),
+        },
+        ConfigFieldKind::Hosts => Value::from(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|host| !host.is_empty())
+                .collect::<Vec<_>>(),
+        ),
+        ConfigFieldKind::Subscriptions
+        | ConfigFieldKind::SecretHeaders
+        | ConfigFieldKind::IoLog => {
+            return Err("is an object; set it in config_json".to_string());
+        }
+        _ => Value::String(raw.to_string()),
+    };
+    match check_config_field(field, &value) {
+        Some(message) => Err(message),
+        None => Ok(value),
+    }
+}
+
+fn check_config_field(field: &ConnectorConfigField, value: &Value) -> Option<String> {
+    let text = || value.as_str().map(str::trim);
+    match field.kind {
+        ConfigFieldKind::Text => match text() {
+            None => Some("must be a string".to_string()),
+            Some("") if field.required => Some("is required".to_string()),
+            Some("") => Some("must not be empty".to_string()),
+            Some(_) => None,
+        },
+        ConfigFieldKind::Url => match text() {
+            None => Some("must be a string".to_string()),
+            Some(url) => match reqwest::Url::parse(url) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => None,
+                Ok(url) => Some(format!("must be an http(s) URL, not {}", url.scheme())),
+                Err(err) => Some(format!("is not a valid URL ({err})")),
+            },
+        },
+        ConfigFieldKind::UrlTemplate => match text() {
+            None => Some("must be a string".to_string()),
+            Some("") => Some("is required".to_string()),
+            Some(template) => {
+                let sample = template
+                    .split('{')
+                    .enumerate()
+                    .map(|(index, part)| match (index, part.split_once('}')) {
+                        (0, _) => Some(part.to_string()),
+                        (_, Some((_, tail))) => Some(format!("x{tail}")),
+                        (_, None) => None,
+                    })
+                    .collect::<Option<String>>();
+                match sample.map(|sample| reqwest::Url::parse(&sample)) {
+                    None => Some("has an unclosed placeholder".to_string()),
+                    Some(Ok(url)) if matches!(url.scheme(), "http" | "https") => None,
+                    Some(Ok(url)) => Some(format!("must be an http(s) URL, not {}", url.scheme())),
+                    Some(Err(err)) => Some(format!("is not a valid URL ({err})")),
+                }
+            }
+        },
+        ConfigFieldKind::Hosts => match value.as_array() {
+            None => Some("must be a list of host names".to_string()),
+            Some(hosts) if hosts.is_empty() && field.required => {
+                Some("must name at least one host".to_string())
+            }
+            Some(hosts) => hosts
+                .iter()
+                .find(|host| {
+                    host.as_str().is_none_or(|host| {
+                        host.is_empty()
+                            || !host
+                                .chars()
+                                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-'))
+                    })
+                })
+                .map(|host| format!("{host} is not a host name")),
+        },
+        ConfigFieldKind::EnvVar => match text() {
+            None => Some("must be a string".to_string()),
+            Some(name) => {
+                (!is_env_var_name(name)).then(|| "must be an environment variable name".to_string())
+            }
+        },
+        ConfigFieldKind::Seconds => value
+            .as_u64()
+            .is_none()
+            .then(|| "must be a whole number of seconds".to_string()),
+        ConfigFieldKind::SecretHeaders => match value.as_object() {
+            None => Some("must be an object of header name to env var name".to_string()),
+            Some(headers) => headers.iter().find_map(|(name, env)| {
+                if name.is_empty()
+                    || reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err()
+                {
+                    Some(format!("{name} is not a header name"))
+                } else if !env.as_str().is_some_and(is_env_var_name) {
+                    Some(format!("{name} must map to an environment variable name"))
+                } else {
+                    None
+                }
+            }),
+        },
+        ConfigFieldKind::Subscriptions => match value.as_object() {
+            None => Some("must be an object of event to template".to_string()),
+            Some(subscriptions) => subscriptions
+                .iter()
+                .find(|(_, template)| !template.is_string())
+                .map(|(event, _)| format!("template for {event} must be a string")),
+        },
+        ConfigFieldKind::IoLog => serde_json::from_value::<IoLogSettings>(value.clone())
+            .err()
+            .map(|err| format!("is invalid ({err})")),
+    }
+}
+
+fn is_env_var_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Field-level problems with `config`: unknown fields, missing required ones, and values of
+/// the wrong shape. Empty when the config is valid.
+pub fn validate_connector_config(
+    connector_type: ConnectorType,
+    config: &Value,
+) -> Vec<ConfigFieldError> {
+    let error = |field: &str, message: String| ConfigFieldError {
+        field: field.to_string(),
+        message,
+    };
+    let Some(object) = config.as_object() else {
+        return vec![error("config", "must be a JSON object".to_string())];
+    };
+    let schema = connector_config_schema(connector_type);
+    let mut errors: Vec<ConfigFieldError> = object
+        .keys()
+        .filter(|key| !schema.iter().any(|field| field.name == key.as_str()))
+        .map(|key| {
+            error(
+                key,
+                format!(
+                    "unknown {} config field; expected one of {}",
+                    connector_type.as_str(),
+                    schema
+                        .iter()
+                        .map(|field| field.name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            )
+        })
+        .collect();
+    for field in schema {
+        match object.get(field.name) {
+            None | Some(Value::Null) if field.required => {
+                errors.push(error(field.name, "is required".to_string()));
+            }
+            None | Some(Value::Null) => {}
+            Some(value) => {
+                if let Some(message) = check_config_field(field, value) {
+                    errors.push(error(field.name, message));
+                }
+            }
+        }
+    }
+    errors
+}
+
+/// [`validate_connector_config`] as an error listing every problem.
+pub fn check_connector_config(connector_type: ConnectorType, config: &Value) -> Result<()> {
+    let errors = validate_connector_config(connector_type, config);
+    if errors.is_empty() {
+        return Ok(());
+    }
+    bail!(
+        "invalid {} connector config: {}",
+        connector_type.as_str(),
+        errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("; ")
+    )
+}
+
+/// The default config with `fields` (`owner=acme`, ...) set over it, then validated. Only
+/// scalar schema fields may be set; secrets stay in the env or the secrets store.
+pub fn connector_config_with_fields(
+    connector_type: ConnectorType,
+    fields: &[(&str, &str)],
+) -> Result<Value> {
+    let mut config = default_connector_config(connector_type);
+    let object = config
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("default connector config is not an object"))?;
+    let schema = connector_config_schema(connector_type);
+    for (key, raw) in fields {
+        let Some(field) = schema
+            .iter()
+            .find(|field| field.name == *key && field.kind.is_scalar())
+        else {
+            let known: Vec<&str> = schema
+                .iter()
+                .filter(|field| field.kind.is_scalar())
+                .map(|field| field.name)
+                .collect();
+            bail!(
+                "unknown {} config field '{key}'; expected one of {}",
+                connector_type.as_str(),
+                known.join(", ")
+            );
+        };
+        let value =
+            parse_config_field(field, raw).map_err(|message| anyhow!("{key}: {message}"))?;
+        object.insert(key.to_string(), value);
+    }
+    check_connector_config(connector_type, &config)?;
+    Ok(config)
+}
+
+/// A connector to add once an operator approves it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConnectorAddPayload {
+    pub connector_type: ConnectorType,
+    pub display_name: String,
+    pub config: Value,
+}
+
+impl ApprovalPayload for ConnectorAddPayload {
+    const KIND: &'static str = "connector_add";
+    const VERSION: u32 = 1;
+}
+
+/// A connector to remove once an operator approves it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConnectorRemovePayload {
+    pub connector_id: String,
+}
+
+impl ApprovalPayload for ConnectorRemovePayload {
+    const KIND: &'static str = "connector_remove";
+    const VERSION: u32 = 1;
+}
+
+/// Validates the config, adds the connector, and returns its new id.
+pub fn add_connector(store: &MemoryStore, payload: &ConnectorAddPayload) -> Result<String> {
+    check_connector_config(payload.connector_type, &payload.config)?;
+    let id = Uuid::new_v4().to_string();
+    store.add_connector(
+        &id,
+        payload.connector_type.as_str(),
+        &payload.display_name,
+        &payload.config.to_string(),
+    )?;
+    Ok(id)
+}
+
+/// Carries out an approved `connector_add` or `connector_remove` and summarizes it as
+/// `connector_added=<id>` or `connector_removed=<bool>`. `None` for other approval kinds.
+pub fn apply_connector_change_approval(
+    store: &MemoryStore,
+    tool_name: &str,
+    input: &str,
+) -> Result<Option<String>> {
+    match tool_name {
+        ConnectorAddPayload::KIND => {
+            let payload: ConnectorAddPayload = decode_approval_payload(input)?;
+            let id = add_connector(store, &payload)?;
+            Ok(Some(format!("connector_added={id}")))
+        }
+        ConnectorRemovePayload::KIND => {
+            let payload: ConnectorRemovePayload = decode_approval_payload(input)?;
+            let removed = store.remove_connector(&payload.connector_id)?;
+            Ok(Some(format!("connector_removed={removed}")))
+        }
+        _ => Ok(None),
+    }
+}
+
 fn execute_connector_tool_now(args: ExecuteNowArgs<'_>) -> Result<()> {
     let config = connector_config_value(args.store, args.connector_id)?;
+    let io_log = ConnectorIoLog::from_config(&config)?;
     let ctx = ConnectorContext {
         connector_id: args.connector_id,
         config: &config,
         secret_resolver: args.secret_resolver,
+        io_log: io_log.as_ref(),
     };
-    let result = args
-        .connector
-        .execute_tool(args.tool_name, &args.input, &ctx)?;
+    let result = execute_tool_cached(&args, &config, &ctx);
+    if let Some(io_log) = &io_log {
+        io_log.persist(args.store, args.connector_id)?;
+    }
+    let result = result.inspect_err(|err| {
+        if let Some(blocked) = err.downcast_ref::<EgressBlocked>() {
+            let _ = args.store.add_trace_event(&TraceEvent::new(
+                args.goal_id.to_string(),
+                "egress_blocked",
+                blocked.trace_detail(args.tool_name),
+            ));
+        }
+    })?;
     args.store
         .update_goal_status(args.goal_id, GoalStatus::Completed)?;
     args.store.record_connector_tool_usage(
@@ -303,6 +1090,94 @@ fn execute_connector_tool_now(args: ExecuteNowArgs<'_>) -> Result<()> {
     Ok(())
 }
 
+/// Default reuse window for idempotent connector reads.
+pub const DEFAULT_CACHE_TTL_SECS: u64 = 60;
+
+/// Tool input flag that skips the cached result and refreshes it from the API.
+pub const CACHE_BYPASS_KEY: &str = "no_cache";
+
+// Serves cacheable reads from the response cache, keyed by connector, tool, and a hash of the
+// input without the bypass flag. The result metadata reports the lookup and the tool's totals.
+fn execute_tool_cached(
+    args: &ExecuteNowArgs<'_>,
+    config: &Value,
+    ctx: &ConnectorContext<'_>,
+) -> Result<ConnectorToolResult> {
+    let ttl_secs = args
+        .connector
+        .tools()
+        .into_iter()
+        .find(|tool| tool.name == args.tool_name)
+        .and_then(|tool| tool.cache_ttl_secs)
+        .map(|ttl| {
+            config
+                .get("cache_ttl_secs")
+                .and_then(Value::as_u64)
+                .unwrap_or(ttl)
+        })
+        .filter(|ttl| *ttl > 0);
+    let mut input = args.input.clone();
+    let bypass = input
+        .as_object_mut()
+        .and_then(|object| object.remove(CACHE_BYPASS_KEY))
+        .and_then(|flag| flag.as_bool())
+        .unwrap_or(false);
+    let Some(ttl_secs) = ttl_secs else {
+        return args.connector.execute_tool(args.tool_name, &input, ctx);
+    };
+    let input_hash = format!("{:x}", Sha256::digest(input.to_string().as_bytes()));
+    let cached = if bypass {
+        None
+    } else {
+        args.store
+            .get_connector_cache(args.connector_id, args.tool_name, &input_hash)?
+    };
+    if let Some(entry) = cached {
+        let stats =
+            args.store
+                .record_connector_cache_lookup(args.connector_id, args.tool_name, true)?;
+        return Ok(ConnectorToolResult {
+            status: "success".to_string(),
+            output_json: serde_json::from_str(&entry.output_json)?,
+            metadata_json: with_cache_metadata(
+                serde_json::from_str(&entry.metadata_json)?,
+                "hit",
+                stats,
+            ),
+        });
+    }
+    let result = args.connector.execute_tool(args.tool_name, &input, ctx)?;
+    args.store.put_connector_cache(
+        args.connector_id,
+        args.tool_name,
+        &input_hash,
+        &result.output_json.to_string(),
+        &result.metadata_json.to_string(),
+        i64::try_from(ttl_secs.saturating_mul(1000)).unwrap_or(i64::MAX),
+    )?;
+    let stats =
+        args.store
+            .record_connector_cache_lookup(args.connector_id, args.tool_name, false)?;
+    Ok(ConnectorToolResult {
+        metadata_json: with_cache_metadata(
+            result.metadata_json,
+            if bypass { "bypass" } else { "miss" },
+            stats,
+        ),
+        ..result
+    })
+}
+
+fn with_cache_metadata(mut metadata: Value, lookup: &str, stats: ConnectorCacheStats) -> Value {
+    if let Some(object) = metadata.as_object_mut() {
+        object.insert(
+            "cache".to_string(),
+            serde_json::json!({"lookup": lookup, "hits": stats.hits, "misses": stats.misses}),
+        );
+    }
+    metadata
+}
+
 struct ExecuteNowArgs<'a> {
     store: &'a MemoryStore,
     connector: &'a dyn Connector,
@@ -321,12 +1196,18 @@ pub fn test_connector(
 ) -> Result<ConnectorHealth> {
     let connector = load_connector(store, connector_id)?;
     let config = connector_config_value(store, connector_id)?;
+    let io_log = ConnectorIoLog::from_config(&config)?;
     let ctx = ConnectorContext {
         connector_id,
         config: &config,
         secret_resolver,
+        io_log: io_log.as_ref(),
     };
-    let health = connector.health_check(&ctx)?;
+    let health = connector.health_check(&ctx);
+    if let Some(io_log) = &io_log {
+        io_log.persist(store, connector_id)?;
+    }
+    let health = health?;
     let status = if health.ok {
         format!("ok: {}", health.detail)
     } else {
@@ -336,11 +1217,105 @@ pub fn test_connector(
     Ok(health)
 }
 
+/// Authenticates a webhook delivery and, when the connector subscribes to its event, renders
+/// the goal text from the subscription template.
+///
+/// Subscriptions live in the connector's `config_json` as a `subscriptions` object mapping an
+/// event kind to a template (an empty template uses the connector's default).
+pub fn route_connector_delivery(
+    store: &MemoryStore,
+    connector_id: &str,
+    delivery: &ConnectorDelivery<'_>,
+    secret_resolver: &dyn SecretResolver,
+) -> Result<ConnectorDeliveryOutcome> {
+    let connector = load_connector(store, connector_id)?;
+    let kinds = connector.event_kinds();
+    if kinds.is_empty() {
+        bail!(
+            "{} connectors do not accept webhook deliveries",
+            connector.connector_type().as_str()
+        );
+    }
+    let config = connector_config_value(store, connector_id)?;
+    let ctx = ConnectorContext {
+        connector_id,
+        config: &config,
+        secret_resolver,
+        io_log: None,
+    };
+    if !connector.verify_delivery(delivery, &ctx)? {
+        return Ok(ConnectorDeliveryOutcome::Unauthorized(
+            "webhook signature mismatch".to_string(),
+        ));
+    }
+    let event = connector.parse_delivery(delivery)?;
+    let Some(template) = config
+        .get("subscriptions")
+        .and_then(|subscriptions| subscriptions.get(&event.event))
+        .and_then(Value::as_str)
+    else {
+        return Ok(ConnectorDeliveryOutcome::Ignored { event: event.event });
+    };
+    let template = if template.trim().is_empty() {
+        kinds
+            .iter()
+            .find(|kind| kind.event == event.event)
+            .map(|kind| kind.default_template.clone())
+            .ok_or_else(|| anyhow!("no default template for event {}", event.event))?
+    } else {
+        template.to_string()
+    };
+    Ok(ConnectorDeliveryOutcome::Goal(ConnectorEventGoal {
+        connector_id: connector_id.to_string(),
+        goal_text: render_event_template(&template, &event.fields),
+        dedupe_key: event
+            .delivery_id
+            .as_ref()
+            .map(|id| format!("connector:{connector_id}:{id}")),
+        event: event.event,
+    }))
+}
+
+fn render_event_template(template: &str, fields: &BTreeMap<String, String>) -> String {
+    fields
+        .iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), value)
+        })
+}
+
 pub fn connector_tools(connector_type: ConnectorType) -> Vec<ConnectorToolDescriptor> {
-    match connector_type {
-        ConnectorType::Github => GitHubConnector::tools_static(),
-        ConnectorType::GoogleCalendar => GoogleCalendarConnector::tools_static(),
+    connector_type.factory().tools()
+}
+
+pub fn connector_tools_for(
+    store: &MemoryStore,
+    connector_id: &str,
+) -> Result<Vec<ConnectorToolDescriptor>> {
+    Ok(load_connector(store, connector_id)?.tools())
+}
+
+pub fn connector_event_kinds(
+    store: &MemoryStore,
+    connector_id: &str,
+) -> Result<Vec<ConnectorEventKind>> {
+    Ok(load_connector(store, connector_id)?.event_kinds())
+}
+
+// Exposes every configured connector's tools to the shared tool registry so planning and
+// policy lookups see connector tools next to the built-ins.
+pub fn register_connector_tools(store: &MemoryStore, registry: &mut ToolRegistry) -> Result<usize> {
+    let mut registered = 0;
+    for row in store.list_connectors()? {
+        let Some(connector_type) = ConnectorType::parse(&row.connector_type) else {
+            continue;
+        };
+        for tool in connector_tools(connector_type) {
+            registry.register(ToolDescriptor::new(tool.name, tool.risk_class));
+            registered += 1;
+        }
     }
+    Ok(registered)
 }
 
 pub fn load_connector(store: &MemoryStore, connector_id: &str) -> Result<Box<dyn Connector>> {
@@ -350,16 +1325,7 @@ pub fn load_connector(store: &MemoryStore, connector_id: &str) -> Result<Box<dyn
     let parsed = ConnectorType::parse(&row.connector_type)
         .ok_or_else(|| anyhow!("unsupported connector type: {}", row.connector_type))?;
     let id = Uuid::parse_str(&row.id).with_context(|| "connector id is not a valid UUID")?;
-    match parsed {
-        ConnectorType::Github => Ok(Box::new(GitHubConnector {
-            id,
-            display_name: row.display_name,
-        })),
-        ConnectorType::GoogleCalendar => Ok(Box::new(GoogleCalendarConnector {
-            id,
-            display_name: row.display_name,
-        })),
-    }
+    Ok(parsed.factory().build(id, row.display_name))
 }
 
 fn connector_config_value(store: &MemoryStore, connector_id: &str) -> Result<Value> {
@@ -378,9 +1344,82 @@ fn sanitize_input_for_trace(input: &Value) -> Value {
                 object.insert(key.to_string(), Value::String("<redacted>".to_string()));
             }
         }
-        Value::Object(object)
+        mask_email_addresses(&Value::Object(object))
     } else {
-        input.clone()
+        mask_email_addresses(input)
+    }
+}
+
+// Keeps the domain of every email address in `value` and hides the mailbox name, so traces
+// show where mail went without naming the people involved.
+fn mask_email_addresses(value: &Value) -> Value {
+    match value {
+        Value::String(text) if text.contains('@') => Value::String(mask_addresses_in(text)),
+        Value::Array(items) => Value::Array(items.iter().map(mask_email_addresses).collect()),
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, value)| (key.clone(), mask_email_addresses(value)))
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
+
+fn mask_addresses_in(text: &str) -> String {
+    let is_local = |c: char| c.is_ascii_alphanumeric() || "._%+-".contains(c);
+    let is_domain = |c: char| c.is_ascii_alphanumeric() || ".-".contains(c);
+    let mut masked = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('@') {
+        let local_start = rest[..at]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_local(*c))
+            .last()
+            .map_or(at, |(index, _)| index);
+        let domain_len = rest[at + 1..]
+            .find(|c: char| !is_domain(c))
+            .unwrap_or(rest.len() - at - 1);
+        let domain = &rest[at + 1..at + 1 + domain_len];
+        if local_start < at && domain.contains('.') {
+            masked.push_str(&rest[..local_start]);
+            masked.push_str("***@");
+            masked.push_str(domain);
+        } else {
+            masked.push_str(&rest[..at + 1 + domain_len]);
+        }
+        rest = &rest[at + 1 + domain_len..];
+    }
+    masked.push_str(rest);
+    masked
+}
+
+struct GitHubConnectorFactory;
+
+impl ConnectorFactory for GitHubConnectorFactory {
+    fn type_name(&self) -> &'static str {
+        "github"
+    }
+
+    fn tools(&self) -> Vec<ConnectorToolDescriptor> {
+        GitHubConnector::tools_static()
+    }
+
+    fn config_schema(&self) -> &'static [ConnectorConfigField] {
+        GITHUB_CONFIG_SCHEMA
+    }
+
+    fn default_config(&self) -> Value {
+        serde_json::json!({
+            "owner": "",
+            "repo": "",
+            "base_url": "https://api.github.com",
+        })
+    }
+
+    fn build(&self, id: Uuid, display_name: String) -> Box<dyn Connector> {
+        Box::new(GitHubConnector { id, display_name })
     }
 }
 
@@ -402,6 +1441,7 @@ impl GitHubConnector {
                     net: true,
                 },
                 risk_class: CapabilityClass::Net,
+                cache_ttl_secs: Some(DEFAULT_CACHE_TTL_SECS),
             },
             ConnectorToolDescriptor {
                 name: "github.list_prs".to_string(),
@@ -412,6 +1452,7 @@ impl GitHubConnector {
                     net: true,
                 },
                 risk_class: CapabilityClass::Net,
+                cache_ttl_secs: Some(DEFAULT_CACHE_TTL_SECS),
             },
             ConnectorToolDescriptor {
                 name: "github.get_issue".to_string(),
@@ -422,6 +1463,7 @@ impl GitHubConnector {
                     net: true,
                 },
                 risk_class: CapabilityClass::Net,
+                cache_ttl_secs: Some(DEFAULT_CACHE_TTL_SECS),
             },
             ConnectorToolDescriptor {
                 name: "github.create_issue".to_string(),
@@ -432,6 +1474,7 @@ impl GitHubConnector {
                     net: true,
                 },
                 risk_class: CapabilityClass::Write,
+                cache_ttl_secs: None,
             },
         ]
     }
@@ -470,16 +1513,14 @@ impl Connector for GitHubConnector {
             "{}/repos/{}/{}/issues?per_page=1",
             cfg.base_url, cfg.owner, cfg.repo
         );
-        let response = reqwest::blocking::Client::new()
+        let request = connector_client(&url)?
             .get(url)
             .header("Authorization", format!("Bearer {token}"))
-            .header("User-Agent", "titan-connectors")
-            .send()
-            .with_context(|| "github health request failed")?;
-        let status = response.status();
+            .header("User-Agent", "titan-connectors");
+        let reply = ctx.send("github.health", request)?;
         Ok(ConnectorHealth {
-            ok: status.is_success(),
-            detail: format!("http_status={}", status.as_u16()),
+            ok: reply.status.is_success(),
+            detail: format!("http_status={}", reply.status.as_u16()),
         })
     }
 
@@ -500,18 +1541,18 @@ impl Connector for GitHubConnector {
             "github_token",
             "GITHUB_TOKEN",
         )?;
-        let client = reqwest::blocking::Client::new();
         let base = format!("{}/repos/{}/{}", cfg.base_url, cfg.owner, cfg.repo);
+        let client = connector_client(&base)?;
         match tool_name {
             "github.list_issues" => {
                 let url = format!("{base}/issues?per_page=20");
-                let response = client
+                let request = client
                     .get(url)
                     .header("Authorization", format!("Bearer {token}"))
-                    .header("User-Agent", "titan-connectors")
-                    .send()?;
-                let status = response.status();
-                let body: Value = response.error_for_status()?.json()?;
+                    .header("User-Agent", "titan-connectors");
+                let reply = ctx.send(tool_name, request)?;
+                let status = reply.status;
+                let body = reply.json()?;
                 Ok(ConnectorToolResult {
                     status: "success".to_string(),
                     output_json: body,
@@ -520,13 +1561,13 @@ impl Connector for GitHubConnector {
             }
             "github.list_prs" => {
                 let url = format!("{base}/pulls?per_page=20");
-                let response = client
+                let request = client
                     .get(url)
                     .header("Authorization", format!("Bearer {token}"))
-                    .header("User-Agent", "titan-connectors")
-                    .send()?;
-                let status = response.status();
-                let body: Value = response.error_for_status()?.json()?;
+                    .header("User-Agent", "titan-connectors");
+                let reply = ctx.send(tool_name, request)?;
+                let status = reply.status;
+                let body = reply.json()?;
                 Ok(ConnectorToolResult {
                     status: "success".to_string(),
                     output_json: body,
@@ -539,13 +1580,13 @@ impl Connector for GitHubConnector {
                     .and_then(Value::as_u64)
                     .ok_or_else(|| anyhow!("number is required"))?;
                 let url = format!("{base}/issues/{number}");
-                let response = client
+                let request = client
                     .get(url)
                     .header("Authorization", format!("Bearer {token}"))
-                    .header("User-Agent", "titan-connectors")
-                    .send()?;
-                let status = response.status();
-                let body: Value = response.error_for_status()?.json()?;
+                    .header("User-Agent", "titan-connectors");
+                let reply = ctx.send(tool_name, request)?;
+                let status = reply.status;
+                let body = reply.json()?;
                 Ok(ConnectorToolResult {
                     status: "success".to_string(),
                     output_json: body,
@@ -562,14 +1603,14 @@ impl Connector for GitHubConnector {
                     .and_then(Value::as_str)
                     .unwrap_or_default();
                 let url = format!("{base}/issues");
-                let response = client
+                let request = client
                     .post(url)
                     .header("Authorization", format!("Bearer {token}"))
                     .header("User-Agent", "titan-connectors")
-                    .json(&serde_json::json!({"title": title, "body": body_text}))
-                    .send()?;
-                let status = response.status();
-                let body: Value = response.error_for_status()?.json()?;
+                    .json(&serde_json::json!({"title": title, "body": body_text}));
+                let reply = ctx.send(tool_name, request)?;
+                let status = reply.status;
+                let body = reply.json()?;
                 Ok(ConnectorToolResult {
                     status: "success".to_string(),
                     output_json: body,
@@ -579,6 +1620,107 @@ impl Connector for GitHubConnector {
             _ => bail!("unsupported github tool: {tool_name}"),
         }
     }
+
+    fn event_kinds(&self) -> Vec<ConnectorEventKind> {
+        vec![
+            ConnectorEventKind {
+                event: "issues.opened".to_string(),
+                description: "An issue was opened".to_string(),
+                default_template: "Triage GitHub issue #{number} in {repo}: {title} ({url})"
+                    .to_string(),
+            },
+            ConnectorEventKind {
+                event: "pull_request.review_requested".to_string(),
+                description: "A review was requested on a pull request".to_string(),
+                default_template:
+                    "Review pull request #{number} in {repo} for {reviewer}: {title} ({url})"
+                        .to_string(),
+            },
+        ]
+    }
+
+    // GitHub signs the raw body with the webhook secret: `X-Hub-Signature-256: sha256=<hex>`.
+    fn verify_delivery(
+        &self,
+        delivery: &ConnectorDelivery<'_>,
+        ctx: &ConnectorContext<'_>,
+    ) -> Result<bool> {
+        let secret = resolve_secret(
+            ctx.secret_resolver,
+            ctx.connector_id,
+            "github_webhook_secret",
+            "GITHUB_WEBHOOK_SECRET",
+        )?;
+        let Some(signature) = delivery
+            .headers
+            .get("x-hub-signature-256")
+            .and_then(|value| value.trim().strip_prefix("sha256="))
+            .and_then(decode_hex)
+        else {
+            return Ok(false);
+        };
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+        mac.update(delivery.body);
+        Ok(mac.verify_slice(&signature).is_ok())
+    }
+
+    fn parse_delivery(&self, delivery: &ConnectorDelivery<'_>) -> Result<ConnectorDeliveryEvent> {
+        let kind = delivery
+            .headers
+            .get("x-github-event")
+            .map(|value| value.trim().to_string())
+            .ok_or_else(|| anyhow!("missing X-GitHub-Event header"))?;
+        let payload: Value =
+            serde_json::from_slice(delivery.body).with_context(|| "invalid github payload")?;
+        let action = payload.get("action").and_then(Value::as_str);
+        let event = match action {
+            Some(action) => format!("{kind}.{action}"),
+            None => kind.clone(),
+        };
+        let subject = payload
+            .get(if kind == "pull_request" {
+                "pull_request"
+            } else {
+                "issue"
+            })
+            .cloned()
+            .unwrap_or(Value::Null);
+        let mut fields = BTreeMap::new();
+        let text = |value: Option<&Value>| value.and_then(Value::as_str).map(str::to_string);
+        let entries = [
+            ("number", subject.get("number").map(|n| n.to_string())),
+            ("title", text(subject.get("title"))),
+            ("url", text(subject.get("html_url"))),
+            ("author", text(subject.pointer("/user/login"))),
+            ("repo", text(payload.pointer("/repository/full_name"))),
+            (
+                "reviewer",
+                text(payload.pointer("/requested_reviewer/login"))
+                    .or_else(|| text(payload.pointer("/requested_team/name"))),
+            ),
+        ];
+        for (name, value) in entries {
+            if let Some(value) = value {
+                fields.insert(name.to_string(), value);
+            }
+        }
+        Ok(ConnectorDeliveryEvent {
+            event,
+            delivery_id: delivery.headers.get("x-github-delivery").cloned(),
+            fields,
+        })
+    }
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if value.len() % 2 != 0 {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
 }
 
 #[derive(Debug, Deserialize)]
@@ -599,6 +1741,34 @@ fn default_github_base() -> String {
     "https://api.github.com".to_string()
 }
 
+struct GoogleCalendarConnectorFactory;
+
+impl ConnectorFactory for GoogleCalendarConnectorFactory {
+    fn type_name(&self) -> &'static str {
+        "google_calendar"
+    }
+
+    fn tools(&self) -> Vec<ConnectorToolDescriptor> {
+        GoogleCalendarConnector::tools_static()
+    }
+
+    fn config_schema(&self) -> &'static [ConnectorConfigField] {
+        GOOGLE_CALENDAR_CONFIG_SCHEMA
+    }
+
+    fn default_config(&self) -> Value {
+        serde_json::json!({
+            "calendar_id": "primary",
+            "base_url": "https://www.googleapis.com/calendar/v3",
+            "access_token_env": "GOOGLE_CALENDAR_TOKEN",
+        })
+    }
+
+    fn build(&self, id: Uuid, display_name: String) -> Box<dyn Connector> {
+        Box::new(GoogleCalendarConnector { id, display_name })
+    }
+}
+
 #[derive(Debug)]
 struct GoogleCalendarConnector {
     id: Uuid,
@@ -617,6 +1787,7 @@ impl GoogleCalendarConnector {
                     net: true,
                 },
                 risk_class: CapabilityClass::Net,
+                cache_ttl_secs: Some(DEFAULT_CACHE_TTL_SECS),
             },
             ConnectorToolDescriptor {
                 name: "gcal.create_event".to_string(),
@@ -627,6 +1798,7 @@ impl GoogleCalendarConnector {
                     net: true,
                 },
                 risk_class: CapabilityClass::Write,
+                cache_ttl_secs: None,
             },
         ]
     }
@@ -691,22 +1863,22 @@ impl Connector for GoogleCalendarConnector {
                 .as_deref()
                 .unwrap_or("GOOGLE_CALENDAR_TOKEN"),
         )?;
-        let client = reqwest::blocking::Client::new();
         let base = cfg
             .base_url
             .unwrap_or_else(|| "https://www.googleapis.com/calendar/v3".to_string());
+        let client = connector_client(&base)?;
         match tool_name {
             "gcal.list_upcoming_events" => {
                 let url = format!(
                     "{}/calendars/{}/events?maxResults=10&singleEvents=true&orderBy=startTime",
                     base, cfg.calendar_id
                 );
-                let response = client
+                let request = client
                     .get(url)
-                    .header("Authorization", format!("Bearer {token}"))
-                    .send()?;
-                let status = response.status();
-                let body: Value = response.error_for_status()?.json()?;
+                    .header("Authorization", format!("Bearer {token}"));
+                let reply = ctx.send(tool_name, request)?;
+                let status = reply.status;
+                let body = reply.json()?;
                 Ok(ConnectorToolResult {
                     status: "success".to_string(),
                     output_json: body,
@@ -765,6 +1937,13 @@ impl GoogleCalendarConfig {
     }
 }
 
+// Resolves the connector host once, refuses private addresses, and pins every request of the
+// call to the checked address.
+fn connector_client(base: &str) -> Result<reqwest::blocking::Client> {
+    let url = reqwest::Url::parse(base).with_context(|| format!("invalid connector URL {base}"))?;
+    titan_tools::pinned_client(&url, std::time::Duration::from_secs(30))
+}
+
 fn resolve_secret(
     resolver: &dyn SecretResolver,
     connector_id: &str,
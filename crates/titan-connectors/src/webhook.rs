@@ -0,0 +1,319 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+use serde_json::Value;
+use titan_tools::CapabilityClass;
+use uuid::Uuid;
+
+use super::{
+    ConfigFieldKind, Connector, ConnectorConfigField, ConnectorContext, ConnectorFactory,
+    ConnectorHealth, ConnectorScopes, ConnectorToolDescriptor, ConnectorToolResult, ConnectorType,
+    config_field, connector_client, resolve_secret,
+};
+
+/// Response text kept in the tool output; longer bodies are cut on a character boundary.
+const MAX_RESPONSE_CHARS: usize = 16 * 1024;
+
+pub(crate) const WEBHOOK_CONFIG_SCHEMA: &[ConnectorConfigField] = &[
+    config_field(
+        "url_template",
+        "Webhook URL ({name} placeholders are filled from params)",
+        ConfigFieldKind::UrlTemplate,
+        true,
+    ),
+    config_field(
+        "allowed_hosts",
+        "Allowed hosts (comma-separated)",
+        ConfigFieldKind::Hosts,
+        true,
+    ),
+    config_field(
+        "headers",
+        "Secret headers",
+        ConfigFieldKind::SecretHeaders,
+        false,
+    ),
+    config_field("io_log", "I/O log", ConfigFieldKind::IoLog, false),
+];
+
+pub(crate) struct WebhookConnectorFactory;
+
+impl ConnectorFactory for WebhookConnectorFactory {
+    fn type_name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn tools(&self) -> Vec<ConnectorToolDescriptor> {
+        WebhookConnector::tools_static()
+    }
+
+    fn config_schema(&self) -> &'static [ConnectorConfigField] {
+        WEBHOOK_CONFIG_SCHEMA
+    }
+
+    fn default_config(&self) -> Value {
+        serde_json::json!({
+            "url_template": "",
+            "allowed_hosts": [],
+        })
+    }
+
+    fn build(&self, id: Uuid, display_name: String) -> Box<dyn Connector> {
+        Box::new(WebhookConnector { id, display_name })
+    }
+}
+
+#[derive(Debug)]
+struct WebhookConnector {
+    id: Uuid,
+    display_name: String,
+}
+
+impl WebhookConnector {
+    fn tools_static() -> Vec<ConnectorToolDescriptor> {
+        vec![ConnectorToolDescriptor {
+            name: "webhook.post".to_string(),
+            description: "POST a JSON body to the configured webhook URL".to_string(),
+            required_scopes: ConnectorScopes {
+                read: false,
+                write: true,
+                net: true,
+            },
+            risk_class: CapabilityClass::Write,
+            cache_ttl_secs: None,
+        }]
+    }
+}
+
+impl Connector for WebhookConnector {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn connector_type(&self) -> ConnectorType {
+        ConnectorType::Webhook
+    }
+
+    fn display_name(&self) -> &str {
+        &self.display_name
+    }
+
+    fn required_scopes(&self) -> ConnectorScopes {
+        ConnectorScopes {
+            read: false,
+            write: true,
+            net: true,
+        }
+    }
+
+    // A POST could trigger the remote action, so the check stops at config and secrets.
+    fn health_check(&self, ctx: &ConnectorContext<'_>) -> Result<ConnectorHealth> {
+        let cfg = WebhookConfig::from_value(ctx.config)?;
+        for header in cfg.headers.keys() {
+            if cfg.header_value(ctx, header).is_err() {
+                return Ok(ConnectorHealth {
+                    ok: false,
+                    detail: format!("missing_header_secret={header}"),
+                });
+            }
+        }
+        Ok(ConnectorHealth {
+            ok: true,
+            detail: format!("configured hosts={}", cfg.allowed_hosts.join(",")),
+        })
+    }
+
+    fn tools(&self) -> Vec<ConnectorToolDescriptor> {
+        Self::tools_static()
+    }
+
+    fn execute_tool(
+        &self,
+        tool_name: &str,
+        input: &Value,
+        ctx: &ConnectorContext<'_>,
+    ) -> Result<ConnectorToolResult> {
+        if tool_name != "webhook.post" {
+            bail!("unsupported webhook tool: {tool_name}");
+        }
+        let cfg = WebhookConfig::from_value(ctx.config)?;
+        let params = match input.get("params") {
+            None | Some(Value::Null) => serde_json::Map::new(),
+            Some(Value::Object(params)) => params.clone(),
+            Some(_) => bail!("params must be an object"),
+        };
+        let url = cfg.render_url(&params)?;
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let body = input
+            .get("body")
+            .cloned()
+            .unwrap_or_else(|| Value::Object(serde_json::Map::new()));
+        let mut request = connector_client(url.as_str())?
+            .post(url.clone())
+            .json(&body);
+        for header in cfg.headers.keys() {
+            request = request.header(header.as_str(), cfg.header_value(ctx, header)?);
+        }
+        let reply = ctx.send(tool_name, request)?;
+        let text = String::from_utf8_lossy(&reply.body);
+        if !reply.status.is_success() {
+            bail!(
+                "webhook returned http status {}: {}",
+                reply.status,
+                truncate_chars(text.trim(), 200)
+            );
+        }
+        let response = serde_json::from_slice::<Value>(&reply.body)
+            .unwrap_or_else(|_| Value::String(truncate_chars(&text, MAX_RESPONSE_CHARS)));
+        Ok(ConnectorToolResult {
+            status: "success".to_string(),
+            output_json: serde_json::json!({
+                "http_status": reply.status.as_u16(),
+                "response": response,
+            }),
+            metadata_json: serde_json::json!({
+                "http_status": reply.status.as_u16(),
+                "host": host,
+                "response_bytes": reply.body.len(),
+            }),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct WebhookConfig {
+    url_template: String,
+    allowed_hosts: Vec<String>,
+    /// Header name to the env var holding its value.
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+}
+
+impl WebhookConfig {
+    fn from_value(value: &Value) -> Result<Self> {
+        let cfg: Self = serde_json::from_value(value.clone())
+            .with_context(|| "invalid webhook connector config")?;
+        if cfg.url_template.trim().is_empty() {
+            bail!("invalid webhook connector config: url_template is required");
+        }
+        if cfg.allowed_hosts.is_empty() {
+            bail!("invalid webhook connector config: allowed_hosts must name at least one host");
+        }
+        Ok(cfg)
+    }
+
+    // The env var named in `headers` wins; otherwise `connector:<id>:header:<name>`.
+    fn header_value(&self, ctx: &ConnectorContext<'_>, header: &str) -> Result<String> {
+        resolve_secret(
+            ctx.secret_resolver,
+            ctx.connector_id,
+            &format!("header:{}", header.to_ascii_lowercase()),
+            &self.headers[header],
+        )
+    }
+
+    /// Fills every `{name}` from `params`, percent-encoded so a value cannot reach past its
+    /// placeholder, then checks the host against `allowed_hosts`.
+    fn render_url(&self, params: &serde_json::Map<String, Value>) -> Result<reqwest::Url> {
+        let mut rendered = String::with_capacity(self.url_template.len());
+        let mut rest = self.url_template.as_str();
+        while let Some(open) = rest.find('{') {
+            let close = rest[open..]
+                .find('}')
+                .map(|offset| open + offset)
+                .ok_or_else(|| anyhow!("url_template has an unclosed placeholder"))?;
+            let name = &rest[open + 1..close];
+            let value = match params.get(name) {
+                Some(Value::String(text)) => text.clone(),
+                Some(value @ (Value::Number(_) | Value::Bool(_))) => value.to_string(),
+                Some(_) => bail!("params.{name} must be a string, number, or boolean"),
+                None => bail!("url_template needs params.{name}"),
+            };
+            rendered.push_str(&rest[..open]);
+            rendered.push_str(&percent_encode(&value));
+            rest = &rest[close + 1..];
+        }
+        rendered.push_str(rest);
+        let url = reqwest::Url::parse(&rendered)
+            .with_context(|| format!("rendered webhook URL is invalid: {rendered}"))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            bail!("webhook URL must be http(s), not {}", url.scheme());
+        }
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        if !self
+            .allowed_hosts
+            .iter()
+            .any(|allowed| allowed.trim().eq_ignore_ascii_case(&host))
+        {
+            bail!("webhook host {host} is not in allowed_hosts");
+        }
+        Ok(url)
+    }
+}
+
+/// Everything but RFC 3986 unreserved characters becomes `%XX`.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_encoded_params_and_enforces_allowed_hosts() {
+        let cfg = WebhookConfig::from_value(&serde_json::json!({
+            "url_template": "https://hooks.example.com/services/{team}/{channel}?v={version}",
+            "allowed_hosts": ["Hooks.Example.com"],
+        }))
+        .expect("config");
+        let params = |value: Value| value.as_object().expect("object").clone();
+        let url = cfg
+            .render_url(&params(serde_json::json!({
+                "team": "ops",
+                "channel": "alerts/../admin?x=1#",
+                "version": 2,
+            })))
+            .expect("url");
+        assert_eq!(
+            url.as_str(),
+            "https://hooks.example.com/services/ops/alerts%2F..%2Fadmin%3Fx%3D1%23?v=2"
+        );
+        assert!(
+            cfg.render_url(&params(serde_json::json!({"team": "ops"})))
+                .is_err()
+        );
+
+        let open_host = WebhookConfig::from_value(&serde_json::json!({
+            "url_template": "https://{host}/hook",
+            "allowed_hosts": ["hooks.example.com"],
+        }))
+        .expect("config");
+        assert!(
+            open_host
+                .render_url(&params(serde_json::json!({"host": "evil.example.net"})))
+                .is_err()
+        );
+        assert!(
+            open_host
+                .render_url(&params(serde_json::json!({"host": "hooks.example.com"})))
+                .is_ok()
+        );
+    }
+}
@@ -1,16 +1,26 @@
 use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread::JoinHandle;
 
+use hmac::{Hmac, Mac};
 use httpmock::Method::{GET, POST};
 use httpmock::MockServer;
 use serde_json::json;
+use sha2::Sha256;
 use tempfile::tempdir;
 use titan_common::AutonomyMode;
 use titan_connectors::{
-    InMemorySecretResolver, SecretResolver, execute_connector_tool_after_approval,
-    execute_connector_tool_mediated,
+    Connector, ConnectorAddPayload, ConnectorConfigField, ConnectorContext, ConnectorDelivery,
+    ConnectorDeliveryOutcome, ConnectorFactory, ConnectorHealth, ConnectorScopes,
+    ConnectorToolDescriptor, ConnectorToolResult, ConnectorType, InMemorySecretResolver,
+    SecretResolver, add_connector, connector_config_with_fields, connector_tools_for,
+    execute_connector_tool_after_approval, execute_connector_tool_mediated,
+    register_connector_factory, route_connector_delivery, validate_connector_config,
 };
 use titan_memory::{MemoryStore, RiskMode};
 use titan_secrets::SecretsStore;
+use titan_tools::CapabilityClass;
 use uuid::Uuid;
 
 struct StoreBackedSecretResolver {
@@ -33,6 +43,12 @@ fn setup_store() -> (tempfile::TempDir, MemoryStore) {
 }
 
 fn add_github_connector(store: &MemoryStore, base_url: &str) -> String {
+    // The mock server listens on loopback, which connectors refuse unless allowed.
+    titan_tools::PolicyEngine::configure_egress(&titan_common::EgressConfig {
+        allow_private: vec!["127.0.0.1".to_string()],
+        ..titan_common::EgressConfig::default()
+    })
+    .expect("egress");
     let id = Uuid::new_v4().to_string();
     let config = json!({
         "owner": "acme",
@@ -143,8 +159,8 @@ fn connector_write_executes_in_yolo_without_approval() {
     });
 
     let (_tmp, store) = setup_store();
-    store.arm_yolo("test").expect("arm yolo");
-    store.enable_yolo("test", 5).expect("enable yolo");
+    store.arm_yolo("test", None).expect("arm yolo");
+    store.enable_yolo("test", 5, None).expect("enable yolo");
     assert!(matches!(
         store
             .get_runtime_risk_state()
@@ -245,3 +261,692 @@ fn connector_write_can_be_finalized_after_approval() {
     assert!(final_outcome.executed);
     assert_eq!(create_issue.hits(), 1);
 }
+
+fn github_signature(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac key");
+    mac.update(body);
+    let hex: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("sha256={hex}")
+}
+
+#[test]
+fn github_webhook_delivery_is_verified_and_rendered_into_goal_text() {
+    let (_tmp, store) = setup_store();
+    let connector_id = Uuid::new_v4().to_string();
+    let config = json!({
+        "owner": "acme",
+        "repo": "titan",
+        "subscriptions": {
+            "issues.opened": "Look at #{number}: {title} by {author}",
+            "pull_request.review_requested": "",
+        },
+    });
+    store
+        .add_connector(&connector_id, "github", "GitHub", &config.to_string())
+        .expect("add connector");
+    let mut secrets = BTreeMap::new();
+    secrets.insert(
+        format!("connector:{connector_id}:github_webhook_secret"),
+        "hook-secret".to_string(),
+    );
+    let resolver = InMemorySecretResolver::new(secrets);
+
+    let deliver = |event: &str, body: &serde_json::Value, secret: &str| {
+        let body = body.to_string();
+        let headers = BTreeMap::from([
+            ("x-github-event".to_string(), event.to_string()),
+            ("x-github-delivery".to_string(), "d-1".to_string()),
+            (
+                "x-hub-signature-256".to_string(),
+                github_signature(secret, body.as_bytes()),
+            ),
+        ]);
+        route_connector_delivery(
+            &store,
+            &connector_id,
+            &ConnectorDelivery {
+                headers: &headers,
+                body: body.as_bytes(),
+            },
+            &resolver,
+        )
+        .expect("route delivery")
+    };
+
+    let issue = json!({
+        "action": "opened",
+        "issue": {"number": 7, "title": "Crash on start", "html_url": "https://x/7", "user": {"login": "ana"}},
+        "repository": {"full_name": "acme/titan"},
+    });
+    match deliver("issues", &issue, "hook-secret") {
+        ConnectorDeliveryOutcome::Goal(goal) => {
+            assert_eq!(goal.event, "issues.opened");
+            assert_eq!(goal.goal_text, "Look at #7: Crash on start by ana");
+            assert_eq!(
+                goal.dedupe_key.as_deref(),
+                Some(format!("connector:{connector_id}:d-1").as_str())
+            );
+        }
+        other => panic!("expected a goal: {other:?}"),
+    }
+    assert!(matches!(
+        deliver("issues", &issue, "wrong-secret"),
+        ConnectorDeliveryOutcome::Unauthorized(_)
+    ));
+
+    let review = json!({
+        "action": "review_requested",
+        "pull_request": {"number": 9, "title": "Add webhooks", "html_url": "https://x/9"},
+        "requested_reviewer": {"login": "bo"},
+        "repository": {"full_name": "acme/titan"},
+    });
+    match deliver("pull_request", &review, "hook-secret") {
+        ConnectorDeliveryOutcome::Goal(goal) => assert_eq!(
+            goal.goal_text,
+            "Review pull request #9 in acme/titan for bo: Add webhooks (https://x/9)"
+        ),
+        other => panic!("expected a goal: {other:?}"),
+    }
+
+    let closed = json!({"action": "closed", "issue": {"number": 7}});
+    match deliver("issues", &closed, "hook-secret") {
+        ConnectorDeliveryOutcome::Ignored { event } => assert_eq!(event, "issues.closed"),
+        other => panic!("unsubscribed events are ignored: {other:?}"),
+    }
+}
+
+#[test]
+fn connector_io_log_records_sanitized_requests_and_prunes() {
+    let server = MockServer::start();
+    let _issues = server.mock(|when, then| {
+        when.method(GET).path("/repos/acme/titan/issues");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body("[]");
+    });
+    let _issue = server.mock(|when, then| {
+        when.method(GET).path("/repos/acme/titan/issues/7");
+        then.status(404).body("missing");
+    });
+
+    let (_tmp, store) = setup_store();
+    let connector_id = add_github_connector(&store, &server.base_url());
+    let config = json!({
+        "owner": "acme",
+        "repo": "titan",
+        "base_url": server.base_url(),
+        "io_log": {"enabled": true, "max_entries": 2},
+    });
+    store
+        .update_connector(&connector_id, "GitHub", &config.to_string())
+        .expect("enable io log");
+    let mut secrets = BTreeMap::new();
+    secrets.insert(
+        format!("connector:{connector_id}:github_token"),
+        "fake-token".to_string(),
+    );
+    let resolver = InMemorySecretResolver::new(secrets);
+    let run = |tool: &str, input: serde_json::Value| {
+        execute_connector_tool_mediated(
+            &store,
+            AutonomyMode::Autonomous,
+            "test",
+            &connector_id,
+            tool,
+            input,
+            &resolver,
+        )
+    };
+
+    run("github.list_issues", json!({})).expect("list issues");
+    assert!(run("github.get_issue", json!({"number": 7})).is_err());
+    let entries = store.list_connector_io(&connector_id, 10).expect("log");
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].path, "/repos/acme/titan/issues/7");
+    assert_eq!(entries[0].status, Some(404));
+    assert_eq!(entries[0].response_bytes, 7);
+    assert_eq!(entries[1].method, "GET");
+    assert_eq!(entries[1].path, "/repos/acme/titan/issues");
+    assert_eq!(entries[1].status, Some(200));
+
+    run("github.list_issues", json!({"no_cache": true})).expect("list issues again");
+    let entries = store.list_connector_io(&connector_id, 10).expect("log");
+    assert_eq!(entries.len(), 2, "max_entries caps the log");
+    assert_eq!(entries[1].path, "/repos/acme/titan/issues/7");
+}
+
+#[test]
+fn connector_reads_are_cached_until_bypassed() {
+    let server = MockServer::start();
+    let issues = server.mock(|when, then| {
+        when.method(GET).path("/repos/acme/titan/issues");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(r#"[{"number": 1}]"#);
+    });
+
+    let (_tmp, store) = setup_store();
+    let connector_id = add_github_connector(&store, &server.base_url());
+    let mut secrets = BTreeMap::new();
+    secrets.insert(
+        format!("connector:{connector_id}:github_token"),
+        "fake-token".to_string(),
+    );
+    let resolver = InMemorySecretResolver::new(secrets);
+    let cache_metadata = |input: serde_json::Value| {
+        let outcome = execute_connector_tool_mediated(
+            &store,
+            AutonomyMode::Autonomous,
+            "test",
+            &connector_id,
+            "github.list_issues",
+            input,
+            &resolver,
+        )
+        .expect("list issues");
+        let traces = store.get_traces(&outcome.goal_id).expect("traces");
+        let result = traces
+            .iter()
+            .find(|trace| trace.event_type == "connector_tool_result")
+            .expect("result trace");
+        let detail: serde_json::Value = serde_json::from_str(&result.detail).expect("detail");
+        detail["metadata"]["cache"].clone()
+    };
+
+    assert_eq!(
+        cache_metadata(json!({})),
+        json!({"lookup": "miss", "hits": 0, "misses": 1})
+    );
+    assert_eq!(
+        cache_metadata(json!({})),
+        json!({"lookup": "hit", "hits": 1, "misses": 1})
+    );
+    assert_eq!(issues.hits(), 1);
+    assert_eq!(
+        cache_metadata(json!({"no_cache": true})),
+        json!({"lookup": "bypass", "hits": 1, "misses": 2})
+    );
+    assert_eq!(issues.hits(), 2);
+}
+
+#[test]
+fn connector_config_is_validated_field_by_field() {
+    let errors: Vec<String> = validate_connector_config(
+        ConnectorType::Github,
+        &json!({
+            "owner": "",
+            "reop": "titan",
+            "base_url": "ftp://example.com",
+            "cache_ttl_secs": "soon",
+            "subscriptions": {"issues.opened": 3},
+        }),
+    )
+    .iter()
+    .map(ToString::to_string)
+    .collect();
+    assert_eq!(
+        errors,
+        vec![
+            "reop: unknown github config field; expected one of owner, repo, base_url, \
+             cache_ttl_secs, subscriptions, io_log",
+            "owner: is required",
+            "repo: is required",
+            "base_url: must be an http(s) URL, not ftp",
+            "cache_ttl_secs: must be a whole number of seconds",
+            "subscriptions: template for issues.opened must be a string",
+        ]
+    );
+    assert!(
+        validate_connector_config(
+            ConnectorType::GoogleCalendar,
+            &json!({"calendar_id": "primary", "access_token_env": "GCAL_TOKEN", "io_log": {"enabled": true}}),
+        )
+        .is_empty()
+    );
+
+    let config = connector_config_with_fields(
+        ConnectorType::Github,
+        &[
+            ("owner", "acme"),
+            ("repo", "titan"),
+            ("cache_ttl_secs", "30"),
+        ],
+    )
+    .expect("valid fields");
+    assert_eq!(config["cache_ttl_secs"], 30);
+    let err = connector_config_with_fields(ConnectorType::Github, &[("owner", "acme")])
+        .expect_err("repo missing");
+    assert_eq!(
+        err.to_string(),
+        "invalid github connector config: repo: is required"
+    );
+
+    let (_tmp, store) = setup_store();
+    let rejected = add_connector(
+        &store,
+        &ConnectorAddPayload {
+            connector_type: ConnectorType::GoogleCalendar,
+            display_name: "Calendar".to_string(),
+            config: json!({"calendar_id": "primary", "access_token_env": "not a var"}),
+        },
+    );
+    assert!(rejected.is_err());
+    assert!(store.list_connectors().expect("connectors").is_empty());
+}
+
+struct EchoConnectorFactory;
+
+impl ConnectorFactory for EchoConnectorFactory {
+    fn type_name(&self) -> &'static str {
+        "echo_test"
+    }
+
+    fn tools(&self) -> Vec<ConnectorToolDescriptor> {
+        vec![ConnectorToolDescriptor {
+            name: "echo.say".to_string(),
+            description: "Echo the input".to_string(),
+            required_scopes: ConnectorScopes {
+                read: true,
+                ..ConnectorScopes::default()
+            },
+            risk_class: CapabilityClass::Read,
+            cache_ttl_secs: None,
+        }]
+    }
+
+    fn config_schema(&self) -> &'static [ConnectorConfigField] {
+        &[]
+    }
+
+    fn default_config(&self) -> serde_json::Value {
+        json!({})
+    }
+
+    fn build(&self, id: Uuid, display_name: String) -> Box<dyn Connector> {
+        Box::new(EchoConnector { id, display_name })
+    }
+}
+
+struct EchoConnector {
+    id: Uuid,
+    display_name: String,
+}
+
+impl Connector for EchoConnector {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn connector_type(&self) -> ConnectorType {
+        ConnectorType::Registered(&EchoConnectorFactory)
+    }
+
+    fn display_name(&self) -> &str {
+        &self.display_name
+    }
+
+    fn required_scopes(&self) -> ConnectorScopes {
+        ConnectorScopes {
+            read: true,
+            ..ConnectorScopes::default()
+        }
+    }
+
+    fn health_check(&self, _ctx: &ConnectorContext<'_>) -> anyhow::Result<ConnectorHealth> {
+        Ok(ConnectorHealth {
+            ok: true,
+            detail: "echo".to_string(),
+        })
+    }
+
+    fn tools(&self) -> Vec<ConnectorToolDescriptor> {
+        EchoConnectorFactory.tools()
+    }
+
+    fn execute_tool(
+        &self,
+        _tool_name: &str,
+        input: &serde_json::Value,
+        _ctx: &ConnectorContext<'_>,
+    ) -> anyhow::Result<ConnectorToolResult> {
+        Ok(ConnectorToolResult {
+            status: "success".to_string(),
+            output_json: input.clone(),
+            metadata_json: json!({}),
+        })
+    }
+}
+
+#[test]
+fn registered_connector_types_load_without_builtin_changes() {
+    let (_tmp, store) = setup_store();
+    assert!(ConnectorType::parse("echo_test").is_none());
+    register_connector_factory(&EchoConnectorFactory).expect("register");
+    let err = register_connector_factory(&EchoConnectorFactory).expect_err("duplicate");
+    assert!(err.to_string().contains("already registered"), "{err}");
+
+    let parsed = ConnectorType::parse("Echo_Test").expect("registered type parses");
+    assert_eq!(parsed.as_str(), "echo_test");
+    assert!(ConnectorType::all().contains(&parsed));
+    let payload: ConnectorAddPayload = serde_json::from_value(json!({
+        "connector_type": "echo_test",
+        "display_name": "Echo",
+        "config": {},
+    }))
+    .expect("payload with a registered type");
+    let id = add_connector(&store, &payload).expect("add");
+
+    let tools = connector_tools_for(&store, &id).expect("tools");
+    assert_eq!(tools.len(), 1);
+    assert_eq!(tools[0].name, "echo.say");
+    assert_eq!(
+        serde_json::to_value(parsed).expect("serialize"),
+        json!("echo_test")
+    );
+}
+
+// A one-connection SMTP server that accepts everything and returns the lines it received.
+fn fake_smtp_server() -> (String, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind smtp");
+    let addr = listener.local_addr().expect("smtp addr").to_string();
+    let handle = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().expect("accept smtp");
+        let mut reader = BufReader::new(stream.try_clone().expect("clone"));
+        let mut writer = stream;
+        writer.write_all(b"220 fake ESMTP\r\n").expect("greeting");
+        let mut received = Vec::new();
+        let mut in_data = false;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).expect("read") == 0 {
+                break;
+            }
+            let line = line.trim_end_matches(['\r', '\n']).to_string();
+            received.push(line.clone());
+            if in_data {
+                if line == "." {
+                    in_data = false;
+                    writer.write_all(b"250 2.0.0 queued\r\n").expect("queued");
+                }
+                continue;
+            }
+            let verb = line
+                .split([' ', ':'])
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            let reply: &[u8] = match verb.as_str() {
+                "EHLO" => b"250-fake\r\n250 AUTH PLAIN\r\n",
+                "AUTH" => b"235 2.7.0 accepted\r\n",
+                "DATA" => {
+                    in_data = true;
+                    b"354 end with .\r\n"
+                }
+                "QUIT" => {
+                    writer.write_all(b"221 bye\r\n").expect("bye");
+                    break;
+                }
+                _ => b"250 ok\r\n",
+            };
+            writer.write_all(reply).expect("reply");
+        }
+        received
+    });
+    (addr, handle)
+}
+
+// A one-connection IMAP server holding UIDs 3, 7, and 9; returns the commands it received.
+fn fake_imap_server() -> (String, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind imap");
+    let addr = listener.local_addr().expect("imap addr").to_string();
+    let handle = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().expect("accept imap");
+        let mut reader = BufReader::new(stream.try_clone().expect("clone"));
+        let mut writer = stream;
+        writer
+            .write_all(b"* OK fake IMAP4rev1\r\n")
+            .expect("greeting");
+        let mut received = Vec::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).expect("read") == 0 {
+                break;
+            }
+            let line = line.trim_end_matches(['\r', '\n']).to_string();
+            received.push(line.clone());
+            let (tag, command) = line.split_once(' ').expect("tagged command");
+            let mut reply = String::new();
+            if command.starts_with("UID SEARCH") {
+                reply.push_str("* SEARCH 3 7 9\r\n");
+            } else if command.starts_with("UID FETCH") {
+                for (seq, uid, subject) in [(3, 9, "Invoice due"), (2, 7, "Re: invoice")] {
+                    let headers = format!(
+                        "From: Billing <billing@example.com>\r\nSubject: {subject}\r\n \
+                         (folded)\r\nDate: Mon, 5 Oct 2026 09:00:00 +0000\r\n\r\n"
+                    );
+                    reply.push_str(&format!(
+                        "* {seq} FETCH (UID {uid} BODY[HEADER.FIELDS (FROM TO SUBJECT DATE)] \
+                         {{{}}}\r\n{headers})\r\n",
+                        headers.len()
+                    ));
+                }
+            }
+            reply.push_str(&format!("{tag} OK done\r\n"));
+            writer.write_all(reply.as_bytes()).expect("reply");
+            if command == "LOGOUT" {
+                break;
+            }
+        }
+        received
+    });
+    (addr, handle)
+}
+
+#[test]
+fn email_connector_sends_after_approval_and_searches_with_masked_traces() {
+    titan_tools::PolicyEngine::configure_egress(&titan_common::EgressConfig {
+        allow_private: vec!["127.0.0.1".to_string()],
+        ..titan_common::EgressConfig::default()
+    })
+    .expect("egress");
+    let (smtp_addr, smtp) = fake_smtp_server();
+    let (imap_addr, imap) = fake_imap_server();
+    let (_tmp, store) = setup_store();
+    let connector_id = add_connector(
+        &store,
+        &ConnectorAddPayload {
+            connector_type: ConnectorType::parse("email").expect("email type"),
+            display_name: "Mail".to_string(),
+            config: json!({
+                "username": "agent@example.com",
+                "smtp_server": smtp_addr,
+                "imap_server": imap_addr,
+                "security": "plain",
+            }),
+        },
+    )
+    .expect("add email connector");
+    let resolver = InMemorySecretResolver::new(BTreeMap::from([(
+        format!("connector:{connector_id}:email_password"),
+        "hunter2".to_string(),
+    )]));
+
+    let queued = execute_connector_tool_mediated(
+        &store,
+        AutonomyMode::Collaborative,
+        "tester",
+        &connector_id,
+        "email.send",
+        json!({
+            "to": ["alice@example.com"],
+            "subject": "Status",
+            "body": "All green.\n.hidden line",
+        }),
+        &resolver,
+    )
+    .expect("queue send");
+    let approval_id = queued.approval_id.expect("send needs approval");
+    let requested = store.get_traces(&queued.goal_id).expect("traces");
+    let detail = &requested
+        .iter()
+        .find(|trace| trace.event_type == "connector_tool_requested")
+        .expect("requested trace")
+        .detail;
+    assert!(detail.contains("***@example.com"));
+    assert!(!detail.contains("alice@"));
+
+    let approval = store
+        .get_approval_request(&approval_id)
+        .expect("approval lookup")
+        .expect("approval row");
+    let sent = execute_connector_tool_after_approval(&store, "tester", &approval.input, &resolver)
+        .expect("send after approval");
+    assert!(sent.executed);
+    let smtp_lines = smtp.join().expect("smtp thread");
+    assert!(smtp_lines.contains(&"MAIL FROM:<agent@example.com>".to_string()));
+    assert!(smtp_lines.contains(&"RCPT TO:<alice@example.com>".to_string()));
+    assert!(smtp_lines.contains(&"Subject: Status".to_string()));
+    assert!(smtp_lines.contains(&"..hidden line".to_string()));
+    assert!(!smtp_lines.iter().any(|line| line.contains("hunter2")));
+
+    let searched = execute_connector_tool_mediated(
+        &store,
+        AutonomyMode::Collaborative,
+        "tester",
+        &connector_id,
+        "email.search",
+        json!({"query": "invoice", "limit": 2}),
+        &resolver,
+    )
+    .expect("search");
+    assert!(searched.executed);
+    let imap_lines = imap.join().expect("imap thread");
+    assert!(
+        imap_lines
+            .iter()
+            .any(|line| line.ends_with("UID SEARCH TEXT \"invoice\""))
+    );
+    assert!(
+        imap_lines
+            .iter()
+            .any(|line| line.contains("UID FETCH 9,7 "))
+    );
+    let result = store
+        .get_traces(&searched.goal_id)
+        .expect("traces")
+        .into_iter()
+        .find(|trace| trace.event_type == "connector_tool_result")
+        .expect("result trace");
+    let metadata: serde_json::Value = serde_json::from_str(&result.detail).expect("json");
+    assert_eq!(metadata["metadata"]["matched"], 3);
+    assert_eq!(metadata["metadata"]["returned"], 2);
+}
+
+#[test]
+fn webhook_connector_posts_to_allowed_hosts_with_secret_headers() {
+    let server = MockServer::start();
+    let hook = server.mock(|when, then| {
+        when.method(POST)
+            .path("/hooks/deploy%20alerts")
+            .header("x-hook-token", "s3cret")
+            .json_body(json!({"text": "build green"}));
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(r#"{"ok": true}"#);
+    });
+    titan_tools::PolicyEngine::configure_egress(&titan_common::EgressConfig {
+        allow_private: vec!["127.0.0.1".to_string()],
+        ..titan_common::EgressConfig::default()
+    })
+    .expect("egress");
+    let (_tmp, store) = setup_store();
+    store.arm_yolo("test", None).expect("arm yolo");
+    store.enable_yolo("test", 5, None).expect("enable yolo");
+
+    let webhook = ConnectorType::parse("webhook").expect("webhook type");
+    let config = connector_config_with_fields(
+        webhook,
+        &[
+            (
+                "url_template",
+                &format!("{}/hooks/{{channel}}", server.base_url()),
+            ),
+            ("allowed_hosts", "127.0.0.1, hooks.example.com"),
+        ],
+    )
+    .expect("config from fields");
+    assert_eq!(
+        config["allowed_hosts"],
+        json!(["127.0.0.1", "hooks.example.com"])
+    );
+    let mut config = config;
+    config["headers"] = json!({"X-Hook-Token": "TITAN_TEST_UNSET_HOOK_TOKEN"});
+    let connector_id = add_connector(
+        &store,
+        &ConnectorAddPayload {
+            connector_type: webhook,
+            display_name: "Deploy hook".to_string(),
+            config,
+        },
+    )
+    .expect("add webhook connector");
+    let tools = connector_tools_for(&store, &connector_id).expect("tools");
+    assert_eq!(tools.len(), 1);
+    assert_eq!(tools[0].name, "webhook.post");
+    assert_eq!(tools[0].risk_class, CapabilityClass::Write);
+
+    let resolver = InMemorySecretResolver::new(BTreeMap::from([(
+        format!("connector:{connector_id}:header:x-hook-token"),
+        "s3cret".to_string(),
+    )]));
+    let outcome = execute_connector_tool_mediated(
+        &store,
+        AutonomyMode::Collaborative,
+        "test",
+        &connector_id,
+        "webhook.post",
+        json!({"params": {"channel": "deploy alerts"}, "body": {"text": "build green"}}),
+        &resolver,
+    )
+    .expect("post webhook");
+    assert!(outcome.executed);
+    assert_eq!(hook.hits(), 1);
+    let traces = store.get_traces(&outcome.goal_id).expect("traces");
+    assert!(traces.iter().all(|trace| !trace.detail.contains("s3cret")));
+    assert!(
+        traces
+            .iter()
+            .any(|trace| trace.event_type == "connector_tool_result"
+                && trace.detail.contains("\"http_status\":200"))
+    );
+
+    assert!(
+        execute_connector_tool_mediated(
+            &store,
+            AutonomyMode::Collaborative,
+            "test",
+            &connector_id,
+            "webhook.post",
+            json!({"body": {"text": "no channel"}}),
+            &resolver,
+        )
+        .is_err()
+    );
+    assert_eq!(hook.hits(), 1);
+
+    let errors = validate_connector_config(
+        webhook,
+        &json!({
+            "url_template": "ftp://{host}/hook",
+            "allowed_hosts": [],
+            "headers": {"Bad Header": "TOKEN"},
+        }),
+    );
+    let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+    assert_eq!(fields, vec!["url_template", "allowed_hosts", "headers"]);
+}
@@ -0,0 +1,94 @@
+use std::fmt;
+
+use regex::Regex;
+
+use crate::StepResult;
+
+/// A `run_if` predicate over the result of an earlier step.
+///
+/// Written as `steps.<id>.output contains <text>`, `steps.<id>.output matches <regex>`, or
+/// `steps.<id>.status is <status>`. A step that has not run satisfies no predicate.
+#[derive(Debug, Clone)]
+pub struct StepCondition {
+    pub step_id: String,
+    pub test: ConditionTest,
+}
+
+#[derive(Debug, Clone)]
+pub enum ConditionTest {
+    OutputContains(String),
+    OutputMatches(Regex),
+    StatusIs(String),
+}
+
+impl StepCondition {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        let invalid = || {
+            format!(
+                "condition must look like 'steps.<id>.output contains|matches <value>' or \
+                 'steps.<id>.status is <status>': {spec}"
+            )
+        };
+        let (subject, rest) = spec.split_once(char::is_whitespace).ok_or_else(invalid)?;
+        let (operator, value) = rest
+            .trim_start()
+            .split_once(char::is_whitespace)
+            .ok_or_else(invalid)?;
+        let value = value.trim();
+        let (step_id, field) = subject
+            .strip_prefix("steps.")
+            .and_then(|path| path.rsplit_once('.'))
+            .ok_or_else(invalid)?;
+        let test = match (field, operator) {
+            ("output", "contains") => ConditionTest::OutputContains(value.to_string()),
+            ("output", "matches") => ConditionTest::OutputMatches(
+                Regex::new(value).map_err(|err| format!("invalid condition regex: {err}"))?,
+            ),
+            ("status", "is") => ConditionTest::StatusIs(value.to_string()),
+            _ => return Err(invalid()),
+        };
+        Ok(Self {
+            step_id: step_id.to_string(),
+            test,
+        })
+    }
+
+    pub fn holds(&self, results: &[StepResult]) -> bool {
+        let Some(result) = results.iter().find(|result| result.step_id == self.step_id) else {
+            return false;
+        };
+        match &self.test {
+            ConditionTest::OutputContains(text) => result.output.contains(text.as_str()),
+            ConditionTest::OutputMatches(regex) => regex.is_match(&result.output),
+            ConditionTest::StatusIs(status) => result.status == *status,
+        }
+    }
+
+    /// Whether this predicate inspects `step_id`'s status, so that step's failure is a branch
+    /// rather than the end of the run.
+    pub fn handles_failure_of(&self, step_id: &str) -> bool {
+        self.step_id == step_id && matches!(self.test, ConditionTest::StatusIs(_))
+    }
+}
+
+impl fmt::Display for StepCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.test {
+            ConditionTest::OutputContains(text) => {
+                write!(f, "steps.{}.output contains {text}", self.step_id)
+            }
+            ConditionTest::OutputMatches(regex) => {
+                write!(
+                    f,
+                    "steps.{}.output matches {}",
+                    self.step_id,
+                    regex.as_str()
+                )
+            }
+            ConditionTest::StatusIs(status) => {
+                write!(f, "steps.{}.status is {status}", self.step_id)
+            }
+        }
+    }
+}
@@ -1,7 +1,16 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+pub mod condition;
+pub mod planner;
+pub mod transform;
+
+pub use condition::StepCondition;
+pub use planner::{HeuristicPlanner, LlmPlanner, ModelClient, Planner};
+pub use transform::OutputTransform;
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum RuntimeState {
     #[default]
@@ -12,8 +21,16 @@ pub enum RuntimeState {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GoalStatus {
     Pending,
+    /// Held before planning until the requester answers a clarification question.
+    AwaitingClarification,
+    /// Held before planning until a concurrency slot frees up.
+    Queued,
     Planning,
     Executing,
+    /// Checkpointed between steps so an urgent goal could run; resumes from the next step.
+    Paused,
+    /// Held in the queue while the model provider is unreachable; requeued once it is back.
+    WaitingForModel,
     Completed,
     Failed,
     Cancelled,
@@ -23,13 +40,61 @@ impl GoalStatus {
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::Pending => "pending",
+            Self::AwaitingClarification => "awaiting_clarification",
+            Self::Queued => "queued",
             Self::Planning => "planning",
             Self::Executing => "executing",
+            Self::Paused => "paused",
+            Self::WaitingForModel => "waiting_for_model",
             Self::Completed => "completed",
             Self::Failed => "failed",
             Self::Cancelled => "cancelled",
         }
     }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(Self::Pending),
+            "awaiting_clarification" => Some(Self::AwaitingClarification),
+            "queued" => Some(Self::Queued),
+            "planning" => Some(Self::Planning),
+            "executing" => Some(Self::Executing),
+            "paused" => Some(Self::Paused),
+            "waiting_for_model" => Some(Self::WaitingForModel),
+            "completed" => Some(Self::Completed),
+            "failed" => Some(Self::Failed),
+            "cancelled" => Some(Self::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+/// Queue order for goals. Urgent goals run first and may preempt lower ones between steps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GoalPriority {
+    Low,
+    #[default]
+    Normal,
+    Urgent,
+}
+
+impl GoalPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::Urgent => "urgent",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "low" => Some(Self::Low),
+            "normal" => Some(Self::Normal),
+            "urgent" => Some(Self::Urgent),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -38,6 +103,7 @@ pub struct Goal {
     pub description: String,
     pub status: GoalStatus,
     pub dedupe_key: Option<String>,
+    pub priority: GoalPriority,
 }
 
 impl Goal {
@@ -47,9 +113,15 @@ impl Goal {
             description: description.into(),
             status: GoalStatus::Pending,
             dedupe_key: None,
+            priority: GoalPriority::Normal,
         }
     }
 
+    pub fn with_priority(mut self, priority: GoalPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
     pub fn with_dedupe_key(mut self, dedupe_key: Option<String>) -> Self {
         self.dedupe_key = dedupe_key.and_then(|v| {
             let trimmed = v.trim().to_string();
@@ -91,6 +163,94 @@ impl TraceEvent {
     }
 }
 
+/// How much of a goal's trace is kept. Approval and security events are always kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TraceVerbosity {
+    /// Lifecycle milestones and failures only.
+    Minimal,
+    /// Everything, with repetitive detail events sampled.
+    #[default]
+    Normal,
+    /// Everything, unsampled.
+    Debug,
+}
+
+/// Detail events of one type beyond this many per goal are sampled at `Normal`.
+pub const TRACE_SAMPLE_BURST: i64 = 20;
+/// After the burst, every Nth detail event of a type is kept.
+pub const TRACE_SAMPLE_EVERY: i64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceClass {
+    /// Approval, policy, and security events; never dropped.
+    Protected,
+    Lifecycle,
+    Detail,
+}
+
+impl TraceClass {
+    pub fn of(event_type: &str) -> Self {
+        const PROTECTED: &[&str] = &[
+            "approval_",
+            "scope_violation",
+            "skill_quarantined",
+            "skill_dangerous_exec",
+            "canary_",
+            "risk_mode",
+            "yolo",
+            "security",
+            "policy",
+        ];
+        if PROTECTED
+            .iter()
+            .any(|prefix| event_type.starts_with(prefix))
+        {
+            return Self::Protected;
+        }
+        if event_type.starts_with("goal_")
+            || event_type.ends_with("_failed")
+            || event_type.ends_with("_timeout")
+            || event_type.ends_with("_started")
+            || event_type.ends_with("_completed")
+            || event_type == "plan_selected"
+        {
+            return Self::Lifecycle;
+        }
+        Self::Detail
+    }
+}
+
+impl TraceVerbosity {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "minimal" => Some(Self::Minimal),
+            "normal" => Some(Self::Normal),
+            "debug" => Some(Self::Debug),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Minimal => "minimal",
+            Self::Normal => "normal",
+            Self::Debug => "debug",
+        }
+    }
+
+    /// Whether the `seen`-th (1-based) event of this type for a goal is stored.
+    pub fn keeps(&self, class: TraceClass, seen: i64) -> bool {
+        match (class, self) {
+            (TraceClass::Protected, _) | (_, Self::Debug) => true,
+            (TraceClass::Lifecycle, _) => true,
+            (TraceClass::Detail, Self::Minimal) => false,
+            (TraceClass::Detail, Self::Normal) => {
+                seen <= TRACE_SAMPLE_BURST || seen % TRACE_SAMPLE_EVERY == 0
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GoalAttemptBehavior {
     Succeed,
@@ -148,6 +308,8 @@ pub struct CoreEvent {
     pub actor_id: String,
     pub text: String,
     pub dedupe_key: Option<String>,
+    /// Facts the requester asked TITAN to remember, as `key: value` lines for the planner.
+    pub context: Vec<String>,
 }
 
 impl CoreEvent {
@@ -161,9 +323,15 @@ impl CoreEvent {
             actor_id: actor_id.into(),
             text: text.into(),
             dedupe_key: None,
+            context: Vec::new(),
         }
     }
 
+    pub fn with_context(mut self, context: Vec<String>) -> Self {
+        self.context = context;
+        self
+    }
+
     pub fn with_dedupe_key(mut self, dedupe_key: Option<String>) -> Self {
         self.dedupe_key = dedupe_key.and_then(|value| {
             let trimmed = value.trim();
@@ -182,12 +350,18 @@ pub enum GoalIntent {
     ScanWorkspace,
     UpdateReadme,
     ReadPath(String),
+    CatalogTool(String),
     GenericRecon,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StepKind {
     ToolCall,
+    /// Post-condition check on an earlier write/exec step; failing it fails the goal.
+    Verify,
+    /// Pauses the run until the requester answers the question in `input`; the answer becomes
+    /// this step's output.
+    AskUser,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -216,6 +390,12 @@ pub struct Step {
     pub permission: StepPermission,
     pub tool_name: String,
     pub input: Option<String>,
+    /// Text a `Verify` step's output must contain to pass.
+    pub expect: Option<String>,
+    /// Applied to the tool output before it is stored or seen by later steps.
+    pub transform: Option<OutputTransform>,
+    /// All must hold for the step to run; otherwise it is recorded as `skipped_condition`.
+    pub run_if: Vec<StepCondition>,
 }
 
 impl Step {
@@ -231,8 +411,56 @@ impl Step {
             permission,
             tool_name: tool_name.into(),
             input,
+            expect: None,
+            transform: None,
+            run_if: Vec::new(),
+        }
+    }
+
+    pub fn with_condition(mut self, condition: StepCondition) -> Self {
+        self.run_if.push(condition);
+        self
+    }
+
+    pub fn ask_user(id: impl Into<String>, question: impl Into<String>) -> Self {
+        Self {
+            kind: StepKind::AskUser,
+            ..Self::new(
+                id,
+                StepPermission::Read,
+                ASK_USER_TOOL,
+                Some(question.into()),
+            )
+        }
+    }
+
+    pub fn with_transform(mut self, transform: OutputTransform) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    pub fn verify(
+        id: impl Into<String>,
+        permission: StepPermission,
+        tool_name: impl Into<String>,
+        input: Option<String>,
+        expect: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind: StepKind::Verify,
+            expect: Some(expect.into()),
+            ..Self::new(id, permission, tool_name, input)
         }
     }
+
+    /// Whether a successful `result` of this verify step satisfies its expectation.
+    pub fn verified_by(&self, result: &StepResult) -> bool {
+        result.status == "success"
+            && self
+                .expect
+                .as_deref()
+                .is_none_or(|expect| result.output.contains(expect))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -240,29 +468,97 @@ pub struct PlanCandidate {
     pub id: String,
     pub rationale: String,
     pub score: f32,
+    /// Model context the steps' inputs and outputs are expected to use; set by scoring.
+    pub estimated_tokens: u64,
     pub steps: Vec<Step>,
 }
 
 #[derive(Debug, Clone)]
 pub struct TaskPipelineConfig {
     pub candidate_count: usize,
+    /// Candidate id to run instead of the best-scored one when the intent offers it.
+    pub preferred_candidate: Option<String>,
+    /// Per-goal cap on `PlanCandidate::estimated_tokens`; candidates above it are dropped.
+    pub token_budget: Option<u64>,
 }
 
 impl Default for TaskPipelineConfig {
     fn default() -> Self {
-        Self { candidate_count: 3 }
+        Self {
+            candidate_count: 3,
+            preferred_candidate: None,
+            token_budget: None,
+        }
     }
 }
 
+/// Tokens a call's output is expected to add to the run's model context, by tool. Connector
+/// and skill dispatches fall back to `DEFAULT_TOOL_TOKEN_COST`.
+pub const TOOL_TOKEN_COSTS: &[(&str, u64)] = &[
+    ("list_dir", 150),
+    ("read_file", 800),
+    ("search_text", 600),
+    ("write_file", 120),
+    ("run_command", 500),
+    ("http_get", 1200),
+];
+pub const DEFAULT_TOOL_TOKEN_COST: u64 = 400;
+
+/// Rough token count for model-bound text, at about four characters per token.
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
+pub fn estimate_step_tokens(step: &Step) -> u64 {
+    let output = TOOL_TOKEN_COSTS
+        .iter()
+        .find(|(tool, _)| *tool == step.tool_name)
+        .map_or(DEFAULT_TOOL_TOKEN_COST, |(_, cost)| *cost);
+    output + step.input.as_deref().map_or(0, estimate_tokens)
+}
+
+// A connector or skill tool the planner may call. `tool_name`/`input` are the mediated
+// dispatch the gateway understands (e.g. `connector_tool` with its approval payload).
+#[derive(Debug, Clone)]
+pub struct ToolCatalogEntry {
+    pub name: String,
+    pub description: String,
+    pub permission: StepPermission,
+    pub tool_name: String,
+    pub input: Option<String>,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ExecutionOptions<'a> {
+    pub canary_writes: bool,
+    /// Asked between steps; returning an urgent goal's id pauses this run before the next step.
+    pub preempt: Option<&'a dyn Fn() -> Option<String>>,
+    /// Asked before each step runs; a hold pauses the run for approval of that step even
+    /// where policy would let it run.
+    pub hold: Option<&'a StepHoldCheck<'a>>,
+}
+
+/// See [`ExecutionOptions::hold`].
+pub type StepHoldCheck<'a> = dyn Fn(&Step) -> Option<StepHold> + 'a;
+
+/// A step kept from running until an operator approves it, recorded as a trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepHold {
+    pub event_type: String,
+    pub detail: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct TaskPlan {
     pub intent: GoalIntent,
     pub candidates: Vec<PlanCandidate>,
     pub selected_index: usize,
     pub traces: Vec<TraceEvent>,
+    /// False when no candidate fits the token budget; executing the plan then fails the goal.
+    pub within_budget: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StepResult {
     pub step_id: String,
     pub tool_name: String,
@@ -285,26 +581,178 @@ pub struct TaskRunResult {
     pub step_results: Vec<StepResult>,
     pub pending_approval: Option<PendingApprovalAction>,
     pub reflection: String,
+    /// Set when a verify step failed after write/exec steps ran; the caller should revert them.
+    pub undo_required: bool,
+    /// Set when an `AskUser` step paused the run; resume it with the answer as that step's result.
+    pub pending_input: Option<PendingInputRequest>,
+    /// The urgent goal this run paused for; `step_results` hold the checkpoint to resume from.
+    pub preempted_by: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PendingInputRequest {
+    pub step_id: String,
+    pub question: String,
+}
+
+/// Lessons from one run, persisted with its episodic memory. `strategy_changes` feed the
+/// procedural strategy table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StructuredReflection {
+    pub summary: String,
+    #[serde(default)]
+    pub worked: Vec<String>,
+    #[serde(default)]
+    pub failed: Vec<String>,
+    #[serde(default)]
+    pub strategy_changes: Vec<String>,
+}
+
+/// Produces a reflection for a finished run, typically by asking the configured model.
+pub trait ReflectionGenerator {
+    fn generate(&self, run: &TaskRunResult) -> Result<StructuredReflection, String>;
+}
+
+/// Reflection derived from the run's step results and traces alone.
+pub fn deterministic_reflection(run: &TaskRunResult) -> StructuredReflection {
+    let selected = &run.plan.candidates[run.plan.selected_index];
+    let worked = run
+        .step_results
+        .iter()
+        .filter(|result| result.status == "success")
+        .map(|result| format!("{}:{} succeeded", result.step_id, result.tool_name))
+        .collect();
+    let mut failed = Vec::new();
+    let mut strategy_changes = Vec::new();
+    for trace in &run.traces {
+        match trace.event_type.as_str() {
+            "execution_failed" => {
+                failed.push(trace.detail.clone());
+                strategy_changes.push(match trace.detail.split_once(": ") {
+                    Some((tool, _)) => {
+                        format!(
+                            "avoid {tool} in {} until its failure is resolved",
+                            selected.id
+                        )
+                    }
+                    // Only the token-budget failure has no `tool: error` shape.
+                    None => format!("prefer a cheaper plan than {}", selected.id),
+                });
+            }
+            "verification_failed" => {
+                failed.push(trace.detail.clone());
+                strategy_changes.push(format!(
+                    "check write inputs in {} against its verify step before running",
+                    selected.id
+                ));
+            }
+            _ => {}
+        }
+    }
+    if run.goal.status == GoalStatus::Completed {
+        strategy_changes.push(format!("reuse {} for similar goals", selected.id));
+    }
+    StructuredReflection {
+        summary: run.reflection.clone(),
+        worked,
+        failed,
+        strategy_changes,
+    }
+}
+
+/// Asks `generator` for a reflection and falls back to [`deterministic_reflection`] when
+/// there is none or it fails. Returns the reflection and which source produced it.
+pub fn reflect_run(
+    run: &TaskRunResult,
+    generator: Option<&dyn ReflectionGenerator>,
+) -> (StructuredReflection, &'static str) {
+    match generator.map(|generator| generator.generate(run)) {
+        Some(Ok(reflection)) => (reflection, "model"),
+        _ => (deterministic_reflection(run), "deterministic"),
+    }
+}
+
+/// The intent the planner will plan for. `GenericRecon` means nothing specific matched.
+pub fn classify_intent(text: &str, catalog: &[ToolCatalogEntry]) -> GoalIntent {
+    match detect_intent(text) {
+        GoalIntent::GenericRecon => match_catalog_tool(text, catalog)
+            .map(|entry| GoalIntent::CatalogTool(entry.name.clone()))
+            .unwrap_or(GoalIntent::GenericRecon),
+        detected => detected,
+    }
 }
 
 pub fn build_task_plan(goal_id: &str, event: &CoreEvent, config: &TaskPipelineConfig) -> TaskPlan {
-    let intent = detect_intent(&event.text);
+    build_task_plan_with_catalog(goal_id, event, config, &[])
+}
+
+pub fn build_task_plan_with_catalog(
+    goal_id: &str,
+    event: &CoreEvent,
+    config: &TaskPipelineConfig,
+    catalog: &[ToolCatalogEntry],
+) -> TaskPlan {
+    build_task_plan_with_planner(goal_id, event, config, catalog, &HeuristicPlanner)
+}
+
+/// Plans with `planner`, falling back to [`HeuristicPlanner`] when it fails.
+pub fn build_task_plan_with_planner(
+    goal_id: &str,
+    event: &CoreEvent,
+    config: &TaskPipelineConfig,
+    catalog: &[ToolCatalogEntry],
+    planner: &dyn Planner,
+) -> TaskPlan {
+    let intent = classify_intent(&event.text, catalog);
     let requested_candidates = config.candidate_count.clamp(2, 5);
-    let mut candidates = match &intent {
-        GoalIntent::ScanWorkspace => workspace_scan_candidates(),
-        GoalIntent::UpdateReadme => update_readme_candidates(),
-        GoalIntent::ReadPath(path) => read_intent_candidates(path),
-        GoalIntent::GenericRecon => generic_recon_candidates(),
+    let mut traces = Vec::new();
+    let (mut candidates, planner_name) = match planner.candidates(event, &intent, catalog) {
+        Ok(candidates) => (candidates, planner.name()),
+        Err(err) => {
+            traces.push(TraceEvent::new(
+                goal_id.to_string(),
+                "planner_failed",
+                format!("{}: {err}", planner.name()),
+            ));
+            let fallback = HeuristicPlanner
+                .candidates(event, &intent, catalog)
+                .unwrap_or_else(|_| generic_recon_candidates());
+            (fallback, HeuristicPlanner.name())
+        }
     };
     score_candidates(&mut candidates);
+    let mut over_budget = Vec::new();
+    let mut within_budget = true;
+    if let Some(budget) = config.token_budget {
+        let (fits, exceeds): (Vec<_>, Vec<_>) = candidates
+            .into_iter()
+            .partition(|candidate| candidate.estimated_tokens <= budget);
+        candidates = fits;
+        over_budget = exceeds;
+        if candidates.is_empty() {
+            // Keep the cheapest so the plan still names what would have run.
+            within_budget = false;
+            over_budget.sort_by_key(|candidate| candidate.estimated_tokens);
+            candidates.push(over_budget.remove(0));
+        }
+    }
+    let preferred = config.preferred_candidate.as_deref().and_then(|preferred| {
+        let position = candidates
+            .iter()
+            .position(|candidate| candidate.id == preferred)?;
+        // Keep the preferred candidate even when it scored outside the requested count.
+        let candidate = candidates.remove(position);
+        let slot = position.min(requested_candidates - 1);
+        candidates.insert(slot, candidate);
+        Some(slot)
+    });
     candidates.truncate(requested_candidates);
-    let selected_index = select_best_candidate_index(&candidates);
-    let mut traces = Vec::new();
+    let selected_index = preferred.unwrap_or_else(|| select_best_candidate_index(&candidates));
     traces.push(TraceEvent::new(
         goal_id.to_string(),
         "planning_started",
         format!(
-            "Built {} plan candidates from event '{}'",
+            "Built {} plan candidates with the {planner_name} planner from event '{}'",
             candidates.len(),
             event.text.trim()
         ),
@@ -314,11 +762,33 @@ pub fn build_task_plan(goal_id: &str, event: &CoreEvent, config: &TaskPipelineCo
             goal_id.to_string(),
             "plan_candidate_generated",
             format!(
-                "{} | score={:.2} | {}",
-                candidate.id, candidate.score, candidate.rationale
+                "{} | score={:.2} | tokens={} | {}",
+                candidate.id, candidate.score, candidate.estimated_tokens, candidate.rationale
             ),
         ));
     }
+    if let Some(budget) = config.token_budget {
+        for candidate in &over_budget {
+            traces.push(TraceEvent::new(
+                goal_id.to_string(),
+                "plan_candidate_over_budget",
+                format!(
+                    "{} | tokens={} | budget={budget}",
+                    candidate.id, candidate.estimated_tokens
+                ),
+            ));
+        }
+    }
+    if let Some(preferred_id) = config.preferred_candidate.as_deref() {
+        traces.push(TraceEvent::new(
+            goal_id.to_string(),
+            "plan_preferred",
+            match preferred {
+                Some(_) => format!("{preferred_id} applied"),
+                None => format!("{preferred_id} not offered for this intent; using best score"),
+            },
+        ));
+    }
     traces.push(TraceEvent::new(
         goal_id.to_string(),
         "plan_selected",
@@ -339,6 +809,7 @@ pub fn build_task_plan(goal_id: &str, event: &CoreEvent, config: &TaskPipelineCo
         candidates,
         selected_index,
         traces,
+        within_budget,
     }
 }
 
@@ -347,18 +818,170 @@ pub fn execute_task_plan_with_broker<FCap, FReq, FExec>(
     plan: TaskPlan,
     permission_for_tool: FCap,
     requires_approval: FReq,
+    execute_tool: FExec,
+) -> TaskRunResult
+where
+    FCap: Fn(&str) -> Option<StepPermission>,
+    FReq: Fn(&str, StepPermission) -> bool,
+    FExec: FnMut(&Step) -> Result<StepResult, String>,
+{
+    execute_task_plan_with_options(
+        goal,
+        plan,
+        ExecutionOptions::default(),
+        permission_for_tool,
+        requires_approval,
+        execute_tool,
+    )
+}
+
+pub fn execute_task_plan_with_options<FCap, FReq, FExec>(
+    goal: Goal,
+    plan: TaskPlan,
+    options: ExecutionOptions<'_>,
+    permission_for_tool: FCap,
+    requires_approval: FReq,
+    execute_tool: FExec,
+) -> TaskRunResult
+where
+    FCap: Fn(&str) -> Option<StepPermission>,
+    FReq: Fn(&str, StepPermission) -> bool,
+    FExec: FnMut(&Step) -> Result<StepResult, String>,
+{
+    execute_task_plan_with_cache(
+        goal,
+        plan,
+        options,
+        &mut RunStepCache::default(),
+        permission_for_tool,
+        requires_approval,
+        execute_tool,
+    )
+}
+
+/// Results of read steps already run, so an identical call later is answered without
+/// running the tool again.
+pub trait StepCache {
+    fn get(&mut self, tool_name: &str, input: Option<&str>) -> Option<StepResult>;
+    fn put(&mut self, input: Option<&str>, result: &StepResult);
+    /// Called after a write or exec step, which may change what reads would return.
+    fn invalidate(&mut self);
+}
+
+/// Memoizes read steps for the length of one run.
+#[derive(Debug, Default)]
+pub struct RunStepCache {
+    results: HashMap<(String, Option<String>), StepResult>,
+}
+
+impl StepCache for RunStepCache {
+    fn get(&mut self, tool_name: &str, input: Option<&str>) -> Option<StepResult> {
+        self.results
+            .get(&(tool_name.to_string(), input.map(str::to_string)))
+            .cloned()
+    }
+
+    fn put(&mut self, input: Option<&str>, result: &StepResult) {
+        self.results.insert(
+            (result.tool_name.clone(), input.map(str::to_string)),
+            result.clone(),
+        );
+    }
+
+    fn invalidate(&mut self) {
+        self.results.clear();
+    }
+}
+
+pub fn execute_task_plan_with_cache<FCap, FReq, FExec>(
+    goal: Goal,
+    plan: TaskPlan,
+    options: ExecutionOptions<'_>,
+    cache: &mut dyn StepCache,
+    permission_for_tool: FCap,
+    requires_approval: FReq,
+    execute_tool: FExec,
+) -> TaskRunResult
+where
+    FCap: Fn(&str) -> Option<StepPermission>,
+    FReq: Fn(&str, StepPermission) -> bool,
+    FExec: FnMut(&Step) -> Result<StepResult, String>,
+{
+    resume_task_plan_with_cache(
+        goal,
+        plan,
+        options,
+        Vec::new(),
+        cache,
+        permission_for_tool,
+        requires_approval,
+        execute_tool,
+    )
+}
+
+/// Continues a plan whose steps in `prior` already have results, e.g. after an `AskUser`
+/// step was answered. Those steps are not run again and the plan traces are not repeated.
+#[allow(clippy::too_many_arguments)]
+pub fn resume_task_plan_with_cache<FCap, FReq, FExec>(
+    goal: Goal,
+    plan: TaskPlan,
+    options: ExecutionOptions<'_>,
+    prior: Vec<StepResult>,
+    cache: &mut dyn StepCache,
+    permission_for_tool: FCap,
+    requires_approval: FReq,
     mut execute_tool: FExec,
 ) -> TaskRunResult
 where
     FCap: Fn(&str) -> Option<StepPermission>,
-    FReq: Fn(StepPermission) -> bool,
+    FReq: Fn(&str, StepPermission) -> bool,
     FExec: FnMut(&Step) -> Result<StepResult, String>,
 {
-    let mut traces = plan.traces.clone();
-    let mut step_results = Vec::new();
+    let mut traces = if prior.is_empty() {
+        plan.traces.clone()
+    } else {
+        Vec::new()
+    };
+    let mut step_results = prior;
+    let resumed_results = step_results.len();
     let mut pending_approval = None;
+    let mut pending_input = None;
+    let mut preempted_by = None;
     let mut outcome_goal = goal;
+    let mut mutated = false;
     let selected = &plan.candidates[plan.selected_index];
+    // Canary only matters when a plan fans out writes across several targets; a single
+    // write is already bounded by the normal approval gate.
+    let canary_active = options.canary_writes && write_targets(&selected.steps).len() > 1;
+
+    if !plan.within_budget {
+        outcome_goal.status = GoalStatus::Failed;
+        traces.push(TraceEvent::new(
+            outcome_goal.id.clone(),
+            "execution_failed",
+            format!(
+                "no plan candidate fits the token budget; cheapest {} needs {} tokens",
+                selected.id, selected.estimated_tokens
+            ),
+        ));
+        let reflection = "Goal exceeded its token budget before execution".to_string();
+        traces.push(TraceEvent::new(
+            outcome_goal.id.clone(),
+            "reflection_recorded",
+            reflection.clone(),
+        ));
+        return TaskRunResult {
+            goal: outcome_goal,
+            traces,
+            plan,
+            step_results,
+            pending_approval,
+            reflection,
+            undo_required: false,
+            pending_input,
+            preempted_by,
+        };
+    }
 
     traces.push(TraceEvent::new(
         outcome_goal.id.clone(),
@@ -367,36 +990,53 @@ where
     ));
 
     for step in &selected.steps {
-        let permission = permission_for_tool(&step.tool_name).unwrap_or(step.permission);
-        if requires_approval(permission) {
-            pending_approval = Some(PendingApprovalAction {
-                tool_name: step.tool_name.clone(),
-                capability: permission.as_str().to_string(),
-                input: step.input.clone(),
-            });
+        if step_results.iter().any(|done| done.step_id == step.id) {
+            continue;
+        }
+        if step_results.len() > resumed_results
+            && let Some(urgent) = options.preempt.and_then(|preempt| preempt())
+        {
             traces.push(TraceEvent::new(
                 outcome_goal.id.clone(),
-                "approval_required",
-                format!(
-                    "{} requires {} approval",
-                    step.tool_name,
-                    permission.as_str()
-                ),
+                "goal_preempted",
+                format!("paused before {} for urgent goal {urgent}", step.id),
             ));
-            outcome_goal.status = GoalStatus::Pending;
+            preempted_by = Some(urgent);
+            outcome_goal.status = GoalStatus::Paused;
             break;
         }
-
-        match execute_tool(step) {
-            Ok(result) => {
-                traces.push(TraceEvent::new(
-                    outcome_goal.id.clone(),
-                    "tool_executed",
-                    format!("{}:{} -> {}", step.id, result.tool_name, result.status),
-                ));
-                step_results.push(result);
+        if let Some(unmet) = step
+            .run_if
+            .iter()
+            .find(|condition| !condition.holds(&step_results))
+        {
+            traces.push(TraceEvent::new(
+                outcome_goal.id.clone(),
+                "skipped_condition",
+                format!("{}:{} unless {}", step.id, step.tool_name, unmet),
+            ));
+            step_results.push(StepResult {
+                step_id: step.id.clone(),
+                tool_name: step.tool_name.clone(),
+                status: "skipped_condition".to_string(),
+                output: String::new(),
+            });
+            continue;
+        }
+        let resolved_step;
+        let step = match step
+            .input
+            .as_deref()
+            .map(|input| resolve_step_references(input, &step_results))
+        {
+            Some(Ok(input)) if step.input.as_deref() != Some(input.as_str()) => {
+                resolved_step = Step {
+                    input: Some(input),
+                    ..step.clone()
+                };
+                &resolved_step
             }
-            Err(err) => {
+            Some(Err(err)) => {
                 outcome_goal.status = GoalStatus::Failed;
                 traces.push(TraceEvent::new(
                     outcome_goal.id.clone(),
@@ -416,27 +1056,272 @@ where
                     step_results,
                     pending_approval,
                     reflection,
+                    undo_required: false,
+                    pending_input,
+                    preempted_by,
                 };
             }
+            _ => step,
+        };
+        if step.kind == StepKind::AskUser {
+            let question = step.input.clone().unwrap_or_default();
+            traces.push(TraceEvent::new(
+                outcome_goal.id.clone(),
+                "input_requested",
+                format!("{}: {}", step.id, question),
+            ));
+            pending_input = Some(PendingInputRequest {
+                step_id: step.id.clone(),
+                question,
+            });
+            outcome_goal.status = GoalStatus::Pending;
+            break;
         }
-    }
-
-    if pending_approval.is_none() && !matches!(outcome_goal.status, GoalStatus::Failed) {
-        outcome_goal.status = GoalStatus::Completed;
-        traces.push(TraceEvent::new(
-            outcome_goal.id.clone(),
-            "execution_completed",
-            format!("{} steps executed", step_results.len()),
-        ));
-    }
-
-    let reflection = if pending_approval.is_some() {
-        "Execution paused awaiting operator approval".to_string()
-    } else {
-        "Execution outcome recorded for future planning".to_string()
-    };
-    traces.push(TraceEvent::new(
-        outcome_goal.id.clone(),
+        let permission = permission_for_tool(&step.tool_name).unwrap_or(step.permission);
+        if let Some(hold) = options.hold.and_then(|hold| hold(step)) {
+            pending_approval = Some(PendingApprovalAction {
+                tool_name: step.tool_name.clone(),
+                capability: permission.as_str().to_string(),
+                input: step.input.clone(),
+            });
+            traces.push(TraceEvent::new(
+                outcome_goal.id.clone(),
+                hold.event_type,
+                hold.detail,
+            ));
+            outcome_goal.status = GoalStatus::Pending;
+            break;
+        }
+        if requires_approval(&step.tool_name, permission) {
+            pending_approval = Some(PendingApprovalAction {
+                tool_name: step.tool_name.clone(),
+                capability: permission.as_str().to_string(),
+                input: step.input.clone(),
+            });
+            traces.push(TraceEvent::new(
+                outcome_goal.id.clone(),
+                "approval_required",
+                format!(
+                    "{} requires {} approval",
+                    step.tool_name,
+                    permission.as_str()
+                ),
+            ));
+            outcome_goal.status = GoalStatus::Pending;
+            break;
+        }
+
+        if step.kind == StepKind::Verify {
+            // Verify steps always run fresh: a cached read would hide the write under test.
+            let outcome = execute_tool(step);
+            if matches!(permission, StepPermission::Write | StepPermission::Exec) {
+                cache.invalidate();
+            }
+            let passed = match outcome {
+                Ok(result) => {
+                    traces.push(TraceEvent::new(
+                        outcome_goal.id.clone(),
+                        "tool_executed",
+                        format!("{}:{} -> {}", step.id, result.tool_name, result.status),
+                    ));
+                    let passed = step.verified_by(&result);
+                    step_results.push(result);
+                    passed
+                }
+                Err(_) => false,
+            };
+            if passed {
+                traces.push(TraceEvent::new(
+                    outcome_goal.id.clone(),
+                    "verification_passed",
+                    format!("{}:{}", step.id, step.tool_name),
+                ));
+                continue;
+            }
+            outcome_goal.status = GoalStatus::Failed;
+            traces.push(TraceEvent::new(
+                outcome_goal.id.clone(),
+                "verification_failed",
+                format!(
+                    "{}:{} expected {:?}",
+                    step.id,
+                    step.tool_name,
+                    step.expect.as_deref().unwrap_or_default()
+                ),
+            ));
+            let reflection = if mutated {
+                "Verification failed; earlier writes are to be undone".to_string()
+            } else {
+                "Verification failed before any write or exec step ran".to_string()
+            };
+            traces.push(TraceEvent::new(
+                outcome_goal.id.clone(),
+                "reflection_recorded",
+                reflection.clone(),
+            ));
+            return TaskRunResult {
+                goal: outcome_goal,
+                traces,
+                plan,
+                step_results,
+                pending_approval,
+                reflection,
+                undo_required: mutated,
+                pending_input,
+                preempted_by,
+            };
+        }
+
+        // Transformed output is specific to the step, so it never stands in for another read.
+        let cacheable = permission == StepPermission::Read && step.transform.is_none();
+        if cacheable && let Some(cached) = cache.get(&step.tool_name, step.input.as_deref()) {
+            traces.push(TraceEvent::new(
+                outcome_goal.id.clone(),
+                "step_cache_hit",
+                format!(
+                    "{}:{} reused {} -> {}",
+                    step.id, step.tool_name, cached.step_id, cached.status
+                ),
+            ));
+            step_results.push(StepResult {
+                step_id: step.id.clone(),
+                ..cached
+            });
+            continue;
+        }
+
+        let mut transformed_from = None;
+        let outcome = execute_tool(step).and_then(|mut result| {
+            if let Some(transform) = &step.transform {
+                transformed_from = Some(result.output.len());
+                result.output = transform
+                    .apply(&result.output)
+                    .map_err(|err| format!("output transform: {err}"))?;
+            }
+            Ok(result)
+        });
+        match outcome {
+            Ok(result) => {
+                if let Some(before) = transformed_from {
+                    traces.push(TraceEvent::new(
+                        outcome_goal.id.clone(),
+                        "output_transformed",
+                        format!("{}: {} -> {} bytes", step.id, before, result.output.len()),
+                    ));
+                }
+                if cacheable {
+                    cache.put(step.input.as_deref(), &result);
+                } else if matches!(permission, StepPermission::Write | StepPermission::Exec) {
+                    cache.invalidate();
+                    mutated = true;
+                }
+                traces.push(TraceEvent::new(
+                    outcome_goal.id.clone(),
+                    "tool_executed",
+                    format!("{}:{} -> {}", step.id, result.tool_name, result.status),
+                ));
+                let canary_output = (canary_active
+                    && permission == StepPermission::Write
+                    && step_results.len() + 1 < selected.steps.len())
+                .then(|| result.output.clone());
+                step_results.push(result);
+                if let Some(output) = canary_output {
+                    pending_approval = Some(PendingApprovalAction {
+                        tool_name: CANARY_CONTINUE_TOOL.to_string(),
+                        capability: StepPermission::Write.as_str().to_string(),
+                        input: Some(output),
+                    });
+                    traces.push(TraceEvent::new(
+                        outcome_goal.id.clone(),
+                        "canary_paused",
+                        format!(
+                            "{} wrote canary target {}; remaining steps await confirmation",
+                            step.id,
+                            write_target(step).unwrap_or_default()
+                        ),
+                    ));
+                    outcome_goal.status = GoalStatus::Pending;
+                    break;
+                }
+            }
+            Err(err)
+                if selected.steps.iter().any(|later| {
+                    later
+                        .run_if
+                        .iter()
+                        .any(|condition| condition.handles_failure_of(&step.id))
+                }) =>
+            {
+                // A later step branches on this status, so the failure is part of the plan.
+                traces.push(TraceEvent::new(
+                    outcome_goal.id.clone(),
+                    "tool_executed",
+                    format!("{}:{} -> failed ({})", step.id, step.tool_name, err),
+                ));
+                step_results.push(StepResult {
+                    step_id: step.id.clone(),
+                    tool_name: step.tool_name.clone(),
+                    status: "failed".to_string(),
+                    output: err,
+                });
+            }
+            Err(err) => {
+                outcome_goal.status = GoalStatus::Failed;
+                traces.push(TraceEvent::new(
+                    outcome_goal.id.clone(),
+                    "execution_failed",
+                    format!("{}: {}", step.tool_name, err),
+                ));
+                let reflection = "Execution failed and was recorded for retry planning".to_string();
+                traces.push(TraceEvent::new(
+                    outcome_goal.id.clone(),
+                    "reflection_recorded",
+                    reflection.clone(),
+                ));
+                return TaskRunResult {
+                    goal: outcome_goal,
+                    traces,
+                    plan,
+                    step_results,
+                    pending_approval,
+                    reflection,
+                    undo_required: false,
+                    pending_input,
+                    preempted_by,
+                };
+            }
+        }
+    }
+
+    if pending_approval.is_none()
+        && pending_input.is_none()
+        && preempted_by.is_none()
+        && !matches!(outcome_goal.status, GoalStatus::Failed)
+    {
+        outcome_goal.status = GoalStatus::Completed;
+        traces.push(TraceEvent::new(
+            outcome_goal.id.clone(),
+            "execution_completed",
+            format!("{} steps executed", step_results.len()),
+        ));
+    }
+
+    let reflection = if let Some(urgent) = &preempted_by {
+        format!("Paused for urgent goal {urgent}; resumes from the next step")
+    } else if let Some(request) = &pending_input {
+        format!("Awaiting input: {}", request.question)
+    } else if pending_approval
+        .as_ref()
+        .is_some_and(|pending| pending.tool_name == CANARY_CONTINUE_TOOL)
+    {
+        "Canary write applied; paused for operator confirmation of remaining targets".to_string()
+    } else if pending_approval.is_some() {
+        "Execution paused awaiting operator approval".to_string()
+    } else {
+        "Execution outcome recorded for future planning".to_string()
+    };
+    traces.push(TraceEvent::new(
+        outcome_goal.id.clone(),
         "reflection_recorded",
         reflection.clone(),
     ));
@@ -448,9 +1333,80 @@ where
         step_results,
         pending_approval,
         reflection,
+        undo_required: false,
+        pending_input,
+        preempted_by,
     }
 }
 
+/// Approval tool name used when a canary write pauses the remaining plan steps.
+pub const CANARY_CONTINUE_TOOL: &str = "canary_continue";
+
+/// Tool name of `AskUser` steps; nothing executes it.
+pub const ASK_USER_TOOL: &str = "ask_user";
+
+/// Returns the target path of a write step (`<path>::<content>` input), if any.
+/// Most bytes of a step's output that a `{{steps.<id>.output}}` reference substitutes.
+pub const STEP_REFERENCE_MAX_BYTES: usize = 4 * 1024;
+
+/// Replaces `{{steps.<id>.output}}` and `{{steps.<id>.status}}` with results of steps that
+/// already ran. Outputs are cut to [`STEP_REFERENCE_MAX_BYTES`]; other `{{...}}` text is kept.
+pub fn resolve_step_references(input: &str, results: &[StepResult]) -> Result<String, String> {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+        let placeholder = &rest[start..start + end + 4];
+        let reference = after[..end].trim();
+        rest = &after[end + 2..];
+        let Some((step_id, field)) = reference
+            .strip_prefix("steps.")
+            .and_then(|path| path.rsplit_once('.'))
+        else {
+            out.push_str(placeholder);
+            continue;
+        };
+        let result = results
+            .iter()
+            .find(|result| result.step_id == step_id)
+            .ok_or_else(|| format!("step reference {{{{{reference}}}}} has no earlier result"))?;
+        match field {
+            "output" => {
+                let mut cut = result.output.len().min(STEP_REFERENCE_MAX_BYTES);
+                while !result.output.is_char_boundary(cut) {
+                    cut -= 1;
+                }
+                out.push_str(&result.output[..cut]);
+            }
+            "status" => out.push_str(&result.status),
+            other => return Err(format!("unknown step reference field '{other}'")),
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+pub fn write_target(step: &Step) -> Option<&str> {
+    if step.permission != StepPermission::Write {
+        return None;
+    }
+    let input = step.input.as_deref()?;
+    let target = input
+        .split_once("::")
+        .map_or(input, |(path, _)| path)
+        .trim();
+    (!target.is_empty()).then_some(target)
+}
+
+fn write_targets(steps: &[Step]) -> HashSet<&str> {
+    steps.iter().filter_map(write_target).collect()
+}
+
 fn normalize_intent(text: &str) -> String {
     text.trim().to_ascii_lowercase()
 }
@@ -472,10 +1428,47 @@ fn detect_intent(text: &str) -> GoalIntent {
     GoalIntent::GenericRecon
 }
 
+// Picks the most specific catalog tool whose name tokens all appear in the request, so
+// "list open github issues" resolves to `github.list_issues`.
+fn match_catalog_tool<'a>(
+    text: &str,
+    catalog: &'a [ToolCatalogEntry],
+) -> Option<&'a ToolCatalogEntry> {
+    let words = intent_tokens(text);
+    catalog
+        .iter()
+        .filter_map(|entry| {
+            let tokens = intent_tokens(&entry.name);
+            (!tokens.is_empty() && tokens.iter().all(|token| words.contains(token)))
+                .then_some((tokens.len(), entry))
+        })
+        .max_by_key(|(specificity, _)| *specificity)
+        .map(|(_, entry)| entry)
+}
+
+fn intent_tokens(text: &str) -> HashSet<String> {
+    text.to_ascii_lowercase()
+        .split(|ch: char| !ch.is_ascii_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(|token| {
+            token
+                .strip_suffix('s')
+                .filter(|stem| stem.len() > 2)
+                .unwrap_or(token)
+                .to_string()
+        })
+        .collect()
+}
+
+/// Score lost per estimated token, so 1000 tokens weigh about as much as one extra step.
+const TOKEN_COST_WEIGHT: f32 = 0.000_05;
+
 fn score_candidates(candidates: &mut [PlanCandidate]) {
     for candidate in candidates {
+        candidate.estimated_tokens = candidate.steps.iter().map(estimate_step_tokens).sum();
         let mut risk = 0.0_f32;
-        let mut cost = candidate.steps.len() as f32 * 0.05;
+        let mut cost = candidate.steps.len() as f32 * 0.05
+            + candidate.estimated_tokens as f32 * TOKEN_COST_WEIGHT;
         let mut confidence = 0.80_f32;
         for step in &candidate.steps {
             match step.permission {
@@ -499,6 +1492,10 @@ fn score_candidates(candidates: &mut [PlanCandidate]) {
             if step.tool_name == "search_text" {
                 cost += 0.03;
             }
+            if step.kind == StepKind::AskUser {
+                // A human round trip is slower than any tool call.
+                cost += 0.10;
+            }
         }
         candidate.score = (confidence - risk - cost).clamp(-1.0, 1.0);
     }
@@ -522,6 +1519,7 @@ fn workspace_scan_candidates() -> Vec<PlanCandidate> {
             id: "cand_scan_read_1".to_string(),
             rationale: "Low-risk workspace scan with read-only tools".to_string(),
             score: 0.0,
+            estimated_tokens: 0,
             steps: vec![
                 Step::new(
                     "scan-1",
@@ -547,6 +1545,7 @@ fn workspace_scan_candidates() -> Vec<PlanCandidate> {
             id: "cand_scan_read_2".to_string(),
             rationale: "Prioritize source tree indexing before content sampling".to_string(),
             score: 0.0,
+            estimated_tokens: 0,
             steps: vec![
                 Step::new(
                     "scan-src-1",
@@ -572,6 +1571,7 @@ fn workspace_scan_candidates() -> Vec<PlanCandidate> {
             id: "cand_scan_read_3".to_string(),
             rationale: "Wide read-only inspection for common config markers".to_string(),
             score: 0.0,
+            estimated_tokens: 0,
             steps: vec![
                 Step::new(
                     "scan-wide-1",
@@ -597,6 +1597,7 @@ fn workspace_scan_candidates() -> Vec<PlanCandidate> {
             id: "cand_scan_read_4".to_string(),
             rationale: "Focused read of README and docs metadata".to_string(),
             score: 0.0,
+            estimated_tokens: 0,
             steps: vec![
                 Step::new(
                     "scan-doc-1",
@@ -616,6 +1617,7 @@ fn workspace_scan_candidates() -> Vec<PlanCandidate> {
             id: "cand_scan_read_5".to_string(),
             rationale: "Trace recent runtime context through memory artifacts".to_string(),
             score: 0.0,
+            estimated_tokens: 0,
             steps: vec![
                 Step::new(
                     "scan-trace-1",
@@ -640,6 +1642,7 @@ fn update_readme_candidates() -> Vec<PlanCandidate> {
             id: "cand_update_readme_1".to_string(),
             rationale: "Read current README then apply a deterministic append".to_string(),
             score: 0.0,
+            estimated_tokens: 0,
             steps: vec![
                 Step::new(
                     "readme-1",
@@ -656,12 +1659,20 @@ fn update_readme_candidates() -> Vec<PlanCandidate> {
                             .to_string(),
                     ),
                 ),
+                Step::verify(
+                    "readme-3",
+                    StepPermission::Read,
+                    "read_file",
+                    Some("README.md".to_string()),
+                    "## Install Steps (Generated)",
+                ),
             ],
         },
         PlanCandidate {
             id: "cand_update_readme_2".to_string(),
             rationale: "Verify workspace then update README".to_string(),
             score: 0.0,
+            estimated_tokens: 0,
             steps: vec![
                 Step::new("readme-alt-1", StepPermission::Read, "list_dir", Some(".".to_string())),
                 Step::new(
@@ -673,6 +1684,41 @@ fn update_readme_candidates() -> Vec<PlanCandidate> {
                             .to_string(),
                     ),
                 ),
+                Step::verify(
+                    "readme-alt-3",
+                    StepPermission::Read,
+                    "read_file",
+                    Some("README.md".to_string()),
+                    "## Install Steps (Generated)",
+                ),
+            ],
+        },
+        PlanCandidate {
+            id: "cand_update_readme_ask".to_string(),
+            rationale: "Ask which install steps to document, then write them".to_string(),
+            score: 0.0,
+            estimated_tokens: 0,
+            steps: vec![
+                Step::ask_user(
+                    "readme-ask-1",
+                    "Which install steps should the README list?",
+                ),
+                Step::new(
+                    "readme-ask-2",
+                    StepPermission::Write,
+                    "write_file",
+                    Some(
+                        "README.md::\n## Install Steps (Generated)\n{{steps.readme-ask-1.output}}\n"
+                            .to_string(),
+                    ),
+                ),
+                Step::verify(
+                    "readme-ask-3",
+                    StepPermission::Read,
+                    "read_file",
+                    Some("README.md".to_string()),
+                    "## Install Steps (Generated)",
+                ),
             ],
         },
     ]
@@ -689,6 +1735,7 @@ fn read_intent_candidates(path: &str) -> Vec<PlanCandidate> {
             id: "cand_read_1".to_string(),
             rationale: "Directly read requested file".to_string(),
             score: 0.0,
+            estimated_tokens: 0,
             steps: vec![Step::new(
                 "read-1",
                 StepPermission::Read,
@@ -700,6 +1747,7 @@ fn read_intent_candidates(path: &str) -> Vec<PlanCandidate> {
             id: "cand_read_2".to_string(),
             rationale: "Validate path then read file".to_string(),
             score: 0.0,
+            estimated_tokens: 0,
             steps: vec![
                 Step::new(
                     "read-2",
@@ -718,12 +1766,50 @@ fn read_intent_candidates(path: &str) -> Vec<PlanCandidate> {
     ]
 }
 
+fn catalog_tool_candidates(entry: &ToolCatalogEntry) -> Vec<PlanCandidate> {
+    vec![
+        PlanCandidate {
+            id: "cand_catalog_1".to_string(),
+            rationale: format!("Call {} through its mediated tool path", entry.name),
+            score: 0.0,
+            estimated_tokens: 0,
+            steps: vec![Step::new(
+                "catalog-1",
+                entry.permission,
+                entry.tool_name.clone(),
+                entry.input.clone(),
+            )],
+        },
+        PlanCandidate {
+            id: "cand_catalog_2".to_string(),
+            rationale: format!("Inspect workspace context before calling {}", entry.name),
+            score: 0.0,
+            estimated_tokens: 0,
+            steps: vec![
+                Step::new(
+                    "catalog-2",
+                    StepPermission::Read,
+                    "list_dir",
+                    Some(".".to_string()),
+                ),
+                Step::new(
+                    "catalog-3",
+                    entry.permission,
+                    entry.tool_name.clone(),
+                    entry.input.clone(),
+                ),
+            ],
+        },
+    ]
+}
+
 fn generic_recon_candidates() -> Vec<PlanCandidate> {
     vec![
         PlanCandidate {
             id: "cand_generic_1".to_string(),
             rationale: "Baseline read-only inspection".to_string(),
             score: 0.0,
+            estimated_tokens: 0,
             steps: vec![
                 Step::new(
                     "gen-1",
@@ -743,6 +1829,7 @@ fn generic_recon_candidates() -> Vec<PlanCandidate> {
             id: "cand_generic_2".to_string(),
             rationale: "Inspect docs and project metadata".to_string(),
             score: 0.0,
+            estimated_tokens: 0,
             steps: vec![
                 Step::new(
                     "gen-3",
@@ -925,7 +2012,13 @@ impl Runtime {
             }
             self.seen_dedupe.insert(dedupe_key.clone());
         }
-        self.queue.push_back(job);
+        // Higher priorities run first; within a priority, jobs keep submission order.
+        let position = self
+            .queue
+            .iter()
+            .position(|queued| queued.goal.priority < job.goal.priority)
+            .unwrap_or(self.queue.len());
+        self.queue.insert(position, job);
         SubmitOutcome::Accepted
     }
 
@@ -1064,6 +2157,27 @@ impl Runtime {
 mod tests {
     use super::*;
 
+    #[test]
+    fn trace_verbosity_samples_detail_but_never_drops_protected_events() {
+        assert_eq!(TraceClass::of("approval_denied"), TraceClass::Protected);
+        assert_eq!(TraceClass::of("scope_violation"), TraceClass::Protected);
+        assert_eq!(TraceClass::of("execution_failed"), TraceClass::Lifecycle);
+        assert_eq!(TraceClass::of("tool_executed"), TraceClass::Detail);
+
+        let minimal = TraceVerbosity::Minimal;
+        assert!(minimal.keeps(TraceClass::Protected, 10_000));
+        assert!(minimal.keeps(TraceClass::Lifecycle, 1));
+        assert!(!minimal.keeps(TraceClass::Detail, 1));
+
+        let normal = TraceVerbosity::Normal;
+        let kept = (1..=100)
+            .filter(|seen| normal.keeps(TraceClass::Detail, *seen))
+            .count();
+        assert_eq!(kept, 20 + 8);
+        assert!((1..=100).all(|seen| TraceVerbosity::Debug.keeps(TraceClass::Detail, seen)));
+        assert_eq!(TraceVerbosity::parse("DEBUG"), Some(TraceVerbosity::Debug));
+    }
+
     fn test_job(behavior: GoalAttemptBehavior, dedupe_key: Option<&str>) -> GoalJob {
         GoalJob {
             goal: Goal::new("test goal").with_dedupe_key(dedupe_key.map(str::to_string)),
@@ -1080,6 +2194,29 @@ mod tests {
         assert_eq!(second, SubmitOutcome::Duplicate);
     }
 
+    #[test]
+    fn queue_runs_higher_priorities_first_in_submission_order() {
+        let mut runtime = Runtime::new();
+        for (name, priority) in [
+            ("low", GoalPriority::Low),
+            ("normal-1", GoalPriority::Normal),
+            ("urgent", GoalPriority::Urgent),
+            ("normal-2", GoalPriority::Normal),
+        ] {
+            let mut job = test_job(GoalAttemptBehavior::Succeed, None);
+            job.goal = Goal::new(name).with_priority(priority);
+            runtime.submit(job);
+        }
+        let config = GoalExecutionConfig {
+            max_retries: 0,
+            attempt_timeout_ms: 1_000,
+        };
+        let order: Vec<String> = std::iter::from_fn(|| runtime.run_next(config))
+            .map(|result| result.goal.description)
+            .collect();
+        assert_eq!(order, vec!["urgent", "normal-1", "normal-2", "low"]);
+    }
+
     #[test]
     fn retries_then_fails_after_max_attempts() {
         let mut runtime = Runtime::new();
@@ -1175,7 +2312,14 @@ mod tests {
     fn planner_generates_two_to_five_candidates() {
         let goal = Goal::new("scan");
         let event = CoreEvent::new("discord", "user-1", "scan workspace");
-        let plan = build_task_plan(&goal.id, &event, &TaskPipelineConfig { candidate_count: 5 });
+        let plan = build_task_plan(
+            &goal.id,
+            &event,
+            &TaskPipelineConfig {
+                candidate_count: 5,
+                ..Default::default()
+            },
+        );
         assert!(plan.candidates.len() >= 2);
         assert!(plan.candidates.len() <= 5);
         assert!(plan.selected_index < plan.candidates.len());
@@ -1186,11 +2330,163 @@ mod tests {
         );
     }
 
+    #[test]
+    fn planner_runs_preferred_candidate_even_outside_the_requested_count() {
+        let goal = Goal::new("scan");
+        let event = CoreEvent::new("discord", "user-1", "scan workspace");
+        let plan = build_task_plan(
+            &goal.id,
+            &event,
+            &TaskPipelineConfig {
+                candidate_count: 2,
+                preferred_candidate: Some("cand_scan_read_5".to_string()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(plan.candidates.len(), 2);
+        assert_eq!(plan.candidates[plan.selected_index].id, "cand_scan_read_5");
+
+        let fallback = build_task_plan(
+            &goal.id,
+            &event,
+            &TaskPipelineConfig {
+                candidate_count: 2,
+                preferred_candidate: Some("cand_read_1".to_string()),
+                ..Default::default()
+            },
+        );
+        assert!(fallback.traces.iter().any(|trace| {
+            trace.event_type == "plan_preferred" && trace.detail.contains("not offered")
+        }));
+    }
+
+    #[test]
+    fn token_budget_drops_costly_candidates_and_fails_when_none_fit() {
+        let goal = Goal::new("scan");
+        let event = CoreEvent::new("discord", "user-1", "scan workspace");
+        let unbounded = build_task_plan(&goal.id, &event, &TaskPipelineConfig::default());
+        let cheapest = unbounded
+            .candidates
+            .iter()
+            .map(|candidate| candidate.estimated_tokens)
+            .min()
+            .expect("candidates");
+        assert!(cheapest > 0);
+        assert!(unbounded.traces.iter().any(|trace| {
+            trace.event_type == "plan_candidate_generated" && trace.detail.contains("tokens=")
+        }));
+
+        let budgeted = build_task_plan(
+            &goal.id,
+            &event,
+            &TaskPipelineConfig {
+                candidate_count: 5,
+                token_budget: Some(cheapest),
+                ..Default::default()
+            },
+        );
+        assert!(budgeted.within_budget);
+        assert!(
+            budgeted
+                .candidates
+                .iter()
+                .all(|candidate| candidate.estimated_tokens <= cheapest)
+        );
+        assert!(
+            budgeted
+                .traces
+                .iter()
+                .any(|trace| trace.event_type == "plan_candidate_over_budget")
+        );
+
+        let starved = build_task_plan(
+            &goal.id,
+            &event,
+            &TaskPipelineConfig {
+                token_budget: Some(1),
+                ..Default::default()
+            },
+        );
+        assert!(!starved.within_budget);
+        let result = execute_task_plan_with_broker(
+            goal,
+            starved,
+            |_| Some(StepPermission::Read),
+            |_, _| false,
+            |_| panic!("over-budget plans must not run steps"),
+        );
+        assert_eq!(result.goal.status, GoalStatus::Failed);
+    }
+
+    #[test]
+    fn identical_reads_run_once_until_a_write_invalidates_them() {
+        let goal = Goal::new("reads");
+        let read =
+            |id: &str| Step::new(id, StepPermission::Read, "list_dir", Some(".".to_string()));
+        let plan = TaskPlan {
+            intent: GoalIntent::GenericRecon,
+            candidates: vec![PlanCandidate {
+                id: "candidate-1".to_string(),
+                rationale: "repeat reads".to_string(),
+                score: 1.0,
+                estimated_tokens: 0,
+                steps: vec![
+                    read("read-1"),
+                    read("read-2"),
+                    Step::new(
+                        "write-1",
+                        StepPermission::Write,
+                        "write_file",
+                        Some("a.md::alpha".to_string()),
+                    ),
+                    read("read-3"),
+                ],
+            }],
+            selected_index: 0,
+            traces: Vec::new(),
+            within_budget: true,
+        };
+        let mut executed = Vec::new();
+        let result = execute_task_plan_with_broker(
+            goal,
+            plan,
+            |_| None,
+            |_, _| false,
+            |step| {
+                executed.push(step.id.clone());
+                Ok(StepResult {
+                    step_id: step.id.clone(),
+                    tool_name: step.tool_name.clone(),
+                    status: "success".to_string(),
+                    output: format!("output of {}", step.id),
+                })
+            },
+        );
+        assert_eq!(executed, vec!["read-1", "write-1", "read-3"]);
+        assert_eq!(result.step_results.len(), 4);
+        assert_eq!(result.step_results[1].step_id, "read-2");
+        assert_eq!(result.step_results[1].output, "output of read-1");
+        let hits: Vec<&TraceEvent> = result
+            .traces
+            .iter()
+            .filter(|trace| trace.event_type == "step_cache_hit")
+            .collect();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].detail, "read-2:list_dir reused read-1 -> success");
+    }
+
     #[test]
     fn execution_pauses_when_step_requires_approval() {
         let goal = Goal::new("write request");
         let event = CoreEvent::new("discord", "user-1", "update README with install steps");
-        let plan = build_task_plan(&goal.id, &event, &TaskPipelineConfig { candidate_count: 2 });
+        let plan = build_task_plan(
+            &goal.id,
+            &event,
+            &TaskPipelineConfig {
+                candidate_count: 2,
+                ..Default::default()
+            },
+        );
 
         let result = execute_task_plan_with_broker(
             goal,
@@ -1202,7 +2498,7 @@ mod tests {
                     Some(StepPermission::Read)
                 }
             },
-            |capability| capability == StepPermission::Write,
+            |_, capability| capability == StepPermission::Write,
             |step| {
                 Ok(StepResult {
                     step_id: step.id.clone(),
@@ -1221,4 +2517,704 @@ mod tests {
                 .any(|trace| trace.event_type == "approval_required")
         );
     }
+
+    #[test]
+    fn canary_mode_pauses_after_first_write_target() {
+        let goal = Goal::new("multi write");
+        let plan = TaskPlan {
+            intent: GoalIntent::GenericRecon,
+            candidates: vec![PlanCandidate {
+                id: "candidate-1".to_string(),
+                rationale: "write two files".to_string(),
+                score: 1.0,
+                estimated_tokens: 0,
+                steps: vec![
+                    Step::new(
+                        "step-1",
+                        StepPermission::Write,
+                        "write_file",
+                        Some("a.md::one".to_string()),
+                    ),
+                    Step::new(
+                        "step-2",
+                        StepPermission::Write,
+                        "write_file",
+                        Some("b.md::two".to_string()),
+                    ),
+                ],
+            }],
+            selected_index: 0,
+            traces: Vec::new(),
+            within_budget: true,
+        };
+
+        let result = execute_task_plan_with_options(
+            goal,
+            plan,
+            ExecutionOptions {
+                canary_writes: true,
+                ..ExecutionOptions::default()
+            },
+            |_| Some(StepPermission::Write),
+            |_, _| false,
+            |step| {
+                Ok(StepResult {
+                    step_id: step.id.clone(),
+                    tool_name: step.tool_name.to_string(),
+                    status: "success".to_string(),
+                    output: format!("diff for {}", step.id),
+                })
+            },
+        );
+
+        assert_eq!(result.step_results.len(), 1);
+        assert_eq!(result.goal.status, GoalStatus::Pending);
+        let pending = result.pending_approval.expect("canary pending approval");
+        assert_eq!(pending.tool_name, CANARY_CONTINUE_TOOL);
+        assert_eq!(pending.input.as_deref(), Some("diff for step-1"));
+        assert!(
+            result
+                .traces
+                .iter()
+                .any(|trace| trace.event_type == "canary_paused")
+        );
+    }
+
+    #[test]
+    fn failed_verify_step_fails_goal_and_requests_undo() {
+        let goal = Goal::new("write then check");
+        let plan = TaskPlan {
+            intent: GoalIntent::GenericRecon,
+            candidates: vec![PlanCandidate {
+                id: "candidate-1".to_string(),
+                rationale: "write a file and verify it".to_string(),
+                score: 1.0,
+                estimated_tokens: 0,
+                steps: vec![
+                    Step::new(
+                        "step-1",
+                        StepPermission::Write,
+                        "write_file",
+                        Some("a.md::one".to_string()),
+                    ),
+                    Step::verify(
+                        "step-2",
+                        StepPermission::Read,
+                        "read_file",
+                        Some("a.md".to_string()),
+                        "two",
+                    ),
+                    Step::new("step-3", StepPermission::Read, "list_dir", None),
+                ],
+            }],
+            selected_index: 0,
+            traces: Vec::new(),
+            within_budget: true,
+        };
+
+        let result = execute_task_plan_with_options(
+            goal,
+            plan,
+            ExecutionOptions::default(),
+            |_| None,
+            |_, _| false,
+            |step| {
+                Ok(StepResult {
+                    step_id: step.id.clone(),
+                    tool_name: step.tool_name.to_string(),
+                    status: "success".to_string(),
+                    output: "one".to_string(),
+                })
+            },
+        );
+
+        assert_eq!(result.goal.status, GoalStatus::Failed);
+        assert!(result.undo_required);
+        assert_eq!(result.step_results.len(), 2);
+        assert!(
+            result
+                .traces
+                .iter()
+                .any(|trace| trace.event_type == "verification_failed")
+        );
+    }
+
+    #[test]
+    fn deterministic_reflection_lists_failures_and_strategy_changes() {
+        let goal = Goal::new("read a file");
+        let plan = TaskPlan {
+            intent: GoalIntent::GenericRecon,
+            candidates: vec![PlanCandidate {
+                id: "candidate-1".to_string(),
+                rationale: "list then read".to_string(),
+                score: 1.0,
+                estimated_tokens: 0,
+                steps: vec![
+                    Step::new("step-1", StepPermission::Read, "list_dir", None),
+                    Step::new("step-2", StepPermission::Read, "read_file", None),
+                ],
+            }],
+            selected_index: 0,
+            traces: Vec::new(),
+            within_budget: true,
+        };
+        let run = execute_task_plan_with_options(
+            goal,
+            plan,
+            ExecutionOptions::default(),
+            |_| None,
+            |_, _| false,
+            |step| {
+                if step.tool_name == "read_file" {
+                    return Err("missing path".to_string());
+                }
+                Ok(StepResult {
+                    step_id: step.id.clone(),
+                    tool_name: step.tool_name.to_string(),
+                    status: "success".to_string(),
+                    output: String::new(),
+                })
+            },
+        );
+
+        let (reflection, source) = reflect_run(&run, None);
+
+        assert_eq!(source, "deterministic");
+        assert_eq!(reflection.summary, run.reflection);
+        assert_eq!(reflection.worked, vec!["step-1:list_dir succeeded"]);
+        assert_eq!(reflection.failed, vec!["read_file: missing path"]);
+        assert_eq!(
+            reflection.strategy_changes,
+            vec!["avoid read_file in candidate-1 until its failure is resolved"]
+        );
+    }
+
+    #[test]
+    fn steps_read_earlier_outputs_through_references() {
+        let goal = Goal::new("copy version into README");
+        let plan = TaskPlan {
+            intent: GoalIntent::GenericRecon,
+            candidates: vec![PlanCandidate {
+                id: "candidate-1".to_string(),
+                rationale: "read version then write it".to_string(),
+                score: 1.0,
+                estimated_tokens: 0,
+                steps: vec![
+                    Step::new(
+                        "version-1",
+                        StepPermission::Read,
+                        "read_file",
+                        Some("Cargo.toml".to_string()),
+                    )
+                    .with_transform(
+                        OutputTransform::parse(r#"regex:version = "([^"]+)""#).expect("regex"),
+                    ),
+                    Step::new(
+                        "readme-1",
+                        StepPermission::Write,
+                        "write_file",
+                        Some("README.md::Version {{steps.version-1.output}}".to_string()),
+                    ),
+                    Step::new(
+                        "readme-2",
+                        StepPermission::Read,
+                        "read_file",
+                        Some("{{ steps.missing.output }}".to_string()),
+                    ),
+                ],
+            }],
+            selected_index: 0,
+            traces: Vec::new(),
+            within_budget: true,
+        };
+
+        let mut inputs = Vec::new();
+        let result = execute_task_plan_with_options(
+            goal,
+            plan,
+            ExecutionOptions::default(),
+            |_| None,
+            |_, _| false,
+            |step| {
+                inputs.push(step.input.clone().unwrap_or_default());
+                Ok(StepResult {
+                    step_id: step.id.clone(),
+                    tool_name: step.tool_name.to_string(),
+                    status: "success".to_string(),
+                    output: "[package]\nversion = \"1.2.3\"\n".to_string(),
+                })
+            },
+        );
+
+        assert_eq!(inputs, vec!["Cargo.toml", "README.md::Version 1.2.3"]);
+        assert_eq!(result.goal.status, GoalStatus::Failed);
+        assert!(result.traces.iter().any(|trace| {
+            trace.event_type == "execution_failed" && trace.detail.contains("steps.missing.output")
+        }));
+    }
+
+    #[test]
+    fn step_references_cap_output_and_keep_other_braces() {
+        let results = vec![StepResult {
+            step_id: "big".to_string(),
+            tool_name: "read_file".to_string(),
+            status: "success".to_string(),
+            output: "x".repeat(STEP_REFERENCE_MAX_BYTES + 10),
+        }];
+        let resolved = resolve_step_references(
+            "{{steps.big.status}} {{steps.big.output}} {{name}}",
+            &results,
+        )
+        .expect("resolve");
+        assert_eq!(
+            resolved.len(),
+            "success ".len() + STEP_REFERENCE_MAX_BYTES + " {{name}}".len()
+        );
+        assert!(resolved.ends_with(" {{name}}"));
+        assert!(resolve_step_references("{{steps.big.size}}", &results).is_err());
+    }
+
+    #[test]
+    fn run_if_conditions_branch_on_failures_and_outputs() {
+        let condition = |spec: &str| StepCondition::parse(spec).expect("condition");
+        let goal = Goal::new("read config with fallback");
+        let plan = TaskPlan {
+            intent: GoalIntent::GenericRecon,
+            candidates: vec![PlanCandidate {
+                id: "candidate-1".to_string(),
+                rationale: "read, falling back to a listing".to_string(),
+                score: 1.0,
+                estimated_tokens: 0,
+                steps: vec![
+                    Step::new(
+                        "read-1",
+                        StepPermission::Read,
+                        "read_file",
+                        Some("titan.toml".to_string()),
+                    ),
+                    Step::new("list-1", StepPermission::Read, "list_dir", None)
+                        .with_condition(condition("steps.read-1.status is failed")),
+                    Step::new(
+                        "read-2",
+                        StepPermission::Read,
+                        "read_file",
+                        Some("{{steps.read-1.output}}".to_string()),
+                    )
+                    .with_condition(condition("steps.read-1.status is success")),
+                    Step::new(
+                        "search-1",
+                        StepPermission::Read,
+                        "search_text",
+                        Some("titan".to_string()),
+                    )
+                    .with_condition(condition(r"steps.list-1.output matches \.toml$")),
+                ],
+            }],
+            selected_index: 0,
+            traces: Vec::new(),
+            within_budget: true,
+        };
+
+        let result = execute_task_plan_with_options(
+            goal,
+            plan,
+            ExecutionOptions::default(),
+            |_| None,
+            |_, _| false,
+            |step| {
+                if step.id == "read-1" {
+                    return Err("not found".to_string());
+                }
+                Ok(StepResult {
+                    step_id: step.id.clone(),
+                    tool_name: step.tool_name.to_string(),
+                    status: "success".to_string(),
+                    output: "Cargo.toml".to_string(),
+                })
+            },
+        );
+
+        assert_eq!(result.goal.status, GoalStatus::Completed);
+        let statuses: Vec<(&str, &str)> = result
+            .step_results
+            .iter()
+            .map(|result| (result.step_id.as_str(), result.status.as_str()))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("read-1", "failed"),
+                ("list-1", "success"),
+                ("read-2", "skipped_condition"),
+                ("search-1", "success"),
+            ]
+        );
+        assert!(result.traces.iter().any(|trace| {
+            trace.event_type == "skipped_condition"
+                && trace.detail == "read-2:read_file unless steps.read-1.status is success"
+        }));
+        assert!(StepCondition::parse("steps.read-1.output equals x").is_err());
+    }
+
+    #[test]
+    fn ask_user_step_pauses_and_resumes_with_the_answer() {
+        let goal = Goal::new("document install steps");
+        let plan = TaskPlan {
+            intent: GoalIntent::UpdateReadme,
+            candidates: vec![PlanCandidate {
+                id: "candidate-1".to_string(),
+                rationale: "ask, then write the answer".to_string(),
+                score: 1.0,
+                estimated_tokens: 0,
+                steps: vec![
+                    Step::ask_user("ask-1", "Which steps?"),
+                    Step::new(
+                        "write-1",
+                        StepPermission::Write,
+                        "write_file",
+                        Some("README.md::{{steps.ask-1.output}}".to_string()),
+                    ),
+                ],
+            }],
+            selected_index: 0,
+            traces: vec![TraceEvent::new("g", "planning_started", "plan")],
+            within_budget: true,
+        };
+        let run_tool = |inputs: &mut Vec<String>, step: &Step| {
+            inputs.push(step.input.clone().unwrap_or_default());
+            Ok(StepResult {
+                step_id: step.id.clone(),
+                tool_name: step.tool_name.to_string(),
+                status: "success".to_string(),
+                output: String::new(),
+            })
+        };
+
+        let mut inputs = Vec::new();
+        let paused = execute_task_plan_with_options(
+            goal,
+            plan.clone(),
+            ExecutionOptions::default(),
+            |_| None,
+            |_, _| false,
+            |step| run_tool(&mut inputs, step),
+        );
+        assert!(inputs.is_empty());
+        assert_eq!(paused.goal.status, GoalStatus::Pending);
+        assert!(paused.pending_approval.is_none());
+        let request = paused.pending_input.expect("pending input");
+        assert_eq!(request.step_id, "ask-1");
+        assert_eq!(paused.reflection, "Awaiting input: Which steps?");
+
+        let answer = StepResult {
+            step_id: request.step_id,
+            tool_name: ASK_USER_TOOL.to_string(),
+            status: "answered".to_string(),
+            output: "cargo install titan".to_string(),
+        };
+        let resumed = resume_task_plan_with_cache(
+            paused.goal,
+            plan,
+            ExecutionOptions::default(),
+            vec![answer],
+            &mut RunStepCache::default(),
+            |_| None,
+            |_, _| false,
+            |step| run_tool(&mut inputs, step),
+        );
+        assert_eq!(inputs, vec!["README.md::cargo install titan"]);
+        assert_eq!(resumed.goal.status, GoalStatus::Completed);
+        assert!(resumed.pending_input.is_none());
+        assert_eq!(resumed.step_results.len(), 2);
+        assert!(
+            !resumed
+                .traces
+                .iter()
+                .any(|trace| trace.event_type == "planning_started")
+        );
+    }
+
+    #[test]
+    fn urgent_goal_preempts_between_steps_and_run_resumes_from_checkpoint() {
+        let goal = Goal::new("read two files").with_priority(GoalPriority::Low);
+        let read = |id: &str| {
+            Step::new(
+                id,
+                StepPermission::Read,
+                "read_file",
+                Some(format!("{id}.md")),
+            )
+        };
+        let plan = TaskPlan {
+            intent: GoalIntent::GenericRecon,
+            candidates: vec![PlanCandidate {
+                id: "candidate-1".to_string(),
+                rationale: "read both".to_string(),
+                score: 1.0,
+                estimated_tokens: 0,
+                steps: vec![read("read-1"), read("read-2")],
+            }],
+            selected_index: 0,
+            traces: Vec::new(),
+            within_budget: true,
+        };
+        let run_tool = |ran: &mut Vec<String>, step: &Step| {
+            ran.push(step.id.clone());
+            Ok(StepResult {
+                step_id: step.id.clone(),
+                tool_name: step.tool_name.to_string(),
+                status: "success".to_string(),
+                output: String::new(),
+            })
+        };
+        let urgent = || Some("goal-urgent".to_string());
+
+        let mut ran = Vec::new();
+        let paused = execute_task_plan_with_options(
+            goal,
+            plan.clone(),
+            ExecutionOptions {
+                preempt: Some(&urgent),
+                ..ExecutionOptions::default()
+            },
+            |_| None,
+            |_, _| false,
+            |step| run_tool(&mut ran, step),
+        );
+        assert_eq!(ran, vec!["read-1"]);
+        assert_eq!(paused.goal.status, GoalStatus::Paused);
+        assert_eq!(paused.preempted_by.as_deref(), Some("goal-urgent"));
+        assert!(
+            paused
+                .traces
+                .iter()
+                .any(|trace| trace.event_type == "goal_preempted"
+                    && trace.detail == "paused before read-2 for urgent goal goal-urgent")
+        );
+
+        // A resumed run always gets through one step before it can be preempted again.
+        let resumed = resume_task_plan_with_cache(
+            paused.goal,
+            plan,
+            ExecutionOptions {
+                preempt: Some(&urgent),
+                ..ExecutionOptions::default()
+            },
+            paused.step_results,
+            &mut RunStepCache::default(),
+            |_| None,
+            |_, _| false,
+            |step| run_tool(&mut ran, step),
+        );
+        assert_eq!(ran, vec!["read-1", "read-2"]);
+        assert_eq!(resumed.goal.status, GoalStatus::Completed);
+        assert!(resumed.preempted_by.is_none());
+    }
+
+    #[test]
+    fn held_step_pauses_for_approval_even_when_policy_allows_it() {
+        let goal = Goal::new("write config");
+        let plan = TaskPlan {
+            intent: GoalIntent::GenericRecon,
+            candidates: vec![PlanCandidate {
+                id: "candidate-1".to_string(),
+                rationale: "read then write".to_string(),
+                score: 1.0,
+                estimated_tokens: 0,
+                steps: vec![
+                    Step::new(
+                        "read-1",
+                        StepPermission::Read,
+                        "read_file",
+                        Some("a.md".to_string()),
+                    ),
+                    Step::new(
+                        "write-1",
+                        StepPermission::Write,
+                        "write_file",
+                        Some(".env::TOKEN=abc".to_string()),
+                    ),
+                ],
+            }],
+            selected_index: 0,
+            traces: Vec::new(),
+            within_budget: true,
+        };
+        let hold = |step: &Step| {
+            (step.tool_name == "write_file").then(|| StepHold {
+                event_type: "secret_write_blocked".to_string(),
+                detail: "target=.env".to_string(),
+            })
+        };
+        let mut ran = Vec::new();
+        let result = execute_task_plan_with_options(
+            goal,
+            plan,
+            ExecutionOptions {
+                hold: Some(&hold),
+                ..ExecutionOptions::default()
+            },
+            |_| None,
+            |_, _| false,
+            |step| {
+                ran.push(step.id.clone());
+                Ok(StepResult {
+                    step_id: step.id.clone(),
+                    tool_name: step.tool_name.to_string(),
+                    status: "success".to_string(),
+                    output: String::new(),
+                })
+            },
+        );
+        assert_eq!(ran, vec!["read-1"]);
+        assert_eq!(result.goal.status, GoalStatus::Pending);
+        let pending = result.pending_approval.expect("held step awaits approval");
+        assert_eq!(pending.tool_name, "write_file");
+        assert_eq!(pending.input.as_deref(), Some(".env::TOKEN=abc"));
+        assert!(result.traces.iter().any(
+            |trace| trace.event_type == "secret_write_blocked" && trace.detail == "target=.env"
+        ));
+    }
+
+    #[test]
+    fn planner_uses_catalog_tool_for_matching_intent() {
+        let goal = Goal::new("github issues");
+        let event = CoreEvent::new("discord", "user-1", "list open github issues");
+        let catalog = vec![
+            ToolCatalogEntry {
+                name: "github.list_prs".to_string(),
+                description: "List pull requests".to_string(),
+                permission: StepPermission::Net,
+                tool_name: "connector_tool".to_string(),
+                input: Some("prs".to_string()),
+            },
+            ToolCatalogEntry {
+                name: "github.list_issues".to_string(),
+                description: "List issues".to_string(),
+                permission: StepPermission::Net,
+                tool_name: "connector_tool".to_string(),
+                input: Some("issues".to_string()),
+            },
+        ];
+        let plan = build_task_plan_with_catalog(
+            &goal.id,
+            &event,
+            &TaskPipelineConfig::default(),
+            &catalog,
+        );
+        assert!(matches!(
+            &plan.intent,
+            GoalIntent::CatalogTool(name) if name == "github.list_issues"
+        ));
+        let selected = &plan.candidates[plan.selected_index];
+        assert!(
+            selected
+                .steps
+                .iter()
+                .any(|step| step.tool_name == "connector_tool"
+                    && step.input.as_deref() == Some("issues")
+                    && step.permission == StepPermission::Net)
+        );
+    }
+
+    struct FixedModel(&'static str);
+
+    impl ModelClient for FixedModel {
+        fn complete(&self, _prompt: &str) -> Result<String, String> {
+            Ok(self.0.to_string())
+        }
+    }
+
+    #[test]
+    fn model_planner_maps_reply_steps_onto_known_tools() {
+        let goal = Goal::new("changelog");
+        let event = CoreEvent::new("discord", "user-1", "summarize open issues in CHANGELOG.md");
+        let catalog = vec![ToolCatalogEntry {
+            name: "github.list_issues".to_string(),
+            description: "List issues".to_string(),
+            permission: StepPermission::Net,
+            tool_name: "connector_tool".to_string(),
+            input: Some("issues".to_string()),
+        }];
+        let model = FixedModel(
+            "Here is the plan:\n```json\n{\"candidates\":[{\"rationale\":\"fetch then write\",\
+             \"steps\":[{\"id\":\"issues\",\"tool\":\"github.list_issues\",\"input\":\"ignored\"},\
+             {\"id\":\"write\",\"tool\":\"write_file\",\
+             \"input\":\"CHANGELOG.md::{{steps.issues.output}}\\n\"},\
+             {\"id\":\"check\",\"tool\":\"read_file\",\"input\":\"CHANGELOG.md\",\"expect\":\"#\"}]},\
+             {\"steps\":[{\"tool\":\"ask_user\",\"input\":\"Which issues?\"}]}]}\n```",
+        );
+        let plan = build_task_plan_with_planner(
+            &goal.id,
+            &event,
+            &TaskPipelineConfig::default(),
+            &catalog,
+            &LlmPlanner::new(&model),
+        );
+
+        let fetch_then_write = plan
+            .candidates
+            .iter()
+            .find(|candidate| candidate.id == "cand_model_1")
+            .expect("first model candidate");
+        assert_eq!(fetch_then_write.rationale, "fetch then write");
+        let steps = &fetch_then_write.steps;
+        assert_eq!(steps[0].tool_name, "connector_tool");
+        assert_eq!(steps[0].input.as_deref(), Some("issues"));
+        assert_eq!(steps[0].permission, StepPermission::Net);
+        assert_eq!(steps[1].permission, StepPermission::Write);
+        assert_eq!(
+            steps[1].input.as_deref(),
+            Some("CHANGELOG.md::{{steps.issues.output}}\n")
+        );
+        assert_eq!(steps[2].kind, StepKind::Verify);
+        let ask = plan
+            .candidates
+            .iter()
+            .find(|candidate| candidate.id == "cand_model_2")
+            .expect("second model candidate");
+        assert_eq!(ask.steps[0].kind, StepKind::AskUser);
+        assert_eq!(ask.steps[0].id, "model-2-1");
+        assert!(
+            plan.traces
+                .iter()
+                .any(|trace| trace.event_type == "planning_started"
+                    && trace.detail.contains("with the model planner"))
+        );
+    }
+
+    #[test]
+    fn model_planner_falls_back_to_heuristics_on_unknown_tools() {
+        let goal = Goal::new("scan");
+        let event = CoreEvent::new("discord", "user-1", "scan workspace");
+        let model = FixedModel(
+            "{\"candidates\":[{\"steps\":[{\"tool\":\"delete_everything\",\"input\":\"/\"}]}]}",
+        );
+        let plan = build_task_plan_with_planner(
+            &goal.id,
+            &event,
+            &TaskPipelineConfig::default(),
+            &[],
+            &LlmPlanner::new(&model),
+        );
+
+        assert!(
+            plan.candidates[plan.selected_index]
+                .id
+                .starts_with("cand_scan_")
+        );
+        let failed = plan
+            .traces
+            .iter()
+            .find(|trace| trace.event_type == "planner_failed")
+            .expect("planner_failed trace");
+        assert!(failed.detail.contains("unknown tool 'delete_everything'"));
+        assert!(
+            plan.traces
+                .iter()
+                .any(|trace| trace.event_type == "planning_started"
+                    && trace.detail.contains("with the heuristic planner"))
+        );
+        assert!(planner::parse_model_plan("not json", &[]).is_err());
+    }
 }
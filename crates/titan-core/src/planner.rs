@@ -0,0 +1,254 @@
+use std::collections::HashSet;
+
+use serde::Deserialize;
+
+use crate::{
+    ASK_USER_TOOL, CoreEvent, GoalIntent, PlanCandidate, Step, StepPermission, ToolCatalogEntry,
+    catalog_tool_candidates, generic_recon_candidates, read_intent_candidates,
+    update_readme_candidates, workspace_scan_candidates,
+};
+
+/// Produces the plan candidates for a goal. Scoring, token budgets, and selection happen in
+/// [`crate::build_task_plan_with_planner`] whichever planner proposed them.
+pub trait Planner {
+    /// Named in the `planning_started` trace.
+    fn name(&self) -> &str;
+    fn candidates(
+        &self,
+        event: &CoreEvent,
+        intent: &GoalIntent,
+        catalog: &[ToolCatalogEntry],
+    ) -> Result<Vec<PlanCandidate>, String>;
+}
+
+/// Fixed candidates per classified intent; never fails.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicPlanner;
+
+impl Planner for HeuristicPlanner {
+    fn name(&self) -> &str {
+        "heuristic"
+    }
+
+    fn candidates(
+        &self,
+        _event: &CoreEvent,
+        intent: &GoalIntent,
+        catalog: &[ToolCatalogEntry],
+    ) -> Result<Vec<PlanCandidate>, String> {
+        Ok(match intent {
+            GoalIntent::ScanWorkspace => workspace_scan_candidates(),
+            GoalIntent::UpdateReadme => update_readme_candidates(),
+            GoalIntent::ReadPath(path) => read_intent_candidates(path),
+            GoalIntent::CatalogTool(name) => catalog
+                .iter()
+                .find(|entry| &entry.name == name)
+                .map(catalog_tool_candidates)
+                .unwrap_or_else(generic_recon_candidates),
+            GoalIntent::GenericRecon => generic_recon_candidates(),
+        })
+    }
+}
+
+/// Sends one prompt to the configured model and returns its text reply.
+pub trait ModelClient {
+    fn complete(&self, prompt: &str) -> Result<String, String>;
+}
+
+/// Asks a model for candidates as JSON and keeps them only if every step names a tool the
+/// runtime knows. See [`parse_model_plan`] for the reply shape.
+pub struct LlmPlanner<'a> {
+    model: &'a dyn ModelClient,
+}
+
+impl<'a> LlmPlanner<'a> {
+    pub fn new(model: &'a dyn ModelClient) -> Self {
+        Self { model }
+    }
+}
+
+impl Planner for LlmPlanner<'_> {
+    fn name(&self) -> &str {
+        "model"
+    }
+
+    fn candidates(
+        &self,
+        event: &CoreEvent,
+        _intent: &GoalIntent,
+        catalog: &[ToolCatalogEntry],
+    ) -> Result<Vec<PlanCandidate>, String> {
+        let reply = self.model.complete(&model_plan_prompt(event, catalog))?;
+        parse_model_plan(&reply, catalog)
+    }
+}
+
+/// Built-in tools a model plan may call, with their permission and input format.
+pub const PLANNER_TOOLS: &[(&str, StepPermission, &str)] = &[
+    ("list_dir", StepPermission::Read, "<dir>"),
+    ("read_file", StepPermission::Read, "<path>"),
+    ("search_text", StepPermission::Read, "<text>::<dir>"),
+    ("write_file", StepPermission::Write, "<path>::<content>"),
+    ("run_command", StepPermission::Exec, "<command line>"),
+    ("http_get", StepPermission::Net, "<url>"),
+    (
+        ASK_USER_TOOL,
+        StepPermission::Read,
+        "<question for the requester>",
+    ),
+];
+
+/// Most steps kept from one model candidate.
+pub const MODEL_PLAN_MAX_STEPS: usize = 12;
+
+pub fn model_plan_prompt(event: &CoreEvent, catalog: &[ToolCatalogEntry]) -> String {
+    let mut tools: Vec<String> = PLANNER_TOOLS
+        .iter()
+        .map(|(name, permission, input)| {
+            format!("- {name} ({}): input {input}", permission.as_str())
+        })
+        .collect();
+    tools.extend(catalog.iter().map(|entry| {
+        format!(
+            "- {} ({}): {}; no input",
+            entry.name,
+            entry.permission.as_str(),
+            entry.description
+        )
+    }));
+    let known = if event.context.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "known about the requester:\n{}\n",
+            event
+                .context
+                .iter()
+                .map(|line| format!("- {line}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+    };
+    format!(
+        "Plan how an agent should handle this request in its workspace. Propose 2 or 3 \
+         alternative plans, safest first, using only these tools:\n{}\n\
+         A step may set \"expect\" to text its output must contain, which makes it a check on \
+         earlier steps. Inputs may use {{{{steps.<id>.output}}}} to refer to an earlier step. \
+         Reply with JSON only, shaped as {{\"candidates\":[{{\"rationale\":string,\
+         \"steps\":[{{\"id\":string,\"tool\":string,\"input\":string,\"expect\":string}}]}}]}}.\n\
+         {known}source: {}\nrequest: {}",
+        tools.join("\n"),
+        event.source,
+        event.text.trim()
+    )
+}
+
+#[derive(Deserialize)]
+struct ModelPlan {
+    candidates: Vec<ModelCandidate>,
+}
+
+#[derive(Deserialize)]
+struct ModelCandidate {
+    #[serde(default)]
+    rationale: String,
+    steps: Vec<ModelStep>,
+}
+
+#[derive(Deserialize)]
+struct ModelStep {
+    #[serde(default)]
+    id: Option<String>,
+    tool: String,
+    #[serde(default)]
+    input: Option<String>,
+    #[serde(default)]
+    expect: Option<String>,
+}
+
+/// Candidates from a model reply shaped as [`model_plan_prompt`] asks. Candidates are named
+/// `cand_model_<n>`; catalog tools keep their mediated dispatch and ignore any model input.
+/// Any unknown tool, empty plan, or duplicate step id rejects the whole reply.
+pub fn parse_model_plan(
+    reply: &str,
+    catalog: &[ToolCatalogEntry],
+) -> Result<Vec<PlanCandidate>, String> {
+    // Models often wrap JSON in prose or a code fence.
+    let json = match (reply.find('{'), reply.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &reply[start..=end],
+        _ => return Err("model plan reply has no JSON object".to_string()),
+    };
+    let plan: ModelPlan =
+        serde_json::from_str(json).map_err(|err| format!("model plan reply: {err}"))?;
+    if plan.candidates.is_empty() {
+        return Err("model plan has no candidates".to_string());
+    }
+    let mut candidates = Vec::new();
+    for (index, candidate) in plan.candidates.into_iter().enumerate() {
+        let id = format!("cand_model_{}", index + 1);
+        if candidate.steps.is_empty() {
+            return Err(format!("{id} has no steps"));
+        }
+        if candidate.steps.len() > MODEL_PLAN_MAX_STEPS {
+            return Err(format!(
+                "{id} has {} steps; at most {MODEL_PLAN_MAX_STEPS} are allowed",
+                candidate.steps.len()
+            ));
+        }
+        let mut seen = HashSet::new();
+        let mut steps = Vec::new();
+        for (position, step) in candidate.steps.into_iter().enumerate() {
+            let step_id = step
+                .id
+                .as_deref()
+                .map(|id| id.trim().to_string())
+                .filter(|id| !id.is_empty())
+                .unwrap_or_else(|| format!("model-{}-{}", index + 1, position + 1));
+            if !seen.insert(step_id.clone()) {
+                return Err(format!("{id} repeats step id {step_id}"));
+            }
+            steps.push(model_step(step_id, step, catalog).map_err(|err| format!("{id}: {err}"))?);
+        }
+        candidates.push(PlanCandidate {
+            id,
+            rationale: match candidate.rationale.trim() {
+                "" => "Model-proposed plan".to_string(),
+                rationale => rationale.to_string(),
+            },
+            score: 0.0,
+            estimated_tokens: 0,
+            steps,
+        });
+    }
+    Ok(candidates)
+}
+
+fn model_step(id: String, step: ModelStep, catalog: &[ToolCatalogEntry]) -> Result<Step, String> {
+    let tool = step.tool.trim();
+    // Not trimmed: `write_file` content may end in a newline on purpose.
+    let input = step.input.filter(|input| !input.trim().is_empty());
+    let expect = step
+        .expect
+        .map(|expect| expect.trim().to_string())
+        .filter(|expect| !expect.is_empty());
+    if tool == ASK_USER_TOOL {
+        let question = input.ok_or_else(|| format!("{id} asks the user nothing"))?;
+        return Ok(Step::ask_user(id, question));
+    }
+    let (permission, tool_name, input) =
+        if let Some((_, permission, _)) = PLANNER_TOOLS.iter().find(|(name, ..)| *name == tool) {
+            (*permission, tool.to_string(), input)
+        } else if let Some(entry) = catalog.iter().find(|entry| entry.name == tool) {
+            (
+                entry.permission,
+                entry.tool_name.clone(),
+                entry.input.clone(),
+            )
+        } else {
+            return Err(format!("{id} uses unknown tool '{tool}'"));
+        };
+    Ok(match expect {
+        Some(expect) => Step::verify(id, permission, tool_name, input, expect),
+        None => Step::new(id, permission, tool_name, input),
+    })
+}
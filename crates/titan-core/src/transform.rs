@@ -0,0 +1,157 @@
+use regex::Regex;
+use serde_json::Value;
+
+/// Reduces a step's tool output before it is stored or read by later steps.
+///
+/// Written as `json:<path>` for a jq-style path such as `.items[].name`, or `regex:<pattern>`
+/// for the first capture group (or whole match) of every match.
+#[derive(Debug, Clone)]
+pub enum OutputTransform {
+    JsonPath(Vec<PathSegment>),
+    Regex(Regex),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+    Iterate,
+}
+
+impl OutputTransform {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        if let Some(path) = spec.strip_prefix("json:") {
+            return parse_path(path.trim()).map(Self::JsonPath);
+        }
+        if let Some(pattern) = spec.strip_prefix("regex:") {
+            return Regex::new(pattern)
+                .map(Self::Regex)
+                .map_err(|err| format!("invalid regex transform: {err}"));
+        }
+        Err(format!(
+            "output transform must start with 'json:' or 'regex:': {spec}"
+        ))
+    }
+
+    /// Applies the transform; several results are joined one per line, strings unquoted.
+    pub fn apply(&self, output: &str) -> Result<String, String> {
+        match self {
+            Self::JsonPath(path) => {
+                let root: Value = serde_json::from_str(output)
+                    .map_err(|err| format!("tool output is not JSON: {err}"))?;
+                let mut current = vec![&root];
+                for segment in path {
+                    current = current
+                        .into_iter()
+                        .flat_map(|value| select(value, segment))
+                        .collect();
+                }
+                if current.is_empty() {
+                    return Err("json path matched nothing".to_string());
+                }
+                Ok(current
+                    .into_iter()
+                    .map(|value| match value {
+                        Value::String(text) => text.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"))
+            }
+            Self::Regex(regex) => {
+                let matches: Vec<&str> = regex
+                    .captures_iter(output)
+                    .filter_map(|captures| captures.get(1).or_else(|| captures.get(0)))
+                    .map(|found| found.as_str())
+                    .collect();
+                if matches.is_empty() {
+                    return Err("regex matched nothing".to_string());
+                }
+                Ok(matches.join("\n"))
+            }
+        }
+    }
+}
+
+fn select<'a>(value: &'a Value, segment: &PathSegment) -> Vec<&'a Value> {
+    match (segment, value) {
+        (PathSegment::Key(key), Value::Object(map)) => map.get(key).into_iter().collect(),
+        (PathSegment::Index(index), Value::Array(items)) => items.get(*index).into_iter().collect(),
+        (PathSegment::Iterate, Value::Array(items)) => items.iter().collect(),
+        (PathSegment::Iterate, Value::Object(map)) => map.values().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn parse_path(path: &str) -> Result<Vec<PathSegment>, String> {
+    let Some(mut rest) = path.strip_prefix('.') else {
+        return Err(format!("json path must start with '.': {path}"));
+    };
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = after
+                .find(']')
+                .ok_or_else(|| format!("unclosed '[' in json path: {path}"))?;
+            let inner = after[..end].trim();
+            segments.push(if inner.is_empty() {
+                PathSegment::Iterate
+            } else {
+                PathSegment::Index(
+                    inner
+                        .parse()
+                        .map_err(|_| format!("invalid index '{inner}' in json path: {path}"))?,
+                )
+            });
+            rest = &after[end + 1..];
+            continue;
+        }
+        rest = rest.strip_prefix('.').unwrap_or(rest);
+        let end = rest.find(['.', '[']).unwrap_or(rest.len());
+        if end == 0 {
+            return Err(format!("empty key in json path: {path}"));
+        }
+        segments.push(PathSegment::Key(rest[..end].to_string()));
+        rest = &rest[end..];
+    }
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_paths_select_and_iterate() {
+        let output = r#"{"items":[{"name":"a","n":1},{"name":"b","n":2}],"total":2}"#;
+        let names = OutputTransform::parse("json:.items[].name").expect("parse");
+        assert_eq!(names.apply(output).expect("apply"), "a\nb");
+        let second = OutputTransform::parse("json:.items[1]").expect("parse");
+        assert_eq!(
+            second.apply(output).expect("apply"),
+            r#"{"n":2,"name":"b"}"#
+        );
+        let total = OutputTransform::parse("json:.total").expect("parse");
+        assert_eq!(total.apply(output).expect("apply"), "2");
+        assert!(
+            OutputTransform::parse("json:.missing")
+                .expect("parse")
+                .apply(output)
+                .is_err()
+        );
+        assert!(OutputTransform::parse("json:items").is_err());
+    }
+
+    #[test]
+    fn regex_keeps_first_capture_of_each_match() {
+        let transform = OutputTransform::parse(r"regex:version (\d+\.\d+)").expect("parse");
+        assert_eq!(
+            transform
+                .apply("titan version 1.2\nplugin version 0.9\n")
+                .expect("apply"),
+            "1.2\n0.9"
+        );
+        assert!(OutputTransform::parse("sed:s/a/b/").is_err());
+    }
+}
@@ -0,0 +1,60 @@
+use anyhow::Result;
+use titan_common::TitanConfig;
+use titan_memory::{AccessLogEntry, MemoryStore};
+
+/// Commands that resolve approvals; they get the stricter anomaly rules.
+const APPROVAL_COMMANDS: &[&str] = &["/approve", "/deny"];
+
+/// Appends `entry` to the access log, then raises any anomalies it trips: an approval or denial
+/// from an actor seen for under `access.new_actor_secs`, an approval attempt the actor is not
+/// allowlisted or authorized for, and the request that takes an actor to
+/// `access.burst_limit` within the burst window. With `access.lock_on_anomaly` an anomaly also enters safe mode.
+/// CLI requests are logged but never flagged. Returns the rules tripped.
+pub fn record_access(
+    store: &MemoryStore,
+    cfg: &TitanConfig,
+    entry: &AccessLogEntry,
+) -> Result<Vec<&'static str>> {
+    let first_seen_ms = store.actor_first_seen_ms(&entry.channel, &entry.actor_id)?;
+    let id = store.record_access(entry)?;
+    if entry.channel == "cli" {
+        return Ok(Vec::new());
+    }
+    let actor = format!("channel={} actor={}", entry.channel, entry.actor_id);
+    let mut anomalies: Vec<(&'static str, String)> = Vec::new();
+    if entry.kind == "command" && APPROVAL_COMMANDS.contains(&entry.action.as_str()) {
+        let allowlisted = cfg.chat.allowlist.is_empty()
+            || cfg.chat.allowlist.iter().any(|id| id == &entry.actor_id);
+        if entry.outcome == "denied" || !allowlisted {
+            anomalies.push((
+                "unauthorized_approval",
+                format!("{actor} command={} outcome={}", entry.action, entry.outcome),
+            ));
+        } else if first_seen_ms
+            .is_none_or(|first| entry.at_ms - first < cfg.access.new_actor_secs as i64 * 1000)
+        {
+            anomalies.push((
+                "new_actor_approval",
+                format!("{actor} command={}", entry.action),
+            ));
+        }
+    }
+    let window_start = entry.at_ms - cfg.access.burst_window_secs as i64 * 1000;
+    let recent = store.count_actor_access_since(&entry.channel, &entry.actor_id, window_start)?;
+    if recent == cfg.access.burst_limit as usize {
+        anomalies.push((
+            "burst",
+            format!(
+                "{actor} requests={recent} window_secs={}",
+                cfg.access.burst_window_secs
+            ),
+        ));
+    }
+    store.flag_access_anomalies(id, &anomalies)?;
+    if let Some((rule, detail)) = anomalies.first()
+        && cfg.access.lock_on_anomaly
+    {
+        store.enter_safe_mode("access_anomaly", &format!("{rule}: {detail}"))?;
+    }
+    Ok(anomalies.into_iter().map(|(rule, _)| rule).collect())
+}
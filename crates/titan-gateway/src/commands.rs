@@ -0,0 +1,1302 @@
+use anyhow::{Result, anyhow};
+use titan_common::AutonomyMode;
+use titan_core::estimate_tokens;
+use titan_memory::{GOAL_LABEL_USER, GoalFilter, MemoryStore, SessionPinRecord, SessionRecord};
+use titan_tools::CapabilityClass;
+
+use crate::crash::{is_secret_key, looks_like_secret};
+use crate::profile::{extract_peer_facts, remember_peer_facts};
+use crate::{
+    InboundEvent, TitanGatewayRuntime, handle_connectors_command, load_runtime_config,
+    load_runtime_config_with_path, model_provider_name, utc_day_start_ms,
+};
+
+/// Who may run a command. Roles are ordered, so an operator may run anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CommandRole {
+    Anyone,
+    /// CLI users, and chat actors listed in `chat.operators` (everyone while that list is empty).
+    Operator,
+}
+
+impl CommandRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Anyone => "anyone",
+            Self::Operator => "operator",
+        }
+    }
+}
+
+/// State a command handler runs against. Handlers that start a new session replace `session`.
+pub struct CommandContext<'a> {
+    pub runtime: &'a TitanGatewayRuntime,
+    pub store: &'a MemoryStore,
+    pub inbound: &'a InboundEvent,
+    pub session: SessionRecord,
+}
+
+pub type CommandHandler = fn(&mut CommandContext<'_>, &[&str]) -> Result<String>;
+
+/// One slash command, declared once.
+///
+/// Dispatch, RBAC, `/help`, and completion all read the registry, so a registered command
+/// shows up everywhere at once.
+#[derive(Debug, Clone, Copy)]
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    /// Accepted argument forms; `""` means the command takes no arguments.
+    pub usages: &'static [&'static str],
+    pub summary: &'static str,
+    pub role: CommandRole,
+    pub handler: CommandHandler,
+}
+
+impl CommandSpec {
+    /// Keywords accepted as the first argument, taken from the literal words of `usages`.
+    pub fn first_arg_keywords(&self) -> Vec<&'static str> {
+        let mut keywords = Vec::new();
+        for usage in self.usages {
+            let Some(first) = usage.split_whitespace().next() else {
+                continue;
+            };
+            if first.starts_with('<') || first.starts_with('[') {
+                continue;
+            }
+            for keyword in first.split('|') {
+                if !keywords.contains(&keyword) {
+                    keywords.push(keyword);
+                }
+            }
+        }
+        keywords
+    }
+
+    fn names(&self) -> impl Iterator<Item = &'static str> {
+        std::iter::once(self.name).chain(self.aliases.iter().copied())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CommandRegistry {
+    commands: Vec<CommandSpec>,
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl CommandRegistry {
+    pub fn builtin() -> Self {
+        Self {
+            commands: BUILTIN_COMMANDS.to_vec(),
+        }
+    }
+
+    /// Adds a command; names and aliases must not collide with registered ones.
+    pub fn register(&mut self, spec: CommandSpec) -> Result<()> {
+        if !spec.name.starts_with('/') {
+            return Err(anyhow!("command name must start with '/': {}", spec.name));
+        }
+        if let Some(taken) = spec.names().find(|name| self.lookup(name).is_some()) {
+            return Err(anyhow!("command already registered: {taken}"));
+        }
+        self.commands.push(spec);
+        Ok(())
+    }
+
+    pub fn commands(&self) -> &[CommandSpec] {
+        &self.commands
+    }
+
+    pub fn lookup(&self, head: &str) -> Option<&CommandSpec> {
+        let head = head.to_ascii_lowercase();
+        self.commands
+            .iter()
+            .find(|spec| spec.names().any(|name| name == head))
+    }
+
+    pub fn help(&self) -> String {
+        let mut lines = vec!["commands:".to_string()];
+        for spec in &self.commands {
+            let role = match spec.role {
+                CommandRole::Anyone => String::new(),
+                role => format!(" [{}]", role.as_str()),
+            };
+            for usage in spec.usages {
+                lines.push(format!(
+                    "{} - {}{role}",
+                    join_usage(spec.name, usage),
+                    spec.summary
+                ));
+            }
+            for alias in spec.aliases {
+                lines.push(format!("{alias} - alias for {}", spec.name));
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// Returns completions for a partial slash command such as `/sk` or `/mode co`.
+    pub fn complete(&self, input: &str) -> Vec<Completion> {
+        let input = input.trim_start();
+        if !input.starts_with('/') {
+            return Vec::new();
+        }
+        let mut tokens = input.split_whitespace().collect::<Vec<_>>();
+        let ends_with_space = input.ends_with(char::is_whitespace);
+        if tokens.len() <= 1 && !ends_with_space {
+            let partial = input.trim().to_ascii_lowercase();
+            let mut out = Vec::new();
+            for spec in &self.commands {
+                for name in spec.names().filter(|name| name.starts_with(&partial)) {
+                    out.push(Completion {
+                        value: name.to_string(),
+                        hint: spec.usages.join(" | ").trim_matches([' ', '|']).to_string(),
+                        summary: spec.summary.to_string(),
+                    });
+                }
+            }
+            return out;
+        }
+
+        let Some(spec) = tokens.first().and_then(|head| self.lookup(head)) else {
+            return Vec::new();
+        };
+        let partial = if ends_with_space {
+            ""
+        } else {
+            tokens.pop().unwrap_or_default()
+        };
+        if tokens.len() > 1 {
+            // Past the first argument only free-form values remain; echo the matching hint.
+            let first = tokens[1];
+            return spec
+                .usages
+                .iter()
+                .filter(|usage| {
+                    usage.split_whitespace().next().is_some_and(|word| {
+                        word.starts_with('<')
+                            || word.starts_with('[')
+                            || word.split('|').any(|keyword| keyword == first)
+                    })
+                })
+                .map(|usage| Completion {
+                    value: input.trim_end().to_string(),
+                    hint: usage
+                        .split_whitespace()
+                        .skip(tokens.len() - 1)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    summary: spec.summary.to_string(),
+                })
+                .collect();
+        }
+        let keywords = spec.first_arg_keywords();
+        if keywords.is_empty() {
+            return spec
+                .usages
+                .iter()
+                .filter(|usage| !usage.is_empty())
+                .map(|usage| Completion {
+                    value: input.to_string(),
+                    hint: usage.to_string(),
+                    summary: spec.summary.to_string(),
+                })
+                .collect();
+        }
+        keywords
+            .into_iter()
+            .filter(|keyword| keyword.starts_with(partial))
+            .map(|keyword| {
+                let rest = spec
+                    .usages
+                    .iter()
+                    .find_map(|usage| {
+                        let mut words = usage.split_whitespace();
+                        let first = words.next()?;
+                        first
+                            .split('|')
+                            .any(|candidate| candidate == keyword)
+                            .then(|| words.collect::<Vec<_>>().join(" "))
+                    })
+                    .unwrap_or_default();
+                Completion {
+                    value: format!("{} {keyword}", spec.name),
+                    hint: rest,
+                    summary: spec.summary.to_string(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    /// Replacement for the whole input line.
+    pub value: String,
+    /// Argument hint for what follows `value`.
+    pub hint: String,
+    pub summary: String,
+}
+
+/// Completes against the built-in commands, for callers without a runtime (CLI, webchat).
+pub fn complete_slash_command(input: &str) -> Vec<Completion> {
+    CommandRegistry::builtin().complete(input)
+}
+
+const BUILTIN_COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "/status",
+        aliases: &[],
+        usages: &[""],
+        summary: "mode, model, session, and approval counts",
+        role: CommandRole::Anyone,
+        handler: status,
+    },
+    CommandSpec {
+        name: "/mode",
+        aliases: &[],
+        usages: &["supervised|collab|auto"],
+        summary: "switch the autonomy mode",
+        role: CommandRole::Operator,
+        handler: mode,
+    },
+    CommandSpec {
+        name: "/safemode",
+        aliases: &[],
+        usages: &["", "resume [reason]", "enter [reason]"],
+        summary: "show, enter, or leave safe mode",
+        role: CommandRole::Operator,
+        handler: safemode,
+    },
+    CommandSpec {
+        name: "/new",
+        aliases: &["/reset"],
+        usages: &["[model?]"],
+        summary: "start a fresh session",
+        role: CommandRole::Anyone,
+        handler: new_session,
+    },
+    CommandSpec {
+        name: "/compact",
+        aliases: &[],
+        usages: &["[instructions?]"],
+        summary: "summarize older session messages",
+        role: CommandRole::Anyone,
+        handler: compact,
+    },
+    CommandSpec {
+        name: "/stop",
+        aliases: &[],
+        usages: &[""],
+        summary: "stop the session's current run",
+        role: CommandRole::Anyone,
+        handler: stop,
+    },
+    CommandSpec {
+        name: "/approve",
+        aliases: &[],
+        usages: &[
+            "<approval_id>",
+            "<approval_id> --input <text>",
+            "all read|write|exec|net",
+        ],
+        summary: "approve a pending request",
+        role: CommandRole::Operator,
+        handler: approve,
+    },
+    CommandSpec {
+        name: "/deny",
+        aliases: &[],
+        usages: &["<approval_id>", "all read|write|exec|net"],
+        summary: "deny a pending request",
+        role: CommandRole::Operator,
+        handler: deny,
+    },
+    CommandSpec {
+        name: "/comment",
+        aliases: &[],
+        usages: &["<approval_id> <text>"],
+        summary: "comment on an approval",
+        role: CommandRole::Anyone,
+        handler: comment,
+    },
+    CommandSpec {
+        name: "/rerequest",
+        aliases: &[],
+        usages: &["<goal_id>"],
+        summary: "ask again for a goal's expired approval",
+        role: CommandRole::Anyone,
+        handler: rerequest,
+    },
+    CommandSpec {
+        name: "/comments",
+        aliases: &[],
+        usages: &["<approval_id>"],
+        summary: "list comments on an approval",
+        role: CommandRole::Anyone,
+        handler: comments,
+    },
+    CommandSpec {
+        name: "/trace",
+        aliases: &[],
+        usages: &["last"],
+        summary: "show the latest trace event",
+        role: CommandRole::Anyone,
+        handler: trace,
+    },
+    CommandSpec {
+        name: "/model",
+        aliases: &[],
+        usages: &["", "list", "status", "<model>"],
+        summary: "show or override the session model",
+        role: CommandRole::Anyone,
+        handler: model,
+    },
+    CommandSpec {
+        name: "/yolo",
+        aliases: &[],
+        usages: &[""],
+        summary: "cli-only; use `titan yolo`",
+        role: CommandRole::Anyone,
+        handler: yolo,
+    },
+    CommandSpec {
+        name: "/skill",
+        aliases: &[],
+        usages: &["install <slug>[@version]"],
+        summary: "request a skill install",
+        role: CommandRole::Operator,
+        handler: skill,
+    },
+    CommandSpec {
+        name: "/connector",
+        aliases: &[],
+        usages: &[
+            "list",
+            "add <type> [name] [field=value...]",
+            "test <connector_id>",
+            "remove <connector_id>",
+        ],
+        summary: "manage connectors; add and remove need approval outside autonomous mode",
+        role: CommandRole::Operator,
+        handler: connector,
+    },
+    CommandSpec {
+        name: "/connectors",
+        aliases: &[],
+        usages: &["tools [connector_id]"],
+        summary: "list connector tools",
+        role: CommandRole::Anyone,
+        handler: connectors,
+    },
+    CommandSpec {
+        name: "/label",
+        aliases: &[],
+        usages: &["<goal_id>", "<goal_id> <label|-label>..."],
+        summary: "show, add, or remove (-label) a goal's labels",
+        role: CommandRole::Anyone,
+        handler: label,
+    },
+    CommandSpec {
+        name: "/feedback",
+        aliases: &[],
+        usages: &["good|bad [goal_id] [comment]"],
+        summary: "rate a goal's outcome; defaults to the session's last goal",
+        role: CommandRole::Anyone,
+        handler: feedback,
+    },
+    CommandSpec {
+        name: "/goals",
+        aliases: &[],
+        usages: &[
+            "[status=<status>] [label=<label>...]",
+            "<saved_filter>",
+            "save <saved_filter> <terms...>",
+            "filters",
+            "forget <saved_filter>",
+        ],
+        summary: "list goals by status and labels, or by one of your saved filters",
+        role: CommandRole::Anyone,
+        handler: goals,
+    },
+    CommandSpec {
+        name: "/usage",
+        aliases: &[],
+        usages: &["", "off|tokens|full"],
+        summary: "show token usage and budgets, or set per-reply usage reporting",
+        role: CommandRole::Anyone,
+        handler: usage,
+    },
+    CommandSpec {
+        name: "/context",
+        aliases: &[],
+        usages: &["list", "detail [before_id]"],
+        summary: "inspect session context",
+        role: CommandRole::Anyone,
+        handler: context,
+    },
+    CommandSpec {
+        name: "/pin",
+        aliases: &[],
+        usages: &["", "<text|#message_id>"],
+        summary: "keep a fact or message in context through compaction",
+        role: CommandRole::Anyone,
+        handler: pin,
+    },
+    CommandSpec {
+        name: "/unpin",
+        aliases: &[],
+        usages: &["<pin_id>"],
+        summary: "remove a pin",
+        role: CommandRole::Anyone,
+        handler: unpin,
+    },
+    CommandSpec {
+        name: "/remember",
+        aliases: &[],
+        usages: &["", "on", "<fact>"],
+        summary: "opt in to TITAN keeping facts about you, and show or add them",
+        role: CommandRole::Anyone,
+        handler: remember,
+    },
+    CommandSpec {
+        name: "/forget",
+        aliases: &[],
+        usages: &["me"],
+        summary: "delete every fact kept about you and opt out",
+        role: CommandRole::Anyone,
+        handler: forget,
+    },
+    CommandSpec {
+        name: "/allowlist",
+        aliases: &[],
+        usages: &["add|remove <id>"],
+        summary: "edit the channel allowlist",
+        role: CommandRole::Operator,
+        handler: allowlist,
+    },
+    CommandSpec {
+        name: "/activation",
+        aliases: &[],
+        usages: &["mention|always"],
+        summary: "set when the bot responds",
+        role: CommandRole::Operator,
+        handler: activation,
+    },
+    CommandSpec {
+        name: "/run",
+        aliases: &[],
+        usages: &["", "<template> [params...]"],
+        summary: "run a configured goal template, prompting for missing parameters",
+        role: CommandRole::Anyone,
+        handler: run,
+    },
+    CommandSpec {
+        name: "/plan",
+        aliases: &[],
+        usages: &["<request>"],
+        summary: "preview a request's plan and which steps would need approval, without running it",
+        role: CommandRole::Anyone,
+        handler: plan,
+    },
+    CommandSpec {
+        name: "/priority",
+        aliases: &[],
+        usages: &["low|normal|urgent <request>"],
+        summary: "run a request at a priority; urgent pauses lower goals between steps",
+        role: CommandRole::Anyone,
+        handler: priority,
+    },
+    CommandSpec {
+        name: "/env",
+        aliases: &[],
+        usages: &["", "set <KEY=value>", "unset <KEY>"],
+        summary: "manage variables exec and http tools see in this session",
+        role: CommandRole::Anyone,
+        handler: env,
+    },
+    CommandSpec {
+        name: "/help",
+        aliases: &[],
+        usages: &[""],
+        summary: "list commands",
+        role: CommandRole::Anyone,
+        handler: help,
+    },
+];
+
+pub(crate) fn mode_name(mode: &AutonomyMode) -> &'static str {
+    match mode {
+        AutonomyMode::Supervised => "supervised",
+        AutonomyMode::Collaborative => "collaborative",
+        AutonomyMode::Autonomous => "autonomous",
+    }
+}
+
+fn safemode(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    let actor = format!("{}:{}", ctx.inbound.channel.as_str(), ctx.inbound.actor_id);
+    let reason = (args.len() > 1).then(|| args[1..].join(" "));
+    match args.first().copied() {
+        None => {
+            let state = ctx.store.safe_mode_state()?;
+            Ok(format!(
+                "safe_mode={} reason={} changed_by={} consecutive_failures={}",
+                if state.active { "active" } else { "off" },
+                state.reason.as_deref().unwrap_or("<none>"),
+                state.changed_by.as_deref().unwrap_or("<none>"),
+                ctx.store.consecutive_failed_goals()?
+            ))
+        }
+        Some("resume") => {
+            if ctx.store.resume_from_safe_mode(&actor, reason.as_deref())? {
+                Ok(format!("safe_mode=off resumed_by={actor}"))
+            } else {
+                Ok("safe_mode=off (was not active)".to_string())
+            }
+        }
+        Some("enter") => {
+            let reason = reason.unwrap_or_else(|| "entered by operator".to_string());
+            ctx.store.enter_safe_mode(&actor, &reason)?;
+            Ok(format!("safe_mode=active reason={reason}"))
+        }
+        Some(_) => Ok("usage: /safemode [resume|enter] [reason]".to_string()),
+    }
+}
+
+fn help(ctx: &mut CommandContext<'_>, _args: &[&str]) -> Result<String> {
+    Ok(ctx.runtime.commands.help())
+}
+
+fn status(ctx: &mut CommandContext<'_>, _args: &[&str]) -> Result<String> {
+    let store = ctx.store;
+    let session = &ctx.session;
+    let cfg = load_runtime_config(ctx.runtime.config_path.as_deref())?;
+    let risk = store.get_runtime_risk_state()?;
+    let pending = store.list_pending_approvals()?.len();
+    let last_run = store
+        .last_goal_for_session(&session.id)?
+        .unwrap_or_else(|| "<none>".to_string());
+    // The session's goals waiting for a concurrency slot, as `<goal_id>@<position>`.
+    let queued: Vec<String> = store
+        .queued_goals()?
+        .iter()
+        .enumerate()
+        .filter(|(_, queued)| queued.session_id == session.id)
+        .map(|(index, queued)| format!("{}@{}", queued.goal_id, index + 1))
+        .collect();
+    Ok(format!(
+        "mode={} provider={} model={} session_id={} last_run_id={} compactions={} pending_approvals={} queue_depth={} queued_goals={} risk_mode={} yolo_expires_at_ms={} safe_mode={} model_provider={}",
+        mode_name(&ctx.runtime.effective_mode(store)?),
+        model_provider_name(&cfg.model.provider),
+        session.model_override.clone().unwrap_or(cfg.model.model_id),
+        session.id,
+        last_run,
+        session.compactions_count,
+        pending,
+        session.queue_depth,
+        if queued.is_empty() {
+            "<none>".to_string()
+        } else {
+            queued.join(",")
+        },
+        risk.risk_mode.as_str(),
+        risk.yolo_expires_at_ms
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "<none>".to_string()),
+        if store.safe_mode_state()?.active {
+            "active"
+        } else {
+            "off"
+        },
+        if store.model_provider_state()?.degraded {
+            "degraded"
+        } else {
+            "ok"
+        }
+    ))
+}
+
+fn mode(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    let selected = match args {
+        [choice] => match choice.trim().to_ascii_lowercase().as_str() {
+            "supervised" => Some(AutonomyMode::Supervised),
+            "collab" | "collaborative" => Some(AutonomyMode::Collaborative),
+            "auto" | "autonomous" => Some(AutonomyMode::Autonomous),
+            _ => None,
+        },
+        _ => None,
+    };
+    let Some(mode) = selected else {
+        return Ok("usage: /mode supervised|collab|auto".to_string());
+    };
+    let (mut cfg, path, _) = load_runtime_config_with_path(ctx.runtime.config_path.as_deref())
+        .map_err(|err| anyhow!("{err}"))?;
+    cfg.mode = mode.clone();
+    cfg.save(&path).map_err(|err| anyhow!("{err}"))?;
+    Ok(format!("mode_updated={}", mode_name(&mode)))
+}
+
+fn new_session(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    ctx.session = ctx.store.create_session(
+        ctx.inbound.channel.as_str(),
+        &ctx.inbound.actor_id,
+        args.first().copied(),
+    )?;
+    Ok(format!(
+        "session_reset: {} model={}",
+        ctx.session.id,
+        ctx.session.model_override.as_deref().unwrap_or("<default>")
+    ))
+}
+
+fn compact(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    let instructions = if args.is_empty() {
+        None
+    } else {
+        Some(args.join(" "))
+    };
+    let compacted = ctx
+        .store
+        .compact_session(&ctx.session.id, instructions.as_deref())?;
+    if let Some(refreshed) = ctx.store.get_session(&ctx.session.id)? {
+        ctx.session = refreshed;
+    }
+    Ok(format!(
+        "session_compacted: {} messages_compacted={} compactions={}",
+        ctx.session.id, compacted, ctx.session.compactions_count
+    ))
+}
+
+fn stop(ctx: &mut CommandContext<'_>, _args: &[&str]) -> Result<String> {
+    ctx.store.mark_session_stop(&ctx.session.id)?;
+    Ok("session_stop_requested: true".to_string())
+}
+
+fn approve(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    if let ["all", capability] = args {
+        return resolve_all(ctx, capability, true);
+    }
+    let (approval_id, edited_input) = match args {
+        [approval_id] => (approval_id, None),
+        [approval_id, "--input", input @ ..] if !input.is_empty() => {
+            (approval_id, Some(input.join(" ")))
+        }
+        _ => {
+            return Ok(
+                "usage: /approve <approval_id> [--input <text>] | /approve all <capability>"
+                    .to_string(),
+            );
+        }
+    };
+    let status = ctx.runtime.resolve_approval_with_input(
+        approval_id,
+        true,
+        ctx.inbound.actor_id.as_str(),
+        Some("chat approve"),
+        edited_input.as_deref(),
+    )?;
+    Ok(format!("approval_status={status}"))
+}
+
+fn deny(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    if let ["all", capability] = args {
+        return resolve_all(ctx, capability, false);
+    }
+    let [approval_id] = args else {
+        return Ok("usage: /deny <approval_id> | /deny all <capability>".to_string());
+    };
+    let status = ctx.runtime.resolve_approval(
+        approval_id,
+        false,
+        ctx.inbound.actor_id.as_str(),
+        Some("chat deny"),
+    )?;
+    Ok(format!("approval_status={status}"))
+}
+
+/// `/approve all <capability>` and `/deny all <capability>`: one status line per approval.
+fn resolve_all(ctx: &mut CommandContext<'_>, capability: &str, approved: bool) -> Result<String> {
+    let Some(class) = CapabilityClass::parse(capability) else {
+        return Ok(format!(
+            "unknown capability '{capability}': use read, write, exec, or net"
+        ));
+    };
+    let reason = if approved {
+        "chat approve all"
+    } else {
+        "chat deny all"
+    };
+    let results = ctx.runtime.resolve_approvals_by_capability(
+        class,
+        approved,
+        ctx.inbound.actor_id.as_str(),
+        Some(reason),
+    )?;
+    if results.is_empty() {
+        return Ok(format!("no pending {} approvals", class.as_str()));
+    }
+    let decided = if approved { "approved" } else { "denied" };
+    let done = results
+        .iter()
+        .filter(|(_, status)| status.starts_with(decided))
+        .count();
+    let mut lines = vec![format!(
+        "{decided}={done} of {} pending {} approvals",
+        results.len(),
+        class.as_str()
+    )];
+    lines.extend(
+        results
+            .into_iter()
+            .map(|(id, status)| format!("{id} approval_status={status}")),
+    );
+    Ok(lines.join("\n"))
+}
+
+fn comment(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    let [approval_id, text @ ..] = args else {
+        return Ok("usage: /comment <approval_id> <text>".to_string());
+    };
+    if text.is_empty() {
+        return Ok("usage: /comment <approval_id> <text>".to_string());
+    }
+    let comment = ctx.runtime.comment_on_approval(
+        approval_id,
+        ctx.inbound.actor_id.as_str(),
+        &text.join(" "),
+    )?;
+    Ok(format!(
+        "comment_added id={} approval={approval_id}",
+        comment.id
+    ))
+}
+
+fn rerequest(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    let [goal_id] = args else {
+        return Ok("usage: /rerequest <goal_id>".to_string());
+    };
+    ctx.runtime
+        .rerequest_approval(ctx.store, ctx.inbound, goal_id)
+}
+
+fn comments(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    let [approval_id] = args else {
+        return Ok("usage: /comments <approval_id>".to_string());
+    };
+    let rows = ctx.store.list_approval_comments(approval_id)?;
+    if rows.is_empty() {
+        return Ok(format!("no comments for approval {approval_id}"));
+    }
+    Ok(rows
+        .iter()
+        .map(|row| format!("#{} {}: {}", row.id, row.author, row.body))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn trace(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    if args.first().copied() != Some("last") {
+        return Ok("usage: /trace last".to_string());
+    }
+    let rows = ctx.store.list_recent_traces(1)?;
+    Ok(match rows.first() {
+        Some(trace) => format!(
+            "trace_last goal={} type={} detail={}",
+            trace.goal_id, trace.event_type, trace.detail
+        ),
+        None => "trace_last none".to_string(),
+    })
+}
+
+fn label(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    let Some((goal_id, changes)) = args.split_first() else {
+        return Ok("usage: /label <goal_id> [label|-label...]".to_string());
+    };
+    if ctx.store.get_goal(goal_id)?.is_none() {
+        return Ok(format!("goal not found: {goal_id}"));
+    }
+    let mut added = Vec::new();
+    for change in changes {
+        match change.strip_prefix('-') {
+            Some(label) => {
+                ctx.store.remove_goal_label(goal_id, label)?;
+            }
+            None => added.push(change.to_string()),
+        }
+    }
+    ctx.store
+        .add_goal_labels(goal_id, &added, GOAL_LABEL_USER)?;
+    Ok(format!(
+        "goal={goal_id} labels={}",
+        ctx.store.goal_labels(goal_id)?.join(",")
+    ))
+}
+
+/// A word after the rating names a goal only when such a goal exists; otherwise the rating
+/// applies to the session's last goal and every word after it is the comment.
+fn feedback(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    const USAGE: &str = "usage: /feedback good|bad [goal_id] [comment]";
+    let rating = match args.first().copied() {
+        Some("good") => 1,
+        Some("bad") => -1,
+        _ => return Ok(USAGE.to_string()),
+    };
+    let mut rest = &args[1..];
+    let goal_id = match rest.first() {
+        Some(candidate) if ctx.store.get_goal(candidate)?.is_some() => {
+            rest = &rest[1..];
+            candidate.to_string()
+        }
+        _ => match ctx.store.last_goal_for_session(&ctx.session.id)? {
+            Some(goal_id) => goal_id,
+            None => return Ok("no goal to rate in this session".to_string()),
+        },
+    };
+    let comment = (!rest.is_empty()).then(|| rest.join(" "));
+    let actor = format!("{}:{}", ctx.inbound.channel.as_str(), ctx.inbound.actor_id);
+    ctx.store
+        .record_goal_feedback(&goal_id, &actor, rating, comment.as_deref())?;
+    Ok(format!(
+        "feedback_recorded goal={goal_id} rating={}",
+        args[0]
+    ))
+}
+
+/// Saved filters belong to the channel and actor that saved them.
+fn goals(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    let operator = format!("{}:{}", ctx.inbound.channel.as_str(), ctx.inbound.actor_id);
+    let filter = match args {
+        ["filters"] => {
+            let saved = ctx.store.list_goal_filters(&operator)?;
+            if saved.is_empty() {
+                return Ok("no saved goal filters".to_string());
+            }
+            return Ok(saved
+                .iter()
+                .map(|saved| format!("{}: {}", saved.name, saved.filter.terms()))
+                .collect::<Vec<_>>()
+                .join("\n"));
+        }
+        ["save", name, terms @ ..] if !terms.is_empty() => {
+            let filter = GoalFilter::parse(&terms.join(" "))?;
+            ctx.store.save_goal_filter(&operator, name, &filter)?;
+            return Ok(format!(
+                "goal_filter_saved name={name} terms={}",
+                filter.terms()
+            ));
+        }
+        ["forget", name] => {
+            let removed = ctx.store.remove_goal_filter(&operator, name)?;
+            return Ok(format!("goal_filter_removed={removed} name={name}"));
+        }
+        [name] if !name.contains('=') => match ctx.store.get_goal_filter(&operator, name)? {
+            Some(filter) => filter,
+            None => return Ok(format!("no saved goal filter named {name}")),
+        },
+        terms => GoalFilter::parse(&terms.join(" "))?,
+    };
+    let goals = ctx.store.list_goals_filtered(&filter, 20)?;
+    if goals.is_empty() {
+        return Ok("no goals match".to_string());
+    }
+    let mut lines = Vec::new();
+    for goal in goals {
+        lines.push(format!(
+            "{} {} [{}] {}",
+            goal.id,
+            goal.status,
+            ctx.store.goal_labels(&goal.id)?.join(","),
+            goal.description
+        ));
+    }
+    Ok(lines.join("\n"))
+}
+
+fn usage(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    let Some(mode) = args.first().copied() else {
+        let cfg = load_runtime_config(ctx.runtime.config_path.as_deref())?;
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        let session = ctx.store.session_usage(&ctx.session.id)?;
+        let today = ctx.store.peer_usage_since(
+            &ctx.session.channel,
+            &ctx.session.peer_id,
+            utc_day_start_ms(now_ms),
+        )?;
+        let budget = |limit: Option<u64>| limit.map_or("none".to_string(), |n| n.to_string());
+        return Ok(format!(
+            "usage_mode={} session_messages={} session_tokens={} session_cost_usd={:.4} \
+             session_budget={} model_cache_hits={}/{} today_tokens={} today_cost_usd={:.4} \
+             daily_budget={}",
+            ctx.session.usage_mode,
+            session.messages,
+            session.tokens,
+            session.cost_usd,
+            budget(cfg.usage.session_token_budget),
+            session.model_cache_hits,
+            session.model_calls,
+            today.tokens,
+            today.cost_usd,
+            budget(cfg.usage.daily_token_budget),
+        ));
+    };
+    if !matches!(mode, "off" | "tokens" | "full") {
+        return Ok("usage: /usage off|tokens|full".to_string());
+    }
+    ctx.store.set_session_usage_mode(&ctx.session.id, mode)?;
+    Ok(format!("usage_mode_updated={mode}"))
+}
+
+/// Facts are kept per channel peer and only after the peer opts in. A stated fact that
+/// matches no known kind is kept as a `note`.
+fn remember(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    let channel = ctx.inbound.channel.as_str();
+    let peer_id = ctx.inbound.actor_id.as_str();
+    if args.is_empty() {
+        if !ctx.store.peer_memory_enabled(channel, peer_id)? {
+            return Ok(
+                "peer_memory=off (opt in with /remember on or /remember <fact>)".to_string(),
+            );
+        }
+        let facts = ctx.store.peer_facts(channel, peer_id)?;
+        let mut out = format!("peer_memory=on facts={}", facts.len());
+        for (key, value) in facts {
+            out.push_str(&format!("\n{key}: {value}"));
+        }
+        return Ok(out);
+    }
+    ctx.store.grant_peer_memory(channel, peer_id)?;
+    if args == ["on"] {
+        return Ok("peer_memory=on".to_string());
+    }
+    let text = args.join(" ");
+    let mut facts = extract_peer_facts(&text);
+    if facts.is_empty() {
+        facts.push(("note", text));
+    }
+    remember_peer_facts(ctx.store, channel, peer_id, &facts, "remember")?;
+    Ok(format!(
+        "peer_memory=on remembered={}",
+        facts
+            .iter()
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>()
+            .join(",")
+    ))
+}
+
+fn forget(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    if args != ["me"] {
+        return Ok("usage: /forget me".to_string());
+    }
+    let removed = ctx
+        .store
+        .forget_peer(ctx.inbound.channel.as_str(), &ctx.inbound.actor_id)?;
+    Ok(format!("peer_memory=off forgotten={removed}"))
+}
+
+const CONTEXT_PAGE_SIZE: usize = 20;
+
+/// Sizes come from SQL estimates, so sessions with thousands of messages page through
+/// `/context detail <before_id>` instead of loading their whole history.
+fn context(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    const USAGE: &str = "usage: /context list|detail [before_id]";
+    let stats = ctx.store.session_context_stats(&ctx.session.id)?;
+    let before = match args {
+        [] | ["list"] => {
+            return Ok(format!(
+                "context_list session={} items={} compacted={} tokens={} context_tokens={} pins={}",
+                ctx.session.id,
+                stats.messages,
+                stats.compacted_messages,
+                stats.message_tokens,
+                stats.context_tokens,
+                stats.pins
+            ));
+        }
+        ["detail"] => None,
+        ["detail", before] => match before.trim_start_matches('#').parse::<i64>() {
+            Ok(before) => Some(before),
+            Err(_) => return Ok(USAGE.to_string()),
+        },
+        _ => return Ok(USAGE.to_string()),
+    };
+    let mut out = format!(
+        "context_detail session={} messages={} context_tokens={} pin_tokens={}\n",
+        ctx.session.id, stats.messages, stats.context_tokens, stats.pin_tokens
+    );
+    if before.is_none() {
+        for pin in ctx.store.list_session_pins(&ctx.session.id)? {
+            out.push_str(&format!(
+                "{} tokens={} {}\n",
+                pin_label(&pin),
+                estimate_tokens(&pin.content),
+                pin.content
+            ));
+        }
+    }
+    let page = ctx
+        .store
+        .session_context_page(&ctx.session.id, before, CONTEXT_PAGE_SIZE)?;
+    for row in &page {
+        out.push_str(&format!(
+            "#{} {} in_context={} bytes={} tokens={} cumulative_tokens={}\n",
+            row.id, row.role, row.in_context, row.bytes, row.tokens, row.cumulative_tokens
+        ));
+    }
+    if page.len() == CONTEXT_PAGE_SIZE
+        && let Some(oldest) = page.last()
+    {
+        out.push_str(&format!("more=/context detail {}\n", oldest.id));
+    }
+    Ok(out)
+}
+
+fn env(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    match args {
+        [] => {
+            let vars = ctx.store.session_env(&ctx.session.id)?;
+            if vars.is_empty() {
+                return Ok("no env vars for this session".to_string());
+            }
+            Ok(vars
+                .iter()
+                .map(|(key, value)| {
+                    if is_secret_key(key) || looks_like_secret(value) {
+                        format!("{key}=<redacted>")
+                    } else {
+                        format!("{key}={value}")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        ["set", assignment @ ..] => {
+            let assignment = assignment.join(" ");
+            let Some((key, value)) = assignment.split_once('=') else {
+                return Ok("usage: /env set <KEY=value>".to_string());
+            };
+            let key = key.trim();
+            if !is_env_key(key) {
+                return Ok(format!("invalid env key: {key}"));
+            }
+            ctx.store.set_session_env(&ctx.session.id, key, value)?;
+            Ok(format!("env_set={key}"))
+        }
+        ["unset", key] => {
+            let removed = ctx.store.unset_session_env(&ctx.session.id, key)?;
+            Ok(format!("env_unset={key} removed={removed}"))
+        }
+        _ => Ok("usage: /env [set <KEY=value> | unset <KEY>]".to_string()),
+    }
+}
+
+fn is_env_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn pin(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    if args.is_empty() {
+        let pins = ctx.store.list_session_pins(&ctx.session.id)?;
+        if pins.is_empty() {
+            return Ok("no pins for this session".to_string());
+        }
+        return Ok(pins
+            .iter()
+            .map(|pin| format!("{} {}", pin_label(pin), pin.content))
+            .collect::<Vec<_>>()
+            .join("\n"));
+    }
+    let pin = ctx.store.pin_session_item(
+        &ctx.session.id,
+        &args.join(" "),
+        ctx.inbound.actor_id.as_str(),
+    )?;
+    Ok(format!(
+        "pinned id={} source={}",
+        pin.id,
+        pin.message_id
+            .map(|id| format!("message:{id}"))
+            .unwrap_or_else(|| "text".to_string())
+    ))
+}
+
+fn unpin(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    let Some(pin_id) = args.first().and_then(|id| id.parse::<i64>().ok()) else {
+        return Ok("usage: /unpin <pin_id>".to_string());
+    };
+    let removed = ctx.store.unpin_session_item(&ctx.session.id, pin_id)?;
+    Ok(format!("unpinned={removed} id={pin_id}"))
+}
+
+fn pin_label(pin: &SessionPinRecord) -> String {
+    match pin.message_id {
+        Some(message_id) => format!("pin#{} message=#{message_id}", pin.id),
+        None => format!("pin#{}", pin.id),
+    }
+}
+
+fn model(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    ctx.runtime
+        .handle_model_command(ctx.store, &ctx.session.id, args)
+}
+
+fn yolo(_ctx: &mut CommandContext<'_>, _args: &[&str]) -> Result<String> {
+    Ok("YOLO mode can only be enabled from local CLI via `titan yolo ...`".to_string())
+}
+
+fn skill(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    ctx.runtime
+        .handle_skill_command(ctx.store, args, ctx.inbound.actor_id.as_str())
+}
+
+fn connectors(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    handle_connectors_command(ctx.store, args)
+}
+
+fn connector(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    ctx.runtime
+        .handle_connector_command(ctx.inbound, ctx.store, args)
+}
+
+fn allowlist(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    ctx.runtime
+        .handle_allowlist_command(ctx.inbound, ctx.store, args)
+}
+
+fn activation(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    ctx.runtime
+        .handle_activation_command(ctx.inbound, ctx.store, args)
+}
+
+fn run(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    ctx.runtime
+        .handle_run_command(ctx.store, ctx.inbound, &ctx.session.id, args)
+}
+
+fn plan(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    ctx.runtime.handle_plan_command(ctx.inbound, args)
+}
+
+fn priority(ctx: &mut CommandContext<'_>, args: &[&str]) -> Result<String> {
+    ctx.runtime.handle_priority_command(ctx.inbound, args)
+}
+
+fn join_usage(name: &str, usage: &str) -> String {
+    if usage.is_empty() {
+        name.to_string()
+    } else {
+        format!("{name} {usage}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(input: &str) -> Vec<String> {
+        complete_slash_command(input)
+            .into_iter()
+            .map(|completion| completion.value)
+            .collect()
+    }
+
+    #[test]
+    fn completes_command_names_then_argument_keywords() {
+        let skill = complete_slash_command("/sk");
+        assert_eq!(skill.len(), 1);
+        assert_eq!(skill[0].value, "/skill");
+        assert_eq!(skill[0].hint, "install <slug>[@version]");
+        assert_eq!(values("/re"), vec!["/reset", "/rerequest", "/remember"]);
+        assert_eq!(
+            values("/co"),
+            vec![
+                "/compact",
+                "/comment",
+                "/comments",
+                "/connector",
+                "/connectors",
+                "/context"
+            ]
+        );
+        assert!(values("hello").is_empty());
+
+        assert_eq!(
+            values("/mode "),
+            vec!["/mode supervised", "/mode collab", "/mode auto"]
+        );
+        assert_eq!(values("/mode co"), vec!["/mode collab"]);
+        assert_eq!(values("/model s"), vec!["/model status"]);
+        let install = complete_slash_command("/skill in");
+        assert_eq!(install[0].value, "/skill install");
+        assert_eq!(install[0].hint, "<slug>[@version]");
+        assert_eq!(values("/approve "), vec!["/approve all"]);
+        assert!(
+            complete_slash_command("/approve all ")
+                .iter()
+                .any(|completion| completion.hint == "read|write|exec|net")
+        );
+        assert_eq!(
+            complete_slash_command("/comments ")[0].hint,
+            "<approval_id>"
+        );
+        assert_eq!(complete_slash_command("/allowlist add ")[0].hint, "<id>");
+        assert_eq!(complete_slash_command("/comment a1 ")[0].hint, "<text>");
+        assert!(values("/nope x").is_empty());
+    }
+
+    #[test]
+    fn registered_commands_appear_in_lookup_help_and_completion() {
+        fn ping(_ctx: &mut CommandContext<'_>, _args: &[&str]) -> Result<String> {
+            Ok("pong".to_string())
+        }
+        let mut registry = CommandRegistry::builtin();
+        assert_eq!(
+            registry.lookup("/RESET").map(|spec| spec.name),
+            Some("/new")
+        );
+        assert!(registry.lookup("/ping").is_none());
+        let ping_spec = CommandSpec {
+            name: "/ping",
+            aliases: &[],
+            usages: &["[target]"],
+            summary: "liveness check",
+            role: CommandRole::Operator,
+            handler: ping,
+        };
+        registry.register(ping_spec).expect("register");
+        assert!(registry.register(ping_spec).is_err());
+        assert!(
+            registry
+                .register(CommandSpec {
+                    aliases: &["/reset"],
+                    name: "/fresh",
+                    ..ping_spec
+                })
+                .is_err()
+        );
+
+        assert_eq!(
+            registry.lookup("/ping").map(|spec| spec.role),
+            Some(CommandRole::Operator)
+        );
+        let help = registry.help();
+        for spec in registry.commands() {
+            assert!(help.contains(spec.name), "{} missing from /help", spec.name);
+        }
+        assert!(help.contains("/ping [target] - liveness check [operator]"));
+        assert!(help.contains("/status - mode, model, session, and approval counts\n"));
+        assert_eq!(registry.complete("/ping")[0].value, "/ping");
+    }
+}
@@ -0,0 +1,320 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use titan_common::now_epoch_ms;
+use titan_memory::{MemoryStore, TraceView};
+
+/// Relative to the workspace; reports never leave it unless submitted.
+pub const CRASH_REPORTS_DIR: &str = ".titan/reports";
+
+/// A panic or failed command, captured with enough context to debug it and nothing that
+/// identifies the operator: paths under the home directory, credentials, and trace payloads
+/// are scrubbed before the report is written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub id: String,
+    /// `panic` or `error`.
+    pub kind: String,
+    pub created_at_ms: i64,
+    pub version: String,
+    /// Panic location or CLI command that failed.
+    pub module: String,
+    pub message: String,
+    /// Goal with the most recent trace activity when the report was taken.
+    pub goal_id: Option<String>,
+    pub trace_events: Vec<CrashTraceEvent>,
+    #[serde(default)]
+    pub submitted_at_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashTraceEvent {
+    pub event_type: String,
+    pub detail: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct CrashReporter {
+    reports_dir: PathBuf,
+    db_path: Option<PathBuf>,
+    trace_events: usize,
+}
+
+impl CrashReporter {
+    pub fn new(workspace_root: &Path, trace_events: usize) -> Self {
+        Self {
+            reports_dir: workspace_root.join(CRASH_REPORTS_DIR),
+            db_path: Some(workspace_root.join("titan.db")),
+            trace_events,
+        }
+    }
+
+    pub fn reports_dir(&self) -> &Path {
+        &self.reports_dir
+    }
+
+    /// Writes one report and returns it. Trace context is best effort: a report is still
+    /// written when the database cannot be opened.
+    pub fn capture(&self, kind: &str, module: &str, message: &str) -> Result<CrashReport> {
+        let created_at_ms = now_epoch_ms();
+        let (goal_id, trace_events) = self.recent_context().unwrap_or_default();
+        let report = CrashReport {
+            id: format!("{created_at_ms}-{kind}-{}", std::process::id()),
+            kind: kind.to_string(),
+            created_at_ms,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            module: scrub_diagnostic(module),
+            message: scrub_diagnostic(message),
+            goal_id,
+            trace_events,
+            submitted_at_ms: None,
+        };
+        self.write(&report)?;
+        Ok(report)
+    }
+
+    /// Records every later panic in this process, then defers to the previous hook.
+    pub fn install_panic_hook(self) {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let module = info
+                .location()
+                .map(|loc| format!("{}:{}", loc.file(), loc.line()))
+                .unwrap_or_else(|| "<unknown>".to_string());
+            let message = info
+                .payload()
+                .downcast_ref::<&str>()
+                .map(|msg| msg.to_string())
+                .or_else(|| info.payload().downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "<non-string panic payload>".to_string());
+            if let Ok(report) = self.capture("panic", &module, &message) {
+                eprintln!("crash_report: {}", report.id);
+            }
+            previous(info);
+        }));
+    }
+
+    /// Reports newest first. Unreadable files are skipped.
+    pub fn list(&self) -> Result<Vec<CrashReport>> {
+        let entries = match std::fs::read_dir(&self.reports_dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut reports: Vec<CrashReport> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|entry| std::fs::read(entry.path()).ok())
+            .filter_map(|raw| serde_json::from_slice(&raw).ok())
+            .collect();
+        reports.sort_by(|a, b| b.created_at_ms.cmp(&a.created_at_ms));
+        Ok(reports)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<CrashReport>> {
+        Ok(self.list()?.into_iter().find(|report| report.id == id))
+    }
+
+    /// Posts a report as JSON to `endpoint` and marks it submitted on a 2xx response.
+    pub fn submit(&self, report: &mut CrashReport, endpoint: &str) -> Result<u16> {
+        let body = serde_json::to_string(report)?;
+        let code =
+            titan_comms::post_webhook(endpoint, "crash_report", report.created_at_ms, &body, None)?;
+        if (200..300).contains(&code) {
+            report.submitted_at_ms = Some(now_epoch_ms());
+            self.write(report)?;
+        }
+        Ok(code)
+    }
+
+    fn write(&self, report: &CrashReport) -> Result<()> {
+        std::fs::create_dir_all(&self.reports_dir)
+            .with_context(|| format!("failed to create {}", self.reports_dir.display()))?;
+        let path = self.reports_dir.join(format!("{}.json", report.id));
+        std::fs::write(&path, serde_json::to_vec_pretty(report)?)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    fn recent_context(&self) -> Result<(Option<String>, Vec<CrashTraceEvent>)> {
+        let Some(db_path) = self.db_path.as_deref().filter(|path| path.exists()) else {
+            return Ok((None, Vec::new()));
+        };
+        let store = MemoryStore::open(db_path)?;
+        let Some(latest) = store
+            .recent_trace_view(1, TraceView::Redacted)?
+            .into_iter()
+            .next()
+        else {
+            return Ok((None, Vec::new()));
+        };
+        let traces = store.trace_view_for_goal(&latest.goal_id, TraceView::Redacted)?;
+        let skip = traces.len().saturating_sub(self.trace_events);
+        let events = traces
+            .into_iter()
+            .skip(skip)
+            .map(|trace| CrashTraceEvent {
+                event_type: trace.event_type,
+                detail: scrub_diagnostic(&trace.detail),
+                created_at: trace.created_at,
+            })
+            .collect();
+        Ok((Some(latest.goal_id), events))
+    }
+}
+
+const SECRET_KEYS: &[&str] = &[
+    "token",
+    "secret",
+    "password",
+    "passwd",
+    "api_key",
+    "apikey",
+    "authorization",
+    "bearer",
+];
+
+const SECRET_PREFIXES: &[&str] = &[
+    "sk-",
+    "ghp_",
+    "gho_",
+    "ghs_",
+    "github_pat_",
+    "xoxb-",
+    "xoxp-",
+];
+
+/// Masks credentials and the home directory in free-form diagnostic text.
+///
+/// Catches `key=value` and `key: value` pairs whose key names a credential, the word after
+/// `Bearer`, well-known token prefixes, and long opaque strings.
+pub fn scrub_diagnostic(text: &str) -> String {
+    let home = home_dir();
+    let text = match home.as_deref() {
+        Some(home) if home.len() > 1 => text.replace(home, "~"),
+        _ => text.to_string(),
+    };
+    let mut out = String::with_capacity(text.len());
+    let mut mask_next = false;
+    for piece in text.split_inclusive(char::is_whitespace) {
+        let word = piece.trim_end_matches(char::is_whitespace);
+        let trailing = &piece[word.len()..];
+        if word.is_empty() {
+            out.push_str(trailing);
+            continue;
+        }
+        let lower = word.to_ascii_lowercase();
+        if mask_next && !is_secret_key(&lower) {
+            out.push_str("<redacted>");
+            mask_next = false;
+        } else if let Some((key, _)) = word
+            .split_once(['=', ':'])
+            .filter(|(key, value)| !value.is_empty() && is_secret_key(key))
+        {
+            let sep = &word[key.len()..key.len() + 1];
+            out.push_str(&format!("{key}{sep}<redacted>"));
+        } else if is_secret_key(lower.trim_end_matches(':')) {
+            // `Bearer abc`, `token: abc`
+            out.push_str(word);
+            mask_next = true;
+        } else if looks_like_secret(word) {
+            out.push_str("<redacted>");
+        } else {
+            out.push_str(word);
+        }
+        out.push_str(trailing);
+    }
+    out
+}
+
+pub(crate) fn is_secret_key(key: &str) -> bool {
+    let key = key
+        .trim_matches(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .to_ascii_lowercase();
+    !key.is_empty()
+        && SECRET_KEYS
+            .iter()
+            .any(|secret| key == *secret || key.ends_with(&format!("_{secret}")))
+}
+
+pub(crate) fn looks_like_secret(word: &str) -> bool {
+    let word = word.trim_matches(|c: char| "\"'`,;()[]{}".contains(c));
+    if SECRET_PREFIXES
+        .iter()
+        .any(|prefix| word.starts_with(prefix))
+    {
+        return true;
+    }
+    // Long runs mixing letters and digits without separators, e.g. API keys or hashes.
+    word.len() >= 32
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+/=_".contains(c))
+        && word.chars().any(|c| c.is_ascii_digit())
+        && word.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+fn home_dir() -> Option<String> {
+    std::env::var("HOME")
+        .ok()
+        .or_else(|| std::env::var("USERPROFILE").ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    use titan_core::{Goal, TraceEvent};
+
+    #[test]
+    fn scrubs_credentials_and_opaque_tokens() {
+        let scrubbed = scrub_diagnostic(
+            "request failed: Authorization: Bearer abc123 token=s3cr3t \
+             db_password: hunter2 key ghp_0123456789abcdef goal 6f1c2a4e-0000-4000-8000-000000000001",
+        );
+        assert!(!scrubbed.contains("abc123"));
+        assert!(!scrubbed.contains("s3cr3t"));
+        assert!(!scrubbed.contains("hunter2"));
+        assert!(!scrubbed.contains("ghp_0123"));
+        assert!(scrubbed.contains("token=<redacted>"));
+        assert!(scrubbed.contains("6f1c2a4e-0000-4000-8000-000000000001"));
+        assert!(scrubbed.starts_with("request failed:"));
+    }
+
+    #[test]
+    fn captures_redacted_trace_context_and_lists_newest_first() {
+        let tmp = tempdir().expect("tempdir");
+        let store = MemoryStore::open(&tmp.path().join("titan.db")).expect("store");
+        let goal = Goal::new("write a file");
+        store.create_goal(&goal).expect("goal");
+        for detail in ["planning", "contents with password=hunter2", "done"] {
+            store
+                .add_trace_event(&TraceEvent::new(&goal.id, "tool_output", detail))
+                .expect("trace");
+        }
+        let reporter = CrashReporter::new(tmp.path(), 2);
+
+        let first = reporter
+            .capture("error", "goal submit", "boom api_key=abc")
+            .expect("capture");
+        assert_eq!(first.goal_id.as_deref(), Some(goal.id.as_str()));
+        assert_eq!(first.trace_events.len(), 2);
+        assert!(
+            first
+                .trace_events
+                .iter()
+                .all(|event| !event.detail.contains("hunter2"))
+        );
+        assert_eq!(first.message, "boom api_key=<redacted>");
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = reporter
+            .capture("panic", "src/lib.rs:1", "oops")
+            .expect("capture");
+        let listed = reporter.list().expect("list");
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].id, second.id);
+        assert!(reporter.get(&first.id).expect("get").is_some());
+    }
+}
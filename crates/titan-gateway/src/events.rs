@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use titan_memory::{LifecycleEventRecord, MemoryStore};
+use tokio::sync::broadcast;
+
+/// In-process pub/sub for lifecycle events.
+///
+/// The store writes every lifecycle event to `lifecycle_events`, whichever process made the
+/// change. One pump tails that table and publishes here, so SSE, WebSocket, webhook, and
+/// metrics consumers subscribe instead of each polling the database.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<LifecycleEventRecord>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LifecycleEventRecord> {
+        self.sender.subscribe()
+    }
+
+    /// Returns the number of subscribers that received the event.
+    pub fn publish(&self, event: LifecycleEventRecord) -> usize {
+        self.sender.send(event).unwrap_or(0)
+    }
+
+    /// Publishes events recorded after `cursor` and returns the new cursor.
+    pub fn pump(&self, store: &MemoryStore, cursor: i64) -> Result<i64> {
+        let mut cursor = cursor;
+        loop {
+            let events = store.list_lifecycle_events_after(cursor, 500)?;
+            let Some(last) = events.last() else {
+                return Ok(cursor);
+            };
+            cursor = last.id;
+            for event in events {
+                self.publish(event);
+            }
+        }
+    }
+
+    /// Starts tailing `lifecycle_events` from the newest row. Must run inside a tokio runtime;
+    /// each tick's store work runs on the blocking pool.
+    pub fn spawn_pump(&self, db_path: PathBuf, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let bus = self.clone();
+        tokio::spawn(async move {
+            let mut cursor = None;
+            loop {
+                let worker = bus.clone();
+                let path = db_path.clone();
+                let tick = tokio::task::spawn_blocking(move || {
+                    let store = MemoryStore::open(&path)?;
+                    let start = match cursor {
+                        Some(cursor) => cursor,
+                        None => store.latest_lifecycle_event_id()?,
+                    };
+                    worker.pump(&store, start)
+                });
+                match tick.await {
+                    Ok(Ok(next)) => cursor = Some(next),
+                    Ok(Err(err)) => tracing::warn!(error = %err, "event_pump_error"),
+                    Err(err) => tracing::warn!(error = %err, "event_pump_join_error"),
+                }
+                tokio::time::sleep(interval).await;
+            }
+        })
+    }
+}
+
+/// Per-kind event counts kept by a bus subscriber.
+#[derive(Debug, Clone, Default)]
+pub struct EventMetrics {
+    counts: Arc<Mutex<BTreeMap<String, u64>>>,
+}
+
+impl EventMetrics {
+    /// Subscribes to `bus` and counts every event it carries from now on.
+    pub fn spawn(bus: &EventBus) -> Self {
+        let metrics = Self::default();
+        let counts = Arc::clone(&metrics.counts);
+        let mut rx = bus.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => {
+                        let mut guard = counts.lock().unwrap_or_else(|e| e.into_inner());
+                        *guard.entry(event.kind).or_default() += 1;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+        metrics
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<String, u64> {
+        self.counts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn pump_publishes_recorded_events_to_subscribers_and_metrics() {
+        let tmp = tempdir().expect("tempdir");
+        let store = MemoryStore::open(&tmp.path().join("titan.db")).expect("open store");
+        let bus = EventBus::new(16);
+        let mut rx = bus.subscribe();
+        let metrics = EventMetrics::spawn(&bus);
+
+        let approval = store
+            .create_approval_request("write_file", "write", "a.txt", Some("test"), 60_000)
+            .expect("approval");
+        store.enable_yolo("cli", 5, None).expect("yolo");
+        let cursor = bus.pump(&store, 0).expect("pump");
+        assert_eq!(cursor, store.latest_lifecycle_event_id().expect("latest"));
+        assert_eq!(bus.pump(&store, cursor).expect("idle pump"), cursor);
+
+        let first = rx.recv().await.expect("first event");
+        assert_eq!(first.kind, "approval_created");
+        assert_eq!(first.subject_id, approval.id);
+        assert_eq!(rx.recv().await.expect("second").kind, "risk_mode_changed");
+
+        tokio::task::yield_now().await;
+        for _ in 0..50 {
+            if metrics.snapshot().len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let counts = metrics.snapshot();
+        assert_eq!(counts.get("approval_created"), Some(&1));
+        assert_eq!(counts.get("risk_mode_changed"), Some(&1));
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::{Result, anyhow};
+use titan_common::FederationPeerConfig;
+use titan_core::{GoalIntent, GoalStatus, TraceEvent};
+use titan_memory::{MemoryStore, RemoteGoalRecord};
+
+/// Actor-id prefix for goals a peer forwarded here; they always run locally, so two peers
+/// that match each other's goals cannot bounce one back and forth.
+pub const FEDERATION_ACTOR_PREFIX: &str = "federation:";
+
+/// The name peers list in `intents`: `scan_workspace`, `update_readme`, `read_path`,
+/// `generic_recon`, or `tool:<name>`.
+pub fn intent_label(intent: &GoalIntent) -> String {
+    match intent {
+        GoalIntent::ScanWorkspace => "scan_workspace".to_string(),
+        GoalIntent::UpdateReadme => "update_readme".to_string(),
+        GoalIntent::ReadPath(_) => "read_path".to_string(),
+        GoalIntent::CatalogTool(name) => format!("tool:{name}"),
+        GoalIntent::GenericRecon => "generic_recon".to_string(),
+    }
+}
+
+/// The first peer whose intents or path prefixes match the goal.
+pub fn federation_peer_for<'a>(
+    peers: &'a [FederationPeerConfig],
+    intent: &GoalIntent,
+) -> Option<&'a FederationPeerConfig> {
+    let label = intent_label(intent);
+    let path = match intent {
+        GoalIntent::ReadPath(path) => Some(path.as_str()),
+        GoalIntent::UpdateReadme => Some("README.md"),
+        _ => None,
+    };
+    peers.iter().find(|peer| {
+        peer.intents.contains(&label)
+            || path.is_some_and(|path| peer.paths.iter().any(|prefix| path_under(path, prefix)))
+    })
+}
+
+fn path_under(path: &str, prefix: &str) -> bool {
+    let path = path.trim_start_matches("./");
+    let prefix = prefix.trim_start_matches("./").trim_end_matches('/');
+    prefix.is_empty() || path == prefix || path.starts_with(&format!("{prefix}/"))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FederationSyncSummary {
+    pub polled: usize,
+    pub traces_merged: usize,
+    pub finished: usize,
+    pub errors: Vec<String>,
+}
+
+/// Pulls status and new traces for every forwarded goal that has not finished. A peer that
+/// cannot be reached is retried on the next pass.
+pub fn sync_remote_goals(
+    store: &MemoryStore,
+    peers: &[FederationPeerConfig],
+) -> Result<FederationSyncSummary> {
+    let mut summary = FederationSyncSummary::default();
+    for record in store.list_active_remote_goals()? {
+        summary.polled += 1;
+        match sync_remote_goal(store, peers, &record) {
+            Ok((merged, status)) => {
+                summary.traces_merged += merged;
+                if matches!(
+                    status,
+                    Some(GoalStatus::Completed | GoalStatus::Failed | GoalStatus::Cancelled)
+                ) {
+                    summary.finished += 1;
+                }
+            }
+            Err(err) => summary.errors.push(format!("{}: {err}", record.goal_id)),
+        }
+    }
+    Ok(summary)
+}
+
+/// Merges the remote traces after `last_trace_id` into the local goal as
+/// `remote:<event_type>` and mirrors the remote status. Returns the merged count and status.
+pub fn sync_remote_goal(
+    store: &MemoryStore,
+    peers: &[FederationPeerConfig],
+    record: &RemoteGoalRecord,
+) -> Result<(usize, Option<GoalStatus>)> {
+    let peer = peers
+        .iter()
+        .find(|peer| peer.name == record.peer)
+        .ok_or_else(|| anyhow!("federation peer {} is no longer configured", record.peer))?;
+    let state =
+        titan_comms::fetch_remote_goal(&peer.url, &record.remote_goal_id, record.last_trace_id)?;
+    let mut last_trace_id = record.last_trace_id;
+    for trace in &state.traces {
+        store.add_trace_event(
+            &TraceEvent::new(
+                record.goal_id.clone(),
+                format!("remote:{}", trace.event_type),
+                trace.detail.clone(),
+            )
+            .with_risk_mode(trace.risk_mode.clone()),
+        )?;
+        last_trace_id = last_trace_id.max(trace.id);
+    }
+    let status = GoalStatus::parse(&state.status);
+    if state.status != record.remote_status
+        && let Some(status) = status
+    {
+        store.update_goal_status(&record.goal_id, status)?;
+    }
+    store.update_remote_goal(&record.goal_id, &state.status, last_trace_id)?;
+    Ok((state.traces.len(), status))
+}
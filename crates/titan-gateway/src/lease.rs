@@ -0,0 +1,142 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use anyhow::Result;
+use titan_common::now_epoch_ms;
+use titan_memory::{GATEWAY_WRITER_LEASE, MemoryStore, WriterLeaseRecord};
+
+const DEFAULT_LEASE_TTL: Duration = Duration::from_secs(15);
+
+/// Identifies this process as a lease holder.
+pub fn process_writer_id() -> &'static str {
+    static ID: OnceLock<String> = OnceLock::new();
+    ID.get_or_init(|| format!("pid{}-{}", std::process::id(), now_epoch_ms()))
+}
+
+/// Returns the live gateway lease when another process holds it.
+///
+/// Callers proxy execution-critical writes to `endpoint` (or refuse them) instead of
+/// writing to the database alongside the leader.
+pub fn remote_writer(store: &MemoryStore) -> Result<Option<WriterLeaseRecord>> {
+    Ok(store
+        .live_writer_lease(GATEWAY_WRITER_LEASE)?
+        .filter(|lease| lease.holder != process_writer_id()))
+}
+
+/// Leader election for execution-critical writes.
+///
+/// Every `titan run` or `titan web serve` process competes for one row in `writer_leases`.
+/// The winner renews it on a heartbeat; the others stay on standby and take over once the
+/// lease expires.
+#[derive(Debug, Clone)]
+pub struct WriterLease {
+    db_path: PathBuf,
+    endpoint: Option<String>,
+    ttl: Duration,
+    leader: Arc<AtomicBool>,
+}
+
+impl WriterLease {
+    pub fn new(db_path: PathBuf, endpoint: Option<String>) -> Self {
+        Self {
+            db_path,
+            endpoint,
+            ttl: DEFAULT_LEASE_TTL,
+            leader: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.leader.load(Ordering::SeqCst)
+    }
+
+    /// Takes or renews the lease and returns whether this process is now the writer.
+    pub fn try_acquire(&self) -> Result<bool> {
+        let store = MemoryStore::open(&self.db_path)?;
+        let acquired = store.try_acquire_writer_lease(
+            GATEWAY_WRITER_LEASE,
+            process_writer_id(),
+            self.endpoint.as_deref(),
+            self.ttl.as_millis() as i64,
+        )?;
+        self.leader.store(acquired, Ordering::SeqCst);
+        Ok(acquired)
+    }
+
+    pub fn release(&self) -> Result<bool> {
+        self.leader.store(false, Ordering::SeqCst);
+        MemoryStore::open(&self.db_path)?
+            .release_writer_lease(GATEWAY_WRITER_LEASE, Some(process_writer_id()))
+    }
+
+    /// Renews the lease every third of its ttl. Must run inside a tokio runtime.
+    pub fn spawn_heartbeat(&self) -> tokio::task::JoinHandle<()> {
+        let lease = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let was_leader = lease.is_leader();
+                let worker = lease.clone();
+                match tokio::task::spawn_blocking(move || worker.try_acquire()).await {
+                    Ok(Ok(true)) if !was_leader => println!("writer_role: leader"),
+                    Ok(Ok(false)) if was_leader => println!("writer_role: standby (lease lost)"),
+                    Ok(Ok(_)) => {}
+                    Ok(Err(err)) => {
+                        lease.leader.store(false, Ordering::SeqCst);
+                        tracing::warn!(error = %err, "writer_lease_error");
+                    }
+                    Err(err) => tracing::warn!(error = %err, "writer_lease_join_error"),
+                }
+                tokio::time::sleep(lease.ttl / 3).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn only_one_holder_until_the_lease_expires() {
+        let tmp = tempdir().expect("tempdir");
+        let db = tmp.path().join("titan.db");
+        let lease = WriterLease::new(db.clone(), Some("http://127.0.0.1:3000".to_string()));
+        assert!(lease.try_acquire().expect("acquire"));
+        assert!(lease.try_acquire().expect("renew"));
+        assert!(lease.is_leader());
+
+        let store = MemoryStore::open(&db).expect("open store");
+        assert!(
+            !store
+                .try_acquire_writer_lease(GATEWAY_WRITER_LEASE, "other", None, 60_000)
+                .expect("contend")
+        );
+        assert!(remote_writer(&store).expect("remote").is_none());
+
+        assert!(lease.release().expect("release"));
+        assert!(!lease.is_leader());
+        assert!(
+            store
+                .try_acquire_writer_lease(GATEWAY_WRITER_LEASE, "other", None, -1)
+                .expect("other takes free lease")
+        );
+        let remote = remote_writer(&store).expect("remote");
+        assert!(remote.is_none(), "an expired lease is not a live writer");
+        assert!(lease.try_acquire().expect("take over expired lease"));
+        let row = store
+            .writer_lease(GATEWAY_WRITER_LEASE)
+            .expect("lookup")
+            .expect("lease row");
+        assert_eq!(row.holder, process_writer_id());
+        assert_eq!(row.endpoint.as_deref(), Some("http://127.0.0.1:3000"));
+    }
+}
@@ -1,19 +1,78 @@
 use std::path::PathBuf;
+use std::sync::Arc;
 
-use anyhow::{Context, Result, anyhow};
-use titan_common::{ActivationMode, AutonomyMode, TitanConfig};
-use titan_connectors::{CompositeSecretResolver, execute_connector_tool_after_approval};
+pub mod access;
+pub mod commands;
+pub mod crash;
+pub mod events;
+pub mod federation;
+pub mod lease;
+pub mod model;
+pub mod plan_preview;
+pub mod profile;
+pub mod progress;
+pub mod telegram;
+pub mod undo;
+pub mod workspace;
+
+use anyhow::{Context, Result, anyhow, bail};
+use titan_common::{
+    ActivationMode, AutonomyMode, ExperimentConfig, ExperimentPlanner, GoalTemplateConfig,
+    ModelConfig, TitanConfig, UsageConfig,
+};
+use titan_connectors::{
+    CompositeSecretResolver, ConnectorAddPayload, ConnectorEventGoal, ConnectorRemovePayload,
+    ConnectorType, apply_connector_change_approval, connector_approval_payload,
+    connector_config_with_fields, connector_tools, connector_tools_for,
+    execute_connector_tool_after_approval, execute_connector_tool_from_payload,
+    register_connector_tools, test_connector,
+};
 use titan_core::{
-    CoreEvent, Goal, GoalStatus, StepPermission, StepResult, TaskPipelineConfig, TraceEvent,
-    build_task_plan, execute_task_plan_with_broker,
+    ASK_USER_TOOL, CANARY_CONTINUE_TOOL, CoreEvent, ExecutionOptions, Goal, GoalIntent,
+    GoalPriority, GoalStatus, LlmPlanner, ModelClient, OutputTransform, ReflectionGenerator,
+    RunStepCache, StepCache, StepHold, StepHoldCheck, StepKind, StepPermission, StepResult,
+    TaskPipelineConfig, ToolCatalogEntry, TraceEvent, build_task_plan_with_catalog,
+    build_task_plan_with_planner, classify_intent, estimate_tokens, reflect_run,
+    resume_task_plan_with_cache,
+};
+use titan_memory::approval_payload::{
+    AllowlistAction, ApprovalPayload, ConfigActivationPayload, ConfigAllowlistPayload,
+    decode_approval_payload, encode_approval_payload,
+};
+use titan_memory::{
+    AccessLogEntry, ChannelTarget, ClarificationRecord, GOAL_LABEL_AUTO, GoalCheckpointRecord,
+    MemoryStore, OutboxMessage, PendingInputRecord, PlanExperiment, QueuedGoalRecord,
+    ReflectionOutcome, RiskMode, RunPersistenceBundle, RunningGoalCounts, SessionRecord,
+    UsageEntry,
 };
-use titan_memory::{MemoryStore, RiskMode, RunPersistenceBundle};
 use titan_tools::{PolicyEngine, ToolExecutionContext, ToolExecutor, ToolRegistry, ToolRiskMode};
 
+pub use commands::{
+    CommandContext, CommandHandler, CommandRegistry, CommandRole, CommandSpec, Completion,
+    complete_slash_command,
+};
+pub use crash::{CrashReport, CrashReporter, scrub_diagnostic};
+pub use events::{EventBus, EventMetrics};
+pub use federation::{
+    FEDERATION_ACTOR_PREFIX, FederationSyncSummary, federation_peer_for, intent_label,
+    sync_remote_goals,
+};
+pub use lease::{WriterLease, process_writer_id, remote_writer};
+pub use model::{
+    CachingModelClient, HealthTrackingModelClient, MODEL_UNREACHABLE, ProviderModelClient,
+    RecordingModelClient, ReplayModelClient,
+};
+pub use plan_preview::{PlanPreview, PlanPreviewCandidate, PlanPreviewStep};
+pub use progress::{CandidateSummary, ProgressSink, RunProgress};
+pub use telegram::{TelegramReply, route_telegram_update};
+pub use undo::UndoJournal;
+pub use workspace::{ContentProvenance, WorkspaceSnapshot, unmerged_overlaps};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Channel {
     Cli,
     Discord,
+    Telegram,
     Webchat,
 }
 
@@ -22,9 +81,20 @@ impl Channel {
         match self {
             Self::Cli => "cli",
             Self::Discord => "discord",
+            Self::Telegram => "telegram",
             Self::Webchat => "webchat",
         }
     }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "cli" => Some(Self::Cli),
+            "discord" => Some(Self::Discord),
+            "telegram" => Some(Self::Telegram),
+            "webchat" => Some(Self::Webchat),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -33,6 +103,15 @@ pub struct InboundEvent {
     pub actor_id: String,
     pub text: String,
     pub dedupe_key: Option<String>,
+    /// Channel-specific conversation id (e.g. a Discord channel id) that run replies go to.
+    pub reply_target: Option<String>,
+    /// Caps the goal's estimated plan tokens, ahead of template and config budgets.
+    pub token_budget: Option<u64>,
+    pub priority: GoalPriority,
+    /// Client address, for requests that came through the web server.
+    pub remote_addr: Option<String>,
+    /// Stop after planning; see [`Self::with_plan_only`].
+    pub plan_only: bool,
 }
 
 impl InboundEvent {
@@ -42,10 +121,57 @@ impl InboundEvent {
             actor_id: actor_id.into(),
             text: text.into(),
             dedupe_key: None,
+            reply_target: None,
+            token_budget: None,
+            priority: GoalPriority::Normal,
+            remote_addr: None,
+            plan_only: false,
         }
     }
+
+    pub fn with_remote_addr(mut self, remote_addr: impl Into<String>) -> Self {
+        self.remote_addr = Some(remote_addr.into());
+        self
+    }
+
+    pub fn with_token_budget(mut self, token_budget: Option<u64>) -> Self {
+        self.token_budget = token_budget;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: GoalPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Plans the goal and stores the plan without running it; see [`PlanPreview`]. The event
+    /// is never forwarded to a federation peer, held for the model, or queued behind the
+    /// concurrency limit, and it is not recorded as budget usage. Allowlist and budget
+    /// refusals still apply.
+    pub fn with_plan_only(mut self) -> Self {
+        self.plan_only = true;
+        self
+    }
+
+    /// Queues the run reply in the outbox so it is sent even if this process dies first.
+    pub fn with_reply_target(mut self, target: impl Into<String>) -> Self {
+        self.reply_target = Some(target.into());
+        self
+    }
+
+    pub fn with_dedupe_key(mut self, dedupe_key: Option<String>) -> Self {
+        self.dedupe_key = dedupe_key
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+        self
+    }
 }
 
+/// `ProcessedEvent::goal_id` of a message the activation or allowlist policy ignored.
+pub const POLICY_BLOCKED_GOAL_ID: &str = "policy_blocked";
+/// `ProcessedEvent::goal_id` of a request refused because a usage budget is spent.
+pub const BUDGET_BLOCKED_GOAL_ID: &str = "budget_blocked";
+
 #[derive(Debug, Clone)]
 pub struct ProcessedEvent {
     pub session_id: String,
@@ -53,12 +179,29 @@ pub struct ProcessedEvent {
     pub goal_status: GoalStatus,
     pub pending_approval_id: Option<String>,
     pub summary: String,
+    pub reply_outbox_id: Option<i64>,
+    /// Set for `plan_only` events, which stop after planning.
+    pub plan: Option<PlanPreview>,
+}
+
+/// Result for one item of [`TitanGatewayRuntime::process_event_batch`].
+#[derive(Debug, Clone)]
+pub enum BatchItemOutcome {
+    Processed(ProcessedEvent),
+    /// The dedupe key already belongs to an earlier goal, which is left untouched.
+    Duplicate {
+        goal_id: String,
+        status: String,
+    },
+    Failed(String),
 }
 
 #[derive(Debug, Clone)]
 pub struct ChatCommandResult {
     pub session_id: String,
     pub response: String,
+    /// Outbox row holding `response`; mark it delivered after sending it directly.
+    pub reply_outbox_id: Option<i64>,
 }
 
 pub struct TitanGatewayRuntime {
@@ -66,6 +209,10 @@ pub struct TitanGatewayRuntime {
     workspace_root: PathBuf,
     db_path: PathBuf,
     config_path: Option<PathBuf>,
+    commands: CommandRegistry,
+    reflection_generator: Option<Arc<dyn ReflectionGenerator + Send + Sync>>,
+    planner_model: Option<Arc<dyn ModelClient + Send + Sync>>,
+    progress: Option<Arc<dyn ProgressSink>>,
 }
 
 impl TitanGatewayRuntime {
@@ -75,6 +222,10 @@ impl TitanGatewayRuntime {
             workspace_root,
             db_path,
             config_path: None,
+            commands: CommandRegistry::builtin(),
+            reflection_generator: None,
+            planner_model: None,
+            progress: None,
         }
     }
 
@@ -83,6 +234,43 @@ impl TitanGatewayRuntime {
         self
     }
 
+    /// Model-backed reflections for finished runs; without one, or when it fails, runs get
+    /// the deterministic reflection.
+    pub fn with_reflection_generator(
+        mut self,
+        generator: Arc<dyn ReflectionGenerator + Send + Sync>,
+    ) -> Self {
+        self.reflection_generator = Some(generator);
+        self
+    }
+
+    /// Plans goals with this model instead of the one `execution.model_planner` would use.
+    pub fn with_planner_model(mut self, model: Arc<dyn ModelClient + Send + Sync>) -> Self {
+        self.planner_model = Some(model);
+        self
+    }
+
+    /// Reports plans, steps, and traces of the goals this runtime runs while they run.
+    pub fn with_progress_sink(mut self, sink: Arc<dyn ProgressSink>) -> Self {
+        self.progress = Some(sink);
+        self
+    }
+
+    fn emit_progress(&self, progress: impl FnOnce() -> RunProgress) {
+        if let Some(sink) = &self.progress {
+            sink.emit(progress());
+        }
+    }
+
+    /// Adds a slash command for every channel this runtime serves.
+    pub fn register_command(&mut self, spec: CommandSpec) -> Result<()> {
+        self.commands.register(spec)
+    }
+
+    pub fn commands(&self) -> &CommandRegistry {
+        &self.commands
+    }
+
     pub fn set_mode(&mut self, mode: AutonomyMode) {
         self.mode = mode;
     }
@@ -91,29 +279,288 @@ impl TitanGatewayRuntime {
         self.mode.clone()
     }
 
+    /// The configured mode, or Supervised while safe mode is active.
+    pub fn effective_mode(&self, store: &MemoryStore) -> Result<AutonomyMode> {
+        Ok(if store.safe_mode_state()?.active {
+            AutonomyMode::Supervised
+        } else {
+            self.mode.clone()
+        })
+    }
+
     pub fn process_chat_input(&self, inbound: InboundEvent) -> Result<ChatCommandResult> {
+        let channel = inbound.channel;
+        // Commands are logged by name with the outcome of the role check, or `denied` when
+        // the approver list refuses the decision; plain messages by the session they landed in.
+        let command = match parse_slash_command(inbound.text.trim()) {
+            None => None,
+            Some(command) => {
+                let head = command.split_whitespace().next().unwrap_or_default();
+                Some(match self.commands.lookup(head) {
+                    None => (head.chars().take(32).collect::<String>(), Some("unknown")),
+                    Some(spec) => (
+                        spec.name.to_string(),
+                        (self.actor_role(&inbound)? < spec.role).then_some("denied"),
+                    ),
+                })
+            }
+        };
+        let logged = inbound.clone();
+        let result = self.route_chat_input(inbound);
+        let (kind, action, outcome) = match (&command, &result) {
+            (Some((name, Some(refused))), _) => ("command", name.as_str(), *refused),
+            (Some((name, None)), Ok(result))
+                if result
+                    .response
+                    .starts_with("approval_status=approver_not_allowed") =>
+            {
+                ("command", name.as_str(), "denied")
+            }
+            (Some((name, None)), Ok(_)) => ("command", name.as_str(), "ok"),
+            (Some((name, None)), Err(_)) => ("command", name.as_str(), "error"),
+            (None, Ok(result)) => ("message", result.session_id.as_str(), "ok"),
+            (None, Err(_)) => ("message", "", "error"),
+        };
+        self.log_access(&logged, kind, action, outcome)?;
+        let mut result = result?;
+        self.attach_approval_link(channel, &mut result)?;
+        Ok(result)
+    }
+
+    fn log_access(
+        &self,
+        inbound: &InboundEvent,
+        kind: &str,
+        action: &str,
+        outcome: &str,
+    ) -> Result<()> {
+        let store = MemoryStore::open(&self.db_path)?;
+        let cfg = load_runtime_config(self.config_path.as_deref())?;
+        let entry = AccessLogEntry {
+            id: 0,
+            at_ms: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as i64,
+            channel: inbound.channel.as_str().to_string(),
+            actor_id: inbound.actor_id.clone(),
+            remote_addr: inbound.remote_addr.clone(),
+            kind: kind.to_string(),
+            action: action.to_string(),
+            outcome: outcome.to_string(),
+            anomaly: None,
+        };
+        access::record_access(&store, &cfg, &entry)?;
+        Ok(())
+    }
+
+    fn route_chat_input(&self, inbound: InboundEvent) -> Result<ChatCommandResult> {
         let trimmed = inbound.text.trim();
         if let Some(command) = parse_slash_command(trimmed) {
             let output = self.handle_slash_command(&inbound, &command)?;
             return Ok(output);
         }
-        let event_result = self.process_event(inbound)?;
-        Ok(ChatCommandResult {
-            session_id: event_result.session_id,
-            response: format!(
-                "goal={} status={} summary={}{}",
-                event_result.goal_id,
-                event_result.goal_status.as_str(),
-                event_result.summary,
-                event_result
-                    .pending_approval_id
-                    .map(|id| format!(" approval_pending={id}"))
-                    .unwrap_or_default()
-            ),
-        })
+        if let Some(output) = self.answer_template_prompt(&inbound)? {
+            return Ok(output);
+        }
+        if let Some(output) = self.answer_clarification(&inbound)? {
+            return Ok(output);
+        }
+        if let Some(output) = self.answer_pending_input(&inbound)? {
+            return Ok(output);
+        }
+        let event_result = self.process_goal(
+            inbound,
+            GoalRunOptions {
+                clarify_unsure: true,
+                ..GoalRunOptions::default()
+            },
+        )?;
+        Ok(event_chat_result(event_result))
+    }
+
+    /// Follows ` approval_pending=<id>` with a signed dashboard link when
+    /// `approvals.dashboard_url` and the link secret are configured. Discord replies also get
+    /// the pending write's diff, so approvers can judge it without leaving the channel.
+    fn attach_approval_link(&self, channel: Channel, result: &mut ChatCommandResult) -> Result<()> {
+        let Some(approval_id) = result
+            .response
+            .split_whitespace()
+            .find_map(|word| word.strip_prefix("approval_pending="))
+            .map(str::to_string)
+        else {
+            return Ok(());
+        };
+        let store = MemoryStore::open(&self.db_path)?;
+        let Some(approval) = store.get_approval_request(&approval_id)? else {
+            return Ok(());
+        };
+        let cfg = load_runtime_config(self.config_path.as_deref())?;
+        if let (Some(dashboard_url), Some(secret)) = (
+            cfg.approvals.dashboard_url.as_deref(),
+            cfg.approvals.link_secret(),
+        ) {
+            let link = titan_comms::approval_deep_link(
+                dashboard_url,
+                &secret,
+                &approval.id,
+                approval.expires_at_ms,
+            );
+            result.response.push_str(&format!(" approval_url={link}"));
+        }
+        if channel == Channel::Discord
+            && let Some(diff) = approval_diff_preview(&self.workspace_root, &approval)
+        {
+            result.response.push_str(&format!("\n```diff\n{diff}\n```"));
+        }
+        Ok(())
+    }
+
+    /// True when the actor's next plain message answers a `/run` prompt, a clarification
+    /// question, or an `AskUser` step, so channels that only forward goal-like messages must
+    /// forward it anyway.
+    pub fn awaiting_reply(&self, channel: Channel, actor_id: &str) -> Result<bool> {
+        let store = MemoryStore::open(&self.db_path)?;
+        let session = store.get_or_create_active_session(channel.as_str(), actor_id)?;
+        Ok(store.template_prompt(&session.id)?.is_some()
+            || store.clarification(&session.id)?.is_some()
+            || store.pending_input(&session.id)?.is_some())
     }
 
     pub fn process_event(&self, inbound: InboundEvent) -> Result<ProcessedEvent> {
+        let logged = inbound.clone();
+        let result = self.process_goal(inbound, GoalRunOptions::default());
+        match &result {
+            Ok(processed) => self.log_access(
+                &logged,
+                "event",
+                &processed.goal_id,
+                processed.goal_status.as_str(),
+            )?,
+            Err(_) => self.log_access(&logged, "event", "", "error")?,
+        }
+        result
+    }
+
+    /// Continues goals an urgent goal paused once it has finished, highest priority first.
+    /// Stops early if another urgent goal starts meanwhile; its pauses resume on a later call.
+    pub fn resume_paused_goals(&self) -> Result<Vec<ProcessedEvent>> {
+        let store = MemoryStore::open(&self.db_path)?;
+        let cfg = load_runtime_config(self.config_path.as_deref())?;
+        let mut resumed = Vec::new();
+        for checkpoint in store.resumable_goal_checkpoints()? {
+            if store.waiting_urgent_goal(&checkpoint.goal_id)?.is_some() {
+                break;
+            }
+            let running = store.running_goal_counts(&checkpoint.channel, &checkpoint.session_id)?;
+            if concurrency_limit(&cfg.execution, running).is_some() {
+                continue;
+            }
+            let Some(channel) = Channel::parse(&checkpoint.channel) else {
+                continue;
+            };
+            let inbound = InboundEvent::new(
+                channel,
+                checkpoint.actor_id.clone(),
+                checkpoint.request_text.clone(),
+            );
+            resumed.push(self.process_goal(
+                inbound,
+                GoalRunOptions {
+                    resumed: Some(&checkpoint),
+                    ..GoalRunOptions::default()
+                },
+            )?);
+        }
+        Ok(resumed)
+    }
+
+    /// Starts queued goals, highest priority and oldest first, while the concurrency limits
+    /// leave room. Goals whose channel or session is still full wait for a later call.
+    pub fn start_queued_goals(&self) -> Result<Vec<ProcessedEvent>> {
+        let store = MemoryStore::open(&self.db_path)?;
+        let cfg = load_runtime_config(self.config_path.as_deref())?;
+        // Goals held for a down provider go back in line once a probe is due; the first one
+        // probes, and the rest are held again if it fails.
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        let provider = store.model_provider_state()?;
+        if provider.degraded
+            && !provider.holds_model_off(now_ms, cfg.model.outage_probe_secs as i64 * 1000)
+        {
+            store.requeue_goals_waiting_for_model()?;
+        }
+        let mut started = Vec::new();
+        for queued in store.queued_goals()? {
+            let running = store.running_goal_counts(&queued.channel, &queued.session_id)?;
+            if cfg
+                .execution
+                .max_concurrent_goals
+                .is_some_and(|max| running.global >= max as usize)
+            {
+                break;
+            }
+            if concurrency_limit(&cfg.execution, running).is_some() {
+                continue;
+            }
+            let Some(channel) = Channel::parse(&queued.channel) else {
+                continue;
+            };
+            let inbound = InboundEvent::new(
+                channel,
+                queued.actor_id.clone(),
+                queued.request_text.clone(),
+            );
+            started.push(self.process_goal(
+                inbound,
+                GoalRunOptions {
+                    dequeued: Some(&queued),
+                    ..GoalRunOptions::default()
+                },
+            )?);
+        }
+        Ok(started)
+    }
+
+    /// `/plan <request>`: plans the request as a `plan_only` goal and replies with the selected
+    /// candidate's steps, marking those that would wait for approval.
+    fn handle_plan_command(&self, inbound: &InboundEvent, args: &[&str]) -> Result<String> {
+        if args.is_empty() {
+            return Ok("usage: /plan <request>".to_string());
+        }
+        let mut goal_inbound = inbound.clone().with_plan_only();
+        goal_inbound.text = args.join(" ");
+        goal_inbound.reply_target = None;
+        Ok(self
+            .process_goal(goal_inbound, GoalRunOptions::default())?
+            .summary)
+    }
+
+    /// `/priority <low|normal|urgent> <request>`: runs the request at that priority.
+    fn handle_priority_command(&self, inbound: &InboundEvent, args: &[&str]) -> Result<String> {
+        let (Some(level), Some(_)) = (args.first(), args.get(1)) else {
+            return Ok("usage: /priority <low|normal|urgent> <request>".to_string());
+        };
+        let Some(priority) = GoalPriority::parse(level) else {
+            return Ok(format!(
+                "unknown priority '{level}': use low, normal, or urgent"
+            ));
+        };
+        // Slash replies go back directly, so the run must not also queue one in the outbox.
+        let mut goal_inbound = inbound.clone().with_priority(priority);
+        goal_inbound.text = args[1..].join(" ");
+        goal_inbound.reply_target = None;
+        let processed = self.process_goal(goal_inbound, GoalRunOptions::default())?;
+        Ok(event_chat_result(processed).response)
+    }
+
+    fn process_goal(
+        &self,
+        inbound: InboundEvent,
+        options: GoalRunOptions<'_>,
+    ) -> Result<ProcessedEvent> {
         let mut store = MemoryStore::open(&self.db_path)?;
         store.apply_yolo_expiry("gateway")?;
         let cfg = load_runtime_config(self.config_path.as_deref())?;
@@ -122,95 +569,587 @@ impl TitanGatewayRuntime {
         let risk_mode_str = risk_mode.as_str().to_string();
         let session =
             store.get_or_create_active_session(inbound.channel.as_str(), &inbound.actor_id)?;
-        if !is_message_allowed(&inbound, &session, self.config_path.as_deref())? {
-            let detail = "Message ignored by activation/allowlist policy".to_string();
-            store.add_trace_event(&TraceEvent::new(
-                session.id.clone(),
-                "command_invoked",
-                detail.clone(),
-            ))?;
+        if options.resumed.is_none()
+            && !is_message_allowed(&inbound, &session, self.config_path.as_deref())?
+        {
+            // There is no goal to trace against; the access log records the refusal.
+            return Ok(ProcessedEvent {
+                session_id: session.id,
+                goal_id: POLICY_BLOCKED_GOAL_ID.to_string(),
+                goal_status: GoalStatus::Cancelled,
+                pending_approval_id: None,
+                summary: "Message ignored by activation/allowlist policy".to_string(),
+                reply_outbox_id: None,
+                plan: None,
+            });
+        }
+
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        // Resumed and dequeued goals were admitted already, and an answer finishes a goal
+        // that was; only fresh requests count against budgets.
+        let new_request = options.resumed.is_none() && options.dequeued.is_none();
+        if new_request
+            && options.answered.is_none()
+            && let Some(refusal) = usage_budget_refusal(&store, &cfg.usage, &session, now_ms)?
+        {
             return Ok(ProcessedEvent {
                 session_id: session.id,
-                goal_id: "policy_blocked".to_string(),
+                goal_id: BUDGET_BLOCKED_GOAL_ID.to_string(),
                 goal_status: GoalStatus::Cancelled,
                 pending_approval_id: None,
-                summary: detail,
+                summary: refusal,
+                reply_outbox_id: None,
+                plan: None,
             });
         }
 
         store.set_session_queue_depth(&session.id, 1)?;
         store.clear_session_stop(&session.id)?;
-        store.add_session_message(&session.id, "user", inbound.text.trim(), false)?;
+        if options.resumed.is_none() {
+            store.add_session_message(&session.id, "user", inbound.text.trim(), false)?;
+        }
+        // A plan preview runs nothing, so it is not charged.
+        if new_request && !inbound.plan_only {
+            let tokens = estimate_tokens(inbound.text.trim());
+            store.record_session_usage(
+                &session,
+                &UsageEntry {
+                    role: "user".to_string(),
+                    tokens,
+                    cost_usd: usage_cost_usd(&cfg.usage, tokens),
+                    ..UsageEntry::default()
+                },
+            )?;
+        }
 
-        let registry = ToolRegistry::with_defaults();
+        let mut registry = ToolRegistry::with_defaults();
+        register_connector_tools(&store, &mut registry)?;
         let mut execution_ctx =
             ToolExecutionContext::default_for_workspace(self.workspace_root.clone());
         execution_ctx.bypass_path_guard = matches!(risk_mode, RiskMode::Yolo)
             && risk_state.yolo_bypass_path_guard
             && cfg.security.yolo_bypass_path_guard;
+        execution_ctx.env = store.session_env(&session.id)?;
+        execution_ctx.scan_secrets = cfg.security.scan_writes_for_secrets;
+        let mode = self.effective_mode(&store)?;
+        if let Some(profile) = cfg.sandbox.profile_for(&mode, &risk_mode_str) {
+            execution_ctx.apply_sandbox(profile);
+        }
 
-        let goal_description = format!("[{}] {}", inbound.channel.as_str(), inbound.text.trim());
-        let goal = Goal::new(goal_description).with_dedupe_key(inbound.dedupe_key.clone());
+        // An answered question replans the original request; the reply is the step's output.
+        let request_text = options
+            .answered
+            .map(|pending| pending.request_text.as_str())
+            .or(options
+                .resumed
+                .map(|checkpoint| checkpoint.request_text.as_str()))
+            .or(options.dequeued.map(|queued| queued.request_text.as_str()))
+            .unwrap_or(inbound.text.as_str());
+        let goal_description = format!("[{}] {}", inbound.channel.as_str(), request_text.trim());
+        let resumed_goal_id = options
+            .clarified
+            .map(|clarified| &clarified.goal_id)
+            .or(options.answered.map(|pending| &pending.goal_id))
+            .or(options.resumed.map(|checkpoint| &checkpoint.goal_id))
+            .or(options.dequeued.map(|queued| &queued.goal_id));
+        let goal = match resumed_goal_id {
+            Some(goal_id) => Goal {
+                id: goal_id.clone(),
+                description: goal_description,
+                status: GoalStatus::Pending,
+                dedupe_key: None,
+                priority: store
+                    .get_goal(goal_id)?
+                    .and_then(|stored| GoalPriority::parse(&stored.priority))
+                    .unwrap_or(inbound.priority),
+            },
+            None => Goal::new(goal_description)
+                .with_dedupe_key(inbound.dedupe_key.clone())
+                .with_priority(inbound.priority),
+        };
+        // Facts the peer opted in to sharing; only new requests add to them.
+        let peer_context = profile::peer_context(
+            &store,
+            inbound.channel.as_str(),
+            &inbound.actor_id,
+            request_text,
+            resumed_goal_id.is_none(),
+        )?;
         let event = CoreEvent::new(
             inbound.channel.as_str(),
             inbound.actor_id.clone(),
-            inbound.text.clone(),
+            request_text,
         )
-        .with_dedupe_key(inbound.dedupe_key.clone());
-        let plan = build_task_plan(&goal.id, &event, &TaskPipelineConfig { candidate_count: 3 });
-        let result = execute_task_plan_with_broker(
+        .with_dedupe_key(inbound.dedupe_key.clone())
+        .with_context(peer_context);
+        let catalog = planner_tool_catalog(&store, &self.workspace_root)?;
+        let intent = classify_intent(&event.text, &catalog);
+        let labels = auto_goal_labels(inbound.channel, &intent);
+        if resumed_goal_id.is_none()
+            && !inbound.plan_only
+            && !inbound.actor_id.starts_with(FEDERATION_ACTOR_PREFIX)
+            && let Some(peer) = federation_peer_for(&cfg.federation, &intent)
+        {
+            return forward_goal(
+                &store,
+                &session,
+                &inbound,
+                request_text,
+                goal,
+                &labels,
+                peer,
+            );
+        }
+        let experiment = experiment_arm(&cfg.experiment, &goal.id);
+        let variant_planner = experiment
+            .as_ref()
+            .filter(|arm| arm.variant == EXPERIMENT_VARIANT)
+            .and(cfg.experiment.planner);
+        let use_model = match variant_planner {
+            Some(ExperimentPlanner::Rules) => false,
+            Some(ExperimentPlanner::Model) => true,
+            None => self.planner_model.is_some() || cfg.execution.model_planner,
+        };
+        let provider_model = (use_model && self.planner_model.is_none())
+            .then(|| ProviderModelClient::new(&cfg.model));
+        let planner_model = self
+            .planner_model
+            .as_deref()
+            .map(|model| model as &dyn ModelClient)
+            .or(provider_model
+                .as_ref()
+                .map(|model| model as &dyn ModelClient))
+            .filter(|_| use_model);
+        // While the provider is down, plan with rules only. Once the probe interval passes a
+        // request tries the model again, and its reply ends degraded mode.
+        let model_held_off = planner_model.is_some()
+            && store
+                .model_provider_state()?
+                .holds_model_off(now_ms, cfg.model.outage_probe_secs as i64 * 1000);
+        // A model planner can take requests the keyword intents do not cover.
+        if options.clarify_unsure
+            && planner_model.is_none()
+            && matches!(intent, GoalIntent::GenericRecon)
+        {
+            return request_clarification(
+                &store,
+                &session,
+                &inbound,
+                goal,
+                &labels,
+                &catalog,
+                &risk_mode_str,
+            );
+        }
+        let preferred_candidate = options
+            .answered
+            .map(|pending| pending.candidate_id.clone())
+            .or(options
+                .resumed
+                .map(|checkpoint| checkpoint.candidate_id.clone()))
+            .or(options
+                .dequeued
+                .and_then(|queued| queued.preferred_candidate.clone()))
+            .or_else(|| {
+                options
+                    .template
+                    .as_ref()
+                    .and_then(|run| run.template.plan.clone())
+            });
+        let token_budget = inbound
+            .token_budget
+            .or(options.dequeued.and_then(|queued| queued.token_budget))
+            .or(options
+                .template
+                .as_ref()
+                .and_then(|run| run.template.token_budget))
+            .or(cfg.execution.token_budget);
+        if model_held_off
+            && !inbound.plan_only
+            && matches!(intent, GoalIntent::GenericRecon)
+            && (resumed_goal_id.is_none() || options.dequeued.is_some())
+        {
+            let queued = QueuedGoalRecord {
+                goal_id: goal.id.clone(),
+                session_id: session.id.clone(),
+                channel: inbound.channel.as_str().to_string(),
+                actor_id: inbound.actor_id.clone(),
+                request_text: request_text.trim().to_string(),
+                token_budget,
+                preferred_candidate,
+                created_at_ms: now_ms,
+            };
+            return hold_goal_for_model(
+                &store,
+                &session,
+                &inbound,
+                goal,
+                &labels,
+                &queued,
+                options.dequeued.is_some(),
+            );
+        }
+        let planner_model = planner_model.filter(|_| !model_held_off);
+        // A plan-only goal never runs, so it takes no concurrency slot.
+        if resumed_goal_id.is_none()
+            && !inbound.plan_only
+            && let Some(limit) = concurrency_limit(
+                &cfg.execution,
+                store.running_goal_counts(inbound.channel.as_str(), &session.id)?,
+            )
+        {
+            let queued = QueuedGoalRecord {
+                goal_id: goal.id.clone(),
+                session_id: session.id.clone(),
+                channel: inbound.channel.as_str().to_string(),
+                actor_id: inbound.actor_id.clone(),
+                request_text: request_text.trim().to_string(),
+                token_budget,
+                preferred_candidate,
+                created_at_ms: now_ms,
+            };
+            return queue_goal(&store, &session, &inbound, goal, &labels, &queued, &limit);
+        }
+        // Past memories close to the request; a failing embedder never blocks the goal.
+        let recall = store.recall_similar(request_text, cfg.embeddings.recall_top_k);
+        let pipeline = TaskPipelineConfig {
+            candidate_count: experiment
+                .as_ref()
+                .filter(|arm| arm.variant == EXPERIMENT_VARIANT)
+                .and(cfg.experiment.candidate_count)
+                .unwrap_or(3),
+            preferred_candidate,
+            token_budget,
+        };
+        let mut model_stats = (0, 0);
+        let mut plan = match planner_model {
+            // A goal replans when it resumes; reuse the reply it first ran with.
+            Some(model) => match store.model_plan(&goal.id)? {
+                Some(reply) => build_task_plan_with_planner(
+                    &goal.id,
+                    &event,
+                    &pipeline,
+                    &catalog,
+                    &LlmPlanner::new(&ReplayModelClient(reply)),
+                ),
+                None => {
+                    let tracked =
+                        HealthTrackingModelClient::new(model, &store, cfg.model.outage_threshold);
+                    let caching = CachingModelClient::new(
+                        &tracked,
+                        &store,
+                        ProviderModelClient::cache_identity(&cfg.model),
+                        cfg.model.cache_ttl_secs,
+                    );
+                    let recording = RecordingModelClient::new(&caching);
+                    let plan = build_task_plan_with_planner(
+                        &goal.id,
+                        &event,
+                        &pipeline,
+                        &catalog,
+                        &LlmPlanner::new(&recording),
+                    );
+                    if let Some(reply) = recording.reply() {
+                        store.save_model_plan(&goal.id, cfg.model.provider.as_str(), &reply)?;
+                    }
+                    model_stats = caching.stats();
+                    plan
+                }
+            },
+            None => build_task_plan_with_catalog(&goal.id, &event, &pipeline, &catalog),
+        };
+        if let Some(arm) = &experiment {
+            plan.traces.insert(
+                0,
+                TraceEvent::new(
+                    goal.id.clone(),
+                    "experiment_assigned",
+                    format!("experiment={} variant={}", arm.name, arm.variant),
+                ),
+            );
+        }
+        if model_held_off {
+            plan.traces.insert(
+                0,
+                TraceEvent::new(
+                    goal.id.clone(),
+                    "model_degraded",
+                    "model provider unreachable; planned with rules only",
+                ),
+            );
+        }
+        for (tool, spec) in &cfg.execution.output_transforms {
+            let transform = OutputTransform::parse(spec)
+                .map_err(|err| anyhow!("execution.output_transforms.{tool}: {err}"))?;
+            for step in plan
+                .candidates
+                .iter_mut()
+                .flat_map(|candidate| candidate.steps.iter_mut())
+                .filter(|step| step.tool_name == *tool && step.kind == StepKind::ToolCall)
+            {
+                step.transform = Some(transform.clone());
+            }
+        }
+        if !event.context.is_empty() {
+            plan.traces.insert(
+                0,
+                TraceEvent::new(
+                    goal.id.clone(),
+                    "peer_context",
+                    format!("facts={}", event.context.len()),
+                ),
+            );
+        }
+        match recall {
+            Ok(recalled) if !recalled.is_empty() => plan.traces.insert(
+                0,
+                TraceEvent::new(
+                    goal.id.clone(),
+                    "memory_recalled",
+                    recalled
+                        .iter()
+                        .map(|memory| {
+                            format!(
+                                "{}#{} {:.2} {}",
+                                memory.kind, memory.id, memory.score, memory.text
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" | "),
+                ),
+            ),
+            Ok(_) => {}
+            Err(err) => plan.traces.insert(
+                0,
+                TraceEvent::new(goal.id.clone(), "memory_recall_failed", err.to_string()),
+            ),
+        }
+        self.emit_progress(|| RunProgress::plan(&goal.id, &plan));
+        if inbound.plan_only {
+            return persist_plan_only(
+                &store,
+                &session,
+                &inbound,
+                goal,
+                &labels,
+                &plan,
+                PlanPreview::new(&plan, &registry, &mode, &risk_mode)?,
+            );
+        }
+        let mutating_steps: Vec<&titan_core::Step> = plan.candidates[plan.selected_index]
+            .steps
+            .iter()
+            .filter(|step| {
+                matches!(
+                    step.permission,
+                    StepPermission::Write | StepPermission::Exec
+                )
+            })
+            .collect();
+        let snapshot = if mutating_steps.is_empty() {
+            WorkspaceSnapshot::default()
+        } else {
+            WorkspaceSnapshot::capture(&self.workspace_root)
+        };
+        let targets: Vec<String> = mutating_steps
+            .iter()
+            .filter_map(|step| titan_core::write_target(step))
+            .map(str::to_string)
+            .collect();
+        let mutating_tools: Vec<String> = mutating_steps
+            .iter()
+            .map(|step| step.tool_name.clone())
+            .collect();
+        let file_writes: Vec<(String, String)> = mutating_steps
+            .iter()
+            .filter_map(|step| {
+                write_file_path(&step.tool_name, step.input.as_deref())
+                    .map(|path| (step.id.clone(), path))
+            })
+            .collect();
+        let overlaps = unmerged_overlaps(&store, &snapshot, &targets)?;
+        let undo = UndoJournal::capture(&self.workspace_root, &targets);
+        let prior = options
+            .answered
+            .map(|pending| {
+                let mut results = pending.results.clone();
+                results.push(StepResult {
+                    step_id: pending.step_id.clone(),
+                    tool_name: ASK_USER_TOOL.to_string(),
+                    status: "answered".to_string(),
+                    output: inbound.text.trim().to_string(),
+                });
+                results
+            })
+            .or(options.resumed.map(|checkpoint| checkpoint.results.clone()))
+            .unwrap_or_default();
+        // A goal is visible while it runs, so it counts toward concurrency limits and, when
+        // urgent, lower goals elsewhere yield to it.
+        match resumed_goal_id {
+            Some(goal_id) => store.update_goal_status(goal_id, GoalStatus::Planning)?,
+            None => {
+                let mut planning = goal.clone();
+                planning.status = GoalStatus::Planning;
+                store.create_goal_for_session(&planning, Some(&session.id))?;
+            }
+        }
+        store.add_goal_labels(&goal.id, &labels, GOAL_LABEL_AUTO)?;
+        if let Some(queued) = options.dequeued {
+            store.dequeue_goal(&queued.goal_id)?;
+        }
+        let goal_id = goal.id.clone();
+        let preemptible = goal.priority < GoalPriority::Urgent;
+        let preempt = || store.waiting_urgent_goal(&goal_id).ok().flatten();
+        let mut step_cache = SessionStepCache {
+            store: &store,
+            registry: &registry,
+            session_id: &session.id,
+            since_ms: now_ms.saturating_sub(cfg.execution.step_cache_ttl_ms as i64),
+            enabled: cfg.execution.step_cache_ttl_ms > 0,
+            run: RunStepCache::default(),
+        };
+        // Writes that look like they carry credentials wait for an operator override.
+        let secret_hold = |step: &titan_core::Step| {
+            let input = step.input.as_deref().unwrap_or_default().trim();
+            let blocked =
+                titan_tools::check_secret_write(&step.tool_name, input, &self.workspace_root)?;
+            Some(StepHold {
+                event_type: "secret_write_blocked".to_string(),
+                detail: blocked.trace_detail(&step.tool_name),
+            })
+        };
+        let run_step = |step: &titan_core::Step| -> Result<StepResult, String> {
+            let egress_trace = |err: anyhow::Error| {
+                if let Some(blocked) = err.downcast_ref::<titan_tools::EgressBlocked>() {
+                    let _ = store.add_trace_event(&TraceEvent::new(
+                        goal_id.clone(),
+                        "egress_blocked",
+                        blocked.trace_detail(&step.tool_name),
+                    ));
+                }
+                if let Some(blocked) = err.downcast_ref::<titan_tools::SecretWriteBlocked>() {
+                    let _ = store.add_trace_event(&TraceEvent::new(
+                        goal_id.clone(),
+                        "secret_write_blocked",
+                        blocked.trace_detail(&step.tool_name),
+                    ));
+                }
+                err.to_string()
+            };
+            if let Some(output) = self
+                .execute_catalog_step(&store, &cfg.skills, &inbound.actor_id, step)
+                .map_err(egress_trace)?
+            {
+                return Ok(StepResult {
+                    step_id: step.id.clone(),
+                    tool_name: step.tool_name.to_string(),
+                    status: "success".to_string(),
+                    output,
+                });
+            }
+            let tool = registry
+                .get(&step.tool_name)
+                .ok_or_else(|| format!("unknown tool '{}'", step.tool_name))?;
+            let tool_result = ToolExecutor::execute(tool, step.input.as_deref(), &execution_ctx)
+                .map_err(egress_trace)?;
+            Ok(StepResult {
+                step_id: step.id.clone(),
+                tool_name: step.tool_name.to_string(),
+                status: tool_result.status,
+                output: tool_result.output,
+            })
+        };
+        let result = resume_task_plan_with_cache(
             goal,
             plan,
+            ExecutionOptions {
+                canary_writes: cfg.execution.canary_writes,
+                preempt: preemptible.then_some(&preempt as &dyn Fn() -> Option<String>),
+                hold: cfg
+                    .security
+                    .scan_writes_for_secrets
+                    .then_some(&secret_hold as &StepHoldCheck<'_>),
+            },
+            prior,
+            &mut step_cache,
             |tool_name| {
-                let class = registry.get(tool_name).map(|tool| tool.class);
-                match class {
-                    Some(titan_tools::CapabilityClass::Read) => Some(StepPermission::Read),
-                    Some(titan_tools::CapabilityClass::Write) => Some(StepPermission::Write),
-                    Some(titan_tools::CapabilityClass::Exec) => Some(StepPermission::Exec),
-                    Some(titan_tools::CapabilityClass::Net) => Some(StepPermission::Net),
-                    None => None,
-                }
+                registry
+                    .get(tool_name)
+                    .map(|tool| step_permission_for_class(tool.class))
             },
-            |permission| {
-                let class = match permission {
-                    StepPermission::Read => titan_tools::CapabilityClass::Read,
-                    StepPermission::Write => titan_tools::CapabilityClass::Write,
-                    StepPermission::Exec => titan_tools::CapabilityClass::Exec,
-                    StepPermission::Net => titan_tools::CapabilityClass::Net,
-                };
-                let risk = if matches!(risk_mode, RiskMode::Yolo) {
-                    ToolRiskMode::Yolo
-                } else {
-                    ToolRiskMode::Secure
-                };
-                PolicyEngine::requires_approval_with_risk(self.mode.clone(), risk, class)
+            |tool_name, permission| {
+                step_requires_approval(&mode, &risk_mode, tool_name, permission)
             },
             |step| {
-                let tool = registry
-                    .get(&step.tool_name)
-                    .ok_or_else(|| format!("unknown tool '{}'", step.tool_name))?;
-                let tool_result =
-                    ToolExecutor::execute(tool, step.input.as_deref(), &execution_ctx)
-                        .map_err(|err| err.to_string())?;
-                Ok(StepResult {
+                self.emit_progress(|| RunProgress::StepStarted {
+                    goal_id: goal_id.clone(),
                     step_id: step.id.clone(),
-                    tool_name: step.tool_name.to_string(),
-                    status: tool_result.status,
-                    output: tool_result.output,
-                })
+                    tool_name: step.tool_name.clone(),
+                });
+                let result = run_step(step);
+                self.emit_progress(|| RunProgress::StepFinished {
+                    goal_id: goal_id.clone(),
+                    step_id: step.id.clone(),
+                    tool_name: step.tool_name.clone(),
+                    status: result
+                        .as_ref()
+                        .map_or("error".to_string(), |done| done.status.clone()),
+                });
+                result
             },
         );
         let mut run = result;
+        if run.undo_required {
+            let detail = if undo.is_empty() {
+                "no write targets journaled; exec side effects are not undoable".to_string()
+            } else {
+                format!("restored={}", undo.restore()?.join(","))
+            };
+            run.traces
+                .push(TraceEvent::new(run.goal.id.clone(), "undo_applied", detail));
+        }
+        for change in &overlaps {
+            run.traces.push(TraceEvent::new(
+                run.goal.id.clone(),
+                "workspace_overlap_warning",
+                format!(
+                    "path={} goal={} change={} (unmerged)",
+                    change.path,
+                    change.goal_id,
+                    change.change_kind.trim()
+                ),
+            ));
+        }
+        let (reflection, reflection_source) = reflect_run(
+            &run,
+            self.reflection_generator
+                .as_deref()
+                .filter(|_| !model_held_off)
+                .map(|generator| generator as &dyn ReflectionGenerator),
+        );
+        run.traces.push(TraceEvent::new(
+            run.goal.id.clone(),
+            "reflection_structured",
+            format!(
+                "source={} worked={} failed={} strategy_changes={}",
+                reflection_source,
+                reflection.worked.len(),
+                reflection.failed.len(),
+                reflection.strategy_changes.len()
+            ),
+        ));
+        let reflection = ReflectionOutcome {
+            reflection,
+            source: reflection_source.to_string(),
+        };
         for trace in &mut run.traces {
             trace.risk_mode = risk_mode.as_str().to_string();
         }
-        run.traces.insert(
-            0,
-            TraceEvent::new(run.goal.id.clone(), "goal_submitted", inbound.text.clone())
-                .with_risk_mode(risk_mode_str.clone()),
-        );
-        run.traces.insert(
-            1,
+        let mut opening = vec![
+            TraceEvent::new(run.goal.id.clone(), "goal_submitted", inbound.text.clone()),
             TraceEvent::new(
                 run.goal.id.clone(),
                 "event_received",
@@ -219,43 +1158,290 @@ impl TitanGatewayRuntime {
                     inbound.channel.as_str(),
                     inbound.actor_id
                 ),
-            )
-            .with_risk_mode(risk_mode_str),
+            ),
+        ];
+        if options.clarified.is_some() {
+            // Submission was traced when the question was asked.
+            opening = vec![TraceEvent::new(
+                run.goal.id.clone(),
+                "clarification_received",
+                inbound.text.trim(),
+            )];
+        } else if let Some(pending) = options.answered {
+            opening = vec![TraceEvent::new(
+                run.goal.id.clone(),
+                "input_received",
+                format!("{}: {}", pending.step_id, inbound.text.trim()),
+            )];
+        } else if let Some(checkpoint) = options.resumed {
+            opening = vec![TraceEvent::new(
+                run.goal.id.clone(),
+                "goal_resumed",
+                format!(
+                    "after urgent goal {}; {} steps already done",
+                    checkpoint.preempted_by,
+                    checkpoint.results.len()
+                ),
+            )];
+        } else if let Some(queued) = options.dequeued {
+            opening = vec![TraceEvent::new(
+                run.goal.id.clone(),
+                "goal_dequeued",
+                format!(
+                    "waited {}s for a concurrency slot",
+                    (now_ms - queued.created_at_ms).max(0) / 1000
+                ),
+            )];
+        }
+        if let Some(profile) = &execution_ctx.sandbox {
+            run.traces.insert(
+                0,
+                TraceEvent::new(
+                    run.goal.id.clone(),
+                    "sandbox_profile",
+                    format!(
+                        "{} mode={} risk={risk_mode_str}",
+                        profile.name,
+                        commands::mode_name(&mode)
+                    ),
+                )
+                .with_risk_mode(risk_mode_str.clone()),
+            );
+        }
+        let opening_len = opening.len();
+        run.traces.splice(
+            0..0,
+            opening
+                .into_iter()
+                .map(|trace| trace.with_risk_mode(risk_mode_str.clone())),
         );
-        store.create_goal_for_session(&run.goal, Some(&session.id))?;
+        if let Some(template) = &options.template {
+            run.traces.insert(
+                opening_len,
+                TraceEvent::new(
+                    run.goal.id.clone(),
+                    "goal_template_applied",
+                    template.describe(),
+                )
+                .with_risk_mode(risk_mode_str),
+            );
+        }
+        if options.clarified.is_some() {
+            store.update_goal_description(&run.goal.id, &run.goal.description)?;
+        }
+        let reply = inbound.reply_target.as_ref().map(|target| OutboxMessage {
+            operator: None,
+            event_kind: "run_reply".to_string(),
+            subject_id: run.goal.id.clone(),
+            channel: inbound.channel.as_str().to_string(),
+            target: target.clone(),
+            message: format!(
+                "goal={} status={} summary={}",
+                run.goal.id,
+                run.goal.status.as_str(),
+                run.reflection
+            ),
+        });
         let persisted = store.persist_run_bundle(RunPersistenceBundle {
             run: &run,
             source: inbound.channel.as_str(),
             requested_by: Some(inbound.actor_id.as_str()),
-            approval_ttl_ms: 300_000,
+            approval_ttl_ms: PolicyEngine::approval_ttl_ms(
+                run.pending_approval
+                    .as_ref()
+                    .map_or("", |action| action.capability.as_str()),
+            ),
+            reply,
+            approval_notices: approval_notice_targets(
+                &cfg.approvals,
+                inbound
+                    .reply_target
+                    .as_deref()
+                    .map(|target| (inbound.channel, target)),
+            ),
+            reflection: Some(&reflection),
+            experiment: experiment.as_ref(),
         })?;
+        let executed_tools: Vec<&str> = run
+            .step_results
+            .iter()
+            .map(|result| result.tool_name.as_str())
+            .filter(|tool| mutating_tools.iter().any(|name| name == tool))
+            .collect();
+        if !executed_tools.is_empty() {
+            let mut written = snapshot.attribute(
+                &store,
+                &self.workspace_root,
+                &run.goal.id,
+                &executed_tools.join(","),
+            )?;
+            written.extend(
+                file_writes
+                    .iter()
+                    .filter(|(step_id, _)| {
+                        run.step_results
+                            .iter()
+                            .any(|result| &result.step_id == step_id && result.status == "success")
+                    })
+                    .map(|(_, path)| path.clone()),
+            );
+            record_provenance(
+                &store,
+                &cfg.model,
+                &run.goal.id,
+                &executed_tools.join(","),
+                &written,
+            )?;
+        }
+        if let Some(request) = &run.pending_input {
+            let ttl_ms = cfg
+                .execution
+                .ask_user_ttl_ms
+                .unwrap_or(DEFAULT_ASK_USER_TTL_MS);
+            store.set_pending_input(&PendingInputRecord {
+                session_id: session.id.clone(),
+                goal_id: run.goal.id.clone(),
+                step_id: request.step_id.clone(),
+                question: request.question.clone(),
+                request_text: request_text.trim().to_string(),
+                candidate_id: run.plan.candidates[run.plan.selected_index].id.clone(),
+                results: run.step_results.clone(),
+                created_at_ms: now_ms,
+                expires_at_ms: now_ms.saturating_add(ttl_ms as i64),
+            })?;
+        }
+        if options.resumed.is_some() {
+            store.delete_goal_checkpoint(&run.goal.id)?;
+        }
+        if let Some(urgent) = &run.preempted_by {
+            store.save_goal_checkpoint(&GoalCheckpointRecord {
+                goal_id: run.goal.id.clone(),
+                session_id: session.id.clone(),
+                channel: inbound.channel.as_str().to_string(),
+                actor_id: inbound.actor_id.clone(),
+                request_text: request_text.trim().to_string(),
+                candidate_id: run.plan.candidates[run.plan.selected_index].id.clone(),
+                results: run.step_results.clone(),
+                preempted_by: urgent.clone(),
+                created_at_ms: now_ms,
+            })?;
+        }
         store.set_session_queue_depth(&session.id, 0)?;
         store.add_session_message(&session.id, "assistant", &run.reflection, false)?;
-        let pending_approval_id = persisted.approval_id;
+        // A reply costs the plan's model context plus the reply itself.
+        let tokens = run.plan.candidates[run.plan.selected_index].estimated_tokens
+            + estimate_tokens(&run.reflection);
+        store.record_session_usage(
+            &session,
+            &UsageEntry {
+                goal_id: Some(run.goal.id.clone()),
+                role: "assistant".to_string(),
+                tokens,
+                cost_usd: usage_cost_usd(&cfg.usage, tokens),
+                model_calls: model_stats.0,
+                model_cache_hits: model_stats.1,
+            },
+        )?;
+        deliver_outbox_ids(&store, &persisted.notification_outbox_ids)?;
+        for trace in &run.traces {
+            self.emit_progress(|| RunProgress::trace(trace));
+        }
 
+        let mut summary = run.reflection;
+        if run.goal.status == GoalStatus::Failed
+            && let Some(reason) =
+                store.evaluate_safe_mode(cfg.safe_mode.failure_threshold, "circuit_breaker")?
+        {
+            summary.push_str(&format!(" safe_mode=entered reason={reason}"));
+        }
+        if !overlaps.is_empty() {
+            let mut goals: Vec<&str> = overlaps.iter().map(|c| c.goal_id.as_str()).collect();
+            goals.sort_unstable();
+            goals.dedup();
+            summary.push_str(&format!(
+                " warning=unmerged_changes_from_goals:{}",
+                goals.join(",")
+            ));
+        }
         Ok(ProcessedEvent {
             session_id: session.id,
             goal_id: run.goal.id,
             goal_status: run.goal.status,
-            pending_approval_id,
-            summary: run.reflection,
+            pending_approval_id: persisted.approval_id,
+            summary,
+            reply_outbox_id: persisted.reply_outbox_id,
+            plan: None,
         })
     }
 
-    fn handle_slash_command(
-        &self,
-        inbound: &InboundEvent,
-        command: &str,
-    ) -> Result<ChatCommandResult> {
+    /// Runs a burst of inbound events one at a time, urgent first and low last, keeping
+    /// arrival order within a priority. Outcomes are returned in the order given.
+    ///
+    /// An event whose dedupe key already names a goal (including one created earlier in the
+    /// same batch) is reported as a duplicate, and a failing event does not stop the rest.
+    pub fn process_event_batch(&self, events: Vec<InboundEvent>) -> Result<Vec<BatchItemOutcome>> {
         let store = MemoryStore::open(&self.db_path)?;
-        let mut session =
-            store.get_or_create_active_session(inbound.channel.as_str(), &inbound.actor_id)?;
-        let trace_goal_id = store.last_goal_for_session(&session.id)?;
-        if let Some(goal_id) = trace_goal_id.as_deref() {
-            store.add_trace_event(&TraceEvent::new(
+        let mut queue: Vec<(usize, InboundEvent)> = events.into_iter().enumerate().collect();
+        queue.sort_by_key(|(_, inbound)| std::cmp::Reverse(inbound.priority));
+        let mut outcomes: Vec<Option<BatchItemOutcome>> = queue.iter().map(|_| None).collect();
+        for (index, inbound) in queue {
+            if let Some(key) = inbound.dedupe_key.as_deref()
+                && let Some(existing) = store.find_goal_by_dedupe_key(key)?
+            {
+                outcomes[index] = Some(BatchItemOutcome::Duplicate {
+                    goal_id: existing.id,
+                    status: existing.status,
+                });
+                continue;
+            }
+            outcomes[index] = Some(match self.process_event(inbound) {
+                Ok(processed) => BatchItemOutcome::Processed(processed),
+                Err(err) => BatchItemOutcome::Failed(err.to_string()),
+            });
+        }
+        Ok(outcomes.into_iter().flatten().collect())
+    }
+
+    /// Runs a subscribed connector webhook event as a goal and records the goal it produced in
+    /// `connector_tool_usage` under `webhook:<event>`.
+    pub fn process_connector_event(&self, event: &ConnectorEventGoal) -> Result<BatchItemOutcome> {
+        if MemoryStore::open(&self.db_path)?.safe_mode_state()?.active {
+            bail!("safe_mode_active: connector triggers are paused until an operator resumes");
+        }
+        let inbound = InboundEvent::new(
+            Channel::Cli,
+            format!("connector:{}", event.connector_id),
+            event.goal_text.clone(),
+        )
+        .with_dedupe_key(event.dedupe_key.clone());
+        let outcome = self
+            .process_event_batch(vec![inbound])?
+            .pop()
+            .context("connector event produced no outcome")?;
+        if let BatchItemOutcome::Processed(processed) = &outcome {
+            MemoryStore::open(&self.db_path)?.record_connector_tool_usage(
+                &event.connector_id,
+                &format!("webhook:{}", event.event),
+                Some(&processed.goal_id),
+            )?;
+        }
+        Ok(outcome)
+    }
+
+    fn handle_slash_command(
+        &self,
+        inbound: &InboundEvent,
+        command: &str,
+    ) -> Result<ChatCommandResult> {
+        let store = MemoryStore::open(&self.db_path)?;
+        let session =
+            store.get_or_create_active_session(inbound.channel.as_str(), &inbound.actor_id)?;
+        let trace_goal_id = store.last_goal_for_session(&session.id)?;
+        if let Some(goal_id) = trace_goal_id.as_deref() {
+            store.add_trace_event(&TraceEvent::new(
                 goal_id.to_string(),
                 "command_invoked",
-                format!("{} {}", inbound.channel.as_str(), command),
+                scrub_diagnostic(&format!("{} {}", inbound.channel.as_str(), command)),
             ))?;
         }
 
@@ -263,184 +1449,20 @@ impl TitanGatewayRuntime {
         let head = parts.next().unwrap_or_default();
         let args: Vec<&str> = parts.collect();
 
-        let response = match head {
-            "/help" => slash_help(),
-            "/status" => {
-                let cfg = load_runtime_config(self.config_path.as_deref())?;
-                let risk = store.get_runtime_risk_state()?;
-                let pending = store.list_pending_approvals()?.len();
-                let last_run = store
-                    .last_goal_for_session(&session.id)?
-                    .unwrap_or_else(|| "<none>".to_string());
-                format!(
-                    "mode={} provider={} model={} session_id={} last_run_id={} compactions={} pending_approvals={} queue_depth={} risk_mode={} yolo_expires_at_ms={}",
-                    match self.mode {
-                        AutonomyMode::Supervised => "supervised",
-                        AutonomyMode::Collaborative => "collaborative",
-                        AutonomyMode::Autonomous => "autonomous",
-                    },
-                    model_provider_name(&cfg.model.provider),
-                    session.model_override.clone().unwrap_or(cfg.model.model_id),
-                    session.id,
-                    last_run,
-                    session.compactions_count,
-                    pending,
-                    session.queue_depth,
-                    risk.risk_mode.as_str(),
-                    risk.yolo_expires_at_ms
-                        .map(|v| v.to_string())
-                        .unwrap_or_else(|| "<none>".to_string())
-                )
-            }
-            "/mode" => {
-                if args.len() != 1 {
-                    "usage: /mode supervised|collab|auto".to_string()
-                } else {
-                    let selected = match args[0].trim().to_ascii_lowercase().as_str() {
-                        "supervised" => Some(AutonomyMode::Supervised),
-                        "collab" | "collaborative" => Some(AutonomyMode::Collaborative),
-                        "auto" | "autonomous" => Some(AutonomyMode::Autonomous),
-                        _ => None,
-                    };
-                    if let Some(mode) = selected {
-                        let (mut cfg, path, _) =
-                            load_runtime_config_with_path(self.config_path.as_deref())
-                                .map_err(|err| anyhow!("{err}"))?;
-                        cfg.mode = mode.clone();
-                        cfg.save(&path).map_err(|err| anyhow!("{err}"))?;
-                        format!(
-                            "mode_updated={}",
-                            match mode {
-                                AutonomyMode::Supervised => "supervised",
-                                AutonomyMode::Collaborative => "collaborative",
-                                AutonomyMode::Autonomous => "autonomous",
-                            }
-                        )
-                    } else {
-                        "usage: /mode supervised|collab|auto".to_string()
-                    }
-                }
-            }
-            "/new" | "/reset" => {
-                let model_or_text = args.first().map(|s| s.to_string());
-                session = store.create_session(
-                    inbound.channel.as_str(),
-                    &inbound.actor_id,
-                    model_or_text.as_deref(),
-                )?;
-                format!(
-                    "session_reset: {} model={}",
-                    session.id,
-                    session
-                        .model_override
-                        .unwrap_or_else(|| "<default>".to_string())
-                )
-            }
-            "/compact" => {
-                let instructions = if args.is_empty() {
-                    None
-                } else {
-                    Some(args.join(" "))
-                };
-                let compacted = store.compact_session(&session.id, instructions.as_deref())?;
-                let refreshed = store.get_session(&session.id)?.unwrap_or(session.clone());
-                session = refreshed;
-                format!(
-                    "session_compacted: {} messages_compacted={} compactions={}",
-                    session.id, compacted, session.compactions_count
-                )
-            }
-            "/stop" => {
-                store.mark_session_stop(&session.id)?;
-                "session_stop_requested: true".to_string()
-            }
-            "/approve" => {
-                if args.len() != 1 {
-                    "usage: /approve <approval_id>".to_string()
-                } else {
-                    let status = self.resolve_approval(
-                        args[0],
-                        true,
-                        inbound.actor_id.as_str(),
-                        Some("chat approve"),
-                    )?;
-                    format!("approval_status={status}")
-                }
-            }
-            "/deny" => {
-                if args.len() != 1 {
-                    "usage: /deny <approval_id>".to_string()
-                } else {
-                    let status = self.resolve_approval(
-                        args[0],
-                        false,
-                        inbound.actor_id.as_str(),
-                        Some("chat deny"),
-                    )?;
-                    format!("approval_status={status}")
-                }
-            }
-            "/trace" => {
-                if args.first().copied() == Some("last") {
-                    let rows = store.list_recent_traces(1)?;
-                    if let Some(trace) = rows.first() {
-                        format!(
-                            "trace_last goal={} type={} detail={}",
-                            trace.goal_id, trace.event_type, trace.detail
-                        )
-                    } else {
-                        "trace_last none".to_string()
-                    }
-                } else {
-                    "usage: /trace last".to_string()
-                }
-            }
-            "/usage" => {
-                if args.is_empty() {
-                    format!("usage_mode={}", session.usage_mode)
-                } else {
-                    let mode = args[0];
-                    if !matches!(mode, "off" | "tokens" | "full") {
-                        "usage: /usage off|tokens|full".to_string()
-                    } else {
-                        store.set_session_usage_mode(&session.id, mode)?;
-                        format!("usage_mode_updated={mode}")
-                    }
-                }
-            }
-            "/context" => {
-                if args.first().copied() == Some("detail") {
-                    let rows = store.list_session_messages(&session.id, 20)?;
-                    let mut out = format!("context_detail session={}\n", session.id);
-                    for row in rows {
-                        out.push_str(&format!(
-                            "#{} {} compacted={} bytes={}\n",
-                            row.id,
-                            row.role,
-                            row.compacted,
-                            row.content.len()
-                        ));
-                    }
-                    out
-                } else {
-                    let rows = store.list_session_messages(&session.id, 20)?;
-                    let total: usize = rows.iter().map(|r| r.content.len()).sum();
-                    format!(
-                        "context_list session={} items={} bytes={}",
-                        session.id,
-                        rows.len(),
-                        total
-                    )
-                }
-            }
-            "/model" => self.handle_model_command(&store, &session.id, &args)?,
-            "/yolo" => {
-                "YOLO mode can only be enabled from local CLI via `titan yolo ...`".to_string()
-            }
-            "/skill" => self.handle_skill_command(&store, &args, inbound.actor_id.as_str())?,
-            "/allowlist" => self.handle_allowlist_command(inbound, &store, &session, &args)?,
-            "/activation" => self.handle_activation_command(inbound, &store, &session, &args)?,
-            _ => "unknown command. try /help".to_string(),
+        let mut ctx = CommandContext {
+            runtime: self,
+            store: &store,
+            inbound,
+            session,
+        };
+        let response = match self.commands.lookup(head) {
+            None => "unknown command. try /help".to_string(),
+            Some(spec) if self.actor_role(inbound)? < spec.role => format!(
+                "permission_denied: {} requires the {} role",
+                spec.name,
+                spec.role.as_str()
+            ),
+            Some(spec) => (spec.handler)(&mut ctx, &args)?,
         };
         if let Some(goal_id) = trace_goal_id.as_deref() {
             store.add_trace_event(&TraceEvent::new(
@@ -450,11 +1472,24 @@ impl TitanGatewayRuntime {
             ))?;
         }
         Ok(ChatCommandResult {
-            session_id: session.id,
+            session_id: ctx.session.id,
             response,
+            reply_outbox_id: None,
         })
     }
 
+    fn actor_role(&self, inbound: &InboundEvent) -> Result<CommandRole> {
+        if matches!(inbound.channel, Channel::Cli) {
+            return Ok(CommandRole::Operator);
+        }
+        let cfg = load_runtime_config(self.config_path.as_deref())?;
+        if cfg.chat.operators.is_empty() || cfg.chat.operators.contains(&inbound.actor_id) {
+            Ok(CommandRole::Operator)
+        } else {
+            Ok(CommandRole::Anyone)
+        }
+    }
+
     fn handle_model_command(
         &self,
         store: &MemoryStore,
@@ -483,37 +1518,155 @@ impl TitanGatewayRuntime {
         Ok(format!("model_override_updated={}", selection.trim()))
     }
 
+    /// Notifies operator routes and `approvals.operator_channel` of an approval queued outside
+    /// a run; the command that queued it already answers its own conversation.
+    fn announce_approval(&self, store: &MemoryStore, approval_id: &str) -> Result<()> {
+        let cfg = load_runtime_config(self.config_path.as_deref())?;
+        notify_approval_requested(
+            store,
+            approval_id,
+            &approval_notice_targets(&cfg.approvals, None),
+        )
+    }
+
     fn handle_allowlist_command(
         &self,
         inbound: &InboundEvent,
         store: &MemoryStore,
-        _session: &titan_memory::SessionRecord,
         args: &[&str],
     ) -> Result<String> {
         if args.len() < 2 {
             return Ok("usage: /allowlist add|remove <id>".to_string());
         }
-        let action = args[0];
+        let Some(action) = AllowlistAction::parse(args[0]) else {
+            return Ok("usage: /allowlist add|remove <id>".to_string());
+        };
         let id = args[1].trim();
         if id.is_empty() {
             return Ok("usage: /allowlist add|remove <id>".to_string());
         }
-        if requires_config_approval(self.mode.clone()) {
+        if requires_config_approval(self.effective_mode(store)?) {
             let approval = store.create_approval_request_for_goal(
                 None,
                 "config_allowlist",
                 "write",
-                &format!("{action}:{id}"),
+                &encode_approval_payload(&ConfigAllowlistPayload {
+                    action,
+                    id: id.to_string(),
+                })?,
                 Some(inbound.actor_id.as_str()),
-                300_000,
+                PolicyEngine::approval_ttl_ms("write"),
             )?;
+            self.announce_approval(store, &approval.id)?;
             return Ok(format!(
                 "approval_required=true approval_id={}",
                 approval.id
             ));
         }
         apply_allowlist_change(action, id, self.config_path.as_deref())?;
-        Ok(format!("allowlist_updated action={action} id={id}"))
+        Ok(format!(
+            "allowlist_updated action={} id={id}",
+            action.as_str()
+        ))
+    }
+
+    fn handle_connector_command(
+        &self,
+        inbound: &InboundEvent,
+        store: &MemoryStore,
+        args: &[&str],
+    ) -> Result<String> {
+        const USAGE: &str = "usage: /connector list | add <type> [name] [field=value...] | test <connector_id> | remove <connector_id>";
+        let change = match args {
+            ["list"] => {
+                let rows = store.list_connectors()?;
+                if rows.is_empty() {
+                    return Ok("no connectors configured".to_string());
+                }
+                return Ok(rows
+                    .iter()
+                    .map(|row| {
+                        format!(
+                            "{} {} {} last_test={}",
+                            row.id,
+                            row.connector_type,
+                            row.display_name,
+                            row.last_test_status.as_deref().unwrap_or("<never>")
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"));
+            }
+            ["test", id] => {
+                if store.get_connector(id)?.is_none() {
+                    return Ok(format!("connector not found: {id}"));
+                }
+                let resolver = CompositeSecretResolver::from_env()?;
+                let health = test_connector(store, id, &resolver)?;
+                return Ok(format!(
+                    "connector_id={id} health_ok={} detail={}",
+                    health.ok, health.detail
+                ));
+            }
+            ["add", connector_type, rest @ ..] => {
+                let Some(parsed) = ConnectorType::parse(connector_type) else {
+                    return Ok(format!("unsupported connector type: {connector_type}"));
+                };
+                let (fields, names): (Vec<&str>, Vec<&str>) =
+                    rest.iter().partition(|arg| arg.contains('='));
+                if names.len() > 1 {
+                    return Ok(USAGE.to_string());
+                }
+                let fields: Vec<(&str, &str)> = fields
+                    .iter()
+                    .filter_map(|field| field.split_once('='))
+                    .collect();
+                let config = match connector_config_with_fields(parsed, &fields) {
+                    Ok(config) => config,
+                    Err(err) => return Ok(err.to_string()),
+                };
+                encode_approval_payload(&ConnectorAddPayload {
+                    connector_type: parsed,
+                    display_name: names
+                        .first()
+                        .copied()
+                        .unwrap_or(parsed.as_str())
+                        .to_string(),
+                    config,
+                })?
+            }
+            ["remove", id] => {
+                if store.get_connector(id)?.is_none() {
+                    return Ok(format!("connector not found: {id}"));
+                }
+                encode_approval_payload(&ConnectorRemovePayload {
+                    connector_id: id.to_string(),
+                })?
+            }
+            _ => return Ok(USAGE.to_string()),
+        };
+        let kind = if args[0] == "add" {
+            ConnectorAddPayload::KIND
+        } else {
+            ConnectorRemovePayload::KIND
+        };
+        if requires_config_approval(self.effective_mode(store)?) {
+            let approval = store.create_approval_request_for_goal(
+                None,
+                kind,
+                "write",
+                &change,
+                Some(inbound.actor_id.as_str()),
+                PolicyEngine::approval_ttl_ms("write"),
+            )?;
+            self.announce_approval(store, &approval.id)?;
+            return Ok(format!(
+                "approval_required=true approval_id={}",
+                approval.id
+            ));
+        }
+        Ok(apply_connector_change_approval(store, kind, &change)?
+            .unwrap_or_else(|| format!("{kind}=skipped")))
     }
 
     fn handle_skill_command(
@@ -543,8 +1696,9 @@ impl TitanGatewayRuntime {
             "write",
             &payload_json,
             Some(actor_id),
-            300_000,
+            PolicyEngine::approval_ttl_ms("write"),
         )?;
+        self.announce_approval(store, &approval.id)?;
         Ok(format!(
             "approval_required=true approval_id={} skill={}@{} signed={} scopes={} allowed_paths={} allowed_hosts={}",
             approval.id,
@@ -561,102 +1715,483 @@ impl TitanGatewayRuntime {
         &self,
         inbound: &InboundEvent,
         store: &MemoryStore,
-        _session: &titan_memory::SessionRecord,
         args: &[&str],
     ) -> Result<String> {
         if args.len() != 1 {
             return Ok("usage: /activation mention|always".to_string());
         }
-        let mode = args[0].trim().to_ascii_lowercase();
-        if mode != "mention" && mode != "always" {
-            return Ok("usage: /activation mention|always".to_string());
-        }
-        if requires_config_approval(self.mode.clone()) {
+        let mode = match args[0].trim().to_ascii_lowercase().as_str() {
+            "mention" => ActivationMode::Mention,
+            "always" => ActivationMode::Always,
+            _ => return Ok("usage: /activation mention|always".to_string()),
+        };
+        if requires_config_approval(self.effective_mode(store)?) {
             let approval = store.create_approval_request_for_goal(
                 None,
                 "config_activation",
                 "write",
-                &mode,
+                &encode_approval_payload(&ConfigActivationPayload { mode: mode.clone() })?,
                 Some(inbound.actor_id.as_str()),
-                300_000,
+                PolicyEngine::approval_ttl_ms("write"),
             )?;
+            self.announce_approval(store, &approval.id)?;
             return Ok(format!(
                 "approval_required=true approval_id={}",
                 approval.id
             ));
         }
-        apply_activation_mode(&mode, self.config_path.as_deref())?;
-        Ok(format!("activation_mode_updated={mode}"))
+        let name = activation_mode_name(&mode);
+        apply_activation_mode(mode, self.config_path.as_deref())?;
+        Ok(format!("activation_mode_updated={name}"))
     }
 
-    pub fn resolve_approval(
+    fn handle_run_command(
         &self,
-        approval_id: &str,
-        approved: bool,
-        resolved_by: &str,
-        reason: Option<&str>,
+        store: &MemoryStore,
+        inbound: &InboundEvent,
+        session_id: &str,
+        args: &[&str],
     ) -> Result<String> {
+        let cfg = load_runtime_config(self.config_path.as_deref())?;
+        let Some(name) = args.first() else {
+            if cfg.goal_templates.is_empty() {
+                return Ok("no goal templates configured".to_string());
+            }
+            let names: Vec<String> = cfg
+                .goal_templates
+                .iter()
+                .map(|template| format!("{}({})", template.name, template.params.join(",")))
+                .collect();
+            return Ok(format!("templates: {}", names.join(" ")));
+        };
+        let Some(template) = cfg.goal_templates.iter().find(|t| t.name == *name) else {
+            return Ok(format!("unknown template '{name}'. try /run"));
+        };
+        let mut values: Vec<String> = args[1..].iter().map(|value| value.to_string()).collect();
+        if values.len() > template.params.len() {
+            if template.params.is_empty() {
+                return Ok(format!("usage: /run {}", template.name));
+            }
+            // The last parameter takes the rest of the line, e.g. a release title.
+            let rest = values.split_off(template.params.len() - 1).join(" ");
+            values.push(rest);
+        }
+        // Slash replies go back directly, so the run must not also queue one in the outbox.
+        let mut inbound = inbound.clone();
+        inbound.reply_target = None;
+        Ok(
+            match self.advance_template(store, &inbound, session_id, template, values)? {
+                TemplateProgress::Prompt(prompt) => prompt,
+                TemplateProgress::Ran(processed) => event_chat_result(*processed).response,
+            },
+        )
+    }
+
+    /// Treats a plain message as the answer to the session's pending `/run` prompt, if any.
+    fn answer_template_prompt(&self, inbound: &InboundEvent) -> Result<Option<ChatCommandResult>> {
         let store = MemoryStore::open(&self.db_path)?;
-        store.apply_yolo_expiry("gateway")?;
+        let session =
+            store.get_or_create_active_session(inbound.channel.as_str(), &inbound.actor_id)?;
+        let Some(prompt) = store.template_prompt(&session.id)? else {
+            return Ok(None);
+        };
+        store.clear_template_prompt(&session.id)?;
+        let answer = inbound.text.trim();
         let cfg = load_runtime_config(self.config_path.as_deref())?;
-        let approval = store
-            .get_approval_request(approval_id)?
-            .ok_or_else(|| anyhow!("approval not found: {approval_id}"))?;
+        let template = cfg
+            .goal_templates
+            .iter()
+            .find(|template| template.name == prompt.template);
+        let response = match template {
+            _ if answer.eq_ignore_ascii_case("cancel") => {
+                format!("template_cancelled={}", prompt.template)
+            }
+            None => format!("template '{}' is no longer configured", prompt.template),
+            Some(template) => {
+                let mut values = prompt.values;
+                values.push(answer.to_string());
+                match self.advance_template(&store, inbound, &session.id, template, values)? {
+                    TemplateProgress::Prompt(prompt) => prompt,
+                    TemplateProgress::Ran(processed) => {
+                        return Ok(Some(event_chat_result(*processed)));
+                    }
+                }
+            }
+        };
+        Ok(Some(ChatCommandResult {
+            session_id: session.id,
+            response,
+            reply_outbox_id: None,
+        }))
+    }
 
-        let resolved =
-            store.resolve_approval_request(approval_id, approved, Some(resolved_by), reason)?;
-        if !resolved {
-            return Ok("not_pending".to_string());
+    /// Merges a plain message into the session's goal awaiting clarification, then plans it.
+    fn answer_clarification(&self, inbound: &InboundEvent) -> Result<Option<ChatCommandResult>> {
+        let store = MemoryStore::open(&self.db_path)?;
+        let session =
+            store.get_or_create_active_session(inbound.channel.as_str(), &inbound.actor_id)?;
+        let Some(clarification) = store.clarification(&session.id)? else {
+            return Ok(None);
+        };
+        store.clear_clarification(&session.id)?;
+        let answer = inbound.text.trim();
+        if answer.eq_ignore_ascii_case("cancel") {
+            store.update_goal_status(&clarification.goal_id, GoalStatus::Cancelled)?;
+            store.add_trace_event(&TraceEvent::new(
+                clarification.goal_id.clone(),
+                "clarification_cancelled",
+                format!("actor={}", inbound.actor_id),
+            ))?;
+            return Ok(Some(ChatCommandResult {
+                session_id: session.id,
+                response: format!(
+                    "goal={} status={}",
+                    clarification.goal_id,
+                    GoalStatus::Cancelled.as_str()
+                ),
+                reply_outbox_id: None,
+            }));
         }
+        let mut merged = inbound.clone();
+        merged.text = format!("{}; {answer}", clarification.request_text);
+        let processed = self.process_goal(
+            merged,
+            GoalRunOptions {
+                clarified: Some(&clarification),
+                ..GoalRunOptions::default()
+            },
+        )?;
+        Ok(Some(event_chat_result(processed)))
+    }
 
-        if !approved {
-            if let Some(goal_id) = approval.goal_id {
-                store.update_goal_status(&goal_id, GoalStatus::Cancelled)?;
-                store.add_trace_event(&TraceEvent::new(
-                    goal_id.clone(),
-                    "approval_denied",
-                    approval_id.to_string(),
-                ))?;
-                store.add_episodic_memory(&goal_id, "Approval denied by operator", "discord")?;
-            }
-            return Ok("denied".to_string());
+    /// Resumes the session's goal paused on an `AskUser` step with a plain message as the
+    /// answer. `cancel` abandons the goal; an answer after the TTL fails it.
+    fn answer_pending_input(&self, inbound: &InboundEvent) -> Result<Option<ChatCommandResult>> {
+        let store = MemoryStore::open(&self.db_path)?;
+        let session =
+            store.get_or_create_active_session(inbound.channel.as_str(), &inbound.actor_id)?;
+        let Some(pending) = store.pending_input(&session.id)? else {
+            return Ok(None);
+        };
+        store.clear_pending_input(&session.id)?;
+        let answer = inbound.text.trim();
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        let (status, event, detail) = if answer.eq_ignore_ascii_case("cancel") {
+            (
+                GoalStatus::Cancelled,
+                "input_cancelled",
+                format!("actor={}", inbound.actor_id),
+            )
+        } else if now_ms > pending.expires_at_ms {
+            (
+                GoalStatus::Failed,
+                "input_expired",
+                format!("{}: no answer before ttl", pending.step_id),
+            )
+        } else {
+            let processed = self.process_goal(
+                inbound.clone(),
+                GoalRunOptions {
+                    answered: Some(&pending),
+                    ..GoalRunOptions::default()
+                },
+            )?;
+            return Ok(Some(event_chat_result(processed)));
+        };
+        store.update_goal_status(&pending.goal_id, status)?;
+        store.add_trace_event(&TraceEvent::new(pending.goal_id.clone(), event, detail))?;
+        Ok(Some(ChatCommandResult {
+            session_id: session.id,
+            response: format!("goal={} status={}", pending.goal_id, status.as_str()),
+            reply_outbox_id: None,
+        }))
+    }
+
+    /// Runs the template once every parameter has a value; otherwise asks for the next one.
+    fn advance_template(
+        &self,
+        store: &MemoryStore,
+        inbound: &InboundEvent,
+        session_id: &str,
+        template: &GoalTemplateConfig,
+        values: Vec<String>,
+    ) -> Result<TemplateProgress> {
+        if let Some(missing) = template.params.get(values.len()) {
+            store.set_template_prompt(session_id, &template.name, &values)?;
+            return Ok(TemplateProgress::Prompt(format!(
+                "{} needs {missing} ({} of {}): reply with a value, or 'cancel'",
+                template.name,
+                values.len() + 1,
+                template.params.len()
+            )));
         }
+        let mut goal_inbound = inbound.clone();
+        goal_inbound.text = template.expand(&values);
+        let processed = self.process_goal(
+            goal_inbound,
+            GoalRunOptions {
+                template: Some(TemplateRun {
+                    template,
+                    values: &values,
+                }),
+                ..GoalRunOptions::default()
+            },
+        )?;
+        Ok(TemplateProgress::Ran(Box::new(processed)))
+    }
 
-        if store.approval_has_tool_run(approval_id)? {
-            return Ok("replay_blocked".to_string());
+    // Comments land on the approval and, when the approval belongs to a goal, in the
+    // requesting session's message history so the requester sees them on their channel.
+    pub fn comment_on_approval(
+        &self,
+        approval_id: &str,
+        author: &str,
+        body: &str,
+    ) -> Result<titan_memory::ApprovalCommentRecord> {
+        let body = body.trim();
+        if body.is_empty() {
+            return Err(anyhow!("comment body is required"));
+        }
+        let store = MemoryStore::open(&self.db_path)?;
+        let approval = store
+            .get_approval_request(approval_id)?
+            .ok_or_else(|| anyhow!("approval not found: {approval_id}"))?;
+        let comment = store.add_approval_comment(approval_id, author, body)?;
+        if let Some(goal_id) = approval.goal_id.as_deref() {
+            store.add_trace_event(&TraceEvent::new(
+                goal_id.to_string(),
+                "approval_comment",
+                format!("approval={} author={} {}", approval_id, author, body),
+            ))?;
+            if let Some(session_id) = store.session_id_for_goal(goal_id)? {
+                store.add_session_message(
+                    &session_id,
+                    "approval_comment",
+                    &format!("[approval {}] {}: {}", approval_id, author, body),
+                    false,
+                )?;
+            }
         }
+        Ok(comment)
+    }
 
-        if approval.tool_name == "config_allowlist" {
-            let (action, value) = approval
-                .input
-                .split_once(':')
-                .ok_or_else(|| anyhow!("invalid config_allowlist approval payload"))?;
-            apply_allowlist_change(action, value, self.config_path.as_deref())?;
-            return Ok("approved".to_string());
+    /// Replaces a goal's expired approval with a fresh one for the same action. Only the
+    /// original requester or an operator may ask, and only within `approvals.rerequest_grace_ms`.
+    fn rerequest_approval(
+        &self,
+        store: &MemoryStore,
+        inbound: &InboundEvent,
+        goal_id: &str,
+    ) -> Result<String> {
+        let Some(previous) = store.latest_approval_for_goal(goal_id)? else {
+            return Ok(format!("no approval found for goal {goal_id}"));
+        };
+        if previous.status != "expired" {
+            return Ok(format!(
+                "approval {} for goal {goal_id} is {}; nothing to re-request",
+                previous.id, previous.status
+            ));
         }
-        if approval.tool_name == "config_activation" {
-            apply_activation_mode(approval.input.trim(), self.config_path.as_deref())?;
-            return Ok("approved".to_string());
+        if previous.requested_by.as_deref() != Some(inbound.actor_id.as_str())
+            && self.actor_role(inbound)? < CommandRole::Operator
+        {
+            return Ok(
+                "permission_denied: only the requester or an operator may re-request".to_string(),
+            );
         }
-        if approval.tool_name == "skill_install" {
-            let payload = titan_skills::deserialize_approval_payload(&approval.input)?;
-            let installed = titan_skills::finalize_install_from_payload(&payload)?;
-            let installed_ref =
-                format!("{}@{}", installed.manifest.slug, installed.manifest.version);
-            store.upsert_installed_skill(&titan_memory::InstalledSkillRecord {
-                slug: installed.manifest.slug.clone(),
-                name: installed.manifest.name.clone(),
-                version: installed.manifest.version.clone(),
-                description: installed.manifest.description.clone(),
-                source: installed.source.clone(),
-                hash: installed.hash.clone(),
-                signature_status: installed.signature_status.clone(),
-                scopes: installed
-                    .manifest
-                    .permissions
-                    .scopes
-                    .iter()
-                    .map(|scope| scope.as_str())
+        let cfg = load_runtime_config(self.config_path.as_deref())?;
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        let grace_ms = cfg.approvals.rerequest_grace_ms as i64;
+        if now_ms > previous.expires_at_ms.saturating_add(grace_ms) {
+            return Ok(format!(
+                "rerequest_window_closed approval={} expired more than {}m ago; submit the goal again",
+                previous.id,
+                grace_ms / 60_000
+            ));
+        }
+        let approval = store.rerequest_approval(
+            &previous.id,
+            PolicyEngine::approval_ttl_ms(&previous.capability),
+        )?;
+        self.announce_approval(store, &approval.id)?;
+        Ok(format!(
+            "approval_required=true approval_id={} previous={} tool={} capability={} expires_at_ms={}",
+            approval.id,
+            previous.id,
+            approval.tool_name,
+            approval.capability,
+            approval.expires_at_ms
+        ))
+    }
+
+    pub fn resolve_approval(
+        &self,
+        approval_id: &str,
+        approved: bool,
+        resolved_by: &str,
+        reason: Option<&str>,
+    ) -> Result<String> {
+        self.resolve_approval_with_input(approval_id, approved, resolved_by, reason, None)
+    }
+
+    /// Resolves every pending approval of `capability` (read, write, exec, or net), oldest
+    /// first and at most [`BULK_APPROVAL_LIMIT`], each through [`Self::resolve_approval`] so
+    /// allowlists, quorums, and the replay guard still apply. Returns `(id, status)` pairs; an
+    /// approval that fails gets an `error: ...` status and the rest are still resolved.
+    pub fn resolve_approvals_by_capability(
+        &self,
+        capability: titan_tools::CapabilityClass,
+        approved: bool,
+        resolved_by: &str,
+        reason: Option<&str>,
+    ) -> Result<Vec<(String, String)>> {
+        let store = MemoryStore::open(&self.db_path)?;
+        store
+            .list_pending_approvals()?
+            .into_iter()
+            .filter(|approval| approval.capability == capability.as_str())
+            .take(BULK_APPROVAL_LIMIT)
+            .map(|approval| {
+                let status = self
+                    .resolve_approval(&approval.id, approved, resolved_by, reason)
+                    .unwrap_or_else(|err| format!("error: {err:#}"));
+                Ok((approval.id, status))
+            })
+            .collect()
+    }
+
+    /// Like [`Self::resolve_approval`], but an approval may run `edited_input` in place of the
+    /// requested input. Only built-in tool approvals can be edited.
+    pub fn resolve_approval_with_input(
+        &self,
+        approval_id: &str,
+        approved: bool,
+        resolved_by: &str,
+        reason: Option<&str>,
+        edited_input: Option<&str>,
+    ) -> Result<String> {
+        let status =
+            self.resolve_approval_inner(approval_id, approved, resolved_by, reason, edited_input)?;
+        if status != "not_pending"
+            && !status.starts_with("awaiting_quorum")
+            && !status.starts_with("approver_not_allowed")
+            && !status.starts_with("edit_not_allowed")
+        {
+            let store = MemoryStore::open(&self.db_path)?;
+            notify_approval_resolved(&store, approval_id, resolved_by, &status)?;
+        }
+        Ok(status)
+    }
+
+    fn resolve_approval_inner(
+        &self,
+        approval_id: &str,
+        approved: bool,
+        resolved_by: &str,
+        reason: Option<&str>,
+        edited_input: Option<&str>,
+    ) -> Result<String> {
+        let store = MemoryStore::open(&self.db_path)?;
+        store.apply_yolo_expiry("gateway")?;
+        let mut approval = store
+            .get_approval_request(approval_id)?
+            .ok_or_else(|| anyhow!("approval not found: {approval_id}"))?;
+
+        if let Some(refusal) = approver_refusal(&store, &approval, resolved_by)? {
+            return Ok(refusal);
+        }
+        if let Some(refusal) =
+            approval_edit_refusal(&store, &self.mode, &approval, approved, edited_input)?
+        {
+            return Ok(refusal);
+        }
+        if approved
+            && let Some((votes, quorum)) =
+                approval_quorum_shortfall(&store, &self.mode, &approval, resolved_by)?
+        {
+            return Ok(format!("awaiting_quorum votes={votes}/{quorum}"));
+        }
+        let resolved = store.resolve_approval_request(
+            approval_id,
+            approved,
+            Some(resolved_by),
+            reason,
+            edited_input,
+        )?;
+        if !resolved {
+            return Ok("not_pending".to_string());
+        }
+        if let Some(edited) = edited_input {
+            approval.original_input = Some(std::mem::replace(&mut approval.input, edited.into()));
+        }
+
+        if !approved {
+            if let Some(goal_id) = approval.goal_id {
+                store.update_goal_status(&goal_id, GoalStatus::Cancelled)?;
+                store.add_trace_event(&TraceEvent::new(
+                    goal_id.clone(),
+                    "approval_denied",
+                    approval_id.to_string(),
+                ))?;
+                store.add_episodic_memory(&goal_id, "Approval denied by operator", "discord")?;
+            }
+            return Ok("denied".to_string());
+        }
+
+        self.run_approved(approval, resolved_by)
+    }
+
+    /// Carries out what an already-approved request unlocks: the tool run, config or skill
+    /// change, or the rest of a canary plan. For callers that record the decision themselves,
+    /// such as the web approval endpoints. A tool approval that already ran is refused as a
+    /// replay.
+    pub fn run_approved(
+        &self,
+        approval: titan_memory::ApprovalRecord,
+        resolved_by: &str,
+    ) -> Result<String> {
+        let store = MemoryStore::open(&self.db_path)?;
+        let cfg = load_runtime_config(self.config_path.as_deref())?;
+        let approval_id = approval.id.as_str();
+        if store.approval_has_tool_run(approval_id)? {
+            return Ok("replay_blocked".to_string());
+        }
+
+        if approval.tool_name == "config_allowlist" {
+            let payload: ConfigAllowlistPayload = decode_approval_payload(&approval.input)?;
+            apply_allowlist_change(payload.action, &payload.id, self.config_path.as_deref())?;
+            return Ok("approved".to_string());
+        }
+        if approval.tool_name == "config_activation" {
+            let payload: ConfigActivationPayload = decode_approval_payload(&approval.input)?;
+            apply_activation_mode(payload.mode, self.config_path.as_deref())?;
+            return Ok("approved".to_string());
+        }
+        if approval.tool_name == "skill_install" {
+            let payload = titan_skills::deserialize_approval_payload(&approval.input)?;
+            let installed = titan_skills::finalize_install_from_payload(&payload)?;
+            let installed_ref =
+                format!("{}@{}", installed.manifest.slug, installed.manifest.version);
+            store.upsert_installed_skill(&titan_memory::InstalledSkillRecord {
+                slug: installed.manifest.slug.clone(),
+                name: installed.manifest.name.clone(),
+                version: installed.manifest.version.clone(),
+                description: installed.manifest.description.clone(),
+                source: installed.source.clone(),
+                hash: installed.hash.clone(),
+                signature_status: installed.signature_status.clone(),
+                scopes: installed
+                    .manifest
+                    .permissions
+                    .scopes
+                    .iter()
+                    .map(|scope| scope.as_str())
                     .collect::<Vec<_>>()
                     .join(","),
                 allowed_paths: installed.manifest.permissions.allowed_paths.join(","),
@@ -668,6 +2203,11 @@ impl TitanGatewayRuntime {
         if approval.tool_name == "skill_exec_grant" {
             return Ok("approved".to_string());
         }
+        if let Some(change) =
+            apply_connector_change_approval(&store, &approval.tool_name, &approval.input)?
+        {
+            return Ok(format!("approved {change}"));
+        }
         if approval.tool_name == "connector_tool" {
             let resolver = CompositeSecretResolver::from_env()?;
             let outcome = execute_connector_tool_after_approval(
@@ -676,13 +2216,54 @@ impl TitanGatewayRuntime {
                 &approval.input,
                 &resolver,
             )?;
-            return Ok(format!(
-                "approved connector_goal={} status={}",
+            let output = format!(
+                "connector_goal={} status={}",
                 outcome.goal_id, outcome.result_status
-            ));
+            );
+            complete_planned_step(
+                &store,
+                approval.goal_id.as_deref(),
+                "connector_tool",
+                &output,
+            )?;
+            return Ok(format!("approved {output}"));
+        }
+        if approval.tool_name == "skill_run" {
+            let outcome = titan_skills::run_approved_skill_v1(
+                &store,
+                &self.workspace_root,
+                titan_skills::WasmLimits::from_config(&cfg.skills),
+                resolved_by,
+                &approval.input,
+            )?;
+            let output = skill_outcome_summary(&outcome);
+            complete_planned_step(&store, approval.goal_id.as_deref(), "skill_run", &output)?;
+            return Ok(format!("approved {output}"));
         }
 
         let registry = ToolRegistry::with_defaults();
+        let mut exec_ctx = ToolExecutionContext::default_for_workspace(self.workspace_root.clone());
+        let risk = store.get_runtime_risk_state()?;
+        exec_ctx.bypass_path_guard = matches!(risk.risk_mode, RiskMode::Yolo)
+            && risk.yolo_bypass_path_guard
+            && cfg.security.yolo_bypass_path_guard;
+        if let Some(profile) = cfg
+            .sandbox
+            .profile_for(&self.effective_mode(&store)?, risk.risk_mode.as_str())
+        {
+            exec_ctx.apply_sandbox(profile);
+        }
+        exec_ctx.scan_secrets = cfg.security.scan_writes_for_secrets;
+        if approval.tool_name == CANARY_CONTINUE_TOOL {
+            let goal_id = approval
+                .goal_id
+                .clone()
+                .ok_or_else(|| anyhow!("canary approval is missing its goal"))?;
+            return self.resume_canary_steps(
+                &store, &registry, &exec_ctx, &cfg.model, &approval, &goal_id,
+            );
+        }
+
         let tool = registry
             .get(&approval.tool_name)
             .ok_or_else(|| anyhow!("unknown tool '{}'", approval.tool_name))?;
@@ -691,12 +2272,22 @@ impl TitanGatewayRuntime {
         } else {
             Some(approval.input.as_str())
         };
-        let mut exec_ctx = ToolExecutionContext::default_for_workspace(self.workspace_root.clone());
-        let risk = store.get_runtime_risk_state()?;
-        exec_ctx.bypass_path_guard = matches!(risk.risk_mode, RiskMode::Yolo)
-            && risk.yolo_bypass_path_guard
-            && cfg.security.yolo_bypass_path_guard;
+        // Approving the step is the override for a write held by the secret scan.
+        let scan_secrets = std::mem::replace(&mut exec_ctx.scan_secrets, false);
+        let snapshot = WorkspaceSnapshot::capture(&self.workspace_root);
         let result = ToolExecutor::execute(tool, input_ref, &exec_ctx)
+            .inspect_err(|err| {
+                if let (Some(goal_id), Some(blocked)) = (
+                    approval.goal_id.as_ref(),
+                    err.downcast_ref::<titan_tools::EgressBlocked>(),
+                ) {
+                    let _ = store.add_trace_event(&TraceEvent::new(
+                        goal_id.clone(),
+                        "egress_blocked",
+                        blocked.trace_detail(&tool.name),
+                    ));
+                }
+            })
             .with_context(|| format!("approved tool '{}' execution failed", tool.name))?;
         store.record_tool_run(
             Some(approval_id),
@@ -704,7 +2295,11 @@ impl TitanGatewayRuntime {
             &result.status,
             &result.output,
         )?;
-        if let Some(goal_id) = approval.goal_id {
+        if let Some(goal_id) = approval.goal_id.clone() {
+            let mut written =
+                snapshot.attribute(&store, &self.workspace_root, &goal_id, &tool.name)?;
+            written.extend(write_file_path(&tool.name, input_ref));
+            record_provenance(&store, &cfg.model, &goal_id, &tool.name, &written)?;
             store.mark_blocked_step_executed_for_goal(&goal_id, &tool.name, &result.output)?;
             store.add_trace_event(&TraceEvent::new(
                 goal_id.clone(),
@@ -716,6 +2311,13 @@ impl TitanGatewayRuntime {
                 "write_diff",
                 format!("tool_output={}", result.output),
             ))?;
+            // A step held after a canary confirmation picks the rest of the canary plan back up.
+            if !store.list_pending_canary_steps(&goal_id)?.is_empty() {
+                exec_ctx.scan_secrets = scan_secrets;
+                return self.resume_canary_steps(
+                    &store, &registry, &exec_ctx, &cfg.model, &approval, &goal_id,
+                );
+            }
             store.update_goal_status(&goal_id, GoalStatus::Completed)?;
             store.add_episodic_memory(
                 &goal_id,
@@ -725,224 +2327,3447 @@ impl TitanGatewayRuntime {
         }
         Ok("approved".to_string())
     }
-}
 
-fn parse_slash_command(text: &str) -> Option<String> {
-    let trimmed = text.trim();
-    if trimmed.starts_with("/titan ") {
-        let cmd = trimmed.trim_start_matches("/titan").trim();
-        return Some(format!("/{cmd}"));
+    // Planned connector/skill steps reuse the mediated paths; built-in tools return None.
+    fn execute_catalog_step(
+        &self,
+        store: &MemoryStore,
+        skills: &titan_common::SkillsConfig,
+        actor_id: &str,
+        step: &titan_core::Step,
+    ) -> Result<Option<String>> {
+        let input = step.input.as_deref().unwrap_or_default();
+        match step.tool_name.as_str() {
+            "connector_tool" => {
+                let resolver = CompositeSecretResolver::from_env()?;
+                let outcome = execute_connector_tool_from_payload(
+                    store,
+                    self.effective_mode(store)?,
+                    actor_id,
+                    input,
+                    &resolver,
+                )?;
+                Ok(Some(format!(
+                    "connector_goal={} status={}",
+                    outcome.goal_id, outcome.result_status
+                )))
+            }
+            "skill_run" => {
+                let outcome = titan_skills::run_approved_skill_v1(
+                    store,
+                    &self.workspace_root,
+                    titan_skills::WasmLimits::from_config(skills),
+                    actor_id,
+                    input,
+                )?;
+                Ok(Some(skill_outcome_summary(&outcome)))
+            }
+            _ => Ok(None),
+        }
     }
-    if trimmed.starts_with('/') {
-        return Some(trimmed.to_string());
+
+    /// Runs the steps a canary paused, in order. Each still goes through policy: the first one
+    /// that needs approval gets its own request and the goal waits on it again.
+    fn resume_canary_steps(
+        &self,
+        store: &MemoryStore,
+        registry: &ToolRegistry,
+        exec_ctx: &ToolExecutionContext,
+        model: &ModelConfig,
+        approval: &titan_memory::ApprovalRecord,
+        goal_id: &str,
+    ) -> Result<String> {
+        let approval_id = approval.id.as_str();
+        let mode = self.effective_mode(store)?;
+        let risk_mode = store.get_runtime_risk_state()?.risk_mode;
+        let steps = store.list_pending_canary_steps(goal_id)?;
+        store.add_trace_event(&TraceEvent::new(
+            goal_id.to_string(),
+            "canary_resumed",
+            format!("remaining_steps={}", steps.len()),
+        ))?;
+        let snapshot = WorkspaceSnapshot::capture(&self.workspace_root);
+        let mut written = Vec::new();
+        for step in &steps {
+            let tool = registry
+                .get(&step.tool_name)
+                .ok_or_else(|| anyhow!("unknown tool '{}'", step.tool_name))?;
+            let permission = step_permission_for_class(tool.class);
+            if step_requires_approval(&mode, &risk_mode, &tool.name, permission) {
+                let held = store.create_approval_request_for_goal(
+                    Some(goal_id),
+                    &tool.name,
+                    permission.as_str(),
+                    step.input.as_deref().unwrap_or_default(),
+                    approval.requested_by.as_deref(),
+                    PolicyEngine::approval_ttl_ms(permission.as_str()),
+                )?;
+                store.update_run_step_status(&step.id, "blocked_pending_approval", "")?;
+                store.add_trace_event(&TraceEvent::new(
+                    goal_id.to_string(),
+                    "approval_required",
+                    format!("{} requires {} approval", tool.name, permission.as_str()),
+                ))?;
+                written.extend(snapshot.attribute(
+                    store,
+                    &self.workspace_root,
+                    goal_id,
+                    "write_file",
+                )?);
+                record_provenance(store, model, goal_id, "write_file", &written)?;
+                store.update_goal_status(goal_id, GoalStatus::Pending)?;
+                self.announce_approval(store, &held.id)?;
+                return Ok(format!(
+                    "approved canary_held step={} approval_id={}",
+                    step.step_id, held.id
+                ));
+            }
+            match ToolExecutor::execute(tool, step.input.as_deref(), exec_ctx) {
+                Ok(result) => {
+                    store.record_tool_run(
+                        Some(approval_id),
+                        &tool.name,
+                        &result.status,
+                        &result.output,
+                    )?;
+                    store.update_run_step_status(
+                        &step.id,
+                        "executed_after_canary",
+                        &result.output,
+                    )?;
+                    written.extend(write_file_path(&tool.name, step.input.as_deref()));
+                    store.add_trace_event(&TraceEvent::new(
+                        goal_id.to_string(),
+                        "write_diff",
+                        format!("tool_output={}", result.output),
+                    ))?;
+                }
+                Err(err) => {
+                    store.update_run_step_status(&step.id, "failed", &err.to_string())?;
+                    store.add_trace_event(&TraceEvent::new(
+                        goal_id.to_string(),
+                        "execution_failed",
+                        format!("{}: {}", step.tool_name, err),
+                    ))?;
+                    written.extend(snapshot.attribute(
+                        store,
+                        &self.workspace_root,
+                        goal_id,
+                        &step.tool_name,
+                    )?);
+                    record_provenance(store, model, goal_id, &step.tool_name, &written)?;
+                    store.update_goal_status(goal_id, GoalStatus::Failed)?;
+                    return Ok(format!("canary_failed step={}", step.step_id));
+                }
+            }
+        }
+        written.extend(snapshot.attribute(store, &self.workspace_root, goal_id, "write_file")?);
+        record_provenance(store, model, goal_id, "write_file", &written)?;
+        store.update_goal_status(goal_id, GoalStatus::Completed)?;
+        store.add_episodic_memory(
+            goal_id,
+            "Canary confirmed and remaining write targets completed",
+            "discord",
+        )?;
+        Ok(format!("approved canary_remaining={}", steps.len()))
     }
-    None
 }
 
-fn slash_help() -> String {
-    [
-        "commands:",
-        "/status",
-        "/mode supervised|collab|auto",
-        "/new [model?]",
-        "/reset",
-        "/compact [instructions?]",
-        "/stop",
-        "/approve <approval_id>",
-        "/deny <approval_id>",
-        "/trace last",
-        "/model",
-        "/model list",
-        "/model status",
-        "/yolo (cli-only)",
-        "/skill install <slug>[@version]",
-        "/usage off|tokens|full",
-        "/context list|detail",
-        "/allowlist add|remove <id>",
-        "/activation mention|always",
-        "/help",
-    ]
-    .join("\n")
+// The file a `write_file` call writes to, as named in its `<path>::<content>` input.
+fn write_file_path(tool_name: &str, input: Option<&str>) -> Option<String> {
+    let (path, _) = input?.split_once("::")?;
+    (tool_name == "write_file" && !path.trim().is_empty()).then(|| path.trim().to_string())
 }
 
-fn load_runtime_config(config_path: Option<&std::path::Path>) -> Result<TitanConfig> {
-    let (cfg, _, _) = load_runtime_config_with_path(config_path)?;
-    Ok(cfg)
+// Stamps the files a goal wrote with the run's model and a hash of the goal's request.
+fn record_provenance(
+    store: &MemoryStore,
+    model: &ModelConfig,
+    goal_id: &str,
+    tool_name: &str,
+    paths: &[String],
+) -> Result<()> {
+    if paths.is_empty() {
+        return Ok(());
+    }
+    let Some(goal) = store.get_goal(goal_id)? else {
+        return Ok(());
+    };
+    ContentProvenance::new(model, &goal.description).record(store, goal_id, tool_name, paths)?;
+    Ok(())
 }
 
-fn load_runtime_config_with_path(
-    config_path: Option<&std::path::Path>,
-) -> Result<(TitanConfig, PathBuf, bool)> {
-    if let Some(path) = config_path {
-        if path.exists() {
-            let cfg = TitanConfig::load(path).map_err(|err| anyhow!("{err}"))?;
-            Ok((cfg, path.to_path_buf(), false))
-        } else {
-            let cfg = TitanConfig::default();
-            cfg.save(path).map_err(|err| anyhow!("{err}"))?;
-            Ok((cfg, path.to_path_buf(), true))
+pub const NOTIFICATION_EVENT_KINDS: &[&str] = &[
+    "approval_requested",
+    "approval_resolved",
+    "approval_expired",
+    "goal_failed",
+    "digest",
+];
+
+/// Parses a quiet-hours window such as `22:00-07:00` into UTC minutes after midnight.
+pub fn parse_quiet_hours(spec: &str) -> Result<(i64, i64)> {
+    let parse_clock = |value: &str| -> Result<i64> {
+        let (hours, minutes) = value
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| anyhow!("expected HH:MM, got '{value}'"))?;
+        let hours: i64 = hours.parse().context("invalid hour")?;
+        let minutes: i64 = minutes.parse().context("invalid minute")?;
+        if !(0..24).contains(&hours) || !(0..60).contains(&minutes) {
+            return Err(anyhow!("time out of range: '{value}'"));
         }
-    } else {
-        TitanConfig::load_or_create().map_err(|err| anyhow!("{err}"))
-    }
+        Ok(hours * 60 + minutes)
+    };
+    let (start, end) = spec
+        .split_once('-')
+        .ok_or_else(|| anyhow!("quiet hours must look like HH:MM-HH:MM"))?;
+    Ok((parse_clock(start)?, parse_clock(end)?))
 }
 
-fn model_provider_name(provider: &titan_common::ModelProvider) -> &'static str {
-    match provider {
-        titan_common::ModelProvider::OpenAi => "openai",
-        titan_common::ModelProvider::Anthropic => "anthropic",
-        titan_common::ModelProvider::Ollama => "ollama",
-        titan_common::ModelProvider::Custom => "custom",
-    }
+/// Routes an event to every operator whose preferences cover it. Each route is queued in
+/// `channel_outbox` and sent at once; every attempt is written to `notification_log`, and a
+/// failed send stays queued for the outbox worker to retry instead of failing the caller.
+pub fn dispatch_notification(
+    store: &MemoryStore,
+    event_kind: &str,
+    message: &str,
+) -> Result<Vec<titan_memory::NotificationLogRecord>> {
+    let ids = store.enqueue_notification(event_kind, "", message)?;
+    deliver_outbox_ids(store, &ids)
 }
 
-fn requires_config_approval(mode: AutonomyMode) -> bool {
-    !matches!(mode, AutonomyMode::Autonomous)
+/// Attempts before an outbox row is marked `failed`.
+pub const OUTBOX_MAX_ATTEMPTS: i64 = 8;
+
+/// How long a goal waits on an `AskUser` step when `execution.ask_user_ttl_ms` is unset.
+pub const DEFAULT_ASK_USER_TTL_MS: u64 = 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutboxDeliverySummary {
+    pub delivered: usize,
+    pub retrying: usize,
+    pub failed: usize,
 }
 
-fn apply_allowlist_change(
-    action: &str,
-    value: &str,
-    config_path: Option<&std::path::Path>,
-) -> Result<()> {
-    let (mut cfg, path, _) = load_runtime_config_with_path(config_path)?;
-    let mut allowlist: std::collections::BTreeSet<String> =
-        cfg.chat.allowlist.into_iter().collect();
-    match action {
-        "add" => {
-            allowlist.insert(value.trim().to_string());
+/// Sends every due outbox row. Retries back off on the webhook schedule.
+pub fn deliver_outbox(store: &MemoryStore, now_ms: i64) -> Result<OutboxDeliverySummary> {
+    let mut summary = OutboxDeliverySummary::default();
+    for row in store.list_due_outbox(now_ms, 100)? {
+        match deliver_outbox_row(store, &row, now_ms)?.0 {
+            "delivered" => summary.delivered += 1,
+            "pending" => summary.retrying += 1,
+            _ => summary.failed += 1,
         }
-        "remove" => {
-            allowlist.remove(value.trim());
+    }
+    Ok(summary)
+}
+
+fn deliver_outbox_ids(
+    store: &MemoryStore,
+    ids: &[i64],
+) -> Result<Vec<titan_memory::NotificationLogRecord>> {
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    let mut logged = Vec::new();
+    for id in ids {
+        if let Some(row) = store.get_outbox(*id)?
+            && row.status == "pending"
+        {
+            logged.extend(deliver_outbox_row(store, &row, now_ms)?.1);
         }
-        _ => return Err(anyhow!("unknown allowlist action: {action}")),
     }
-    cfg.chat.allowlist = allowlist.into_iter().collect();
-    cfg.save(&path).map_err(|err| anyhow!("{err}"))?;
-    Ok(())
+    Ok(logged)
 }
 
-fn apply_activation_mode(mode: &str, config_path: Option<&std::path::Path>) -> Result<()> {
-    let (mut cfg, path, _) = load_runtime_config_with_path(config_path)?;
-    cfg.chat.activation_mode = match mode.trim().to_ascii_lowercase().as_str() {
-        "always" => ActivationMode::Always,
-        "mention" => ActivationMode::Mention,
-        _ => return Err(anyhow!("invalid activation mode: {mode}")),
+/// Sends one row and returns its new status, plus the `notification_log` entry for
+/// operator routes.
+fn deliver_outbox_row(
+    store: &MemoryStore,
+    row: &titan_memory::OutboxRecord,
+    now_ms: i64,
+) -> Result<(&'static str, Option<titan_memory::NotificationLogRecord>)> {
+    let sent = match titan_comms::ChannelKind::parse(&row.channel) {
+        Some(channel) => titan_comms::channel_send(channel, &row.target, &row.message)
+            .map_err(|err| err.to_string()),
+        None => Err(format!("unsupported channel: {}", row.channel)),
     };
-    cfg.save(&path).map_err(|err| anyhow!("{err}"))?;
-    Ok(())
+    let (outbox_status, status, detail) = match sent {
+        Ok(sent) => {
+            store.record_outbox_attempt(row.id, "delivered", None, None)?;
+            ("delivered", sent.status, sent.detail)
+        }
+        Err(err) => {
+            let attempt = row.attempts + 1;
+            let outbox_status = if attempt >= OUTBOX_MAX_ATTEMPTS {
+                store.record_outbox_attempt(row.id, "failed", None, Some(&err))?;
+                "failed"
+            } else {
+                store.record_outbox_attempt(
+                    row.id,
+                    "pending",
+                    Some(now_ms + webhook_backoff_ms(attempt)),
+                    Some(&err),
+                )?;
+                "pending"
+            };
+            (outbox_status, "failed".to_string(), err)
+        }
+    };
+    let Some(operator) = row.operator.clone() else {
+        return Ok((outbox_status, None));
+    };
+    let route = titan_memory::NotificationPrefRecord {
+        operator,
+        event_kind: row.event_kind.clone(),
+        channel: row.channel.clone(),
+        target: row.target.clone(),
+        quiet_start_minute: None,
+        quiet_end_minute: None,
+    };
+    let log = store.record_notification(&route, &row.event_kind, &row.message, &status, &detail)?;
+    Ok((outbox_status, Some(log)))
 }
 
-fn is_message_allowed(
-    inbound: &InboundEvent,
-    session: &titan_memory::SessionRecord,
-    config_path: Option<&std::path::Path>,
-) -> Result<bool> {
-    // Local/system-triggered events (CLI + scheduler jobs) bypass channel activation and allowlist checks.
-    if matches!(inbound.channel, Channel::Cli) {
-        return Ok(true);
+/// Longest diff embedded in a chat reply; Discord rejects messages over 2000 characters.
+pub const APPROVAL_DIFF_EMBED_MAX_BYTES: usize = 1500;
+
+/// Most approvals one bulk decision resolves, from the web API or `/approve all`.
+pub const BULK_APPROVAL_LIMIT: usize = 200;
+
+/// The line diff a pending `write_file` approval would apply, cut to
+/// [`APPROVAL_DIFF_EMBED_MAX_BYTES`]. `None` for other tools or targets outside the workspace.
+pub fn approval_diff_preview(
+    workspace_root: &std::path::Path,
+    approval: &titan_memory::ApprovalRecord,
+) -> Option<String> {
+    if approval.tool_name != "write_file" {
+        return None;
     }
-    let cfg = load_runtime_config(config_path)?;
-    if !cfg.chat.allowlist.is_empty()
-        && !cfg.chat.allowlist.iter().any(|id| id == &inbound.actor_id)
+    let (path, content) = approval.input.split_once("::")?;
+    let relative = std::path::Path::new(path.trim());
+    if relative.is_absolute()
+        || relative
+            .components()
+            .any(|part| matches!(part, std::path::Component::ParentDir))
     {
-        return Ok(false);
+        return None;
     }
-    let session_mode = session.activation_mode.to_ascii_lowercase();
-    let effective_mode = if session_mode == "mention" {
-        ActivationMode::Mention
-    } else {
-        cfg.chat.activation_mode
-    };
-    if matches!(effective_mode, ActivationMode::Mention) {
-        let lowered = inbound.text.to_ascii_lowercase();
-        if !lowered.contains("titan") && !inbound.text.contains('/') {
-            return Ok(false);
+    let before = std::fs::read_to_string(workspace_root.join(relative)).unwrap_or_default();
+    let mut diff = titan_tools::render_line_diff(&before, content);
+    if diff.len() > APPROVAL_DIFF_EMBED_MAX_BYTES {
+        let mut cut = APPROVAL_DIFF_EMBED_MAX_BYTES;
+        while !diff.is_char_boundary(cut) {
+            cut -= 1;
         }
+        diff.truncate(cut);
+        diff.push_str("\n...");
     }
-    Ok(true)
+    Some(diff)
 }
 
-fn parse_slug_and_version(input: &str) -> (String, Option<String>) {
-    match input.split_once('@') {
-        Some((slug, version)) => (slug.to_string(), Some(version.to_string())),
-        None => (input.to_string(), None),
+/// Where approval notices go besides operator routes: the originating conversation when
+/// `approvals.notify_origin` is on, and `approvals.operator_channel`, each once.
+pub fn approval_notice_targets(
+    approvals: &titan_common::ApprovalsConfig,
+    origin: Option<(Channel, &str)>,
+) -> Vec<ChannelTarget> {
+    let mut targets = Vec::new();
+    if approvals.notify_origin
+        && let Some((channel, target)) = origin
+    {
+        targets.push(ChannelTarget {
+            channel: channel.as_str().to_string(),
+            target: target.to_string(),
+        });
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::tempdir;
+    if let Some(operator) = &approvals.operator_channel {
+        let target = ChannelTarget {
+            channel: operator.channel.trim().to_string(),
+            target: operator.target.trim().to_string(),
+        };
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+    targets
+}
 
-    fn write_test_config(workspace: &std::path::Path) -> std::path::PathBuf {
-        let mut cfg = TitanConfig {
-            workspace_dir: workspace.to_path_buf(),
-            ..TitanConfig::default()
+/// Routes `approval_requested` to operator routes and posts an approval notice to `targets`.
+pub fn notify_approval_requested(
+    store: &MemoryStore,
+    approval_id: &str,
+    targets: &[ChannelTarget],
+) -> Result<()> {
+    let ids = store.enqueue_approval_notices(approval_id, targets)?;
+    deliver_outbox_ids(store, &ids)?;
+    if let Some(approval) = store.get_approval_request(approval_id)? {
+        dispatch_notification(
+            store,
+            "approval_requested",
+            &format!(
+                "approval {} pending: {} ({}) requested by {}",
+                approval.id,
+                approval.tool_name,
+                approval.capability,
+                approval.requested_by.as_deref().unwrap_or("unknown")
+            ),
+        )?;
+    }
+    Ok(())
+}
+
+pub fn notify_approval_resolved(
+    store: &MemoryStore,
+    approval_id: &str,
+    resolved_by: &str,
+    status: &str,
+) -> Result<()> {
+    dispatch_notification(
+        store,
+        "approval_resolved",
+        &format!("approval {approval_id} resolved by {resolved_by}: {status}"),
+    )?;
+    Ok(())
+}
+
+pub const WEBHOOK_EVENT_KINDS: &[&str] = &[
+    "goal_completed",
+    "goal_failed",
+    "approval_created",
+    "approval_expired",
+    "risk_mode_changed",
+    "safe_mode_changed",
+    "model_provider_changed",
+    "access_anomaly",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WebhookDispatchSummary {
+    pub enqueued: usize,
+    pub delivered: usize,
+    pub retrying: usize,
+    pub failed: usize,
+}
+
+/// Delay before retry `attempt` (1-based): 10s doubling per attempt, capped at one hour.
+pub fn webhook_backoff_ms(attempt: i64) -> i64 {
+    let exponent = attempt.saturating_sub(1).clamp(0, 20) as u32;
+    10_000_i64
+        .saturating_mul(2_i64.pow(exponent))
+        .min(3_600_000)
+}
+
+pub fn webhook_payload(event: &titan_memory::LifecycleEventRecord) -> serde_json::Value {
+    let data: serde_json::Map<String, serde_json::Value> = event
+        .detail
+        .split_whitespace()
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (key.to_string(), serde_json::Value::from(value)))
+        .collect();
+    serde_json::json!({
+        "id": event.id,
+        "event": event.kind,
+        "subject_id": event.subject_id,
+        "created_at_ms": event.created_at_ms,
+        "data": data,
+    })
+}
+
+/// Fans new lifecycle events out to the configured webhooks, then sends every due delivery.
+/// A webhook added later starts from the newest event instead of replaying history.
+pub fn dispatch_webhooks(
+    store: &MemoryStore,
+    webhooks: &[titan_common::WebhookConfig],
+    now_ms: i64,
+) -> Result<WebhookDispatchSummary> {
+    let mut summary = WebhookDispatchSummary::default();
+    for hook in webhooks {
+        let Some(cursor) = store.webhook_cursor(&hook.name)? else {
+            store.set_webhook_cursor(&hook.name, store.latest_lifecycle_event_id()?)?;
+            continue;
         };
-        cfg.chat.activation_mode = ActivationMode::Always;
-        let tmp = workspace.join("test-config.toml");
-        cfg.save(&tmp).expect("save config");
-        tmp
+        let events = store.list_lifecycle_events_after(cursor, 500)?;
+        for event in &events {
+            if hook.events.is_empty() || hook.events.contains(&event.kind) {
+                store.enqueue_webhook_delivery(event.id, &hook.name)?;
+                summary.enqueued += 1;
+            }
+        }
+        if let Some(last) = events.last() {
+            store.set_webhook_cursor(&hook.name, last.id)?;
+        }
     }
 
-    fn seed_local_registry_skill(workspace: &std::path::Path, slug: &str, version: &str) {
-        let bundle = workspace
-            .join(".titan/registry/local/bundles")
-            .join(format!("{slug}-{version}"));
-        std::fs::create_dir_all(&bundle).expect("bundle dir");
-        std::fs::write(bundle.join("SKILL.md"), "# skill\n").expect("skill docs");
-        std::fs::write(
-            bundle.join("skill.toml"),
+    for delivery in store.list_due_webhook_deliveries(now_ms, 100)? {
+        let Some(hook) = webhooks.iter().find(|hook| hook.name == delivery.webhook) else {
+            store.record_webhook_attempt(
+                delivery.id,
+                "failed",
+                None,
+                None,
+                Some("webhook no longer configured"),
+            )?;
+            summary.failed += 1;
+            continue;
+        };
+        let Some(event) = store.get_lifecycle_event(delivery.event_id)? else {
+            store.record_webhook_attempt(
+                delivery.id,
+                "failed",
+                None,
+                None,
+                Some("event missing"),
+            )?;
+            summary.failed += 1;
+            continue;
+        };
+        let body = webhook_payload(&event).to_string();
+        let outcome = match &hook.secret_env {
+            Some(var) => std::env::var(var)
+                .map_err(|_| anyhow!("missing webhook secret env {var}"))
+                .and_then(|secret| {
+                    titan_comms::post_webhook(
+                        &hook.url,
+                        &event.kind,
+                        delivery.id,
+                        &body,
+                        Some(&secret),
+                    )
+                }),
+            None => titan_comms::post_webhook(&hook.url, &event.kind, delivery.id, &body, None),
+        };
+        let (code, error) = match outcome {
+            Ok(code) if (200..300).contains(&code) => {
+                store.record_webhook_attempt(delivery.id, "delivered", None, Some(code), None)?;
+                summary.delivered += 1;
+                continue;
+            }
+            Ok(code) => (Some(code), format!("http status {code}")),
+            Err(err) => (None, err.to_string()),
+        };
+        let attempts = delivery.attempts + 1;
+        if attempts >= i64::from(hook.max_attempts) {
+            store.record_webhook_attempt(delivery.id, "failed", None, code, Some(&error))?;
+            summary.failed += 1;
+        } else {
+            store.record_webhook_attempt(
+                delivery.id,
+                "pending",
+                Some(now_ms + webhook_backoff_ms(attempts)),
+                code,
+                Some(&error),
+            )?;
+            summary.retrying += 1;
+        }
+    }
+    Ok(summary)
+}
+
+/// `approver_not_allowed ...` when `[approvals] approvers` lists the approval's capability
+/// without `approver`, traced on the approval's goal; `None` when they may decide it.
+pub fn approver_refusal(
+    store: &MemoryStore,
+    approval: &titan_memory::ApprovalRecord,
+    approver: &str,
+) -> Result<Option<String>> {
+    if PolicyEngine::may_decide_approval(&approval.capability, approver) {
+        return Ok(None);
+    }
+    let refusal = format!(
+        "approver_not_allowed approver={approver} capability={}",
+        approval.capability
+    );
+    if let Some(goal_id) = &approval.goal_id {
+        store.add_trace_event(&TraceEvent::new(
+            goal_id.clone(),
+            "approval_refused",
+            format!("approval={} {refusal}", approval.id),
+        ))?;
+    }
+    Ok(Some(refusal))
+}
+
+/// Why `edited_input` cannot replace this approval's input, or `None` when there is no edit or
+/// it is allowed. Only approvals of built-in tools take edits; connector, skill, and config
+/// approvals carry encoded payloads that must run as requested. Approvals that need more than
+/// one approver take no edits either, since earlier votes were cast for the original input.
+pub fn approval_edit_refusal(
+    store: &MemoryStore,
+    mode: &AutonomyMode,
+    approval: &titan_memory::ApprovalRecord,
+    approved: bool,
+    edited_input: Option<&str>,
+) -> Result<Option<String>> {
+    if edited_input.is_none() {
+        return Ok(None);
+    }
+    if !approved {
+        return Ok(Some("edit_not_allowed reason=deny_with_input".to_string()));
+    }
+    if approval.tool_name == CANARY_CONTINUE_TOOL
+        || ToolRegistry::with_defaults()
+            .get(&approval.tool_name)
+            .is_none()
+    {
+        return Ok(Some(format!(
+            "edit_not_allowed tool={}",
+            approval.tool_name
+        )));
+    }
+    if approval_quorum(store, mode, approval)? > 1 {
+        return Ok(Some("edit_not_allowed reason=quorum".to_string()));
+    }
+    Ok(None)
+}
+
+/// Distinct approvers `approval` needs: the policy's quorum, raised to two for exec and write
+/// approvals when `[approvals] two_person_secure` is on and the runtime is in secure mode.
+fn approval_quorum(
+    store: &MemoryStore,
+    mode: &AutonomyMode,
+    approval: &titan_memory::ApprovalRecord,
+) -> Result<u32> {
+    let class = titan_tools::CapabilityClass::parse(&approval.capability)
+        .unwrap_or(titan_tools::CapabilityClass::Write);
+    let quorum = PolicyEngine::approval_quorum(mode, &approval.tool_name, class);
+    if PolicyEngine::two_person_approval(class)
+        && matches!(store.get_runtime_risk_state()?.risk_mode, RiskMode::Secure)
+    {
+        return Ok(quorum.max(2));
+    }
+    Ok(quorum)
+}
+
+/// Records `voter`'s approve vote and reports `(votes, quorum)` while the quorum is unmet. See
+/// [`approval_quorum`] for how the quorum is set.
+pub fn approval_quorum_shortfall(
+    store: &MemoryStore,
+    mode: &AutonomyMode,
+    approval: &titan_memory::ApprovalRecord,
+    voter: &str,
+) -> Result<Option<(i64, u32)>> {
+    if approval.status != "pending" {
+        return Ok(None);
+    }
+    let quorum = approval_quorum(store, mode, approval)?;
+    if quorum <= 1 {
+        return Ok(None);
+    }
+    let votes = store.record_approval_decision(&approval.id, voter, true)?;
+    if votes >= i64::from(quorum) {
+        return Ok(None);
+    }
+    if let Some(goal_id) = &approval.goal_id {
+        store.add_trace_event(&TraceEvent::new(
+            goal_id.clone(),
+            "approval_vote",
             format!(
-                r#"name = "{slug}"
-slug = "{slug}"
-version = "{version}"
-description = "demo"
-entrypoint_type = "prompt"
-entrypoint = "tool:list_dir ."
+                "approval={} voter={voter} votes={votes}/{quorum}",
+                approval.id
+            ),
+        ))?;
+    }
+    Ok(Some((votes, quorum)))
+}
+
+/// The run's read-step memo, extended to built-in read tools across the session's goals for
+/// `execution.step_cache_ttl_ms`. Storage errors count as misses; the step just runs.
+struct SessionStepCache<'a> {
+    store: &'a MemoryStore,
+    registry: &'a ToolRegistry,
+    session_id: &'a str,
+    since_ms: i64,
+    enabled: bool,
+    run: RunStepCache,
+}
+
+impl SessionStepCache<'_> {
+    fn shares(&self, tool_name: &str) -> bool {
+        self.enabled
+            && self
+                .registry
+                .get(tool_name)
+                .is_some_and(|tool| tool.class == titan_tools::CapabilityClass::Read)
+    }
+}
+
+impl StepCache for SessionStepCache<'_> {
+    fn get(&mut self, tool_name: &str, input: Option<&str>) -> Option<StepResult> {
+        if let Some(hit) = self.run.get(tool_name, input) {
+            return Some(hit);
+        }
+        if !self.shares(tool_name) {
+            return None;
+        }
+        self.store
+            .cached_step_result(self.session_id, tool_name, input, self.since_ms)
+            .ok()
+            .flatten()
+    }
+
+    fn put(&mut self, input: Option<&str>, result: &StepResult) {
+        self.run.put(input, result);
+        if self.shares(&result.tool_name) {
+            let _ = self.store.cache_step_result(self.session_id, input, result);
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.run.invalidate();
+        let _ = self.store.clear_step_cache(self.session_id);
+    }
+}
+
+/// How a goal reaches the planner beyond its inbound text.
+#[derive(Default)]
+struct GoalRunOptions<'a> {
+    template: Option<TemplateRun<'a>>,
+    /// Ask a clarification question instead of planning generic recon for an unclear request.
+    clarify_unsure: bool,
+    /// Resumes this goal, held in `awaiting_clarification`, instead of creating one.
+    clarified: Option<&'a ClarificationRecord>,
+    /// Resumes this goal paused on an `AskUser` step; the inbound text is the answer.
+    answered: Option<&'a PendingInputRecord>,
+    /// Resumes this goal from where an urgent goal preempted it.
+    resumed: Option<&'a GoalCheckpointRecord>,
+    /// Starts this goal, held in `queued` until a concurrency slot freed up.
+    dequeued: Option<&'a QueuedGoalRecord>,
+}
+
+/// The first configured limit the running goals already fill, as `<scope> limit n/max`.
+fn concurrency_limit(
+    execution: &titan_common::ExecutionConfig,
+    running: RunningGoalCounts,
+) -> Option<String> {
+    [
+        ("global", running.global, execution.max_concurrent_goals),
+        (
+            "channel",
+            running.channel,
+            execution.max_concurrent_goals_per_channel,
+        ),
+        (
+            "session",
+            running.session,
+            execution.max_concurrent_goals_per_session,
+        ),
+    ]
+    .into_iter()
+    .find_map(|(scope, count, limit)| {
+        let limit = limit? as usize;
+        (count >= limit).then(|| format!("{scope} limit {count}/{limit}"))
+    })
+}
+
+/// The reply refusing a new request once `[usage]` budgets are spent: the session's own
+/// budget first, then its peer's budget for the current UTC day.
+pub(crate) fn usage_budget_refusal(
+    store: &MemoryStore,
+    usage: &UsageConfig,
+    session: &SessionRecord,
+    now_ms: i64,
+) -> Result<Option<String>> {
+    if let Some(budget) = usage.session_token_budget {
+        let used = store.session_usage(&session.id)?.tokens;
+        if used >= budget {
+            return Ok(Some(format!(
+                "Token budget reached: this session used {used} of {budget} estimated tokens. \
+                 Send /new to start a fresh session."
+            )));
+        }
+    }
+    if let Some(budget) = usage.daily_token_budget {
+        let used = store
+            .peer_usage_since(&session.channel, &session.peer_id, utc_day_start_ms(now_ms))?
+            .tokens;
+        if used >= budget {
+            return Ok(Some(format!(
+                "Daily token budget reached: {used} of {budget} estimated tokens used today. \
+                 It resets at 00:00 UTC."
+            )));
+        }
+    }
+    Ok(None)
+}
+
+pub(crate) fn usage_cost_usd(usage: &UsageConfig, tokens: u64) -> f64 {
+    tokens as f64 / 1000.0 * usage.usd_per_1k_tokens
+}
+
+pub(crate) fn utc_day_start_ms(now_ms: i64) -> i64 {
+    now_ms - now_ms.rem_euclid(86_400_000)
+}
+
+/// `channel:<channel>` and `intent:<intent>`, with intents named as federation peers list them.
+pub fn auto_goal_labels(channel: Channel, intent: &GoalIntent) -> Vec<String> {
+    vec![
+        format!("channel:{}", channel.as_str()),
+        format!("intent:{}", intent_label(intent)),
+    ]
+}
+
+/// Holds a new goal in `queued`; `start_queued_goals` runs it once a slot frees up.
+fn queue_goal(
+    store: &MemoryStore,
+    session: &titan_memory::SessionRecord,
+    inbound: &InboundEvent,
+    mut goal: Goal,
+    labels: &[String],
+    queued: &QueuedGoalRecord,
+    limit: &str,
+) -> Result<ProcessedEvent> {
+    let risk_mode = store
+        .get_runtime_risk_state()?
+        .risk_mode
+        .as_str()
+        .to_string();
+    goal.status = GoalStatus::Queued;
+    store.create_goal_for_session(&goal, Some(&session.id))?;
+    store.add_goal_labels(&goal.id, labels, GOAL_LABEL_AUTO)?;
+    store.enqueue_goal(queued)?;
+    let position = store.queued_goal_position(&goal.id)?.unwrap_or(1);
+    for (event_type, detail) in [
+        ("goal_submitted", inbound.text.clone()),
+        (
+            "event_received",
+            format!(
+                "source={} actor={}",
+                inbound.channel.as_str(),
+                inbound.actor_id
+            ),
+        ),
+        ("goal_queued", format!("position={position} {limit}")),
+    ] {
+        store.add_trace_event(
+            &TraceEvent::new(goal.id.clone(), event_type, detail).with_risk_mode(risk_mode.clone()),
+        )?;
+    }
+    let summary = format!("Queued at position {position}: {limit} goals running");
+    store.set_session_queue_depth(&session.id, 0)?;
+    store.add_session_message(&session.id, "assistant", &summary, false)?;
+    Ok(ProcessedEvent {
+        session_id: session.id.clone(),
+        goal_id: goal.id,
+        goal_status: goal.status,
+        pending_approval_id: None,
+        summary,
+        reply_outbox_id: None,
+        plan: None,
+    })
+}
+
+/// Stores a `plan_only` goal as cancelled, with its plan and which steps would need approval,
+/// and replies with the rendered preview.
+fn persist_plan_only(
+    store: &MemoryStore,
+    session: &titan_memory::SessionRecord,
+    inbound: &InboundEvent,
+    mut goal: Goal,
+    labels: &[String],
+    plan: &titan_core::TaskPlan,
+    preview: PlanPreview,
+) -> Result<ProcessedEvent> {
+    let risk_mode = store
+        .get_runtime_risk_state()?
+        .risk_mode
+        .as_str()
+        .to_string();
+    goal.status = GoalStatus::Cancelled;
+    let gated = preview.gated_step_ids();
+    let mut traces = vec![
+        TraceEvent::new(goal.id.clone(), "goal_submitted", inbound.text.clone()),
+        TraceEvent::new(
+            goal.id.clone(),
+            "event_received",
+            format!(
+                "source={} actor={}",
+                inbound.channel.as_str(),
+                inbound.actor_id
+            ),
+        ),
+    ];
+    traces.extend(plan.traces.iter().cloned());
+    traces.push(TraceEvent::new(
+        goal.id.clone(),
+        "plan_only",
+        format!(
+            "execution skipped; {} of {} steps would need approval",
+            gated.len(),
+            preview
+                .selected()
+                .map_or(0, |selected| selected.steps.len())
+        ),
+    ));
+    let traces: Vec<TraceEvent> = traces
+        .into_iter()
+        .map(|trace| trace.with_risk_mode(risk_mode.clone()))
+        .collect();
+    store.persist_planned_goal(&goal, Some(&session.id), plan, &gated, &traces)?;
+    store.add_goal_labels(&goal.id, labels, GOAL_LABEL_AUTO)?;
+    let summary = preview.render(&goal.id);
+    store.set_session_queue_depth(&session.id, 0)?;
+    store.add_session_message(&session.id, "assistant", &summary, false)?;
+    Ok(ProcessedEvent {
+        session_id: session.id.clone(),
+        goal_id: goal.id,
+        goal_status: goal.status,
+        pending_approval_id: None,
+        summary,
+        reply_outbox_id: None,
+        plan: Some(preview),
+    })
+}
+
+/// Holds a goal only a model planner can handle as `waiting_for_model` while the provider
+/// is unreachable. It keeps its queue entry, so `start_queued_goals` runs it once the
+/// provider answers again.
+const EXPERIMENT_CONTROL: &str = "control";
+const EXPERIMENT_VARIANT: &str = "variant";
+
+/// The experiment arm for `goal_id`, stable across resumes: an FNV-1a hash of the id picks a
+/// bucket in 0..100 and buckets below `variant_percent` get the variant.
+fn experiment_arm(cfg: &ExperimentConfig, goal_id: &str) -> Option<PlanExperiment> {
+    let name = cfg.name.as_ref()?;
+    let hash = goal_id
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    let variant = if hash % 100 < u64::from(cfg.variant_percent) {
+        EXPERIMENT_VARIANT
+    } else {
+        EXPERIMENT_CONTROL
+    };
+    Some(PlanExperiment {
+        name: name.clone(),
+        variant: variant.to_string(),
+    })
+}
+
+fn hold_goal_for_model(
+    store: &MemoryStore,
+    session: &titan_memory::SessionRecord,
+    inbound: &InboundEvent,
+    mut goal: Goal,
+    labels: &[String],
+    queued: &QueuedGoalRecord,
+    dequeued: bool,
+) -> Result<ProcessedEvent> {
+    let risk_mode = store
+        .get_runtime_risk_state()?
+        .risk_mode
+        .as_str()
+        .to_string();
+    goal.status = GoalStatus::WaitingForModel;
+    let mut traces = Vec::new();
+    if dequeued {
+        store.update_goal_status(&goal.id, GoalStatus::WaitingForModel)?;
+    } else {
+        store.create_goal_for_session(&goal, Some(&session.id))?;
+        store.add_goal_labels(&goal.id, labels, GOAL_LABEL_AUTO)?;
+        store.enqueue_goal(queued)?;
+        traces.push(("goal_submitted", inbound.text.clone()));
+        traces.push((
+            "event_received",
+            format!(
+                "source={} actor={}",
+                inbound.channel.as_str(),
+                inbound.actor_id
+            ),
+        ));
+    }
+    traces.push((
+        "goal_waiting_for_model",
+        "model provider unreachable; the request needs the model planner".to_string(),
+    ));
+    for (event_type, detail) in traces {
+        store.add_trace_event(
+            &TraceEvent::new(goal.id.clone(), event_type, detail).with_risk_mode(risk_mode.clone()),
+        )?;
+    }
+    let summary = "The model provider is unreachable, so this request waits until it is back. \
+                   Requests the built-in rules cover, like 'scan workspace', still run."
+        .to_string();
+    store.set_session_queue_depth(&session.id, 0)?;
+    store.add_session_message(&session.id, "assistant", &summary, false)?;
+    Ok(ProcessedEvent {
+        session_id: session.id.clone(),
+        goal_id: goal.id,
+        goal_status: goal.status,
+        pending_approval_id: None,
+        summary,
+        reply_outbox_id: None,
+        plan: None,
+    })
+}
+
+fn request_clarification(
+    store: &MemoryStore,
+    session: &titan_memory::SessionRecord,
+    inbound: &InboundEvent,
+    mut goal: Goal,
+    labels: &[String],
+    catalog: &[ToolCatalogEntry],
+    risk_mode: &str,
+) -> Result<ProcessedEvent> {
+    let mut suggestions = vec![
+        "scan workspace".to_string(),
+        "read <path>".to_string(),
+        "update readme".to_string(),
+    ];
+    suggestions.extend(catalog.iter().take(5).map(|entry| entry.name.clone()));
+    let question = format!(
+        "Not sure what to do with '{}'. Could you say more, e.g. {}? Reply with details, or 'cancel'.",
+        inbound.text.trim(),
+        suggestions.join(", ")
+    );
+    goal.status = GoalStatus::AwaitingClarification;
+    store.create_goal_for_session(&goal, Some(&session.id))?;
+    store.add_goal_labels(&goal.id, labels, GOAL_LABEL_AUTO)?;
+    for (event_type, detail) in [
+        ("goal_submitted", inbound.text.clone()),
+        (
+            "event_received",
+            format!(
+                "source={} actor={}",
+                inbound.channel.as_str(),
+                inbound.actor_id
+            ),
+        ),
+        ("clarification_requested", question.clone()),
+    ] {
+        store.add_trace_event(
+            &TraceEvent::new(goal.id.clone(), event_type, detail).with_risk_mode(risk_mode),
+        )?;
+    }
+    store.set_clarification(&session.id, &goal.id, inbound.text.trim(), &question)?;
+    store.set_session_queue_depth(&session.id, 0)?;
+    store.add_session_message(&session.id, "assistant", &question, false)?;
+    Ok(ProcessedEvent {
+        session_id: session.id.clone(),
+        goal_id: goal.id,
+        goal_status: goal.status,
+        pending_approval_id: None,
+        summary: question,
+        reply_outbox_id: None,
+        plan: None,
+    })
+}
+
+/// Hands a new goal to a federation peer. The local goal mirrors the remote one's status, and
+/// `sync_remote_goals` keeps merging its traces until it finishes.
+fn forward_goal(
+    store: &MemoryStore,
+    session: &titan_memory::SessionRecord,
+    inbound: &InboundEvent,
+    request_text: &str,
+    mut goal: Goal,
+    labels: &[String],
+    peer: &titan_common::FederationPeerConfig,
+) -> Result<ProcessedEvent> {
+    let risk_mode = store
+        .get_runtime_risk_state()?
+        .risk_mode
+        .as_str()
+        .to_string();
+    let trace = |event_type: &str, detail: String| {
+        store.add_trace_event(
+            &TraceEvent::new(goal.id.clone(), event_type, detail).with_risk_mode(risk_mode.clone()),
+        )
+    };
+    goal.status = GoalStatus::Executing;
+    store.create_goal_for_session(&goal, Some(&session.id))?;
+    store.add_goal_labels(&goal.id, labels, GOAL_LABEL_AUTO)?;
+    trace("goal_submitted", inbound.text.clone())?;
+    trace(
+        "event_received",
+        format!(
+            "source={} actor={}",
+            inbound.channel.as_str(),
+            inbound.actor_id
+        ),
+    )?;
+    trace(
+        "goal_forwarded",
+        format!("peer={} url={}", peer.name, peer.url),
+    )?;
+    let submitted = titan_comms::submit_remote_goal(
+        &peer.url,
+        &format!("{FEDERATION_ACTOR_PREFIX}{}", inbound.actor_id),
+        request_text.trim(),
+        &format!("{FEDERATION_ACTOR_PREFIX}{}", goal.id),
+    )
+    .and_then(|submission| match &submission.goal_id {
+        Some(remote_goal_id) if matches!(submission.status.as_str(), "processed" | "duplicate") => {
+            Ok((remote_goal_id.clone(), submission))
+        }
+        _ => Err(anyhow!("{}: {}", submission.status, submission.detail)),
+    });
+    let summary = match submitted {
+        Ok((remote_goal_id, submission)) => {
+            // Recorded as executing so the first sync applies whatever status the peer reports.
+            store.record_remote_goal(
+                &goal.id,
+                &peer.name,
+                &remote_goal_id,
+                GoalStatus::Executing.as_str(),
+            )?;
+            if let Some(record) = store.remote_goal(&goal.id)?
+                && let Err(err) =
+                    federation::sync_remote_goal(store, std::slice::from_ref(peer), &record)
+            {
+                trace("federation_sync_failed", err.to_string())?;
+            }
+            goal.status = store
+                .get_goal(&goal.id)?
+                .and_then(|stored| GoalStatus::parse(&stored.status))
+                .unwrap_or(goal.status);
+            format!(
+                "Forwarded to {} as goal {}: status={} {}",
+                peer.name,
+                remote_goal_id,
+                submission.goal_status.as_deref().unwrap_or("pending"),
+                submission.detail
+            )
+        }
+        Err(err) => {
+            trace("goal_forward_failed", format!("peer={} {err}", peer.name))?;
+            store.update_goal_status(&goal.id, GoalStatus::Failed)?;
+            goal.status = GoalStatus::Failed;
+            format!("Forwarding to {} failed: {err}", peer.name)
+        }
+    };
+    store.set_session_queue_depth(&session.id, 0)?;
+    store.add_session_message(&session.id, "assistant", &summary, false)?;
+    Ok(ProcessedEvent {
+        session_id: session.id.clone(),
+        goal_id: goal.id,
+        goal_status: goal.status,
+        pending_approval_id: None,
+        summary,
+        reply_outbox_id: None,
+        plan: None,
+    })
+}
+
+/// A goal template filled in by `/run`, planned with the template's preferred candidate.
+struct TemplateRun<'a> {
+    template: &'a GoalTemplateConfig,
+    values: &'a [String],
+}
+
+impl TemplateRun<'_> {
+    fn describe(&self) -> String {
+        let mut detail = format!("template={}", self.template.name);
+        for (param, value) in self.template.params.iter().zip(self.values) {
+            detail.push_str(&format!(" {param}={value}"));
+        }
+        if let Some(plan) = &self.template.plan {
+            detail.push_str(&format!(" plan={plan}"));
+        }
+        detail
+    }
+}
+
+enum TemplateProgress {
+    Prompt(String),
+    Ran(Box<ProcessedEvent>),
+}
+
+fn event_chat_result(event_result: ProcessedEvent) -> ChatCommandResult {
+    ChatCommandResult {
+        session_id: event_result.session_id,
+        reply_outbox_id: event_result.reply_outbox_id,
+        response: format!(
+            "goal={} status={} summary={}{}",
+            event_result.goal_id,
+            event_result.goal_status.as_str(),
+            event_result.summary,
+            event_result
+                .pending_approval_id
+                .map(|id| format!(" approval_pending={id}"))
+                .unwrap_or_default()
+        ),
+    }
+}
+
+fn parse_slash_command(text: &str) -> Option<String> {
+    let trimmed = text.trim();
+    if trimmed.starts_with("/titan ") {
+        let cmd = trimmed.trim_start_matches("/titan").trim();
+        return Some(format!("/{cmd}"));
+    }
+    if trimmed.starts_with('/') {
+        return Some(trimmed.to_string());
+    }
+    None
+}
+
+fn planner_tool_catalog(
+    store: &MemoryStore,
+    workspace_root: &std::path::Path,
+) -> Result<Vec<ToolCatalogEntry>> {
+    let mut catalog = Vec::new();
+    for row in store.list_connectors()? {
+        let Some(connector_type) = ConnectorType::parse(&row.connector_type) else {
+            continue;
+        };
+        for tool in connector_tools(connector_type) {
+            let payload = connector_approval_payload(&row.id, &tool.name, serde_json::json!({}))?;
+            catalog.push(ToolCatalogEntry {
+                permission: step_permission_for_class(tool.risk_class),
+                name: tool.name,
+                description: tool.description,
+                tool_name: "connector_tool".to_string(),
+                input: Some(payload),
+            });
+        }
+    }
+    for skill in titan_skills::list_installed_skills_v1(workspace_root)? {
+        let permission = skill
+            .manifest
+            .permissions
+            .scopes
+            .iter()
+            .map(|scope| step_permission_for_class(scope.as_capability_class()))
+            .max_by_key(|permission| match permission {
+                StepPermission::Read => 0,
+                StepPermission::Net => 1,
+                StepPermission::Exec => 2,
+                StepPermission::Write => 3,
+            })
+            .unwrap_or(StepPermission::Read);
+        catalog.push(ToolCatalogEntry {
+            name: skill.manifest.slug.clone(),
+            description: skill.manifest.description.clone(),
+            permission,
+            tool_name: "skill_run".to_string(),
+            input: Some(titan_skills::skill_run_payload(&skill.manifest.slug, None)?),
+        });
+    }
+    Ok(catalog)
+}
+
+fn step_permission_for_class(class: titan_tools::CapabilityClass) -> StepPermission {
+    match class {
+        titan_tools::CapabilityClass::Read => StepPermission::Read,
+        titan_tools::CapabilityClass::Write => StepPermission::Write,
+        titan_tools::CapabilityClass::Exec => StepPermission::Exec,
+        titan_tools::CapabilityClass::Net => StepPermission::Net,
+    }
+}
+
+/// Whether policy holds a step with `permission` for approval under `mode` and the runtime
+/// risk mode.
+fn step_requires_approval(
+    mode: &AutonomyMode,
+    risk_mode: &RiskMode,
+    tool_name: &str,
+    permission: StepPermission,
+) -> bool {
+    let class = match permission {
+        StepPermission::Read => titan_tools::CapabilityClass::Read,
+        StepPermission::Write => titan_tools::CapabilityClass::Write,
+        StepPermission::Exec => titan_tools::CapabilityClass::Exec,
+        StepPermission::Net => titan_tools::CapabilityClass::Net,
+    };
+    let risk = if matches!(risk_mode, RiskMode::Yolo) {
+        ToolRiskMode::Yolo
+    } else {
+        ToolRiskMode::Secure
+    };
+    PolicyEngine::requires_approval_for_tool(mode.clone(), risk, class, Some(tool_name))
+}
+
+fn skill_outcome_summary(outcome: &titan_skills::SkillRunOutcome) -> String {
+    match &outcome.state {
+        titan_skills::SkillRunState::Completed => {
+            format!("skill_goal={} status=completed", outcome.goal_id)
+        }
+        titan_skills::SkillRunState::PendingApproval(approval_id) => format!(
+            "skill_goal={} status=pending_approval approval_id={}",
+            outcome.goal_id, approval_id
+        ),
+    }
+}
+
+// Closes out the planner goal when an approval came from a planned catalog step.
+fn complete_planned_step(
+    store: &MemoryStore,
+    goal_id: Option<&str>,
+    tool_name: &str,
+    output: &str,
+) -> Result<()> {
+    let Some(goal_id) = goal_id else {
+        return Ok(());
+    };
+    if store.mark_blocked_step_executed_for_goal(goal_id, tool_name, output)? > 0 {
+        store.add_trace_event(&TraceEvent::new(
+            goal_id.to_string(),
+            "approval_executed",
+            format!("{tool_name} -> {output}"),
+        ))?;
+        store.update_goal_status(goal_id, GoalStatus::Completed)?;
+    }
+    Ok(())
+}
+
+fn handle_connectors_command(store: &MemoryStore, args: &[&str]) -> Result<String> {
+    if args.first() != Some(&"tools") || args.len() > 2 {
+        return Ok("usage: /connectors tools [connector_id]".to_string());
+    }
+    let connectors = store
+        .list_connectors()?
+        .into_iter()
+        .filter(|row| args.get(1).is_none_or(|id| row.id == *id))
+        .collect::<Vec<_>>();
+    if connectors.is_empty() {
+        return Ok(match args.get(1) {
+            Some(id) => format!("connector not found: {id}"),
+            None => "no connectors configured".to_string(),
+        });
+    }
+    let mut lines = Vec::new();
+    for row in connectors {
+        for tool in connector_tools_for(store, &row.id)? {
+            lines.push(format!(
+                "{} {} risk={} scopes={} - {}",
+                row.id,
+                tool.name,
+                tool.risk_class.as_str(),
+                tool.required_scopes.labels().join(","),
+                tool.description
+            ));
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+fn load_runtime_config(config_path: Option<&std::path::Path>) -> Result<TitanConfig> {
+    let (cfg, _, _) = load_runtime_config_with_path(config_path)?;
+    Ok(cfg)
+}
+
+fn load_runtime_config_with_path(
+    config_path: Option<&std::path::Path>,
+) -> Result<(TitanConfig, PathBuf, bool)> {
+    if let Some(path) = config_path {
+        if path.exists() {
+            let cfg = TitanConfig::load(path).map_err(|err| anyhow!("{err}"))?;
+            Ok((cfg, path.to_path_buf(), false))
+        } else {
+            let cfg = TitanConfig::default();
+            cfg.save(path).map_err(|err| anyhow!("{err}"))?;
+            Ok((cfg, path.to_path_buf(), true))
+        }
+    } else {
+        TitanConfig::load_or_create().map_err(|err| anyhow!("{err}"))
+    }
+}
+
+fn model_provider_name(provider: &titan_common::ModelProvider) -> &'static str {
+    match provider {
+        titan_common::ModelProvider::OpenAi => "openai",
+        titan_common::ModelProvider::Anthropic => "anthropic",
+        titan_common::ModelProvider::Ollama => "ollama",
+        titan_common::ModelProvider::Custom => "custom",
+    }
+}
+
+pub fn requires_config_approval(mode: AutonomyMode) -> bool {
+    !matches!(mode, AutonomyMode::Autonomous)
+}
+
+fn apply_allowlist_change(
+    action: AllowlistAction,
+    value: &str,
+    config_path: Option<&std::path::Path>,
+) -> Result<()> {
+    let (mut cfg, path, _) = load_runtime_config_with_path(config_path)?;
+    let mut allowlist: std::collections::BTreeSet<String> =
+        cfg.chat.allowlist.into_iter().collect();
+    match action {
+        AllowlistAction::Add => {
+            allowlist.insert(value.trim().to_string());
+        }
+        AllowlistAction::Remove => {
+            allowlist.remove(value.trim());
+        }
+    }
+    cfg.chat.allowlist = allowlist.into_iter().collect();
+    cfg.save(&path).map_err(|err| anyhow!("{err}"))?;
+    Ok(())
+}
+
+fn apply_activation_mode(
+    mode: ActivationMode,
+    config_path: Option<&std::path::Path>,
+) -> Result<()> {
+    let (mut cfg, path, _) = load_runtime_config_with_path(config_path)?;
+    cfg.chat.activation_mode = mode;
+    cfg.save(&path).map_err(|err| anyhow!("{err}"))?;
+    Ok(())
+}
+
+fn activation_mode_name(mode: &ActivationMode) -> &'static str {
+    match mode {
+        ActivationMode::Always => "always",
+        ActivationMode::Mention => "mention",
+    }
+}
+
+fn is_message_allowed(
+    inbound: &InboundEvent,
+    session: &titan_memory::SessionRecord,
+    config_path: Option<&std::path::Path>,
+) -> Result<bool> {
+    // Local/system-triggered events (CLI, scheduler jobs, verified connector webhooks) bypass
+    // channel activation and allowlist checks.
+    if matches!(inbound.channel, Channel::Cli) {
+        return Ok(true);
+    }
+    let cfg = load_runtime_config(config_path)?;
+    if !cfg.chat.allowlist.is_empty()
+        && !cfg.chat.allowlist.iter().any(|id| id == &inbound.actor_id)
+    {
+        return Ok(false);
+    }
+    let session_mode = session.activation_mode.to_ascii_lowercase();
+    let effective_mode = if session_mode == "mention" {
+        ActivationMode::Mention
+    } else {
+        cfg.chat.activation_mode
+    };
+    if matches!(effective_mode, ActivationMode::Mention) {
+        let lowered = inbound.text.to_ascii_lowercase();
+        if !lowered.contains("titan") && !inbound.text.contains('/') {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn parse_slug_and_version(input: &str) -> (String, Option<String>) {
+    match input.split_once('@') {
+        Some((slug, version)) => (slug.to_string(), Some(version.to_string())),
+        None => (input.to_string(), None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write_test_config(workspace: &std::path::Path) -> std::path::PathBuf {
+        let mut cfg = TitanConfig {
+            workspace_dir: workspace.to_path_buf(),
+            ..TitanConfig::default()
+        };
+        cfg.chat.activation_mode = ActivationMode::Always;
+        let tmp = workspace.join("test-config.toml");
+        cfg.save(&tmp).expect("save config");
+        tmp
+    }
+
+    fn seed_local_registry_skill(workspace: &std::path::Path, slug: &str, version: &str) {
+        let bundle = workspace
+            .join(".titan/registry/local/bundles")
+            .join(format!("{slug}-{version}"));
+        std::fs::create_dir_all(&bundle).expect("bundle dir");
+        std::fs::write(bundle.join("SKILL.md"), "# skill\n").expect("skill docs");
+        std::fs::write(
+            bundle.join("skill.toml"),
+            format!(
+                r#"name = "{slug}"
+slug = "{slug}"
+version = "{version}"
+description = "demo"
+entrypoint_type = "prompt"
+entrypoint = "tool:list_dir ."
+
+[permissions]
+scopes = ["READ"]
+allowed_paths = ["."]
+allowed_hosts = []
+"#
+            ),
+        )
+        .expect("skill manifest");
+        let hash = titan_skills::compute_bundle_hash(&bundle).expect("bundle hash");
+        let index = format!(
+            r#"{{
+  "skills": [
+    {{
+      "slug": "{slug}",
+      "name": "{slug}",
+      "latest": "{version}",
+      "versions": [
+        {{
+          "version": "{version}",
+          "download_url": "bundles/{slug}-{version}",
+          "sha256": "{hash}"
+        }}
+      ]
+    }}
+  ]
+}}"#
+        );
+        let index_path = workspace.join(".titan/registry/local/index.json");
+        std::fs::create_dir_all(index_path.parent().expect("parent")).expect("registry root");
+        std::fs::write(index_path, index).expect("index");
+    }
+
+    #[test]
+    fn injected_read_event_executes_and_persists_trace_and_memory() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "hello").expect("seed readme");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+        let outcome = runtime
+            .process_event(InboundEvent::new(Channel::Discord, "u1", "scan workspace"))
+            .expect("process event");
+        assert!(outcome.pending_approval_id.is_none());
+
+        let store = MemoryStore::open(&db_path).expect("open store");
+        let traces = store.get_traces(&outcome.goal_id).expect("traces");
+        assert!(
+            traces
+                .iter()
+                .any(|trace| trace.event_type == "execution_started")
+        );
+        assert_eq!(
+            store
+                .count_plans_for_goal(&outcome.goal_id)
+                .expect("plan rows"),
+            1
+        );
+        assert!(
+            store
+                .count_steps_for_goal(&outcome.goal_id)
+                .expect("step rows")
+                >= 1
+        );
+        let memories = store.list_episodic_memory(10).expect("memories");
+        assert!(
+            memories
+                .iter()
+                .any(|entry| entry.goal_id == outcome.goal_id)
+        );
+        println!(
+            "run_proof goal_id={} traces={} plans={} steps={} episodic={}",
+            outcome.goal_id,
+            traces.len(),
+            store
+                .count_plans_for_goal(&outcome.goal_id)
+                .expect("plan rows"),
+            store
+                .count_steps_for_goal(&outcome.goal_id)
+                .expect("step rows"),
+            memories.len()
+        );
+    }
+
+    struct UnreachableModel;
+
+    impl ReflectionGenerator for UnreachableModel {
+        fn generate(
+            &self,
+            _run: &titan_core::TaskRunResult,
+        ) -> std::result::Result<titan_core::StructuredReflection, String> {
+            Err("model offline".to_string())
+        }
+    }
+
+    #[test]
+    fn run_reflection_falls_back_and_feeds_procedural_strategies() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path)
+        .with_reflection_generator(Arc::new(UnreachableModel));
+        let outcome = runtime
+            .process_event(InboundEvent::new(Channel::Discord, "u1", "scan workspace"))
+            .expect("process event");
+
+        let store = MemoryStore::open(&db_path).expect("open store");
+        let traces = store.get_traces(&outcome.goal_id).expect("traces");
+        assert!(traces.iter().any(|trace| {
+            trace.event_type == "reflection_structured"
+                && trace.detail.starts_with("source=deterministic")
+        }));
+        let memory = store
+            .list_episodic_memory(10)
+            .expect("memories")
+            .into_iter()
+            .find(|entry| entry.goal_id == outcome.goal_id)
+            .expect("episodic memory");
+        let reflection = memory.reflection.expect("structured reflection");
+        assert!(!reflection.worked.is_empty());
+        let change = reflection
+            .strategy_changes
+            .first()
+            .expect("strategy change");
+        let candidate = change
+            .strip_prefix("reuse ")
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("candidate id");
+        let strategies = store
+            .list_procedural_strategies(candidate, 10)
+            .expect("strategies");
+        assert_eq!(strategies.len(), 1);
+        assert_eq!(strategies[0].source, "reflection:deterministic");
+    }
+
+    #[test]
+    fn event_batch_reports_duplicates_per_item() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            workspace.join("titan.db"),
+        )
+        .with_config_path(config_path);
+
+        let event = |key: &str| {
+            InboundEvent::new(Channel::Webchat, "log-shipper", "scan workspace")
+                .with_dedupe_key(Some(format!(" {key} ")))
+        };
+        let outcomes = runtime
+            .process_event_batch(vec![event("a"), event("b"), event("a")])
+            .expect("batch");
+        assert_eq!(outcomes.len(), 3);
+        let BatchItemOutcome::Processed(first) = &outcomes[0] else {
+            panic!("first item should run: {:?}", outcomes[0]);
+        };
+        assert!(matches!(outcomes[1], BatchItemOutcome::Processed(_)));
+        match &outcomes[2] {
+            BatchItemOutcome::Duplicate { goal_id, .. } => assert_eq!(goal_id, &first.goal_id),
+            other => panic!("repeated key should be a duplicate: {other:?}"),
+        }
+
+        let again = runtime
+            .process_event_batch(vec![event("b")])
+            .expect("second batch");
+        assert!(matches!(again[0], BatchItemOutcome::Duplicate { .. }));
+    }
+
+    #[test]
+    fn connector_event_runs_as_goal_and_records_the_correlation() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let connector_id = "6f1c2a4e-0000-4000-8000-000000000001".to_string();
+        MemoryStore::open(&db_path)
+            .expect("store")
+            .add_connector(&connector_id, "github", "GitHub", "{}")
+            .expect("connector");
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Collaborative, workspace, db_path.clone())
+                .with_config_path(config_path);
+        let event = ConnectorEventGoal {
+            connector_id: connector_id.clone(),
+            event: "issues.opened".to_string(),
+            goal_text: "scan workspace".to_string(),
+            dedupe_key: Some(format!("connector:{connector_id}:d-1")),
+        };
+
+        let BatchItemOutcome::Processed(first) =
+            runtime.process_connector_event(&event).expect("first")
+        else {
+            panic!("first delivery should run");
+        };
+        let usage = MemoryStore::open(&db_path)
+            .expect("store")
+            .connector_usage_for_goal(&first.goal_id)
+            .expect("usage");
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].connector_id, connector_id);
+        assert_eq!(usage[0].tool_name, "webhook:issues.opened");
+
+        assert!(matches!(
+            runtime.process_connector_event(&event).expect("redelivery"),
+            BatchItemOutcome::Duplicate { .. }
+        ));
+    }
+
+    #[test]
+    fn safe_mode_forces_supervision_until_an_operator_resumes() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Autonomous, workspace, db_path.clone())
+                .with_config_path(config_path);
+        let chat = |text: &str| {
+            runtime
+                .process_chat_input(InboundEvent::new(Channel::Cli, "ops", text))
+                .expect("chat")
+                .response
+        };
+
+        assert!(chat("/safemode enter testing breaker").contains("safe_mode=active"));
+        assert!(chat("/status").contains("mode=supervised"));
+        assert!(chat("/status").contains("safe_mode=active"));
+        let supervised = runtime
+            .process_event(InboundEvent::new(
+                Channel::Cli,
+                "ops",
+                "update README with install steps",
+            ))
+            .expect("supervised write");
+        assert!(supervised.pending_approval_id.is_some());
+        let connector_err = runtime
+            .process_connector_event(&ConnectorEventGoal {
+                connector_id: "c1".to_string(),
+                event: "issues.opened".to_string(),
+                goal_text: "scan workspace".to_string(),
+                dedupe_key: None,
+            })
+            .expect_err("connector triggers paused");
+        assert!(connector_err.to_string().contains("safe_mode_active"));
+
+        assert!(chat("/safemode resume checked logs").contains("resumed_by=cli:ops"));
+        assert!(chat("/status").contains("mode=autonomous"));
+        let state = MemoryStore::open(&db_path)
+            .expect("store")
+            .safe_mode_state()
+            .expect("state");
+        assert!(!state.active);
+        assert_eq!(state.changed_by.as_deref(), Some("cli:ops"));
+    }
+
+    #[test]
+    fn approve_all_resolves_pending_approvals_of_one_capability() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Supervised, workspace.clone(), db_path.clone())
+                .with_config_path(config_path);
+        let store = MemoryStore::open(&db_path).expect("open store");
+        let reads: Vec<String> = (0..2)
+            .map(|_| {
+                store
+                    .create_approval_request("read_file", "read", "README.md", Some("u1"), 60_000)
+                    .expect("read approval")
+                    .id
+            })
+            .collect();
+        let write = store
+            .create_approval_request("write_file", "write", "notes.md::hi", Some("u1"), 60_000)
+            .expect("write approval");
+        let chat = |text: &str| {
+            runtime
+                .process_chat_input(InboundEvent::new(Channel::Webchat, "op", text))
+                .expect("chat")
+                .response
+        };
+
+        let approved = chat("/approve all read");
+        assert!(
+            approved.starts_with("approved=2 of 2 pending read approvals"),
+            "{approved}"
+        );
+        for id in &reads {
+            assert!(store.approval_has_tool_run(id).expect("tool run"), "{id}");
+        }
+        let pending: Vec<String> = store
+            .list_pending_approvals()
+            .expect("pending")
+            .into_iter()
+            .map(|approval| approval.id)
+            .collect();
+        assert_eq!(pending, vec![write.id.clone()]);
+        assert_eq!(chat("/approve all read"), "no pending read approvals");
+
+        let denied = chat("/deny all write");
+        assert!(
+            denied.starts_with("denied=1 of 1 pending write approvals"),
+            "{denied}"
+        );
+        assert!(!workspace.join("notes.md").exists());
+        assert_eq!(
+            chat("/approve all admin"),
+            "unknown capability 'admin': use read, write, exec, or net"
+        );
+
+        // A failing approval mid-batch is reported and the others are still decided.
+        let batch: Vec<String> = ["README.md", "missing.md", "README.md"]
+            .into_iter()
+            .map(|input| {
+                store
+                    .create_approval_request("read_file", "read", input, Some("u1"), 60_000)
+                    .expect("read approval")
+                    .id
+            })
+            .collect();
+        let mixed = chat("/approve all read");
+        let lines: Vec<&str> = mixed.lines().collect();
+        assert_eq!(
+            lines[0], "approved=2 of 3 pending read approvals",
+            "{mixed}"
+        );
+        assert_eq!(lines.len(), 1 + batch.len(), "{mixed}");
+        for (id, ok) in batch.iter().zip([true, false, true]) {
+            let status = format!(
+                "{id} approval_status={}",
+                if ok { "approved" } else { "error: " }
+            );
+            assert!(
+                lines.iter().any(|line| line.starts_with(&status)),
+                "{status} in {mixed}"
+            );
+        }
+        assert!(store.list_pending_approvals().expect("pending").is_empty());
+    }
+
+    #[test]
+    fn plan_command_previews_approval_gates_without_running() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+
+        let preview = runtime
+            .process_chat_input(InboundEvent::new(
+                Channel::Webchat,
+                "u1",
+                "/plan update README with install steps",
+            ))
+            .expect("plan")
+            .response;
+        assert!(
+            preview.starts_with("goal=") && preview.contains("intent=update_readme"),
+            "{preview}"
+        );
+        assert!(
+            preview
+                .lines()
+                .any(|line| line.contains("write_file [write]")
+                    && line.ends_with(" - needs approval")),
+            "{preview}"
+        );
+        assert!(
+            preview
+                .lines()
+                .any(|line| line.contains("[read]") && !line.ends_with("needs approval")),
+            "{preview}"
+        );
+        assert_eq!(
+            std::fs::read_to_string(workspace.join("README.md")).expect("readme"),
+            "seed"
+        );
+        let store = MemoryStore::open(&db_path).expect("open store");
+        let goals = store.list_goals(10).expect("goals");
+        assert_eq!(goals.len(), 1);
+        assert_eq!(goals[0].status, "cancelled");
+        assert!(preview.starts_with(&format!("goal={} ", goals[0].id)));
+        let summary = store
+            .goal_run_summary(&goals[0].id)
+            .expect("summary")
+            .expect("persisted plan");
+        assert!(summary.selected_candidate_id.is_some());
+        assert!(
+            summary
+                .steps
+                .iter()
+                .any(|step| step.tool_name == "write_file"
+                    && step.status == "planned_needs_approval")
+        );
+        assert!(
+            summary
+                .steps
+                .iter()
+                .all(|step| step.status.starts_with("planned"))
+        );
+        assert!(
+            store
+                .list_pending_approvals()
+                .expect("approvals")
+                .is_empty()
+        );
+
+        let processed = runtime
+            .process_event(
+                InboundEvent::new(Channel::Webchat, "u1", "update README with install steps")
+                    .with_plan_only(),
+            )
+            .expect("plan only");
+        let plan = processed.plan.expect("preview");
+        assert_eq!(processed.goal_status, GoalStatus::Cancelled);
+        assert!(!plan.candidates.is_empty());
+        assert!(!plan.gated_step_ids().is_empty());
+        assert_eq!(
+            std::fs::read_to_string(workspace.join("README.md")).expect("readme"),
+            "seed"
+        );
+
+        let usage = runtime
+            .process_chat_input(InboundEvent::new(Channel::Webchat, "u1", "/plan"))
+            .expect("usage")
+            .response;
+        assert_eq!(usage, "usage: /plan <request>");
+    }
+
+    #[test]
+    fn plan_only_events_are_not_charged_and_still_meet_budgets_and_allowlists() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path.clone());
+        let plan = |actor: &str| {
+            runtime
+                .process_event(
+                    InboundEvent::new(Channel::Webchat, actor, "update README with install steps")
+                        .with_plan_only(),
+                )
+                .expect("plan only")
+        };
+
+        let previewed = plan("u1");
+        assert!(previewed.plan.is_some());
+        let store = MemoryStore::open(&db_path).expect("open store");
+        assert_eq!(
+            store
+                .session_usage(&previewed.session_id)
+                .expect("usage")
+                .tokens,
+            0,
+            "a preview is not charged"
+        );
+
+        let mut cfg = TitanConfig::load(&config_path).expect("load config");
+        cfg.usage.session_token_budget = Some(0);
+        cfg.save(&config_path).expect("save config");
+        let refused = plan("u1");
+        assert_eq!(refused.goal_id, BUDGET_BLOCKED_GOAL_ID);
+        assert!(refused.plan.is_none());
+
+        cfg.usage.session_token_budget = None;
+        cfg.chat.allowlist = vec!["owner".to_string()];
+        cfg.save(&config_path).expect("save config");
+        let ignored = plan("u1");
+        assert_eq!(ignored.goal_id, POLICY_BLOCKED_GOAL_ID);
+        assert!(ignored.plan.is_none());
+        assert_eq!(store.list_goals(10).expect("goals").len(), 1);
+    }
+
+    #[test]
+    fn collaborative_write_requires_approval_then_executes_after_approve() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+        let outcome = runtime
+            .process_event(InboundEvent::new(
+                Channel::Discord,
+                "u1",
+                "update README with install steps",
+            ))
+            .expect("process event");
+        let approval_id = outcome.pending_approval_id.expect("approval id");
+
+        assert!(
+            !std::fs::read_to_string(workspace.join("README.md"))
+                .expect("read readme")
+                .contains("Install Steps (Generated)")
+        );
+        let status = runtime
+            .resolve_approval(&approval_id, true, "test", Some("approved in test"))
+            .expect("resolve approval");
+        assert_eq!(status, "approved");
+        assert!(
+            std::fs::read_to_string(workspace.join("README.md"))
+                .expect("read readme")
+                .contains("Install Steps (Generated)")
+        );
+
+        let store = MemoryStore::open(&db_path).expect("open store");
+        let traces = store.get_traces(&outcome.goal_id).expect("traces");
+        assert!(
+            traces
+                .iter()
+                .any(|trace| trace.event_type == "approval_queued")
+        );
+        assert!(
+            traces
+                .iter()
+                .any(|trace| trace.event_type == "approval_executed")
+        );
+        let memories = store.list_episodic_memory(10).expect("memories");
+        assert!(
+            memories
+                .iter()
+                .any(|entry| entry.goal_id == outcome.goal_id)
+        );
+        println!(
+            "approval_proof goal_id={} approval_id={} traces={} episodic={} readme_updated={}",
+            outcome.goal_id,
+            approval_id,
+            traces.len(),
+            memories.len(),
+            std::fs::read_to_string(workspace.join("README.md"))
+                .expect("read readme")
+                .contains("Install Steps (Generated)")
+        );
+    }
+
+    #[test]
+    fn slash_status_reports_expected_fields() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+        let out = runtime
+            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "/status"))
+            .expect("status");
+        assert!(out.response.contains("mode="));
+        assert!(out.response.contains("session_id="));
+        assert!(out.response.contains("pending_approvals="));
+    }
+
+    #[test]
+    fn slash_context_detail_pages_with_token_estimates() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Autonomous, workspace.clone(), db_path.clone())
+                .with_config_path(config_path);
+        let store = MemoryStore::open(&db_path).expect("store");
+        let session = store
+            .get_or_create_active_session("webchat", "u1")
+            .expect("session");
+        for _ in 0..30 {
+            store
+                .add_session_message(&session.id, "user", "12345678", false)
+                .expect("message");
+        }
+        let chat = |text: &str| {
+            runtime
+                .process_chat_input(InboundEvent::new(Channel::Webchat, "u1", text))
+                .expect("chat")
+                .response
+        };
+
+        assert!(chat("/context").contains("items=30 compacted=0 tokens=60 context_tokens=60"));
+        let first = chat("/context detail");
+        let lines: Vec<&str> = first.lines().collect();
+        assert_eq!(lines.len(), 22, "{first}");
+        assert!(lines[1].ends_with("tokens=2 cumulative_tokens=2"));
+        let more = lines[21].strip_prefix("more=").expect("more hint");
+        let second = chat(more);
+        assert_eq!(second.lines().count(), 11, "{second}");
+        assert!(second.trim_end().ends_with("cumulative_tokens=60"));
+    }
+
+    #[test]
+    fn slash_env_persists_per_session_and_masks_secrets() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+        let chat = |text: &str| {
+            runtime
+                .process_chat_input(InboundEvent::new(Channel::Discord, "u1", text))
+                .expect("chat input")
+                .response
+        };
+        let run = runtime
+            .process_event(InboundEvent::new(Channel::Discord, "u1", "scan workspace"))
+            .expect("run");
+
+        assert_eq!(chat("/env set REPO=titan"), "env_set=REPO");
+        assert_eq!(chat("/env set API_TOKEN=abc123"), "env_set=API_TOKEN");
+        assert_eq!(chat("/env set 1BAD=x"), "invalid env key: 1BAD");
+        assert_eq!(chat("/env"), "API_TOKEN=<redacted>\nREPO=titan");
+        assert_eq!(chat("/env unset REPO"), "env_unset=REPO removed=true");
+
+        let store = MemoryStore::open(&db_path).expect("open store");
+        assert_eq!(store.session_env(&run.session_id).expect("env").len(), 1);
+        let traces = store.get_traces(&run.goal_id).expect("traces");
+        assert!(traces.iter().all(|trace| !trace.detail.contains("abc123")));
+    }
+
+    #[test]
+    fn slash_new_and_compact_and_stop_mutate_session_state() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+        let _ = runtime
+            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "scan workspace"))
+            .expect("run1");
+        let status1 = runtime
+            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "/status"))
+            .expect("status1");
+        let session_id_old = status1
+            .response
+            .split_whitespace()
+            .find(|part| part.starts_with("session_id="))
+            .and_then(|part| part.split_once('=').map(|(_, v)| v.to_string()))
+            .expect("session id");
+
+        let _ = runtime
+            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "/new"))
+            .expect("new");
+        let status2 = runtime
+            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "/status"))
+            .expect("status2");
+        let session_id_new = status2
+            .response
+            .split_whitespace()
+            .find(|part| part.starts_with("session_id="))
+            .and_then(|part| part.split_once('=').map(|(_, v)| v.to_string()))
+            .expect("session id");
+        assert_ne!(session_id_old, session_id_new);
+
+        let _ = runtime
+            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "scan workspace"))
+            .expect("run2");
+        let compact = runtime
+            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "/compact"))
+            .expect("compact");
+        assert!(compact.response.contains("session_compacted"));
+        let stop = runtime
+            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "/stop"))
+            .expect("stop");
+        assert!(stop.response.contains("session_stop_requested"));
+    }
+
+    #[test]
+    fn run_command_prompts_for_missing_template_params_then_plans() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let mut cfg = TitanConfig::load(&config_path).expect("load config");
+        cfg.goal_templates.push(GoalTemplateConfig {
+            name: "release-scan".to_string(),
+            params: vec!["version".to_string(), "notes".to_string()],
+            description: "scan workspace for release {version}: {notes}".to_string(),
+            plan: Some("cand_scan_read_2".to_string()),
+            token_budget: None,
+        });
+        cfg.save(&config_path).expect("save config");
+        let db_path = workspace.join("titan.db");
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Collaborative, workspace, db_path.clone())
+                .with_config_path(config_path);
+
+        let listed = runtime
+            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "/run"))
+            .expect("list");
+        assert_eq!(listed.response, "templates: release-scan(version,notes)");
+        let prompt = runtime
+            .process_chat_input(InboundEvent::new(
+                Channel::Discord,
+                "u1",
+                "/run release-scan 1.4.0",
+            ))
+            .expect("run");
+        assert!(
+            prompt
+                .response
+                .starts_with("release-scan needs notes (2 of 2)")
+        );
+
+        let ran = runtime
+            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "final cut"))
+            .expect("answer");
+        let goal_id = ran
+            .response
+            .strip_prefix("goal=")
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("goal id");
+        let store = MemoryStore::open(&db_path).expect("store");
+        let goal = store.get_goal(goal_id).expect("goal").expect("goal row");
+        assert_eq!(
+            goal.description,
+            "[discord] scan workspace for release 1.4.0: final cut"
+        );
+        let traces = store.get_traces(goal_id).expect("traces");
+        assert!(traces.iter().any(|t| t.event_type == "goal_template_applied"
+            && t.detail == "template=release-scan version=1.4.0 notes=final cut plan=cand_scan_read_2"));
+        assert!(
+            traces.iter().any(
+                |t| t.event_type == "plan_selected" && t.detail.starts_with("cand_scan_read_2")
+            )
+        );
+        let session = store
+            .get_or_create_active_session("discord", "u1")
+            .expect("session");
+        assert!(
+            store
+                .template_prompt(&session.id)
+                .expect("prompt")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn pending_approval_replies_carry_signed_link_and_discord_diff() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "# Titan\n").expect("seed readme");
+        let config_path = write_test_config(&workspace);
+        let mut cfg = TitanConfig::load(&config_path).expect("load config");
+        cfg.approvals.dashboard_url = Some("http://127.0.0.1:3000".to_string());
+        cfg.approvals.link_secret_env = Some("TITAN_TEST_APPROVAL_LINK_SECRET".to_string());
+        cfg.save(&config_path).expect("save config");
+        // SAFETY: no other test reads or writes this variable.
+        unsafe { std::env::set_var("TITAN_TEST_APPROVAL_LINK_SECRET", "s3cret") };
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            workspace.join("titan.db"),
+        )
+        .with_config_path(config_path);
+
+        let reply = runtime
+            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "update readme"))
+            .expect("run");
+        let approval_id = reply
+            .response
+            .split_whitespace()
+            .find_map(|word| word.strip_prefix("approval_pending="))
+            .expect("approval pending");
+        let link = reply
+            .response
+            .split_whitespace()
+            .find_map(|word| word.strip_prefix("approval_url="))
+            .expect("approval url");
+        assert!(link.starts_with(&format!(
+            "http://127.0.0.1:3000/approvals/{approval_id}?exp="
+        )));
+        assert!(
+            reply
+                .response
+                .contains("\n```diff\n@@ -1,1 +1,4 @@\n-# Titan\n")
+        );
+        assert!(reply.response.contains("+## Install Steps (Generated)"));
+
+        let webchat = runtime
+            .process_chat_input(InboundEvent::new(Channel::Webchat, "u2", "update readme"))
+            .expect("run");
+        assert!(
+            webchat
+                .response
+                .contains(" approval_url=http://127.0.0.1:3000/")
+        );
+        assert!(!webchat.response.contains("```diff"));
+    }
+
+    struct CountingPlanModel {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl ModelClient for CountingPlanModel {
+        fn complete(&self, prompt: &str) -> std::result::Result<String, String> {
+            assert!(prompt.contains("request: note the deploy steps"));
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(r#"{"candidates":[{"rationale":"ask then write","steps":[
+                {"id":"ask","tool":"ask_user","input":"Which deploy steps?"},
+                {"id":"write","tool":"write_file","input":"NOTES.md::{{steps.ask.output}}"}]}]}"#
+                .to_string())
+        }
+    }
+
+    struct ListingPlanModel {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl ModelClient for ListingPlanModel {
+        fn complete(&self, _prompt: &str) -> std::result::Result<String, String> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(r#"{"candidates":[{"rationale":"look around","steps":[
+                {"id":"ls","tool":"list_dir","input":"."}]}]}"#
+                .to_string())
+        }
+    }
+
+    struct PromptLogModel {
+        prompts: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl ModelClient for PromptLogModel {
+        fn complete(&self, prompt: &str) -> std::result::Result<String, String> {
+            self.prompts
+                .lock()
+                .expect("prompts")
+                .push(prompt.to_string());
+            Ok(r#"{"candidates":[{"rationale":"look around","steps":[
+                {"id":"ls","tool":"list_dir","input":"."}]}]}"#
+                .to_string())
+        }
+    }
+
+    #[test]
+    fn opted_in_peer_facts_reach_the_planner_until_forgotten() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let model = Arc::new(PromptLogModel {
+            prompts: std::sync::Mutex::new(Vec::new()),
+        });
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Autonomous, workspace.clone(), db_path.clone())
+                .with_config_path(config_path)
+                .with_planner_model(model.clone());
+        let chat = |actor: &str, text: &str| {
+            runtime
+                .process_chat_input(InboundEvent::new(Channel::Webchat, actor, text))
+                .expect("chat")
+                .response
+        };
+        let last_prompt = || {
+            model
+                .prompts
+                .lock()
+                .expect("prompts")
+                .last()
+                .cloned()
+                .expect("prompt")
+        };
+
+        // Without consent nothing stated in a request is kept.
+        chat("u1", "my timezone is UTC+9, look around the workspace");
+        assert!(!last_prompt().contains("known about the requester"));
+        assert!(chat("u1", "/remember").starts_with("peer_memory=off"));
+
+        assert!(chat("u1", "/remember I prefer terse replies").contains("remembered=preference"));
+        chat("u1", "my timezone is UTC+2, list files in repo acme/titan");
+        let prompt = last_prompt();
+        assert!(prompt.contains("known about the requester:"), "{prompt}");
+        assert!(prompt.contains("- preference: terse replies"));
+        assert!(prompt.contains("- repos: acme/titan"));
+        assert!(prompt.contains("- timezone: UTC+2"));
+        assert!(chat("u1", "/remember").contains("timezone: UTC+2"));
+
+        chat("u2", "look around the workspace please");
+        assert!(!last_prompt().contains("known about the requester"));
+
+        assert_eq!(chat("u1", "/forget me"), "peer_memory=off forgotten=3");
+        chat(
+            "u1",
+            "my timezone is UTC+3, look around the workspace again",
+        );
+        assert!(!last_prompt().contains("known about the requester"));
+        let store = MemoryStore::open(&db_path).expect("store");
+        assert!(store.peer_facts("webchat", "u1").expect("facts").is_empty());
+    }
+
+    #[test]
+    fn identical_planning_prompts_are_answered_from_the_response_cache() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let mut cfg = TitanConfig::load(&config_path).expect("load config");
+        cfg.model.cache_ttl_secs = Some(300);
+        cfg.save(&config_path).expect("save config");
+        let db_path = workspace.join("titan.db");
+        let model = Arc::new(ListingPlanModel {
+            calls: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Autonomous, workspace.clone(), db_path.clone())
+                .with_config_path(config_path)
+                .with_planner_model(model.clone());
+        let chat = |text: &str| {
+            runtime
+                .process_chat_input(InboundEvent::new(Channel::Webchat, "u1", text))
+                .expect("chat")
+                .response
+        };
+
+        assert!(chat("look around the workspace").contains("status=completed"));
+        assert!(chat("look around the workspace").contains("status=completed"));
+        assert_eq!(model.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let store = MemoryStore::open(&db_path).expect("store");
+        let session = store
+            .get_or_create_active_session("webchat", "u1")
+            .expect("session");
+        let used = store.session_usage(&session.id).expect("usage");
+        assert_eq!((used.model_calls, used.model_cache_hits), (2, 1));
+        assert!(chat("/usage").contains("model_cache_hits=1/2"));
+        let stats = store.model_cache_stats(0).expect("stats");
+        assert_eq!((stats.entries, stats.hits), (1, 1));
+    }
+
+    struct FlakyProviderModel {
+        up: std::sync::atomic::AtomicBool,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl ModelClient for FlakyProviderModel {
+        fn complete(&self, prompt: &str) -> std::result::Result<String, String> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if self.up.load(std::sync::atomic::Ordering::SeqCst) {
+                ListingPlanModel {
+                    calls: std::sync::atomic::AtomicUsize::new(0),
+                }
+                .complete(prompt)
+            } else {
+                Err(format!("{MODEL_UNREACHABLE}: connection refused"))
+            }
+        }
+    }
+
+    #[test]
+    fn provider_outage_degrades_to_rule_planning_and_holds_model_goals() {
+        use std::sync::atomic::Ordering;
+
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let model = Arc::new(FlakyProviderModel {
+            up: std::sync::atomic::AtomicBool::new(false),
+            calls: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Autonomous, workspace.clone(), db_path.clone())
+                .with_config_path(config_path)
+                .with_planner_model(model.clone());
+        let chat = |text: &str| {
+            runtime
+                .process_event(InboundEvent::new(Channel::Webchat, "u1", text))
+                .expect("event")
+        };
+        let store = MemoryStore::open(&db_path).expect("store");
+
+        for _ in 0..3 {
+            assert_eq!(chat("scan workspace").goal_status, GoalStatus::Completed);
+        }
+        assert_eq!(model.calls.load(Ordering::SeqCst), 3);
+        assert!(store.model_provider_state().expect("state").degraded);
+
+        let ruled = chat("scan workspace");
+        assert_eq!(ruled.goal_status, GoalStatus::Completed);
+        assert_eq!(model.calls.load(Ordering::SeqCst), 3);
+        let traces = store.get_traces(&ruled.goal_id).expect("traces");
+        assert!(traces.iter().any(|t| t.event_type == "model_degraded"));
+
+        let held = chat("look around the workspace");
+        assert_eq!(held.goal_status, GoalStatus::WaitingForModel);
+        assert!(held.summary.contains("model provider is unreachable"));
+        assert_eq!(store.count_goals_waiting_for_model().expect("count"), 1);
+        assert!(runtime.start_queued_goals().expect("start").is_empty());
+
+        // Backdate the last failure so the next queue pass probes the provider.
+        store
+            .record_model_outage("model provider unreachable: backdated", 3, 0)
+            .expect("backdate");
+        model.up.store(true, Ordering::SeqCst);
+        let started = runtime.start_queued_goals().expect("start");
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].goal_id, held.goal_id);
+        assert_eq!(started[0].goal_status, GoalStatus::Completed);
+        let state = store.model_provider_state().expect("state");
+        assert!(!state.degraded);
+        assert_eq!(state.consecutive_failures, 0);
+        assert_eq!(store.count_goals_waiting_for_model().expect("count"), 0);
+    }
+
+    #[test]
+    fn experiment_arms_are_recorded_and_reported_with_feedback() {
+        use std::sync::atomic::Ordering;
+
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let set_variant_percent = |percent: u8| {
+            let mut cfg = TitanConfig::load(&config_path).expect("load config");
+            cfg.experiment.name = Some("rules-vs-model".to_string());
+            cfg.experiment.variant_percent = percent;
+            cfg.experiment.planner = Some(ExperimentPlanner::Rules);
+            cfg.experiment.candidate_count = Some(2);
+            cfg.save(&config_path).expect("save config");
+        };
+        let db_path = workspace.join("titan.db");
+        let model = Arc::new(ListingPlanModel {
+            calls: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Autonomous, workspace.clone(), db_path.clone())
+                .with_config_path(config_path.clone())
+                .with_planner_model(model.clone());
+        let run = |text: &str| {
+            runtime
+                .process_event(InboundEvent::new(Channel::Webchat, "u1", text))
+                .expect("event")
+        };
+        let chat = |text: &str| {
+            runtime
+                .process_chat_input(InboundEvent::new(Channel::Webchat, "u1", text))
+                .expect("chat")
+        };
+        let store = MemoryStore::open(&db_path).expect("store");
+
+        set_variant_percent(100);
+        for _ in 0..2 {
+            let goal_id = run("scan workspace").goal_id;
+            let traces = store.get_traces(&goal_id).expect("traces");
+            assert!(traces.iter().any(|t| t.event_type == "experiment_assigned"
+                && t.detail == "experiment=rules-vs-model variant=variant"));
+        }
+        assert_eq!(model.calls.load(Ordering::SeqCst), 0);
+        assert!(
+            chat("/feedback bad missed the docs folder")
+                .response
+                .contains("rating=bad")
+        );
+
+        set_variant_percent(0);
+        let control = run("scan workspace").goal_id;
+        assert_eq!(model.calls.load(Ordering::SeqCst), 1);
+        assert!(
+            chat(&format!("/feedback good {control}"))
+                .response
+                .contains(&format!("goal={control}"))
+        );
+        assert!(chat("/feedback maybe").response.starts_with("usage:"));
+
+        let report = store.experiment_report("rules-vs-model").expect("report");
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].variant, "control");
+        assert_eq!((report[0].goals, report[0].completed), (1, 1));
+        assert_eq!((report[0].feedback_up, report[0].feedback_down), (1, 0));
+        assert_eq!(report[1].variant, "variant");
+        assert_eq!((report[1].goals, report[1].completed), (2, 2));
+        assert_eq!((report[1].feedback_up, report[1].feedback_down), (0, 1));
+        assert_eq!(report[1].success_rate(), 1.0);
+    }
+
+    #[test]
+    fn model_planned_goal_resumes_with_its_stored_plan() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let model = Arc::new(CountingPlanModel {
+            calls: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Autonomous, workspace.clone(), db_path.clone())
+                .with_config_path(config_path)
+                .with_planner_model(model.clone());
+
+        let asked = runtime
+            .process_chat_input(InboundEvent::new(
+                Channel::Webchat,
+                "u1",
+                "note the deploy steps",
+            ))
+            .expect("ask");
+        assert!(
+            asked
+                .response
+                .contains("Awaiting input: Which deploy steps?")
+        );
+        let goal_id = asked
+            .response
+            .strip_prefix("goal=")
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("goal id")
+            .to_string();
+        let ran = runtime
+            .process_chat_input(InboundEvent::new(Channel::Webchat, "u1", "cargo deploy"))
+            .expect("answer");
+
+        assert!(
+            ran.response
+                .starts_with(&format!("goal={goal_id} status=completed"))
+        );
+        assert_eq!(
+            std::fs::read_to_string(workspace.join("NOTES.md")).expect("notes"),
+            "cargo deploy"
+        );
+        assert_eq!(model.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        let store = MemoryStore::open(&db_path).expect("store");
+        assert!(store.model_plan(&goal_id).expect("model plan").is_some());
+        assert!(
+            store
+                .get_traces(&goal_id)
+                .expect("traces")
+                .iter()
+                .any(|trace| trace.event_type == "plan_selected"
+                    && trace.detail.starts_with("cand_model_1"))
+        );
+    }
+
+    #[test]
+    fn ask_user_step_waits_for_reply_then_resumes_same_goal() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "# Titan\n").expect("seed readme");
+        let config_path = write_test_config(&workspace);
+        let mut cfg = TitanConfig::load(&config_path).expect("load config");
+        cfg.goal_templates.push(GoalTemplateConfig {
+            name: "install-docs".to_string(),
+            params: Vec::new(),
+            description: "update readme install steps".to_string(),
+            plan: Some("cand_update_readme_ask".to_string()),
+            token_budget: None,
+        });
+        cfg.save(&config_path).expect("save config");
+        let db_path = workspace.join("titan.db");
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Autonomous, workspace.clone(), db_path.clone())
+                .with_config_path(config_path.clone());
+
+        let asked = runtime
+            .process_chat_input(InboundEvent::new(
+                Channel::Webchat,
+                "u1",
+                "/run install-docs",
+            ))
+            .expect("ask");
+        assert!(asked.response.contains(
+            "status=pending summary=Awaiting input: Which install steps should the README list?"
+        ));
+        assert!(
+            runtime
+                .awaiting_reply(Channel::Webchat, "u1")
+                .expect("awaiting")
+        );
+        let goal_id = asked
+            .response
+            .strip_prefix("goal=")
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("goal id")
+            .to_string();
+
+        let ran = runtime
+            .process_chat_input(InboundEvent::new(
+                Channel::Webchat,
+                "u1",
+                "1. cargo install titan",
+            ))
+            .expect("answer");
+        assert!(
+            ran.response
+                .starts_with(&format!("goal={goal_id} status=completed"))
+        );
+        assert!(
+            std::fs::read_to_string(workspace.join("README.md"))
+                .expect("readme")
+                .contains("## Install Steps (Generated)\n1. cargo install titan")
+        );
+        let store = MemoryStore::open(&db_path).expect("store");
+        let events: Vec<String> = store
+            .get_traces(&goal_id)
+            .expect("traces")
+            .into_iter()
+            .map(|trace| trace.event_type)
+            .collect();
+        let asked_at = events.iter().position(|e| e == "input_requested");
+        let answered_at = events.iter().position(|e| e == "input_received");
+        assert!(asked_at.is_some() && asked_at < answered_at);
+        assert_eq!(events.iter().filter(|e| *e == "goal_submitted").count(), 1);
+
+        let mut cfg = TitanConfig::load(&config_path).expect("load config");
+        cfg.execution.ask_user_ttl_ms = Some(0);
+        cfg.save(&config_path).expect("save config");
+        let asked = runtime
+            .process_chat_input(InboundEvent::new(
+                Channel::Webchat,
+                "u1",
+                "/run install-docs",
+            ))
+            .expect("ask again");
+        assert!(asked.response.contains("status=pending"));
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let expired = runtime
+            .process_chat_input(InboundEvent::new(Channel::Webchat, "u1", "too late"))
+            .expect("expire");
+        assert!(expired.response.ends_with("status=failed"));
+        assert!(
+            !runtime
+                .awaiting_reply(Channel::Webchat, "u1")
+                .expect("awaiting")
+        );
+    }
+
+    #[test]
+    fn unclear_chat_request_waits_for_clarification_then_plans_same_goal() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Collaborative, workspace, db_path.clone())
+                .with_config_path(config_path);
+
+        let asked = runtime
+            .process_chat_input(InboundEvent::new(Channel::Webchat, "u1", "tidy things up"))
+            .expect("ask");
+        assert!(asked.response.contains("status=awaiting_clarification"));
+        assert!(
+            runtime
+                .awaiting_reply(Channel::Webchat, "u1")
+                .expect("awaiting")
+        );
+        let goal_id = asked
+            .response
+            .strip_prefix("goal=")
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("goal id")
+            .to_string();
+
+        let ran = runtime
+            .process_chat_input(InboundEvent::new(
+                Channel::Webchat,
+                "u1",
+                "scan workspace please",
+            ))
+            .expect("answer");
+        assert!(
+            ran.response
+                .starts_with(&format!("goal={goal_id} status=completed"))
+        );
+        assert!(
+            !runtime
+                .awaiting_reply(Channel::Webchat, "u1")
+                .expect("awaiting")
+        );
+        let store = MemoryStore::open(&db_path).expect("store");
+        let goal = store.get_goal(&goal_id).expect("goal").expect("goal row");
+        assert_eq!(
+            goal.description,
+            "[webchat] tidy things up; scan workspace please"
+        );
+        let events: Vec<String> = store
+            .get_traces(&goal_id)
+            .expect("traces")
+            .into_iter()
+            .map(|trace| trace.event_type)
+            .collect();
+        let asked_at = events.iter().position(|e| e == "clarification_requested");
+        let answered_at = events.iter().position(|e| e == "clarification_received");
+        assert!(asked_at.is_some() && asked_at < answered_at);
+        assert_eq!(events.iter().filter(|e| *e == "goal_submitted").count(), 1);
+
+        let asked = runtime
+            .process_chat_input(InboundEvent::new(Channel::Webchat, "u1", "hmm"))
+            .expect("ask again");
+        assert!(asked.response.contains("status=awaiting_clarification"));
+        let cancelled = runtime
+            .process_chat_input(InboundEvent::new(Channel::Webchat, "u1", "cancel"))
+            .expect("cancel");
+        assert!(cancelled.response.ends_with("status=cancelled"));
+    }
+
+    #[test]
+    fn session_step_cache_reuses_reads_across_goals_within_ttl() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
+        let config_path = write_test_config(&workspace);
+        let mut cfg = TitanConfig::load(&config_path).expect("load config");
+        cfg.execution.step_cache_ttl_ms = 60_000;
+        cfg.save(&config_path).expect("save config");
+        let db_path = workspace.join("titan.db");
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Collaborative, workspace, db_path.clone())
+                .with_config_path(config_path);
+
+        let first = runtime
+            .process_event(InboundEvent::new(Channel::Discord, "u1", "scan workspace"))
+            .expect("first");
+        let second = runtime
+            .process_event(InboundEvent::new(Channel::Discord, "u1", "scan workspace"))
+            .expect("second");
+        assert_eq!(second.goal_status, GoalStatus::Completed);
+        let store = MemoryStore::open(&db_path).expect("store");
+        let hits = |goal_id: &str| {
+            store
+                .get_traces(goal_id)
+                .expect("traces")
+                .into_iter()
+                .filter(|trace| trace.event_type == "step_cache_hit")
+                .count()
+        };
+        assert_eq!(hits(&first.goal_id), 0);
+        assert!(hits(&second.goal_id) > 0);
+
+        let other = runtime
+            .process_event(InboundEvent::new(Channel::Discord, "u2", "scan workspace"))
+            .expect("other session");
+        assert_eq!(hits(&other.goal_id), 0);
+    }
+
+    #[test]
+    fn configured_output_transform_trims_tool_output() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(
+            workspace.join("notes.md"),
+            "# Title\nversion 4.2\nlong body\n",
+        )
+        .expect("seed notes");
+        let config_path = write_test_config(&workspace);
+        let mut cfg = TitanConfig::load(&config_path).expect("load config");
+        cfg.execution
+            .output_transforms
+            .insert("read_file".to_string(), r"regex:version (\S+)".to_string());
+        cfg.save(&config_path).expect("save config");
+        let db_path = workspace.join("titan.db");
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Collaborative, workspace, db_path.clone())
+                .with_config_path(config_path);
+
+        let outcome = runtime
+            .process_event(InboundEvent::new(Channel::Discord, "u1", "read notes.md"))
+            .expect("run");
+
+        assert_eq!(outcome.goal_status, GoalStatus::Completed);
+        let store = MemoryStore::open(&db_path).expect("store");
+        let transformed = store
+            .get_traces(&outcome.goal_id)
+            .expect("traces")
+            .into_iter()
+            .find(|trace| trace.event_type == "output_transformed")
+            .expect("output_transformed trace");
+        assert!(transformed.detail.ends_with("-> 3 bytes"));
+    }
+
+    #[test]
+    fn webchat_slash_is_intercepted_not_routed_as_goal() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+        let out = runtime
+            .process_chat_input(InboundEvent::new(Channel::Webchat, "web-user", "/status"))
+            .expect("web status");
+        assert!(out.response.contains("session_id="));
+        let store = MemoryStore::open(&db_path).expect("store");
+        let session = store
+            .get_or_create_active_session("webchat", "web-user")
+            .expect("session");
+        let last_goal = store
+            .last_goal_for_session(&session.id)
+            .expect("last goal for session");
+        assert!(last_goal.is_none());
+    }
+
+    #[test]
+    fn connector_command_adds_after_approval_and_removes_directly_in_auto_mode() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+        let chat = |text: &str| {
+            runtime
+                .process_chat_input(InboundEvent::new(Channel::Discord, "lead", text))
+                .expect("chat")
+                .response
+        };
+        assert_eq!(chat("/connector list"), "no connectors configured");
+        assert!(
+            chat("/connector add github widgets bogus=1")
+                .contains("unknown github config field 'bogus'")
+        );
+
+        let requested = chat("/connector add github widgets owner=acme repo=widgets");
+        let approval_id = requested
+            .strip_prefix("approval_required=true approval_id=")
+            .expect("approval requested");
+        let store = MemoryStore::open(&db_path).expect("store");
+        assert!(store.list_connectors().expect("connectors").is_empty());
+
+        let approved = chat(&format!("/approve {approval_id}"));
+        let connector_id = approved
+            .strip_prefix("approval_status=approved connector_added=")
+            .expect("connector added");
+        let connector = store
+            .get_connector(connector_id)
+            .expect("get connector")
+            .expect("connector exists");
+        assert_eq!(connector.display_name, "widgets");
+        let config: serde_json::Value =
+            serde_json::from_str(&connector.config_json).expect("config json");
+        assert_eq!(config["owner"], "acme");
+        assert_eq!(config["repo"], "widgets");
+        assert!(chat("/connector list").starts_with(&format!("{connector_id} github widgets")));
+
+        let autonomous = TitanGatewayRuntime::new(AutonomyMode::Autonomous, workspace, db_path);
+        let auto_chat = |text: &str| {
+            autonomous
+                .process_chat_input(InboundEvent::new(Channel::Discord, "lead", text))
+                .expect("chat")
+                .response
+        };
+        assert_eq!(
+            auto_chat(&format!("/connector remove {connector_id}")),
+            "connector_removed=true"
+        );
+        assert!(store.list_connectors().expect("connectors").is_empty());
+        assert_eq!(
+            auto_chat(&format!("/connector remove {connector_id}")),
+            format!("connector not found: {connector_id}")
+        );
+    }
+
+    #[test]
+    fn telegram_updates_route_by_user_id_and_buttons_resolve_approvals() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+        let allowed = vec!["1001".to_string()];
+        let message = |user_id: i64, text: &str| {
+            serde_json::from_value::<titan_comms::telegram::TelegramUpdate>(serde_json::json!({
+                "update_id": 1,
+                "message": {
+                    "message_id": 7,
+                    "chat": {"id": -500},
+                    "from": {"id": user_id, "is_bot": false},
+                    "text": text,
+                },
+            }))
+            .expect("update")
+        };
+
+        assert!(
+            route_telegram_update(&runtime, &allowed, &message(2002, "/status"))
+                .expect("route")
+                .is_none()
+        );
+        let status = route_telegram_update(&runtime, &allowed, &message(1001, "/status@titan_bot"))
+            .expect("route")
+            .expect("reply");
+        assert_eq!(status.chat_id, "-500");
+        assert!(status.text.contains("session_id="));
+
+        let pending = route_telegram_update(&runtime, &allowed, &message(1001, "update readme"))
+            .expect("route")
+            .expect("reply");
+        let approval_id = pending.approval_id.clone().expect("approval buttons");
+        assert!(pending.reply_outbox_id.is_some());
+
+        let press = |user_id: i64| {
+            serde_json::from_value::<titan_comms::telegram::TelegramUpdate>(serde_json::json!({
+                "update_id": 2,
+                "callback_query": {
+                    "id": "cb-1",
+                    "from": {"id": user_id, "is_bot": false},
+                    "message": {"message_id": 8, "chat": {"id": -500}},
+                    "data": format!("approve:{approval_id}"),
+                },
+            }))
+            .expect("update")
+        };
+        assert!(
+            route_telegram_update(&runtime, &allowed, &press(2002))
+                .expect("route")
+                .is_none()
+        );
+        let approved = route_telegram_update(&runtime, &allowed, &press(1001))
+            .expect("route")
+            .expect("reply");
+        assert_eq!(approved.callback_query_id.as_deref(), Some("cb-1"));
+        assert!(approved.text.starts_with("approval_status=approved"));
+        let store = MemoryStore::open(&db_path).expect("store");
+        let approval = store
+            .get_approval_request(&approval_id)
+            .expect("approval")
+            .expect("exists");
+        assert_eq!(approval.status, "approved");
+        assert_eq!(approval.resolved_by.as_deref(), Some("1001"));
+    }
+
+    #[test]
+    fn operator_commands_are_limited_to_configured_operators() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let mut cfg = TitanConfig::load(&config_path).expect("load config");
+        cfg.chat.operators = vec!["lead".to_string()];
+        cfg.save(&config_path).expect("save config");
+        let db_path = workspace.join("titan.db");
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+        let approval = MemoryStore::open(&db_path)
+            .expect("store")
+            .create_approval_request("write_file", "write", "a.txt", Some("test"), 60_000)
+            .expect("approval");
+
+        let chat = |actor: &str, text: &str| {
+            runtime
+                .process_chat_input(InboundEvent::new(Channel::Discord, actor, text))
+                .expect("chat")
+                .response
+        };
+        assert_eq!(
+            chat("intern", &format!("/approve {}", approval.id)),
+            "permission_denied: /approve requires the operator role"
+        );
+        assert!(chat("intern", "/status").contains("session_id="));
+        assert!(
+            chat("intern", "/help")
+                .contains("/approve <approval_id> - approve a pending request [operator]")
+        );
+        assert_eq!(
+            chat("lead", &format!("/deny {}", approval.id)),
+            "approval_status=denied"
+        );
+        let cli = runtime
+            .process_chat_input(InboundEvent::new(Channel::Cli, "intern", "/mode auto"))
+            .expect("cli");
+        assert_eq!(cli.response, "mode_updated=autonomous");
+    }
+
+    #[test]
+    fn chat_skill_install_creates_approval_then_finalizes_on_approve() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
+        seed_local_registry_skill(&workspace, "list-docs", "1.0.0");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+
+        let out = runtime
+            .process_chat_input(InboundEvent::new(
+                Channel::Discord,
+                "u1",
+                "/titan skill install list-docs@1.0.0",
+            ))
+            .expect("skill install command");
+        assert!(out.response.contains("approval_required=true"));
+        let approval_id = out
+            .response
+            .split_whitespace()
+            .find(|part| part.starts_with("approval_id="))
+            .and_then(|part| part.split_once('=').map(|(_, value)| value.to_string()))
+            .expect("approval id");
+
+        let approval_status = runtime
+            .process_chat_input(InboundEvent::new(
+                Channel::Discord,
+                "u1",
+                format!("/approve {approval_id}"),
+            ))
+            .expect("approve");
+        assert!(
+            approval_status
+                .response
+                .contains("installed=list-docs@1.0.0")
+        );
+        let store = MemoryStore::open(&db_path).expect("store");
+        let installed = store
+            .get_installed_skill("list-docs")
+            .expect("installed lookup")
+            .expect("installed row");
+        assert_eq!(installed.version, "1.0.0");
+    }
+
+    #[test]
+    fn secure_mode_blocks_write_in_collab() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+        let outcome = runtime
+            .process_event(InboundEvent::new(
+                Channel::Discord,
+                "u1",
+                "update README with install steps",
+            ))
+            .expect("run");
+        assert!(outcome.pending_approval_id.is_some());
+    }
+
+    #[test]
+    fn yolo_allows_write_without_approval_and_traces() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let store = MemoryStore::open(&db_path).expect("store");
+        let _ = store.get_runtime_risk_state().expect("risk state");
+        store.enable_yolo("cli", 15, None).expect("enable yolo");
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+        let outcome = runtime
+            .process_event(InboundEvent::new(
+                Channel::Discord,
+                "u1",
+                "update README with install steps",
+            ))
+            .expect("run");
+        assert!(outcome.pending_approval_id.is_none());
+        let readme = std::fs::read_to_string(workspace.join("README.md")).expect("readme");
+        assert!(readme.contains("Install Steps (Generated)"));
+        let traces = MemoryStore::open(&db_path)
+            .expect("store")
+            .get_traces(&outcome.goal_id)
+            .expect("traces");
+        assert!(traces.iter().any(|trace| trace.risk_mode == "yolo"));
+    }
+
+    #[test]
+    fn write_steps_are_attributed_and_overlapping_goals_are_warned() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .arg("-C")
+                .arg(&workspace)
+                .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+                .args(args)
+                .status()
+                .expect("git");
+            assert!(status.success(), "git {args:?}");
+        };
+        git(&["init", "-q"]);
+        git(&["add", "README.md"]);
+        git(&["commit", "-q", "-m", "seed"]);
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let store = MemoryStore::open(&db_path).expect("store");
+        store.enable_yolo("cli", 15, None).expect("enable yolo");
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+        let run = || {
+            runtime
+                .process_event(InboundEvent::new(
+                    Channel::Discord,
+                    "u1",
+                    "update README with install steps",
+                ))
+                .expect("run")
+        };
+
+        let first = run();
+        let changes = store
+            .list_workspace_changes(&first.goal_id)
+            .expect("changes");
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "README.md");
+        assert_eq!(changes[0].change_kind, " M");
+        assert_eq!(changes[0].tool_name, "write_file");
+        assert!(!first.summary.contains("warning="));
+
+        let second = run();
+        assert!(second.summary.contains(&format!(
+            "warning=unmerged_changes_from_goals:{}",
+            first.goal_id
+        )));
+        let traces = store.get_traces(&second.goal_id).expect("traces");
+        assert!(
+            traces
+                .iter()
+                .any(|trace| trace.event_type == "workspace_overlap_warning")
+        );
+
+        git(&["commit", "-q", "-am", "merge readme"]);
+        let third = run();
+        assert!(!third.summary.contains("warning="));
+        assert!(
+            store
+                .list_workspace_changes(&first.goal_id)
+                .expect("changes")[0]
+                .merged_at_ms
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn written_files_carry_model_and_prompt_provenance() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+        let request = "update README with install steps";
+
+        let approved = runtime
+            .process_event(InboundEvent::new(Channel::Discord, "u1", request))
+            .expect("run");
+        let store = MemoryStore::open(&db_path).expect("store");
+        assert!(
+            store
+                .list_content_provenance(None, None, 10)
+                .expect("provenance")
+                .is_empty(),
+            "nothing is written before approval"
+        );
+        runtime
+            .resolve_approval(
+                &approved.pending_approval_id.expect("approval id"),
+                true,
+                "test",
+                None,
+            )
+            .expect("approve");
+        store.enable_yolo("cli", 15, None).expect("enable yolo");
+        let direct = runtime
+            .process_event(InboundEvent::new(Channel::Discord, "u1", request))
+            .expect("run");
 
-[permissions]
-scopes = ["READ"]
-allowed_paths = ["."]
-allowed_hosts = []
-"#
-            ),
-        )
-        .expect("skill manifest");
-        let hash = titan_skills::compute_bundle_hash(&bundle).expect("bundle hash");
-        let index = format!(
-            r#"{{
-  "skills": [
-    {{
-      "slug": "{slug}",
-      "name": "{slug}",
-      "latest": "{version}",
-      "versions": [
-        {{
-          "version": "{version}",
-          "download_url": "bundles/{slug}-{version}",
-          "sha256": "{hash}"
-        }}
-      ]
-    }}
-  ]
-}}"#
+        let entries = store
+            .list_content_provenance(Some("README.md"), None, 10)
+            .expect("provenance");
+        let goals: Vec<&str> = entries.iter().map(|entry| entry.goal_id.as_str()).collect();
+        assert_eq!(
+            goals,
+            vec![direct.goal_id.as_str(), approved.goal_id.as_str()]
+        );
+        let expected = ContentProvenance::new(
+            &TitanConfig::default().model,
+            &format!("[discord] {request}"),
+        );
+        assert_eq!(expected.model, "ollama:llama3.2:latest");
+        for entry in &entries {
+            assert_eq!(entry.model, expected.model);
+            assert_eq!(entry.prompt_hash, expected.prompt_hash);
+            assert_eq!(entry.tool_name, "write_file");
+        }
+        assert_eq!(
+            store
+                .list_content_provenance(None, Some(&direct.goal_id), 10)
+                .expect("by goal")
+                .len(),
+            1
         );
-        let index_path = workspace.join(".titan/registry/local/index.json");
-        std::fs::create_dir_all(index_path.parent().expect("parent")).expect("registry root");
-        std::fs::write(index_path, index).expect("index");
     }
 
     #[test]
-    fn injected_read_event_executes_and_persists_trace_and_memory() {
+    fn yolo_cannot_be_enabled_from_discord_or_web() {
         let tmp = tempdir().expect("tempdir");
         let workspace = tmp.path().join("ws");
         std::fs::create_dir_all(&workspace).expect("workspace");
-        std::fs::write(workspace.join("README.md"), "hello").expect("seed readme");
+        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+        let discord = runtime
+            .process_chat_input(InboundEvent::new(
+                Channel::Discord,
+                "u1",
+                "/titan yolo enable abc I_ACCEPT_UNBOUNDED_AUTONOMY",
+            ))
+            .expect("discord yolo");
+        assert!(discord.response.contains("local CLI"));
+        let web = runtime
+            .process_chat_input(InboundEvent::new(
+                Channel::Webchat,
+                "u1",
+                "/yolo enable abc I_ACCEPT_UNBOUNDED_AUTONOMY",
+            ))
+            .expect("web yolo");
+        assert!(web.response.contains("local CLI"));
+    }
+
+    #[test]
+    fn yolo_auto_expires() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
         let config_path = write_test_config(&workspace);
         let db_path = workspace.join("titan.db");
+        let store = MemoryStore::open(&db_path).expect("store");
+        let _ = store.get_runtime_risk_state().expect("risk");
+        store.enable_yolo("cli", 15, None).expect("yolo on");
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("clock")
+            .as_millis() as i64;
+        store
+            .set_yolo_expiry_at_ms(now_ms.saturating_sub(1))
+            .expect("expire");
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+        let outcome = runtime
+            .process_event(InboundEvent::new(
+                Channel::Discord,
+                "u1",
+                "update README with install steps",
+            ))
+            .expect("run");
+        assert!(outcome.pending_approval_id.is_some());
+        let state = MemoryStore::open(&db_path)
+            .expect("store")
+            .get_runtime_risk_state()
+            .expect("risk");
+        assert!(matches!(state.risk_mode, RiskMode::Secure));
+    }
 
+    #[test]
+    fn traces_include_risk_mode() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
         let runtime = TitanGatewayRuntime::new(
             AutonomyMode::Collaborative,
             workspace.clone(),
@@ -951,427 +5776,1435 @@ allowed_hosts = []
         .with_config_path(config_path);
         let outcome = runtime
             .process_event(InboundEvent::new(Channel::Discord, "u1", "scan workspace"))
-            .expect("process event");
-        assert!(outcome.pending_approval_id.is_none());
+            .expect("run");
+        let traces = MemoryStore::open(&db_path)
+            .expect("store")
+            .get_traces(&outcome.goal_id)
+            .expect("traces");
+        assert!(!traces.is_empty());
+        assert!(
+            traces
+                .iter()
+                .all(|trace| !trace.risk_mode.trim().is_empty())
+        );
+    }
 
-        let store = MemoryStore::open(&db_path).expect("open store");
-        let traces = store.get_traces(&outcome.goal_id).expect("traces");
+    #[test]
+    fn canary_write_plan_resumes_remaining_targets_after_confirmation() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+
+        let goal = Goal::new("write docs");
+        let plan = titan_core::TaskPlan {
+            intent: titan_core::GoalIntent::GenericRecon,
+            candidates: vec![titan_core::PlanCandidate {
+                id: "candidate-1".to_string(),
+                rationale: "write two docs".to_string(),
+                score: 1.0,
+                estimated_tokens: 0,
+                steps: vec![
+                    titan_core::Step::new(
+                        "step-1",
+                        StepPermission::Write,
+                        "write_file",
+                        Some("a.md::alpha".to_string()),
+                    ),
+                    titan_core::Step::new(
+                        "step-2",
+                        StepPermission::Write,
+                        "write_file",
+                        Some("b.md::beta".to_string()),
+                    ),
+                ],
+            }],
+            selected_index: 0,
+            traces: Vec::new(),
+            within_budget: true,
+        };
+        let registry = ToolRegistry::with_defaults();
+        let ctx = ToolExecutionContext::default_for_workspace(workspace.clone());
+        let run = titan_core::execute_task_plan_with_options(
+            goal,
+            plan,
+            ExecutionOptions {
+                canary_writes: true,
+                ..ExecutionOptions::default()
+            },
+            |_| Some(StepPermission::Write),
+            |_, _| false,
+            |step| {
+                let tool = registry.get(&step.tool_name).expect("tool");
+                let result = ToolExecutor::execute(tool, step.input.as_deref(), &ctx)
+                    .map_err(|err| err.to_string())?;
+                Ok(StepResult {
+                    step_id: step.id.clone(),
+                    tool_name: step.tool_name.clone(),
+                    status: result.status,
+                    output: result.output,
+                })
+            },
+        );
+        let mut store = MemoryStore::open(&db_path).expect("open store");
+        let persisted = store
+            .persist_run_bundle(RunPersistenceBundle {
+                run: &run,
+                source: "cli",
+                requested_by: Some("u1"),
+                approval_ttl_ms: 300_000,
+                reply: None,
+                approval_notices: Vec::new(),
+                reflection: None,
+                experiment: None,
+            })
+            .expect("persist run");
+        let approval_id = persisted.approval_id.expect("canary approval");
+        let approval = store
+            .get_approval_request(&approval_id)
+            .expect("get approval")
+            .expect("approval exists");
+        assert_eq!(approval.tool_name, CANARY_CONTINUE_TOOL);
+        assert!(approval.input.contains("+alpha"));
+        assert!(workspace.join("a.md").exists());
+        assert!(!workspace.join("b.md").exists());
+
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Autonomous, workspace.clone(), db_path)
+                .with_config_path(config_path);
+        let status = runtime
+            .resolve_approval(&approval_id, true, "test", None)
+            .expect("resolve canary");
+        assert_eq!(status, "approved canary_remaining=1");
+        assert_eq!(
+            std::fs::read_to_string(workspace.join("b.md")).expect("read b"),
+            "beta"
+        );
+        let traces = store.get_traces(&run.goal.id).expect("traces");
         assert!(
             traces
                 .iter()
-                .any(|trace| trace.event_type == "execution_started")
+                .any(|trace| trace.event_type == "canary_resumed")
+        );
+    }
+
+    #[test]
+    fn canary_confirmation_holds_remaining_steps_that_need_approval() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+
+        let write = |id: &str, input: &str| {
+            titan_core::Step::new(
+                id,
+                StepPermission::Write,
+                "write_file",
+                Some(input.to_string()),
+            )
+        };
+        let plan = titan_core::TaskPlan {
+            intent: titan_core::GoalIntent::GenericRecon,
+            candidates: vec![titan_core::PlanCandidate {
+                id: "candidate-1".to_string(),
+                rationale: "write three docs".to_string(),
+                score: 1.0,
+                estimated_tokens: 0,
+                steps: vec![
+                    write("step-1", "a.md::alpha"),
+                    write("step-2", "b.md::beta"),
+                    write("step-3", "c.md::gamma"),
+                ],
+            }],
+            selected_index: 0,
+            traces: Vec::new(),
+            within_budget: true,
+        };
+        let registry = ToolRegistry::with_defaults();
+        let ctx = ToolExecutionContext::default_for_workspace(workspace.clone());
+        let run = titan_core::execute_task_plan_with_options(
+            Goal::new("write docs"),
+            plan,
+            ExecutionOptions {
+                canary_writes: true,
+                ..ExecutionOptions::default()
+            },
+            |_| Some(StepPermission::Write),
+            |_, _| false,
+            |step| {
+                let tool = registry.get(&step.tool_name).expect("tool");
+                let result = ToolExecutor::execute(tool, step.input.as_deref(), &ctx)
+                    .map_err(|err| err.to_string())?;
+                Ok(StepResult {
+                    step_id: step.id.clone(),
+                    tool_name: step.tool_name.clone(),
+                    status: result.status,
+                    output: result.output,
+                })
+            },
+        );
+        let mut store = MemoryStore::open(&db_path).expect("open store");
+        let canary_id = store
+            .persist_run_bundle(RunPersistenceBundle {
+                run: &run,
+                source: "cli",
+                requested_by: Some("u1"),
+                approval_ttl_ms: 300_000,
+                reply: None,
+                approval_notices: Vec::new(),
+                reflection: None,
+                experiment: None,
+            })
+            .expect("persist run")
+            .approval_id
+            .expect("canary approval");
+
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Collaborative, workspace.clone(), db_path)
+                .with_config_path(config_path);
+        let pending_write = |store: &MemoryStore| {
+            let pending = store.list_pending_approvals().expect("approvals");
+            assert_eq!(pending.len(), 1);
+            assert_eq!(pending[0].tool_name, "write_file");
+            assert_eq!(pending[0].goal_id.as_deref(), Some(run.goal.id.as_str()));
+            pending[0].id.clone()
+        };
+
+        let status = runtime
+            .resolve_approval(&canary_id, true, "test", None)
+            .expect("resolve canary");
+        let held = pending_write(&store);
+        assert_eq!(
+            status,
+            format!("approved canary_held step=step-2 approval_id={held}")
         );
+        assert!(!workspace.join("b.md").exists());
+        let goal = store
+            .get_goal(&run.goal.id)
+            .expect("goal")
+            .expect("goal exists");
+        assert_eq!(goal.status, "pending");
+
+        runtime
+            .resolve_approval(&held, true, "test", None)
+            .expect("approve step-2");
         assert_eq!(
-            store
-                .count_plans_for_goal(&outcome.goal_id)
-                .expect("plan rows"),
-            1
+            std::fs::read_to_string(workspace.join("b.md")).expect("read b"),
+            "beta"
+        );
+        assert!(!workspace.join("c.md").exists());
+        let last = pending_write(&store);
+        assert_ne!(last, held);
+
+        runtime
+            .resolve_approval(&last, true, "test", None)
+            .expect("approve step-3");
+        assert_eq!(
+            std::fs::read_to_string(workspace.join("c.md")).expect("read c"),
+            "gamma"
         );
         assert!(
             store
-                .count_steps_for_goal(&outcome.goal_id)
-                .expect("step rows")
-                >= 1
+                .list_pending_approvals()
+                .expect("approvals")
+                .is_empty()
         );
-        let memories = store.list_episodic_memory(10).expect("memories");
-        assert!(
-            memories
-                .iter()
-                .any(|entry| entry.goal_id == outcome.goal_id)
+        let goal = store
+            .get_goal(&run.goal.id)
+            .expect("goal")
+            .expect("goal exists");
+        assert_eq!(goal.status, "completed");
+    }
+
+    #[test]
+    fn notifications_follow_operator_routes_and_quiet_hours() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let store = MemoryStore::open(&db_path).expect("store");
+        let pref = |operator: &str, event_kind: &str, quiet: Option<(i64, i64)>| {
+            titan_memory::NotificationPrefRecord {
+                operator: operator.to_string(),
+                event_kind: event_kind.to_string(),
+                channel: "webchat".to_string(),
+                target: operator.to_string(),
+                quiet_start_minute: quiet.map(|(start, _)| start),
+                quiet_end_minute: quiet.map(|(_, end)| end),
+            }
+        };
+        let now_minute = ((std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("clock")
+            .as_secs()
+            / 60)
+            % 1440) as i64;
+        let quiet = ((now_minute + 1430) % 1440, (now_minute + 10) % 1440);
+        store
+            .upsert_notification_pref(&pref("admin", "approval_resolved", None))
+            .expect("pref");
+        store
+            .upsert_notification_pref(&pref("admin", "*", Some(quiet)))
+            .expect("pref");
+        store
+            .upsert_notification_pref(&pref("sleeper", "*", Some(quiet)))
+            .expect("pref");
+        store
+            .upsert_notification_pref(&pref("digest-only", "digest", None))
+            .expect("pref");
+
+        let approval = store
+            .create_approval_request("write_file", "write", "a.txt::x", Some("u1"), 300_000)
+            .expect("approval");
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Collaborative, workspace.clone(), db_path)
+                .with_config_path(config_path);
+        let status = runtime
+            .resolve_approval(&approval.id, false, "admin", None)
+            .expect("resolve");
+        assert_eq!(status, "denied");
+
+        let mut log = store.list_notification_log(10).expect("log");
+        log.sort_by(|a, b| a.operator.cmp(&b.operator));
+        let summary: Vec<(&str, &str)> = log
+            .iter()
+            .map(|row| (row.operator.as_str(), row.status.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("admin", "queued"), ("sleeper", "suppressed")]
         );
-        println!(
-            "run_proof goal_id={} traces={} plans={} steps={} episodic={}",
-            outcome.goal_id,
-            traces.len(),
-            store
-                .count_plans_for_goal(&outcome.goal_id)
-                .expect("plan rows"),
-            store
-                .count_steps_for_goal(&outcome.goal_id)
-                .expect("step rows"),
-            memories.len()
+        assert!(log[0].message.contains("denied"));
+        assert_eq!(
+            parse_quiet_hours("22:00-07:30").expect("parse"),
+            (1320, 450)
+        );
+        assert!(parse_quiet_hours("25:00-07:00").is_err());
+    }
+
+    #[test]
+    fn goals_carry_auto_and_user_labels_and_saved_filters_are_per_actor() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let store = MemoryStore::open(&db_path).expect("store");
+        let runtime = TitanGatewayRuntime::new(
+            AutonomyMode::Collaborative,
+            workspace.clone(),
+            db_path.clone(),
+        )
+        .with_config_path(config_path);
+        let chat = |actor: &str, text: &str| {
+            runtime
+                .process_chat_input(InboundEvent::new(Channel::Discord, actor, text))
+                .expect("chat")
+        };
+
+        chat("u1", "scan workspace");
+        let goal_id = store.list_goals(1).expect("goals")[0].id.clone();
+        assert_eq!(
+            store.goal_labels(&goal_id).expect("labels"),
+            vec!["channel:discord", "intent:scan_workspace"]
+        );
+        assert_eq!(
+            chat(
+                "u1",
+                &format!("/label {goal_id} Release hotfix -channel:discord")
+            )
+            .response,
+            format!("goal={goal_id} labels=hotfix,intent:scan_workspace,release")
+        );
+        let listed = chat("u1", "/goals label=release status=completed").response;
+        assert!(listed.starts_with(&format!(
+            "{goal_id} completed [hotfix,intent:scan_workspace,release]"
+        )));
+        assert_eq!(
+            chat("u1", "/goals label=missing").response,
+            "no goals match"
+        );
+
+        assert_eq!(
+            chat("u1", "/goals save releases label=release").response,
+            "goal_filter_saved name=releases terms=label=release"
+        );
+        assert_eq!(chat("u1", "/goals releases").response, listed);
+        assert_eq!(
+            chat("u1", "/goals filters").response,
+            "releases: label=release"
+        );
+        assert_eq!(
+            chat("u2", "/goals releases").response,
+            "no saved goal filter named releases"
+        );
+        assert_eq!(
+            chat("u1", "/goals forget releases").response,
+            "goal_filter_removed=true name=releases"
         );
     }
 
     #[test]
-    fn collaborative_write_requires_approval_then_executes_after_approve() {
+    fn queued_approvals_notify_the_origin_and_operator_channel() {
         let tmp = tempdir().expect("tempdir");
         let workspace = tmp.path().join("ws");
         std::fs::create_dir_all(&workspace).expect("workspace");
         std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
         let config_path = write_test_config(&workspace);
+        let mut cfg = TitanConfig::load(&config_path).expect("load config");
+        cfg.approvals.operator_channel = Some(titan_common::ApprovalChannelConfig {
+            channel: "webchat".to_string(),
+            target: "ops-room".to_string(),
+        });
+        cfg.save(&config_path).expect("save config");
         let db_path = workspace.join("titan.db");
-
+        let store = MemoryStore::open(&db_path).expect("store");
         let runtime = TitanGatewayRuntime::new(
             AutonomyMode::Collaborative,
             workspace.clone(),
             db_path.clone(),
         )
         .with_config_path(config_path);
-        let outcome = runtime
-            .process_event(InboundEvent::new(
+        let notices = || {
+            let mut rows: Vec<(String, String, String)> = store
+                .list_outbox(None, 50)
+                .expect("outbox")
+                .into_iter()
+                .filter(|row| row.event_kind == "approval_requested")
+                .map(|row| (row.channel, row.target, row.message))
+                .collect();
+            rows.sort();
+            rows
+        };
+
+        let reply = runtime
+            .process_chat_input(
+                InboundEvent::new(Channel::Discord, "u1", "update readme")
+                    .with_reply_target("4242"),
+            )
+            .expect("chat");
+        let approval_id = reply
+            .response
+            .split_whitespace()
+            .find_map(|word| word.strip_prefix("approval_pending="))
+            .expect("approval queued")
+            .to_string();
+        let queued = notices();
+        let targets: Vec<(&str, &str)> = queued
+            .iter()
+            .map(|(channel, target, _)| (channel.as_str(), target.as_str()))
+            .collect();
+        assert_eq!(targets, vec![("discord", "4242"), ("webchat", "ops-room")]);
+        for (_, _, message) in &queued {
+            assert!(message.starts_with(&format!(
+                "approval {approval_id} queued: write_file (write), expires in "
+            )));
+            assert!(message.ends_with(&format!(
+                "Reply /approve {approval_id} or /deny {approval_id}."
+            )));
+        }
+
+        // A command's own reply answers its conversation, so only the operator channel hears.
+        let requested = runtime
+            .process_chat_input(InboundEvent::new(
                 Channel::Discord,
                 "u1",
-                "update README with install steps",
+                "/connector add github widgets owner=acme repo=widgets",
             ))
-            .expect("process event");
-        let approval_id = outcome.pending_approval_id.expect("approval id");
-
-        assert!(
-            !std::fs::read_to_string(workspace.join("README.md"))
-                .expect("read readme")
-                .contains("Install Steps (Generated)")
-        );
-        let status = runtime
-            .resolve_approval(&approval_id, true, "test", Some("approved in test"))
-            .expect("resolve approval");
-        assert_eq!(status, "approved");
-        assert!(
-            std::fs::read_to_string(workspace.join("README.md"))
-                .expect("read readme")
-                .contains("Install Steps (Generated)")
-        );
-
-        let store = MemoryStore::open(&db_path).expect("open store");
-        let traces = store.get_traces(&outcome.goal_id).expect("traces");
-        assert!(
-            traces
-                .iter()
-                .any(|trace| trace.event_type == "approval_queued")
-        );
-        assert!(
-            traces
-                .iter()
-                .any(|trace| trace.event_type == "approval_executed")
-        );
-        let memories = store.list_episodic_memory(10).expect("memories");
+            .expect("chat")
+            .response;
+        let connector_approval = requested
+            .strip_prefix("approval_required=true approval_id=")
+            .expect("approval requested");
+        let after = notices();
+        assert_eq!(after.len(), 3);
         assert!(
-            memories
+            after
                 .iter()
-                .any(|entry| entry.goal_id == outcome.goal_id)
-        );
-        println!(
-            "approval_proof goal_id={} approval_id={} traces={} episodic={} readme_updated={}",
-            outcome.goal_id,
-            approval_id,
-            traces.len(),
-            memories.len(),
-            std::fs::read_to_string(workspace.join("README.md"))
-                .expect("read readme")
-                .contains("Install Steps (Generated)")
+                .any(|(channel, _, message)| channel == "webchat"
+                    && message.contains(&format!(
+                        "{connector_approval} queued: connector_add (write)"
+                    )))
         );
     }
 
     #[test]
-    fn slash_status_reports_expected_fields() {
+    fn run_replies_and_notifications_go_through_the_outbox() {
         let tmp = tempdir().expect("tempdir");
         let workspace = tmp.path().join("ws");
         std::fs::create_dir_all(&workspace).expect("workspace");
-        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
         let config_path = write_test_config(&workspace);
         let db_path = workspace.join("titan.db");
+        let store = MemoryStore::open(&db_path).expect("store");
         let runtime = TitanGatewayRuntime::new(
             AutonomyMode::Collaborative,
             workspace.clone(),
             db_path.clone(),
         )
         .with_config_path(config_path);
-        let out = runtime
-            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "/status"))
-            .expect("status");
-        assert!(out.response.contains("mode="));
-        assert!(out.response.contains("session_id="));
-        assert!(out.response.contains("pending_approvals="));
+
+        let reply = runtime
+            .process_chat_input(
+                InboundEvent::new(Channel::Discord, "u1", "scan workspace")
+                    .with_reply_target("4242"),
+            )
+            .expect("chat");
+        let outbox_id = reply.reply_outbox_id.expect("reply queued with the run");
+        let row = store.get_outbox(outbox_id).expect("get").expect("row");
+        assert_eq!(row.status, "pending");
+        assert_eq!(
+            (row.channel.as_str(), row.target.as_str()),
+            ("discord", "4242")
+        );
+        assert_eq!(row.message, reply.response);
+        assert!(row.next_attempt_at_ms > row.created_at_ms);
+        assert!(store.mark_outbox_delivered(outbox_id).expect("mark"));
+        assert!(!store.mark_outbox_delivered(outbox_id).expect("mark twice"));
+
+        for (operator, channel) in [("web", "webchat"), ("pigeon", "carrier-pigeon")] {
+            store
+                .upsert_notification_pref(&titan_memory::NotificationPrefRecord {
+                    operator: operator.to_string(),
+                    event_kind: "digest".to_string(),
+                    channel: channel.to_string(),
+                    target: operator.to_string(),
+                    quiet_start_minute: None,
+                    quiet_end_minute: None,
+                })
+                .expect("pref");
+        }
+        let sent = dispatch_notification(&store, "digest", "hello").expect("dispatch");
+        let mut statuses: Vec<&str> = sent.iter().map(|row| row.status.as_str()).collect();
+        statuses.sort();
+        assert_eq!(statuses, vec!["failed", "queued"]);
+
+        let pending = store.list_outbox(Some("pending"), 10).expect("pending");
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].channel, "carrier-pigeon");
+        assert_eq!(pending[0].attempts, 1);
+        let mut now = pending[0].next_attempt_at_ms - 1;
+        assert_eq!(
+            deliver_outbox(&store, now).expect("not due"),
+            OutboxDeliverySummary::default()
+        );
+        let mut summary = OutboxDeliverySummary::default();
+        for _ in 1..OUTBOX_MAX_ATTEMPTS {
+            now += 3_600_000;
+            let tick = deliver_outbox(&store, now).expect("deliver");
+            summary.retrying += tick.retrying;
+            summary.failed += tick.failed;
+        }
+        assert_eq!(summary.retrying as i64, OUTBOX_MAX_ATTEMPTS - 2);
+        assert_eq!(summary.failed, 1);
+        let failed = store.list_outbox(Some("failed"), 10).expect("failed");
+        assert_eq!(failed[0].attempts, OUTBOX_MAX_ATTEMPTS);
     }
 
     #[test]
-    fn slash_new_and_compact_and_stop_mutate_session_state() {
+    fn approval_comments_reach_requester_session() {
         let tmp = tempdir().expect("tempdir");
         let workspace = tmp.path().join("ws");
         std::fs::create_dir_all(&workspace).expect("workspace");
-        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
         let config_path = write_test_config(&workspace);
         let db_path = workspace.join("titan.db");
-        let runtime = TitanGatewayRuntime::new(
-            AutonomyMode::Collaborative,
-            workspace.clone(),
-            db_path.clone(),
-        )
-        .with_config_path(config_path);
-        let _ = runtime
-            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "scan workspace"))
-            .expect("run1");
-        let status1 = runtime
-            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "/status"))
-            .expect("status1");
-        let session_id_old = status1
-            .response
-            .split_whitespace()
-            .find(|part| part.starts_with("session_id="))
-            .and_then(|part| part.split_once('=').map(|(_, v)| v.to_string()))
-            .expect("session id");
+        let store = MemoryStore::open(&db_path).expect("store");
+        let session = store
+            .get_or_create_active_session("discord", "requester")
+            .expect("session");
+        let goal = titan_core::Goal::new("write config".to_string());
+        store
+            .create_goal_for_session(&goal, Some(&session.id))
+            .expect("goal");
+        let approval = store
+            .create_approval_request_for_goal(
+                Some(&goal.id),
+                "write_file",
+                "write",
+                "/etc/hosts::x",
+                Some("requester"),
+                300_000,
+            )
+            .expect("approval");
 
-        let _ = runtime
-            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "/new"))
-            .expect("new");
-        let status2 = runtime
-            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "/status"))
-            .expect("status2");
-        let session_id_new = status2
-            .response
-            .split_whitespace()
-            .find(|part| part.starts_with("session_id="))
-            .and_then(|part| part.split_once('=').map(|(_, v)| v.to_string()))
-            .expect("session id");
-        assert_ne!(session_id_old, session_id_new);
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Collaborative, workspace.clone(), db_path)
+                .with_config_path(config_path);
+        let out = runtime
+            .process_chat_input(InboundEvent::new(
+                Channel::Webchat,
+                "approver",
+                format!(
+                    "/comment {} why do you need write access to /etc?",
+                    approval.id
+                ),
+            ))
+            .expect("comment");
+        assert!(out.response.starts_with("comment_added"));
 
-        let _ = runtime
-            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "scan workspace"))
-            .expect("run2");
-        let compact = runtime
-            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "/compact"))
-            .expect("compact");
-        assert!(compact.response.contains("session_compacted"));
-        let stop = runtime
-            .process_chat_input(InboundEvent::new(Channel::Discord, "u1", "/stop"))
-            .expect("stop");
-        assert!(stop.response.contains("session_stop_requested"));
+        let messages = store
+            .list_session_messages(&session.id, 10)
+            .expect("messages");
+        assert!(messages.iter().any(|m| {
+            m.role == "approval_comment"
+                && m.content
+                    .contains("approver: why do you need write access to /etc?")
+        }));
+        let traces = store.get_traces(&goal.id).expect("traces");
+        assert!(traces.iter().any(|t| t.event_type == "approval_comment"));
+
+        let listed = runtime
+            .process_chat_input(InboundEvent::new(
+                Channel::Discord,
+                "requester",
+                format!("/comments {}", approval.id),
+            ))
+            .expect("comments");
+        assert!(
+            listed
+                .response
+                .contains("approver: why do you need write access")
+        );
     }
 
     #[test]
-    fn webchat_slash_is_intercepted_not_routed_as_goal() {
+    fn expired_approval_is_rerequested_by_its_requester() {
         let tmp = tempdir().expect("tempdir");
         let workspace = tmp.path().join("ws");
         std::fs::create_dir_all(&workspace).expect("workspace");
-        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
         let config_path = write_test_config(&workspace);
+        let mut cfg = TitanConfig::load(&config_path).expect("config");
+        cfg.chat.operators = vec!["ops".to_string()];
+        cfg.save(&config_path).expect("save config");
         let db_path = workspace.join("titan.db");
-        let runtime = TitanGatewayRuntime::new(
-            AutonomyMode::Collaborative,
-            workspace.clone(),
-            db_path.clone(),
-        )
-        .with_config_path(config_path);
-        let out = runtime
-            .process_chat_input(InboundEvent::new(Channel::Webchat, "web-user", "/status"))
-            .expect("web status");
-        assert!(out.response.contains("session_id="));
         let store = MemoryStore::open(&db_path).expect("store");
         let session = store
-            .get_or_create_active_session("webchat", "web-user")
+            .get_or_create_active_session("discord", "requester")
             .expect("session");
-        let last_goal = store
-            .last_goal_for_session(&session.id)
-            .expect("last goal for session");
-        assert!(last_goal.is_none());
+        let goal = titan_core::Goal::new("write notes".to_string());
+        store
+            .create_goal_for_session(&goal, Some(&session.id))
+            .expect("goal");
+        let expired = store
+            .create_approval_request_for_goal(
+                Some(&goal.id),
+                "write_file",
+                "write",
+                "notes.txt::+ new line",
+                Some("requester"),
+                0,
+            )
+            .expect("approval");
+        assert!(store.list_pending_approvals().expect("expire").is_empty());
+        let messages = store
+            .list_session_messages(&session.id, 10)
+            .expect("messages");
+        assert!(messages.iter().any(|m| m.role == "approval_expired"
+            && m.content.contains(&format!("/rerequest {}", goal.id))));
+
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Collaborative, workspace.clone(), db_path)
+                .with_config_path(config_path);
+        let command = format!("/rerequest {}", goal.id);
+        let denied = runtime
+            .process_chat_input(InboundEvent::new(
+                Channel::Discord,
+                "bystander",
+                command.clone(),
+            ))
+            .expect("bystander");
+        assert!(denied.response.starts_with("permission_denied"));
+
+        let out = runtime
+            .process_chat_input(InboundEvent::new(
+                Channel::Discord,
+                "requester",
+                command.clone(),
+            ))
+            .expect("rerequest");
+        assert!(out.response.starts_with("approval_required=true"));
+        let fresh = store
+            .latest_approval_for_goal(&goal.id)
+            .expect("latest")
+            .expect("fresh approval");
+        assert_ne!(fresh.id, expired.id);
+        assert_eq!(fresh.status, "pending");
+        assert_eq!(fresh.input, expired.input);
+        assert_eq!(fresh.requested_by.as_deref(), Some("requester"));
+        assert_eq!(
+            store.rerequested_from(&fresh.id).expect("link").as_deref(),
+            Some(expired.id.as_str())
+        );
+
+        let again = runtime
+            .process_chat_input(InboundEvent::new(Channel::Discord, "requester", command))
+            .expect("again");
+        assert!(again.response.contains("is pending; nothing to re-request"));
     }
 
     #[test]
-    fn chat_skill_install_creates_approval_then_finalizes_on_approve() {
+    fn connectors_tools_slash_command_lists_descriptors() {
         let tmp = tempdir().expect("tempdir");
         let workspace = tmp.path().join("ws");
         std::fs::create_dir_all(&workspace).expect("workspace");
-        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
-        seed_local_registry_skill(&workspace, "list-docs", "1.0.0");
         let config_path = write_test_config(&workspace);
         let db_path = workspace.join("titan.db");
-        let runtime = TitanGatewayRuntime::new(
-            AutonomyMode::Collaborative,
-            workspace.clone(),
-            db_path.clone(),
-        )
-        .with_config_path(config_path);
+        let store = MemoryStore::open(&db_path).expect("store");
+        store
+            .add_connector(
+                "44444444-4444-4444-4444-444444444444",
+                "github",
+                "GitHub",
+                r#"{"owner":"acme","repo":"demo"}"#,
+            )
+            .expect("connector");
+
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Collaborative, workspace.clone(), db_path)
+                .with_config_path(config_path);
+        let out = runtime
+            .process_chat_input(InboundEvent::new(
+                Channel::Discord,
+                "u1",
+                "/connectors tools",
+            ))
+            .expect("connectors tools");
+        assert!(
+            out.response
+                .contains("github.list_issues risk=net scopes=read,net")
+        );
+        assert!(out.response.contains("github.create_issue"));
+
+        let missing = runtime
+            .process_chat_input(InboundEvent::new(
+                Channel::Discord,
+                "u1",
+                "/connectors tools missing-id",
+            ))
+            .expect("missing connector");
+        assert_eq!(missing.response, "connector not found: missing-id");
+    }
+
+    #[test]
+    fn planner_routes_connector_intent_through_connector_approval() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let store = MemoryStore::open(&db_path).expect("store");
+        store
+            .add_connector(
+                "55555555-5555-5555-5555-555555555555",
+                "github",
+                "GitHub",
+                r#"{"owner":"acme","repo":"demo"}"#,
+            )
+            .expect("connector");
+
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Collaborative, workspace.clone(), db_path)
+                .with_config_path(config_path);
+        let outcome = runtime
+            .process_event(InboundEvent::new(
+                Channel::Discord,
+                "u1",
+                "list open github issues",
+            ))
+            .expect("process event");
+        let approval_id = outcome.pending_approval_id.expect("connector approval");
+        let approval = store
+            .get_approval_request(&approval_id)
+            .expect("approval lookup")
+            .expect("approval row");
+        assert_eq!(approval.tool_name, "connector_tool");
+        assert_eq!(approval.capability, "net");
+        let payload: serde_json::Value =
+            serde_json::from_str(&approval.input).expect("payload json");
+        assert_eq!(payload["kind"], "connector_tool");
+        assert_eq!(payload["payload"]["tool_name"], "github.list_issues");
+        assert_eq!(
+            payload["payload"]["connector_id"],
+            "55555555-5555-5555-5555-555555555555"
+        );
+    }
+
+    #[test]
+    fn webhooks_deliver_lifecycle_events_and_back_off_on_failure() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("accept");
+            let mut raw = Vec::new();
+            let mut buf = [0_u8; 4096];
+            loop {
+                let n = stream.read(&mut buf).expect("read");
+                raw.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&raw).to_string();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .find_map(|line| {
+                            line.to_ascii_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap_or(0))
+                        })
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        break;
+                    }
+                }
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .expect("respond");
+            String::from_utf8_lossy(&raw).to_string()
+        });
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").expect("bind closed");
+        let closed_addr = closed.local_addr().expect("closed addr");
+        drop(closed);
+
+        let tmp = tempdir().expect("tempdir");
+        let store = MemoryStore::open(&tmp.path().join("titan.db")).expect("open store");
+        let hooks = vec![
+            titan_common::WebhookConfig {
+                name: "ops".to_string(),
+                url: format!("http://{addr}/hook"),
+                events: vec!["approval_created".to_string()],
+                secret_env: None,
+                max_attempts: 3,
+            },
+            titan_common::WebhookConfig {
+                name: "down".to_string(),
+                url: format!("http://{closed_addr}/hook"),
+                events: Vec::new(),
+                secret_env: None,
+                max_attempts: 2,
+            },
+        ];
+        store
+            .create_approval_request("write_file", "write", "a.txt", Some("test"), 60_000)
+            .expect("pre-existing approval");
+        let first = dispatch_webhooks(&store, &hooks, 0).expect("init cursors");
+        assert_eq!(first, WebhookDispatchSummary::default());
+
+        let approval = store
+            .create_approval_request("write_file", "write", "b.txt", Some("test"), 60_000)
+            .expect("approval");
+        store.enable_yolo("cli", 5, None).expect("yolo");
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("clock")
+            .as_millis() as i64
+            + 1_000;
+        let summary = dispatch_webhooks(&store, &hooks, now).expect("dispatch");
+        assert_eq!(summary.enqueued, 3);
+        assert_eq!(summary.delivered, 1);
+        assert_eq!(summary.retrying, 2);
+
+        let request = server.join().expect("server");
+        assert!(
+            request
+                .to_ascii_lowercase()
+                .contains("x-titan-event: approval_created")
+        );
+        assert!(request.contains(&approval.id));
+        assert!(request.contains("\"tool\":\"write_file\""));
+
+        let down: Vec<_> = store
+            .list_webhook_deliveries(10)
+            .expect("deliveries")
+            .into_iter()
+            .filter(|d| d.webhook == "down")
+            .collect();
+        assert!(down.iter().all(|d| d.status == "pending"
+            && d.attempts == 1
+            && d.next_attempt_at_ms == now + webhook_backoff_ms(1)));
+
+        let later = dispatch_webhooks(&store, &hooks, now + 60_000).expect("retry");
+        assert_eq!(later.failed, 2);
+        assert_eq!(webhook_backoff_ms(3), 40_000);
+        assert_eq!(webhook_backoff_ms(30), 3_600_000);
+    }
+
+    #[test]
+    fn urgent_goal_pauses_lower_goal_until_it_finishes() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Autonomous, workspace.clone(), db_path.clone())
+                .with_config_path(config_path);
 
-        let out = runtime
-            .process_chat_input(InboundEvent::new(
-                Channel::Discord,
-                "u1",
-                "/titan skill install list-docs@1.0.0",
-            ))
-            .expect("skill install command");
-        assert!(out.response.contains("approval_required=true"));
-        let approval_id = out
+        // An urgent goal another process is running.
+        let store = MemoryStore::open(&db_path).expect("store");
+        let urgent = Goal::new("rotate keys").with_priority(GoalPriority::Urgent);
+        store.create_goal(&urgent).expect("urgent goal");
+
+        let paused = runtime
+            .process_chat_input(
+                InboundEvent::new(Channel::Webchat, "u1", "scan workspace")
+                    .with_priority(GoalPriority::Low),
+            )
+            .expect("paused run");
+        assert!(
+            paused.response.contains("status=paused"),
+            "{}",
+            paused.response
+        );
+        let goal_id = paused
             .response
-            .split_whitespace()
-            .find(|part| part.starts_with("approval_id="))
-            .and_then(|part| part.split_once('=').map(|(_, value)| value.to_string()))
-            .expect("approval id");
+            .strip_prefix("goal=")
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("goal id")
+            .to_string();
+        let checkpoint = store
+            .goal_checkpoint(&goal_id)
+            .expect("checkpoint")
+            .expect("row");
+        assert_eq!(checkpoint.results.len(), 1);
+        assert_eq!(checkpoint.preempted_by, urgent.id);
+        assert!(
+            runtime
+                .resume_paused_goals()
+                .expect("still waiting")
+                .is_empty()
+        );
 
-        let approval_status = runtime
+        store
+            .update_goal_status(&urgent.id, GoalStatus::Completed)
+            .expect("finish urgent");
+        let resumed = runtime.resume_paused_goals().expect("resume");
+        assert_eq!(resumed.len(), 1);
+        assert_eq!(
+            (resumed[0].goal_id.as_str(), resumed[0].goal_status),
+            (goal_id.as_str(), GoalStatus::Completed)
+        );
+        assert!(
+            store
+                .goal_checkpoint(&goal_id)
+                .expect("checkpoint")
+                .is_none()
+        );
+        let traces = store.get_traces(&goal_id).expect("traces");
+        assert!(traces.iter().any(|trace| trace.event_type == "goal_resumed"
+            && trace.detail.ends_with("1 steps already done")));
+
+        let reply = runtime
             .process_chat_input(InboundEvent::new(
-                Channel::Discord,
+                Channel::Webchat,
                 "u1",
-                format!("/approve {approval_id}"),
+                "/priority urgent scan workspace",
             ))
-            .expect("approve");
+            .expect("priority command");
         assert!(
-            approval_status
-                .response
-                .contains("installed=list-docs@1.0.0")
+            reply.response.contains("status=completed"),
+            "{}",
+            reply.response
+        );
+        let goal_id = reply
+            .response
+            .strip_prefix("goal=")
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("goal id");
+        assert_eq!(
+            store
+                .get_goal(goal_id)
+                .expect("goal")
+                .expect("row")
+                .priority,
+            "urgent"
         );
-        let store = MemoryStore::open(&db_path).expect("store");
-        let installed = store
-            .get_installed_skill("list-docs")
-            .expect("installed lookup")
-            .expect("installed row");
-        assert_eq!(installed.version, "1.0.0");
     }
 
     #[test]
-    fn secure_mode_blocks_write_in_collab() {
+    fn usage_is_recorded_per_message_and_budgets_block_new_requests() {
         let tmp = tempdir().expect("tempdir");
         let workspace = tmp.path().join("ws");
         std::fs::create_dir_all(&workspace).expect("workspace");
-        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
         let config_path = write_test_config(&workspace);
+        let mut cfg = TitanConfig::load(&config_path).expect("load config");
+        cfg.usage.session_token_budget = Some(50);
+        cfg.usage.usd_per_1k_tokens = 2.0;
+        cfg.save(&config_path).expect("save config");
         let db_path = workspace.join("titan.db");
-        let runtime = TitanGatewayRuntime::new(
-            AutonomyMode::Collaborative,
-            workspace.clone(),
-            db_path.clone(),
-        )
-        .with_config_path(config_path);
-        let outcome = runtime
-            .process_event(InboundEvent::new(
-                Channel::Discord,
-                "u1",
-                "update README with install steps",
-            ))
-            .expect("run");
-        assert!(outcome.pending_approval_id.is_some());
+        let store = MemoryStore::open(&db_path).expect("store");
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Autonomous, workspace.clone(), db_path.clone())
+                .with_config_path(config_path.clone());
+        let chat = |text: &str| {
+            runtime
+                .process_chat_input(InboundEvent::new(Channel::Webchat, "u1", text))
+                .expect("chat")
+                .response
+        };
+
+        chat("scan workspace");
+        let session = store
+            .get_or_create_active_session("webchat", "u1")
+            .expect("session");
+        let used = store.session_usage(&session.id).expect("usage");
+        assert_eq!(used.messages, 2, "the request and its reply");
+        assert!(used.tokens > 50, "{used:?}");
+        assert!((used.cost_usd - used.tokens as f64 / 1000.0 * 2.0).abs() < 1e-9);
+
+        let refused = chat("scan workspace");
+        assert!(
+            refused.contains("Token budget reached: this session used")
+                && refused.contains("Send /new"),
+            "{refused}"
+        );
+        assert_eq!(store.list_goals(10).expect("goals").len(), 1);
+        let report = chat("/usage");
+        assert!(
+            report.starts_with(&format!(
+                "usage_mode=tokens session_messages=2 session_tokens={} ",
+                used.tokens
+            )) && report.contains("session_budget=50")
+                && report.contains("daily_budget=none"),
+            "{report}"
+        );
+        assert_eq!(chat("/usage full"), "usage_mode_updated=full");
+
+        // A fresh session gets its own budget, but the day's budget spans sessions.
+        chat("/new");
+        assert!(!chat("scan workspace").contains("budget reached"));
+        let mut cfg = TitanConfig::load(&config_path).expect("load config");
+        cfg.usage.daily_token_budget = Some(100);
+        cfg.save(&config_path).expect("save config");
+        chat("/new");
+        let refused = chat("scan workspace");
+        assert!(refused.contains("Daily token budget reached"), "{refused}");
     }
 
     #[test]
-    fn yolo_allows_write_without_approval_and_traces() {
+    fn goals_over_the_concurrency_limit_queue_until_a_slot_frees() {
         let tmp = tempdir().expect("tempdir");
         let workspace = tmp.path().join("ws");
         std::fs::create_dir_all(&workspace).expect("workspace");
-        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
         let config_path = write_test_config(&workspace);
+        let mut cfg = TitanConfig::load(&config_path).expect("load config");
+        cfg.execution.max_concurrent_goals_per_channel = Some(1);
+        cfg.save(&config_path).expect("save config");
         let db_path = workspace.join("titan.db");
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Autonomous, workspace.clone(), db_path.clone())
+                .with_config_path(config_path);
+
+        // A webchat goal another request is still running.
         let store = MemoryStore::open(&db_path).expect("store");
-        let _ = store.get_runtime_risk_state().expect("risk state");
-        store.enable_yolo("cli", 15).expect("enable yolo");
-        let runtime = TitanGatewayRuntime::new(
-            AutonomyMode::Collaborative,
-            workspace.clone(),
-            db_path.clone(),
-        )
-        .with_config_path(config_path);
-        let outcome = runtime
-            .process_event(InboundEvent::new(
-                Channel::Discord,
-                "u1",
-                "update README with install steps",
-            ))
-            .expect("run");
-        assert!(outcome.pending_approval_id.is_none());
-        let readme = std::fs::read_to_string(workspace.join("README.md")).expect("readme");
-        assert!(readme.contains("Install Steps (Generated)"));
-        let traces = MemoryStore::open(&db_path)
-            .expect("store")
-            .get_traces(&outcome.goal_id)
-            .expect("traces");
-        assert!(traces.iter().any(|trace| trace.risk_mode == "yolo"));
+        let session = store
+            .get_or_create_active_session("webchat", "u2")
+            .expect("session");
+        let mut running = Goal::new("[webchat] scan workspace");
+        running.status = GoalStatus::Executing;
+        store
+            .create_goal_for_session(&running, Some(&session.id))
+            .expect("running goal");
+
+        let queued = runtime
+            .process_chat_input(InboundEvent::new(Channel::Webchat, "u1", "scan workspace"))
+            .expect("queued");
+        assert!(
+            queued
+                .response
+                .contains("status=queued summary=Queued at position 1: channel limit 1/1"),
+            "{}",
+            queued.response
+        );
+        let goal_id = queued
+            .response
+            .strip_prefix("goal=")
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("goal id")
+            .to_string();
+        let status = runtime
+            .process_chat_input(InboundEvent::new(Channel::Webchat, "u1", "/status"))
+            .expect("status");
+        assert!(
+            status
+                .response
+                .contains(&format!("queued_goals={goal_id}@1")),
+            "{}",
+            status.response
+        );
+        // Other channels have their own slot.
+        let cli = runtime
+            .process_chat_input(InboundEvent::new(Channel::Cli, "u1", "scan workspace"))
+            .expect("cli goal");
+        assert!(
+            cli.response.contains("status=completed"),
+            "{}",
+            cli.response
+        );
+        assert!(runtime.start_queued_goals().expect("still full").is_empty());
+
+        store
+            .update_goal_status(&running.id, GoalStatus::Completed)
+            .expect("finish running goal");
+        let started = runtime.start_queued_goals().expect("start");
+        assert_eq!(started.len(), 1);
+        assert_eq!(
+            (started[0].goal_id.as_str(), started[0].goal_status),
+            (goal_id.as_str(), GoalStatus::Completed)
+        );
+        assert!(store.queued_goals().expect("queue").is_empty());
+        let types: Vec<String> = store
+            .get_traces(&goal_id)
+            .expect("traces")
+            .into_iter()
+            .map(|trace| trace.event_type)
+            .collect();
+        assert!(types.iter().any(|kind| kind == "goal_queued"));
+        assert!(types.iter().any(|kind| kind == "goal_dequeued"));
     }
 
     #[test]
-    fn yolo_cannot_be_enabled_from_discord_or_web() {
+    fn federated_goals_forward_to_peer_and_merge_remote_traces() {
+        use std::io::{Read, Write};
+
+        // Stand-in peer: accepts one event, then reports the goal executing and later completed.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let peer = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            let mut polls = 0;
+            for stream in listener.incoming().take(3) {
+                let mut stream = stream.expect("accept");
+                let mut raw = Vec::new();
+                let mut buf = [0_u8; 4096];
+                let request = loop {
+                    let n = stream.read(&mut buf).expect("read");
+                    raw.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&raw).to_string();
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let length = head
+                            .lines()
+                            .find_map(|line| {
+                                line.to_ascii_lowercase()
+                                    .strip_prefix("content-length:")
+                                    .map(|v| v.trim().parse::<usize>().unwrap_or(0))
+                            })
+                            .unwrap_or(0);
+                        if body.len() >= length {
+                            break text;
+                        }
+                    }
+                };
+                let body = if request.starts_with("POST /api/events/batch") {
+                    serde_json::json!({"results": [{
+                        "index": 0, "status": "processed", "goal_id": "remote-1",
+                        "goal_status": "executing", "approval_id": null, "detail": "scan queued"
+                    }]})
+                } else {
+                    polls += 1;
+                    let (status, traces) = if polls == 1 {
+                        (
+                            "executing",
+                            serde_json::json!([
+                                {"id": 4, "goal_id": "remote-1", "event_type": "execution_started",
+                                 "detail": "steps=2", "risk_mode": "secure",
+                                 "created_at": "", "redacted": false}
+                            ]),
+                        )
+                    } else {
+                        (
+                            "completed",
+                            serde_json::json!([
+                                {"id": 9, "goal_id": "remote-1", "event_type": "goal_completed",
+                                 "detail": "done", "risk_mode": "secure",
+                                 "created_at": "", "redacted": false}
+                            ]),
+                        )
+                    };
+                    serde_json::json!({
+                        "id": "remote-1", "description": "[webchat] scan workspace",
+                        "status": status, "dedupe_key": null, "traces": traces
+                    })
+                }
+                .to_string();
+                stream
+                    .write_all(
+                        format!(
+                            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                            body.len()
+                        )
+                        .as_bytes(),
+                    )
+                    .expect("respond");
+                requests.push(request.lines().next().unwrap_or_default().to_string());
+                if request.starts_with("POST") {
+                    requests.push(
+                        request
+                            .rsplit("\r\n\r\n")
+                            .next()
+                            .unwrap_or_default()
+                            .to_string(),
+                    );
+                }
+            }
+            requests
+        });
+
         let tmp = tempdir().expect("tempdir");
         let workspace = tmp.path().join("ws");
         std::fs::create_dir_all(&workspace).expect("workspace");
-        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
         let config_path = write_test_config(&workspace);
+        let mut cfg = TitanConfig::load(&config_path).expect("load config");
+        cfg.federation.push(titan_common::FederationPeerConfig {
+            name: "homelab".to_string(),
+            url: format!("http://{addr}"),
+            intents: vec!["scan_workspace".to_string()],
+            paths: vec!["lab/".to_string()],
+        });
+        cfg.save(&config_path).expect("save config");
         let db_path = workspace.join("titan.db");
-        let runtime = TitanGatewayRuntime::new(
-            AutonomyMode::Collaborative,
-            workspace.clone(),
-            db_path.clone(),
-        )
-        .with_config_path(config_path);
-        let discord = runtime
-            .process_chat_input(InboundEvent::new(
-                Channel::Discord,
-                "u1",
-                "/titan yolo enable abc I_ACCEPT_UNBOUNDED_AUTONOMY",
-            ))
-            .expect("discord yolo");
-        assert!(discord.response.contains("local CLI"));
-        let web = runtime
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Autonomous, workspace.clone(), db_path.clone())
+                .with_config_path(config_path.clone());
+
+        let forwarded = runtime
+            .process_chat_input(InboundEvent::new(Channel::Webchat, "u1", "scan workspace"))
+            .expect("forward");
+        assert!(
+            forwarded
+                .response
+                .contains("status=executing summary=Forwarded to homelab as goal remote-1")
+        );
+        let goal_id = forwarded
+            .response
+            .strip_prefix("goal=")
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("goal id")
+            .to_string();
+        let store = MemoryStore::open(&db_path).expect("store");
+        let remote = store.remote_goal(&goal_id).expect("remote").expect("row");
+        assert_eq!(
+            (remote.remote_goal_id.as_str(), remote.last_trace_id),
+            ("remote-1", 4)
+        );
+
+        let summary = sync_remote_goals(&store, &cfg.federation).expect("sync");
+        assert_eq!((summary.polled, summary.finished), (1, 1));
+        assert_eq!(
+            store.get_goal(&goal_id).expect("goal").expect("row").status,
+            "completed"
+        );
+        let types: Vec<String> = store
+            .get_traces(&goal_id)
+            .expect("traces")
+            .into_iter()
+            .map(|trace| trace.event_type)
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                "goal_submitted",
+                "event_received",
+                "goal_forwarded",
+                "remote:execution_started",
+                "remote:goal_completed"
+            ]
+        );
+        assert!(store.list_active_remote_goals().expect("active").is_empty());
+
+        let requests = peer.join().expect("peer");
+        assert_eq!(requests[0], "POST /api/events/batch HTTP/1.1");
+        let submitted: serde_json::Value = serde_json::from_str(&requests[1]).expect("body");
+        assert_eq!(submitted["events"][0]["actor_id"], "federation:u1");
+        assert_eq!(
+            submitted["events"][0]["dedupe_key"],
+            format!("federation:{goal_id}")
+        );
+        assert_eq!(requests[2], "GET /api/goals/remote-1?after_id=0 HTTP/1.1");
+        assert_eq!(requests[3], "GET /api/goals/remote-1?after_id=4 HTTP/1.1");
+
+        // A goal a peer forwarded here runs locally even when it matches a peer.
+        let local = runtime
             .process_chat_input(InboundEvent::new(
                 Channel::Webchat,
-                "u1",
-                "/yolo enable abc I_ACCEPT_UNBOUNDED_AUTONOMY",
+                "federation:laptop",
+                "scan workspace",
             ))
-            .expect("web yolo");
-        assert!(web.response.contains("local CLI"));
+            .expect("local");
+        assert!(!local.response.contains("Forwarded"));
     }
 
     #[test]
-    fn yolo_auto_expires() {
+    fn goals_recall_similar_memories_before_planning() {
         let tmp = tempdir().expect("tempdir");
         let workspace = tmp.path().join("ws");
         std::fs::create_dir_all(&workspace).expect("workspace");
-        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
         let config_path = write_test_config(&workspace);
         let db_path = workspace.join("titan.db");
         let store = MemoryStore::open(&db_path).expect("store");
-        let _ = store.get_runtime_risk_state().expect("risk");
-        store.enable_yolo("cli", 15).expect("yolo on");
-        let now_ms = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .expect("clock")
-            .as_millis() as i64;
+        let earlier = Goal::new("scan workspace");
+        store.create_goal(&earlier).expect("goal");
         store
-            .set_yolo_expiry_at_ms(now_ms.saturating_sub(1))
-            .expect("expire");
-        let runtime = TitanGatewayRuntime::new(
-            AutonomyMode::Collaborative,
-            workspace.clone(),
-            db_path.clone(),
-        )
-        .with_config_path(config_path);
-        let outcome = runtime
-            .process_event(InboundEvent::new(
-                Channel::Discord,
-                "u1",
-                "update README with install steps",
-            ))
-            .expect("run");
-        assert!(outcome.pending_approval_id.is_some());
-        let state = MemoryStore::open(&db_path)
-            .expect("store")
-            .get_runtime_risk_state()
-            .expect("risk");
-        assert!(matches!(state.risk_mode, RiskMode::Secure));
+            .add_episodic_memory(&earlier.id, "Scan workspace found a stale lockfile", "run")
+            .expect("memory");
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Autonomous, workspace.clone(), db_path.clone())
+                .with_config_path(config_path);
+
+        let out = runtime
+            .process_chat_input(InboundEvent::new(Channel::Webchat, "u1", "scan workspace"))
+            .expect("goal");
+        let goal_id = out
+            .response
+            .strip_prefix("goal=")
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("goal id");
+        let recalled = store
+            .get_traces(goal_id)
+            .expect("traces")
+            .into_iter()
+            .find(|trace| trace.event_type == "memory_recalled")
+            .expect("memory_recalled trace");
+        assert!(
+            recalled.detail.contains("stale lockfile"),
+            "{}",
+            recalled.detail
+        );
+    }
+
+    #[derive(Default)]
+    struct RecordingSink(std::sync::Mutex<Vec<RunProgress>>);
+
+    impl ProgressSink for RecordingSink {
+        fn emit(&self, progress: RunProgress) {
+            self.0.lock().expect("sink").push(progress);
+        }
     }
 
     #[test]
-    fn traces_include_risk_mode() {
+    fn progress_sink_sees_plan_steps_and_traces_in_order() {
+        let tmp = tempdir().expect("tempdir");
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        let config_path = write_test_config(&workspace);
+        let db_path = workspace.join("titan.db");
+        let sink = Arc::new(RecordingSink::default());
+        let runtime =
+            TitanGatewayRuntime::new(AutonomyMode::Autonomous, workspace.clone(), db_path.clone())
+                .with_config_path(config_path)
+                .with_progress_sink(sink.clone());
+
+        runtime
+            .process_chat_input(InboundEvent::new(Channel::Webchat, "u1", "scan workspace"))
+            .expect("goal");
+        let events = sink.0.lock().expect("sink");
+        let kinds: Vec<&str> = events.iter().map(RunProgress::kind).collect();
+        assert_eq!(kinds.first(), Some(&"plan"));
+        let started = kinds.iter().position(|kind| *kind == "step_started");
+        let finished = kinds.iter().position(|kind| *kind == "step_finished");
+        let first_trace = kinds.iter().position(|kind| *kind == "trace");
+        assert!(
+            started.is_some() && started < finished && finished < first_trace,
+            "{kinds:?}"
+        );
+        assert!(events.iter().any(|event| matches!(
+            event,
+            RunProgress::Trace { event_type, .. } if event_type == "goal_submitted"
+        )));
+    }
+
+    #[test]
+    fn suspicious_access_is_logged_flagged_and_can_lock_the_runtime() {
         let tmp = tempdir().expect("tempdir");
         let workspace = tmp.path().join("ws");
         std::fs::create_dir_all(&workspace).expect("workspace");
-        std::fs::write(workspace.join("README.md"), "seed").expect("seed readme");
         let config_path = write_test_config(&workspace);
+        let mut cfg = TitanConfig::load(&config_path).expect("config");
+        cfg.chat.operators = vec!["ops".to_string()];
+        cfg.access.burst_limit = 3;
+        cfg.save(&config_path).expect("save config");
         let db_path = workspace.join("titan.db");
         let runtime = TitanGatewayRuntime::new(
             AutonomyMode::Collaborative,
             workspace.clone(),
             db_path.clone(),
         )
-        .with_config_path(config_path);
-        let outcome = runtime
-            .process_event(InboundEvent::new(Channel::Discord, "u1", "scan workspace"))
-            .expect("run");
-        let traces = MemoryStore::open(&db_path)
-            .expect("store")
-            .get_traces(&outcome.goal_id)
-            .expect("traces");
-        assert!(!traces.is_empty());
+        .with_config_path(config_path.clone());
+        let send = |actor: &str, text: &str| {
+            runtime
+                .process_chat_input(
+                    InboundEvent::new(Channel::Webchat, actor, text)
+                        .with_remote_addr("203.0.113.7"),
+                )
+                .expect("chat")
+        };
+
+        let store = MemoryStore::open(&db_path).expect("store");
+        let approval = store
+            .create_approval_request_for_goal(
+                None,
+                "write_file",
+                "write",
+                "notes.txt::x",
+                None,
+                60_000,
+            )
+            .expect("approval");
+        send("ops", &format!("/deny {}", approval.id));
         assert!(
-            traces
-                .iter()
-                .all(|trace| !trace.risk_mode.trim().is_empty())
+            send("bystander", "/deny missing")
+                .response
+                .starts_with("permission_denied")
+        );
+        for _ in 0..4 {
+            send("chatty", "/help");
+        }
+
+        let log = store.list_access_log(20, false).expect("log");
+        assert_eq!(log.len(), 6);
+        assert!(
+            log.iter().all(|entry| entry.kind == "command"
+                && entry.remote_addr.as_deref() == Some("203.0.113.7"))
+        );
+        let flagged: Vec<(String, String, Option<String>)> = store
+            .list_access_log(20, true)
+            .expect("anomalies")
+            .into_iter()
+            .map(|entry| (entry.actor_id, entry.outcome, entry.anomaly))
+            .collect();
+        assert_eq!(
+            flagged,
+            vec![
+                (
+                    "chatty".to_string(),
+                    "ok".to_string(),
+                    Some("burst".to_string())
+                ),
+                (
+                    "bystander".to_string(),
+                    "denied".to_string(),
+                    Some("unauthorized_approval".to_string())
+                ),
+                (
+                    "ops".to_string(),
+                    "ok".to_string(),
+                    Some("new_actor_approval".to_string())
+                ),
+            ]
+        );
+        assert!(!store.safe_mode_state().expect("safe mode").active);
+
+        cfg.access.lock_on_anomaly = true;
+        cfg.save(&config_path).expect("save config");
+        send("bystander", "/approve missing");
+        let safe_mode = store.safe_mode_state().expect("safe mode");
+        assert!(safe_mode.active);
+        assert_eq!(safe_mode.changed_by.as_deref(), Some("access_anomaly"));
+        assert_eq!(
+            runtime.effective_mode(&store).expect("mode"),
+            AutonomyMode::Supervised
         );
     }
 }
@@ -0,0 +1,306 @@
+use std::cell::{Cell, RefCell};
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use reqwest::blocking::Client;
+use serde_json::{Value, json};
+use titan_common::{ModelConfig, ModelProvider};
+use titan_core::ModelClient;
+use titan_memory::{MemoryStore, model_cache_key};
+
+/// Longest a single planning request may take before the run falls back to keyword plans.
+const MODEL_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+const ANTHROPIC_MAX_TOKENS: u32 = 2048;
+
+/// Starts the error of a request that never got an answer from the provider: a connection
+/// failure, a timeout, a 5xx, or a 429. These, unlike unusable replies, count as an outage.
+pub const MODEL_UNREACHABLE: &str = "model provider unreachable";
+
+/// Completions from the provider in `TitanConfig.model`: Ollama's `/api/generate`,
+/// OpenAI's or a custom OpenAI-compatible `/chat/completions`, or Anthropic's `/v1/messages`.
+#[derive(Debug, Clone)]
+pub struct ProviderModelClient {
+    config: ModelConfig,
+}
+
+impl ProviderModelClient {
+    pub fn new(config: &ModelConfig) -> Self {
+        Self {
+            config: config.clone(),
+        }
+    }
+
+    /// What besides the prompt decides a reply: provider, model, endpoint, and request
+    /// parameters. Keys the response cache.
+    pub fn cache_identity(config: &ModelConfig) -> String {
+        format!(
+            "{}|{}|{}|max_tokens={ANTHROPIC_MAX_TOKENS}",
+            config.provider.as_str(),
+            config.model_id,
+            config.endpoint.as_deref().unwrap_or_default()
+        )
+    }
+
+    fn api_key(&self) -> Result<Option<String>> {
+        let env = self
+            .config
+            .api_key_env
+            .clone()
+            .or(match self.config.provider {
+                ModelProvider::OpenAi => Some("OPENAI_API_KEY".to_string()),
+                ModelProvider::Anthropic => Some("ANTHROPIC_API_KEY".to_string()),
+                _ => None,
+            });
+        match env {
+            Some(env) => std::env::var(&env)
+                .map(Some)
+                .with_context(|| format!("model key {env} is not set")),
+            None => Ok(None),
+        }
+    }
+
+    fn request(&self, prompt: &str) -> Result<String> {
+        let endpoint = self
+            .config
+            .endpoint
+            .as_deref()
+            .map(|e| e.trim_end_matches('/'));
+        let model = &self.config.model_id;
+        let key = self.api_key()?;
+        match self.config.provider {
+            ModelProvider::Ollama => {
+                let url = format!(
+                    "{}/api/generate",
+                    endpoint.unwrap_or("http://127.0.0.1:11434")
+                );
+                let body =
+                    json!({"model": model, "prompt": prompt, "format": "json", "stream": false});
+                reply_text(post_json(&url, &[], key.as_deref(), &body)?, "/response")
+            }
+            ModelProvider::OpenAi | ModelProvider::Custom => {
+                let base = match (&self.config.provider, endpoint) {
+                    (_, Some(endpoint)) => endpoint,
+                    (ModelProvider::OpenAi, None) => "https://api.openai.com/v1",
+                    _ => bail!("model.endpoint is required for the custom provider"),
+                };
+                let body = json!({
+                    "model": model,
+                    "messages": [{"role": "user", "content": prompt}],
+                });
+                let url = format!("{base}/chat/completions");
+                reply_text(
+                    post_json(&url, &[], key.as_deref(), &body)?,
+                    "/choices/0/message/content",
+                )
+            }
+            ModelProvider::Anthropic => {
+                let key = key.ok_or_else(|| anyhow!("anthropic requires an api key"))?;
+                let url = format!(
+                    "{}/v1/messages",
+                    endpoint.unwrap_or("https://api.anthropic.com")
+                );
+                let body = json!({
+                    "model": model,
+                    "max_tokens": ANTHROPIC_MAX_TOKENS,
+                    "messages": [{"role": "user", "content": prompt}],
+                });
+                let headers = [
+                    ("x-api-key", key.as_str()),
+                    ("anthropic-version", ANTHROPIC_VERSION),
+                ];
+                reply_text(post_json(&url, &headers, None, &body)?, "/content/0/text")
+            }
+        }
+    }
+}
+
+impl ModelClient for ProviderModelClient {
+    fn complete(&self, prompt: &str) -> Result<String, String> {
+        self.request(prompt).map_err(|err| format!("{err:#}"))
+    }
+}
+
+/// Remembers the last reply of the client it wraps, so the gateway can store it with the goal.
+pub struct RecordingModelClient<'a> {
+    inner: &'a dyn ModelClient,
+    reply: RefCell<Option<String>>,
+}
+
+impl<'a> RecordingModelClient<'a> {
+    pub fn new(inner: &'a dyn ModelClient) -> Self {
+        Self {
+            inner,
+            reply: RefCell::new(None),
+        }
+    }
+
+    pub fn reply(&self) -> Option<String> {
+        self.reply.borrow().clone()
+    }
+}
+
+impl ModelClient for RecordingModelClient<'_> {
+    fn complete(&self, prompt: &str) -> Result<String, String> {
+        let reply = self.inner.complete(prompt)?;
+        *self.reply.borrow_mut() = Some(reply.clone());
+        Ok(reply)
+    }
+}
+
+/// Answers repeated prompts from the store's model response cache while the cached reply is
+/// fresh, and counts the calls it saw and how many the cache answered.
+pub struct CachingModelClient<'a> {
+    inner: &'a dyn ModelClient,
+    store: &'a MemoryStore,
+    identity: String,
+    ttl_ms: i64,
+    calls: Cell<u64>,
+    hits: Cell<u64>,
+}
+
+impl<'a> CachingModelClient<'a> {
+    /// `ttl_secs` of `None` passes every prompt through, still counting calls.
+    pub fn new(
+        inner: &'a dyn ModelClient,
+        store: &'a MemoryStore,
+        identity: String,
+        ttl_secs: Option<u64>,
+    ) -> Self {
+        Self {
+            inner,
+            store,
+            identity,
+            ttl_ms: ttl_secs.map_or(0, |secs| secs as i64 * 1000),
+            calls: Cell::new(0),
+            hits: Cell::new(0),
+        }
+    }
+
+    /// `(calls, cache_hits)` so far.
+    pub fn stats(&self) -> (u64, u64) {
+        (self.calls.get(), self.hits.get())
+    }
+}
+
+impl ModelClient for CachingModelClient<'_> {
+    fn complete(&self, prompt: &str) -> Result<String, String> {
+        self.calls.set(self.calls.get() + 1);
+        if self.ttl_ms == 0 {
+            return self.inner.complete(prompt);
+        }
+        let key = model_cache_key(&self.identity, prompt);
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        // A broken cache never blocks the call it would have saved.
+        if let Ok(Some(reply)) = self.store.cached_model_response(&key, now_ms) {
+            self.hits.set(self.hits.get() + 1);
+            return Ok(reply);
+        }
+        let reply = self.inner.complete(prompt)?;
+        let _ = self.store.cache_model_response(
+            &key,
+            &self.identity,
+            &reply,
+            now_ms,
+            now_ms + self.ttl_ms,
+        );
+        Ok(reply)
+    }
+}
+
+/// Reports the provider's reachability to the store: [`MODEL_UNREACHABLE`] errors count
+/// toward degraded mode, and any reply ends it.
+pub struct HealthTrackingModelClient<'a> {
+    inner: &'a dyn ModelClient,
+    store: &'a MemoryStore,
+    threshold: u32,
+}
+
+impl<'a> HealthTrackingModelClient<'a> {
+    pub fn new(inner: &'a dyn ModelClient, store: &'a MemoryStore, threshold: u32) -> Self {
+        Self {
+            inner,
+            store,
+            threshold,
+        }
+    }
+}
+
+impl ModelClient for HealthTrackingModelClient<'_> {
+    fn complete(&self, prompt: &str) -> Result<String, String> {
+        // Bookkeeping failures never turn a reply into an error.
+        match self.inner.complete(prompt) {
+            Ok(reply) => {
+                let _ = self.store.record_model_recovery();
+                Ok(reply)
+            }
+            Err(err) => {
+                if err.starts_with(MODEL_UNREACHABLE) {
+                    let now_ms = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map(|d| d.as_millis() as i64)
+                        .unwrap_or(0);
+                    let _ = self.store.record_model_outage(&err, self.threshold, now_ms);
+                }
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Answers with a stored reply, so a resumed goal gets the candidates it first ran with.
+pub struct ReplayModelClient(pub String);
+
+impl ModelClient for ReplayModelClient {
+    fn complete(&self, _prompt: &str) -> Result<String, String> {
+        Ok(self.0.clone())
+    }
+}
+
+fn reply_text(body: Value, pointer: &str) -> Result<String> {
+    body.pointer(pointer)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("model response has no text at {pointer}"))
+}
+
+// The blocking client cannot run on a tokio worker, and the gateway plans from one.
+fn post_json(
+    url: &str,
+    headers: &[(&str, &str)],
+    bearer: Option<&str>,
+    body: &Value,
+) -> Result<Value> {
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| -> Result<Value> {
+                let mut request = Client::builder()
+                    .timeout(MODEL_REQUEST_TIMEOUT)
+                    .build()?
+                    .post(url)
+                    .json(body);
+                for (name, value) in headers {
+                    request = request.header(*name, *value);
+                }
+                if let Some(token) = bearer {
+                    request = request.bearer_auth(token);
+                }
+                let response = request.send().map_err(|err| {
+                    anyhow!("{MODEL_UNREACHABLE}: request to {url} failed: {err}")
+                })?;
+                let status = response.status();
+                if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    bail!("{MODEL_UNREACHABLE}: {status}");
+                }
+                if !status.is_success() {
+                    bail!("model request failed: {status}");
+                }
+                Ok(response.json()?)
+            })
+            .join()
+            .unwrap_or_else(|_| bail!("model request thread panicked"))
+    })
+}
@@ -0,0 +1,205 @@
+use anyhow::{Result, anyhow};
+use titan_common::AutonomyMode;
+use titan_core::TaskPlan;
+use titan_memory::RiskMode;
+use titan_tools::ToolRegistry;
+
+use crate::commands::mode_name;
+use crate::{intent_label, step_permission_for_class, step_requires_approval};
+
+/// Step inputs in a rendered preview are cut to this many characters.
+const PREVIEW_INPUT_CHARS: usize = 80;
+
+/// A request planned without running it, for `/plan` and `POST /api/plan`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanPreview {
+    pub intent: String,
+    pub mode: String,
+    pub risk_mode: String,
+    pub selected_candidate_id: String,
+    pub candidates: Vec<PlanPreviewCandidate>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanPreviewCandidate {
+    pub id: String,
+    pub rationale: String,
+    pub score: f32,
+    pub estimated_tokens: u64,
+    pub steps: Vec<PlanPreviewStep>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanPreviewStep {
+    pub step_id: String,
+    pub tool_name: String,
+    /// The tool's registered capability, which is what policy checks, else the planned one.
+    pub permission: String,
+    pub input: Option<String>,
+    /// Policy would hold this step for approval under the current mode and risk state.
+    pub needs_approval: bool,
+}
+
+impl PlanPreview {
+    pub(crate) fn new(
+        plan: &TaskPlan,
+        registry: &ToolRegistry,
+        mode: &AutonomyMode,
+        risk_mode: &RiskMode,
+    ) -> Result<Self> {
+        let selected = plan
+            .candidates
+            .get(plan.selected_index)
+            .ok_or_else(|| anyhow!("plan has no candidate {}", plan.selected_index))?;
+        let candidates = plan
+            .candidates
+            .iter()
+            .map(|candidate| PlanPreviewCandidate {
+                id: candidate.id.clone(),
+                rationale: candidate.rationale.clone(),
+                score: candidate.score,
+                estimated_tokens: candidate.estimated_tokens,
+                steps: candidate
+                    .steps
+                    .iter()
+                    .map(|step| {
+                        let permission = registry
+                            .get(&step.tool_name)
+                            .map(|tool| step_permission_for_class(tool.class))
+                            .unwrap_or(step.permission);
+                        PlanPreviewStep {
+                            step_id: step.id.clone(),
+                            tool_name: step.tool_name.clone(),
+                            permission: permission.as_str().to_string(),
+                            input: step.input.clone(),
+                            needs_approval: step_requires_approval(
+                                mode,
+                                risk_mode,
+                                &step.tool_name,
+                                permission,
+                            ),
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+        Ok(Self {
+            intent: intent_label(&plan.intent),
+            mode: mode_name(mode).to_string(),
+            risk_mode: risk_mode.as_str().to_string(),
+            selected_candidate_id: selected.id.clone(),
+            candidates,
+        })
+    }
+
+    /// The candidate that would run; `None` only if `selected_candidate_id` names none of them.
+    pub fn selected(&self) -> Option<&PlanPreviewCandidate> {
+        self.candidates
+            .iter()
+            .find(|candidate| candidate.id == self.selected_candidate_id)
+    }
+
+    /// Ids of the selected candidate's steps that would wait for approval.
+    pub fn gated_step_ids(&self) -> Vec<&str> {
+        self.selected()
+            .map(|selected| {
+                selected
+                    .steps
+                    .iter()
+                    .filter(|step| step.needs_approval)
+                    .map(|step| step.step_id.as_str())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The chat reply: the selected candidate's steps, one per line, with gated ones marked.
+    pub fn render(&self, goal_id: &str) -> String {
+        let Some(selected) = self.selected() else {
+            return format!(
+                "goal={goal_id} plan {} not found among {} candidate(s): nothing has run",
+                self.selected_candidate_id,
+                self.candidates.len()
+            );
+        };
+        let mut lines = vec![
+            format!(
+                "goal={goal_id} plan {} (intent={} mode={} risk={}): nothing has run",
+                selected.id, self.intent, self.mode, self.risk_mode
+            ),
+            selected.rationale.clone(),
+        ];
+        for (index, step) in selected.steps.iter().enumerate() {
+            // One line per step, so a multi-line write shows its newlines escaped.
+            let input = step
+                .input
+                .as_deref()
+                .unwrap_or_default()
+                .replace('\n', "\\n");
+            let input = if input.chars().count() > PREVIEW_INPUT_CHARS {
+                format!(
+                    "{}...",
+                    input.chars().take(PREVIEW_INPUT_CHARS).collect::<String>()
+                )
+            } else {
+                input
+            };
+            lines.push(format!(
+                "{}. {} [{}] {input}{}",
+                index + 1,
+                step.tool_name,
+                step.permission,
+                if step.needs_approval {
+                    " - needs approval"
+                } else {
+                    ""
+                }
+            ));
+        }
+        lines.push(format!(
+            "{} of {} step(s) would wait for approval. Send the request without /plan to run it.",
+            self.gated_step_ids().len(),
+            selected.steps.len()
+        ));
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preview_without_its_selected_candidate_renders_instead_of_panicking() {
+        let preview = PlanPreview {
+            intent: "generic_recon".to_string(),
+            mode: "collaborative".to_string(),
+            risk_mode: "secure".to_string(),
+            selected_candidate_id: "candidate-1".to_string(),
+            candidates: Vec::new(),
+        };
+        assert!(preview.selected().is_none());
+        assert!(preview.gated_step_ids().is_empty());
+        assert_eq!(
+            preview.render("g1"),
+            "goal=g1 plan candidate-1 not found among 0 candidate(s): nothing has run"
+        );
+
+        let empty = TaskPlan {
+            intent: titan_core::GoalIntent::GenericRecon,
+            candidates: Vec::new(),
+            selected_index: 0,
+            traces: Vec::new(),
+            within_budget: true,
+        };
+        assert!(
+            PlanPreview::new(
+                &empty,
+                &ToolRegistry::with_defaults(),
+                &AutonomyMode::Collaborative,
+                &RiskMode::Secure,
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use anyhow::Result;
+use titan_memory::{MemoryStore, peer_memory_namespace};
+
+/// Most repos kept in the `repos` fact, newest first.
+const MAX_REMEMBERED_REPOS: usize = 5;
+
+/// Facts `text` states about its author: `timezone` ("my timezone is ..."), `preference`
+/// ("I prefer ..."), and `repos` (GitHub URLs or "repo owner/name").
+pub fn extract_peer_facts(text: &str) -> Vec<(&'static str, String)> {
+    let lower = text.to_ascii_lowercase();
+    let mut facts = Vec::new();
+    for marker in ["my timezone is ", "my time zone is "] {
+        if let Some(start) = lower.find(marker)
+            && let Some(zone) = text[start + marker.len()..]
+                .split_whitespace()
+                .next()
+                .map(|word| word.trim_end_matches(['.', ',', '!', '?', ';']))
+                .filter(|word| !word.is_empty())
+        {
+            facts.push(("timezone", zone.to_string()));
+            break;
+        }
+    }
+    if let Some(start) = lower.find("i prefer ") {
+        let rest = &text[start + "i prefer ".len()..];
+        let end = rest.find(['.', '!', '?', '\n']).unwrap_or(rest.len());
+        let preference = rest[..end].trim();
+        if !preference.is_empty() {
+            facts.push(("preference", preference.to_string()));
+        }
+    }
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let mut repos: Vec<String> = Vec::new();
+    for (index, word) in words.iter().enumerate() {
+        let candidate = match word.find("github.com/") {
+            Some(at) => Some(&word[at + "github.com/".len()..]),
+            None if index > 0 && words[index - 1].eq_ignore_ascii_case("repo") => Some(*word),
+            None => None,
+        };
+        if let Some(repo) = candidate.and_then(owner_repo)
+            && !repos.contains(&repo)
+        {
+            repos.push(repo);
+        }
+    }
+    if !repos.is_empty() {
+        facts.push(("repos", repos.join(",")));
+    }
+    facts
+}
+
+/// `owner/name` from the start of `path`, dropping any `.git` suffix or deeper path.
+fn owner_repo(path: &str) -> Option<String> {
+    let mut parts = path.split('/');
+    let owner = parts.next()?;
+    let name = parts
+        .next()?
+        .trim_end_matches(['.', ',', '!', '?', ';', ')'])
+        .trim_end_matches(".git");
+    let valid = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+    (valid(owner) && valid(name)).then(|| format!("{owner}/{name}"))
+}
+
+/// Stores `facts` for the peer. New repos are merged in front of the ones already kept.
+pub fn remember_peer_facts(
+    store: &MemoryStore,
+    channel: &str,
+    peer_id: &str,
+    facts: &[(&str, String)],
+    source: &str,
+) -> Result<()> {
+    let namespace = peer_memory_namespace(channel, peer_id);
+    for (key, value) in facts {
+        let value = if *key == "repos" {
+            let known = store
+                .get_semantic_fact(&namespace, "repos")?
+                .unwrap_or_default();
+            let mut repos: Vec<&str> = value.split(',').collect();
+            for repo in known.split(',').filter(|repo| !repo.is_empty()) {
+                if !repos.contains(&repo) {
+                    repos.push(repo);
+                }
+            }
+            repos.truncate(MAX_REMEMBERED_REPOS);
+            repos.join(",")
+        } else {
+            value.clone()
+        };
+        if store.get_semantic_fact(&namespace, key)?.as_deref() != Some(value.as_str()) {
+            store.upsert_semantic_fact(&namespace, key, &value, source)?;
+        }
+    }
+    Ok(())
+}
+
+/// The planner context for a goal from this peer: empty unless they opted in. New requests
+/// (`capture`) first add whatever facts they state.
+pub fn peer_context(
+    store: &MemoryStore,
+    channel: &str,
+    peer_id: &str,
+    text: &str,
+    capture: bool,
+) -> Result<Vec<String>> {
+    if !store.peer_memory_enabled(channel, peer_id)? {
+        return Ok(Vec::new());
+    }
+    if capture {
+        let facts = extract_peer_facts(text);
+        remember_peer_facts(store, channel, peer_id, &facts, "conversation")?;
+    }
+    Ok(store
+        .peer_facts(channel, peer_id)?
+        .into_iter()
+        .map(|(key, value)| format!("{key}: {value}"))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_timezone_preference_and_repos() {
+        let facts = extract_peer_facts(
+            "My timezone is Europe/Berlin. I prefer short answers! Check \
+             https://github.com/acme/titan.git and repo acme/docs, not docs/README.md",
+        );
+        assert_eq!(
+            facts,
+            vec![
+                ("timezone", "Europe/Berlin".to_string()),
+                ("preference", "short answers".to_string()),
+                ("repos", "acme/titan,acme/docs".to_string()),
+            ]
+        );
+        assert!(extract_peer_facts("scan workspace").is_empty());
+    }
+}
@@ -0,0 +1,88 @@
+use serde::Serialize;
+use titan_core::{TaskPlan, TraceEvent};
+
+/// A step of a goal run reported while the run is still going, so a client can show live
+/// progress instead of polling for the finished goal.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RunProgress {
+    /// The planner's candidates, sent before any step runs.
+    Plan {
+        goal_id: String,
+        selected_index: usize,
+        candidates: Vec<CandidateSummary>,
+    },
+    StepStarted {
+        goal_id: String,
+        step_id: String,
+        tool_name: String,
+    },
+    /// `status` is the tool's status, or `error` when the step could not run.
+    StepFinished {
+        goal_id: String,
+        step_id: String,
+        tool_name: String,
+        status: String,
+    },
+    /// A trace of the run, sent once the run is persisted.
+    Trace {
+        goal_id: String,
+        event_type: String,
+        detail: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CandidateSummary {
+    pub id: String,
+    pub rationale: String,
+    pub score: f32,
+    pub tools: Vec<String>,
+}
+
+impl RunProgress {
+    pub fn plan(goal_id: &str, plan: &TaskPlan) -> Self {
+        Self::Plan {
+            goal_id: goal_id.to_string(),
+            selected_index: plan.selected_index,
+            candidates: plan
+                .candidates
+                .iter()
+                .map(|candidate| CandidateSummary {
+                    id: candidate.id.clone(),
+                    rationale: candidate.rationale.clone(),
+                    score: candidate.score,
+                    tools: candidate
+                        .steps
+                        .iter()
+                        .map(|step| step.tool_name.clone())
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn trace(trace: &TraceEvent) -> Self {
+        Self::Trace {
+            goal_id: trace.goal_id.clone(),
+            event_type: trace.event_type.clone(),
+            detail: trace.detail.clone(),
+        }
+    }
+
+    /// Event name for transports that label messages, e.g. `step_started`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Plan { .. } => "plan",
+            Self::StepStarted { .. } => "step_started",
+            Self::StepFinished { .. } => "step_finished",
+            Self::Trace { .. } => "trace",
+        }
+    }
+}
+
+/// Receives the progress of runs started by a runtime. Called on the thread running the goal,
+/// so implementations should hand events off rather than block.
+pub trait ProgressSink: Send + Sync {
+    fn emit(&self, progress: RunProgress);
+}
@@ -0,0 +1,106 @@
+use anyhow::Result;
+use titan_comms::telegram::{TelegramUpdate, parse_approval_callback};
+
+use crate::{Channel, InboundEvent, TitanGatewayRuntime};
+
+/// What the bot should send back for one update.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelegramReply {
+    pub chat_id: String,
+    pub text: String,
+    /// Pending approval the reply reports; the bot attaches Approve and Deny buttons for it.
+    pub approval_id: Option<String>,
+    /// Outbox row holding a run reply, to mark delivered once the bot has sent it.
+    pub reply_outbox_id: Option<i64>,
+    /// Set for button presses, which are also answered with `text`.
+    pub callback_query_id: Option<String>,
+}
+
+/// Feeds one update to the runtime as a [`Channel::Telegram`] event from the sender's user id.
+/// Updates from bots, without text, or from users outside `allowed_user_ids` produce no reply.
+/// Approval button presses run `/approve` or `/deny`, so they take the same role checks and
+/// resolution path as typed commands.
+pub fn route_telegram_update(
+    runtime: &TitanGatewayRuntime,
+    allowed_user_ids: &[String],
+    update: &TelegramUpdate,
+) -> Result<Option<TelegramReply>> {
+    let allowed = |user_id: i64| {
+        let user_id = user_id.to_string();
+        allowed_user_ids.iter().any(|id| id.trim() == user_id)
+    };
+    if let Some(query) = &update.callback_query {
+        if query.from.is_bot || !allowed(query.from.id) {
+            return Ok(None);
+        }
+        let Some((approve, approval_id)) = query.data.as_deref().and_then(parse_approval_callback)
+        else {
+            return Ok(None);
+        };
+        let command = format!(
+            "/{} {approval_id}",
+            if approve { "approve" } else { "deny" }
+        );
+        let result = runtime.process_chat_input(InboundEvent::new(
+            Channel::Telegram,
+            query.from.id.to_string(),
+            command,
+        ))?;
+        // A private chat's id is the user's id, so that is where a detached press replies.
+        let chat_id = query
+            .message
+            .as_ref()
+            .map_or(query.from.id, |message| message.chat.id);
+        return Ok(Some(TelegramReply {
+            chat_id: chat_id.to_string(),
+            text: result.response,
+            approval_id: None,
+            reply_outbox_id: None,
+            callback_query_id: Some(query.id.clone()),
+        }));
+    }
+
+    let Some(message) = &update.message else {
+        return Ok(None);
+    };
+    let Some(from) = message.from.as_ref().filter(|from| !from.is_bot) else {
+        return Ok(None);
+    };
+    let text = message.text.as_deref().map(str::trim).unwrap_or_default();
+    if text.is_empty() || !allowed(from.id) {
+        return Ok(None);
+    }
+    let chat_id = message.chat.id.to_string();
+    let result = runtime.process_chat_input(
+        InboundEvent::new(
+            Channel::Telegram,
+            from.id.to_string(),
+            strip_bot_mention(text),
+        )
+        .with_reply_target(chat_id.clone()),
+    )?;
+    let approval_id = result.response.split_whitespace().find_map(|word| {
+        word.strip_prefix("approval_pending=")
+            .or_else(|| word.strip_prefix("approval_id="))
+            .map(str::to_string)
+    });
+    Ok(Some(TelegramReply {
+        chat_id,
+        text: result.response,
+        approval_id,
+        reply_outbox_id: result.reply_outbox_id,
+        callback_query_id: None,
+    }))
+}
+
+// Group chats send commands as `/status@titan_bot`.
+fn strip_bot_mention(text: &str) -> String {
+    match text.split_once(char::is_whitespace) {
+        Some((head, rest)) if head.starts_with('/') => match head.split_once('@') {
+            Some((command, _)) => format!("{command} {rest}"),
+            None => text.to_string(),
+        },
+        _ if text.starts_with('/') => text.split('@').next().unwrap_or(text).to_string(),
+        _ => text.to_string(),
+    }
+}
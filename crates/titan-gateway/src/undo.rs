@@ -0,0 +1,70 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+/// Contents of a goal's write targets before its steps ran, so a failed verify step can put
+/// them back. Only `write_file` targets are journaled; exec side effects cannot be undone.
+#[derive(Debug, Clone, Default)]
+pub struct UndoJournal {
+    entries: Vec<(String, PathBuf, Option<Vec<u8>>)>,
+}
+
+impl UndoJournal {
+    pub fn capture(root: &Path, targets: &[String]) -> Self {
+        let mut entries: Vec<(String, PathBuf, Option<Vec<u8>>)> = Vec::new();
+        for target in targets {
+            if entries.iter().any(|(name, _, _)| name == target) {
+                continue;
+            }
+            let path = root.join(target);
+            let before = fs::read(&path).ok();
+            entries.push((target.clone(), path, before));
+        }
+        Self { entries }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Restores every journaled target, deleting files the goal created. Returns the targets
+    /// that were restored.
+    pub fn restore(&self) -> Result<Vec<String>> {
+        let mut restored = Vec::new();
+        for (target, path, before) in &self.entries {
+            match before {
+                Some(bytes) => fs::write(path, bytes)?,
+                None if path.exists() => fs::remove_file(path)?,
+                None => continue,
+            }
+            restored.push(target.clone());
+        }
+        Ok(restored)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn restores_edited_targets_and_removes_created_ones() {
+        let tmp = tempdir().expect("tempdir");
+        fs::write(tmp.path().join("README.md"), "original\n").expect("seed");
+        let journal =
+            UndoJournal::capture(tmp.path(), &["README.md".to_string(), "new.md".to_string()]);
+        fs::write(tmp.path().join("README.md"), "broken\n").expect("edit");
+        fs::write(tmp.path().join("new.md"), "created\n").expect("create");
+
+        let restored = journal.restore().expect("restore");
+
+        assert_eq!(restored, vec!["README.md", "new.md"]);
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("README.md")).expect("read"),
+            "original\n"
+        );
+        assert!(!tmp.path().join("new.md").exists());
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use titan_common::ModelConfig;
+use titan_memory::{MemoryStore, WorkspaceChangeRecord};
+
+/// One dirty path as git reports it: porcelain status code plus a numstat summary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitPathState {
+    pub change_kind: String,
+    pub diff_stat: String,
+}
+
+/// `git status` of the workspace before a goal's write/exec steps, used to attribute what
+/// changed afterwards. Empty outside a git work tree.
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceSnapshot {
+    paths: Option<BTreeMap<String, GitPathState>>,
+}
+
+impl WorkspaceSnapshot {
+    pub fn capture(root: &Path) -> Self {
+        Self {
+            paths: git_dirty_paths(root),
+        }
+    }
+
+    pub fn is_git(&self) -> bool {
+        self.paths.is_some()
+    }
+
+    pub fn dirty_paths(&self) -> Vec<String> {
+        self.paths
+            .as_ref()
+            .map(|paths| paths.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Records every path whose git state changed since this snapshot against `goal_id`, and
+    /// marks earlier changes merged once their path is clean again.
+    pub fn attribute(
+        &self,
+        store: &MemoryStore,
+        root: &Path,
+        goal_id: &str,
+        tool_name: &str,
+    ) -> Result<Vec<String>> {
+        let Some(before) = &self.paths else {
+            return Ok(Vec::new());
+        };
+        let after = git_dirty_paths(root).unwrap_or_default();
+        let mut changed = Vec::new();
+        for (path, state) in &after {
+            if before.get(path) == Some(state) {
+                continue;
+            }
+            store.record_workspace_change(
+                goal_id,
+                tool_name,
+                path,
+                &state.change_kind,
+                &state.diff_stat,
+            )?;
+            changed.push(path.clone());
+        }
+        store.mark_workspace_changes_merged(&after.keys().cloned().collect::<Vec<_>>())?;
+        Ok(changed)
+    }
+}
+
+/// What a goal's written files are stamped with: the model configured for the run and a
+/// hash of the request, so the prompt itself is not kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentProvenance {
+    pub model: String,
+    pub prompt_hash: String,
+}
+
+impl ContentProvenance {
+    pub fn new(model: &ModelConfig, prompt: &str) -> Self {
+        let prompt_hash = Sha256::digest(prompt.trim().as_bytes())
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        Self {
+            model: format!("{}:{}", model.provider.as_str(), model.model_id),
+            prompt_hash,
+        }
+    }
+
+    /// Records each of `paths` once as written by `goal_id`. Returns the paths recorded.
+    pub fn record(
+        &self,
+        store: &MemoryStore,
+        goal_id: &str,
+        tool_name: &str,
+        paths: &[String],
+    ) -> Result<Vec<String>> {
+        let mut recorded: Vec<String> = Vec::new();
+        for path in paths {
+            let path = normalize_path(path);
+            if path.is_empty() || is_titan_state(&path) || recorded.contains(&path) {
+                continue;
+            }
+            store.record_content_provenance(
+                goal_id,
+                tool_name,
+                &path,
+                &self.model,
+                &self.prompt_hash,
+            )?;
+            recorded.push(path);
+        }
+        Ok(recorded)
+    }
+}
+
+/// Unmerged changes by other goals to any of `paths`, after settling which earlier changes
+/// have since been committed.
+pub fn unmerged_overlaps(
+    store: &MemoryStore,
+    snapshot: &WorkspaceSnapshot,
+    paths: &[String],
+) -> Result<Vec<WorkspaceChangeRecord>> {
+    if !snapshot.is_git() {
+        return Ok(Vec::new());
+    }
+    store.mark_workspace_changes_merged(&snapshot.dirty_paths())?;
+    let mut overlaps = Vec::new();
+    for path in paths {
+        overlaps.extend(store.unmerged_workspace_changes(&normalize_path(path))?);
+    }
+    Ok(overlaps)
+}
+
+fn normalize_path(path: &str) -> String {
+    path.trim().trim_start_matches("./").to_string()
+}
+
+// TITAN's own state lives in the workspace and changes on every run.
+fn is_titan_state(path: &str) -> bool {
+    path.starts_with(".titan/") || path.starts_with("titan.db")
+}
+
+fn git(root: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn git_dirty_paths(root: &Path) -> Option<BTreeMap<String, GitPathState>> {
+    // Porcelain paths are relative to the repository root, which may sit above the workspace.
+    let prefix = git(root, &["rev-parse", "--show-prefix"])?
+        .trim()
+        .to_string();
+    let status = git(
+        root,
+        &[
+            "status",
+            "--porcelain=v1",
+            "-z",
+            "--untracked-files=all",
+            ".",
+        ],
+    )?;
+    let stats = numstat(root);
+
+    let mut paths = BTreeMap::new();
+    let mut entries = status.split('\0').filter(|entry| !entry.is_empty());
+    while let Some(entry) = entries.next() {
+        let (code, path) = entry.split_at(entry.len().min(3));
+        let code = code.trim_end_matches(' ').to_string();
+        if code.starts_with('R') || code.starts_with('C') {
+            // Renames and copies are followed by the source path.
+            let _ = entries.next();
+        }
+        let Some(path) = path.strip_prefix(prefix.as_str()) else {
+            continue;
+        };
+        if is_titan_state(path) {
+            continue;
+        }
+        let diff_stat = if code == "??" {
+            let len = std::fs::metadata(root.join(path)).map_or(0, |meta| meta.len());
+            format!("untracked {len} bytes")
+        } else {
+            stats.get(path).cloned().unwrap_or_default()
+        };
+        paths.insert(
+            path.to_string(),
+            GitPathState {
+                change_kind: code,
+                diff_stat,
+            },
+        );
+    }
+    Some(paths)
+}
+
+fn numstat(root: &Path) -> BTreeMap<String, String> {
+    let Some(raw) = git(root, &["diff", "HEAD", "--numstat", "--relative"])
+        .or_else(|| git(root, &["diff", "--numstat", "--relative"]))
+    else {
+        return BTreeMap::new();
+    };
+    raw.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let added = fields.next()?;
+            let removed = fields.next()?;
+            let path = fields.next()?;
+            Some((path.to_string(), format!("+{added} -{removed}")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn run_git(root: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+            .args(args)
+            .status()
+            .expect("git");
+        assert!(status.success(), "git {args:?}");
+    }
+
+    #[test]
+    fn attributes_new_dirty_paths_and_settles_merges() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path().join("repo");
+        let workspace = root.join("ws");
+        std::fs::create_dir_all(&workspace).expect("workspace");
+        std::fs::write(workspace.join("a.txt"), "one\n").expect("seed");
+        run_git(&root, &["init", "-q"]);
+        run_git(&root, &["add", "."]);
+        run_git(&root, &["commit", "-q", "-m", "seed"]);
+        let store = MemoryStore::open(&workspace.join("titan.db")).expect("store");
+
+        std::fs::write(workspace.join("a.txt"), "one\ntwo\n").expect("edit");
+        let before = WorkspaceSnapshot::capture(&workspace);
+        assert!(before.is_git());
+        std::fs::write(workspace.join("b.txt"), "new").expect("create");
+        let changed = before
+            .attribute(&store, &workspace, "goal-1", "write_file")
+            .expect("attribute");
+        assert_eq!(changed, vec!["b.txt".to_string()]);
+
+        std::fs::write(workspace.join("b.txt"), "newer").expect("rewrite");
+        let before = WorkspaceSnapshot::capture(&workspace);
+        let overlaps =
+            unmerged_overlaps(&store, &before, &["./b.txt".to_string()]).expect("overlaps");
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(overlaps[0].goal_id, "goal-1");
+        assert_eq!(overlaps[0].change_kind, "??");
+
+        run_git(&root, &["add", "ws/b.txt"]);
+        run_git(&root, &["commit", "-q", "-m", "merge b"]);
+        let after_commit = WorkspaceSnapshot::capture(&workspace);
+        assert!(
+            unmerged_overlaps(&store, &after_commit, &["b.txt".to_string()])
+                .expect("overlaps")
+                .is_empty()
+        );
+        let recorded = store.list_workspace_changes("goal-1").expect("changes");
+        assert!(recorded[0].merged_at_ms.is_some());
+    }
+
+    #[test]
+    fn outside_a_git_work_tree_nothing_is_recorded() {
+        let tmp = tempdir().expect("tempdir");
+        let store = MemoryStore::open(&tmp.path().join("titan.db")).expect("store");
+        let snapshot = WorkspaceSnapshot::capture(tmp.path());
+        assert!(!snapshot.is_git());
+        std::fs::write(tmp.path().join("x.txt"), "x").expect("write");
+        assert!(
+            snapshot
+                .attribute(&store, tmp.path(), "goal-1", "write_file")
+                .expect("attribute")
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+
+use tempfile::tempdir;
+use titan_common::{ApprovalsConfig, AutonomyMode, TitanConfig};
+use titan_core::Goal;
+use titan_gateway::{
+    TitanGatewayRuntime, approval_edit_refusal, approval_quorum_shortfall, approver_refusal,
+};
+use titan_memory::{MemoryStore, RiskMode};
+use titan_tools::PolicyEngine;
+
+#[test]
+fn allowlists_and_two_person_secure_record_every_approver() {
+    PolicyEngine::configure_approvals(&ApprovalsConfig {
+        approvers: BTreeMap::from([("write".to_string(), vec!["alice".to_string()])]),
+        two_person_secure: true,
+        ..ApprovalsConfig::default()
+    });
+
+    let tmp = tempdir().expect("tempdir");
+    let db = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db).expect("open store");
+    assert_eq!(
+        store.get_runtime_risk_state().expect("risk").risk_mode,
+        RiskMode::Secure
+    );
+    let mode = AutonomyMode::Collaborative;
+
+    let write = store
+        .create_approval_request("write_file", "write", "notes.md", Some("test"), 60_000)
+        .expect("write approval");
+    assert_eq!(
+        approver_refusal(&store, &write, "mallory").expect("refusal"),
+        Some("approver_not_allowed approver=mallory capability=write".to_string())
+    );
+    assert_eq!(
+        approver_refusal(&store, &write, "alice").expect("allowed"),
+        None
+    );
+
+    let exec = store
+        .create_approval_request("run_command", "exec", "echo hi", Some("test"), 60_000)
+        .expect("exec approval");
+    assert_eq!(
+        approval_quorum_shortfall(&store, &mode, &exec, "alice").expect("first vote"),
+        Some((1, 2)),
+        "secure mode needs a second approver for exec"
+    );
+
+    let config_path = tmp.path().join("config.toml");
+    TitanConfig {
+        workspace_dir: tmp.path().to_path_buf(),
+        ..TitanConfig::default()
+    }
+    .save(&config_path)
+    .expect("save config");
+    let runtime = TitanGatewayRuntime::new(mode.clone(), tmp.path().to_path_buf(), db.clone())
+        .with_config_path(config_path);
+    let status = runtime
+        .resolve_approval(&write.id, true, "mallory", None)
+        .expect("refused");
+    assert!(status.starts_with("approver_not_allowed"), "{status}");
+    let status = runtime
+        .resolve_approval(&exec.id, false, "bob", Some("not today"))
+        .expect("deny");
+    assert!(!status.starts_with("approver_not_allowed"), "{status}");
+
+    let decisions: Vec<(String, String)> = store
+        .approval_decisions(&exec.id)
+        .expect("decisions")
+        .into_iter()
+        .map(|row| (row.approver, row.decision))
+        .collect();
+    assert_eq!(
+        decisions,
+        vec![
+            ("alice".to_string(), "approve".to_string()),
+            ("bob".to_string(), "deny".to_string()),
+        ]
+    );
+    assert!(
+        store
+            .approval_decisions(&write.id)
+            .expect("decisions")
+            .is_empty()
+    );
+
+    // Votes are cast for the input the approvers saw, so a two-person approval takes no edit:
+    // neither from the first voter nor from the one who would complete the quorum.
+    let goal = Goal::new("tidy notes");
+    store.create_goal(&goal).expect("goal");
+    let two_person = store
+        .create_approval_request_for_goal(
+            Some(&goal.id),
+            "run_command",
+            "exec",
+            "echo hi",
+            Some("test"),
+            60_000,
+        )
+        .expect("two-person approval");
+    let status = runtime
+        .resolve_approval_with_input(&two_person.id, true, "alice", None, Some("rm -rf ."))
+        .expect("first voter edits");
+    assert_eq!(status, "edit_not_allowed reason=quorum");
+    assert!(
+        store
+            .approval_decisions(&two_person.id)
+            .expect("decisions")
+            .is_empty(),
+        "a refused edit casts no vote"
+    );
+    let status = runtime
+        .resolve_approval(&two_person.id, true, "alice", None)
+        .expect("first vote");
+    assert_eq!(status, "awaiting_quorum votes=1/2");
+    let status = runtime
+        .resolve_approval_with_input(&two_person.id, true, "bob", None, Some("rm -rf ."))
+        .expect("second voter edits");
+    assert_eq!(status, "edit_not_allowed reason=quorum");
+    let stored = store
+        .get_approval_request(&two_person.id)
+        .expect("get")
+        .expect("approval");
+    assert_eq!(
+        (stored.status.as_str(), stored.input.as_str()),
+        ("pending", "echo hi")
+    );
+
+    let read = store
+        .create_approval_request("read_file", "read", "README.md", Some("test"), 60_000)
+        .expect("read approval");
+    assert_eq!(
+        approval_quorum_shortfall(&store, &mode, &read, "mallory").expect("read vote"),
+        None
+    );
+    PolicyEngine::configure_approvals(&ApprovalsConfig::default());
+}
+
+#[test]
+fn approving_with_an_edited_input_runs_the_edit_and_keeps_the_original() {
+    let tmp = tempdir().expect("tempdir");
+    let db = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db).expect("open store");
+    let config_path = tmp.path().join("config.toml");
+    TitanConfig {
+        workspace_dir: tmp.path().to_path_buf(),
+        ..TitanConfig::default()
+    }
+    .save(&config_path)
+    .expect("save config");
+    let runtime = TitanGatewayRuntime::new(
+        AutonomyMode::Collaborative,
+        tmp.path().to_path_buf(),
+        db.clone(),
+    )
+    .with_config_path(config_path);
+
+    let goal = Goal::new("tidy notes");
+    store.create_goal(&goal).expect("goal");
+    let write = store
+        .create_approval_request_for_goal(
+            Some(&goal.id),
+            "write_file",
+            "write",
+            "notes.md::rm everything",
+            Some("test"),
+            60_000,
+        )
+        .expect("write approval");
+    let status = runtime
+        .resolve_approval_with_input(&write.id, true, "alice", None, Some("notes.md::hello"))
+        .expect("approve with edit");
+    assert!(status.starts_with("approved"), "{status}");
+    assert_eq!(
+        std::fs::read_to_string(tmp.path().join("notes.md")).expect("written"),
+        "hello"
+    );
+    let stored = store
+        .get_approval_request(&write.id)
+        .expect("get")
+        .expect("approval");
+    assert_eq!(stored.input, "notes.md::hello");
+    assert_eq!(
+        stored.original_input.as_deref(),
+        Some("notes.md::rm everything")
+    );
+    let modified: Vec<String> = store
+        .get_traces(&goal.id)
+        .expect("traces")
+        .into_iter()
+        .filter(|event| event.event_type == "approval_modified")
+        .map(|event| event.detail)
+        .collect();
+    assert_eq!(modified.len(), 1);
+    assert!(modified[0].contains("by=alice"), "{}", modified[0]);
+
+    let connector = store
+        .create_approval_request("connector_tool", "net", "{}", Some("test"), 60_000)
+        .expect("connector approval");
+    assert_eq!(
+        approval_edit_refusal(
+            &store,
+            &AutonomyMode::Collaborative,
+            &connector,
+            true,
+            Some("{\"x\":1}")
+        )
+        .expect("refusal"),
+        Some("edit_not_allowed tool=connector_tool".to_string())
+    );
+    let status = runtime
+        .resolve_approval_with_input(&connector.id, false, "alice", None, Some("{}"))
+        .expect("deny with edit");
+    assert_eq!(status, "edit_not_allowed reason=deny_with_input");
+    assert_eq!(
+        store
+            .get_approval_request(&connector.id)
+            .expect("get")
+            .expect("approval")
+            .status,
+        "pending"
+    );
+}
@@ -0,0 +1,70 @@
+use tempfile::tempdir;
+use titan_common::{AutonomyMode, TitanConfig};
+use titan_gateway::{TitanGatewayRuntime, approval_quorum_shortfall};
+use titan_memory::MemoryStore;
+use titan_tools::PolicyEngine;
+
+#[test]
+fn quorum_rule_holds_approval_until_enough_distinct_voters() {
+    let tmp = tempdir().expect("tempdir");
+    let policy = tmp.path().join("policy.toml");
+    std::fs::write(
+        &policy,
+        r#"
+        version = 1
+
+        [[rules]]
+        name = "two-person-exec"
+        capability = "exec"
+        decision = "approve"
+        quorum = 2
+        "#,
+    )
+    .expect("write policy");
+    PolicyEngine::load_policy(&policy).expect("load policy");
+
+    let db = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db).expect("open store");
+    let approval = store
+        .create_approval_request("run_command", "exec", "echo hi", Some("test"), 60_000)
+        .expect("create approval");
+    let mode = AutonomyMode::Collaborative;
+
+    assert_eq!(
+        approval_quorum_shortfall(&store, &mode, &approval, "alice").expect("vote"),
+        Some((1, 2))
+    );
+    assert_eq!(
+        approval_quorum_shortfall(&store, &mode, &approval, "alice").expect("repeat vote"),
+        Some((1, 2))
+    );
+
+    let config_path = tmp.path().join("config.toml");
+    TitanConfig {
+        workspace_dir: tmp.path().to_path_buf(),
+        ..TitanConfig::default()
+    }
+    .save(&config_path)
+    .expect("save config");
+    let runtime = TitanGatewayRuntime::new(mode.clone(), tmp.path().to_path_buf(), db.clone())
+        .with_config_path(config_path);
+    let status = runtime
+        .resolve_approval(&approval.id, true, "bob", None)
+        .expect("resolve");
+    assert!(!status.starts_with("awaiting_quorum"), "{status}");
+    let row = store
+        .get_approval_request(&approval.id)
+        .expect("lookup")
+        .expect("approval exists");
+    assert_eq!(row.status, "approved");
+    assert_eq!(row.resolved_by.as_deref(), Some("bob"));
+
+    let other = store
+        .create_approval_request("read_file", "read", "README.md", Some("test"), 60_000)
+        .expect("create approval");
+    assert_eq!(
+        approval_quorum_shortfall(&store, &mode, &other, "alice").expect("single quorum"),
+        None
+    );
+    PolicyEngine::clear_policy();
+}
@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result, bail};
+use chrono::{Datelike, NaiveDate, TimeZone, Utc};
+use cron::Schedule;
+use rusqlite::params;
+
+use crate::{MemoryStore, parse_interval_ms};
+
+/// Projections stop here per job so a per-second schedule cannot stall the calendar.
+const MAX_PROJECTED_RUNS: usize = 100_000;
+
+/// One UTC day of unattended activity.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ActivityDay {
+    /// `YYYY-MM-DD`.
+    pub date: String,
+    /// Job runs the scheduler is expected to start, projected from now on.
+    pub scheduled: i64,
+    /// Scheduler job runs that started.
+    pub job_runs: i64,
+    /// Connector webhook events that started a goal.
+    pub webhook_triggers: i64,
+    /// Goals that finished `completed`.
+    pub completed: i64,
+    /// Goals that finished `failed`.
+    pub failed: i64,
+}
+
+impl ActivityDay {
+    /// Everything the agent did or will do that day; the heatmap shades by this.
+    pub fn total(&self) -> i64 {
+        self.scheduled + self.job_runs + self.webhook_triggers + self.completed + self.failed
+    }
+}
+
+/// Every day of one month, in order, including the empty ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActivityCalendar {
+    /// `YYYY-MM`.
+    pub month: String,
+    pub days: Vec<ActivityDay>,
+}
+
+/// Parses `YYYY-MM` into its first day.
+pub fn parse_calendar_month(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-01", value.trim()), "%Y-%m-%d")
+        .with_context(|| format!("invalid month (expected YYYY-MM): {value}"))
+}
+
+/// The first day of the UTC month containing `epoch_ms`.
+pub fn calendar_month_at(epoch_ms: i64) -> Result<NaiveDate> {
+    let at = Utc
+        .timestamp_millis_opt(epoch_ms)
+        .single()
+        .with_context(|| format!("invalid timestamp: {epoch_ms}"))?;
+    at.date_naive()
+        .with_day(1)
+        .context("month has no first day")
+}
+
+impl MemoryStore {
+    /// Buckets a month's scheduled job runs, webhook triggers, and finished goals by UTC day.
+    /// Scheduled runs are only projected for the part of the month after `now_ms`.
+    pub fn activity_calendar(&self, month: NaiveDate, now_ms: i64) -> Result<ActivityCalendar> {
+        let first = month.with_day(1).context("month has no first day")?;
+        let next = first
+            .checked_add_months(chrono::Months::new(1))
+            .context("month out of range")?;
+        let start_ms = day_start_ms(first);
+        let end_ms = day_start_ms(next);
+        let mut days: BTreeMap<String, ActivityDay> = first
+            .iter_days()
+            .take_while(|day| *day < next)
+            .map(|day| {
+                let date = day.format("%Y-%m-%d").to_string();
+                (
+                    date.clone(),
+                    ActivityDay {
+                        date,
+                        ..ActivityDay::default()
+                    },
+                )
+            })
+            .collect();
+
+        let mut stmt = self.conn.prepare(
+            "SELECT started_at_ms FROM job_runs WHERE started_at_ms >= ?1 AND started_at_ms < ?2",
+        )?;
+        for started_at_ms in stmt.query_map(params![start_ms, end_ms], |row| row.get(0))? {
+            if let Some(day) = days.get_mut(&day_key(started_at_ms?)?) {
+                day.job_runs += 1;
+            }
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT last_used_at_ms FROM connector_tool_usage
+             WHERE tool_name LIKE 'webhook:%' AND last_used_at_ms >= ?1 AND last_used_at_ms < ?2",
+        )?;
+        for used_at_ms in stmt.query_map(params![start_ms, end_ms], |row| row.get(0))? {
+            if let Some(day) = days.get_mut(&day_key(used_at_ms?)?) {
+                day.webhook_triggers += 1;
+            }
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT substr(updated_at, 1, 10), status, COUNT(*) FROM goals
+             WHERE status IN ('completed', 'failed') AND updated_at >= ?1 AND updated_at < ?2
+             GROUP BY 1, 2",
+        )?;
+        let finished = stmt.query_map(params![first.to_string(), next.to_string()], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get(2)?,
+            ))
+        })?;
+        for row in finished {
+            let (date, status, count): (String, String, i64) = row?;
+            if let Some(day) = days.get_mut(&date) {
+                if status == "completed" {
+                    day.completed += count;
+                } else {
+                    day.failed += count;
+                }
+            }
+        }
+
+        let from_ms = start_ms.max(now_ms);
+        for job in self.list_jobs()?.into_iter().filter(|job| job.enabled) {
+            // A job with a schedule the scheduler cannot parse never runs, so it projects nothing.
+            let Ok(runs) = projected_runs(
+                &job.schedule_kind,
+                &job.schedule_value,
+                job.last_run_at_ms,
+                from_ms,
+                end_ms,
+            ) else {
+                continue;
+            };
+            for run_ms in runs {
+                if let Some(day) = days.get_mut(&day_key(run_ms)?) {
+                    day.scheduled += 1;
+                }
+            }
+        }
+
+        Ok(ActivityCalendar {
+            month: first.format("%Y-%m").to_string(),
+            days: days.into_values().collect(),
+        })
+    }
+}
+
+/// When a job is expected to start within `[from_ms, end_ms)`, following the scheduler: an
+/// interval job is due one interval after its last run, a cron job at each matching time.
+fn projected_runs(
+    schedule_kind: &str,
+    schedule_value: &str,
+    last_run_at_ms: Option<i64>,
+    from_ms: i64,
+    end_ms: i64,
+) -> Result<Vec<i64>> {
+    let mut runs = Vec::new();
+    match schedule_kind {
+        "interval" => {
+            let interval_ms = parse_interval_ms(schedule_value)?;
+            // An overdue job runs on the next tick, then every interval after that.
+            let mut next_ms = (last_run_at_ms.unwrap_or(0) + interval_ms).max(from_ms);
+            while next_ms < end_ms && runs.len() < MAX_PROJECTED_RUNS {
+                runs.push(next_ms);
+                next_ms += interval_ms;
+            }
+        }
+        "cron" => {
+            let schedule = schedule_value
+                .parse::<Schedule>()
+                .with_context(|| format!("invalid cron expression: {schedule_value}"))?;
+            let from = Utc
+                .timestamp_millis_opt(from_ms)
+                .single()
+                .context("invalid projection start")?;
+            for next in schedule.after(&from).take(MAX_PROJECTED_RUNS) {
+                let next_ms = next.timestamp_millis();
+                if next_ms >= end_ms {
+                    break;
+                }
+                runs.push(next_ms);
+            }
+        }
+        other => bail!("unsupported schedule_kind: {other}"),
+    }
+    Ok(runs)
+}
+
+fn day_start_ms(day: NaiveDate) -> i64 {
+    day.and_hms_opt(0, 0, 0)
+        .map(|start| start.and_utc().timestamp_millis())
+        .unwrap_or_default()
+}
+
+fn day_key(epoch_ms: i64) -> Result<String> {
+    let at = Utc
+        .timestamp_millis_opt(epoch_ms)
+        .single()
+        .with_context(|| format!("invalid timestamp: {epoch_ms}"))?;
+    Ok(at.format("%Y-%m-%d").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interval_projection_starts_at_the_next_due_time_and_stops_at_the_end() {
+        let hour = 3_600_000;
+        let runs = projected_runs("interval", "6h", Some(hour), 0, 24 * hour).expect("interval");
+        assert_eq!(runs, vec![7 * hour, 13 * hour, 19 * hour]);
+
+        let overdue = projected_runs("interval", "6h", None, 10 * hour, 24 * hour).expect("due");
+        assert_eq!(overdue, vec![10 * hour, 16 * hour, 22 * hour]);
+
+        let daily = projected_runs("cron", "0 0 9 * * *", None, 0, 3 * 24 * hour).expect("cron");
+        assert_eq!(daily.len(), 3);
+        assert!(projected_runs("weekly", "x", None, 0, hour).is_err());
+    }
+}
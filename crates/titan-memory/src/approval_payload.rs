@@ -0,0 +1,214 @@
+//! Typed inputs for approvals TITAN raises on its own behalf (config changes, skill installs,
+//! connector calls, canary continuation), as opposed to tool approvals whose input is the
+//! tool's own argument string.
+//!
+//! Each payload is stored as `{"kind": ..., "version": N, "payload": {...}}`. Decoding is
+//! strict: the kind must match, unknown fields are rejected, and a version newer than this
+//! build is refused instead of guessed at. Older versions go through
+//! [`ApprovalPayload::upgrade`]; version 0 is whatever was stored before the envelope
+//! existed.
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use titan_common::ActivationMode;
+use titan_core::CANARY_CONTINUE_TOOL;
+
+pub trait ApprovalPayload: Serialize + DeserializeOwned {
+    /// The approval's `tool_name`.
+    const KIND: &'static str;
+    const VERSION: u32;
+
+    /// Converts a payload written by an older build. The default reads version 0 JSON as the
+    /// current shape, which holds for kinds that were already JSON before versioning.
+    fn upgrade(version: u32, value: serde_json::Value) -> Result<Self> {
+        if version == 0 {
+            return serde_json::from_value(value)
+                .map_err(|err| anyhow!("unversioned {} approval payload: {err}", Self::KIND));
+        }
+        bail!(
+            "{} approval payload version {version} has no upgrade path",
+            Self::KIND
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope<'a> {
+    kind: &'a str,
+    version: u32,
+    payload: serde_json::Value,
+}
+
+pub fn encode_approval_payload<P: ApprovalPayload>(payload: &P) -> Result<String> {
+    Ok(serde_json::to_string(&Envelope {
+        kind: P::KIND,
+        version: P::VERSION,
+        payload: serde_json::to_value(payload)?,
+    })?)
+}
+
+pub fn decode_approval_payload<P: ApprovalPayload>(input: &str) -> Result<P> {
+    let value: serde_json::Value = match serde_json::from_str(input) {
+        Ok(value) => value,
+        Err(_) => return P::upgrade(0, serde_json::Value::String(input.to_string())),
+    };
+    let is_envelope = value
+        .as_object()
+        .is_some_and(|object| object.contains_key("kind") && object.contains_key("version"));
+    if !is_envelope {
+        return P::upgrade(0, value);
+    }
+    let envelope: Envelope<'_> = serde_json::from_str(input)
+        .with_context(|| format!("malformed {} approval payload envelope", P::KIND))?;
+    if envelope.kind != P::KIND {
+        bail!(
+            "approval payload is for '{}', expected '{}'",
+            envelope.kind,
+            P::KIND
+        );
+    }
+    if envelope.version > P::VERSION {
+        bail!(
+            "{} approval payload version {} is newer than this build supports ({}); upgrade TITAN to approve it",
+            P::KIND,
+            envelope.version,
+            P::VERSION
+        );
+    }
+    if envelope.version < P::VERSION {
+        return P::upgrade(envelope.version, envelope.payload);
+    }
+    serde_json::from_value(envelope.payload).map_err(|err| {
+        anyhow!(
+            "invalid {} approval payload (version {}): {err}",
+            P::KIND,
+            envelope.version
+        )
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AllowlistAction {
+    Add,
+    Remove,
+}
+
+impl AllowlistAction {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "add" => Some(Self::Add),
+            "remove" => Some(Self::Remove),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Add => "add",
+            Self::Remove => "remove",
+        }
+    }
+}
+
+/// `/allowlist add|remove <id>` awaiting approval.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigAllowlistPayload {
+    pub action: AllowlistAction,
+    pub id: String,
+}
+
+impl ApprovalPayload for ConfigAllowlistPayload {
+    const KIND: &'static str = "config_allowlist";
+    const VERSION: u32 = 1;
+
+    fn upgrade(version: u32, value: serde_json::Value) -> Result<Self> {
+        // Before versioning: "add:<id>".
+        let legacy = value
+            .as_str()
+            .filter(|_| version == 0)
+            .ok_or_else(|| anyhow!("config_allowlist payload version {version} is unknown"))?;
+        let (action, id) = legacy.split_once(':').ok_or_else(|| {
+            anyhow!("legacy config_allowlist payload '{legacy}' is not action:id")
+        })?;
+        Ok(Self {
+            action: AllowlistAction::parse(action)
+                .ok_or_else(|| anyhow!("unknown allowlist action '{action}'"))?,
+            id: id.to_string(),
+        })
+    }
+}
+
+/// `/activation mention|always` awaiting approval.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigActivationPayload {
+    pub mode: ActivationMode,
+}
+
+impl ApprovalPayload for ConfigActivationPayload {
+    const KIND: &'static str = "config_activation";
+    const VERSION: u32 = 1;
+
+    fn upgrade(version: u32, value: serde_json::Value) -> Result<Self> {
+        // Before versioning: the bare mode name.
+        let legacy = value
+            .as_str()
+            .filter(|_| version == 0)
+            .ok_or_else(|| anyhow!("config_activation payload version {version} is unknown"))?;
+        Ok(Self {
+            mode: serde_json::from_value(serde_json::Value::String(legacy.trim().to_string()))
+                .map_err(|_| anyhow!("unknown activation mode '{legacy}'"))?,
+        })
+    }
+}
+
+/// Standing permission for a skill with the exec scope to run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SkillExecGrantPayload {
+    pub slug: String,
+}
+
+impl ApprovalPayload for SkillExecGrantPayload {
+    const KIND: &'static str = "skill_exec_grant";
+    const VERSION: u32 = 1;
+
+    fn upgrade(version: u32, value: serde_json::Value) -> Result<Self> {
+        // Before versioning: the bare slug.
+        match value.as_str() {
+            Some(slug) if version == 0 => Ok(Self {
+                slug: slug.to_string(),
+            }),
+            _ => bail!("skill_exec_grant payload version {version} is unknown"),
+        }
+    }
+}
+
+/// Confirmation to continue a goal after its canary write.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CanaryContinuePayload {
+    /// Output of the canary write, shown to the approver.
+    pub canary_output: String,
+}
+
+impl ApprovalPayload for CanaryContinuePayload {
+    const KIND: &'static str = CANARY_CONTINUE_TOOL;
+    const VERSION: u32 = 1;
+
+    fn upgrade(version: u32, value: serde_json::Value) -> Result<Self> {
+        // Before versioning: the raw canary output, which may itself happen to parse as JSON.
+        if version != 0 {
+            bail!("canary_continue payload version {version} is unknown");
+        }
+        Ok(Self {
+            canary_output: match value {
+                serde_json::Value::String(output) => output,
+                other => other.to_string(),
+            },
+        })
+    }
+}
@@ -0,0 +1,209 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, params};
+use serde_json::{Map, Value};
+use titan_common::{ArchiveConfig, ArchiveTarget};
+use uuid::Uuid;
+
+use crate::blob::{self, BLOB_REF_PREFIX};
+use crate::{MemoryStore, now_epoch_ms};
+
+/// Per-goal tables moved into an archive, children before parents. Labels, approvals,
+/// episodic memories, and file provenance stay in SQLite so lists, recall, and provenance
+/// still find archived goals.
+pub const ARCHIVED_GOAL_TABLES: &[&str] = &[
+    "trace_events",
+    "trace_counters",
+    "run_steps",
+    "run_plans",
+    "model_plans",
+    "goal_checkpoints",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct GoalArchiveReport {
+    /// A file path, or `blob:sha256:<hex>` for the blob store; `None` when nothing was due.
+    pub archive_ref: Option<String>,
+    pub goals: usize,
+    pub rows: usize,
+    pub bytes: u64,
+}
+
+impl MemoryStore {
+    /// Archives up to `config.batch_size` finished goals last updated before `cutoff_ms`.
+    /// Each goal becomes one JSON line holding its `goals` row and its rows from
+    /// [`ARCHIVED_GOAL_TABLES`]. The archive is written before anything is deleted, and the
+    /// goal row stays behind with `archive_ref` set.
+    pub fn archive_goals(
+        &self,
+        cutoff_ms: i64,
+        config: &ArchiveConfig,
+    ) -> Result<GoalArchiveReport> {
+        let goal_ids = {
+            let mut stmt = self.conn.prepare(
+                "SELECT id FROM goals
+                 WHERE status IN ('completed', 'failed', 'cancelled')
+                   AND archive_ref IS NULL
+                   AND updated_at < datetime(?1 / 1000, 'unixepoch')
+                 ORDER BY updated_at ASC
+                 LIMIT ?2",
+            )?;
+            stmt.query_map(params![cutoff_ms, config.batch_size as i64], |row| {
+                row.get::<_, String>(0)
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        if goal_ids.is_empty() {
+            return Ok(GoalArchiveReport::default());
+        }
+
+        let mut encoder = zstd::Encoder::new(Vec::new(), 3)?;
+        let mut rows = 0;
+        for goal_id in &goal_ids {
+            let mut tables = Map::new();
+            for table in ARCHIVED_GOAL_TABLES {
+                let table_rows = rows_as_json(
+                    &self.conn,
+                    &format!("SELECT * FROM {table} WHERE goal_id = ?1"),
+                    goal_id,
+                )?;
+                rows += table_rows.len();
+                tables.insert(table.to_string(), Value::Array(table_rows));
+            }
+            let goal = rows_as_json(&self.conn, "SELECT * FROM goals WHERE id = ?1", goal_id)?
+                .pop()
+                .unwrap_or(Value::Null);
+            let line = serde_json::json!({"goal": goal, "tables": tables});
+            serde_json::to_writer(&mut encoder, &line)?;
+            encoder.write_all(b"\n")?;
+        }
+        let bytes = encoder.finish()?;
+        let archive_ref = self.write_archive(config, &bytes)?;
+
+        let archived_at_ms = now_epoch_ms();
+        let tx = self.conn.unchecked_transaction()?;
+        for goal_id in &goal_ids {
+            for table in ARCHIVED_GOAL_TABLES {
+                tx.execute(
+                    &format!("DELETE FROM {table} WHERE goal_id = ?1"),
+                    params![goal_id],
+                )?;
+            }
+            tx.execute(
+                "UPDATE goals SET archive_ref = ?1, archived_at_ms = ?2 WHERE id = ?3",
+                params![archive_ref, archived_at_ms, goal_id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(GoalArchiveReport {
+            archive_ref: Some(archive_ref),
+            goals: goal_ids.len(),
+            rows,
+            bytes: bytes.len() as u64,
+        })
+    }
+
+    /// Where a goal was archived, or `None` while its history is still in SQLite.
+    pub fn goal_archive_ref(&self, goal_id: &str) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT archive_ref FROM goals WHERE id = ?1",
+                params![goal_id],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .ok()
+            .flatten())
+    }
+
+    /// The archived JSON line of a goal: `{"goal": {...}, "tables": {"trace_events": [...]}}`.
+    pub fn archived_goal(&self, goal_id: &str) -> Result<Option<Value>> {
+        let Some(archive_ref) = self.goal_archive_ref(goal_id)? else {
+            return Ok(None);
+        };
+        let bytes = match blob::blob_reference_key(&archive_ref) {
+            Some(key) => self.blobs.store.get(key)?,
+            None => fs::read(&archive_ref)
+                .with_context(|| format!("failed to read goal archive {archive_ref}"))?,
+        };
+        let reader = BufReader::new(zstd::Decoder::new(bytes.as_slice())?);
+        for line in reader.lines() {
+            let entry: Value = serde_json::from_str(&line?)?;
+            if entry["goal"]["id"] == goal_id {
+                return Ok(Some(entry));
+            }
+        }
+        anyhow::bail!("goal {goal_id} is missing from archive {archive_ref}")
+    }
+
+    fn write_archive(&self, config: &ArchiveConfig, bytes: &[u8]) -> Result<String> {
+        match config.target {
+            ArchiveTarget::BlobStore => {
+                let key = blob::blob_key(bytes);
+                self.blobs.store.put(&key, bytes)?;
+                self.conn.execute(
+                    "INSERT OR IGNORE INTO blobs (key, backend, size_bytes, created_at_ms)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![
+                        key,
+                        self.blobs.store.backend(),
+                        bytes.len() as i64,
+                        now_epoch_ms()
+                    ],
+                )?;
+                Ok(format!("{BLOB_REF_PREFIX}{key}"))
+            }
+            ArchiveTarget::Dir => {
+                let dir = config
+                    .dir
+                    .clone()
+                    .unwrap_or_else(|| self.default_archive_dir());
+                fs::create_dir_all(&dir).with_context(|| {
+                    format!("failed to create archive directory {}", dir.display())
+                })?;
+                let name = format!("goals-{}-{}.jsonl.zst", now_epoch_ms(), Uuid::new_v4());
+                let path = dir.join(name);
+                // Write aside and rename so a crash never leaves a truncated archive behind.
+                let partial = dir.join(format!(".{}.partial", Uuid::new_v4()));
+                fs::write(&partial, bytes)?;
+                fs::rename(&partial, &path)?;
+                Ok(path.display().to_string())
+            }
+        }
+    }
+
+    fn default_archive_dir(&self) -> PathBuf {
+        self.db_path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join("archive")
+    }
+}
+
+fn rows_as_json(conn: &Connection, sql: &str, goal_id: &str) -> Result<Vec<Value>> {
+    let mut stmt = conn.prepare(sql)?;
+    let names: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let rows = stmt.query_map(params![goal_id], |row| {
+        let mut object = Map::new();
+        for (index, name) in names.iter().enumerate() {
+            let value = match row.get_ref(index)? {
+                ValueRef::Null => Value::Null,
+                ValueRef::Integer(value) => value.into(),
+                ValueRef::Real(value) => value.into(),
+                ValueRef::Text(text) => String::from_utf8_lossy(text).into_owned().into(),
+                ValueRef::Blob(bytes) => bytes
+                    .iter()
+                    .map(|byte| format!("{byte:02x}"))
+                    .collect::<String>()
+                    .into(),
+            };
+            object.insert(name.clone(), value);
+        }
+        Ok(Value::Object(object))
+    })?;
+    Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+}
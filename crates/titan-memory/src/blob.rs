@@ -0,0 +1,180 @@
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use anyhow::{Context, Result, bail};
+use titan_common::{BlobBackend, S3BlobConfig, StorageConfig};
+use uuid::Uuid;
+
+use crate::s3::{S3Client, S3Credentials, sha256_hex};
+
+/// Stored in place of an offloaded output, followed by the blob's SHA-256 hex digest.
+pub const BLOB_REF_PREFIX: &str = "blob:sha256:";
+
+/// Content-addressed storage for outputs too large to keep in SQLite. Keys are the SHA-256
+/// hex digest of the bytes, so equal outputs share one blob.
+pub trait BlobStore: Send + Sync {
+    /// `local` or `s3`, recorded with each blob row.
+    fn backend(&self) -> &'static str;
+    fn put(&self, key: &str, bytes: &[u8]) -> Result<()>;
+    fn get(&self, key: &str) -> Result<Vec<u8>>;
+}
+
+pub fn blob_key(bytes: &[u8]) -> String {
+    sha256_hex(bytes)
+}
+
+/// The blob key of a stored `blob:sha256:<hex>` reference.
+pub fn blob_reference_key(stored: &str) -> Option<&str> {
+    stored
+        .strip_prefix(BLOB_REF_PREFIX)
+        .filter(|key| key.len() == 64 && key.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Blobs under `<root>/<first two hex digits>/<key>`.
+#[derive(Debug, Clone)]
+pub struct LocalBlobStore {
+    root: PathBuf,
+}
+
+impl LocalBlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn path_for(&self, key: &str) -> PathBuf {
+        self.root.join(&key[..2]).join(key)
+    }
+}
+
+impl BlobStore for LocalBlobStore {
+    fn backend(&self) -> &'static str {
+        "local"
+    }
+
+    fn put(&self, key: &str, bytes: &[u8]) -> Result<()> {
+        let path = self.path_for(key);
+        if path.exists() {
+            return Ok(());
+        }
+        let dir = path.parent().unwrap_or(&self.root);
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create blob directory {}", dir.display()))?;
+        // Write aside and rename so a reader never sees a partial blob.
+        let partial = dir.join(format!(".{key}.{}", Uuid::new_v4()));
+        fs::write(&partial, bytes)?;
+        fs::rename(&partial, &path)?;
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let path = self.path_for(key);
+        fs::read(&path).with_context(|| format!("blob {key} missing at {}", path.display()))
+    }
+}
+
+/// An S3-compatible bucket under an optional key prefix. Credentials are read from the
+/// configured environment variables on each request.
+#[derive(Debug, Clone)]
+pub struct S3BlobStore {
+    client: S3Client,
+    config: S3BlobConfig,
+}
+
+impl S3BlobStore {
+    pub fn new(config: S3BlobConfig) -> Self {
+        Self {
+            client: S3Client::new(&config.endpoint, &config.bucket, &config.region),
+            config,
+        }
+    }
+
+    fn credentials(&self) -> Result<S3Credentials> {
+        let credential = |name: &str| {
+            std::env::var(name).with_context(|| format!("storage.s3 credential {name} is not set"))
+        };
+        Ok(S3Credentials {
+            access_key: credential(&self.config.access_key_env)?,
+            secret_key: credential(&self.config.secret_key_env)?,
+        })
+    }
+
+    fn object_key(&self, key: &str) -> String {
+        format!("{}{key}", self.config.prefix)
+    }
+}
+
+impl BlobStore for S3BlobStore {
+    fn backend(&self) -> &'static str {
+        "s3"
+    }
+
+    fn put(&self, key: &str, bytes: &[u8]) -> Result<()> {
+        self.client
+            .put(&self.credentials()?, &self.object_key(key), bytes)
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>> {
+        self.client.get(&self.credentials()?, &self.object_key(key))
+    }
+}
+
+/// The blob store a `MemoryStore` offloads to, the size at which it does, and the size
+/// outputs are cut to first.
+#[derive(Clone)]
+pub(crate) struct BlobSettings {
+    pub(crate) store: Arc<dyn BlobStore>,
+    pub(crate) threshold_bytes: usize,
+    pub(crate) max_output_bytes: Option<usize>,
+}
+
+/// `output` cut to at most `max_bytes` on a character boundary, followed by a line giving
+/// the original size; shorter outputs come back unchanged.
+pub fn truncate_output(output: &str, max_bytes: usize) -> Cow<'_, str> {
+    if output.len() <= max_bytes {
+        return Cow::Borrowed(output);
+    }
+    let mut end = max_bytes;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    Cow::Owned(format!(
+        "{}\n[output truncated: kept {end} of {} bytes]",
+        &output[..end],
+        output.len()
+    ))
+}
+
+static STORAGE_CONFIG: RwLock<Option<StorageConfig>> = RwLock::new(None);
+
+pub(crate) fn install_storage_config(config: &StorageConfig) {
+    let mut guard = STORAGE_CONFIG.write().unwrap_or_else(|e| e.into_inner());
+    *guard = Some(config.clone());
+}
+
+// Processes that never load a config keep local blobs next to the database.
+pub(crate) fn settings_for(db_path: &Path) -> Result<BlobSettings> {
+    let config = STORAGE_CONFIG
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_default();
+    let store: Arc<dyn BlobStore> = match (config.blob_backend, &config.s3) {
+        (BlobBackend::S3, Some(s3)) => Arc::new(S3BlobStore::new(s3.clone())),
+        (BlobBackend::S3, None) => bail!("storage.blob_backend = \"s3\" needs [storage.s3]"),
+        (BlobBackend::Local, _) => Arc::new(LocalBlobStore::new(
+            config.blob_dir.clone().unwrap_or_else(|| {
+                db_path
+                    .parent()
+                    .unwrap_or_else(|| Path::new("."))
+                    .join("blobs")
+            }),
+        )),
+    };
+    Ok(BlobSettings {
+        store,
+        threshold_bytes: config.blob_threshold_bytes as usize,
+        max_output_bytes: config.max_output_bytes.map(|bytes| bytes as usize),
+    })
+}
@@ -0,0 +1,270 @@
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use titan_common::{EmbeddingProvider, EmbeddingsConfig};
+
+/// Turns text into a vector for similarity recall. Vectors from different embedders are
+/// never compared, so `id` must change whenever the vector space does.
+pub trait Embedder: Send + Sync {
+    /// Recorded with each stored vector, e.g. `hashing:256` or `ollama:nomic-embed-text`.
+    fn id(&self) -> String;
+    fn embed(&self, text: &str) -> Result<Vec<f32>>;
+
+    /// One vector per text, in order. Embedders with a batch endpoint override this so
+    /// ingestion makes one request per batch instead of one per memory.
+    fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        texts.iter().map(|text| self.embed(text)).collect()
+    }
+}
+
+/// Signed feature hashing of lowercase words and word pairs. Needs no model, and texts that
+/// share words land close together.
+#[derive(Debug, Clone)]
+pub struct HashingEmbedder {
+    dimensions: usize,
+}
+
+impl HashingEmbedder {
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            dimensions: dimensions.max(1),
+        }
+    }
+}
+
+impl Embedder for HashingEmbedder {
+    fn id(&self) -> String {
+        format!("hashing:{}", self.dimensions)
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let words: Vec<String> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        let pairs = words
+            .windows(2)
+            .map(|pair| format!("{} {}", pair[0], pair[1]));
+        let mut vector = vec![0.0f32; self.dimensions];
+        for feature in words.iter().cloned().chain(pairs) {
+            let digest = Sha256::digest(feature.as_bytes());
+            let bucket = u64::from_le_bytes(digest[..8].try_into().expect("8 bytes"));
+            let sign = if digest[8] & 1 == 0 { 1.0 } else { -1.0 };
+            vector[(bucket % self.dimensions as u64) as usize] += sign;
+        }
+        Ok(normalize(vector))
+    }
+}
+
+/// Ollama's `/api/embed`.
+#[derive(Debug, Clone)]
+pub struct OllamaEmbedder {
+    pub endpoint: String,
+    pub model: String,
+}
+
+impl Embedder for OllamaEmbedder {
+    fn id(&self) -> String {
+        format!("ollama:{}", self.model)
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let url = format!("{}/api/embed", self.endpoint.trim_end_matches('/'));
+        let body = post_json(&url, None, &json!({"model": self.model, "input": text}))?;
+        vector_from(body.pointer("/embeddings/0"))
+    }
+
+    fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let url = format!("{}/api/embed", self.endpoint.trim_end_matches('/'));
+        let body = post_json(&url, None, &json!({"model": self.model, "input": texts}))?;
+        let vectors = body
+            .get("embeddings")
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow!("embedding response has no vectors"))?;
+        batch_from(vectors.iter().map(Some).collect(), texts.len())
+    }
+}
+
+/// An OpenAI-compatible `/embeddings` endpoint.
+#[derive(Debug, Clone)]
+pub struct OpenAiEmbedder {
+    pub endpoint: String,
+    pub model: String,
+    pub api_key_env: String,
+}
+
+impl Embedder for OpenAiEmbedder {
+    fn id(&self) -> String {
+        format!("openai:{}", self.model)
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let key = std::env::var(&self.api_key_env)
+            .with_context(|| format!("embeddings key {} is not set", self.api_key_env))?;
+        let url = format!("{}/embeddings", self.endpoint.trim_end_matches('/'));
+        let body = post_json(
+            &url,
+            Some(&key),
+            &json!({"model": self.model, "input": text}),
+        )?;
+        vector_from(body.pointer("/data/0/embedding"))
+    }
+
+    fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let key = std::env::var(&self.api_key_env)
+            .with_context(|| format!("embeddings key {} is not set", self.api_key_env))?;
+        let url = format!("{}/embeddings", self.endpoint.trim_end_matches('/'));
+        let body = post_json(
+            &url,
+            Some(&key),
+            &json!({"model": self.model, "input": texts}),
+        )?;
+        let mut data: Vec<&Value> = body
+            .get("data")
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow!("embedding response has no vectors"))?
+            .iter()
+            .collect();
+        // Entries carry the position of their input and are not promised to come in order.
+        data.sort_by_key(|entry| entry.get("index").and_then(Value::as_u64));
+        batch_from(
+            data.into_iter()
+                .map(|entry| entry.get("embedding"))
+                .collect(),
+            texts.len(),
+        )
+    }
+}
+
+fn vector_from(value: Option<&Value>) -> Result<Vec<f32>> {
+    let values = value
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("embedding response has no vector"))?;
+    let vector = values
+        .iter()
+        .map(|v| v.as_f64().map(|v| v as f32))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| anyhow!("embedding vector has non-numeric values"))?;
+    if vector.is_empty() {
+        bail!("embedding vector is empty");
+    }
+    Ok(normalize(vector))
+}
+
+fn batch_from(values: Vec<Option<&Value>>, expected: usize) -> Result<Vec<Vec<f32>>> {
+    if values.len() != expected {
+        bail!(
+            "embedding response has {} vectors for {expected} inputs",
+            values.len()
+        );
+    }
+    values.into_iter().map(vector_from).collect()
+}
+
+// The blocking client cannot run on a tokio worker, and the gateway embeds from one.
+fn post_json(url: &str, bearer: Option<&str>, body: &Value) -> Result<Value> {
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| -> Result<Value> {
+                let mut request = reqwest::blocking::Client::builder()
+                    .timeout(Duration::from_secs(30))
+                    .build()?
+                    .post(url)
+                    .json(body);
+                if let Some(token) = bearer {
+                    request = request.bearer_auth(token);
+                }
+                let response = request
+                    .send()
+                    .with_context(|| format!("embedding request to {url} failed"))?;
+                if !response.status().is_success() {
+                    bail!("embedding request failed: {}", response.status());
+                }
+                Ok(response.json()?)
+            })
+            .join()
+            .unwrap_or_else(|_| bail!("embedding request thread panicked"))
+    })
+}
+
+fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+    vector
+}
+
+/// Dot product of two unit vectors; 0 when their sizes differ.
+pub(crate) fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+pub(crate) fn encode(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+pub(crate) fn decode(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes(chunk.try_into().expect("4 bytes")))
+        .collect()
+}
+
+static EMBEDDINGS_CONFIG: RwLock<Option<EmbeddingsConfig>> = RwLock::new(None);
+
+pub(crate) fn install_embeddings_config(config: &EmbeddingsConfig) {
+    let mut guard = EMBEDDINGS_CONFIG.write().unwrap_or_else(|e| e.into_inner());
+    *guard = Some(config.clone());
+}
+
+pub(crate) fn embedding_batch_size() -> usize {
+    EMBEDDINGS_CONFIG
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map_or_else(
+            || EmbeddingsConfig::default().batch_size,
+            |config| config.batch_size,
+        )
+        .max(1)
+}
+
+// Processes that never load a config use the hashing embedder.
+pub(crate) fn embedder_for_config() -> Arc<dyn Embedder> {
+    let config = EMBEDDINGS_CONFIG
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_default();
+    let model = config.model.clone().unwrap_or_default();
+    match config.provider {
+        EmbeddingProvider::Hashing => Arc::new(HashingEmbedder::new(config.dimensions)),
+        EmbeddingProvider::Ollama => Arc::new(OllamaEmbedder {
+            endpoint: config
+                .endpoint
+                .unwrap_or_else(|| "http://127.0.0.1:11434".to_string()),
+            model,
+        }),
+        EmbeddingProvider::OpenAi => Arc::new(OpenAiEmbedder {
+            endpoint: config
+                .endpoint
+                .unwrap_or_else(|| "https://api.openai.com/v1".to_string()),
+            model,
+            api_key_env: config.api_key_env,
+        }),
+    }
+}
@@ -1,24 +1,117 @@
+pub mod activity;
+pub mod approval_payload;
+pub mod archive;
+pub mod blob;
+pub mod embedding;
+pub mod pool;
+pub mod repair;
+pub mod s3;
+pub mod trace_diff;
+
+pub use activity::{ActivityCalendar, ActivityDay, calendar_month_at, parse_calendar_month};
+pub use archive::{ARCHIVED_GOAL_TABLES, GoalArchiveReport};
+pub use pool::{MemoryStorePool, PooledStore};
+pub use repair::{RepairFinding, RepairReport};
+pub use trace_diff::{GoalRunDiff, GoalRunSummary, RunStepOutcome, StepDiff};
+
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Arc;
 
 use anyhow::{Context, Result, bail};
 use chrono::{DateTime, TimeZone, Utc};
 use cron::Schedule;
 use rusqlite::{Connection, params};
-use titan_core::{Goal, GoalStatus, PendingApprovalAction, StepResult, TaskRunResult, TraceEvent};
+use titan_common::{EmbeddingsConfig, StorageConfig, now_epoch_ms};
+use titan_core::{
+    CANARY_CONTINUE_TOOL, Goal, GoalPriority, GoalStatus, PendingApprovalAction, StepResult,
+    StructuredReflection, TaskPlan, TaskRunResult, TraceClass, TraceEvent, TraceVerbosity,
+};
 use uuid::Uuid;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct StoredGoal {
     pub id: String,
     pub description: String,
     pub status: String,
     pub dedupe_key: Option<String>,
+    /// `low`, `normal`, or `urgent`.
+    pub priority: String,
+}
+
+/// Which goals a list shows: goals carrying every one of `labels`, in `status` when set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GoalFilter {
+    pub status: Option<String>,
+    pub labels: Vec<String>,
+}
+
+impl GoalFilter {
+    /// Whitespace-separated `status=<status>` and `label=<label>` terms, as [`Self::terms`]
+    /// writes them.
+    pub fn parse(terms: &str) -> Result<Self> {
+        let mut filter = Self::default();
+        for term in terms.split_whitespace() {
+            match term.split_once('=') {
+                Some(("status", status)) => {
+                    if GoalStatus::parse(status).is_none() {
+                        bail!("unknown goal status: {status}");
+                    }
+                    filter.status = Some(status.to_string());
+                }
+                Some(("label", label)) => {
+                    let label = normalize_goal_label(label)?;
+                    if !filter.labels.contains(&label) {
+                        filter.labels.push(label);
+                    }
+                }
+                _ => bail!("filter terms are status=<status> or label=<label>: {term}"),
+            }
+        }
+        Ok(filter)
+    }
+
+    pub fn terms(&self) -> String {
+        self.status
+            .iter()
+            .map(|status| format!("status={status}"))
+            .chain(self.labels.iter().map(|label| format!("label={label}")))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// A [`GoalFilter`] an operator saved under a name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedGoalFilter {
+    pub operator: String,
+    pub name: String,
+    pub filter: GoalFilter,
+}
+
+/// Labels the runtime derives for a goal; operators add theirs as [`GOAL_LABEL_USER`].
+pub const GOAL_LABEL_AUTO: &str = "auto";
+pub const GOAL_LABEL_USER: &str = "user";
+
+/// Lowercases a label and checks it is at most 64 letters, digits, and `:-_./`.
+pub fn normalize_goal_label(label: &str) -> Result<String> {
+    let label = label.trim().to_lowercase();
+    if label.is_empty()
+        || label.len() > 64
+        || !label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ":-_./".contains(c))
+    {
+        bail!("invalid label '{label}': use up to 64 letters, digits, and : - _ . /");
+    }
+    Ok(label)
 }
 
 pub struct MemoryStore {
     conn: Connection,
     db_path: PathBuf,
+    blobs: blob::BlobSettings,
+    embedder: Arc<dyn embedding::Embedder>,
 }
 
 #[derive(Debug, Clone)]
@@ -34,6 +127,8 @@ pub struct ApprovalRecord {
     pub resolved_by: Option<String>,
     pub expires_at_ms: i64,
     pub decision_reason: Option<String>,
+    /// The requested input when the approver edited it; `input` then holds what runs.
+    pub original_input: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -45,12 +140,32 @@ pub struct ToolRunRecord {
     pub output: String,
 }
 
+#[derive(Debug, Clone)]
+pub struct RunStepRecord {
+    pub id: String,
+    pub step_id: String,
+    pub tool_name: String,
+    pub permission: String,
+    pub input: Option<String>,
+    pub status: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct EpisodicMemoryRecord {
     pub id: i64,
     pub goal_id: String,
     pub summary: String,
     pub source: String,
+    pub reflection: Option<StructuredReflection>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProceduralStrategyRecord {
+    pub id: i64,
+    pub strategy_name: String,
+    pub strategy_body: String,
+    pub confidence: f64,
+    pub source: String,
 }
 
 #[derive(Debug, Clone)]
@@ -64,6 +179,8 @@ pub struct SessionRecord {
     pub compactions_count: i64,
     pub queue_depth: i64,
     pub stop_requested: bool,
+    /// Set once the session was closed for inactivity; closed sessions are never resumed.
+    pub closed_at_ms: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -75,6 +192,319 @@ pub struct SessionMessageRecord {
     pub compacted: bool,
 }
 
+/// A fact or message that stays in a session's context through compaction.
+#[derive(Debug, Clone)]
+pub struct SessionPinRecord {
+    pub id: i64,
+    pub session_id: String,
+    /// Set when the pin copies a session message; `content` is its text at pin time.
+    pub message_id: Option<i64>,
+    pub content: String,
+    pub pinned_by: String,
+    pub created_at_ms: i64,
+}
+
+/// A `/run` waiting for the session's next message to fill its next parameter.
+#[derive(Debug, Clone)]
+pub struct TemplatePromptRecord {
+    pub session_id: String,
+    pub template: String,
+    /// Parameters filled so far, in template order.
+    pub values: Vec<String>,
+    pub created_at_ms: i64,
+}
+
+/// A goal held in `awaiting_clarification` until the session's next message answers it.
+#[derive(Debug, Clone)]
+pub struct ClarificationRecord {
+    pub session_id: String,
+    pub goal_id: String,
+    /// The request as first sent, before any answer is merged in.
+    pub request_text: String,
+    pub question: String,
+    pub created_at_ms: i64,
+}
+
+/// A goal paused on an `AskUser` step until the session answers it or `expires_at_ms` passes.
+#[derive(Debug, Clone)]
+pub struct PendingInputRecord {
+    pub session_id: String,
+    pub goal_id: String,
+    pub step_id: String,
+    pub question: String,
+    pub request_text: String,
+    /// Plan candidate that asked, so the resumed run replans onto the same steps.
+    pub candidate_id: String,
+    /// Results of the steps that ran before the question.
+    pub results: Vec<StepResult>,
+    pub created_at_ms: i64,
+    pub expires_at_ms: i64,
+}
+
+/// A local goal forwarded to a federation peer, mirrored until the remote goal finishes.
+#[derive(Debug, Clone)]
+pub struct RemoteGoalRecord {
+    pub goal_id: String,
+    pub peer: String,
+    pub remote_goal_id: String,
+    pub remote_status: String,
+    /// Newest remote trace id already merged into the local timeline.
+    pub last_trace_id: i64,
+    pub created_at_ms: i64,
+    pub updated_at_ms: i64,
+}
+
+/// Where a goal paused for an urgent one stopped, so it resumes without rerunning steps.
+#[derive(Debug, Clone)]
+pub struct GoalCheckpointRecord {
+    pub goal_id: String,
+    pub session_id: String,
+    pub channel: String,
+    pub actor_id: String,
+    pub request_text: String,
+    /// Plan candidate that was running, so the resumed run replans onto the same steps.
+    pub candidate_id: String,
+    /// Results of the steps that ran before the pause.
+    pub results: Vec<StepResult>,
+    pub preempted_by: String,
+    pub created_at_ms: i64,
+}
+
+/// A goal held in `queued` status until a concurrency slot frees up.
+#[derive(Debug, Clone)]
+pub struct QueuedGoalRecord {
+    pub goal_id: String,
+    pub session_id: String,
+    pub channel: String,
+    pub actor_id: String,
+    pub request_text: String,
+    pub token_budget: Option<u64>,
+    /// Plan candidate a `/run` template asked for.
+    pub preferred_candidate: Option<String>,
+    pub created_at_ms: i64,
+}
+
+/// Which table a [`MemoryHit`] came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryKind {
+    Trace,
+    Episodic,
+    Message,
+}
+
+impl MemoryKind {
+    pub const ALL: [Self; 3] = [Self::Trace, Self::Episodic, Self::Message];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Trace => "trace",
+            Self::Episodic => "episodic",
+            Self::Message => "message",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "trace" | "traces" => Some(Self::Trace),
+            "episodic" => Some(Self::Episodic),
+            "message" | "messages" => Some(Self::Message),
+            _ => None,
+        }
+    }
+
+    // (FTS table, source table, owner column)
+    fn tables(self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            Self::Trace => ("trace_events_fts", "trace_events", "goal_id"),
+            Self::Episodic => ("episodic_memories_fts", "episodic_memories", "goal_id"),
+            Self::Message => ("session_messages_fts", "session_messages", "session_id"),
+        }
+    }
+}
+
+/// One ranked result of [`MemoryStore::search_memory`].
+#[derive(Debug, Clone)]
+pub struct MemoryHit {
+    pub kind: MemoryKind,
+    /// Row id in the source table.
+    pub id: i64,
+    /// Goal id for traces and episodic memories, session id for messages.
+    pub owner_id: String,
+    /// Matching excerpt with matched terms wrapped in `[` and `]`.
+    pub snippet: String,
+    /// Relevance; higher is better.
+    pub score: f64,
+    pub created_at: String,
+}
+
+/// One result of [`MemoryStore::recall_similar`].
+#[derive(Debug, Clone)]
+pub struct RecalledMemory {
+    /// `semantic` or `episodic`.
+    pub kind: String,
+    /// Row id in `semantic_facts` or `episodic_memories`.
+    pub id: i64,
+    /// `namespace.key: value` for facts, the summary for episodic memories.
+    pub text: String,
+    /// Cosine similarity to the query, from -1 to 1.
+    pub score: f32,
+}
+
+/// The vector size one embedder produced for a memory namespace: a fact namespace, or
+/// `episodic` for episodic memories.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddingNamespace {
+    pub namespace: String,
+    pub embedder: String,
+    pub dimensions: usize,
+    pub updated_at_ms: i64,
+}
+
+/// Goals in `planning` or `executing`, overall and for one channel and session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunningGoalCounts {
+    pub global: usize,
+    pub channel: usize,
+    pub session: usize,
+}
+
+/// What [`MemoryStore::close_idle_sessions`] did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionCloseReport {
+    pub closed: Vec<String>,
+    /// Closed sessions whose final summary went to episodic memory; sessions that never ran a
+    /// goal have no goal to attach one to.
+    pub summarized: usize,
+}
+
+/// One request or reply for [`MemoryStore::record_session_usage`].
+#[derive(Debug, Clone, Default)]
+pub struct UsageEntry {
+    pub goal_id: Option<String>,
+    /// `user` or `assistant`.
+    pub role: String,
+    pub tokens: u64,
+    pub cost_usd: f64,
+    /// Model prompts behind the message, and how many of them the response cache answered.
+    pub model_calls: u64,
+    pub model_cache_hits: u64,
+}
+
+/// Estimated usage summed over `session_usage` rows.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UsageTotals {
+    pub messages: u64,
+    pub tokens: u64,
+    pub cost_usd: f64,
+    pub model_calls: u64,
+    pub model_cache_hits: u64,
+}
+
+/// Rows in the model response cache, and how often cached replies were reused.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModelCacheStats {
+    pub entries: u64,
+    /// Entries that have not expired yet.
+    pub live: u64,
+    pub hits: u64,
+}
+
+/// Whether the model provider is reachable, as the gateway last saw it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelProviderState {
+    /// Too many consecutive unreachable errors: plan without the model until a probe succeeds.
+    pub degraded: bool,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    pub last_failure_at_ms: Option<i64>,
+    pub degraded_since_ms: Option<i64>,
+}
+
+impl ModelProviderState {
+    /// Degraded and not yet due to probe the provider again.
+    pub fn holds_model_off(&self, now_ms: i64, probe_after_ms: i64) -> bool {
+        self.degraded
+            && self
+                .last_failure_at_ms
+                .is_some_and(|failed_at| now_ms - failed_at < probe_after_ms)
+    }
+}
+
+/// `semantic_facts` namespace holding what a peer asked TITAN to remember about them. Shared
+/// recall skips these namespaces, so one peer's facts never reach another peer's goals.
+pub fn peer_memory_namespace(channel: &str, peer_id: &str) -> String {
+    format!("peer:{channel}:{peer_id}")
+}
+
+/// Cache key for a prompt sent to the model `identity` names: its provider, model id,
+/// endpoint, and the request parameters that shape the reply.
+pub fn model_cache_key(identity: &str, prompt: &str) -> String {
+    blob::blob_key(format!("{identity}\n{prompt}").as_bytes())
+}
+
+/// What a session run sees: every pin, the newest summary, then live messages in order.
+#[derive(Debug, Clone, Default)]
+pub struct SessionContext {
+    pub pins: Vec<SessionPinRecord>,
+    pub messages: Vec<SessionMessageRecord>,
+}
+
+/// One inbound message, event, or command as the gateway saw it. `action` is the command
+/// name, the goal id, or empty; message text is never logged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessLogEntry {
+    pub id: i64,
+    pub at_ms: i64,
+    pub channel: String,
+    pub actor_id: String,
+    /// Client address, for requests that came through the web server.
+    pub remote_addr: Option<String>,
+    /// `command`, `message`, or `event`.
+    pub kind: String,
+    pub action: String,
+    pub outcome: String,
+    /// Anomaly rules the request tripped, comma separated.
+    pub anomaly: Option<String>,
+}
+
+/// Size of a session's history, with tokens estimated at four characters each like
+/// `titan_core::estimate_tokens`. `context_tokens` counts what a run sees: pins, the newest
+/// summary, and live messages.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionContextStats {
+    pub messages: usize,
+    pub compacted_messages: usize,
+    pub message_tokens: u64,
+    pub pins: usize,
+    pub pin_tokens: u64,
+    pub context_tokens: u64,
+}
+
+/// One message's share of the context. `cumulative_tokens` runs from the newest message back
+/// and only counts messages still `in_context`, so it shows where a budget would cut.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextMessageStat {
+    pub id: i64,
+    pub role: String,
+    pub in_context: bool,
+    pub bytes: usize,
+    pub tokens: u64,
+    pub cumulative_tokens: u64,
+}
+
+/// A session as it stood at `as_of_ms`: what TITAN knew when it acted at that moment.
+#[derive(Debug, Clone)]
+pub struct SessionSnapshot {
+    pub session_id: String,
+    pub as_of_ms: i64,
+    pub risk_mode: RiskMode,
+    pub pins: Vec<SessionPinRecord>,
+    /// Messages written by `as_of_ms`; `compacted` only if the summary replacing it was too.
+    pub messages: Vec<SessionMessageRecord>,
+    pub open_goals: Vec<StoredGoal>,
+    pub open_approvals: Vec<ApprovalRecord>,
+}
+
 #[derive(Debug, Clone)]
 pub struct InstalledSkillRecord {
     pub slug: String,
@@ -123,6 +553,20 @@ pub struct RuntimeRiskState {
     pub last_changed_by: String,
 }
 
+/// One row of `risk_state_history`: an arm, enable, disable, or expiry and who caused it.
+#[derive(Debug, Clone)]
+pub struct RiskStateHistoryRecord {
+    pub id: i64,
+    /// `arm`, `enable`, `disable`, or `expire`.
+    pub action: String,
+    /// Risk mode in force after the action.
+    pub risk_mode: RiskMode,
+    pub actor: String,
+    pub reason: Option<String>,
+    pub expires_at_ms: Option<i64>,
+    pub created_at_ms: i64,
+}
+
 #[derive(Debug, Clone)]
 pub struct ConnectorRecord {
     pub id: String,
@@ -133,6 +577,132 @@ pub struct ConnectorRecord {
     pub last_test_status: Option<String>,
 }
 
+/// A migrating process that dies leaves the lock behind for at most this long. The holder
+/// renews it with each migration it commits, so the ttl bounds a single migration, not the run.
+const MIGRATION_LOCK_TTL: std::time::Duration = std::time::Duration::from_secs(60);
+const MIGRATION_LOCK_MAX_WAIT: std::time::Duration = std::time::Duration::from_secs(120);
+const MIGRATION_LOCK_INITIAL_BACKOFF: std::time::Duration = std::time::Duration::from_millis(20);
+const MIGRATION_LOCK_MAX_BACKOFF: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Highest migration this build applies; `schema_version()` should match it once opened.
+pub const LATEST_SCHEMA_VERSION: i64 = 54;
+
+/// The memories similarity recall searches: the latest value of each non-peer fact and every
+/// episodic memory, with the namespace their vector size is recorded under.
+const RECALL_SOURCES: &str = "SELECT 'semantic' AS kind, f.id AS id, f.namespace AS namespace,
+                                f.namespace || '.' || f.fact_key || ': ' || f.fact_value AS text
+                              FROM semantic_facts f
+                              WHERE f.id = (SELECT MAX(id) FROM semantic_facts
+                                            WHERE namespace = f.namespace AND fact_key = f.fact_key)
+                                AND f.namespace NOT LIKE 'peer:%'
+                              UNION ALL
+                              SELECT 'episodic', id, 'episodic', summary FROM episodic_memories";
+
+/// Longest final summary [`MemoryStore::close_idle_sessions`] keeps, in characters.
+pub const SESSION_CLOSE_SUMMARY_CHARS: usize = 4_000;
+
+/// Last tick of a background worker loop in `titan run`.
+#[derive(Debug, Clone)]
+pub struct WorkerHeartbeatRecord {
+    pub name: String,
+    /// Writer id of the process running the loop.
+    pub holder: String,
+    pub interval_ms: i64,
+    pub beat_at_ms: i64,
+}
+
+impl WorkerHeartbeatRecord {
+    /// A worker is stale after missing three ticks (and at least 15 seconds).
+    pub fn is_stale(&self, now_ms: i64) -> bool {
+        now_ms - self.beat_at_ms > (self.interval_ms * 3).max(15_000)
+    }
+}
+
+/// Circuit-breaker state. While `active`, the runtime runs Supervised with YOLO off and
+/// autonomous triggers paused until an operator resumes it.
+#[derive(Debug, Clone)]
+pub struct SafeModeState {
+    pub active: bool,
+    pub reason: Option<String>,
+    pub changed_by: Option<String>,
+    pub changed_at_ms: Option<i64>,
+    pub last_panic_at_ms: Option<i64>,
+    pub last_panic_detail: Option<String>,
+    /// A panic was recorded since the last resume.
+    pub panic_pending: bool,
+}
+
+/// A workspace file change attributed to the goal whose write or exec steps made it.
+#[derive(Debug, Clone)]
+pub struct WorkspaceChangeRecord {
+    pub id: i64,
+    pub goal_id: String,
+    pub tool_name: String,
+    /// Path relative to the workspace root.
+    pub path: String,
+    /// Two-letter `git status --porcelain` code, e.g. ` M` or `??`.
+    pub change_kind: String,
+    pub diff_stat: String,
+    /// Set once the path is no longer dirty in git (committed or reverted).
+    pub merged_at_ms: Option<i64>,
+    pub created_at_ms: i64,
+}
+
+/// Where agent-written content in a workspace file came from: the goal that wrote it, the
+/// model configured for that run, and a sha256 of the request it was given.
+#[derive(Debug, Clone)]
+pub struct ContentProvenanceRecord {
+    pub id: i64,
+    pub goal_id: String,
+    pub tool_name: String,
+    /// Path relative to the workspace root.
+    pub path: String,
+    /// `<provider>:<model id>`, e.g. `ollama:llama3.2:latest`.
+    pub model: String,
+    pub prompt_hash: String,
+    pub created_at_ms: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConnectorUsageRecord {
+    pub connector_id: String,
+    pub tool_name: String,
+    pub last_used_at_ms: i64,
+    pub last_goal_id: Option<String>,
+}
+
+/// One outbound connector request. Only the method, the URL path, and response figures are
+/// kept; query strings, headers, and bodies never reach the log.
+#[derive(Debug, Clone)]
+pub struct ConnectorIoRecord {
+    pub id: i64,
+    pub connector_id: String,
+    pub tool_name: String,
+    pub method: String,
+    pub path: String,
+    /// `None` when no response arrived.
+    pub status: Option<u16>,
+    pub response_bytes: u64,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+    pub created_at_ms: i64,
+}
+
+/// A cached connector read, stored as the tool returned it.
+#[derive(Debug, Clone)]
+pub struct ConnectorCacheEntry {
+    pub output_json: String,
+    pub metadata_json: String,
+    pub expires_at_ms: i64,
+}
+
+/// Cache lookups for one connector tool since the database was created.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConnectorCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct JobRecord {
     pub job_id: String,
@@ -161,59 +731,556 @@ pub struct JobRunRecord {
     pub error_summary: Option<String>,
 }
 
-pub struct NewJobRecord<'a> {
-    pub job_id: &'a str,
-    pub name: &'a str,
-    pub schedule_kind: &'a str,
-    pub schedule_value: &'a str,
-    pub goal_template: &'a str,
-    pub mode: &'a str,
-    pub allowed_scopes: &'a str,
+#[derive(Debug, Clone)]
+pub struct SkillRunRecord {
+    pub id: i64,
+    pub slug: String,
+    pub goal_id: Option<String>,
+    pub status: String,
+    pub duration_ms: i64,
+    pub output_bytes: i64,
+    pub created_at_ms: i64,
 }
 
-pub struct RunPersistenceBundle<'a> {
-    pub run: &'a TaskRunResult,
-    pub source: &'a str,
-    pub requested_by: Option<&'a str>,
-    pub approval_ttl_ms: u64,
+#[derive(Debug, Clone)]
+pub struct SkillRunSummary {
+    pub slug: String,
+    pub total_runs: i64,
+    pub failed_runs: i64,
+    // Oldest bucket first; one bucket per day ending now.
+    pub daily_runs: Vec<i64>,
 }
 
-pub struct RunPersistenceOutcome {
-    pub approval_id: Option<String>,
+/// One approver's say on an approval: `approve` or `deny`. Quorum votes are approve
+/// decisions that did not yet resolve the request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalDecisionRecord {
+    pub approval_id: String,
+    pub approver: String,
+    pub decision: String,
+    pub created_at_ms: i64,
 }
 
-impl MemoryStore {
-    pub fn open(db_path: &Path) -> Result<Self> {
-        // Ensure parent directory exists so sqlite can create/open the db file.
-        if let Some(parent) = db_path.parent() {
-            std::fs::create_dir_all(parent)
-                .with_context(|| format!("failed to create db directory {}", parent.display()))?;
+#[derive(Debug, Clone)]
+pub struct ApprovalCommentRecord {
+    pub id: i64,
+    pub approval_id: String,
+    pub author: String,
+    pub body: String,
+    pub created_at_ms: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct NotificationPrefRecord {
+    pub operator: String,
+    pub event_kind: String,
+    pub channel: String,
+    pub target: String,
+    // Minutes after midnight UTC; the window may wrap past midnight.
+    pub quiet_start_minute: Option<i64>,
+    pub quiet_end_minute: Option<i64>,
+}
+
+impl NotificationPrefRecord {
+    pub fn in_quiet_hours(&self, minute_of_day: i64) -> bool {
+        match (self.quiet_start_minute, self.quiet_end_minute) {
+            (Some(start), Some(end)) if start <= end => {
+                minute_of_day >= start && minute_of_day < end
+            }
+            (Some(start), Some(end)) => minute_of_day >= start || minute_of_day < end,
+            _ => false,
         }
-        let conn = Connection::open(db_path)
-            .with_context(|| format!("failed to open database at {}", db_path.display()))?;
-        let store = Self {
-            conn,
-            db_path: db_path.to_path_buf(),
-        };
-        store.migrate()?;
-        Ok(store)
     }
+}
 
-    fn migrate(&self) -> Result<()> {
-        self.conn.execute_batch(
-            r#"
-            CREATE TABLE IF NOT EXISTS schema_migrations (
-              version INTEGER PRIMARY KEY,
-              name TEXT NOT NULL,
-              applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
-            );
-            "#,
-        )?;
+#[derive(Debug, Clone)]
+pub struct NotificationLogRecord {
+    pub id: i64,
+    pub operator: String,
+    pub event_kind: String,
+    pub channel: String,
+    pub target: String,
+    pub message: String,
+    pub status: String,
+    pub detail: String,
+    pub created_at_ms: i64,
+}
 
-        self.apply_migration(
-            1,
-            "base_runtime_tables",
-            r#"
+#[derive(Debug, Clone)]
+pub struct LifecycleEventRecord {
+    pub id: i64,
+    pub kind: String,
+    pub subject_id: String,
+    pub detail: String,
+    pub created_at_ms: i64,
+}
+
+/// A channel message to deliver through `channel_outbox`.
+#[derive(Debug, Clone)]
+pub struct OutboxMessage {
+    /// Notification route owner; `None` for replies to the originating conversation.
+    pub operator: Option<String>,
+    pub event_kind: String,
+    pub subject_id: String,
+    pub channel: String,
+    pub target: String,
+    pub message: String,
+}
+
+/// A channel conversation that hears about new approvals regardless of operator routes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelTarget {
+    pub channel: String,
+    pub target: String,
+}
+
+/// The `approval_requested` notice sent to [`ChannelTarget`]s.
+pub fn approval_notice_message(
+    approval_id: &str,
+    tool_name: &str,
+    capability: &str,
+    expires_at_ms: i64,
+    now_ms: i64,
+) -> String {
+    let minutes = (expires_at_ms.saturating_sub(now_ms).max(0) + 59_999) / 60_000;
+    format!(
+        "approval {approval_id} queued: {tool_name} ({capability}), expires in {minutes}m \
+         (expires_at_ms={expires_at_ms}). Reply /approve {approval_id} or /deny {approval_id}."
+    )
+}
+
+#[derive(Debug, Clone)]
+pub struct OutboxRecord {
+    pub id: i64,
+    pub operator: Option<String>,
+    pub event_kind: String,
+    pub subject_id: String,
+    pub channel: String,
+    pub target: String,
+    pub message: String,
+    pub status: String,
+    pub attempts: i64,
+    pub next_attempt_at_ms: i64,
+    pub last_error: Option<String>,
+    pub created_at_ms: i64,
+    pub delivered_at_ms: Option<i64>,
+}
+
+/// How long a run reply waits for the process that handled the message to send it before
+/// the outbox worker takes over.
+pub const OUTBOX_REPLY_GRACE_MS: i64 = 30_000;
+
+/// Who is reading traces. `Redacted` masks details that can carry tool input/output, file
+/// contents, chat text, or connector payloads, and keeps event types and timestamps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TraceView {
+    #[default]
+    Full,
+    Redacted,
+}
+
+impl TraceView {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "full" => Some(Self::Full),
+            "redacted" | "viewer" => Some(Self::Redacted),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Full => "full",
+            Self::Redacted => "redacted",
+        }
+    }
+}
+
+/// Trace types whose detail is status metadata only. Every other type is masked in the
+/// redacted view, so new event types start out hidden.
+const REDACTION_SAFE_TRACE_TYPES: &[&str] = &[
+    "event_received",
+    "planning_started",
+    "planning_completed",
+    "plan_candidate_generated",
+    "plan_selected",
+    "execution_started",
+    "execution_completed",
+    "execution_timeout",
+    "step_cache_hit",
+    "retry_scheduled",
+    "goal_cancelled",
+    "approval_required",
+    "approval_queued",
+    "approval_vote",
+    "approval_modified",
+    "approval_rerequested",
+    "canary_paused",
+    "canary_resumed",
+    "skill_run_started",
+    "skill_quarantined",
+];
+
+/// A stored trace event as rendered for one `TraceView`.
+#[derive(Debug, Clone)]
+pub struct TraceViewRecord {
+    pub id: i64,
+    pub goal_id: String,
+    pub event_type: String,
+    pub detail: String,
+    pub risk_mode: String,
+    pub created_at: String,
+    pub redacted: bool,
+}
+
+pub fn redact_trace_detail(event_type: &str, detail: &str) -> Option<String> {
+    if REDACTION_SAFE_TRACE_TYPES.contains(&event_type) || detail.is_empty() {
+        None
+    } else {
+        Some(format!("[redacted {} bytes]", detail.len()))
+    }
+}
+
+/// Lease name guarding execution-critical writes (goal runs, approvals, jobs, webhooks).
+pub const GATEWAY_WRITER_LEASE: &str = "gateway";
+
+#[derive(Debug, Clone)]
+pub struct WriterLeaseRecord {
+    pub name: String,
+    pub holder: String,
+    /// Web API base URL other processes can proxy writes to.
+    pub endpoint: Option<String>,
+    pub acquired_at_ms: i64,
+    pub renewed_at_ms: i64,
+    pub expires_at_ms: i64,
+}
+
+impl WriterLeaseRecord {
+    pub fn is_live(&self, now_ms: i64) -> bool {
+        self.expires_at_ms > now_ms
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DbSizeStats {
+    pub file_bytes: u64,
+    pub wal_bytes: u64,
+    pub page_size: i64,
+    pub page_count: i64,
+    pub freelist_pages: i64,
+    pub journal_mode: String,
+}
+
+impl DbSizeStats {
+    pub fn total_bytes(&self) -> u64 {
+        self.file_bytes + self.wal_bytes
+    }
+
+    pub fn free_ratio(&self) -> f64 {
+        if self.page_count == 0 {
+            return 0.0;
+        }
+        self.freelist_pages as f64 / self.page_count as f64
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MaintenanceReport {
+    pub trigger: String,
+    pub before: DbSizeStats,
+    pub after: DbSizeStats,
+    pub wal_checkpointed: bool,
+    pub vacuumed: bool,
+    pub index_count: i64,
+    /// `ok`, or the problems `PRAGMA integrity_check` reported.
+    pub integrity: Vec<String>,
+    pub duration_ms: i64,
+}
+
+impl MaintenanceReport {
+    pub fn reclaimed_bytes(&self) -> u64 {
+        self.before
+            .total_bytes()
+            .saturating_sub(self.after.total_bytes())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookDeliveryRecord {
+    pub id: i64,
+    pub event_id: i64,
+    pub webhook: String,
+    pub status: String,
+    pub attempts: i64,
+    pub next_attempt_at_ms: i64,
+    pub last_error: Option<String>,
+    pub last_status_code: Option<i64>,
+    pub updated_at_ms: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SkillQuarantineRecord {
+    pub slug: String,
+    pub reason: String,
+    pub violation_count: i64,
+    pub quarantined_at_ms: i64,
+}
+
+pub struct NewJobRecord<'a> {
+    pub job_id: &'a str,
+    pub name: &'a str,
+    pub schedule_kind: &'a str,
+    pub schedule_value: &'a str,
+    pub goal_template: &'a str,
+    pub mode: &'a str,
+    pub allowed_scopes: &'a str,
+}
+
+pub struct RunPersistenceBundle<'a> {
+    pub run: &'a TaskRunResult,
+    pub source: &'a str,
+    pub requested_by: Option<&'a str>,
+    pub approval_ttl_ms: u64,
+    /// Reply to the originating conversation, queued in the same transaction as the run.
+    /// ` approval_pending=<id>` is appended when the run queues an approval.
+    pub reply: Option<OutboxMessage>,
+    /// Conversations sent an [`approval_notice_message`] when the run queues an approval.
+    pub approval_notices: Vec<ChannelTarget>,
+    /// Stored as JSON on the episodic memory; its strategy changes become procedural strategies
+    /// named after the selected plan candidate.
+    pub reflection: Option<&'a ReflectionOutcome>,
+    /// The planner experiment arm, recorded on the run's `run_plans` row.
+    pub experiment: Option<&'a PlanExperiment>,
+}
+
+/// The planner experiment arm a goal was planned under: `control` or `variant`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanExperiment {
+    pub name: String,
+    pub variant: String,
+}
+
+/// Outcomes of one experiment arm, over goals whose plans are not archived yet.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExperimentArmStats {
+    pub variant: String,
+    pub goals: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub feedback_up: usize,
+    pub feedback_down: usize,
+}
+
+impl ExperimentArmStats {
+    /// Completed share of the arm's finished goals; 0 when none finished yet.
+    pub fn success_rate(&self) -> f64 {
+        let finished = self.completed + self.failed;
+        if finished == 0 {
+            0.0
+        } else {
+            self.completed as f64 / finished as f64
+        }
+    }
+}
+
+/// A structured reflection and the generator that produced it (`model` or `deterministic`).
+#[derive(Debug, Clone)]
+pub struct ReflectionOutcome {
+    pub reflection: StructuredReflection,
+    pub source: String,
+}
+
+pub struct RunPersistenceOutcome {
+    pub approval_id: Option<String>,
+    /// Notification rows queued with the run, ready for immediate delivery.
+    pub notification_outbox_ids: Vec<i64>,
+    pub reply_outbox_id: Option<i64>,
+}
+
+impl MemoryStore {
+    pub fn open(db_path: &Path) -> Result<Self> {
+        let store = Self::connect(db_path)?;
+        store.migrate()?;
+        Ok(store)
+    }
+
+    // A connection to a database whose schema is already current.
+    fn connect(db_path: &Path) -> Result<Self> {
+        // Ensure parent directory exists so sqlite can create/open the db file.
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create db directory {}", parent.display()))?;
+        }
+        let conn = Connection::open(db_path)
+            .with_context(|| format!("failed to open database at {}", db_path.display()))?;
+        // Web, CLI, and the run loop share this file; wait out short write locks instead of
+        // failing with SQLITE_BUSY.
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        Ok(Self {
+            conn,
+            db_path: db_path.to_path_buf(),
+            blobs: blob::settings_for(db_path)?,
+            embedder: embedding::embedder_for_config(),
+        })
+    }
+
+    fn enable_wal(&self) -> Result<()> {
+        let mode: String = self
+            .conn
+            .query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0))?;
+        if !mode.eq_ignore_ascii_case("wal") {
+            bail!("database did not switch to WAL (journal_mode={mode})");
+        }
+        Ok(())
+    }
+
+    fn is_autocommit(&self) -> bool {
+        self.conn.is_autocommit()
+    }
+
+    /// Uses `[storage]` from `config.toml` for every store this process opens.
+    pub fn configure_blob_storage(config: &StorageConfig) {
+        blob::install_storage_config(config);
+    }
+
+    /// Uses `[embeddings]` from `config.toml` for every store this process opens.
+    pub fn configure_embeddings(config: &EmbeddingsConfig) {
+        embedding::install_embeddings_config(config);
+    }
+
+    /// Replaces the embedder [`Self::recall_similar`] uses for this store.
+    pub fn with_embedder(mut self, embedder: Arc<dyn embedding::Embedder>) -> Self {
+        self.embedder = embedder;
+        self
+    }
+
+    /// What to keep in SQLite for a tool output: the output itself, or a
+    /// `blob:sha256:<hex>` reference once it exceeds `storage.blob_threshold_bytes`.
+    /// Outputs over `storage.max_output_bytes` are truncated first.
+    pub fn offload_output(&self, output: &str) -> Result<String> {
+        let output = match self.blobs.max_output_bytes {
+            Some(max_bytes) => blob::truncate_output(output, max_bytes),
+            None => output.into(),
+        };
+        let output = output.as_ref();
+        if output.len() <= self.blobs.threshold_bytes {
+            return Ok(output.to_string());
+        }
+        let key = blob::blob_key(output.as_bytes());
+        self.blobs.store.put(&key, output.as_bytes())?;
+        self.conn.execute(
+            "INSERT OR IGNORE INTO blobs (key, backend, size_bytes, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                key,
+                self.blobs.store.backend(),
+                output.len() as i64,
+                now_epoch_ms()
+            ],
+        )?;
+        Ok(format!("{}{key}", blob::BLOB_REF_PREFIX))
+    }
+
+    /// Reverses [`MemoryStore::offload_output`]; values that are not references come back as-is.
+    pub fn resolve_output(&self, stored: &str) -> Result<String> {
+        match blob::blob_reference_key(stored) {
+            Some(key) => String::from_utf8(self.blobs.store.get(key)?)
+                .with_context(|| format!("blob {key} is not UTF-8")),
+            None => Ok(stored.to_string()),
+        }
+    }
+
+    /// Bytes of a recorded blob, e.g. to share an artifact by key; `None` for unknown keys.
+    pub fn blob(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT 1 FROM blobs WHERE key = ?1 LIMIT 1")?;
+        let mut rows = stmt.query(params![key])?;
+        match rows.next()? {
+            Some(_) => Ok(Some(self.blobs.store.get(key)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Brings the schema up to date. Processes opening the same database at once take turns
+    /// through `migration_lock`: one migrates while the others back off, and find nothing
+    /// left to do once it releases. Each migration renews the lock inside its own transaction
+    /// and aborts if another process has taken it over, so a long run never overlaps another.
+    fn migrate(&self) -> Result<()> {
+        self.conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS schema_migrations (
+              version INTEGER PRIMARY KEY,
+              name TEXT NOT NULL,
+              applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            );
+            CREATE TABLE IF NOT EXISTS migration_lock (
+              id INTEGER PRIMARY KEY CHECK (id = 1),
+              holder TEXT NOT NULL,
+              acquired_at_ms INTEGER NOT NULL,
+              expires_at_ms INTEGER NOT NULL
+            );
+            "#,
+        )?;
+
+        let holder = Uuid::new_v4().to_string();
+        let started = std::time::Instant::now();
+        let mut backoff = MIGRATION_LOCK_INITIAL_BACKOFF;
+        loop {
+            if self.schema_version()? >= LATEST_SCHEMA_VERSION {
+                return Ok(());
+            }
+            if self.try_acquire_migration_lock(&holder)? {
+                break;
+            }
+            if started.elapsed() > MIGRATION_LOCK_MAX_WAIT {
+                let current: Option<String> = self
+                    .conn
+                    .query_row(
+                        "SELECT holder FROM migration_lock WHERE id = 1",
+                        [],
+                        |row| row.get(0),
+                    )
+                    .ok();
+                bail!(
+                    "timed out waiting for migration lock held by {}",
+                    current.as_deref().unwrap_or("<unknown>")
+                );
+            }
+            std::thread::sleep(backoff);
+            backoff = (backoff * 2).min(MIGRATION_LOCK_MAX_BACKOFF);
+        }
+
+        let result = self.apply_migrations(&holder);
+        self.conn.execute(
+            "DELETE FROM migration_lock WHERE id = 1 AND holder = ?1",
+            params![holder],
+        )?;
+        result
+    }
+
+    /// Takes the lock when it is free or its holder let it expire, e.g. by crashing mid-way.
+    fn try_acquire_migration_lock(&self, holder: &str) -> Result<bool> {
+        let now = now_epoch_ms();
+        let changed = self.conn.execute(
+            "INSERT INTO migration_lock (id, holder, acquired_at_ms, expires_at_ms)
+             VALUES (1, ?1, ?2, ?3)
+             ON CONFLICT(id) DO UPDATE SET
+               holder = excluded.holder,
+               acquired_at_ms = excluded.acquired_at_ms,
+               expires_at_ms = excluded.expires_at_ms
+             WHERE migration_lock.expires_at_ms <= excluded.acquired_at_ms",
+            params![holder, now, now + MIGRATION_LOCK_TTL.as_millis() as i64],
+        )?;
+        Ok(changed == 1)
+    }
+
+    fn apply_migrations(&self, holder: &str) -> Result<()> {
+        self.apply_migration(
+            holder,
+            1,
+            "base_runtime_tables",
+            r#"
             CREATE TABLE IF NOT EXISTS goals (
               id TEXT PRIMARY KEY,
               description TEXT NOT NULL,
@@ -255,6 +1322,7 @@ impl MemoryStore {
         )?;
 
         self.apply_migration(
+            holder,
             2,
             "goal_dedupe_and_approval_hardening",
             r#"
@@ -267,6 +1335,7 @@ impl MemoryStore {
         )?;
 
         self.apply_migration(
+            holder,
             3,
             "semantic_and_procedural_memory",
             r#"
@@ -291,6 +1360,7 @@ impl MemoryStore {
         )?;
 
         self.apply_migration(
+            holder,
             4,
             "episodic_memory_and_goal_linked_approvals",
             r#"
@@ -308,6 +1378,7 @@ impl MemoryStore {
         )?;
 
         self.apply_migration(
+            holder,
             5,
             "run_plan_and_step_tables",
             r#"
@@ -339,6 +1410,7 @@ impl MemoryStore {
         )?;
 
         self.apply_migration(
+            holder,
             6,
             "sessions_and_chat_history",
             r#"
@@ -371,6 +1443,7 @@ impl MemoryStore {
         )?;
 
         self.apply_migration(
+            holder,
             7,
             "installed_skills_table",
             r#"
@@ -393,6 +1466,7 @@ impl MemoryStore {
         )?;
 
         self.apply_migration(
+            holder,
             8,
             "runtime_risk_modes_and_trace_risk",
             r#"
@@ -415,6 +1489,7 @@ impl MemoryStore {
         )?;
 
         self.apply_migration(
+            holder,
             9,
             "connectors_and_tool_usage",
             r#"
@@ -441,6 +1516,7 @@ impl MemoryStore {
         )?;
 
         self.apply_migration(
+            holder,
             10,
             "jobs_and_scheduler_runs",
             r#"
@@ -473,54 +1549,932 @@ impl MemoryStore {
             "#,
         )?;
 
-        self.conn.execute(
-            "CREATE UNIQUE INDEX IF NOT EXISTS idx_goals_dedupe_key
-             ON goals(dedupe_key)
-             WHERE dedupe_key IS NOT NULL",
-            [],
-        )?;
-        self.conn.execute(
-            "CREATE UNIQUE INDEX IF NOT EXISTS idx_tool_runs_approval_id
-             ON tool_runs(approval_id)
-             WHERE approval_id IS NOT NULL",
-            [],
-        )?;
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_sessions_channel_peer_updated
-             ON sessions(channel, peer_id, updated_at DESC)",
-            [],
-        )?;
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_goals_session_id
-             ON goals(session_id)",
-            [],
+        self.apply_migration(
+            holder,
+            11,
+            "skill_runs",
+            r#"
+            CREATE TABLE IF NOT EXISTS skill_runs (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              slug TEXT NOT NULL,
+              goal_id TEXT,
+              status TEXT NOT NULL,
+              duration_ms INTEGER NOT NULL,
+              output_bytes INTEGER NOT NULL,
+              created_at_ms INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_skill_runs_slug_created
+              ON skill_runs(slug, created_at_ms DESC)
+            "#,
         )?;
 
-        self.conn.execute(
-            "UPDATE approval_requests
-             SET nonce = COALESCE(nonce, id),
-                 expires_at_ms = COALESCE(expires_at_ms, CAST((julianday(created_at) - 2440587.5) * 86400000 AS INTEGER) + 300000)",
-            [],
+        self.apply_migration(
+            holder,
+            12,
+            "skill_scope_violations",
+            r#"
+            CREATE TABLE IF NOT EXISTS skill_scope_violations (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              slug TEXT NOT NULL,
+              goal_id TEXT,
+              tool_name TEXT NOT NULL,
+              capability TEXT NOT NULL,
+              cleared INTEGER NOT NULL DEFAULT 0,
+              created_at_ms INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_skill_scope_violations_slug
+              ON skill_scope_violations(slug, cleared);
+
+            CREATE TABLE IF NOT EXISTS skill_quarantine (
+              slug TEXT PRIMARY KEY,
+              reason TEXT NOT NULL,
+              violation_count INTEGER NOT NULL,
+              quarantined_at_ms INTEGER NOT NULL
+            );
+            "#,
         )?;
 
-        Ok(())
-    }
+        self.apply_migration(
+            holder,
+            13,
+            "approval_comments",
+            r#"
+            CREATE TABLE IF NOT EXISTS approval_comments (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              approval_id TEXT NOT NULL,
+              author TEXT NOT NULL,
+              body TEXT NOT NULL,
+              created_at_ms INTEGER NOT NULL,
+              FOREIGN KEY(approval_id) REFERENCES approval_requests(id)
+            );
 
-    fn apply_migration(&self, version: i64, name: &str, sql: &str) -> Result<()> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT 1 FROM schema_migrations WHERE version = ?1 LIMIT 1")?;
-        let mut rows = stmt.query(params![version])?;
-        if rows.next()?.is_some() {
-            return Ok(());
-        }
+            CREATE INDEX IF NOT EXISTS idx_approval_comments_approval
+              ON approval_comments(approval_id, id)
+            "#,
+        )?;
 
-        let tx = self.conn.unchecked_transaction()?;
-        for raw in sql.split(';') {
-            let trimmed = raw.trim();
-            if trimmed.is_empty() {
-                continue;
-            }
+        self.apply_migration(
+            holder,
+            14,
+            "notification_prefs",
+            r#"
+            CREATE TABLE IF NOT EXISTS notification_prefs (
+              operator TEXT NOT NULL,
+              event_kind TEXT NOT NULL,
+              channel TEXT NOT NULL,
+              target TEXT NOT NULL,
+              quiet_start_minute INTEGER,
+              quiet_end_minute INTEGER,
+              updated_at_ms INTEGER NOT NULL,
+              PRIMARY KEY(operator, event_kind)
+            );
+
+            CREATE TABLE IF NOT EXISTS notification_log (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              operator TEXT NOT NULL,
+              event_kind TEXT NOT NULL,
+              channel TEXT NOT NULL,
+              target TEXT NOT NULL,
+              message TEXT NOT NULL,
+              status TEXT NOT NULL,
+              detail TEXT NOT NULL,
+              created_at_ms INTEGER NOT NULL
+            )
+            "#,
+        )?;
+        self.apply_migration(
+            holder,
+            15,
+            "approval_votes",
+            r#"
+            CREATE TABLE IF NOT EXISTS approval_votes (
+              approval_id TEXT NOT NULL,
+              voter TEXT NOT NULL,
+              created_at_ms INTEGER NOT NULL,
+              PRIMARY KEY(approval_id, voter)
+            )
+            "#,
+        )?;
+        self.apply_migration(
+            holder,
+            16,
+            "lifecycle_events_webhooks",
+            r#"
+            CREATE TABLE IF NOT EXISTS lifecycle_events (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              kind TEXT NOT NULL,
+              subject_id TEXT NOT NULL,
+              detail TEXT NOT NULL,
+              created_at_ms INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS webhook_cursors (
+              webhook TEXT PRIMARY KEY,
+              last_event_id INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS webhook_deliveries (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              event_id INTEGER NOT NULL,
+              webhook TEXT NOT NULL,
+              status TEXT NOT NULL,
+              attempts INTEGER NOT NULL DEFAULT 0,
+              next_attempt_at_ms INTEGER NOT NULL,
+              last_error TEXT,
+              last_status_code INTEGER,
+              updated_at_ms INTEGER NOT NULL,
+              UNIQUE(event_id, webhook)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due
+              ON webhook_deliveries(status, next_attempt_at_ms)
+            "#,
+        )?;
+        self.apply_migration(
+            holder,
+            17,
+            "trace_verbosity",
+            r#"
+            CREATE TABLE IF NOT EXISTS trace_levels (
+              scope TEXT PRIMARY KEY,
+              level TEXT NOT NULL,
+              updated_at_ms INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS trace_counters (
+              goal_id TEXT NOT NULL,
+              event_type TEXT NOT NULL,
+              seen INTEGER NOT NULL DEFAULT 0,
+              kept INTEGER NOT NULL DEFAULT 0,
+              PRIMARY KEY(goal_id, event_type)
+            )
+            "#,
+        )?;
+        self.apply_migration(
+            holder,
+            18,
+            "maintenance_runs",
+            r#"
+            CREATE TABLE IF NOT EXISTS maintenance_runs (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              trigger TEXT NOT NULL,
+              bytes_before INTEGER NOT NULL,
+              bytes_after INTEGER NOT NULL,
+              vacuumed INTEGER NOT NULL,
+              integrity TEXT NOT NULL,
+              duration_ms INTEGER NOT NULL,
+              created_at_ms INTEGER NOT NULL
+            )
+            "#,
+        )?;
+        self.apply_migration(
+            holder,
+            19,
+            "writer_leases",
+            r#"
+            CREATE TABLE IF NOT EXISTS writer_leases (
+              name TEXT PRIMARY KEY,
+              holder TEXT NOT NULL,
+              endpoint TEXT,
+              acquired_at_ms INTEGER NOT NULL,
+              renewed_at_ms INTEGER NOT NULL,
+              expires_at_ms INTEGER NOT NULL
+            )
+            "#,
+        )?;
+        self.apply_migration(
+            holder,
+            20,
+            "channel_outbox",
+            r#"
+            CREATE TABLE IF NOT EXISTS channel_outbox (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              operator TEXT,
+              event_kind TEXT NOT NULL,
+              subject_id TEXT NOT NULL,
+              channel TEXT NOT NULL,
+              target TEXT NOT NULL,
+              message TEXT NOT NULL,
+              status TEXT NOT NULL,
+              attempts INTEGER NOT NULL DEFAULT 0,
+              next_attempt_at_ms INTEGER NOT NULL,
+              last_error TEXT,
+              created_at_ms INTEGER NOT NULL,
+              delivered_at_ms INTEGER
+            );
+            CREATE INDEX IF NOT EXISTS idx_channel_outbox_due
+              ON channel_outbox(status, next_attempt_at_ms);
+            "#,
+        )?;
+        self.apply_migration(
+            holder,
+            21,
+            "session_pins",
+            r#"
+            CREATE TABLE IF NOT EXISTS session_pins (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              session_id TEXT NOT NULL,
+              message_id INTEGER,
+              content TEXT NOT NULL,
+              pinned_by TEXT NOT NULL,
+              created_at_ms INTEGER NOT NULL,
+              FOREIGN KEY(session_id) REFERENCES sessions(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_session_pins_session
+              ON session_pins(session_id, id);
+            "#,
+        )?;
+        self.apply_migration(
+            holder,
+            22,
+            "risk_state_history",
+            r#"
+            CREATE TABLE IF NOT EXISTS risk_state_history (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              action TEXT NOT NULL,
+              risk_mode TEXT NOT NULL,
+              actor TEXT NOT NULL,
+              reason TEXT,
+              expires_at_ms INTEGER,
+              created_at_ms INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_risk_state_history_created
+              ON risk_state_history(created_at_ms);
+            INSERT INTO risk_state_history (action, risk_mode, actor, reason, created_at_ms)
+            SELECT CASE subject_id WHEN 'yolo' THEN 'enable' ELSE 'disable' END,
+                   subject_id,
+                   CASE WHEN instr(rest, ' ') > 0 THEN substr(rest, 1, instr(rest, ' ') - 1) ELSE rest END,
+                   'backfilled from lifecycle_events',
+                   created_at_ms
+            FROM (
+              SELECT id, subject_id, created_at_ms,
+                     substr(detail, instr(detail, 'changed_by=') + 11) AS rest
+              FROM lifecycle_events
+              WHERE kind = 'risk_mode_changed'
+            )
+            ORDER BY id;
+            "#,
+        )?;
+        self.apply_migration(
+            holder,
+            23,
+            "approval_rerequests",
+            r#"
+            ALTER TABLE approval_requests ADD COLUMN rerequested_from TEXT;
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            24,
+            "workspace_changes",
+            r#"
+            CREATE TABLE IF NOT EXISTS workspace_changes (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              goal_id TEXT NOT NULL,
+              tool_name TEXT NOT NULL,
+              path TEXT NOT NULL,
+              change_kind TEXT NOT NULL,
+              diff_stat TEXT NOT NULL,
+              merged_at_ms INTEGER,
+              created_at_ms INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_workspace_changes_goal
+              ON workspace_changes(goal_id);
+            CREATE INDEX IF NOT EXISTS idx_workspace_changes_unmerged
+              ON workspace_changes(path)
+              WHERE merged_at_ms IS NULL;
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            25,
+            "safe_mode_state",
+            r#"
+            CREATE TABLE IF NOT EXISTS safe_mode_state (
+              id INTEGER PRIMARY KEY CHECK (id = 1),
+              active INTEGER NOT NULL DEFAULT 0,
+              reason TEXT,
+              changed_by TEXT,
+              changed_at_ms INTEGER,
+              goal_watermark INTEGER NOT NULL DEFAULT 0,
+              last_panic_at_ms INTEGER,
+              last_panic_detail TEXT,
+              panic_pending INTEGER NOT NULL DEFAULT 0
+            );
+            INSERT OR IGNORE INTO safe_mode_state (id) VALUES (1);
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            26,
+            "worker_heartbeats",
+            r#"
+            CREATE TABLE IF NOT EXISTS worker_heartbeats (
+              name TEXT PRIMARY KEY,
+              holder TEXT NOT NULL,
+              interval_ms INTEGER NOT NULL,
+              beat_at_ms INTEGER NOT NULL
+            );
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            27,
+            "session_template_prompts",
+            r#"
+            CREATE TABLE IF NOT EXISTS session_template_prompts (
+              session_id TEXT PRIMARY KEY,
+              template TEXT NOT NULL,
+              values_json TEXT NOT NULL,
+              created_at_ms INTEGER NOT NULL,
+              FOREIGN KEY(session_id) REFERENCES sessions(id)
+            );
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            28,
+            "session_clarifications",
+            r#"
+            CREATE TABLE IF NOT EXISTS session_clarifications (
+              session_id TEXT PRIMARY KEY,
+              goal_id TEXT NOT NULL,
+              request_text TEXT NOT NULL,
+              question TEXT NOT NULL,
+              created_at_ms INTEGER NOT NULL,
+              FOREIGN KEY(session_id) REFERENCES sessions(id),
+              FOREIGN KEY(goal_id) REFERENCES goals(id)
+            );
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            29,
+            "session_step_cache",
+            r#"
+            CREATE TABLE IF NOT EXISTS session_step_cache (
+              session_id TEXT NOT NULL,
+              tool_name TEXT NOT NULL,
+              input TEXT NOT NULL,
+              step_id TEXT NOT NULL,
+              status TEXT NOT NULL,
+              output TEXT NOT NULL,
+              created_at_ms INTEGER NOT NULL,
+              PRIMARY KEY(session_id, tool_name, input),
+              FOREIGN KEY(session_id) REFERENCES sessions(id)
+            );
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            30,
+            "structured_reflections",
+            r#"
+            ALTER TABLE episodic_memories ADD COLUMN reflection_json TEXT;
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            31,
+            "session_env",
+            r#"
+            CREATE TABLE IF NOT EXISTS session_env (
+              session_id TEXT NOT NULL,
+              key TEXT NOT NULL,
+              value TEXT NOT NULL,
+              updated_at_ms INTEGER NOT NULL,
+              PRIMARY KEY(session_id, key),
+              FOREIGN KEY(session_id) REFERENCES sessions(id)
+            );
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            32,
+            "session_pending_inputs",
+            r#"
+            CREATE TABLE IF NOT EXISTS session_pending_inputs (
+              session_id TEXT PRIMARY KEY,
+              goal_id TEXT NOT NULL,
+              step_id TEXT NOT NULL,
+              question TEXT NOT NULL,
+              request_text TEXT NOT NULL,
+              candidate_id TEXT NOT NULL,
+              results_json TEXT NOT NULL,
+              created_at_ms INTEGER NOT NULL,
+              expires_at_ms INTEGER NOT NULL,
+              FOREIGN KEY(session_id) REFERENCES sessions(id),
+              FOREIGN KEY(goal_id) REFERENCES goals(id)
+            );
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            33,
+            "blobs",
+            r#"
+            CREATE TABLE IF NOT EXISTS blobs (
+              key TEXT PRIMARY KEY,
+              backend TEXT NOT NULL,
+              size_bytes INTEGER NOT NULL,
+              created_at_ms INTEGER NOT NULL
+            );
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            34,
+            "remote_goals",
+            r#"
+            CREATE TABLE IF NOT EXISTS remote_goals (
+              goal_id TEXT PRIMARY KEY,
+              peer TEXT NOT NULL,
+              remote_goal_id TEXT NOT NULL,
+              remote_status TEXT NOT NULL,
+              last_trace_id INTEGER NOT NULL DEFAULT 0,
+              created_at_ms INTEGER NOT NULL,
+              updated_at_ms INTEGER NOT NULL,
+              FOREIGN KEY(goal_id) REFERENCES goals(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_remote_goals_status ON remote_goals(remote_status);
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            35,
+            "goal_priorities",
+            r#"
+            ALTER TABLE goals ADD COLUMN priority TEXT NOT NULL DEFAULT 'normal';
+            CREATE TABLE IF NOT EXISTS goal_checkpoints (
+              goal_id TEXT PRIMARY KEY,
+              session_id TEXT NOT NULL,
+              channel TEXT NOT NULL,
+              actor_id TEXT NOT NULL,
+              request_text TEXT NOT NULL,
+              candidate_id TEXT NOT NULL,
+              results_json TEXT NOT NULL,
+              preempted_by TEXT NOT NULL,
+              created_at_ms INTEGER NOT NULL,
+              FOREIGN KEY(goal_id) REFERENCES goals(id)
+            );
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            36,
+            "queued_goals",
+            r#"
+            CREATE TABLE IF NOT EXISTS queued_goals (
+              goal_id TEXT PRIMARY KEY,
+              session_id TEXT NOT NULL,
+              channel TEXT NOT NULL,
+              actor_id TEXT NOT NULL,
+              request_text TEXT NOT NULL,
+              token_budget INTEGER,
+              preferred_candidate TEXT,
+              created_at_ms INTEGER NOT NULL,
+              FOREIGN KEY(goal_id) REFERENCES goals(id)
+            );
+            "#,
+        )?;
+
+        // External-content FTS5 indexes: the text stays in the source tables and triggers
+        // keep the indexes in step with every insert, update, and delete.
+        self.apply_migration(
+            holder,
+            37,
+            "memory_fts",
+            r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS trace_events_fts
+              USING fts5(detail, content='trace_events', content_rowid='id');
+            CREATE TRIGGER IF NOT EXISTS trace_events_fts_ai AFTER INSERT ON trace_events BEGIN
+              INSERT INTO trace_events_fts(rowid, detail) VALUES (new.id, new.detail);
+            END;
+            CREATE TRIGGER IF NOT EXISTS trace_events_fts_ad AFTER DELETE ON trace_events BEGIN
+              INSERT INTO trace_events_fts(trace_events_fts, rowid, detail) VALUES ('delete', old.id, old.detail);
+            END;
+            CREATE TRIGGER IF NOT EXISTS trace_events_fts_au AFTER UPDATE OF detail ON trace_events BEGIN
+              INSERT INTO trace_events_fts(trace_events_fts, rowid, detail) VALUES ('delete', old.id, old.detail);
+              INSERT INTO trace_events_fts(rowid, detail) VALUES (new.id, new.detail);
+            END;
+            INSERT INTO trace_events_fts(trace_events_fts) VALUES ('rebuild');
+            CREATE VIRTUAL TABLE IF NOT EXISTS episodic_memories_fts
+              USING fts5(summary, content='episodic_memories', content_rowid='id');
+            CREATE TRIGGER IF NOT EXISTS episodic_memories_fts_ai AFTER INSERT ON episodic_memories BEGIN
+              INSERT INTO episodic_memories_fts(rowid, summary) VALUES (new.id, new.summary);
+            END;
+            CREATE TRIGGER IF NOT EXISTS episodic_memories_fts_ad AFTER DELETE ON episodic_memories BEGIN
+              INSERT INTO episodic_memories_fts(episodic_memories_fts, rowid, summary) VALUES ('delete', old.id, old.summary);
+            END;
+            CREATE TRIGGER IF NOT EXISTS episodic_memories_fts_au AFTER UPDATE OF summary ON episodic_memories BEGIN
+              INSERT INTO episodic_memories_fts(episodic_memories_fts, rowid, summary) VALUES ('delete', old.id, old.summary);
+              INSERT INTO episodic_memories_fts(rowid, summary) VALUES (new.id, new.summary);
+            END;
+            INSERT INTO episodic_memories_fts(episodic_memories_fts) VALUES ('rebuild');
+            CREATE VIRTUAL TABLE IF NOT EXISTS session_messages_fts
+              USING fts5(content, content='session_messages', content_rowid='id');
+            CREATE TRIGGER IF NOT EXISTS session_messages_fts_ai AFTER INSERT ON session_messages BEGIN
+              INSERT INTO session_messages_fts(rowid, content) VALUES (new.id, new.content);
+            END;
+            CREATE TRIGGER IF NOT EXISTS session_messages_fts_ad AFTER DELETE ON session_messages BEGIN
+              INSERT INTO session_messages_fts(session_messages_fts, rowid, content) VALUES ('delete', old.id, old.content);
+            END;
+            CREATE TRIGGER IF NOT EXISTS session_messages_fts_au AFTER UPDATE OF content ON session_messages BEGIN
+              INSERT INTO session_messages_fts(session_messages_fts, rowid, content) VALUES ('delete', old.id, old.content);
+              INSERT INTO session_messages_fts(rowid, content) VALUES (new.id, new.content);
+            END;
+            INSERT INTO session_messages_fts(session_messages_fts) VALUES ('rebuild');
+            "#,
+        )?;
+
+        // Vectors are filled in lazily by recall, so switching embedders only re-embeds.
+        self.apply_migration(
+            holder,
+            38,
+            "memory_embeddings",
+            r#"
+            CREATE TABLE IF NOT EXISTS memory_embeddings (
+              kind TEXT NOT NULL,
+              source_id INTEGER NOT NULL,
+              embedder TEXT NOT NULL,
+              vector BLOB NOT NULL,
+              created_at_ms INTEGER NOT NULL,
+              PRIMARY KEY(kind, source_id, embedder)
+            );
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            39,
+            "connector_io_log",
+            r#"
+            CREATE TABLE IF NOT EXISTS connector_io_log (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              connector_id TEXT NOT NULL,
+              tool_name TEXT NOT NULL,
+              method TEXT NOT NULL,
+              path TEXT NOT NULL,
+              status INTEGER NULL,
+              response_bytes INTEGER NOT NULL,
+              latency_ms INTEGER NOT NULL,
+              error TEXT NULL,
+              created_at_ms INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_connector_io_log_connector
+              ON connector_io_log(connector_id, created_at_ms);
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            40,
+            "connector_response_cache",
+            r#"
+            CREATE TABLE IF NOT EXISTS connector_response_cache (
+              connector_id TEXT NOT NULL,
+              tool_name TEXT NOT NULL,
+              input_hash TEXT NOT NULL,
+              output_json TEXT NOT NULL,
+              metadata_json TEXT NOT NULL,
+              expires_at_ms INTEGER NOT NULL,
+              created_at_ms INTEGER NOT NULL,
+              PRIMARY KEY(connector_id, tool_name, input_hash)
+            );
+            CREATE TABLE IF NOT EXISTS connector_cache_stats (
+              connector_id TEXT NOT NULL,
+              tool_name TEXT NOT NULL,
+              hits INTEGER NOT NULL DEFAULT 0,
+              misses INTEGER NOT NULL DEFAULT 0,
+              PRIMARY KEY(connector_id, tool_name)
+            );
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            41,
+            "content_provenance",
+            r#"
+            CREATE TABLE IF NOT EXISTS content_provenance (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              goal_id TEXT NOT NULL,
+              tool_name TEXT NOT NULL,
+              path TEXT NOT NULL,
+              model TEXT NOT NULL,
+              prompt_hash TEXT NOT NULL,
+              created_at_ms INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_content_provenance_path
+              ON content_provenance(path, created_at_ms);
+            CREATE INDEX IF NOT EXISTS idx_content_provenance_goal
+              ON content_provenance(goal_id);
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            42,
+            "model_plans",
+            r#"
+            CREATE TABLE IF NOT EXISTS model_plans (
+              goal_id TEXT PRIMARY KEY,
+              planner TEXT NOT NULL,
+              response TEXT NOT NULL,
+              created_at_ms INTEGER NOT NULL
+            );
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            43,
+            "goal_labels",
+            r#"
+            CREATE TABLE IF NOT EXISTS goal_labels (
+              goal_id TEXT NOT NULL,
+              label TEXT NOT NULL,
+              source TEXT NOT NULL,
+              created_at_ms INTEGER NOT NULL,
+              PRIMARY KEY(goal_id, label),
+              FOREIGN KEY(goal_id) REFERENCES goals(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_goal_labels_label
+              ON goal_labels(label, goal_id);
+            CREATE TABLE IF NOT EXISTS goal_filters (
+              operator TEXT NOT NULL,
+              name TEXT NOT NULL,
+              terms TEXT NOT NULL,
+              created_at_ms INTEGER NOT NULL,
+              PRIMARY KEY(operator, name)
+            );
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            44,
+            "goal_archives",
+            r#"
+            ALTER TABLE goals ADD COLUMN archive_ref TEXT;
+            ALTER TABLE goals ADD COLUMN archived_at_ms INTEGER;
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            45,
+            "session_usage",
+            r#"
+            CREATE TABLE IF NOT EXISTS session_usage (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              session_id TEXT NOT NULL,
+              channel TEXT NOT NULL,
+              peer_id TEXT NOT NULL,
+              goal_id TEXT,
+              role TEXT NOT NULL,
+              tokens INTEGER NOT NULL,
+              cost_usd REAL NOT NULL,
+              created_at_ms INTEGER NOT NULL,
+              FOREIGN KEY(session_id) REFERENCES sessions(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_session_usage_session
+              ON session_usage(session_id);
+            CREATE INDEX IF NOT EXISTS idx_session_usage_peer
+              ON session_usage(channel, peer_id, created_at_ms);
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            46,
+            "session_close",
+            r#"
+            ALTER TABLE sessions ADD COLUMN closed_at_ms INTEGER;
+            CREATE INDEX IF NOT EXISTS idx_sessions_open_peer
+              ON sessions(channel, peer_id, updated_at)
+              WHERE closed_at_ms IS NULL;
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            47,
+            "model_response_cache",
+            r#"
+            CREATE TABLE IF NOT EXISTS model_response_cache (
+              key TEXT PRIMARY KEY,
+              model TEXT NOT NULL,
+              response TEXT NOT NULL,
+              hits INTEGER NOT NULL DEFAULT 0,
+              created_at_ms INTEGER NOT NULL,
+              expires_at_ms INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_model_response_cache_expiry
+              ON model_response_cache(expires_at_ms);
+            ALTER TABLE session_usage ADD COLUMN model_calls INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE session_usage ADD COLUMN model_cache_hits INTEGER NOT NULL DEFAULT 0;
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            48,
+            "model_provider_state",
+            r#"
+            CREATE TABLE IF NOT EXISTS model_provider_state (
+              id INTEGER PRIMARY KEY CHECK (id = 1),
+              degraded INTEGER NOT NULL DEFAULT 0,
+              consecutive_failures INTEGER NOT NULL DEFAULT 0,
+              last_error TEXT,
+              last_failure_at_ms INTEGER,
+              degraded_since_ms INTEGER
+            );
+            INSERT OR IGNORE INTO model_provider_state (id) VALUES (1);
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            49,
+            "planner_experiments",
+            r#"
+            ALTER TABLE run_plans ADD COLUMN experiment TEXT;
+            ALTER TABLE run_plans ADD COLUMN variant TEXT;
+            CREATE INDEX IF NOT EXISTS idx_run_plans_experiment
+              ON run_plans(experiment, variant);
+            CREATE TABLE IF NOT EXISTS goal_feedback (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              goal_id TEXT NOT NULL,
+              actor_id TEXT NOT NULL,
+              rating INTEGER NOT NULL CHECK (rating IN (-1, 1)),
+              comment TEXT,
+              created_at_ms INTEGER NOT NULL,
+              FOREIGN KEY(goal_id) REFERENCES goals(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_goal_feedback_goal ON goal_feedback(goal_id);
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            50,
+            "peer_memory_consent",
+            r#"
+            CREATE TABLE IF NOT EXISTS peer_memory_consent (
+              channel TEXT NOT NULL,
+              peer_id TEXT NOT NULL,
+              granted_at_ms INTEGER NOT NULL,
+              PRIMARY KEY (channel, peer_id)
+            );
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            51,
+            "access_log",
+            r#"
+            CREATE TABLE IF NOT EXISTS access_log (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              at_ms INTEGER NOT NULL,
+              channel TEXT NOT NULL,
+              actor_id TEXT NOT NULL,
+              remote_addr TEXT,
+              kind TEXT NOT NULL,
+              action TEXT NOT NULL,
+              outcome TEXT NOT NULL,
+              anomaly TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_access_log_actor
+              ON access_log(channel, actor_id, at_ms);
+            "#,
+        )?;
+
+        // Lets a model swap behind an unchanged embedder id fail loudly instead of
+        // scoring vectors of different sizes against each other.
+        self.apply_migration(
+            holder,
+            52,
+            "embedding_namespaces",
+            r#"
+            CREATE TABLE IF NOT EXISTS embedding_namespaces (
+              namespace TEXT NOT NULL,
+              embedder TEXT NOT NULL,
+              dimensions INTEGER NOT NULL,
+              updated_at_ms INTEGER NOT NULL,
+              PRIMARY KEY(namespace, embedder)
+            );
+            "#,
+        )?;
+
+        // Quorum votes move here, so votes and final decisions share one ledger. The old
+        // `approval_votes` table is left in place so the copy can be replayed.
+        self.apply_migration(
+            holder,
+            53,
+            "approval_decisions",
+            r#"
+            CREATE TABLE IF NOT EXISTS approval_decisions (
+              approval_id TEXT NOT NULL,
+              approver TEXT NOT NULL,
+              decision TEXT NOT NULL,
+              created_at_ms INTEGER NOT NULL,
+              PRIMARY KEY(approval_id, approver)
+            );
+            INSERT OR IGNORE INTO approval_decisions (approval_id, approver, decision, created_at_ms)
+              SELECT approval_id, voter, 'approve', created_at_ms FROM approval_votes;
+            "#,
+        )?;
+
+        self.apply_migration(
+            holder,
+            54,
+            "approval_original_input",
+            "ALTER TABLE approval_requests ADD COLUMN original_input TEXT",
+        )?;
+
+        self.conn.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_goals_dedupe_key
+             ON goals(dedupe_key)
+             WHERE dedupe_key IS NOT NULL",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_tool_runs_approval_id
+             ON tool_runs(approval_id)
+             WHERE approval_id IS NOT NULL",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_sessions_channel_peer_updated
+             ON sessions(channel, peer_id, updated_at DESC)",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_goals_session_id
+             ON goals(session_id)",
+            [],
+        )?;
+
+        self.conn.execute(
+            "UPDATE approval_requests
+             SET nonce = COALESCE(nonce, id),
+                 expires_at_ms = COALESCE(expires_at_ms, CAST((julianday(created_at) - 2440587.5) * 86400000 AS INTEGER) + 300000)",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    fn apply_migration(&self, holder: &str, version: i64, name: &str, sql: &str) -> Result<()> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT 1 FROM schema_migrations WHERE version = ?1 LIMIT 1")?;
+        let mut rows = stmt.query(params![version])?;
+        if rows.next()?.is_some() {
+            return Ok(());
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        let now = now_epoch_ms();
+        let renewed = tx.execute(
+            "UPDATE migration_lock SET expires_at_ms = ?1 WHERE id = 1 AND holder = ?2",
+            params![now + MIGRATION_LOCK_TTL.as_millis() as i64, holder],
+        )?;
+        if renewed == 0 {
+            bail!("migration lock lost before migration {version} ({name})");
+        }
+        let mut statement = String::new();
+        for raw in sql.split(';') {
+            statement.push_str(raw);
+            let upper = statement.trim().to_ascii_uppercase();
+            if upper.is_empty() {
+                statement.clear();
+                continue;
+            }
+            // A trigger body holds `;`-terminated statements of its own; read on to its END.
+            if upper.starts_with("CREATE TRIGGER") && !upper.ends_with("END") {
+                statement.push(';');
+                continue;
+            }
+            let trimmed = std::mem::take(&mut statement);
+            let trimmed = trimmed.trim();
             if let Err(err) = tx.execute(trimmed, []) {
                 // Migrations are written to be backward-compatible with existing DBs.
                 // Duplicate-column style errors are safe to ignore.
@@ -533,1330 +2487,4693 @@ impl MemoryStore {
                 }
             }
         }
-        tx.execute(
-            "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
-            params![version, name],
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+            params![version, name],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn create_goal(&self, goal: &Goal) -> Result<()> {
+        self.create_goal_for_session(goal, None)
+    }
+
+    pub fn create_goal_for_session(&self, goal: &Goal, session_id: Option<&str>) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO goals (id, description, status, dedupe_key, session_id, priority)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                goal.id,
+                goal.description,
+                goal.status.as_str(),
+                goal.dedupe_key,
+                session_id,
+                goal.priority.as_str()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Stores a goal that was planned but not run: the goal, its traces, and the selected
+    /// candidate's steps as `planned`, or `planned_needs_approval` for the ids in
+    /// `gated_step_ids`. Returns the `run_plans` id.
+    pub fn persist_planned_goal(
+        &self,
+        goal: &Goal,
+        session_id: Option<&str>,
+        plan: &TaskPlan,
+        gated_step_ids: &[&str],
+        traces: &[TraceEvent],
+    ) -> Result<String> {
+        let selected = &plan.candidates[plan.selected_index];
+        let plan_id = Uuid::new_v4().to_string();
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO goals (id, description, status, dedupe_key, session_id, priority)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                goal.id,
+                goal.description,
+                goal.status.as_str(),
+                goal.dedupe_key,
+                session_id,
+                goal.priority.as_str()
+            ],
+        )?;
+        record_goal_lifecycle(&tx, &goal.id, &goal.status)?;
+        tx.execute(
+            "INSERT INTO run_plans (id, goal_id, intent, selected_candidate_id, selected_score)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                plan_id,
+                goal.id,
+                format!("{:?}", plan.intent),
+                selected.id,
+                selected.score
+            ],
+        )?;
+        for step in &selected.steps {
+            let status = if gated_step_ids.contains(&step.id.as_str()) {
+                "planned_needs_approval"
+            } else {
+                "planned"
+            };
+            tx.execute(
+                "INSERT INTO run_steps
+                 (id, goal_id, plan_id, step_id, tool_name, permission, input, status, output)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, '')",
+                params![
+                    Uuid::new_v4().to_string(),
+                    goal.id,
+                    plan_id,
+                    step.id,
+                    step.tool_name,
+                    step.permission.as_str(),
+                    step.input,
+                    status
+                ],
+            )?;
+        }
+        for trace in traces {
+            insert_trace(&tx, trace)?;
+        }
+        tx.commit()?;
+        Ok(plan_id)
+    }
+
+    pub fn persist_run_bundle(
+        &mut self,
+        bundle: RunPersistenceBundle<'_>,
+    ) -> Result<RunPersistenceOutcome> {
+        let run = bundle.run;
+        let now_ms = now_epoch_ms();
+        let approval_expires_at_ms = now_ms.saturating_add(bundle.approval_ttl_ms as i64);
+        let selected = &run.plan.candidates[run.plan.selected_index];
+        let plan_id = Uuid::new_v4().to_string();
+        let mut step_outcomes = std::collections::HashMap::<&str, &StepResult>::new();
+        for result in &run.step_results {
+            step_outcomes.insert(result.step_id.as_str(), result);
+        }
+        let approval_routes = if run.pending_approval.is_some() {
+            self.notification_routes("approval_requested")?
+        } else {
+            Vec::new()
+        };
+        let failure_routes = if matches!(run.goal.status, GoalStatus::Failed) {
+            self.notification_routes("goal_failed")?
+        } else {
+            Vec::new()
+        };
+        let mut stored_outputs = std::collections::HashMap::<&str, String>::new();
+        for result in &run.step_results {
+            stored_outputs.insert(
+                result.step_id.as_str(),
+                self.offload_output(&result.output)?,
+            );
+        }
+
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT OR IGNORE INTO goals (id, description, status, dedupe_key, priority)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                run.goal.id,
+                run.goal.description,
+                run.goal.status.as_str(),
+                run.goal.dedupe_key,
+                run.goal.priority.as_str()
+            ],
+        )?;
+        tx.execute(
+            "UPDATE goals SET status = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![run.goal.status.as_str(), run.goal.id],
+        )?;
+        record_goal_lifecycle(&tx, &run.goal.id, &run.goal.status)?;
+        tx.execute(
+            "INSERT INTO run_plans
+             (id, goal_id, intent, selected_candidate_id, selected_score, experiment, variant)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                plan_id,
+                run.goal.id,
+                format!("{:?}", run.plan.intent),
+                selected.id,
+                selected.score,
+                bundle.experiment.map(|arm| arm.name.as_str()),
+                bundle.experiment.map(|arm| arm.variant.as_str())
+            ],
+        )?;
+
+        for step in &selected.steps {
+            let outcome = step_outcomes.get(step.id.as_str());
+            let status = if outcome.is_some() {
+                "executed"
+            } else if run
+                .pending_input
+                .as_ref()
+                .is_some_and(|pending| pending.step_id == step.id)
+            {
+                "awaiting_input"
+            } else if run
+                .pending_approval
+                .as_ref()
+                .map(|pending| pending.tool_name.as_str())
+                == Some(step.tool_name.as_str())
+            {
+                "blocked_pending_approval"
+            } else if run
+                .pending_approval
+                .as_ref()
+                .is_some_and(|pending| pending.tool_name == CANARY_CONTINUE_TOOL)
+            {
+                "pending_canary"
+            } else if run.preempted_by.is_some() {
+                "paused"
+            } else {
+                "skipped"
+            };
+            let output = stored_outputs
+                .get(step.id.as_str())
+                .cloned()
+                .unwrap_or_default();
+            tx.execute(
+                "INSERT INTO run_steps
+                 (id, goal_id, plan_id, step_id, tool_name, permission, input, status, output)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    Uuid::new_v4().to_string(),
+                    run.goal.id,
+                    plan_id,
+                    step.id,
+                    step.tool_name,
+                    step.permission.as_str(),
+                    step.input,
+                    status,
+                    output
+                ],
+            )?;
+        }
+
+        for trace in &run.traces {
+            insert_trace(&tx, trace)?;
+        }
+
+        let approval_id = persist_pending_approval(
+            &tx,
+            &run.goal.id,
+            run.pending_approval.as_ref(),
+            bundle.source,
+            bundle.requested_by,
+            approval_expires_at_ms,
+        )?;
+
+        // Channel sends are queued here so a crash after commit cannot lose them.
+        let mut notification_outbox_ids = Vec::new();
+        if let (Some(approval_id), Some(pending)) =
+            (approval_id.as_deref(), run.pending_approval.as_ref())
+        {
+            notification_outbox_ids.extend(enqueue_notifications(
+                &tx,
+                &approval_routes,
+                "approval_requested",
+                approval_id,
+                &format!(
+                    "approval {} pending: {} ({}) requested by {}",
+                    approval_id,
+                    pending.tool_name,
+                    pending.capability,
+                    bundle.requested_by.unwrap_or(bundle.source)
+                ),
+                now_ms,
+            )?);
+            let notice = approval_notice_message(
+                approval_id,
+                &pending.tool_name,
+                &pending.capability,
+                approval_expires_at_ms,
+                now_ms,
+            );
+            for target in &bundle.approval_notices {
+                notification_outbox_ids.push(insert_outbox(
+                    &tx,
+                    &approval_notice(approval_id, target, &notice),
+                    now_ms,
+                )?);
+            }
+        }
+        notification_outbox_ids.extend(enqueue_notifications(
+            &tx,
+            &failure_routes,
+            "goal_failed",
+            &run.goal.id,
+            &format!("goal {} failed: {}", run.goal.id, run.reflection),
+            now_ms,
+        )?);
+        let reply_outbox_id = match bundle.reply {
+            Some(mut reply) => {
+                if let Some(approval_id) = approval_id.as_deref() {
+                    reply
+                        .message
+                        .push_str(&format!(" approval_pending={approval_id}"));
+                }
+                Some(insert_outbox(&tx, &reply, now_ms + OUTBOX_REPLY_GRACE_MS)?)
+            }
+            None => None,
+        };
+
+        let reflection_json = bundle
+            .reflection
+            .map(|outcome| serde_json::to_string(&outcome.reflection))
+            .transpose()?;
+        tx.execute(
+            "INSERT INTO episodic_memories (goal_id, summary, source, reflection_json)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![run.goal.id, run.reflection, bundle.source, reflection_json],
+        )?;
+        if let Some(outcome) = bundle.reflection {
+            let strategy_name = &run.plan.candidates[run.plan.selected_index].id;
+            let confidence = if outcome.source == "model" { 0.7 } else { 0.5 };
+            for change in &outcome.reflection.strategy_changes {
+                tx.execute(
+                    "INSERT INTO procedural_strategies
+                       (strategy_name, strategy_body, confidence, source)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![
+                        strategy_name,
+                        change,
+                        confidence,
+                        format!("reflection:{}", outcome.source)
+                    ],
+                )?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(RunPersistenceOutcome {
+            approval_id,
+            notification_outbox_ids,
+            reply_outbox_id,
+        })
+    }
+
+    pub fn find_goal_by_dedupe_key(&self, dedupe_key: &str) -> Result<Option<StoredGoal>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, description, status, dedupe_key, priority
+             FROM goals
+             WHERE dedupe_key = ?1",
+        )?;
+        let mut rows = stmt.query(params![dedupe_key])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(StoredGoal {
+                id: row.get(0)?,
+                description: row.get(1)?,
+                status: row.get(2)?,
+                dedupe_key: row.get(3)?,
+                priority: row.get(4)?,
+            }));
+        }
+        Ok(None)
+    }
+
+    pub fn update_goal_description(&self, goal_id: &str, description: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE goals SET description = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![description, goal_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn update_goal_status(&self, goal_id: &str, status: GoalStatus) -> Result<()> {
+        let changed = self.conn.execute(
+            "UPDATE goals SET status = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![status.as_str(), goal_id],
+        )?;
+        if changed > 0 {
+            record_goal_lifecycle(&self.conn, goal_id, &status)?;
+        }
+        Ok(())
+    }
+
+    /// Stores the event unless the goal's trace verbosity samples it out.
+    pub fn add_trace_event(&self, event: &TraceEvent) -> Result<()> {
+        insert_trace(&self.conn, event)?;
+        Ok(())
+    }
+
+    /// Sets verbosity for `scope`: `default`, `channel:<name>`, or `goal:<id>`.
+    pub fn set_trace_level(&self, scope: &str, level: TraceVerbosity) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO trace_levels (scope, level, updated_at_ms) VALUES (?1, ?2, ?3)
+             ON CONFLICT(scope) DO UPDATE SET
+               level = excluded.level,
+               updated_at_ms = excluded.updated_at_ms",
+            params![scope, level.as_str(), now_epoch_ms()],
+        )?;
+        Ok(())
+    }
+
+    pub fn clear_trace_level(&self, scope: &str) -> Result<bool> {
+        let changed = self
+            .conn
+            .execute("DELETE FROM trace_levels WHERE scope = ?1", params![scope])?;
+        Ok(changed > 0)
+    }
+
+    pub fn list_trace_levels(&self) -> Result<Vec<(String, String)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT scope, level FROM trace_levels ORDER BY scope ASC")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Goal override, then the goal's session channel, then `default`, then `Normal`.
+    pub fn trace_verbosity_for_goal(&self, goal_id: &str) -> Result<TraceVerbosity> {
+        trace_verbosity_for_goal(&self.conn, goal_id)
+    }
+
+    /// `(event_type, seen, kept)` per event type recorded for a goal.
+    pub fn trace_sampling_stats(&self, goal_id: &str) -> Result<Vec<(String, i64, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT event_type, seen, kept
+             FROM trace_counters
+             WHERE goal_id = ?1
+             ORDER BY seen DESC, event_type ASC",
+        )?;
+        let rows = stmt.query_map(params![goal_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn get_goal(&self, goal_id: &str) -> Result<Option<StoredGoal>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, description, status, dedupe_key, priority FROM goals WHERE id = ?1",
+        )?;
+        let mut rows = stmt.query(params![goal_id])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(StoredGoal {
+                id: row.get(0)?,
+                description: row.get(1)?,
+                status: row.get(2)?,
+                dedupe_key: row.get(3)?,
+                priority: row.get(4)?,
+            }));
+        }
+        Ok(None)
+    }
+
+    pub fn list_goals(&self, limit: usize) -> Result<Vec<StoredGoal>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, description, status, dedupe_key, priority
+             FROM goals
+             ORDER BY updated_at DESC
+             LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(StoredGoal {
+                id: row.get(0)?,
+                description: row.get(1)?,
+                status: row.get(2)?,
+                dedupe_key: row.get(3)?,
+                priority: row.get(4)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// The newest goals matching `filter`, as [`Self::list_goals`] orders them.
+    pub fn list_goals_filtered(
+        &self,
+        filter: &GoalFilter,
+        limit: usize,
+    ) -> Result<Vec<StoredGoal>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, description, status, dedupe_key, priority
+             FROM goals
+             WHERE (?1 IS NULL OR status = ?1)
+               AND (SELECT COUNT(*) FROM goal_labels
+                    WHERE goal_labels.goal_id = goals.id
+                      AND label IN (SELECT value FROM json_each(?2))) = ?3
+             ORDER BY updated_at DESC
+             LIMIT ?4",
+        )?;
+        let rows = stmt.query_map(
+            params![
+                filter.status,
+                serde_json::to_string(&filter.labels)?,
+                filter.labels.len() as i64,
+                limit as i64
+            ],
+            |row| {
+                Ok(StoredGoal {
+                    id: row.get(0)?,
+                    description: row.get(1)?,
+                    status: row.get(2)?,
+                    dedupe_key: row.get(3)?,
+                    priority: row.get(4)?,
+                })
+            },
+        )?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Adds normalized labels to a goal, keeping the first source of any it already has.
+    /// Returns how many were new.
+    pub fn add_goal_labels(&self, goal_id: &str, labels: &[String], source: &str) -> Result<usize> {
+        let now_ms = now_epoch_ms();
+        let mut added = 0;
+        for label in labels {
+            added += self.conn.execute(
+                "INSERT OR IGNORE INTO goal_labels (goal_id, label, source, created_at_ms)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![goal_id, normalize_goal_label(label)?, source, now_ms],
+            )?;
+        }
+        Ok(added)
+    }
+
+    pub fn remove_goal_label(&self, goal_id: &str, label: &str) -> Result<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM goal_labels WHERE goal_id = ?1 AND label = ?2",
+            params![goal_id, normalize_goal_label(label)?],
+        )?;
+        Ok(removed > 0)
+    }
+
+    pub fn goal_labels(&self, goal_id: &str) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT label FROM goal_labels WHERE goal_id = ?1 ORDER BY label ASC")?;
+        let rows = stmt.query_map(params![goal_id], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Saves `filter` as `operator`'s preset `name`, replacing one of the same name.
+    pub fn save_goal_filter(&self, operator: &str, name: &str, filter: &GoalFilter) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            bail!("filter name must be one word");
+        }
+        self.conn.execute(
+            "INSERT INTO goal_filters (operator, name, terms, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(operator, name) DO UPDATE SET terms = excluded.terms",
+            params![operator, name, filter.terms(), now_epoch_ms()],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_goal_filter(&self, operator: &str, name: &str) -> Result<Option<GoalFilter>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT terms FROM goal_filters WHERE operator = ?1 AND name = ?2")?;
+        let mut rows = stmt.query(params![operator, name.trim()])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(GoalFilter::parse(&row.get::<_, String>(0)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn list_goal_filters(&self, operator: &str) -> Result<Vec<SavedGoalFilter>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name, terms FROM goal_filters WHERE operator = ?1 ORDER BY name ASC",
+        )?;
+        let rows = stmt.query_map(params![operator], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut filters = Vec::new();
+        for row in rows {
+            let (name, terms) = row?;
+            filters.push(SavedGoalFilter {
+                operator: operator.to_string(),
+                name,
+                filter: GoalFilter::parse(&terms)?,
+            });
+        }
+        Ok(filters)
+    }
+
+    pub fn remove_goal_filter(&self, operator: &str, name: &str) -> Result<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM goal_filters WHERE operator = ?1 AND name = ?2",
+            params![operator, name.trim()],
+        )?;
+        Ok(removed > 0)
+    }
+
+    pub fn get_traces(&self, goal_id: &str) -> Result<Vec<TraceEvent>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT goal_id, event_type, detail, risk_mode
+             FROM trace_events
+             WHERE goal_id = ?1
+             -- insertion order currently models execution order for the goal timeline
+             ORDER BY id ASC",
+        )?;
+
+        let rows = stmt.query_map(params![goal_id], |row| {
+            Ok(TraceEvent {
+                goal_id: row.get(0)?,
+                event_type: row.get(1)?,
+                detail: row.get(2)?,
+                risk_mode: row.get(3)?,
+            })
+        })?;
+
+        let traces = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(traces)
+    }
+
+    pub fn search_traces(&self, pattern: &str, limit: usize) -> Result<Vec<TraceEvent>> {
+        let like = format!("%{}%", pattern);
+        let mut stmt = self.conn.prepare(
+            "SELECT goal_id, event_type, detail, risk_mode
+             FROM trace_events
+             WHERE detail LIKE ?1 OR event_type LIKE ?1
+             ORDER BY id DESC
+             LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![like, limit as i64], |row| {
+            Ok(TraceEvent {
+                goal_id: row.get(0)?,
+                event_type: row.get(1)?,
+                detail: row.get(2)?,
+                risk_mode: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn list_recent_traces(&self, limit: usize) -> Result<Vec<TraceEvent>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT goal_id, event_type, detail, risk_mode
+             FROM trace_events
+             ORDER BY id DESC
+             LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(TraceEvent {
+                goal_id: row.get(0)?,
+                event_type: row.get(1)?,
+                detail: row.get(2)?,
+                risk_mode: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn trace_view_for_goal(
+        &self,
+        goal_id: &str,
+        view: TraceView,
+    ) -> Result<Vec<TraceViewRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, goal_id, event_type, detail, risk_mode, created_at
+             FROM trace_events
+             WHERE goal_id = ?1
+             ORDER BY id ASC",
+        )?;
+        let rows = stmt.query_map(params![goal_id], |row| trace_view_from_row(row, view))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn recent_trace_view(&self, limit: usize, view: TraceView) -> Result<Vec<TraceViewRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, goal_id, event_type, detail, risk_mode, created_at
+             FROM trace_events
+             ORDER BY id DESC
+             LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| trace_view_from_row(row, view))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Searches traces. The redacted view matches event types only, so a search cannot
+    /// reveal which masked details contain the pattern.
+    pub fn search_trace_view(
+        &self,
+        pattern: &str,
+        limit: usize,
+        view: TraceView,
+    ) -> Result<Vec<TraceViewRecord>> {
+        let like = format!("%{}%", pattern);
+        let filter = match view {
+            TraceView::Full => "detail LIKE ?1 OR event_type LIKE ?1",
+            TraceView::Redacted => "event_type LIKE ?1",
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, goal_id, event_type, detail, risk_mode, created_at
+             FROM trace_events
+             WHERE {filter}
+             ORDER BY id DESC
+             LIMIT ?2"
+        ))?;
+        let rows = stmt.query_map(params![like, limit as i64], |row| {
+            trace_view_from_row(row, view)
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Full-text search over trace details, episodic memories, and session messages, best
+    /// match first. Every word of `query` must appear; words are matched as terms, so FTS
+    /// operators in the query are searched for literally. Empty `kinds` searches all three.
+    pub fn search_memory(
+        &self,
+        query: &str,
+        kinds: &[MemoryKind],
+        limit: usize,
+    ) -> Result<Vec<MemoryHit>> {
+        let terms: Vec<String> = query
+            .split_whitespace()
+            .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+            .collect();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        let selects: Vec<String> = MemoryKind::ALL
+            .iter()
+            .filter(|kind| kinds.is_empty() || kinds.contains(kind))
+            .map(|kind| {
+                let (fts, source, owner) = kind.tables();
+                format!(
+                    "SELECT '{kind}', s.id, s.{owner}, snippet({fts}, 0, '[', ']', '...', 12),
+                            -bm25({fts}), s.created_at
+                     FROM {fts} JOIN {source} s ON s.id = {fts}.rowid
+                     WHERE {fts} MATCH ?1",
+                    kind = kind.as_str()
+                )
+            })
+            .collect();
+        let mut stmt = self.conn.prepare(&format!(
+            "{} ORDER BY 5 DESC LIMIT ?2",
+            selects.join(" UNION ALL ")
+        ))?;
+        let rows = stmt.query_map(params![terms.join(" "), limit as i64], |row| {
+            Ok(MemoryHit {
+                kind: MemoryKind::parse(&row.get::<_, String>(0)?).unwrap_or(MemoryKind::Trace),
+                id: row.get(1)?,
+                owner_id: row.get(2)?,
+                snippet: row.get(3)?,
+                score: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// The `top_k` semantic facts and episodic memories closest to `text` under the store's
+    /// embedder, best first. Only the latest value of each fact is considered. Memories not
+    /// yet embedded by this embedder are embedded first.
+    pub fn recall_similar(&self, text: &str, top_k: usize) -> Result<Vec<RecalledMemory>> {
+        if top_k == 0 || text.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        self.embed_pending_memories()?;
+        let embedder_id = self.embedder.id();
+        let query = self.embedder.embed(text)?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT m.kind, m.id, m.text, e.vector
+             FROM ({RECALL_SOURCES}) AS m
+             JOIN memory_embeddings e
+               ON e.kind = m.kind AND e.source_id = m.id AND e.embedder = ?1"
+        ))?;
+        let mut recalled = stmt
+            .query_map(params![embedder_id], |row| {
+                let vector: Vec<u8> = row.get(3)?;
+                Ok(RecalledMemory {
+                    kind: row.get(0)?,
+                    id: row.get(1)?,
+                    text: row.get(2)?,
+                    score: embedding::cosine(&query, &embedding::decode(&vector)),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        recalled.sort_by(|a, b| b.score.total_cmp(&a.score));
+        recalled.truncate(top_k);
+        Ok(recalled)
+    }
+
+    /// Embeds the recallable memories the store's embedder has no vector for yet,
+    /// `embeddings.batch_size` per request, and records the vector size of each namespace.
+    /// A batch whose vectors differ in size from the ones already stored for this embedder
+    /// is rejected before it is written. Returns how many memories were embedded.
+    pub fn embed_pending_memories(&self) -> Result<usize> {
+        let embedder_id = self.embedder.id();
+        let missing = {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT kind, id, namespace, text FROM ({RECALL_SOURCES}) AS m
+                 WHERE NOT EXISTS (
+                   SELECT 1 FROM memory_embeddings e
+                   WHERE e.kind = m.kind AND e.source_id = m.id AND e.embedder = ?1
+                 )
+                 ORDER BY kind, id"
+            ))?;
+            stmt.query_map(params![embedder_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        let mut dimensions: Option<usize> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT dimensions FROM embedding_namespaces WHERE embedder = ?1 LIMIT 1",
+            )?;
+            let mut rows = stmt.query(params![embedder_id])?;
+            match rows.next()? {
+                Some(row) => Some(row.get::<_, i64>(0)? as usize),
+                None => None,
+            }
+        };
+        for batch in missing.chunks(embedding::embedding_batch_size()) {
+            let texts: Vec<String> = batch.iter().map(|(_, _, _, text)| text.clone()).collect();
+            let vectors = self.embedder.embed_batch(&texts)?;
+            if vectors.len() != batch.len() {
+                bail!(
+                    "embedder {embedder_id} returned {} vectors for {} memories",
+                    vectors.len(),
+                    batch.len()
+                );
+            }
+            let expected = *dimensions.get_or_insert(vectors[0].len());
+            if let Some(vector) = vectors.iter().find(|vector| vector.len() != expected) {
+                bail!(
+                    "embedder {embedder_id} returned {} dimensions but its stored vectors have \
+                     {expected}; give the embedder a new id when its model changes",
+                    vector.len()
+                );
+            }
+            let now_ms = now_epoch_ms();
+            let tx = self.conn.unchecked_transaction()?;
+            for ((kind, id, namespace, _), vector) in batch.iter().zip(&vectors) {
+                tx.execute(
+                    "INSERT OR REPLACE INTO memory_embeddings
+                       (kind, source_id, embedder, vector, created_at_ms)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![kind, id, embedder_id, embedding::encode(vector), now_ms],
+                )?;
+                tx.execute(
+                    "INSERT INTO embedding_namespaces (namespace, embedder, dimensions, updated_at_ms)
+                     VALUES (?1, ?2, ?3, ?4)
+                     ON CONFLICT(namespace, embedder) DO UPDATE SET
+                       dimensions = excluded.dimensions,
+                       updated_at_ms = excluded.updated_at_ms",
+                    params![namespace, embedder_id, expected as i64, now_ms],
+                )?;
+            }
+            tx.commit()?;
+        }
+        Ok(missing.len())
+    }
+
+    /// Vector sizes recorded per namespace and embedder, by namespace.
+    pub fn embedding_namespaces(&self) -> Result<Vec<EmbeddingNamespace>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT namespace, embedder, dimensions, updated_at_ms
+             FROM embedding_namespaces
+             ORDER BY namespace, embedder",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(EmbeddingNamespace {
+                namespace: row.get(0)?,
+                embedder: row.get(1)?,
+                dimensions: row.get::<_, i64>(2)? as usize,
+                updated_at_ms: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn count_plans_for_goal(&self, goal_id: &str) -> Result<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(1) FROM run_plans WHERE goal_id = ?1",
+            params![goal_id],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    pub fn count_steps_for_goal(&self, goal_id: &str) -> Result<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(1) FROM run_steps WHERE goal_id = ?1",
+            params![goal_id],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    pub fn mark_blocked_step_executed_for_goal(
+        &self,
+        goal_id: &str,
+        tool_name: &str,
+        output: &str,
+    ) -> Result<usize> {
+        let changed = self.conn.execute(
+            "UPDATE run_steps
+             SET status = 'executed_after_approval', output = ?1
+             WHERE id = (
+               SELECT id
+               FROM run_steps
+               WHERE goal_id = ?2
+                 AND tool_name = ?3
+                 AND status = 'blocked_pending_approval'
+               ORDER BY created_at DESC
+               LIMIT 1
+             )",
+            params![output, goal_id, tool_name],
+        )?;
+        Ok(changed)
+    }
+
+    pub fn list_pending_canary_steps(&self, goal_id: &str) -> Result<Vec<RunStepRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, step_id, tool_name, permission, input, status
+             FROM run_steps
+             WHERE goal_id = ?1 AND status = 'pending_canary'
+             ORDER BY rowid ASC",
+        )?;
+        let rows = stmt.query_map(params![goal_id], |row| {
+            Ok(RunStepRecord {
+                id: row.get(0)?,
+                step_id: row.get(1)?,
+                tool_name: row.get(2)?,
+                permission: row.get(3)?,
+                input: row.get(4)?,
+                status: row.get(5)?,
+            })
+        })?;
+        let records = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(records)
+    }
+
+    pub fn update_run_step_status(&self, id: &str, status: &str, output: &str) -> Result<()> {
+        let output = self.offload_output(output)?;
+        self.conn.execute(
+            "UPDATE run_steps SET status = ?1, output = ?2 WHERE id = ?3",
+            params![status, output, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn count_active_goals(&self) -> Result<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(1)
+             FROM goals
+             WHERE status IN ('pending', 'awaiting_clarification', 'queued', 'planning', 'executing',
+                              'paused', 'waiting_for_model')",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    pub fn count_goals_waiting_for_model(&self) -> Result<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(1) FROM goals WHERE status = 'waiting_for_model'",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    pub fn get_or_create_active_session(
+        &self,
+        channel: &str,
+        peer_id: &str,
+    ) -> Result<SessionRecord> {
+        if let Some(existing) = self.get_latest_session_for_peer(channel, peer_id)? {
+            return Ok(existing);
+        }
+        self.create_session(channel, peer_id, None)
+    }
+
+    pub fn create_session(
+        &self,
+        channel: &str,
+        peer_id: &str,
+        model_override: Option<&str>,
+    ) -> Result<SessionRecord> {
+        let session = SessionRecord {
+            id: Uuid::new_v4().to_string(),
+            channel: channel.to_string(),
+            peer_id: peer_id.to_string(),
+            model_override: model_override.map(std::string::ToString::to_string),
+            usage_mode: "tokens".to_string(),
+            activation_mode: "always".to_string(),
+            compactions_count: 0,
+            queue_depth: 0,
+            stop_requested: false,
+            closed_at_ms: None,
+        };
+        self.conn.execute(
+            "INSERT INTO sessions
+             (id, channel, peer_id, model_override, usage_mode, activation_mode, compactions_count, queue_depth, stop_requested)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                session.id,
+                session.channel,
+                session.peer_id,
+                session.model_override,
+                session.usage_mode,
+                session.activation_mode,
+                session.compactions_count,
+                session.queue_depth,
+                if session.stop_requested { 1 } else { 0 }
+            ],
+        )?;
+        Ok(session)
+    }
+
+    pub fn get_latest_session_for_peer(
+        &self,
+        channel: &str,
+        peer_id: &str,
+    ) -> Result<Option<SessionRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, channel, peer_id, model_override, usage_mode, activation_mode, compactions_count, queue_depth, stop_requested, closed_at_ms
+             FROM sessions
+             WHERE channel = ?1 AND peer_id = ?2 AND closed_at_ms IS NULL
+             ORDER BY updated_at DESC, rowid DESC
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query(params![channel, peer_id])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(SessionRecord {
+                id: row.get(0)?,
+                channel: row.get(1)?,
+                peer_id: row.get(2)?,
+                model_override: row.get(3)?,
+                usage_mode: row.get(4)?,
+                activation_mode: row.get(5)?,
+                compactions_count: row.get(6)?,
+                queue_depth: row.get(7)?,
+                stop_requested: row.get::<_, i64>(8)? != 0,
+                closed_at_ms: row.get(9)?,
+            }));
+        }
+        Ok(None)
+    }
+
+    pub fn get_session(&self, session_id: &str) -> Result<Option<SessionRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, channel, peer_id, model_override, usage_mode, activation_mode, compactions_count, queue_depth, stop_requested, closed_at_ms
+             FROM sessions
+             WHERE id = ?1
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query(params![session_id])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(SessionRecord {
+                id: row.get(0)?,
+                channel: row.get(1)?,
+                peer_id: row.get(2)?,
+                model_override: row.get(3)?,
+                usage_mode: row.get(4)?,
+                activation_mode: row.get(5)?,
+                compactions_count: row.get(6)?,
+                queue_depth: row.get(7)?,
+                stop_requested: row.get::<_, i64>(8)? != 0,
+                closed_at_ms: row.get(9)?,
+            }));
+        }
+        Ok(None)
+    }
+
+    pub fn list_sessions(&self, limit: usize) -> Result<Vec<SessionRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, channel, peer_id, model_override, usage_mode, activation_mode, compactions_count, queue_depth, stop_requested, closed_at_ms
+             FROM sessions
+             ORDER BY updated_at DESC, rowid DESC
+             LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(SessionRecord {
+                id: row.get(0)?,
+                channel: row.get(1)?,
+                peer_id: row.get(2)?,
+                model_override: row.get(3)?,
+                usage_mode: row.get(4)?,
+                activation_mode: row.get(5)?,
+                compactions_count: row.get(6)?,
+                queue_depth: row.get(7)?,
+                stop_requested: row.get::<_, i64>(8)? != 0,
+                closed_at_ms: row.get(9)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn add_session_message(
+        &self,
+        session_id: &str,
+        role: &str,
+        content: &str,
+        compacted: bool,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO session_messages (session_id, role, content, compacted)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![session_id, role, content, if compacted { 1 } else { 0 }],
+        )?;
+        self.conn.execute(
+            "UPDATE sessions
+             SET updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?1",
+            params![session_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_session_messages(
+        &self,
+        session_id: &str,
+        limit: usize,
+    ) -> Result<Vec<SessionMessageRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, session_id, role, content, compacted
+             FROM session_messages
+             WHERE session_id = ?1
+             ORDER BY id DESC
+             LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![session_id, limit as i64], |row| {
+            Ok(SessionMessageRecord {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                role: row.get(2)?,
+                content: row.get(3)?,
+                compacted: row.get::<_, i64>(4)? != 0,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Pins `target` for the session. `#<id>` or a bare id naming one of the session's
+    /// messages pins that message; anything else is pinned as a fact.
+    pub fn pin_session_item(
+        &self,
+        session_id: &str,
+        target: &str,
+        pinned_by: &str,
+    ) -> Result<SessionPinRecord> {
+        let target = target.trim();
+        if target.is_empty() {
+            anyhow::bail!("nothing to pin");
+        }
+        let mut message = None;
+        if let Ok(message_id) = target.trim_start_matches('#').parse::<i64>() {
+            let mut stmt = self.conn.prepare(
+                "SELECT content FROM session_messages WHERE id = ?1 AND session_id = ?2",
+            )?;
+            let mut rows = stmt.query(params![message_id, session_id])?;
+            if let Some(row) = rows.next()? {
+                message = Some((message_id, row.get::<_, String>(0)?));
+            }
+        }
+        let (message_id, content) = match message {
+            Some((id, content)) => (Some(id), content),
+            None => (None, target.to_string()),
+        };
+        let created_at_ms = now_epoch_ms();
+        self.conn.execute(
+            "INSERT INTO session_pins (session_id, message_id, content, pinned_by, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![session_id, message_id, content, pinned_by, created_at_ms],
+        )?;
+        Ok(SessionPinRecord {
+            id: self.conn.last_insert_rowid(),
+            session_id: session_id.to_string(),
+            message_id,
+            content,
+            pinned_by: pinned_by.to_string(),
+            created_at_ms,
+        })
+    }
+
+    pub fn unpin_session_item(&self, session_id: &str, pin_id: i64) -> Result<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM session_pins WHERE id = ?1 AND session_id = ?2",
+            params![pin_id, session_id],
+        )?;
+        Ok(removed > 0)
+    }
+
+    pub fn list_session_pins(&self, session_id: &str) -> Result<Vec<SessionPinRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, session_id, message_id, content, pinned_by, created_at_ms
+             FROM session_pins
+             WHERE session_id = ?1
+             ORDER BY id ASC",
+        )?;
+        let rows = stmt.query_map(params![session_id], |row| {
+            Ok(SessionPinRecord {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                message_id: row.get(2)?,
+                content: row.get(3)?,
+                pinned_by: row.get(4)?,
+                created_at_ms: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Sets one variable of the session's tool environment.
+    pub fn set_session_env(&self, session_id: &str, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO session_env (session_id, key, value, updated_at_ms)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(session_id, key) DO UPDATE SET
+               value = excluded.value,
+               updated_at_ms = excluded.updated_at_ms",
+            params![session_id, key, value, now_epoch_ms()],
+        )?;
+        Ok(())
+    }
+
+    pub fn unset_session_env(&self, session_id: &str, key: &str) -> Result<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM session_env WHERE session_id = ?1 AND key = ?2",
+            params![session_id, key],
+        )?;
+        Ok(removed > 0)
+    }
+
+    pub fn session_env(&self, session_id: &str) -> Result<BTreeMap<String, String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT key, value
+             FROM session_env
+             WHERE session_id = ?1",
+        )?;
+        let rows = stmt.query_map(params![session_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<BTreeMap<_, _>>>()?)
+    }
+
+    /// Replaces the session's pending template prompt, if any.
+    pub fn set_template_prompt(
+        &self,
+        session_id: &str,
+        template: &str,
+        values: &[String],
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO session_template_prompts (session_id, template, values_json, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(session_id) DO UPDATE SET
+               template = excluded.template,
+               values_json = excluded.values_json,
+               created_at_ms = excluded.created_at_ms",
+            params![
+                session_id,
+                template,
+                serde_json::to_string(values)?,
+                now_epoch_ms()
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn template_prompt(&self, session_id: &str) -> Result<Option<TemplatePromptRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT session_id, template, values_json, created_at_ms
+             FROM session_template_prompts
+             WHERE session_id = ?1",
+        )?;
+        let mut rows = stmt.query(params![session_id])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+        let values_json: String = row.get(2)?;
+        Ok(Some(TemplatePromptRecord {
+            session_id: row.get(0)?,
+            template: row.get(1)?,
+            values: serde_json::from_str(&values_json)
+                .context("invalid session_template_prompts.values_json")?,
+            created_at_ms: row.get(3)?,
+        }))
+    }
+
+    pub fn clear_template_prompt(&self, session_id: &str) -> Result<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM session_template_prompts WHERE session_id = ?1",
+            params![session_id],
+        )?;
+        Ok(removed > 0)
+    }
+
+    pub fn set_clarification(
+        &self,
+        session_id: &str,
+        goal_id: &str,
+        request_text: &str,
+        question: &str,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO session_clarifications
+             (session_id, goal_id, request_text, question, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(session_id) DO UPDATE SET
+               goal_id = excluded.goal_id,
+               request_text = excluded.request_text,
+               question = excluded.question,
+               created_at_ms = excluded.created_at_ms",
+            params![session_id, goal_id, request_text, question, now_epoch_ms()],
+        )?;
+        Ok(())
+    }
+
+    pub fn clarification(&self, session_id: &str) -> Result<Option<ClarificationRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT session_id, goal_id, request_text, question, created_at_ms
+             FROM session_clarifications
+             WHERE session_id = ?1",
+        )?;
+        let mut rows = stmt.query(params![session_id])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+        Ok(Some(ClarificationRecord {
+            session_id: row.get(0)?,
+            goal_id: row.get(1)?,
+            request_text: row.get(2)?,
+            question: row.get(3)?,
+            created_at_ms: row.get(4)?,
+        }))
+    }
+
+    pub fn clear_clarification(&self, session_id: &str) -> Result<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM session_clarifications WHERE session_id = ?1",
+            params![session_id],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// Replaces the session's pending `AskUser` question, if any.
+    pub fn set_pending_input(&self, record: &PendingInputRecord) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO session_pending_inputs
+             (session_id, goal_id, step_id, question, request_text, candidate_id, results_json,
+              created_at_ms, expires_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT(session_id) DO UPDATE SET
+               goal_id = excluded.goal_id,
+               step_id = excluded.step_id,
+               question = excluded.question,
+               request_text = excluded.request_text,
+               candidate_id = excluded.candidate_id,
+               results_json = excluded.results_json,
+               created_at_ms = excluded.created_at_ms,
+               expires_at_ms = excluded.expires_at_ms",
+            params![
+                record.session_id,
+                record.goal_id,
+                record.step_id,
+                record.question,
+                record.request_text,
+                record.candidate_id,
+                serde_json::to_string(&record.results)?,
+                record.created_at_ms,
+                record.expires_at_ms
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn pending_input(&self, session_id: &str) -> Result<Option<PendingInputRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT session_id, goal_id, step_id, question, request_text, candidate_id,
+                    results_json, created_at_ms, expires_at_ms
+             FROM session_pending_inputs
+             WHERE session_id = ?1",
+        )?;
+        let mut rows = stmt.query(params![session_id])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+        let results_json: String = row.get(6)?;
+        Ok(Some(PendingInputRecord {
+            session_id: row.get(0)?,
+            goal_id: row.get(1)?,
+            step_id: row.get(2)?,
+            question: row.get(3)?,
+            request_text: row.get(4)?,
+            candidate_id: row.get(5)?,
+            results: serde_json::from_str(&results_json)
+                .context("invalid session_pending_inputs.results_json")?,
+            created_at_ms: row.get(7)?,
+            expires_at_ms: row.get(8)?,
+        }))
+    }
+
+    pub fn clear_pending_input(&self, session_id: &str) -> Result<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM session_pending_inputs WHERE session_id = ?1",
+            params![session_id],
+        )?;
+        Ok(removed > 0)
+    }
+
+    pub fn record_remote_goal(
+        &self,
+        goal_id: &str,
+        peer: &str,
+        remote_goal_id: &str,
+        remote_status: &str,
+    ) -> Result<()> {
+        let now = now_epoch_ms();
+        self.conn.execute(
+            "INSERT INTO remote_goals
+             (goal_id, peer, remote_goal_id, remote_status, created_at_ms, updated_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+            params![goal_id, peer, remote_goal_id, remote_status, now],
+        )?;
+        Ok(())
+    }
+
+    pub fn update_remote_goal(
+        &self,
+        goal_id: &str,
+        remote_status: &str,
+        last_trace_id: i64,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE remote_goals
+             SET remote_status = ?2, last_trace_id = ?3, updated_at_ms = ?4
+             WHERE goal_id = ?1",
+            params![goal_id, remote_status, last_trace_id, now_epoch_ms()],
+        )?;
+        Ok(())
+    }
+
+    pub fn remote_goal(&self, goal_id: &str) -> Result<Option<RemoteGoalRecord>> {
+        Ok(self
+            .query_remote_goals("WHERE goal_id = ?1", params![goal_id])?
+            .pop())
+    }
+
+    /// Forwarded goals whose remote status is not yet completed, failed, or cancelled.
+    pub fn list_active_remote_goals(&self) -> Result<Vec<RemoteGoalRecord>> {
+        self.query_remote_goals(
+            "WHERE remote_status NOT IN ('completed', 'failed', 'cancelled')
+             ORDER BY created_at_ms ASC",
+            params![],
+        )
+    }
+
+    pub fn list_remote_goals(&self, limit: usize) -> Result<Vec<RemoteGoalRecord>> {
+        self.query_remote_goals(
+            "ORDER BY created_at_ms DESC LIMIT ?1",
+            params![limit as i64],
+        )
+    }
+
+    fn query_remote_goals(
+        &self,
+        filter: &str,
+        args: impl rusqlite::Params,
+    ) -> Result<Vec<RemoteGoalRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT goal_id, peer, remote_goal_id, remote_status, last_trace_id, created_at_ms,
+                    updated_at_ms
+             FROM remote_goals {filter}"
+        ))?;
+        let rows = stmt.query_map(args, |row| {
+            Ok(RemoteGoalRecord {
+                goal_id: row.get(0)?,
+                peer: row.get(1)?,
+                remote_goal_id: row.get(2)?,
+                remote_status: row.get(3)?,
+                last_trace_id: row.get(4)?,
+                created_at_ms: row.get(5)?,
+                updated_at_ms: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn set_goal_priority(&self, goal_id: &str, priority: GoalPriority) -> Result<bool> {
+        let changed = self.conn.execute(
+            "UPDATE goals SET priority = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![priority.as_str(), goal_id],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// An urgent goal other than `goal_id` that is queued or running. Goals a crashed process
+    /// left unfinished stop counting after ten minutes without an update.
+    pub fn waiting_urgent_goal(&self, goal_id: &str) -> Result<Option<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id FROM goals
+             WHERE priority = 'urgent' AND id != ?1
+               AND (status = 'queued'
+                    OR (status IN ('pending', 'planning', 'executing')
+                        AND updated_at >= datetime('now', '-10 minutes')))
+             ORDER BY created_at ASC
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query(params![goal_id])?;
+        Ok(match rows.next()? {
+            Some(row) => Some(row.get(0)?),
+            None => None,
+        })
+    }
+
+    pub fn save_goal_checkpoint(&self, record: &GoalCheckpointRecord) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO goal_checkpoints
+             (goal_id, session_id, channel, actor_id, request_text, candidate_id, results_json,
+              preempted_by, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT(goal_id) DO UPDATE SET
+               candidate_id = excluded.candidate_id,
+               results_json = excluded.results_json,
+               preempted_by = excluded.preempted_by,
+               created_at_ms = excluded.created_at_ms",
+            params![
+                record.goal_id,
+                record.session_id,
+                record.channel,
+                record.actor_id,
+                record.request_text,
+                record.candidate_id,
+                serde_json::to_string(&record.results)?,
+                record.preempted_by,
+                record.created_at_ms
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn goal_checkpoint(&self, goal_id: &str) -> Result<Option<GoalCheckpointRecord>> {
+        Ok(self
+            .query_goal_checkpoints("WHERE c.goal_id = ?1", params![goal_id])?
+            .into_iter()
+            .next())
+    }
+
+    /// Paused goals, highest priority and oldest first. Check [`Self::waiting_urgent_goal`]
+    /// before resuming one.
+    pub fn resumable_goal_checkpoints(&self) -> Result<Vec<GoalCheckpointRecord>> {
+        self.query_goal_checkpoints(
+            "JOIN goals g ON g.id = c.goal_id
+             WHERE g.status = 'paused'
+             ORDER BY CASE g.priority WHEN 'urgent' THEN 2 WHEN 'normal' THEN 1 ELSE 0 END DESC,
+                      c.created_at_ms ASC",
+            [],
+        )
+    }
+
+    /// Keeps the model's plan reply for `goal_id` so a resumed goal replans from the same
+    /// candidates instead of asking the model again.
+    pub fn save_model_plan(&self, goal_id: &str, planner: &str, response: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO model_plans (goal_id, planner, response, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(goal_id) DO UPDATE SET
+               planner = excluded.planner,
+               response = excluded.response,
+               created_at_ms = excluded.created_at_ms",
+            params![goal_id, planner, response, now_epoch_ms()],
+        )?;
+        Ok(())
+    }
+
+    pub fn model_plan(&self, goal_id: &str) -> Result<Option<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT response FROM model_plans WHERE goal_id = ?1")?;
+        let mut rows = stmt.query(params![goal_id])?;
+        Ok(match rows.next()? {
+            Some(row) => Some(row.get(0)?),
+            None => None,
+        })
+    }
+
+    pub fn delete_goal_checkpoint(&self, goal_id: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM goal_checkpoints WHERE goal_id = ?1",
+            params![goal_id],
+        )?;
+        Ok(())
+    }
+
+    /// Goals running here, skipping any a crashed process left without an update for ten
+    /// minutes. Goals forwarded to a federation peer do not count.
+    pub fn running_goal_counts(
+        &self,
+        channel: &str,
+        session_id: &str,
+    ) -> Result<RunningGoalCounts> {
+        let (global, channel, session): (i64, i64, i64) = self.conn.query_row(
+            "SELECT COUNT(1),
+                    COALESCE(SUM(s.channel = ?1), 0),
+                    COALESCE(SUM(g.session_id = ?2), 0)
+             FROM goals g
+             LEFT JOIN sessions s ON s.id = g.session_id
+             WHERE g.status IN ('planning', 'executing')
+               AND g.updated_at >= datetime('now', '-10 minutes')
+               AND g.id NOT IN (SELECT goal_id FROM remote_goals)",
+            params![channel, session_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        Ok(RunningGoalCounts {
+            global: global as usize,
+            channel: channel as usize,
+            session: session as usize,
+        })
+    }
+
+    pub fn enqueue_goal(&self, record: &QueuedGoalRecord) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO queued_goals
+             (goal_id, session_id, channel, actor_id, request_text, token_budget,
+              preferred_candidate, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                record.goal_id,
+                record.session_id,
+                record.channel,
+                record.actor_id,
+                record.request_text,
+                record.token_budget.map(|budget| budget as i64),
+                record.preferred_candidate,
+                record.created_at_ms
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Queued goals in the order they start: highest priority, then oldest.
+    pub fn queued_goals(&self) -> Result<Vec<QueuedGoalRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT q.goal_id, q.session_id, q.channel, q.actor_id, q.request_text,
+                    q.token_budget, q.preferred_candidate, q.created_at_ms
+             FROM queued_goals q
+             JOIN goals g ON g.id = q.goal_id
+             WHERE g.status = 'queued'
+             ORDER BY CASE g.priority WHEN 'urgent' THEN 2 WHEN 'normal' THEN 1 ELSE 0 END DESC,
+                      q.created_at_ms ASC, q.rowid ASC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(QueuedGoalRecord {
+                goal_id: row.get(0)?,
+                session_id: row.get(1)?,
+                channel: row.get(2)?,
+                actor_id: row.get(3)?,
+                request_text: row.get(4)?,
+                token_budget: row.get::<_, Option<i64>>(5)?.map(|budget| budget as u64),
+                preferred_candidate: row.get(6)?,
+                created_at_ms: row.get(7)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// 1-based place of `goal_id` in [`Self::queued_goals`].
+    pub fn queued_goal_position(&self, goal_id: &str) -> Result<Option<usize>> {
+        Ok(self
+            .queued_goals()?
+            .iter()
+            .position(|record| record.goal_id == goal_id)
+            .map(|index| index + 1))
+    }
+
+    pub fn dequeue_goal(&self, goal_id: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM queued_goals WHERE goal_id = ?1",
+            params![goal_id],
+        )?;
+        Ok(())
+    }
+
+    fn query_goal_checkpoints(
+        &self,
+        filter: &str,
+        args: impl rusqlite::Params,
+    ) -> Result<Vec<GoalCheckpointRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT c.goal_id, c.session_id, c.channel, c.actor_id, c.request_text,
+                    c.candidate_id, c.results_json, c.preempted_by, c.created_at_ms
+             FROM goal_checkpoints c {filter}"
+        ))?;
+        let rows = stmt.query_map(args, |row| {
+            Ok((
+                GoalCheckpointRecord {
+                    goal_id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    channel: row.get(2)?,
+                    actor_id: row.get(3)?,
+                    request_text: row.get(4)?,
+                    candidate_id: row.get(5)?,
+                    results: Vec::new(),
+                    preempted_by: row.get(7)?,
+                    created_at_ms: row.get(8)?,
+                },
+                row.get::<_, String>(6)?,
+            ))
+        })?;
+        rows.map(|row| {
+            let (mut record, results_json) = row?;
+            record.results = serde_json::from_str(&results_json)
+                .context("invalid goal_checkpoints.results_json")?;
+            Ok(record)
+        })
+        .collect()
+    }
+
+    /// A read step result stored for the session no earlier than `since_ms`.
+    pub fn cached_step_result(
+        &self,
+        session_id: &str,
+        tool_name: &str,
+        input: Option<&str>,
+        since_ms: i64,
+    ) -> Result<Option<StepResult>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT step_id, tool_name, status, output
+             FROM session_step_cache
+             WHERE session_id = ?1 AND tool_name = ?2 AND input = ?3 AND created_at_ms >= ?4",
+        )?;
+        let mut rows = stmt.query(params![
+            session_id,
+            tool_name,
+            input.unwrap_or_default(),
+            since_ms
+        ])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+        let output: String = row.get(3)?;
+        Ok(Some(StepResult {
+            step_id: row.get(0)?,
+            tool_name: row.get(1)?,
+            status: row.get(2)?,
+            output: self.resolve_output(&output)?,
+        }))
+    }
+
+    pub fn cache_step_result(
+        &self,
+        session_id: &str,
+        input: Option<&str>,
+        result: &StepResult,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO session_step_cache
+             (session_id, tool_name, input, step_id, status, output, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(session_id, tool_name, input) DO UPDATE SET
+               step_id = excluded.step_id,
+               status = excluded.status,
+               output = excluded.output,
+               created_at_ms = excluded.created_at_ms",
+            params![
+                session_id,
+                result.tool_name,
+                input.unwrap_or_default(),
+                result.step_id,
+                result.status,
+                self.offload_output(&result.output)?,
+                now_epoch_ms()
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn clear_step_cache(&self, session_id: &str) -> Result<usize> {
+        Ok(self.conn.execute(
+            "DELETE FROM session_step_cache WHERE session_id = ?1",
+            params![session_id],
+        )?)
+    }
+
+    /// Builds the session context: all pins, plus up to `limit` of the newest summary and
+    /// uncompacted messages in chronological order. Compaction never drops a pin.
+    pub fn session_context(&self, session_id: &str, limit: usize) -> Result<SessionContext> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, session_id, role, content, compacted
+             FROM session_messages
+             WHERE session_id = ?1
+               AND (compacted = 0 OR id = (
+                 SELECT MAX(id) FROM session_messages
+                 WHERE session_id = ?1 AND role = 'summary'
+               ))
+             ORDER BY id DESC
+             LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![session_id, limit as i64], |row| {
+            Ok(SessionMessageRecord {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                role: row.get(2)?,
+                content: row.get(3)?,
+                compacted: row.get::<_, i64>(4)? != 0,
+            })
+        })?;
+        let mut messages = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        messages.reverse();
+        // The summary row is written after the messages it replaces; it reads first.
+        messages.sort_by_key(|row| row.role != "summary");
+        Ok(SessionContext {
+            pins: self.list_session_pins(session_id)?,
+            messages,
+        })
+    }
+
+    /// Counted in SQL so sessions with thousands of messages never load their text.
+    pub fn session_context_stats(&self, session_id: &str) -> Result<SessionContextStats> {
+        let (messages, compacted_messages, message_tokens, live_tokens) = self.conn.query_row(
+            "SELECT COUNT(*),
+                    COALESCE(SUM(compacted), 0),
+                    COALESCE(SUM((LENGTH(content) + 3) / 4), 0),
+                    COALESCE(SUM(CASE WHEN compacted = 0 OR id = (
+                      SELECT MAX(id) FROM session_messages
+                      WHERE session_id = ?1 AND role = 'summary'
+                    ) THEN (LENGTH(content) + 3) / 4 ELSE 0 END), 0)
+             FROM session_messages
+             WHERE session_id = ?1",
+            params![session_id],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            },
+        )?;
+        let (pins, pin_tokens) = self.conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM((LENGTH(content) + 3) / 4), 0)
+             FROM session_pins
+             WHERE session_id = ?1",
+            params![session_id],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )?;
+        Ok(SessionContextStats {
+            messages: messages as usize,
+            compacted_messages: compacted_messages as usize,
+            message_tokens: message_tokens as u64,
+            pins: pins as usize,
+            pin_tokens: pin_tokens as u64,
+            context_tokens: (live_tokens + pin_tokens) as u64,
+        })
+    }
+
+    /// A page of per-message token estimates, newest first, starting below `before_id`.
+    pub fn session_context_page(
+        &self,
+        session_id: &str,
+        before_id: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<ContextMessageStat>> {
+        // The running total needs every newer row, so the page is cut after the window.
+        let mut stmt = self.conn.prepare(
+            "SELECT id, role, in_context, bytes, tokens, cumulative FROM (
+               SELECT id, role, in_context, bytes, tokens,
+                      SUM(CASE WHEN in_context THEN tokens ELSE 0 END)
+                        OVER (ORDER BY id DESC) AS cumulative
+               FROM (
+                 SELECT id, role,
+                        compacted = 0 OR id = COALESCE((
+                          SELECT MAX(id) FROM session_messages
+                          WHERE session_id = ?1 AND role = 'summary'
+                        ), 0) AS in_context,
+                        LENGTH(CAST(content AS BLOB)) AS bytes,
+                        (LENGTH(content) + 3) / 4 AS tokens
+                 FROM session_messages
+                 WHERE session_id = ?1
+               )
+             )
+             WHERE ?2 IS NULL OR id < ?2
+             ORDER BY id DESC
+             LIMIT ?3",
+        )?;
+        let rows = stmt.query_map(params![session_id, before_id, limit as i64], |row| {
+            Ok(ContextMessageStat {
+                id: row.get(0)?,
+                role: row.get(1)?,
+                in_context: row.get::<_, i64>(2)? != 0,
+                bytes: row.get::<_, i64>(3)? as usize,
+                tokens: row.get::<_, i64>(4)? as u64,
+                cumulative_tokens: row.get::<_, i64>(5)? as u64,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Reconstructs the session as of `as_of_ms` from row timestamps. Goals count as open
+    /// unless they had already reached a terminal status; approvals unless resolved or
+    /// expired by then. Row timestamps are second-resolution.
+    pub fn session_snapshot(&self, session_id: &str, as_of_ms: i64) -> Result<SessionSnapshot> {
+        let mut stmt = self.conn.prepare(
+            "SELECT m.id, m.session_id, m.role, m.content,
+                    m.compacted = 1 AND EXISTS (
+                      SELECT 1 FROM session_messages s
+                      WHERE s.session_id = m.session_id
+                        AND s.role = 'summary'
+                        AND s.id > m.id
+                        AND s.created_at <= datetime(?2 / 1000, 'unixepoch')
+                    )
+             FROM session_messages m
+             WHERE m.session_id = ?1
+               AND m.created_at <= datetime(?2 / 1000, 'unixepoch')
+             ORDER BY m.id ASC",
+        )?;
+        let rows = stmt.query_map(params![session_id, as_of_ms], |row| {
+            Ok(SessionMessageRecord {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                role: row.get(2)?,
+                content: row.get(3)?,
+                compacted: row.get::<_, i64>(4)? != 0,
+            })
+        })?;
+        let messages = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT id, description, status, dedupe_key, priority
+             FROM goals
+             WHERE session_id = ?1
+               AND created_at <= datetime(?2 / 1000, 'unixepoch')
+               AND (status IN ('pending', 'awaiting_clarification', 'queued', 'planning', 'executing',
+                               'paused')
+                    OR updated_at > datetime(?2 / 1000, 'unixepoch'))
+             ORDER BY created_at ASC, rowid ASC",
+        )?;
+        let rows = stmt.query_map(params![session_id, as_of_ms], |row| {
+            Ok(StoredGoal {
+                id: row.get(0)?,
+                description: row.get(1)?,
+                status: row.get(2)?,
+                dedupe_key: row.get(3)?,
+                priority: row.get(4)?,
+            })
+        })?;
+        let open_goals = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT a.id, a.nonce, a.goal_id, a.tool_name, a.capability, a.input, a.status,
+                    a.requested_by, a.resolved_by, a.expires_at_ms, a.decision_reason,
+                    a.original_input
+             FROM approval_requests a
+             JOIN goals g ON g.id = a.goal_id
+             WHERE g.session_id = ?1
+               AND a.created_at <= datetime(?2 / 1000, 'unixepoch')
+               AND (a.resolved_at IS NULL OR a.resolved_at > datetime(?2 / 1000, 'unixepoch'))
+               AND (a.expires_at_ms IS NULL OR a.expires_at_ms > ?2)
+             ORDER BY a.created_at ASC",
+        )?;
+        let rows = stmt.query_map(params![session_id, as_of_ms], |row| {
+            Ok(ApprovalRecord {
+                id: row.get(0)?,
+                nonce: row.get(1)?,
+                goal_id: row.get(2)?,
+                tool_name: row.get(3)?,
+                capability: row.get(4)?,
+                input: row.get(5)?,
+                status: row.get(6)?,
+                requested_by: row.get(7)?,
+                resolved_by: row.get(8)?,
+                expires_at_ms: row.get(9)?,
+                decision_reason: row.get(10)?,
+                original_input: row.get(11)?,
+            })
+        })?;
+        let open_approvals = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let pins = self
+            .list_session_pins(session_id)?
+            .into_iter()
+            .filter(|pin| pin.created_at_ms <= as_of_ms)
+            .collect();
+        Ok(SessionSnapshot {
+            session_id: session_id.to_string(),
+            as_of_ms,
+            risk_mode: self.risk_mode_at(as_of_ms)?,
+            pins,
+            messages,
+            open_goals,
+            open_approvals,
+        })
+    }
+
+    /// The risk mode in force at `at_ms`, read from `risk_state_history`.
+    pub fn risk_mode_at(&self, at_ms: i64) -> Result<RiskMode> {
+        let mut stmt = self.conn.prepare(
+            "SELECT risk_mode
+             FROM risk_state_history
+             WHERE created_at_ms <= ?1
+             ORDER BY created_at_ms DESC, id DESC
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query(params![at_ms])?;
+        Ok(match rows.next()? {
+            Some(row) => RiskMode::parse(&row.get::<_, String>(0)?),
+            None => RiskMode::Secure,
+        })
+    }
+
+    pub fn reset_session(&self, session_id: &str) -> Result<usize> {
+        let deleted = self.conn.execute(
+            "DELETE FROM session_messages WHERE session_id = ?1",
+            params![session_id],
+        )?;
+        self.conn.execute(
+            "UPDATE sessions
+             SET queue_depth = 0, stop_requested = 0, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?1",
+            params![session_id],
+        )?;
+        Ok(deleted)
+    }
+
+    pub fn compact_session(&self, session_id: &str, instructions: Option<&str>) -> Result<usize> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, role, content
+             FROM session_messages
+             WHERE session_id = ?1 AND compacted = 0
+             ORDER BY id ASC",
+        )?;
+        let rows = stmt.query_map(params![session_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+        let messages = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        if messages.len() < 3 {
+            return Ok(0);
+        }
+        let cutoff = messages.len().saturating_sub(2);
+        let mut summary = String::new();
+        if let Some(custom) = instructions {
+            summary.push_str("instructions: ");
+            summary.push_str(custom.trim());
+            summary.push('\n');
+        }
+        for (_, role, content) in messages.iter().take(cutoff) {
+            summary.push_str(role);
+            summary.push_str(": ");
+            summary.push_str(content);
+            summary.push('\n');
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        for (id, _, _) in messages.iter().take(cutoff) {
+            tx.execute(
+                "UPDATE session_messages SET compacted = 1 WHERE id = ?1",
+                params![id],
+            )?;
+        }
+        tx.execute(
+            "INSERT INTO session_messages (session_id, role, content, compacted)
+             VALUES (?1, 'summary', ?2, 1)",
+            params![session_id, summary.trim()],
+        )?;
+        tx.execute(
+            "UPDATE sessions
+             SET compactions_count = compactions_count + 1,
+                 updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?1",
+            params![session_id],
+        )?;
+        tx.commit()?;
+        Ok(cutoff)
+    }
+
+    pub fn set_session_queue_depth(&self, session_id: &str, depth: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions
+             SET queue_depth = ?1, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?2",
+            params![depth.max(0), session_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn mark_session_stop(&self, session_id: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions
+             SET stop_requested = 1, queue_depth = 0, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?1",
+            params![session_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn clear_session_stop(&self, session_id: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions
+             SET stop_requested = 0, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?1",
+            params![session_id],
+        )?;
+        Ok(())
+    }
+
+    /// Compacts and closes open sessions last updated before `cutoff_ms` with nothing queued,
+    /// so the next message from their peer starts a fresh session. The final context is kept
+    /// as an episodic memory on the session's newest goal.
+    pub fn close_idle_sessions(&self, cutoff_ms: i64) -> Result<SessionCloseReport> {
+        let idle = {
+            let mut stmt = self.conn.prepare(
+                "SELECT id FROM sessions
+                 WHERE closed_at_ms IS NULL
+                   AND queue_depth = 0
+                   AND updated_at < datetime(?1 / 1000, 'unixepoch')
+                 ORDER BY updated_at ASC",
+            )?;
+            stmt.query_map(params![cutoff_ms], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        let mut report = SessionCloseReport::default();
+        for session_id in idle {
+            self.compact_session(&session_id, None)?;
+            let context = self.session_context(&session_id, 50)?;
+            let mut summary = format!("session {session_id} closed after inactivity");
+            for message in &context.messages {
+                summary.push_str(&format!("\n{}: {}", message.role, message.content));
+            }
+            let summary: String = summary.chars().take(SESSION_CLOSE_SUMMARY_CHARS).collect();
+            let last_goal: Option<String> = {
+                let mut stmt = self.conn.prepare(
+                    "SELECT id FROM goals WHERE session_id = ?1
+                     ORDER BY created_at DESC, rowid DESC LIMIT 1",
+                )?;
+                let mut rows = stmt.query(params![session_id])?;
+                match rows.next()? {
+                    Some(row) => Some(row.get(0)?),
+                    None => None,
+                }
+            };
+            let tx = self.conn.unchecked_transaction()?;
+            if let Some(goal_id) = &last_goal {
+                tx.execute(
+                    "INSERT INTO episodic_memories (goal_id, summary, source)
+                     VALUES (?1, ?2, 'session_close')",
+                    params![goal_id, summary],
+                )?;
+                report.summarized += 1;
+            }
+            tx.execute(
+                "UPDATE sessions SET closed_at_ms = ?1 WHERE id = ?2",
+                params![now_epoch_ms(), session_id],
+            )?;
+            tx.commit()?;
+            report.closed.push(session_id);
+        }
+        Ok(report)
+    }
+
+    pub fn set_session_usage_mode(&self, session_id: &str, usage_mode: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions
+             SET usage_mode = ?1, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?2",
+            params![usage_mode, session_id],
+        )?;
+        Ok(())
+    }
+
+    /// Records the estimated tokens and cost of one request or reply in `session`.
+    pub fn record_session_usage(&self, session: &SessionRecord, entry: &UsageEntry) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO session_usage
+             (session_id, channel, peer_id, goal_id, role, tokens, cost_usd, model_calls,
+              model_cache_hits, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                session.id,
+                session.channel,
+                session.peer_id,
+                entry.goal_id,
+                entry.role,
+                entry.tokens as i64,
+                entry.cost_usd,
+                entry.model_calls as i64,
+                entry.model_cache_hits as i64,
+                now_epoch_ms()
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn session_usage(&self, session_id: &str) -> Result<UsageTotals> {
+        self.usage_totals(
+            "SELECT COUNT(*), COALESCE(SUM(tokens), 0), COALESCE(SUM(cost_usd), 0),
+                    COALESCE(SUM(model_calls), 0), COALESCE(SUM(model_cache_hits), 0)
+             FROM session_usage WHERE session_id = ?1",
+            params![session_id],
+        )
+    }
+
+    /// Usage of every session one channel peer had, from `since_ms` on.
+    pub fn peer_usage_since(
+        &self,
+        channel: &str,
+        peer_id: &str,
+        since_ms: i64,
+    ) -> Result<UsageTotals> {
+        self.usage_totals(
+            "SELECT COUNT(*), COALESCE(SUM(tokens), 0), COALESCE(SUM(cost_usd), 0),
+                    COALESCE(SUM(model_calls), 0), COALESCE(SUM(model_cache_hits), 0)
+             FROM session_usage
+             WHERE channel = ?1 AND peer_id = ?2 AND created_at_ms >= ?3",
+            params![channel, peer_id, since_ms],
+        )
+    }
+
+    fn usage_totals(&self, sql: &str, params: impl rusqlite::Params) -> Result<UsageTotals> {
+        Ok(self.conn.query_row(sql, params, |row| {
+            Ok(UsageTotals {
+                messages: row.get::<_, i64>(0)? as u64,
+                tokens: row.get::<_, i64>(1)? as u64,
+                cost_usd: row.get(2)?,
+                model_calls: row.get::<_, i64>(3)? as u64,
+                model_cache_hits: row.get::<_, i64>(4)? as u64,
+            })
+        })?)
+    }
+
+    /// The cached reply for `key` while it is unexpired, counting the hit.
+    pub fn cached_model_response(&self, key: &str, now_ms: i64) -> Result<Option<String>> {
+        let mut stmt = self.conn.prepare(
+            "UPDATE model_response_cache SET hits = hits + 1
+             WHERE key = ?1 AND expires_at_ms > ?2
+             RETURNING response",
+        )?;
+        let mut rows = stmt.query(params![key, now_ms])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Keeps `response` under `key` until `expires_at_ms`, dropping expired entries first.
+    pub fn cache_model_response(
+        &self,
+        key: &str,
+        model: &str,
+        response: &str,
+        now_ms: i64,
+        expires_at_ms: i64,
+    ) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM model_response_cache WHERE expires_at_ms <= ?1",
+            params![now_ms],
+        )?;
+        self.conn.execute(
+            "INSERT INTO model_response_cache
+             (key, model, response, hits, created_at_ms, expires_at_ms)
+             VALUES (?1, ?2, ?3, 0, ?4, ?5)
+             ON CONFLICT(key) DO UPDATE SET
+               model = excluded.model,
+               response = excluded.response,
+               hits = 0,
+               created_at_ms = excluded.created_at_ms,
+               expires_at_ms = excluded.expires_at_ms",
+            params![key, model, response, now_ms, expires_at_ms],
+        )?;
+        Ok(())
+    }
+
+    pub fn model_cache_stats(&self, now_ms: i64) -> Result<ModelCacheStats> {
+        Ok(self.conn.query_row(
+            "SELECT COUNT(*),
+                    COALESCE(SUM(expires_at_ms > ?1), 0),
+                    COALESCE(SUM(hits), 0)
+             FROM model_response_cache",
+            params![now_ms],
+            |row| {
+                Ok(ModelCacheStats {
+                    entries: row.get::<_, i64>(0)? as u64,
+                    live: row.get::<_, i64>(1)? as u64,
+                    hits: row.get::<_, i64>(2)? as u64,
+                })
+            },
+        )?)
+    }
+
+    /// Thumbs up (`1`) or down (`-1`) from `actor_id` on a finished goal.
+    pub fn record_goal_feedback(
+        &self,
+        goal_id: &str,
+        actor_id: &str,
+        rating: i64,
+        comment: Option<&str>,
+    ) -> Result<()> {
+        if rating != 1 && rating != -1 {
+            bail!("feedback rating must be 1 or -1");
+        }
+        self.conn.execute(
+            "INSERT INTO goal_feedback (goal_id, actor_id, rating, comment, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![goal_id, actor_id, rating, comment, now_epoch_ms()],
+        )?;
+        Ok(())
+    }
+
+    /// Per-arm goal outcomes and feedback for experiment `name`, arms in name order.
+    pub fn experiment_report(&self, name: &str) -> Result<Vec<ExperimentArmStats>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT a.variant,
+                    COUNT(*),
+                    COALESCE(SUM(g.status = 'completed'), 0),
+                    COALESCE(SUM(g.status = 'failed'), 0),
+                    COALESCE(SUM(f.up), 0),
+                    COALESCE(SUM(f.down), 0)
+             FROM (SELECT DISTINCT goal_id, variant FROM run_plans WHERE experiment = ?1) a
+             JOIN goals g ON g.id = a.goal_id
+             LEFT JOIN (SELECT goal_id, SUM(rating > 0) AS up, SUM(rating < 0) AS down
+                        FROM goal_feedback GROUP BY goal_id) f ON f.goal_id = a.goal_id
+             GROUP BY a.variant
+             ORDER BY a.variant",
+        )?;
+        let rows = stmt.query_map(params![name], |row| {
+            Ok(ExperimentArmStats {
+                variant: row.get(0)?,
+                goals: row.get::<_, i64>(1)? as usize,
+                completed: row.get::<_, i64>(2)? as usize,
+                failed: row.get::<_, i64>(3)? as usize,
+                feedback_up: row.get::<_, i64>(4)? as usize,
+                feedback_down: row.get::<_, i64>(5)? as usize,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn model_provider_state(&self) -> Result<ModelProviderState> {
+        Ok(self.conn.query_row(
+            "SELECT degraded, consecutive_failures, last_error, last_failure_at_ms,
+                    degraded_since_ms
+             FROM model_provider_state
+             WHERE id = 1",
+            [],
+            |row| {
+                Ok(ModelProviderState {
+                    degraded: row.get::<_, i64>(0)? != 0,
+                    consecutive_failures: row.get::<_, i64>(1)? as u32,
+                    last_error: row.get(2)?,
+                    last_failure_at_ms: row.get(3)?,
+                    degraded_since_ms: row.get(4)?,
+                })
+            },
+        )?)
+    }
+
+    /// Counts an unreachable-provider error. Returns true when this one reached `threshold`
+    /// and switched the runtime to degraded mode.
+    pub fn record_model_outage(&self, error: &str, threshold: u32, now_ms: i64) -> Result<bool> {
+        let tx = self.conn.unchecked_transaction()?;
+        let (was_degraded, failures): (i64, i64) = tx.query_row(
+            "UPDATE model_provider_state
+             SET consecutive_failures = consecutive_failures + 1,
+                 last_error = ?1,
+                 last_failure_at_ms = ?2
+             WHERE id = 1
+             RETURNING degraded, consecutive_failures",
+            params![error, now_ms],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let entered = was_degraded == 0 && failures >= i64::from(threshold);
+        if entered {
+            tx.execute(
+                "UPDATE model_provider_state SET degraded = 1, degraded_since_ms = ?1 WHERE id = 1",
+                params![now_ms],
+            )?;
+            record_lifecycle_event(
+                &tx,
+                "model_provider_changed",
+                "degraded",
+                &format!("failures={failures} error={error}"),
+            )?;
+        }
+        tx.commit()?;
+        Ok(entered)
+    }
+
+    /// Clears the failure streak after a provider reply. Leaving degraded mode requeues the
+    /// goals held as `waiting_for_model`; returns how many, or `None` if it was not degraded.
+    pub fn record_model_recovery(&self) -> Result<Option<usize>> {
+        let tx = self.conn.unchecked_transaction()?;
+        let was_degraded: i64 = tx.query_row(
+            "SELECT degraded FROM model_provider_state WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )?;
+        tx.execute(
+            "UPDATE model_provider_state
+             SET degraded = 0, consecutive_failures = 0, degraded_since_ms = NULL
+             WHERE id = 1 AND (degraded = 1 OR consecutive_failures > 0)",
+            [],
+        )?;
+        if was_degraded == 0 {
+            tx.commit()?;
+            return Ok(None);
+        }
+        let released = release_goals_waiting_for_model(&tx)?;
+        record_lifecycle_event(
+            &tx,
+            "model_provider_changed",
+            "recovered",
+            &format!("requeued_goals={released}"),
+        )?;
+        tx.commit()?;
+        Ok(Some(released))
+    }
+
+    /// Requeues goals held as `waiting_for_model`, so a probe can run them. Returns how many.
+    pub fn requeue_goals_waiting_for_model(&self) -> Result<usize> {
+        release_goals_waiting_for_model(&self.conn)
+    }
+
+    pub fn set_session_model_override(
+        &self,
+        session_id: &str,
+        model_override: Option<&str>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions
+             SET model_override = ?1, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?2",
+            params![model_override, session_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_session_activation_mode(
+        &self,
+        session_id: &str,
+        activation_mode: &str,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions
+             SET activation_mode = ?1, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?2",
+            params![activation_mode, session_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn upsert_installed_skill(&self, record: &InstalledSkillRecord) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO installed_skills
+             (slug, name, version, description, source, hash, signature_status, scopes, allowed_paths, allowed_hosts, last_run_goal_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+             ON CONFLICT(slug) DO UPDATE SET
+               name=excluded.name,
+               version=excluded.version,
+               description=excluded.description,
+               source=excluded.source,
+               hash=excluded.hash,
+               signature_status=excluded.signature_status,
+               scopes=excluded.scopes,
+               allowed_paths=excluded.allowed_paths,
+               allowed_hosts=excluded.allowed_hosts,
+               last_run_goal_id=COALESCE(excluded.last_run_goal_id, installed_skills.last_run_goal_id),
+               updated_at=CURRENT_TIMESTAMP",
+            params![
+                record.slug,
+                record.name,
+                record.version,
+                record.description,
+                record.source,
+                record.hash,
+                record.signature_status,
+                record.scopes,
+                record.allowed_paths,
+                record.allowed_hosts,
+                record.last_run_goal_id
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_installed_skills(&self) -> Result<Vec<InstalledSkillRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT slug, name, version, description, source, hash, signature_status, scopes, allowed_paths, allowed_hosts, last_run_goal_id
+             FROM installed_skills
+             ORDER BY slug ASC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(InstalledSkillRecord {
+                slug: row.get(0)?,
+                name: row.get(1)?,
+                version: row.get(2)?,
+                description: row.get(3)?,
+                source: row.get(4)?,
+                hash: row.get(5)?,
+                signature_status: row.get(6)?,
+                scopes: row.get(7)?,
+                allowed_paths: row.get(8)?,
+                allowed_hosts: row.get(9)?,
+                last_run_goal_id: row.get(10)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn get_installed_skill(&self, slug: &str) -> Result<Option<InstalledSkillRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT slug, name, version, description, source, hash, signature_status, scopes, allowed_paths, allowed_hosts, last_run_goal_id
+             FROM installed_skills
+             WHERE slug = ?1
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query(params![slug])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(InstalledSkillRecord {
+                slug: row.get(0)?,
+                name: row.get(1)?,
+                version: row.get(2)?,
+                description: row.get(3)?,
+                source: row.get(4)?,
+                hash: row.get(5)?,
+                signature_status: row.get(6)?,
+                scopes: row.get(7)?,
+                allowed_paths: row.get(8)?,
+                allowed_hosts: row.get(9)?,
+                last_run_goal_id: row.get(10)?,
+            }));
+        }
+        Ok(None)
+    }
+
+    pub fn remove_installed_skill(&self, slug: &str) -> Result<bool> {
+        let changed = self.conn.execute(
+            "DELETE FROM installed_skills WHERE slug = ?1",
+            params![slug],
         )?;
-        tx.commit()?;
-        Ok(())
+        Ok(changed > 0)
     }
 
-    pub fn create_goal(&self, goal: &Goal) -> Result<()> {
+    pub fn set_skill_last_run_goal(&self, slug: &str, goal_id: &str) -> Result<()> {
         self.conn.execute(
-            "INSERT INTO goals (id, description, status, dedupe_key) VALUES (?1, ?2, ?3, ?4)",
-            params![
-                goal.id,
-                goal.description,
-                goal.status.as_str(),
-                goal.dedupe_key
-            ],
+            "UPDATE installed_skills
+             SET last_run_goal_id = ?1, updated_at = CURRENT_TIMESTAMP
+             WHERE slug = ?2",
+            params![goal_id, slug],
         )?;
         Ok(())
     }
 
-    pub fn create_goal_for_session(&self, goal: &Goal, session_id: Option<&str>) -> Result<()> {
+    pub fn has_approved_skill_exec_grant(&self, slug: &str) -> Result<bool> {
+        let mut stmt = self.conn.prepare(
+            "SELECT input
+             FROM approval_requests
+             WHERE tool_name = 'skill_exec_grant'
+               AND status = 'approved'",
+        )?;
+        let inputs = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        // A grant this build cannot read does not grant anything.
+        Ok(inputs.iter().any(|input| {
+            approval_payload::decode_approval_payload::<approval_payload::SkillExecGrantPayload>(
+                input,
+            )
+            .is_ok_and(|grant| grant.slug == slug)
+        }))
+    }
+
+    pub fn get_runtime_risk_state(&self) -> Result<RuntimeRiskState> {
+        let mut stmt = self.conn.prepare(
+            "SELECT risk_mode, yolo_armed_token, yolo_armed_at_ms, yolo_expires_at_ms, yolo_bypass_path_guard, last_changed_at_ms, last_changed_by
+             FROM runtime_risk_state
+             WHERE id = 1",
+        )?;
+        let mut rows = stmt.query([])?;
+        if let Some(row) = rows.next()? {
+            return Ok(RuntimeRiskState {
+                risk_mode: RiskMode::parse(&row.get::<_, String>(0)?),
+                yolo_armed_token: row.get(1)?,
+                yolo_armed_at_ms: row.get(2)?,
+                yolo_expires_at_ms: row.get(3)?,
+                yolo_bypass_path_guard: row.get::<_, i64>(4)? != 0,
+                last_changed_at_ms: row.get(5)?,
+                last_changed_by: row.get(6)?,
+            });
+        }
+        let now = now_epoch_ms();
         self.conn.execute(
-            "INSERT INTO goals (id, description, status, dedupe_key, session_id) VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![
-                goal.id,
-                goal.description,
-                goal.status.as_str(),
-                goal.dedupe_key,
-                session_id
-            ],
+            "INSERT INTO runtime_risk_state (id, risk_mode, yolo_bypass_path_guard, last_changed_at_ms, last_changed_by)
+             VALUES (1, 'secure', 1, ?1, 'cli')",
+            params![now],
+        )?;
+        self.get_runtime_risk_state()
+    }
+
+    pub fn arm_yolo(&self, changed_by: &str, reason: Option<&str>) -> Result<String> {
+        self.ensure_not_in_safe_mode()?;
+        let mode = self.get_runtime_risk_state()?.risk_mode;
+        let token = Uuid::new_v4().simple().to_string();
+        let now = now_epoch_ms();
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE runtime_risk_state
+             SET yolo_armed_token = ?1,
+                 yolo_armed_at_ms = ?2,
+                 last_changed_at_ms = ?2,
+                 last_changed_by = ?3
+             WHERE id = 1",
+            params![token, now, changed_by],
+        )?;
+        record_risk_change(&tx, "arm", mode, changed_by, reason, None)?;
+        tx.commit()?;
+        Ok(token)
+    }
+
+    pub fn enable_yolo(
+        &self,
+        changed_by: &str,
+        ttl_minutes: i64,
+        reason: Option<&str>,
+    ) -> Result<()> {
+        self.ensure_not_in_safe_mode()?;
+        let previous = self.get_runtime_risk_state()?.risk_mode;
+        let now = now_epoch_ms();
+        let ttl_ms = ttl_minutes.max(1).saturating_mul(60_000);
+        let expires_at_ms = now.saturating_add(ttl_ms);
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE runtime_risk_state
+             SET risk_mode = 'yolo',
+                 yolo_expires_at_ms = ?1,
+                 yolo_armed_token = NULL,
+                 yolo_armed_at_ms = NULL,
+                 last_changed_at_ms = ?2,
+                 last_changed_by = ?3
+             WHERE id = 1",
+            params![expires_at_ms, now, changed_by],
+        )?;
+        record_risk_change(
+            &tx,
+            "enable",
+            RiskMode::Yolo,
+            changed_by,
+            reason,
+            Some(expires_at_ms),
         )?;
+        if previous != RiskMode::Yolo {
+            record_lifecycle_event(
+                &tx,
+                "risk_mode_changed",
+                "yolo",
+                &format!("risk_mode=yolo changed_by={changed_by} ttl_minutes={ttl_minutes}"),
+            )?;
+        }
+        tx.commit()?;
         Ok(())
     }
 
-    pub fn persist_run_bundle(
-        &mut self,
-        bundle: RunPersistenceBundle<'_>,
-    ) -> Result<RunPersistenceOutcome> {
-        let run = bundle.run;
-        let now_ms = now_epoch_ms();
-        let approval_expires_at_ms = now_ms.saturating_add(bundle.approval_ttl_ms as i64);
-        let selected = &run.plan.candidates[run.plan.selected_index];
-        let plan_id = Uuid::new_v4().to_string();
-        let mut step_outcomes = std::collections::HashMap::<&str, &StepResult>::new();
-        for result in &run.step_results {
-            step_outcomes.insert(result.step_id.as_str(), result);
+    fn ensure_not_in_safe_mode(&self) -> Result<()> {
+        let state = self.safe_mode_state()?;
+        if state.active {
+            bail!(
+                "safe mode is active ({}); resume normal operation before enabling yolo",
+                state.reason.as_deref().unwrap_or("no reason recorded")
+            );
         }
+        Ok(())
+    }
 
-        let tx = self.conn.transaction()?;
+    pub fn set_risk_mode_secure(&self, changed_by: &str, reason: Option<&str>) -> Result<()> {
+        self.set_secure_with_action(changed_by, "disable", reason)
+    }
+
+    pub fn apply_yolo_expiry(&self, changed_by: &str) -> Result<bool> {
+        let state = self.get_runtime_risk_state()?;
+        if state.risk_mode != RiskMode::Yolo {
+            return Ok(false);
+        }
+        let Some(expires_at) = state.yolo_expires_at_ms else {
+            return Ok(false);
+        };
+        if now_epoch_ms() >= expires_at {
+            self.set_secure_with_action(
+                changed_by,
+                "expire",
+                Some(&format!("ttl elapsed at {expires_at}")),
+            )?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    pub fn safe_mode_state(&self) -> Result<SafeModeState> {
+        Ok(self.conn.query_row(
+            "SELECT active, reason, changed_by, changed_at_ms, last_panic_at_ms,
+                    last_panic_detail, panic_pending
+             FROM safe_mode_state
+             WHERE id = 1",
+            [],
+            |row| {
+                Ok(SafeModeState {
+                    active: row.get::<_, i64>(0)? != 0,
+                    reason: row.get(1)?,
+                    changed_by: row.get(2)?,
+                    changed_at_ms: row.get(3)?,
+                    last_panic_at_ms: row.get(4)?,
+                    last_panic_detail: row.get(5)?,
+                    panic_pending: row.get::<_, i64>(6)? != 0,
+                })
+            },
+        )?)
+    }
+
+    /// Enters safe mode and forces YOLO off. Returns false if it was already active.
+    pub fn enter_safe_mode(&self, changed_by: &str, reason: &str) -> Result<bool> {
+        if self.safe_mode_state()?.active {
+            return Ok(false);
+        }
+        if self.get_runtime_risk_state()?.risk_mode != RiskMode::Secure {
+            self.set_secure_with_action(changed_by, "safe_mode", Some(reason))?;
+        }
+        let tx = self.conn.unchecked_transaction()?;
         tx.execute(
-            "INSERT OR IGNORE INTO goals (id, description, status, dedupe_key) VALUES (?1, ?2, ?3, ?4)",
-            params![
-                run.goal.id,
-                run.goal.description,
-                run.goal.status.as_str(),
-                run.goal.dedupe_key
-            ],
+            "UPDATE safe_mode_state
+             SET active = 1, reason = ?1, changed_by = ?2, changed_at_ms = ?3
+             WHERE id = 1",
+            params![reason, changed_by, now_epoch_ms()],
+        )?;
+        record_lifecycle_event(
+            &tx,
+            "safe_mode_changed",
+            "entered",
+            &format!("changed_by={changed_by} reason={reason}"),
         )?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Leaves safe mode. Failures and panics recorded before now no longer count toward the
+    /// breaker. Returns false if safe mode was not active.
+    pub fn resume_from_safe_mode(&self, changed_by: &str, reason: Option<&str>) -> Result<bool> {
+        if !self.safe_mode_state()?.active {
+            return Ok(false);
+        }
+        let tx = self.conn.unchecked_transaction()?;
         tx.execute(
-            "UPDATE goals SET status = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
-            params![run.goal.status.as_str(), run.goal.id],
+            "UPDATE safe_mode_state
+             SET active = 0,
+                 reason = ?1,
+                 changed_by = ?2,
+                 changed_at_ms = ?3,
+                 goal_watermark = (SELECT COALESCE(MAX(rowid), 0) FROM goals),
+                 panic_pending = 0
+             WHERE id = 1",
+            params![reason, changed_by, now_epoch_ms()],
+        )?;
+        record_lifecycle_event(
+            &tx,
+            "safe_mode_changed",
+            "resumed",
+            &format!(
+                "changed_by={changed_by} reason={}",
+                reason.unwrap_or("<none>")
+            ),
+        )?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Remembers a panic so the next startup check boots into safe mode.
+    pub fn record_runtime_panic(&self, detail: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE safe_mode_state
+             SET last_panic_at_ms = ?1, last_panic_detail = ?2, panic_pending = 1
+             WHERE id = 1",
+            params![now_epoch_ms(), detail],
+        )?;
+        Ok(())
+    }
+
+    /// Failed goals in a row, newest first, since the last resume. Goals still running are
+    /// skipped; any other terminal status ends the streak.
+    pub fn consecutive_failed_goals(&self) -> Result<usize> {
+        let mut stmt = self.conn.prepare(
+            "SELECT status FROM goals
+             WHERE rowid > (SELECT goal_watermark FROM safe_mode_state WHERE id = 1)
+             ORDER BY rowid DESC",
         )?;
+        let mut rows = stmt.query([])?;
+        let mut failed = 0;
+        while let Some(row) = rows.next()? {
+            match row.get::<_, String>(0)?.as_str() {
+                "failed" => failed += 1,
+                "completed" | "cancelled" => break,
+                _ => {}
+            }
+        }
+        Ok(failed)
+    }
+
+    /// Trips the breaker on a pending panic or `failure_threshold` consecutive failed goals
+    /// (0 disables the failure check). Returns the reason when this call entered safe mode.
+    pub fn evaluate_safe_mode(
+        &self,
+        failure_threshold: u32,
+        changed_by: &str,
+    ) -> Result<Option<String>> {
+        let state = self.safe_mode_state()?;
+        if state.active {
+            return Ok(None);
+        }
+        let reason = if state.panic_pending {
+            format!(
+                "panic recorded at {}: {}",
+                state.last_panic_at_ms.unwrap_or_default(),
+                state.last_panic_detail.as_deref().unwrap_or("<unknown>")
+            )
+        } else {
+            let failed = self.consecutive_failed_goals()?;
+            if failure_threshold == 0 || failed < failure_threshold as usize {
+                return Ok(None);
+            }
+            format!("{failed} consecutive goals failed")
+        };
+        self.enter_safe_mode(changed_by, &reason)?;
+        Ok(Some(reason))
+    }
+
+    fn set_secure_with_action(
+        &self,
+        changed_by: &str,
+        action: &str,
+        reason: Option<&str>,
+    ) -> Result<()> {
+        let previous = self.get_runtime_risk_state()?.risk_mode;
+        let now = now_epoch_ms();
+        let tx = self.conn.unchecked_transaction()?;
         tx.execute(
-            "INSERT INTO run_plans (id, goal_id, intent, selected_candidate_id, selected_score)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
+            "UPDATE runtime_risk_state
+             SET risk_mode = 'secure',
+                 yolo_expires_at_ms = NULL,
+                 yolo_armed_token = NULL,
+                 yolo_armed_at_ms = NULL,
+                 last_changed_at_ms = ?1,
+                 last_changed_by = ?2
+             WHERE id = 1",
+            params![now, changed_by],
+        )?;
+        record_risk_change(&tx, action, RiskMode::Secure, changed_by, reason, None)?;
+        if previous != RiskMode::Secure {
+            record_lifecycle_event(
+                &tx,
+                "risk_mode_changed",
+                "secure",
+                &format!("risk_mode=secure changed_by={changed_by}"),
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn record_workspace_change(
+        &self,
+        goal_id: &str,
+        tool_name: &str,
+        path: &str,
+        change_kind: &str,
+        diff_stat: &str,
+    ) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO workspace_changes
+             (goal_id, tool_name, path, change_kind, diff_stat, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
             params![
-                plan_id,
-                run.goal.id,
-                format!("{:?}", run.plan.intent),
-                selected.id,
-                selected.score
+                goal_id,
+                tool_name,
+                path,
+                change_kind,
+                diff_stat,
+                now_epoch_ms()
             ],
         )?;
+        Ok(self.conn.last_insert_rowid())
+    }
 
-        for step in &selected.steps {
-            let outcome = step_outcomes.get(step.id.as_str());
-            let status = if outcome.is_some() {
-                "executed"
-            } else if run
-                .pending_approval
-                .as_ref()
-                .map(|pending| pending.tool_name.as_str())
-                == Some(step.tool_name.as_str())
-            {
-                "blocked_pending_approval"
-            } else {
-                "skipped"
-            };
-            let output = outcome
-                .map(|item| item.output.as_str())
-                .unwrap_or_default()
-                .to_string();
-            tx.execute(
-                "INSERT INTO run_steps
-                 (id, goal_id, plan_id, step_id, tool_name, permission, input, status, output)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                params![
-                    Uuid::new_v4().to_string(),
-                    run.goal.id,
-                    plan_id,
-                    step.id,
-                    step.tool_name,
-                    step.permission.as_str(),
-                    step.input,
-                    status,
-                    output
-                ],
+    /// Oldest first.
+    pub fn list_workspace_changes(&self, goal_id: &str) -> Result<Vec<WorkspaceChangeRecord>> {
+        self.query_workspace_changes("WHERE goal_id = ?1", params![goal_id])
+    }
+
+    /// Changes to `path` that are still uncommitted, oldest first.
+    pub fn unmerged_workspace_changes(&self, path: &str) -> Result<Vec<WorkspaceChangeRecord>> {
+        self.query_workspace_changes("WHERE path = ?1 AND merged_at_ms IS NULL", params![path])
+    }
+
+    /// Marks unmerged changes as merged when their path is no longer in `dirty_paths`.
+    pub fn mark_workspace_changes_merged(&self, dirty_paths: &[String]) -> Result<usize> {
+        let unmerged = self.query_workspace_changes("WHERE merged_at_ms IS NULL", [])?;
+        let now = now_epoch_ms();
+        let mut merged = 0;
+        for change in unmerged {
+            if dirty_paths.contains(&change.path) {
+                continue;
+            }
+            merged += self.conn.execute(
+                "UPDATE workspace_changes SET merged_at_ms = ?1 WHERE id = ?2",
+                params![now, change.id],
             )?;
         }
+        Ok(merged)
+    }
 
-        for trace in &run.traces {
-            tx.execute(
-                "INSERT INTO trace_events (goal_id, event_type, detail, risk_mode) VALUES (?1, ?2, ?3, ?4)",
-                params![trace.goal_id, trace.event_type, trace.detail, trace.risk_mode],
-            )?;
-        }
+    fn query_workspace_changes(
+        &self,
+        filter: &str,
+        args: impl rusqlite::Params,
+    ) -> Result<Vec<WorkspaceChangeRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, goal_id, tool_name, path, change_kind, diff_stat, merged_at_ms, created_at_ms
+             FROM workspace_changes
+             {filter}
+             ORDER BY id ASC"
+        ))?;
+        let rows = stmt.query_map(args, |row| {
+            Ok(WorkspaceChangeRecord {
+                id: row.get(0)?,
+                goal_id: row.get(1)?,
+                tool_name: row.get(2)?,
+                path: row.get(3)?,
+                change_kind: row.get(4)?,
+                diff_stat: row.get(5)?,
+                merged_at_ms: row.get(6)?,
+                created_at_ms: row.get(7)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
 
-        let approval_id = persist_pending_approval(
-            &tx,
-            &run.goal.id,
-            run.pending_approval.as_ref(),
-            bundle.source,
-            bundle.requested_by,
-            approval_expires_at_ms,
+    pub fn record_content_provenance(
+        &self,
+        goal_id: &str,
+        tool_name: &str,
+        path: &str,
+        model: &str,
+        prompt_hash: &str,
+    ) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO content_provenance
+             (goal_id, tool_name, path, model, prompt_hash, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![goal_id, tool_name, path, model, prompt_hash, now_epoch_ms()],
         )?;
+        Ok(self.conn.last_insert_rowid())
+    }
 
-        tx.execute(
-            "INSERT INTO episodic_memories (goal_id, summary, source)
-             VALUES (?1, ?2, ?3)",
-            params![run.goal.id, run.reflection, bundle.source],
+    /// Newest first, optionally only for one path or one goal.
+    pub fn list_content_provenance(
+        &self,
+        path: Option<&str>,
+        goal_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<ContentProvenanceRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, goal_id, tool_name, path, model, prompt_hash, created_at_ms
+             FROM content_provenance
+             WHERE (?1 IS NULL OR path = ?1) AND (?2 IS NULL OR goal_id = ?2)
+             ORDER BY created_at_ms DESC, id DESC
+             LIMIT ?3",
         )?;
-        tx.commit()?;
-
-        Ok(RunPersistenceOutcome { approval_id })
+        let rows = stmt.query_map(params![path, goal_id, limit as i64], |row| {
+            Ok(ContentProvenanceRecord {
+                id: row.get(0)?,
+                goal_id: row.get(1)?,
+                tool_name: row.get(2)?,
+                path: row.get(3)?,
+                model: row.get(4)?,
+                prompt_hash: row.get(5)?,
+                created_at_ms: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
-    pub fn find_goal_by_dedupe_key(&self, dedupe_key: &str) -> Result<Option<StoredGoal>> {
+    /// Newest first.
+    pub fn list_risk_state_history(&self, limit: usize) -> Result<Vec<RiskStateHistoryRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, description, status, dedupe_key
-             FROM goals
-             WHERE dedupe_key = ?1",
+            "SELECT id, action, risk_mode, actor, reason, expires_at_ms, created_at_ms
+             FROM risk_state_history
+             ORDER BY id DESC
+             LIMIT ?1",
         )?;
-        let mut rows = stmt.query(params![dedupe_key])?;
-        if let Some(row) = rows.next()? {
-            return Ok(Some(StoredGoal {
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(RiskStateHistoryRecord {
                 id: row.get(0)?,
-                description: row.get(1)?,
-                status: row.get(2)?,
-                dedupe_key: row.get(3)?,
-            }));
-        }
-        Ok(None)
+                action: row.get(1)?,
+                risk_mode: RiskMode::parse(&row.get::<_, String>(2)?),
+                actor: row.get(3)?,
+                reason: row.get(4)?,
+                expires_at_ms: row.get(5)?,
+                created_at_ms: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
-    pub fn update_goal_status(&self, goal_id: &str, status: GoalStatus) -> Result<()> {
+    pub fn set_yolo_expiry_at_ms(&self, expires_at_ms: i64) -> Result<()> {
         self.conn.execute(
-            "UPDATE goals SET status = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
-            params![status.as_str(), goal_id],
+            "UPDATE runtime_risk_state
+             SET yolo_expires_at_ms = ?1
+             WHERE id = 1",
+            params![expires_at_ms],
         )?;
         Ok(())
     }
 
-    pub fn add_trace_event(&self, event: &TraceEvent) -> Result<()> {
+    pub fn add_connector(
+        &self,
+        id: &str,
+        connector_type: &str,
+        display_name: &str,
+        config_json: &str,
+    ) -> Result<()> {
         self.conn.execute(
-            "INSERT INTO trace_events (goal_id, event_type, detail, risk_mode) VALUES (?1, ?2, ?3, ?4)",
-            params![event.goal_id, event.event_type, event.detail, event.risk_mode],
+            "INSERT INTO connectors (id, type, display_name, config_json)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![id, connector_type, display_name, config_json],
         )?;
         Ok(())
     }
 
-    pub fn get_goal(&self, goal_id: &str) -> Result<Option<StoredGoal>> {
-        let mut stmt = self
+    pub fn update_connector(
+        &self,
+        id: &str,
+        display_name: &str,
+        config_json: &str,
+    ) -> Result<bool> {
+        let changed = self.conn.execute(
+            "UPDATE connectors
+             SET display_name = ?1,
+                 config_json = ?2,
+                 updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?3",
+            params![display_name, config_json, id],
+        )?;
+        Ok(changed > 0)
+    }
+
+    pub fn remove_connector(&self, id: &str) -> Result<bool> {
+        let changed = self
             .conn
-            .prepare("SELECT id, description, status, dedupe_key FROM goals WHERE id = ?1")?;
-        let mut rows = stmt.query(params![goal_id])?;
-        if let Some(row) = rows.next()? {
-            return Ok(Some(StoredGoal {
-                id: row.get(0)?,
-                description: row.get(1)?,
-                status: row.get(2)?,
-                dedupe_key: row.get(3)?,
-            }));
-        }
-        Ok(None)
+            .execute("DELETE FROM connectors WHERE id = ?1", params![id])?;
+        Ok(changed > 0)
     }
 
-    pub fn list_goals(&self, limit: usize) -> Result<Vec<StoredGoal>> {
+    pub fn list_connectors(&self) -> Result<Vec<ConnectorRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, description, status, dedupe_key
-             FROM goals
-             ORDER BY updated_at DESC
-             LIMIT ?1",
+            "SELECT id, type, display_name, config_json, last_test_at_ms, last_test_status
+             FROM connectors
+             ORDER BY display_name ASC",
         )?;
-        let rows = stmt.query_map(params![limit as i64], |row| {
-            Ok(StoredGoal {
+        let rows = stmt.query_map([], |row| {
+            Ok(ConnectorRecord {
                 id: row.get(0)?,
-                description: row.get(1)?,
-                status: row.get(2)?,
-                dedupe_key: row.get(3)?,
+                connector_type: row.get(1)?,
+                display_name: row.get(2)?,
+                config_json: row.get(3)?,
+                last_test_at_ms: row.get(4)?,
+                last_test_status: row.get(5)?,
             })
         })?;
         Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
-    pub fn get_traces(&self, goal_id: &str) -> Result<Vec<TraceEvent>> {
+    pub fn get_connector(&self, id: &str) -> Result<Option<ConnectorRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT goal_id, event_type, detail, risk_mode
-             FROM trace_events
-             WHERE goal_id = ?1
-             -- insertion order currently models execution order for the goal timeline
-             ORDER BY id ASC",
+            "SELECT id, type, display_name, config_json, last_test_at_ms, last_test_status
+             FROM connectors
+             WHERE id = ?1
+             LIMIT 1",
         )?;
+        let mut rows = stmt.query(params![id])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(ConnectorRecord {
+                id: row.get(0)?,
+                connector_type: row.get(1)?,
+                display_name: row.get(2)?,
+                config_json: row.get(3)?,
+                last_test_at_ms: row.get(4)?,
+                last_test_status: row.get(5)?,
+            }));
+        }
+        Ok(None)
+    }
 
-        let rows = stmt.query_map(params![goal_id], |row| {
-            Ok(TraceEvent {
-                goal_id: row.get(0)?,
-                event_type: row.get(1)?,
-                detail: row.get(2)?,
-                risk_mode: row.get(3)?,
-            })
-        })?;
-
-        let traces = rows.collect::<rusqlite::Result<Vec<_>>>()?;
-        Ok(traces)
+    pub fn record_connector_test(&self, id: &str, status: &str) -> Result<bool> {
+        let changed = self.conn.execute(
+            "UPDATE connectors
+             SET last_test_at_ms = ?1,
+                 last_test_status = ?2,
+                 updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?3",
+            params![now_epoch_ms(), status, id],
+        )?;
+        Ok(changed > 0)
     }
 
-    pub fn search_traces(&self, pattern: &str, limit: usize) -> Result<Vec<TraceEvent>> {
-        let like = format!("%{}%", pattern);
-        let mut stmt = self.conn.prepare(
-            "SELECT goal_id, event_type, detail, risk_mode
-             FROM trace_events
-             WHERE detail LIKE ?1 OR event_type LIKE ?1
-             ORDER BY id DESC
-             LIMIT ?2",
+    pub fn record_connector_tool_usage(
+        &self,
+        connector_id: &str,
+        tool_name: &str,
+        last_goal_id: Option<&str>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO connector_tool_usage
+             (connector_id, tool_name, last_used_at_ms, last_goal_id)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![connector_id, tool_name, now_epoch_ms(), last_goal_id],
         )?;
-        let rows = stmt.query_map(params![like, limit as i64], |row| {
-            Ok(TraceEvent {
-                goal_id: row.get(0)?,
-                event_type: row.get(1)?,
-                detail: row.get(2)?,
-                risk_mode: row.get(3)?,
-            })
-        })?;
-        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        Ok(())
     }
 
-    pub fn list_recent_traces(&self, limit: usize) -> Result<Vec<TraceEvent>> {
+    /// Connector usage rows that name `goal_id`, linking a goal back to the connector call or
+    /// webhook event behind it.
+    pub fn connector_usage_for_goal(&self, goal_id: &str) -> Result<Vec<ConnectorUsageRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT goal_id, event_type, detail, risk_mode
-             FROM trace_events
-             ORDER BY id DESC
-             LIMIT ?1",
+            "SELECT connector_id, tool_name, last_used_at_ms, last_goal_id
+             FROM connector_tool_usage
+             WHERE last_goal_id = ?1
+             ORDER BY id ASC",
         )?;
-        let rows = stmt.query_map(params![limit as i64], |row| {
-            Ok(TraceEvent {
-                goal_id: row.get(0)?,
-                event_type: row.get(1)?,
-                detail: row.get(2)?,
-                risk_mode: row.get(3)?,
+        let rows = stmt.query_map(params![goal_id], |row| {
+            Ok(ConnectorUsageRecord {
+                connector_id: row.get(0)?,
+                tool_name: row.get(1)?,
+                last_used_at_ms: row.get(2)?,
+                last_goal_id: row.get(3)?,
             })
         })?;
         Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
-    pub fn count_plans_for_goal(&self, goal_id: &str) -> Result<usize> {
-        let count: i64 = self.conn.query_row(
-            "SELECT COUNT(1) FROM run_plans WHERE goal_id = ?1",
-            params![goal_id],
-            |row| row.get(0),
-        )?;
-        Ok(count as usize)
-    }
-
-    pub fn count_steps_for_goal(&self, goal_id: &str) -> Result<usize> {
-        let count: i64 = self.conn.query_row(
-            "SELECT COUNT(1) FROM run_steps WHERE goal_id = ?1",
-            params![goal_id],
-            |row| row.get(0),
+    /// Appends to the connector I/O log; `id` is ignored and the new row id returned.
+    pub fn add_connector_io(&self, record: &ConnectorIoRecord) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO connector_io_log
+             (connector_id, tool_name, method, path, status, response_bytes, latency_ms, error, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                record.connector_id,
+                record.tool_name,
+                record.method,
+                record.path,
+                record.status,
+                record.response_bytes as i64,
+                record.latency_ms as i64,
+                record.error,
+                record.created_at_ms,
+            ],
         )?;
-        Ok(count as usize)
+        Ok(self.conn.last_insert_rowid())
     }
 
-    pub fn mark_blocked_step_executed_for_goal(
+    /// Newest entries first.
+    pub fn list_connector_io(
         &self,
-        goal_id: &str,
-        tool_name: &str,
-        output: &str,
-    ) -> Result<usize> {
-        let changed = self.conn.execute(
-            "UPDATE run_steps
-             SET status = 'executed_after_approval', output = ?1
-             WHERE id = (
-               SELECT id
-               FROM run_steps
-               WHERE goal_id = ?2
-                 AND tool_name = ?3
-                 AND status = 'blocked_pending_approval'
-               ORDER BY created_at DESC
-               LIMIT 1
-             )",
-            params![output, goal_id, tool_name],
+        connector_id: &str,
+        limit: usize,
+    ) -> Result<Vec<ConnectorIoRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, connector_id, tool_name, method, path, status, response_bytes, latency_ms, error, created_at_ms
+             FROM connector_io_log
+             WHERE connector_id = ?1
+             ORDER BY id DESC
+             LIMIT ?2",
         )?;
-        Ok(changed)
+        let rows = stmt.query_map(params![connector_id, limit as i64], |row| {
+            Ok(ConnectorIoRecord {
+                id: row.get(0)?,
+                connector_id: row.get(1)?,
+                tool_name: row.get(2)?,
+                method: row.get(3)?,
+                path: row.get(4)?,
+                status: row.get(5)?,
+                response_bytes: row.get::<_, i64>(6)?.max(0) as u64,
+                latency_ms: row.get::<_, i64>(7)?.max(0) as u64,
+                error: row.get(8)?,
+                created_at_ms: row.get(9)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
-    pub fn count_active_goals(&self) -> Result<usize> {
-        let count: i64 = self.conn.query_row(
-            "SELECT COUNT(1)
-             FROM goals
-             WHERE status IN ('pending', 'planning', 'executing')",
-            [],
-            |row| row.get(0),
+    /// Drops a connector's entries created before `older_than_ms` and, past that, all but the
+    /// newest `max_entries`. Returns how many rows were removed.
+    pub fn prune_connector_io(
+        &self,
+        connector_id: &str,
+        older_than_ms: i64,
+        max_entries: usize,
+    ) -> Result<usize> {
+        let expired = self.conn.execute(
+            "DELETE FROM connector_io_log WHERE connector_id = ?1 AND created_at_ms < ?2",
+            params![connector_id, older_than_ms],
         )?;
-        Ok(count as usize)
+        let overflow = self.conn.execute(
+            "DELETE FROM connector_io_log
+             WHERE connector_id = ?1
+               AND id NOT IN (
+                 SELECT id FROM connector_io_log WHERE connector_id = ?1 ORDER BY id DESC LIMIT ?2
+               )",
+            params![connector_id, max_entries as i64],
+        )?;
+        Ok(expired + overflow)
     }
 
-    pub fn get_or_create_active_session(
+    /// The unexpired cache entry for a connector tool call, if any.
+    pub fn get_connector_cache(
         &self,
-        channel: &str,
-        peer_id: &str,
-    ) -> Result<SessionRecord> {
-        if let Some(existing) = self.get_latest_session_for_peer(channel, peer_id)? {
-            return Ok(existing);
+        connector_id: &str,
+        tool_name: &str,
+        input_hash: &str,
+    ) -> Result<Option<ConnectorCacheEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT output_json, metadata_json, expires_at_ms
+             FROM connector_response_cache
+             WHERE connector_id = ?1 AND tool_name = ?2 AND input_hash = ?3
+               AND expires_at_ms > ?4",
+        )?;
+        let mut rows = stmt.query(params![connector_id, tool_name, input_hash, now_epoch_ms()])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(ConnectorCacheEntry {
+                output_json: row.get(0)?,
+                metadata_json: row.get(1)?,
+                expires_at_ms: row.get(2)?,
+            }));
         }
-        self.create_session(channel, peer_id, None)
+        Ok(None)
     }
 
-    pub fn create_session(
+    /// Stores a connector read for `ttl_ms` and drops the connector's expired entries.
+    pub fn put_connector_cache(
         &self,
-        channel: &str,
-        peer_id: &str,
-        model_override: Option<&str>,
-    ) -> Result<SessionRecord> {
-        let session = SessionRecord {
-            id: Uuid::new_v4().to_string(),
-            channel: channel.to_string(),
-            peer_id: peer_id.to_string(),
-            model_override: model_override.map(std::string::ToString::to_string),
-            usage_mode: "tokens".to_string(),
-            activation_mode: "always".to_string(),
-            compactions_count: 0,
-            queue_depth: 0,
-            stop_requested: false,
-        };
+        connector_id: &str,
+        tool_name: &str,
+        input_hash: &str,
+        output_json: &str,
+        metadata_json: &str,
+        ttl_ms: i64,
+    ) -> Result<()> {
+        let now = now_epoch_ms();
         self.conn.execute(
-            "INSERT INTO sessions
-             (id, channel, peer_id, model_override, usage_mode, activation_mode, compactions_count, queue_depth, stop_requested)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            "DELETE FROM connector_response_cache WHERE connector_id = ?1 AND expires_at_ms <= ?2",
+            params![connector_id, now],
+        )?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO connector_response_cache
+             (connector_id, tool_name, input_hash, output_json, metadata_json, expires_at_ms, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
             params![
-                session.id,
-                session.channel,
-                session.peer_id,
-                session.model_override,
-                session.usage_mode,
-                session.activation_mode,
-                session.compactions_count,
-                session.queue_depth,
-                if session.stop_requested { 1 } else { 0 }
+                connector_id,
+                tool_name,
+                input_hash,
+                output_json,
+                metadata_json,
+                now.saturating_add(ttl_ms),
+                now
             ],
         )?;
-        Ok(session)
+        Ok(())
     }
 
-    pub fn get_latest_session_for_peer(
+    /// Counts one cache hit or miss for a connector tool and returns the updated totals.
+    pub fn record_connector_cache_lookup(
         &self,
-        channel: &str,
-        peer_id: &str,
-    ) -> Result<Option<SessionRecord>> {
+        connector_id: &str,
+        tool_name: &str,
+        hit: bool,
+    ) -> Result<ConnectorCacheStats> {
+        let (hits, misses) = if hit { (1, 0) } else { (0, 1) };
+        let (hits, misses): (i64, i64) = self.conn.query_row(
+            "INSERT INTO connector_cache_stats (connector_id, tool_name, hits, misses)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(connector_id, tool_name)
+             DO UPDATE SET hits = hits + excluded.hits, misses = misses + excluded.misses
+             RETURNING hits, misses",
+            params![connector_id, tool_name, hits, misses],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok(ConnectorCacheStats {
+            hits: hits.max(0) as u64,
+            misses: misses.max(0) as u64,
+        })
+    }
+
+    pub fn add_job(&self, job: NewJobRecord<'_>) -> Result<()> {
+        let now = now_epoch_ms();
+        self.conn.execute(
+            "INSERT INTO jobs
+             (job_id, name, schedule_kind, schedule_value, goal_template, mode, allowed_scopes, enabled, created_at_ms, updated_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 1, ?8, ?8)",
+            params![
+                job.job_id,
+                job.name,
+                job.schedule_kind,
+                job.schedule_value,
+                job.goal_template,
+                job.mode,
+                job.allowed_scopes,
+                now
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn update_job_definition(&self, job: NewJobRecord<'_>) -> Result<bool> {
+        let changed = self.conn.execute(
+            "UPDATE jobs
+             SET name = ?1,
+                 schedule_kind = ?2,
+                 schedule_value = ?3,
+                 goal_template = ?4,
+                 mode = ?5,
+                 allowed_scopes = ?6,
+                 updated_at_ms = ?7
+             WHERE job_id = ?8",
+            params![
+                job.name,
+                job.schedule_kind,
+                job.schedule_value,
+                job.goal_template,
+                job.mode,
+                job.allowed_scopes,
+                now_epoch_ms(),
+                job.job_id
+            ],
+        )?;
+        Ok(changed > 0)
+    }
+
+    pub fn list_jobs(&self) -> Result<Vec<JobRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, channel, peer_id, model_override, usage_mode, activation_mode, compactions_count, queue_depth, stop_requested
-             FROM sessions
-             WHERE channel = ?1 AND peer_id = ?2
-             ORDER BY updated_at DESC, rowid DESC
-             LIMIT 1",
+            "SELECT job_id, name, schedule_kind, schedule_value, goal_template, mode, allowed_scopes, enabled,
+                    created_at_ms, updated_at_ms, last_run_at_ms, last_status, last_goal_id
+             FROM jobs
+             ORDER BY created_at_ms DESC",
         )?;
-        let mut rows = stmt.query(params![channel, peer_id])?;
-        if let Some(row) = rows.next()? {
-            return Ok(Some(SessionRecord {
-                id: row.get(0)?,
-                channel: row.get(1)?,
-                peer_id: row.get(2)?,
-                model_override: row.get(3)?,
-                usage_mode: row.get(4)?,
-                activation_mode: row.get(5)?,
-                compactions_count: row.get(6)?,
-                queue_depth: row.get(7)?,
-                stop_requested: row.get::<_, i64>(8)? != 0,
-            }));
-        }
-        Ok(None)
+        let rows = stmt.query_map([], |row| {
+            Ok(JobRecord {
+                job_id: row.get(0)?,
+                name: row.get(1)?,
+                schedule_kind: row.get(2)?,
+                schedule_value: row.get(3)?,
+                goal_template: row.get(4)?,
+                mode: row.get(5)?,
+                allowed_scopes: row.get(6)?,
+                enabled: row.get::<_, i64>(7)? != 0,
+                created_at_ms: row.get(8)?,
+                updated_at_ms: row.get(9)?,
+                last_run_at_ms: row.get(10)?,
+                last_status: row.get(11)?,
+                last_goal_id: row.get(12)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
-    pub fn get_session(&self, session_id: &str) -> Result<Option<SessionRecord>> {
+    pub fn get_job(&self, job_id: &str) -> Result<Option<JobRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, channel, peer_id, model_override, usage_mode, activation_mode, compactions_count, queue_depth, stop_requested
-             FROM sessions
-             WHERE id = ?1
+            "SELECT job_id, name, schedule_kind, schedule_value, goal_template, mode, allowed_scopes, enabled,
+                    created_at_ms, updated_at_ms, last_run_at_ms, last_status, last_goal_id
+             FROM jobs
+             WHERE job_id = ?1
              LIMIT 1",
         )?;
-        let mut rows = stmt.query(params![session_id])?;
+        let mut rows = stmt.query(params![job_id])?;
         if let Some(row) = rows.next()? {
-            return Ok(Some(SessionRecord {
-                id: row.get(0)?,
-                channel: row.get(1)?,
-                peer_id: row.get(2)?,
-                model_override: row.get(3)?,
-                usage_mode: row.get(4)?,
-                activation_mode: row.get(5)?,
-                compactions_count: row.get(6)?,
-                queue_depth: row.get(7)?,
-                stop_requested: row.get::<_, i64>(8)? != 0,
+            return Ok(Some(JobRecord {
+                job_id: row.get(0)?,
+                name: row.get(1)?,
+                schedule_kind: row.get(2)?,
+                schedule_value: row.get(3)?,
+                goal_template: row.get(4)?,
+                mode: row.get(5)?,
+                allowed_scopes: row.get(6)?,
+                enabled: row.get::<_, i64>(7)? != 0,
+                created_at_ms: row.get(8)?,
+                updated_at_ms: row.get(9)?,
+                last_run_at_ms: row.get(10)?,
+                last_status: row.get(11)?,
+                last_goal_id: row.get(12)?,
             }));
         }
         Ok(None)
     }
 
-    pub fn list_sessions(&self, limit: usize) -> Result<Vec<SessionRecord>> {
+    pub fn set_job_enabled(&self, job_id: &str, enabled: bool) -> Result<bool> {
+        let changed = self.conn.execute(
+            "UPDATE jobs
+             SET enabled = ?1, updated_at_ms = ?2
+             WHERE job_id = ?3",
+            params![if enabled { 1 } else { 0 }, now_epoch_ms(), job_id],
+        )?;
+        Ok(changed > 0)
+    }
+
+    pub fn remove_job(&self, job_id: &str) -> Result<bool> {
+        let changed = self
+            .conn
+            .execute("DELETE FROM jobs WHERE job_id = ?1", params![job_id])?;
+        Ok(changed > 0)
+    }
+
+    pub fn list_due_jobs(&self, now_ms: i64, limit: usize) -> Result<Vec<JobRecord>> {
+        let all = self.list_jobs()?;
+        let due = all
+            .into_iter()
+            .filter(|job| job.enabled)
+            .filter(|job| is_job_due(job, now_ms).unwrap_or(false))
+            .take(limit)
+            .collect();
+        Ok(due)
+    }
+
+    pub fn start_job_run(&self, job_id: &str) -> Result<String> {
+        let run_id = Uuid::new_v4().to_string();
+        let started_at_ms = now_epoch_ms();
+        self.conn.execute(
+            "INSERT INTO job_runs (run_id, job_id, started_at_ms, status)
+             VALUES (?1, ?2, ?3, 'running')",
+            params![run_id, job_id, started_at_ms],
+        )?;
+        self.conn.execute(
+            "UPDATE jobs
+             SET last_run_at_ms = ?1, last_status = 'running', updated_at_ms = ?1
+             WHERE job_id = ?2",
+            params![started_at_ms, job_id],
+        )?;
+        Ok(run_id)
+    }
+
+    pub fn finish_job_run(
+        &self,
+        run_id: &str,
+        status: &str,
+        goal_id: Option<&str>,
+        error_summary: Option<&str>,
+    ) -> Result<bool> {
+        let finished_at_ms = now_epoch_ms();
+        let changed = self.conn.execute(
+            "UPDATE job_runs
+             SET finished_at_ms = ?1, status = ?2, goal_id = ?3, error_summary = ?4
+             WHERE run_id = ?5",
+            params![finished_at_ms, status, goal_id, error_summary, run_id],
+        )?;
+        if changed == 0 {
+            return Ok(false);
+        }
+        let job_id: String = self.conn.query_row(
+            "SELECT job_id FROM job_runs WHERE run_id = ?1",
+            params![run_id],
+            |row| row.get(0),
+        )?;
+        self.conn.execute(
+            "UPDATE jobs
+             SET last_status = ?1, last_goal_id = ?2, updated_at_ms = ?3
+             WHERE job_id = ?4",
+            params![status, goal_id, finished_at_ms, job_id],
+        )?;
+        Ok(true)
+    }
+
+    pub fn list_job_runs(&self, job_id: &str, limit: usize) -> Result<Vec<JobRunRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, channel, peer_id, model_override, usage_mode, activation_mode, compactions_count, queue_depth, stop_requested
-             FROM sessions
-             ORDER BY updated_at DESC, rowid DESC
-             LIMIT ?1",
+            "SELECT run_id, job_id, started_at_ms, finished_at_ms, status, goal_id, error_summary
+             FROM job_runs
+             WHERE job_id = ?1
+             ORDER BY started_at_ms DESC
+             LIMIT ?2",
         )?;
-        let rows = stmt.query_map(params![limit as i64], |row| {
-            Ok(SessionRecord {
-                id: row.get(0)?,
-                channel: row.get(1)?,
-                peer_id: row.get(2)?,
-                model_override: row.get(3)?,
-                usage_mode: row.get(4)?,
-                activation_mode: row.get(5)?,
-                compactions_count: row.get(6)?,
-                queue_depth: row.get(7)?,
-                stop_requested: row.get::<_, i64>(8)? != 0,
+        let rows = stmt.query_map(params![job_id, limit as i64], |row| {
+            Ok(JobRunRecord {
+                run_id: row.get(0)?,
+                job_id: row.get(1)?,
+                started_at_ms: row.get(2)?,
+                finished_at_ms: row.get(3)?,
+                status: row.get(4)?,
+                goal_id: row.get(5)?,
+                error_summary: row.get(6)?,
             })
         })?;
         Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
-    pub fn add_session_message(
+    pub fn record_skill_run(
         &self,
-        session_id: &str,
-        role: &str,
-        content: &str,
-        compacted: bool,
-    ) -> Result<()> {
-        self.conn.execute(
-            "INSERT INTO session_messages (session_id, role, content, compacted)
-             VALUES (?1, ?2, ?3, ?4)",
-            params![session_id, role, content, if compacted { 1 } else { 0 }],
-        )?;
+        slug: &str,
+        goal_id: Option<&str>,
+        status: &str,
+        duration_ms: i64,
+        output_bytes: i64,
+    ) -> Result<i64> {
         self.conn.execute(
-            "UPDATE sessions
-             SET updated_at = CURRENT_TIMESTAMP
-             WHERE id = ?1",
-            params![session_id],
+            "INSERT INTO skill_runs (slug, goal_id, status, duration_ms, output_bytes, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                slug,
+                goal_id,
+                status,
+                duration_ms,
+                output_bytes,
+                now_epoch_ms()
+            ],
         )?;
-        Ok(())
+        Ok(self.conn.last_insert_rowid())
     }
 
-    pub fn list_session_messages(
-        &self,
-        session_id: &str,
-        limit: usize,
-    ) -> Result<Vec<SessionMessageRecord>> {
+    pub fn list_skill_runs(&self, slug: &str, limit: usize) -> Result<Vec<SkillRunRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, session_id, role, content, compacted
-             FROM session_messages
-             WHERE session_id = ?1
-             ORDER BY id DESC
+            "SELECT id, slug, goal_id, status, duration_ms, output_bytes, created_at_ms
+             FROM skill_runs
+             WHERE slug = ?1
+             ORDER BY created_at_ms DESC, id DESC
              LIMIT ?2",
         )?;
-        let rows = stmt.query_map(params![session_id, limit as i64], |row| {
-            Ok(SessionMessageRecord {
+        let rows = stmt.query_map(params![slug, limit as i64], |row| {
+            Ok(SkillRunRecord {
                 id: row.get(0)?,
-                session_id: row.get(1)?,
-                role: row.get(2)?,
-                content: row.get(3)?,
-                compacted: row.get::<_, i64>(4)? != 0,
+                slug: row.get(1)?,
+                goal_id: row.get(2)?,
+                status: row.get(3)?,
+                duration_ms: row.get(4)?,
+                output_bytes: row.get(5)?,
+                created_at_ms: row.get(6)?,
             })
         })?;
         Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
-    pub fn reset_session(&self, session_id: &str) -> Result<usize> {
-        let deleted = self.conn.execute(
-            "DELETE FROM session_messages WHERE session_id = ?1",
-            params![session_id],
+    pub fn skill_run_summaries(&self, days: usize) -> Result<Vec<SkillRunSummary>> {
+        const DAY_MS: i64 = 86_400_000;
+        let days = days.max(1);
+        let now_ms = now_epoch_ms();
+        let since_ms = now_ms - DAY_MS * days as i64;
+        let mut stmt = self.conn.prepare(
+            "SELECT slug, status, created_at_ms
+             FROM skill_runs
+             WHERE created_at_ms > ?1
+             ORDER BY slug ASC",
         )?;
+        let rows = stmt
+            .query_map(params![since_ms], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut summaries: Vec<SkillRunSummary> = Vec::new();
+        for (slug, status, created_at_ms) in rows {
+            if summaries.last().is_none_or(|summary| summary.slug != slug) {
+                summaries.push(SkillRunSummary {
+                    slug: slug.clone(),
+                    total_runs: 0,
+                    failed_runs: 0,
+                    daily_runs: vec![0; days],
+                });
+            }
+            let summary = summaries.last_mut().expect("summary pushed above");
+            summary.total_runs += 1;
+            if status == "failed" {
+                summary.failed_runs += 1;
+            }
+            let age_days = ((now_ms - created_at_ms).max(0) / DAY_MS) as usize;
+            if age_days < days {
+                summary.daily_runs[days - 1 - age_days] += 1;
+            }
+        }
+        Ok(summaries)
+    }
+
+    // Returns the number of uncleared violations for the skill, including this one.
+    pub fn record_skill_scope_violation(
+        &self,
+        slug: &str,
+        goal_id: Option<&str>,
+        tool_name: &str,
+        capability: &str,
+    ) -> Result<i64> {
         self.conn.execute(
-            "UPDATE sessions
-             SET queue_depth = 0, stop_requested = 0, updated_at = CURRENT_TIMESTAMP
-             WHERE id = ?1",
-            params![session_id],
+            "INSERT INTO skill_scope_violations (slug, goal_id, tool_name, capability, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![slug, goal_id, tool_name, capability, now_epoch_ms()],
         )?;
-        Ok(deleted)
+        Ok(self.conn.query_row(
+            "SELECT COUNT(*) FROM skill_scope_violations WHERE slug = ?1 AND cleared = 0",
+            params![slug],
+            |row| row.get(0),
+        )?)
     }
 
-    pub fn compact_session(&self, session_id: &str, instructions: Option<&str>) -> Result<usize> {
+    pub fn quarantine_skill(&self, slug: &str, reason: &str, violation_count: i64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO skill_quarantine (slug, reason, violation_count, quarantined_at_ms)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(slug) DO UPDATE SET
+               reason=excluded.reason,
+               violation_count=excluded.violation_count,
+               quarantined_at_ms=excluded.quarantined_at_ms",
+            params![slug, reason, violation_count, now_epoch_ms()],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_skill_quarantine(&self, slug: &str) -> Result<Option<SkillQuarantineRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, role, content
-             FROM session_messages
-             WHERE session_id = ?1 AND compacted = 0
-             ORDER BY id ASC",
+            "SELECT slug, reason, violation_count, quarantined_at_ms
+             FROM skill_quarantine
+             WHERE slug = ?1",
         )?;
-        let rows = stmt.query_map(params![session_id], |row| {
-            Ok((
-                row.get::<_, i64>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-            ))
-        })?;
-        let messages = rows.collect::<rusqlite::Result<Vec<_>>>()?;
-        if messages.len() < 3 {
-            return Ok(0);
-        }
-        let cutoff = messages.len().saturating_sub(2);
-        let mut summary = String::new();
-        if let Some(custom) = instructions {
-            summary.push_str("instructions: ");
-            summary.push_str(custom.trim());
-            summary.push('\n');
-        }
-        for (_, role, content) in messages.iter().take(cutoff) {
-            summary.push_str(role);
-            summary.push_str(": ");
-            summary.push_str(content);
-            summary.push('\n');
-        }
-        let tx = self.conn.unchecked_transaction()?;
-        for (id, _, _) in messages.iter().take(cutoff) {
-            tx.execute(
-                "UPDATE session_messages SET compacted = 1 WHERE id = ?1",
-                params![id],
-            )?;
+        let mut rows = stmt.query(params![slug])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(SkillQuarantineRecord {
+                slug: row.get(0)?,
+                reason: row.get(1)?,
+                violation_count: row.get(2)?,
+                quarantined_at_ms: row.get(3)?,
+            }));
         }
-        tx.execute(
-            "INSERT INTO session_messages (session_id, role, content, compacted)
-             VALUES (?1, 'summary', ?2, 1)",
-            params![session_id, summary.trim()],
-        )?;
-        tx.execute(
-            "UPDATE sessions
-             SET compactions_count = compactions_count + 1,
-                 updated_at = CURRENT_TIMESTAMP
-             WHERE id = ?1",
-            params![session_id],
-        )?;
-        tx.commit()?;
-        Ok(cutoff)
+        Ok(None)
     }
 
-    pub fn set_session_queue_depth(&self, session_id: &str, depth: i64) -> Result<()> {
+    // Lifts a quarantine and clears the violations that led to it so the count restarts.
+    pub fn release_skill_quarantine(&self, slug: &str) -> Result<bool> {
+        let changed = self.conn.execute(
+            "DELETE FROM skill_quarantine WHERE slug = ?1",
+            params![slug],
+        )?;
         self.conn.execute(
-            "UPDATE sessions
-             SET queue_depth = ?1, updated_at = CURRENT_TIMESTAMP
-             WHERE id = ?2",
-            params![depth.max(0), session_id],
+            "UPDATE skill_scope_violations SET cleared = 1 WHERE slug = ?1",
+            params![slug],
         )?;
-        Ok(())
+        Ok(changed > 0)
     }
 
-    pub fn mark_session_stop(&self, session_id: &str) -> Result<()> {
-        self.conn.execute(
-            "UPDATE sessions
-             SET stop_requested = 1, queue_depth = 0, updated_at = CURRENT_TIMESTAMP
-             WHERE id = ?1",
-            params![session_id],
+    pub fn last_goal_for_session(&self, session_id: &str) -> Result<Option<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id
+             FROM goals
+             WHERE session_id = ?1
+             ORDER BY updated_at DESC
+             LIMIT 1",
         )?;
-        Ok(())
+        let mut rows = stmt.query(params![session_id])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(row.get(0)?));
+        }
+        Ok(None)
     }
 
-    pub fn clear_session_stop(&self, session_id: &str) -> Result<()> {
+    pub fn create_approval_request(
+        &self,
+        tool_name: &str,
+        capability: &str,
+        input: &str,
+        requested_by: Option<&str>,
+        ttl_ms: u64,
+    ) -> Result<ApprovalRecord> {
+        self.create_approval_request_for_goal(
+            None,
+            tool_name,
+            capability,
+            input,
+            requested_by,
+            ttl_ms,
+        )
+    }
+
+    pub fn create_approval_request_for_goal(
+        &self,
+        goal_id: Option<&str>,
+        tool_name: &str,
+        capability: &str,
+        input: &str,
+        requested_by: Option<&str>,
+        ttl_ms: u64,
+    ) -> Result<ApprovalRecord> {
+        let now_ms = now_epoch_ms();
+        let expires_at_ms = now_ms.saturating_add(ttl_ms as i64);
+        let id = Uuid::new_v4().to_string();
+        let nonce = Uuid::new_v4().to_string();
+        let record = ApprovalRecord {
+            id: id.clone(),
+            nonce: nonce.clone(),
+            goal_id: goal_id.map(std::string::ToString::to_string),
+            tool_name: tool_name.to_string(),
+            capability: capability.to_string(),
+            input: input.to_string(),
+            status: "pending".to_string(),
+            requested_by: requested_by.map(std::string::ToString::to_string),
+            resolved_by: None,
+            expires_at_ms,
+            decision_reason: None,
+            original_input: None,
+        };
         self.conn.execute(
-            "UPDATE sessions
-             SET stop_requested = 0, updated_at = CURRENT_TIMESTAMP
-             WHERE id = ?1",
-            params![session_id],
+            "INSERT INTO approval_requests
+             (id, nonce, goal_id, tool_name, capability, input, status, requested_by, expires_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                record.id,
+                record.nonce,
+                record.goal_id,
+                record.tool_name,
+                record.capability,
+                record.input,
+                record.status,
+                record.requested_by,
+                record.expires_at_ms
+            ],
+        )?;
+        record_lifecycle_event(
+            &self.conn,
+            "approval_created",
+            &record.id,
+            &format!(
+                "goal_id={} tool={} capability={}",
+                record.goal_id.as_deref().unwrap_or(""),
+                record.tool_name,
+                record.capability
+            ),
         )?;
-        Ok(())
+        Ok(record)
     }
 
-    pub fn set_session_usage_mode(&self, session_id: &str, usage_mode: &str) -> Result<()> {
-        self.conn.execute(
-            "UPDATE sessions
-             SET usage_mode = ?1, updated_at = CURRENT_TIMESTAMP
-             WHERE id = ?2",
-            params![usage_mode, session_id],
+    pub fn get_approval_request(&self, approval_id: &str) -> Result<Option<ApprovalRecord>> {
+        self.expire_pending_approvals(now_epoch_ms())?;
+        let mut stmt = self.conn.prepare(
+            "SELECT id, nonce, goal_id, tool_name, capability, input, status, requested_by, resolved_by, expires_at_ms, decision_reason,
+                    original_input
+             FROM approval_requests
+             WHERE id = ?1",
         )?;
-        Ok(())
+        let mut rows = stmt.query(params![approval_id])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(ApprovalRecord {
+                id: row.get(0)?,
+                nonce: row.get(1)?,
+                goal_id: row.get(2)?,
+                tool_name: row.get(3)?,
+                capability: row.get(4)?,
+                input: row.get(5)?,
+                status: row.get(6)?,
+                requested_by: row.get(7)?,
+                resolved_by: row.get(8)?,
+                expires_at_ms: row.get(9)?,
+                decision_reason: row.get(10)?,
+                original_input: row.get(11)?,
+            }));
+        }
+        Ok(None)
     }
 
-    pub fn set_session_model_override(
+    pub fn add_approval_comment(
         &self,
-        session_id: &str,
-        model_override: Option<&str>,
-    ) -> Result<()> {
+        approval_id: &str,
+        author: &str,
+        body: &str,
+    ) -> Result<ApprovalCommentRecord> {
+        let created_at_ms = now_epoch_ms();
         self.conn.execute(
-            "UPDATE sessions
-             SET model_override = ?1, updated_at = CURRENT_TIMESTAMP
-             WHERE id = ?2",
-            params![model_override, session_id],
+            "INSERT INTO approval_comments (approval_id, author, body, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![approval_id, author, body, created_at_ms],
         )?;
-        Ok(())
+        Ok(ApprovalCommentRecord {
+            id: self.conn.last_insert_rowid(),
+            approval_id: approval_id.to_string(),
+            author: author.to_string(),
+            body: body.to_string(),
+            created_at_ms,
+        })
     }
 
-    pub fn set_session_activation_mode(
-        &self,
-        session_id: &str,
-        activation_mode: &str,
-    ) -> Result<()> {
-        self.conn.execute(
-            "UPDATE sessions
-             SET activation_mode = ?1, updated_at = CURRENT_TIMESTAMP
-             WHERE id = ?2",
-            params![activation_mode, session_id],
+    pub fn list_approval_comments(&self, approval_id: &str) -> Result<Vec<ApprovalCommentRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, approval_id, author, body, created_at_ms
+             FROM approval_comments
+             WHERE approval_id = ?1
+             ORDER BY id ASC",
         )?;
-        Ok(())
+        let rows = stmt.query_map(params![approval_id], |row| {
+            Ok(ApprovalCommentRecord {
+                id: row.get(0)?,
+                approval_id: row.get(1)?,
+                author: row.get(2)?,
+                body: row.get(3)?,
+                created_at_ms: row.get(4)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
-    pub fn upsert_installed_skill(&self, record: &InstalledSkillRecord) -> Result<()> {
-        self.conn.execute(
-            "INSERT INTO installed_skills
-             (slug, name, version, description, source, hash, signature_status, scopes, allowed_paths, allowed_hosts, last_run_goal_id)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
-             ON CONFLICT(slug) DO UPDATE SET
-               name=excluded.name,
-               version=excluded.version,
-               description=excluded.description,
-               source=excluded.source,
-               hash=excluded.hash,
-               signature_status=excluded.signature_status,
-               scopes=excluded.scopes,
-               allowed_paths=excluded.allowed_paths,
-               allowed_hosts=excluded.allowed_hosts,
-               last_run_goal_id=COALESCE(excluded.last_run_goal_id, installed_skills.last_run_goal_id),
-               updated_at=CURRENT_TIMESTAMP",
-            params![
-                record.slug,
-                record.name,
-                record.version,
-                record.description,
-                record.source,
-                record.hash,
-                record.signature_status,
-                record.scopes,
-                record.allowed_paths,
-                record.allowed_hosts,
-                record.last_run_goal_id
-            ],
-        )?;
-        Ok(())
+    /// Records `approver`'s decision, replacing any earlier one of theirs; returns how many
+    /// distinct approvers have approved so far.
+    pub fn record_approval_decision(
+        &self,
+        approval_id: &str,
+        approver: &str,
+        approved: bool,
+    ) -> Result<i64> {
+        record_approval_decision(&self.conn, approval_id, approver, approved)?;
+        Ok(self.conn.query_row(
+            "SELECT COUNT(*) FROM approval_decisions
+             WHERE approval_id = ?1 AND decision = 'approve'",
+            params![approval_id],
+            |row| row.get(0),
+        )?)
     }
 
-    pub fn list_installed_skills(&self) -> Result<Vec<InstalledSkillRecord>> {
+    /// Every approver's decision on `approval_id`, oldest first.
+    pub fn approval_decisions(&self, approval_id: &str) -> Result<Vec<ApprovalDecisionRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT slug, name, version, description, source, hash, signature_status, scopes, allowed_paths, allowed_hosts, last_run_goal_id
-             FROM installed_skills
-             ORDER BY slug ASC",
+            "SELECT approval_id, approver, decision, created_at_ms
+             FROM approval_decisions
+             WHERE approval_id = ?1
+             ORDER BY created_at_ms ASC, approver ASC",
         )?;
-        let rows = stmt.query_map([], |row| {
-            Ok(InstalledSkillRecord {
-                slug: row.get(0)?,
-                name: row.get(1)?,
-                version: row.get(2)?,
-                description: row.get(3)?,
-                source: row.get(4)?,
-                hash: row.get(5)?,
-                signature_status: row.get(6)?,
-                scopes: row.get(7)?,
-                allowed_paths: row.get(8)?,
-                allowed_hosts: row.get(9)?,
-                last_run_goal_id: row.get(10)?,
+        let rows = stmt.query_map(params![approval_id], |row| {
+            Ok(ApprovalDecisionRecord {
+                approval_id: row.get(0)?,
+                approver: row.get(1)?,
+                decision: row.get(2)?,
+                created_at_ms: row.get(3)?,
             })
         })?;
         Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
-    pub fn get_installed_skill(&self, slug: &str) -> Result<Option<InstalledSkillRecord>> {
+    pub fn list_lifecycle_events_after(
+        &self,
+        after_id: i64,
+        limit: usize,
+    ) -> Result<Vec<LifecycleEventRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT slug, name, version, description, source, hash, signature_status, scopes, allowed_paths, allowed_hosts, last_run_goal_id
-             FROM installed_skills
-             WHERE slug = ?1
-             LIMIT 1",
+            "SELECT id, kind, subject_id, detail, created_at_ms
+             FROM lifecycle_events
+             WHERE id > ?1
+             ORDER BY id ASC
+             LIMIT ?2",
         )?;
-        let mut rows = stmt.query(params![slug])?;
-        if let Some(row) = rows.next()? {
-            return Ok(Some(InstalledSkillRecord {
-                slug: row.get(0)?,
-                name: row.get(1)?,
-                version: row.get(2)?,
-                description: row.get(3)?,
-                source: row.get(4)?,
-                hash: row.get(5)?,
-                signature_status: row.get(6)?,
-                scopes: row.get(7)?,
-                allowed_paths: row.get(8)?,
-                allowed_hosts: row.get(9)?,
-                last_run_goal_id: row.get(10)?,
-            }));
-        }
-        Ok(None)
+        let rows = stmt.query_map(params![after_id, limit as i64], lifecycle_event_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
-    pub fn remove_installed_skill(&self, slug: &str) -> Result<bool> {
-        let changed = self.conn.execute(
-            "DELETE FROM installed_skills WHERE slug = ?1",
-            params![slug],
+    pub fn get_lifecycle_event(&self, id: i64) -> Result<Option<LifecycleEventRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, kind, subject_id, detail, created_at_ms
+             FROM lifecycle_events
+             WHERE id = ?1",
         )?;
-        Ok(changed > 0)
+        let mut rows = stmt.query_map(params![id], lifecycle_event_from_row)?;
+        Ok(rows.next().transpose()?)
     }
 
-    pub fn set_skill_last_run_goal(&self, slug: &str, goal_id: &str) -> Result<()> {
+    pub fn latest_lifecycle_event_id(&self) -> Result<i64> {
+        Ok(self.conn.query_row(
+            "SELECT COALESCE(MAX(id), 0) FROM lifecycle_events",
+            [],
+            |row| row.get(0),
+        )?)
+    }
+
+    pub fn webhook_cursor(&self, webhook: &str) -> Result<Option<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT last_event_id FROM webhook_cursors WHERE webhook = ?1")?;
+        let mut rows = stmt.query_map(params![webhook], |row| row.get(0))?;
+        Ok(rows.next().transpose()?)
+    }
+
+    pub fn set_webhook_cursor(&self, webhook: &str, last_event_id: i64) -> Result<()> {
         self.conn.execute(
-            "UPDATE installed_skills
-             SET last_run_goal_id = ?1, updated_at = CURRENT_TIMESTAMP
-             WHERE slug = ?2",
-            params![goal_id, slug],
+            "INSERT INTO webhook_cursors (webhook, last_event_id) VALUES (?1, ?2)
+             ON CONFLICT(webhook) DO UPDATE SET last_event_id = excluded.last_event_id",
+            params![webhook, last_event_id],
         )?;
         Ok(())
     }
 
-    pub fn has_approved_skill_exec_grant(&self, slug: &str) -> Result<bool> {
-        let mut stmt = self.conn.prepare(
-            "SELECT 1
-             FROM approval_requests
-             WHERE tool_name = 'skill_exec_grant'
-               AND input = ?1
-               AND status = 'approved'
-             LIMIT 1",
+    /// Queues a delivery; a second enqueue of the same event for the same webhook is ignored.
+    pub fn enqueue_webhook_delivery(&self, event_id: i64, webhook: &str) -> Result<()> {
+        let now = now_epoch_ms();
+        self.conn.execute(
+            "INSERT OR IGNORE INTO webhook_deliveries
+               (event_id, webhook, status, attempts, next_attempt_at_ms, updated_at_ms)
+             VALUES (?1, ?2, 'pending', 0, ?3, ?3)",
+            params![event_id, webhook, now],
         )?;
-        let mut rows = stmt.query(params![slug])?;
-        Ok(rows.next()?.is_some())
+        Ok(())
     }
 
-    pub fn get_runtime_risk_state(&self) -> Result<RuntimeRiskState> {
+    pub fn list_due_webhook_deliveries(
+        &self,
+        now_ms: i64,
+        limit: usize,
+    ) -> Result<Vec<WebhookDeliveryRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT risk_mode, yolo_armed_token, yolo_armed_at_ms, yolo_expires_at_ms, yolo_bypass_path_guard, last_changed_at_ms, last_changed_by
-             FROM runtime_risk_state
-             WHERE id = 1",
-        )?;
-        let mut rows = stmt.query([])?;
-        if let Some(row) = rows.next()? {
-            return Ok(RuntimeRiskState {
-                risk_mode: RiskMode::parse(&row.get::<_, String>(0)?),
-                yolo_armed_token: row.get(1)?,
-                yolo_armed_at_ms: row.get(2)?,
-                yolo_expires_at_ms: row.get(3)?,
-                yolo_bypass_path_guard: row.get::<_, i64>(4)? != 0,
-                last_changed_at_ms: row.get(5)?,
-                last_changed_by: row.get(6)?,
-            });
-        }
-        let now = now_epoch_ms();
-        self.conn.execute(
-            "INSERT INTO runtime_risk_state (id, risk_mode, yolo_bypass_path_guard, last_changed_at_ms, last_changed_by)
-             VALUES (1, 'secure', 1, ?1, 'cli')",
-            params![now],
+            "SELECT id, event_id, webhook, status, attempts, next_attempt_at_ms, last_error,
+                    last_status_code, updated_at_ms
+             FROM webhook_deliveries
+             WHERE status = 'pending' AND next_attempt_at_ms <= ?1
+             ORDER BY next_attempt_at_ms ASC, id ASC
+             LIMIT ?2",
         )?;
-        self.get_runtime_risk_state()
+        let rows = stmt.query_map(params![now_ms, limit as i64], webhook_delivery_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
-    pub fn arm_yolo(&self, changed_by: &str) -> Result<String> {
-        let token = Uuid::new_v4().simple().to_string();
-        let now = now_epoch_ms();
-        self.conn.execute(
-            "UPDATE runtime_risk_state
-             SET yolo_armed_token = ?1,
-                 yolo_armed_at_ms = ?2,
-                 last_changed_at_ms = ?2,
-                 last_changed_by = ?3
-             WHERE id = 1",
-            params![token, now, changed_by],
+    pub fn list_webhook_deliveries(&self, limit: usize) -> Result<Vec<WebhookDeliveryRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, event_id, webhook, status, attempts, next_attempt_at_ms, last_error,
+                    last_status_code, updated_at_ms
+             FROM webhook_deliveries
+             ORDER BY id DESC
+             LIMIT ?1",
         )?;
-        Ok(token)
+        let rows = stmt.query_map(params![limit as i64], webhook_delivery_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
-    pub fn enable_yolo(&self, changed_by: &str, ttl_minutes: i64) -> Result<()> {
+    /// Records one delivery attempt. `next_attempt_at_ms = None` ends retries with `status`.
+    pub fn record_webhook_attempt(
+        &self,
+        delivery_id: i64,
+        status: &str,
+        next_attempt_at_ms: Option<i64>,
+        status_code: Option<u16>,
+        error: Option<&str>,
+    ) -> Result<()> {
         let now = now_epoch_ms();
-        let ttl_ms = ttl_minutes.max(1).saturating_mul(60_000);
         self.conn.execute(
-            "UPDATE runtime_risk_state
-             SET risk_mode = 'yolo',
-                 yolo_expires_at_ms = ?1,
-                 yolo_armed_token = NULL,
-                 yolo_armed_at_ms = NULL,
-                 last_changed_at_ms = ?2,
-                 last_changed_by = ?3
-             WHERE id = 1",
-            params![now.saturating_add(ttl_ms), now, changed_by],
+            "UPDATE webhook_deliveries
+             SET status = ?1,
+                 attempts = attempts + 1,
+                 next_attempt_at_ms = COALESCE(?2, next_attempt_at_ms),
+                 last_status_code = ?3,
+                 last_error = ?4,
+                 updated_at_ms = ?5
+             WHERE id = ?6",
+            params![
+                status,
+                next_attempt_at_ms,
+                status_code.map(i64::from),
+                error,
+                now,
+                delivery_id
+            ],
         )?;
         Ok(())
     }
 
-    pub fn set_risk_mode_secure(&self, changed_by: &str) -> Result<()> {
-        let now = now_epoch_ms();
-        self.conn.execute(
-            "UPDATE runtime_risk_state
-             SET risk_mode = 'secure',
-                 yolo_expires_at_ms = NULL,
-                 yolo_armed_token = NULL,
-                 yolo_armed_at_ms = NULL,
-                 last_changed_at_ms = ?1,
-                 last_changed_by = ?2
-             WHERE id = 1",
-            params![now, changed_by],
+    /// Queues `message` for every operator routed for `event_kind` and returns the outbox
+    /// ids. Routes inside quiet hours are logged as suppressed instead.
+    pub fn enqueue_notification(
+        &self,
+        event_kind: &str,
+        subject_id: &str,
+        message: &str,
+    ) -> Result<Vec<i64>> {
+        let routes = self.notification_routes(event_kind)?;
+        let tx = self.conn.unchecked_transaction()?;
+        let ids = enqueue_notifications(
+            &tx,
+            &routes,
+            event_kind,
+            subject_id,
+            message,
+            now_epoch_ms(),
         )?;
-        Ok(())
+        tx.commit()?;
+        Ok(ids)
     }
 
-    pub fn apply_yolo_expiry(&self, changed_by: &str) -> Result<bool> {
-        let state = self.get_runtime_risk_state()?;
-        if state.risk_mode != RiskMode::Yolo {
-            return Ok(false);
-        }
-        let Some(expires_at) = state.yolo_expires_at_ms else {
-            return Ok(false);
+    /// Queues an [`approval_notice_message`] for a pending approval to each target and returns
+    /// the outbox ids; nothing is queued once the approval is no longer pending.
+    pub fn enqueue_approval_notices(
+        &self,
+        approval_id: &str,
+        targets: &[ChannelTarget],
+    ) -> Result<Vec<i64>> {
+        let Some(approval) = self.get_approval_request(approval_id)? else {
+            return Ok(Vec::new());
         };
-        if now_epoch_ms() >= expires_at {
-            self.set_risk_mode_secure(changed_by)?;
-            return Ok(true);
+        if approval.status != "pending" {
+            return Ok(Vec::new());
         }
-        Ok(false)
+        let now_ms = now_epoch_ms();
+        let notice = approval_notice_message(
+            approval_id,
+            &approval.tool_name,
+            &approval.capability,
+            approval.expires_at_ms,
+            now_ms,
+        );
+        let tx = self.conn.unchecked_transaction()?;
+        let mut ids = Vec::new();
+        for target in targets {
+            ids.push(insert_outbox(
+                &tx,
+                &approval_notice(approval_id, target, &notice),
+                now_ms,
+            )?);
+        }
+        tx.commit()?;
+        Ok(ids)
     }
 
-    pub fn set_yolo_expiry_at_ms(&self, expires_at_ms: i64) -> Result<()> {
-        self.conn.execute(
-            "UPDATE runtime_risk_state
-             SET yolo_expires_at_ms = ?1
-             WHERE id = 1",
-            params![expires_at_ms],
+    pub fn get_outbox(&self, id: i64) -> Result<Option<OutboxRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, operator, event_kind, subject_id, channel, target, message, status,
+                    attempts, next_attempt_at_ms, last_error, created_at_ms, delivered_at_ms
+             FROM channel_outbox WHERE id = ?1",
         )?;
-        Ok(())
+        let mut rows = stmt.query_map(params![id], outbox_from_row)?;
+        Ok(rows.next().transpose()?)
     }
 
-    pub fn add_connector(
+    pub fn list_due_outbox(&self, now_ms: i64, limit: usize) -> Result<Vec<OutboxRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, operator, event_kind, subject_id, channel, target, message, status,
+                    attempts, next_attempt_at_ms, last_error, created_at_ms, delivered_at_ms
+             FROM channel_outbox
+             WHERE status = 'pending' AND next_attempt_at_ms <= ?1
+             ORDER BY next_attempt_at_ms ASC, id ASC
+             LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![now_ms, limit as i64], outbox_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn list_outbox(&self, status: Option<&str>, limit: usize) -> Result<Vec<OutboxRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, operator, event_kind, subject_id, channel, target, message, status,
+                    attempts, next_attempt_at_ms, last_error, created_at_ms, delivered_at_ms
+             FROM channel_outbox
+             WHERE ?1 IS NULL OR status = ?1
+             ORDER BY id DESC
+             LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![status, limit as i64], outbox_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Records one send attempt. `next_attempt_at_ms = None` ends retries with `status`.
+    pub fn record_outbox_attempt(
         &self,
-        id: &str,
-        connector_type: &str,
-        display_name: &str,
-        config_json: &str,
+        id: i64,
+        status: &str,
+        next_attempt_at_ms: Option<i64>,
+        error: Option<&str>,
     ) -> Result<()> {
+        let now = now_epoch_ms();
         self.conn.execute(
-            "INSERT INTO connectors (id, type, display_name, config_json)
-             VALUES (?1, ?2, ?3, ?4)",
-            params![id, connector_type, display_name, config_json],
+            "UPDATE channel_outbox
+             SET status = ?1,
+                 attempts = attempts + 1,
+                 next_attempt_at_ms = COALESCE(?2, next_attempt_at_ms),
+                 last_error = ?3,
+                 delivered_at_ms = CASE WHEN ?1 = 'delivered' THEN ?4 ELSE delivered_at_ms END
+             WHERE id = ?5",
+            params![status, next_attempt_at_ms, error, now, id],
         )?;
         Ok(())
     }
 
-    pub fn update_connector(
-        &self,
-        id: &str,
-        display_name: &str,
-        config_json: &str,
-    ) -> Result<bool> {
+    /// Marks a pending row delivered after an out-of-band send, such as a Discord reply sent
+    /// by the handler that received the message.
+    pub fn mark_outbox_delivered(&self, id: i64) -> Result<bool> {
         let changed = self.conn.execute(
-            "UPDATE connectors
-             SET display_name = ?1,
-                 config_json = ?2,
-                 updated_at = CURRENT_TIMESTAMP
-             WHERE id = ?3",
-            params![display_name, config_json, id],
+            "UPDATE channel_outbox
+             SET status = 'delivered', attempts = attempts + 1, delivered_at_ms = ?1
+             WHERE id = ?2 AND status = 'pending'",
+            params![now_epoch_ms(), id],
         )?;
         Ok(changed > 0)
     }
 
-    pub fn remove_connector(&self, id: &str) -> Result<bool> {
-        let changed = self
-            .conn
-            .execute("DELETE FROM connectors WHERE id = ?1", params![id])?;
+    pub fn upsert_notification_pref(&self, pref: &NotificationPrefRecord) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO notification_prefs
+               (operator, event_kind, channel, target, quiet_start_minute, quiet_end_minute, updated_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(operator, event_kind) DO UPDATE SET
+               channel = excluded.channel,
+               target = excluded.target,
+               quiet_start_minute = excluded.quiet_start_minute,
+               quiet_end_minute = excluded.quiet_end_minute,
+               updated_at_ms = excluded.updated_at_ms",
+            params![
+                pref.operator,
+                pref.event_kind,
+                pref.channel,
+                pref.target,
+                pref.quiet_start_minute,
+                pref.quiet_end_minute,
+                now_epoch_ms()
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_notification_pref(&self, operator: &str, event_kind: &str) -> Result<bool> {
+        let changed = self.conn.execute(
+            "DELETE FROM notification_prefs WHERE operator = ?1 AND event_kind = ?2",
+            params![operator, event_kind],
+        )?;
         Ok(changed > 0)
     }
 
-    pub fn list_connectors(&self) -> Result<Vec<ConnectorRecord>> {
+    pub fn list_notification_prefs(&self) -> Result<Vec<NotificationPrefRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, type, display_name, config_json, last_test_at_ms, last_test_status
-             FROM connectors
-             ORDER BY display_name ASC",
+            "SELECT operator, event_kind, channel, target, quiet_start_minute, quiet_end_minute
+             FROM notification_prefs
+             ORDER BY operator ASC, event_kind ASC",
         )?;
         let rows = stmt.query_map([], |row| {
-            Ok(ConnectorRecord {
-                id: row.get(0)?,
-                connector_type: row.get(1)?,
-                display_name: row.get(2)?,
-                config_json: row.get(3)?,
-                last_test_at_ms: row.get(4)?,
-                last_test_status: row.get(5)?,
+            Ok(NotificationPrefRecord {
+                operator: row.get(0)?,
+                event_kind: row.get(1)?,
+                channel: row.get(2)?,
+                target: row.get(3)?,
+                quiet_start_minute: row.get(4)?,
+                quiet_end_minute: row.get(5)?,
             })
         })?;
         Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
-    pub fn get_connector(&self, id: &str) -> Result<Option<ConnectorRecord>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, type, display_name, config_json, last_test_at_ms, last_test_status
-             FROM connectors
-             WHERE id = ?1
-             LIMIT 1",
-        )?;
-        let mut rows = stmt.query(params![id])?;
-        if let Some(row) = rows.next()? {
-            return Ok(Some(ConnectorRecord {
-                id: row.get(0)?,
-                connector_type: row.get(1)?,
-                display_name: row.get(2)?,
-                config_json: row.get(3)?,
-                last_test_at_ms: row.get(4)?,
-                last_test_status: row.get(5)?,
-            }));
+    /// One preference per operator for `event_kind`: an exact match wins over a `*` entry.
+    pub fn notification_routes(&self, event_kind: &str) -> Result<Vec<NotificationPrefRecord>> {
+        let mut routes: Vec<NotificationPrefRecord> = Vec::new();
+        for pref in self.list_notification_prefs()? {
+            if pref.event_kind != event_kind && pref.event_kind != "*" {
+                continue;
+            }
+            match routes.iter_mut().find(|r| r.operator == pref.operator) {
+                Some(existing) if existing.event_kind == "*" => *existing = pref,
+                Some(_) => {}
+                None => routes.push(pref),
+            }
         }
-        Ok(None)
-    }
-
-    pub fn record_connector_test(&self, id: &str, status: &str) -> Result<bool> {
-        let changed = self.conn.execute(
-            "UPDATE connectors
-             SET last_test_at_ms = ?1,
-                 last_test_status = ?2,
-                 updated_at = CURRENT_TIMESTAMP
-             WHERE id = ?3",
-            params![now_epoch_ms(), status, id],
-        )?;
-        Ok(changed > 0)
+        Ok(routes)
     }
 
-    pub fn record_connector_tool_usage(
+    pub fn record_notification(
         &self,
-        connector_id: &str,
-        tool_name: &str,
-        last_goal_id: Option<&str>,
-    ) -> Result<()> {
-        self.conn.execute(
-            "INSERT INTO connector_tool_usage
-             (connector_id, tool_name, last_used_at_ms, last_goal_id)
-             VALUES (?1, ?2, ?3, ?4)",
-            params![connector_id, tool_name, now_epoch_ms(), last_goal_id],
-        )?;
-        Ok(())
-    }
-
-    pub fn add_job(&self, job: NewJobRecord<'_>) -> Result<()> {
-        let now = now_epoch_ms();
+        pref: &NotificationPrefRecord,
+        event_kind: &str,
+        message: &str,
+        status: &str,
+        detail: &str,
+    ) -> Result<NotificationLogRecord> {
+        let created_at_ms = now_epoch_ms();
         self.conn.execute(
-            "INSERT INTO jobs
-             (job_id, name, schedule_kind, schedule_value, goal_template, mode, allowed_scopes, enabled, created_at_ms, updated_at_ms)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 1, ?8, ?8)",
+            "INSERT INTO notification_log
+               (operator, event_kind, channel, target, message, status, detail, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
             params![
-                job.job_id,
-                job.name,
-                job.schedule_kind,
-                job.schedule_value,
-                job.goal_template,
-                job.mode,
-                job.allowed_scopes,
-                now
+                pref.operator,
+                event_kind,
+                pref.channel,
+                pref.target,
+                message,
+                status,
+                detail,
+                created_at_ms
             ],
         )?;
-        Ok(())
+        Ok(NotificationLogRecord {
+            id: self.conn.last_insert_rowid(),
+            operator: pref.operator.clone(),
+            event_kind: event_kind.to_string(),
+            channel: pref.channel.clone(),
+            target: pref.target.clone(),
+            message: message.to_string(),
+            status: status.to_string(),
+            detail: detail.to_string(),
+            created_at_ms,
+        })
     }
 
-    pub fn list_jobs(&self) -> Result<Vec<JobRecord>> {
+    pub fn list_notification_log(&self, limit: usize) -> Result<Vec<NotificationLogRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT job_id, name, schedule_kind, schedule_value, goal_template, mode, allowed_scopes, enabled,
-                    created_at_ms, updated_at_ms, last_run_at_ms, last_status, last_goal_id
-             FROM jobs
-             ORDER BY created_at_ms DESC",
+            "SELECT id, operator, event_kind, channel, target, message, status, detail, created_at_ms
+             FROM notification_log
+             ORDER BY id DESC
+             LIMIT ?1",
         )?;
-        let rows = stmt.query_map([], |row| {
-            Ok(JobRecord {
-                job_id: row.get(0)?,
-                name: row.get(1)?,
-                schedule_kind: row.get(2)?,
-                schedule_value: row.get(3)?,
-                goal_template: row.get(4)?,
-                mode: row.get(5)?,
-                allowed_scopes: row.get(6)?,
-                enabled: row.get::<_, i64>(7)? != 0,
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(NotificationLogRecord {
+                id: row.get(0)?,
+                operator: row.get(1)?,
+                event_kind: row.get(2)?,
+                channel: row.get(3)?,
+                target: row.get(4)?,
+                message: row.get(5)?,
+                status: row.get(6)?,
+                detail: row.get(7)?,
                 created_at_ms: row.get(8)?,
-                updated_at_ms: row.get(9)?,
-                last_run_at_ms: row.get(10)?,
-                last_status: row.get(11)?,
-                last_goal_id: row.get(12)?,
             })
         })?;
         Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
-    pub fn get_job(&self, job_id: &str) -> Result<Option<JobRecord>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT job_id, name, schedule_kind, schedule_value, goal_template, mode, allowed_scopes, enabled,
-                    created_at_ms, updated_at_ms, last_run_at_ms, last_status, last_goal_id
-             FROM jobs
-             WHERE job_id = ?1
-             LIMIT 1",
-        )?;
-        let mut rows = stmt.query(params![job_id])?;
+    pub fn session_id_for_goal(&self, goal_id: &str) -> Result<Option<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT session_id FROM goals WHERE id = ?1")?;
+        let mut rows = stmt.query(params![goal_id])?;
         if let Some(row) = rows.next()? {
-            return Ok(Some(JobRecord {
-                job_id: row.get(0)?,
-                name: row.get(1)?,
-                schedule_kind: row.get(2)?,
-                schedule_value: row.get(3)?,
-                goal_template: row.get(4)?,
-                mode: row.get(5)?,
-                allowed_scopes: row.get(6)?,
-                enabled: row.get::<_, i64>(7)? != 0,
-                created_at_ms: row.get(8)?,
-                updated_at_ms: row.get(9)?,
-                last_run_at_ms: row.get(10)?,
-                last_status: row.get(11)?,
-                last_goal_id: row.get(12)?,
-            }));
+            return Ok(row.get(0)?);
         }
         Ok(None)
     }
 
-    pub fn set_job_enabled(&self, job_id: &str, enabled: bool) -> Result<bool> {
-        let changed = self.conn.execute(
-            "UPDATE jobs
-             SET enabled = ?1, updated_at_ms = ?2
-             WHERE job_id = ?3",
-            params![if enabled { 1 } else { 0 }, now_epoch_ms(), job_id],
+    pub fn list_pending_approvals(&self) -> Result<Vec<ApprovalRecord>> {
+        self.expire_pending_approvals(now_epoch_ms())?;
+        let mut stmt = self.conn.prepare(
+            "SELECT id, nonce, goal_id, tool_name, capability, input, status, requested_by, resolved_by, expires_at_ms, decision_reason,
+                    original_input
+             FROM approval_requests
+             WHERE status = 'pending'
+             ORDER BY created_at ASC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(ApprovalRecord {
+                id: row.get(0)?,
+                nonce: row.get(1)?,
+                goal_id: row.get(2)?,
+                tool_name: row.get(3)?,
+                capability: row.get(4)?,
+                input: row.get(5)?,
+                status: row.get(6)?,
+                requested_by: row.get(7)?,
+                resolved_by: row.get(8)?,
+                expires_at_ms: row.get(9)?,
+                decision_reason: row.get(10)?,
+                original_input: row.get(11)?,
+            })
+        })?;
+        let approvals = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(approvals)
+    }
+
+    /// Approves or denies a pending request. An approval may carry `edited_input`: it replaces
+    /// `input`, so it is what runs, the requested input moves to `original_input`, and the goal
+    /// gets an `approval_modified` trace.
+    pub fn resolve_approval_request(
+        &self,
+        approval_id: &str,
+        approved: bool,
+        resolved_by: Option<&str>,
+        reason: Option<&str>,
+        edited_input: Option<&str>,
+    ) -> Result<bool> {
+        if edited_input.is_some() && !approved {
+            bail!("only an approval can carry an edited input");
+        }
+        self.expire_pending_approvals(now_epoch_ms())?;
+        let status = if approved { "approved" } else { "denied" };
+        let tx = self.conn.unchecked_transaction()?;
+        let rows_changed = tx.execute(
+            "UPDATE approval_requests
+             SET status = ?1, resolved_by = ?2, decision_reason = ?3, resolved_at = CURRENT_TIMESTAMP
+             WHERE id = ?4 AND status = 'pending'",
+            params![status, resolved_by, reason, approval_id],
+        )?;
+        if rows_changed > 0
+            && let Some(approver) = resolved_by
+        {
+            record_approval_decision(&tx, approval_id, approver, approved)?;
+        }
+        if rows_changed > 0
+            && let Some(edited) = edited_input
+        {
+            let (goal_id, original): (Option<String>, String) = tx.query_row(
+                "SELECT goal_id, input FROM approval_requests WHERE id = ?1",
+                params![approval_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            tx.execute(
+                "UPDATE approval_requests SET original_input = input, input = ?1 WHERE id = ?2",
+                params![edited, approval_id],
+            )?;
+            if let Some(goal_id) = goal_id {
+                insert_trace(
+                    &tx,
+                    &TraceEvent::new(
+                        goal_id,
+                        "approval_modified",
+                        format!(
+                            "approval={approval_id} by={} original_bytes={} edited_bytes={}",
+                            resolved_by.unwrap_or("unknown"),
+                            original.len(),
+                            edited.len()
+                        ),
+                    ),
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(rows_changed > 0)
+    }
+
+    // Resolves several approvals in one transaction; returns which ids were still pending.
+    pub fn resolve_approval_requests_bulk(
+        &self,
+        approval_ids: &[String],
+        approved: bool,
+        resolved_by: Option<&str>,
+        reason: Option<&str>,
+    ) -> Result<Vec<(String, bool)>> {
+        self.expire_pending_approvals(now_epoch_ms())?;
+        let status = if approved { "approved" } else { "denied" };
+        let tx = self.conn.unchecked_transaction()?;
+        let mut results = Vec::with_capacity(approval_ids.len());
+        for approval_id in approval_ids {
+            let rows_changed = tx.execute(
+                "UPDATE approval_requests
+                 SET status = ?1, resolved_by = ?2, decision_reason = ?3, resolved_at = CURRENT_TIMESTAMP
+                 WHERE id = ?4 AND status = 'pending'",
+                params![status, resolved_by, reason, approval_id],
+            )?;
+            if rows_changed > 0
+                && let Some(approver) = resolved_by
+            {
+                record_approval_decision(&tx, approval_id, approver, approved)?;
+            }
+            results.push((approval_id.clone(), rows_changed > 0));
+        }
+        tx.commit()?;
+        Ok(results)
+    }
+
+    pub fn approval_has_tool_run(&self, approval_id: &str) -> Result<bool> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT 1 FROM tool_runs WHERE approval_id = ?1 LIMIT 1")?;
+        let mut rows = stmt.query(params![approval_id])?;
+        Ok(rows.next()?.is_some())
+    }
+
+    pub fn record_tool_run(
+        &self,
+        approval_id: Option<&str>,
+        tool_name: &str,
+        status: &str,
+        output: &str,
+    ) -> Result<ToolRunRecord> {
+        let record = ToolRunRecord {
+            id: Uuid::new_v4().to_string(),
+            approval_id: approval_id.map(std::string::ToString::to_string),
+            tool_name: tool_name.to_string(),
+            status: status.to_string(),
+            output: output.to_string(),
+        };
+        self.conn.execute(
+            "INSERT INTO tool_runs (id, approval_id, tool_name, status, output)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                record.id,
+                record.approval_id,
+                record.tool_name,
+                record.status,
+                self.offload_output(&record.output)?
+            ],
         )?;
-        Ok(changed > 0)
+        Ok(record)
     }
 
-    pub fn remove_job(&self, job_id: &str) -> Result<bool> {
-        let changed = self
+    /// The stored output of a tool run, read back from the blob store when it was
+    /// offloaded; `None` for unknown runs.
+    pub fn get_tool_run_output(&self, run_id: &str) -> Result<Option<String>> {
+        let mut stmt = self
             .conn
-            .execute("DELETE FROM jobs WHERE job_id = ?1", params![job_id])?;
-        Ok(changed > 0)
+            .prepare("SELECT output FROM tool_runs WHERE id = ?1")?;
+        let mut rows = stmt.query(params![run_id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(self.resolve_output(&row.get::<_, String>(0)?)?)),
+            None => Ok(None),
+        }
     }
 
-    pub fn list_due_jobs(&self, now_ms: i64, limit: usize) -> Result<Vec<JobRecord>> {
-        let all = self.list_jobs()?;
-        let due = all
-            .into_iter()
-            .filter(|job| job.enabled)
-            .filter(|job| is_job_due(job, now_ms).unwrap_or(false))
-            .take(limit)
-            .collect();
-        Ok(due)
+    pub fn expire_pending_approvals(&self, now_ms: i64) -> Result<usize> {
+        let mut stmt = self.conn.prepare(
+            "UPDATE approval_requests
+             SET status = 'expired', resolved_at = CURRENT_TIMESTAMP
+             WHERE status = 'pending' AND COALESCE(expires_at_ms, 0) <= ?1
+             RETURNING id, tool_name",
+        )?;
+        let expired = stmt
+            .query_map(params![now_ms], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for (id, tool_name) in &expired {
+            record_lifecycle_event(
+                &self.conn,
+                "approval_expired",
+                id,
+                &format!("tool={tool_name}"),
+            )?;
+            self.notify_approval_expired(id, tool_name, now_ms)?;
+        }
+        Ok(expired.len())
     }
 
-    pub fn start_job_run(&self, job_id: &str) -> Result<String> {
-        let run_id = Uuid::new_v4().to_string();
-        let started_at_ms = now_epoch_ms();
-        self.conn.execute(
-            "INSERT INTO job_runs (run_id, job_id, started_at_ms, status)
-             VALUES (?1, ?2, ?3, 'running')",
-            params![run_id, job_id, started_at_ms],
+    // Tells the requester how to get the action back: a session message, a reply on the
+    // conversation the run answered on, and the `approval_expired` operator routes.
+    fn notify_approval_expired(
+        &self,
+        approval_id: &str,
+        tool_name: &str,
+        now_ms: i64,
+    ) -> Result<()> {
+        let goal_id: Option<String> = self.conn.query_row(
+            "SELECT goal_id FROM approval_requests WHERE id = ?1",
+            params![approval_id],
+            |row| row.get(0),
         )?;
-        self.conn.execute(
-            "UPDATE jobs
-             SET last_run_at_ms = ?1, last_status = 'running', updated_at_ms = ?1
-             WHERE job_id = ?2",
-            params![started_at_ms, job_id],
+        let message = match goal_id.as_deref() {
+            Some(goal_id) => format!(
+                "approval {approval_id} for {tool_name} expired; send /rerequest {goal_id} to ask again"
+            ),
+            None => format!("approval {approval_id} for {tool_name} expired"),
+        };
+        if let Some(goal_id) = goal_id.as_deref() {
+            if let Some(session_id) = self.session_id_for_goal(goal_id)? {
+                self.add_session_message(&session_id, "approval_expired", &message, false)?;
+            }
+            let mut stmt = self.conn.prepare(
+                "SELECT channel, target FROM channel_outbox
+                 WHERE event_kind = 'run_reply' AND subject_id = ?1
+                 ORDER BY id DESC
+                 LIMIT 1",
+            )?;
+            let mut rows = stmt.query(params![goal_id])?;
+            if let Some(row) = rows.next()? {
+                insert_outbox(
+                    &self.conn,
+                    &OutboxMessage {
+                        operator: None,
+                        event_kind: "approval_expired".to_string(),
+                        subject_id: approval_id.to_string(),
+                        channel: row.get(0)?,
+                        target: row.get(1)?,
+                        message: message.clone(),
+                    },
+                    now_ms,
+                )?;
+            }
+        }
+        let routes = self.notification_routes("approval_expired")?;
+        enqueue_notifications(
+            &self.conn,
+            &routes,
+            "approval_expired",
+            approval_id,
+            &message,
+            now_ms,
         )?;
-        Ok(run_id)
+        Ok(())
     }
 
-    pub fn finish_job_run(
-        &self,
-        run_id: &str,
-        status: &str,
-        goal_id: Option<&str>,
-        error_summary: Option<&str>,
-    ) -> Result<bool> {
-        let finished_at_ms = now_epoch_ms();
-        let changed = self.conn.execute(
-            "UPDATE job_runs
-             SET finished_at_ms = ?1, status = ?2, goal_id = ?3, error_summary = ?4
-             WHERE run_id = ?5",
-            params![finished_at_ms, status, goal_id, error_summary, run_id],
+    /// The newest approval requested for `goal_id`, whatever its status.
+    pub fn latest_approval_for_goal(&self, goal_id: &str) -> Result<Option<ApprovalRecord>> {
+        self.expire_pending_approvals(now_epoch_ms())?;
+        let mut stmt = self.conn.prepare(
+            "SELECT id, nonce, goal_id, tool_name, capability, input, status, requested_by, resolved_by, expires_at_ms, decision_reason,
+                    original_input
+             FROM approval_requests
+             WHERE goal_id = ?1
+             ORDER BY created_at DESC, rowid DESC
+             LIMIT 1",
         )?;
-        if changed == 0 {
-            return Ok(false);
+        let mut rows = stmt.query_map(params![goal_id], approval_from_row)?;
+        Ok(rows.next().transpose()?)
+    }
+
+    /// Mints a fresh pending approval for the same action as an expired one. Tool, capability,
+    /// input (the preview the approver saw), goal, and requester carry over; no replanning.
+    pub fn rerequest_approval(&self, expired_id: &str, ttl_ms: u64) -> Result<ApprovalRecord> {
+        let previous = self
+            .get_approval_request(expired_id)?
+            .ok_or_else(|| anyhow::anyhow!("approval not found: {expired_id}"))?;
+        if previous.status != "expired" {
+            anyhow::bail!(
+                "approval {expired_id} is {}, only expired approvals can be re-requested",
+                previous.status
+            );
         }
-        let job_id: String = self.conn.query_row(
-            "SELECT job_id FROM job_runs WHERE run_id = ?1",
-            params![run_id],
-            |row| row.get(0),
+        let tx = self.conn.unchecked_transaction()?;
+        let record = self.create_approval_request_for_goal(
+            previous.goal_id.as_deref(),
+            &previous.tool_name,
+            &previous.capability,
+            &previous.input,
+            previous.requested_by.as_deref(),
+            ttl_ms,
         )?;
-        self.conn.execute(
-            "UPDATE jobs
-             SET last_status = ?1, last_goal_id = ?2, updated_at_ms = ?3
-             WHERE job_id = ?4",
-            params![status, goal_id, finished_at_ms, job_id],
+        tx.execute(
+            "UPDATE approval_requests SET rerequested_from = ?1 WHERE id = ?2",
+            params![expired_id, record.id],
         )?;
-        Ok(true)
+        if let Some(goal_id) = previous.goal_id.as_deref() {
+            insert_trace(
+                &tx,
+                &TraceEvent::new(
+                    goal_id,
+                    "approval_rerequested",
+                    format!("previous={expired_id} approval={}", record.id),
+                ),
+            )?;
+        }
+        tx.commit()?;
+        Ok(record)
     }
 
-    pub fn list_job_runs(&self, job_id: &str, limit: usize) -> Result<Vec<JobRunRecord>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT run_id, job_id, started_at_ms, finished_at_ms, status, goal_id, error_summary
-             FROM job_runs
-             WHERE job_id = ?1
-             ORDER BY started_at_ms DESC
-             LIMIT ?2",
+    /// The expired approval a re-requested one replaced, if any.
+    pub fn rerequested_from(&self, approval_id: &str) -> Result<Option<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT rerequested_from FROM approval_requests WHERE id = ?1")?;
+        let mut rows = stmt.query(params![approval_id])?;
+        if let Some(row) = rows.next()? {
+            return Ok(row.get(0)?);
+        }
+        Ok(None)
+    }
+
+    pub fn upsert_semantic_fact(
+        &self,
+        namespace: &str,
+        fact_key: &str,
+        fact_value: &str,
+        source: &str,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO semantic_facts (namespace, fact_key, fact_value, source)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![namespace, fact_key, fact_value, source],
         )?;
-        let rows = stmt.query_map(params![job_id, limit as i64], |row| {
-            Ok(JobRunRecord {
-                run_id: row.get(0)?,
-                job_id: row.get(1)?,
-                started_at_ms: row.get(2)?,
-                finished_at_ms: row.get(3)?,
-                status: row.get(4)?,
-                goal_id: row.get(5)?,
-                error_summary: row.get(6)?,
-            })
-        })?;
-        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        Ok(())
     }
 
-    pub fn last_goal_for_session(&self, session_id: &str) -> Result<Option<String>> {
+    /// Latest value recorded for a fact; older rows are kept as history.
+    pub fn get_semantic_fact(&self, namespace: &str, fact_key: &str) -> Result<Option<String>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id
-             FROM goals
-             WHERE session_id = ?1
-             ORDER BY updated_at DESC
+            "SELECT fact_value FROM semantic_facts
+             WHERE namespace = ?1 AND fact_key = ?2
+             ORDER BY id DESC
              LIMIT 1",
         )?;
-        let mut rows = stmt.query(params![session_id])?;
+        let mut rows = stmt.query(params![namespace, fact_key])?;
         if let Some(row) = rows.next()? {
             return Ok(Some(row.get(0)?));
         }
         Ok(None)
     }
 
-    pub fn create_approval_request(
-        &self,
-        tool_name: &str,
-        capability: &str,
-        input: &str,
-        requested_by: Option<&str>,
-        ttl_ms: u64,
-    ) -> Result<ApprovalRecord> {
-        self.create_approval_request_for_goal(
-            None,
-            tool_name,
-            capability,
-            input,
-            requested_by,
-            ttl_ms,
-        )
-    }
-
-    pub fn create_approval_request_for_goal(
-        &self,
-        goal_id: Option<&str>,
-        tool_name: &str,
-        capability: &str,
-        input: &str,
-        requested_by: Option<&str>,
-        ttl_ms: u64,
-    ) -> Result<ApprovalRecord> {
-        let now_ms = now_epoch_ms();
-        let expires_at_ms = now_ms.saturating_add(ttl_ms as i64);
-        let id = Uuid::new_v4().to_string();
-        let nonce = Uuid::new_v4().to_string();
-        let record = ApprovalRecord {
-            id: id.clone(),
-            nonce: nonce.clone(),
-            goal_id: goal_id.map(std::string::ToString::to_string),
-            tool_name: tool_name.to_string(),
-            capability: capability.to_string(),
-            input: input.to_string(),
-            status: "pending".to_string(),
-            requested_by: requested_by.map(std::string::ToString::to_string),
-            resolved_by: None,
-            expires_at_ms,
-            decision_reason: None,
-        };
+    /// Appends `entry`, ignoring its `id` and `anomaly`, and returns the new row id.
+    pub fn record_access(&self, entry: &AccessLogEntry) -> Result<i64> {
         self.conn.execute(
-            "INSERT INTO approval_requests
-             (id, nonce, goal_id, tool_name, capability, input, status, requested_by, expires_at_ms)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            "INSERT INTO access_log
+               (at_ms, channel, actor_id, remote_addr, kind, action, outcome)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
             params![
-                record.id,
-                record.nonce,
-                record.goal_id,
-                record.tool_name,
-                record.capability,
-                record.input,
-                record.status,
-                record.requested_by,
-                record.expires_at_ms
+                entry.at_ms,
+                entry.channel,
+                entry.actor_id,
+                entry.remote_addr,
+                entry.kind,
+                entry.action,
+                entry.outcome
             ],
         )?;
-        Ok(record)
+        Ok(self.conn.last_insert_rowid())
     }
 
-    pub fn get_approval_request(&self, approval_id: &str) -> Result<Option<ApprovalRecord>> {
-        self.expire_pending_approvals(now_epoch_ms())?;
-        let mut stmt = self.conn.prepare(
-            "SELECT id, nonce, goal_id, tool_name, capability, input, status, requested_by, resolved_by, expires_at_ms, decision_reason
-             FROM approval_requests
-             WHERE id = ?1",
+    /// When the actor first reached the gateway on `channel`, if ever.
+    pub fn actor_first_seen_ms(&self, channel: &str, actor_id: &str) -> Result<Option<i64>> {
+        Ok(self.conn.query_row(
+            "SELECT MIN(at_ms) FROM access_log WHERE channel = ?1 AND actor_id = ?2",
+            params![channel, actor_id],
+            |row| row.get(0),
+        )?)
+    }
+
+    pub fn count_actor_access_since(
+        &self,
+        channel: &str,
+        actor_id: &str,
+        since_ms: i64,
+    ) -> Result<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(1) FROM access_log
+             WHERE channel = ?1 AND actor_id = ?2 AND at_ms >= ?3",
+            params![channel, actor_id, since_ms],
+            |row| row.get(0),
         )?;
-        let mut rows = stmt.query(params![approval_id])?;
-        if let Some(row) = rows.next()? {
-            return Ok(Some(ApprovalRecord {
-                id: row.get(0)?,
-                nonce: row.get(1)?,
-                goal_id: row.get(2)?,
-                tool_name: row.get(3)?,
-                capability: row.get(4)?,
-                input: row.get(5)?,
-                status: row.get(6)?,
-                requested_by: row.get(7)?,
-                resolved_by: row.get(8)?,
-                expires_at_ms: row.get(9)?,
-                decision_reason: row.get(10)?,
-            }));
+        Ok(count as usize)
+    }
+
+    /// Marks access row `id` with the rules it tripped and raises an `access_anomaly`
+    /// lifecycle event for each.
+    pub fn flag_access_anomalies(&self, id: i64, rules: &[(&str, String)]) -> Result<()> {
+        if rules.is_empty() {
+            return Ok(());
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE access_log SET anomaly = ?1 WHERE id = ?2",
+            params![
+                rules
+                    .iter()
+                    .map(|(rule, _)| *rule)
+                    .collect::<Vec<_>>()
+                    .join(","),
+                id
+            ],
+        )?;
+        for (rule, detail) in rules {
+            record_lifecycle_event(&tx, "access_anomaly", rule, detail)?;
         }
-        Ok(None)
+        tx.commit()?;
+        Ok(())
     }
 
-    pub fn list_pending_approvals(&self) -> Result<Vec<ApprovalRecord>> {
-        self.expire_pending_approvals(now_epoch_ms())?;
+    /// Newest first; `anomalies_only` keeps rows that tripped a rule.
+    pub fn list_access_log(
+        &self,
+        limit: usize,
+        anomalies_only: bool,
+    ) -> Result<Vec<AccessLogEntry>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, nonce, goal_id, tool_name, capability, input, status, requested_by, resolved_by, expires_at_ms, decision_reason
-             FROM approval_requests
-             WHERE status = 'pending'
-             ORDER BY created_at ASC",
+            "SELECT id, at_ms, channel, actor_id, remote_addr, kind, action, outcome, anomaly
+             FROM access_log
+             WHERE ?1 = 0 OR anomaly IS NOT NULL
+             ORDER BY id DESC
+             LIMIT ?2",
         )?;
-        let rows = stmt.query_map([], |row| {
-            Ok(ApprovalRecord {
+        let rows = stmt.query_map(params![anomalies_only, limit as i64], |row| {
+            Ok(AccessLogEntry {
                 id: row.get(0)?,
-                nonce: row.get(1)?,
-                goal_id: row.get(2)?,
-                tool_name: row.get(3)?,
-                capability: row.get(4)?,
-                input: row.get(5)?,
-                status: row.get(6)?,
-                requested_by: row.get(7)?,
-                resolved_by: row.get(8)?,
-                expires_at_ms: row.get(9)?,
-                decision_reason: row.get(10)?,
+                at_ms: row.get(1)?,
+                channel: row.get(2)?,
+                actor_id: row.get(3)?,
+                remote_addr: row.get(4)?,
+                kind: row.get(5)?,
+                action: row.get(6)?,
+                outcome: row.get(7)?,
+                anomaly: row.get(8)?,
             })
         })?;
-        let approvals = rows.collect::<rusqlite::Result<Vec<_>>>()?;
-        Ok(approvals)
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
-    pub fn resolve_approval_request(
-        &self,
-        approval_id: &str,
-        approved: bool,
-        resolved_by: Option<&str>,
-        reason: Option<&str>,
-    ) -> Result<bool> {
-        self.expire_pending_approvals(now_epoch_ms())?;
-        let status = if approved { "approved" } else { "denied" };
-        let rows_changed = self.conn.execute(
-            "UPDATE approval_requests
-             SET status = ?1, resolved_by = ?2, decision_reason = ?3, resolved_at = CURRENT_TIMESTAMP
-             WHERE id = ?4 AND status = 'pending'",
-            params![status, resolved_by, reason, approval_id],
+    /// Whether the peer agreed, with `/remember`, to have facts kept about them.
+    pub fn peer_memory_enabled(&self, channel: &str, peer_id: &str) -> Result<bool> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(1) FROM peer_memory_consent WHERE channel = ?1 AND peer_id = ?2",
+            params![channel, peer_id],
+            |row| row.get(0),
         )?;
-        Ok(rows_changed > 0)
-    }
-
-    pub fn approval_has_tool_run(&self, approval_id: &str) -> Result<bool> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT 1 FROM tool_runs WHERE approval_id = ?1 LIMIT 1")?;
-        let mut rows = stmt.query(params![approval_id])?;
-        Ok(rows.next()?.is_some())
+        Ok(count > 0)
     }
 
-    pub fn record_tool_run(
-        &self,
-        approval_id: Option<&str>,
-        tool_name: &str,
-        status: &str,
-        output: &str,
-    ) -> Result<ToolRunRecord> {
-        let record = ToolRunRecord {
-            id: Uuid::new_v4().to_string(),
-            approval_id: approval_id.map(std::string::ToString::to_string),
-            tool_name: tool_name.to_string(),
-            status: status.to_string(),
-            output: output.to_string(),
-        };
+    pub fn grant_peer_memory(&self, channel: &str, peer_id: &str) -> Result<()> {
         self.conn.execute(
-            "INSERT INTO tool_runs (id, approval_id, tool_name, status, output)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![
-                record.id,
-                record.approval_id,
-                record.tool_name,
-                record.status,
-                record.output
-            ],
+            "INSERT OR IGNORE INTO peer_memory_consent (channel, peer_id, granted_at_ms)
+             VALUES (?1, ?2, ?3)",
+            params![channel, peer_id, now_epoch_ms()],
         )?;
-        Ok(record)
+        Ok(())
     }
 
-    pub fn expire_pending_approvals(&self, now_ms: i64) -> Result<usize> {
-        let changed = self.conn.execute(
-            "UPDATE approval_requests
-             SET status = 'expired', resolved_at = CURRENT_TIMESTAMP
-             WHERE status = 'pending' AND COALESCE(expires_at_ms, 0) <= ?1",
-            params![now_ms],
+    /// Latest value of each fact kept about the peer, by key.
+    pub fn peer_facts(&self, channel: &str, peer_id: &str) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT f.fact_key, f.fact_value FROM semantic_facts f
+             WHERE f.namespace = ?1
+               AND f.id = (SELECT MAX(id) FROM semantic_facts
+                           WHERE namespace = f.namespace AND fact_key = f.fact_key)
+             ORDER BY f.fact_key",
         )?;
-        Ok(changed)
+        let rows = stmt.query_map(params![peer_memory_namespace(channel, peer_id)], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
-    pub fn upsert_semantic_fact(
-        &self,
-        namespace: &str,
-        fact_key: &str,
-        fact_value: &str,
-        source: &str,
-    ) -> Result<()> {
+    /// Drops the peer's facts with their history and embeddings, and withdraws consent.
+    /// Returns how many fact rows were removed.
+    pub fn forget_peer(&self, channel: &str, peer_id: &str) -> Result<usize> {
+        let namespace = peer_memory_namespace(channel, peer_id);
         self.conn.execute(
-            "INSERT INTO semantic_facts (namespace, fact_key, fact_value, source)
-             VALUES (?1, ?2, ?3, ?4)",
-            params![namespace, fact_key, fact_value, source],
+            "DELETE FROM memory_embeddings
+             WHERE kind = 'semantic'
+               AND source_id IN (SELECT id FROM semantic_facts WHERE namespace = ?1)",
+            params![namespace],
         )?;
-        Ok(())
+        let removed = self.conn.execute(
+            "DELETE FROM semantic_facts WHERE namespace = ?1",
+            params![namespace],
+        )?;
+        self.conn.execute(
+            "DELETE FROM peer_memory_consent WHERE channel = ?1 AND peer_id = ?2",
+            params![channel, peer_id],
+        )?;
+        Ok(removed)
     }
 
     pub fn add_procedural_strategy(
@@ -1885,22 +7202,286 @@ impl MemoryStore {
 
     pub fn list_episodic_memory(&self, limit: usize) -> Result<Vec<EpisodicMemoryRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, goal_id, summary, source
+            "SELECT id, goal_id, summary, source, reflection_json
              FROM episodic_memories
              ORDER BY id DESC
              LIMIT ?1",
         )?;
         let rows = stmt.query_map(params![limit as i64], |row| {
+            let reflection: Option<String> = row.get(4)?;
             Ok(EpisodicMemoryRecord {
                 id: row.get(0)?,
                 goal_id: row.get(1)?,
                 summary: row.get(2)?,
                 source: row.get(3)?,
+                reflection: reflection.and_then(|json| serde_json::from_str(&json).ok()),
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Newest procedural strategies recorded under `strategy_name`, e.g. a plan candidate id.
+    pub fn list_procedural_strategies(
+        &self,
+        strategy_name: &str,
+        limit: usize,
+    ) -> Result<Vec<ProceduralStrategyRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, strategy_name, strategy_body, confidence, source
+             FROM procedural_strategies
+             WHERE strategy_name = ?1
+             ORDER BY id DESC
+             LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![strategy_name, limit as i64], |row| {
+            Ok(ProceduralStrategyRecord {
+                id: row.get(0)?,
+                strategy_name: row.get(1)?,
+                strategy_body: row.get(2)?,
+                confidence: row.get(3)?,
+                source: row.get(4)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn record_worker_heartbeat(
+        &self,
+        name: &str,
+        holder: &str,
+        interval_ms: i64,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO worker_heartbeats (name, holder, interval_ms, beat_at_ms)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(name) DO UPDATE SET
+               holder = excluded.holder,
+               interval_ms = excluded.interval_ms,
+               beat_at_ms = excluded.beat_at_ms",
+            params![name, holder, interval_ms, now_epoch_ms()],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_worker_heartbeats(&self) -> Result<Vec<WorkerHeartbeatRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name, holder, interval_ms, beat_at_ms
+             FROM worker_heartbeats
+             ORDER BY name ASC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(WorkerHeartbeatRecord {
+                name: row.get(0)?,
+                holder: row.get(1)?,
+                interval_ms: row.get(2)?,
+                beat_at_ms: row.get(3)?,
             })
         })?;
         Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
     }
 
+    /// Takes the database write lock and rolls back, proving writes would succeed now.
+    pub fn probe_write(&self) -> Result<()> {
+        self.conn.execute_batch("BEGIN IMMEDIATE")?;
+        let touched = self.conn.execute(
+            "UPDATE schema_migrations SET name = name
+             WHERE version = (SELECT MAX(version) FROM schema_migrations)",
+            [],
+        );
+        self.conn.execute_batch("ROLLBACK")?;
+        touched?;
+        Ok(())
+    }
+
+    /// Newest timestamp the runtime has written, for spotting a clock that moved backwards.
+    pub fn latest_recorded_at_ms(&self) -> Result<Option<i64>> {
+        Ok(self.conn.query_row(
+            "SELECT MAX(ts) FROM (
+               SELECT MAX(created_at_ms) AS ts FROM lifecycle_events
+               UNION ALL
+               SELECT MAX(beat_at_ms) FROM worker_heartbeats
+             )",
+            [],
+            |row| row.get(0),
+        )?)
+    }
+
+    pub fn schema_version(&self) -> Result<i64> {
+        Ok(self.conn.query_row(
+            "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+            [],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Takes or renews `name` for `holder`. Succeeds when the lease is free, expired, or
+    /// already held by `holder`; the upsert is a single statement so racing processes
+    /// cannot both win.
+    pub fn try_acquire_writer_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        endpoint: Option<&str>,
+        ttl_ms: i64,
+    ) -> Result<bool> {
+        let now = now_epoch_ms();
+        let changed = self.conn.execute(
+            "INSERT INTO writer_leases
+               (name, holder, endpoint, acquired_at_ms, renewed_at_ms, expires_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?4, ?5)
+             ON CONFLICT(name) DO UPDATE SET
+               holder = excluded.holder,
+               endpoint = excluded.endpoint,
+               acquired_at_ms = CASE WHEN writer_leases.holder = excluded.holder
+                 THEN writer_leases.acquired_at_ms ELSE excluded.acquired_at_ms END,
+               renewed_at_ms = excluded.renewed_at_ms,
+               expires_at_ms = excluded.expires_at_ms
+             WHERE writer_leases.holder = excluded.holder
+                OR writer_leases.expires_at_ms <= excluded.renewed_at_ms",
+            params![name, holder, endpoint, now, now + ttl_ms],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Releases `name`. With `holder` set, only that holder's lease is removed.
+    pub fn release_writer_lease(&self, name: &str, holder: Option<&str>) -> Result<bool> {
+        let removed = match holder {
+            Some(holder) => self.conn.execute(
+                "DELETE FROM writer_leases WHERE name = ?1 AND holder = ?2",
+                params![name, holder],
+            )?,
+            None => self
+                .conn
+                .execute("DELETE FROM writer_leases WHERE name = ?1", params![name])?,
+        };
+        Ok(removed > 0)
+    }
+
+    pub fn live_writer_lease(&self, name: &str) -> Result<Option<WriterLeaseRecord>> {
+        let now = now_epoch_ms();
+        Ok(self.writer_lease(name)?.filter(|lease| lease.is_live(now)))
+    }
+
+    pub fn writer_lease(&self, name: &str) -> Result<Option<WriterLeaseRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name, holder, endpoint, acquired_at_ms, renewed_at_ms, expires_at_ms
+             FROM writer_leases WHERE name = ?1",
+        )?;
+        let mut rows = stmt.query(params![name])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(WriterLeaseRecord {
+                name: row.get(0)?,
+                holder: row.get(1)?,
+                endpoint: row.get(2)?,
+                acquired_at_ms: row.get(3)?,
+                renewed_at_ms: row.get(4)?,
+                expires_at_ms: row.get(5)?,
+            }));
+        }
+        Ok(None)
+    }
+
+    pub fn size_stats(&self) -> Result<DbSizeStats> {
+        let file_len = |path: &Path| std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        let mut wal_path = self.db_path.clone().into_os_string();
+        wal_path.push("-wal");
+        Ok(DbSizeStats {
+            file_bytes: file_len(&self.db_path),
+            wal_bytes: file_len(Path::new(&wal_path)),
+            page_size: self
+                .conn
+                .query_row("PRAGMA page_size", [], |row| row.get(0))?,
+            page_count: self
+                .conn
+                .query_row("PRAGMA page_count", [], |row| row.get(0))?,
+            freelist_pages: self
+                .conn
+                .query_row("PRAGMA freelist_count", [], |row| row.get(0))?,
+            journal_mode: self
+                .conn
+                .query_row("PRAGMA journal_mode", [], |row| row.get(0))?,
+        })
+    }
+
+    /// Checkpoints the WAL, runs ANALYZE and an integrity check, and VACUUMs when `vacuum` is
+    /// set or free pages reach `vacuum_free_ratio` of the file.
+    pub fn run_maintenance(
+        &self,
+        trigger: &str,
+        vacuum: bool,
+        vacuum_free_ratio: f64,
+    ) -> Result<MaintenanceReport> {
+        let started = now_epoch_ms();
+        let before = self.size_stats()?;
+        let wal_checkpointed = before.journal_mode.eq_ignore_ascii_case("wal");
+        if wal_checkpointed {
+            self.conn
+                .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        }
+        self.conn.execute_batch("ANALYZE")?;
+        let vacuumed = vacuum || before.free_ratio() >= vacuum_free_ratio;
+        if vacuumed {
+            self.conn.execute_batch("VACUUM")?;
+        }
+        let index_count = self.conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'index'",
+            [],
+            |row| row.get(0),
+        )?;
+        let mut stmt = self.conn.prepare("PRAGMA integrity_check(20)")?;
+        let integrity = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let after = self.size_stats()?;
+        let report = MaintenanceReport {
+            trigger: trigger.to_string(),
+            before,
+            after,
+            wal_checkpointed,
+            vacuumed,
+            index_count,
+            integrity,
+            duration_ms: now_epoch_ms() - started,
+        };
+        self.conn.execute(
+            "INSERT INTO maintenance_runs
+               (trigger, bytes_before, bytes_after, vacuumed, integrity, duration_ms, created_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                report.trigger,
+                report.before.total_bytes() as i64,
+                report.after.total_bytes() as i64,
+                report.vacuumed,
+                report.integrity.join("; "),
+                report.duration_ms,
+                now_epoch_ms()
+            ],
+        )?;
+        Ok(report)
+    }
+
+    pub fn last_maintenance_at_ms(&self) -> Result<Option<i64>> {
+        Ok(self.conn.query_row(
+            "SELECT MAX(created_at_ms) FROM maintenance_runs",
+            [],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Due when the last run is older than `interval_ms` or free pages reach the vacuum ratio.
+    pub fn maintenance_due(
+        &self,
+        now_ms: i64,
+        interval_ms: i64,
+        vacuum_free_ratio: f64,
+    ) -> Result<bool> {
+        let overdue = match self.last_maintenance_at_ms()? {
+            Some(last) => now_ms - last >= interval_ms,
+            None => true,
+        };
+        Ok(overdue || self.size_stats()?.free_ratio() >= vacuum_free_ratio)
+    }
+
     // Backup uses SQLite VACUUM INTO semantics via ATTACH-compatible copy.
     // Closing/re-opening the connection avoids file-lock surprises on active writers.
     pub fn backup_to(&self, destination: &Path) -> Result<()> {
@@ -1934,11 +7515,297 @@ impl MemoryStore {
     }
 }
 
-fn now_epoch_ms() -> i64 {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default();
-    now.as_millis() as i64
+fn trace_verbosity_for_goal(conn: &Connection, goal_id: &str) -> Result<TraceVerbosity> {
+    let mut stmt = conn.prepare(
+        "SELECT level FROM (
+           SELECT level, 0 AS rank FROM trace_levels WHERE scope = 'goal:' || ?1
+           UNION ALL
+           SELECT t.level, 1 AS rank
+           FROM goals g
+           JOIN sessions s ON s.id = g.session_id
+           JOIN trace_levels t ON t.scope = 'channel:' || s.channel
+           WHERE g.id = ?1
+           UNION ALL
+           SELECT level, 2 AS rank FROM trace_levels WHERE scope = 'default'
+         )
+         ORDER BY rank ASC
+         LIMIT 1",
+    )?;
+    let mut rows = stmt.query_map(params![goal_id], |row| row.get::<_, String>(0))?;
+    Ok(rows
+        .next()
+        .transpose()?
+        .and_then(|level| TraceVerbosity::parse(&level))
+        .unwrap_or_default())
+}
+
+// Every trace write goes through here so sampling applies to all callers alike.
+fn insert_trace(conn: &Connection, event: &TraceEvent) -> Result<bool> {
+    let class = TraceClass::of(&event.event_type);
+    let seen: i64 = conn.query_row(
+        "INSERT INTO trace_counters (goal_id, event_type, seen) VALUES (?1, ?2, 1)
+         ON CONFLICT(goal_id, event_type) DO UPDATE SET seen = seen + 1
+         RETURNING seen",
+        params![event.goal_id, event.event_type],
+        |row| row.get(0),
+    )?;
+    let verbosity = if class == TraceClass::Protected {
+        TraceVerbosity::Debug
+    } else {
+        trace_verbosity_for_goal(conn, &event.goal_id)?
+    };
+    if !verbosity.keeps(class, seen) {
+        return Ok(false);
+    }
+    conn.execute(
+        "INSERT INTO trace_events (goal_id, event_type, detail, risk_mode) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            event.goal_id,
+            event.event_type,
+            event.detail,
+            event.risk_mode
+        ],
+    )?;
+    conn.execute(
+        "UPDATE trace_counters SET kept = kept + 1 WHERE goal_id = ?1 AND event_type = ?2",
+        params![event.goal_id, event.event_type],
+    )?;
+    Ok(true)
+}
+
+fn release_goals_waiting_for_model(conn: &Connection) -> Result<usize> {
+    Ok(conn.execute(
+        "UPDATE goals SET status = 'queued', updated_at = CURRENT_TIMESTAMP
+         WHERE status = 'waiting_for_model'
+           AND id IN (SELECT goal_id FROM queued_goals)",
+        [],
+    )?)
+}
+
+fn record_lifecycle_event(
+    conn: &Connection,
+    kind: &str,
+    subject_id: &str,
+    detail: &str,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO lifecycle_events (kind, subject_id, detail, created_at_ms)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![kind, subject_id, detail, now_epoch_ms()],
+    )?;
+    Ok(())
+}
+
+fn record_risk_change(
+    conn: &Connection,
+    action: &str,
+    risk_mode: RiskMode,
+    actor: &str,
+    reason: Option<&str>,
+    expires_at_ms: Option<i64>,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO risk_state_history (action, risk_mode, actor, reason, expires_at_ms, created_at_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            action,
+            risk_mode.as_str(),
+            actor,
+            reason,
+            expires_at_ms,
+            now_epoch_ms()
+        ],
+    )?;
+    Ok(())
+}
+
+fn record_goal_lifecycle(conn: &Connection, goal_id: &str, status: &GoalStatus) -> Result<()> {
+    let kind = match status {
+        GoalStatus::Completed => "goal_completed",
+        GoalStatus::Failed => "goal_failed",
+        _ => return Ok(()),
+    };
+    record_lifecycle_event(conn, kind, goal_id, &format!("status={}", status.as_str()))
+}
+
+fn approval_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ApprovalRecord> {
+    Ok(ApprovalRecord {
+        id: row.get(0)?,
+        nonce: row.get(1)?,
+        goal_id: row.get(2)?,
+        tool_name: row.get(3)?,
+        capability: row.get(4)?,
+        input: row.get(5)?,
+        status: row.get(6)?,
+        requested_by: row.get(7)?,
+        resolved_by: row.get(8)?,
+        expires_at_ms: row.get(9)?,
+        decision_reason: row.get(10)?,
+        original_input: row.get(11)?,
+    })
+}
+
+fn lifecycle_event_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<LifecycleEventRecord> {
+    Ok(LifecycleEventRecord {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        subject_id: row.get(2)?,
+        detail: row.get(3)?,
+        created_at_ms: row.get(4)?,
+    })
+}
+
+fn insert_outbox(
+    conn: &Connection,
+    message: &OutboxMessage,
+    next_attempt_at_ms: i64,
+) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO channel_outbox
+           (operator, event_kind, subject_id, channel, target, message, status,
+            next_attempt_at_ms, created_at_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'pending', ?7, ?8)",
+        params![
+            message.operator,
+            message.event_kind,
+            message.subject_id,
+            message.channel,
+            message.target,
+            message.message,
+            next_attempt_at_ms,
+            now_epoch_ms()
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+fn approval_notice(approval_id: &str, target: &ChannelTarget, notice: &str) -> OutboxMessage {
+    OutboxMessage {
+        operator: None,
+        event_kind: "approval_requested".to_string(),
+        subject_id: approval_id.to_string(),
+        channel: target.channel.clone(),
+        target: target.target.clone(),
+        message: notice.to_string(),
+    }
+}
+
+fn enqueue_notifications(
+    conn: &Connection,
+    routes: &[NotificationPrefRecord],
+    event_kind: &str,
+    subject_id: &str,
+    message: &str,
+    now_ms: i64,
+) -> Result<Vec<i64>> {
+    let minute_of_day = (now_ms / 60_000) % (24 * 60);
+    let mut ids = Vec::new();
+    for pref in routes {
+        if pref.in_quiet_hours(minute_of_day) {
+            conn.execute(
+                "INSERT INTO notification_log
+                   (operator, event_kind, channel, target, message, status, detail, created_at_ms)
+                 VALUES (?1, ?2, ?3, ?4, ?5, 'suppressed', 'quiet hours', ?6)",
+                params![
+                    pref.operator,
+                    event_kind,
+                    pref.channel,
+                    pref.target,
+                    message,
+                    now_ms
+                ],
+            )?;
+            continue;
+        }
+        ids.push(insert_outbox(
+            conn,
+            &OutboxMessage {
+                operator: Some(pref.operator.clone()),
+                event_kind: event_kind.to_string(),
+                subject_id: subject_id.to_string(),
+                channel: pref.channel.clone(),
+                target: pref.target.clone(),
+                message: message.to_string(),
+            },
+            now_ms,
+        )?);
+    }
+    Ok(ids)
+}
+
+fn trace_view_from_row(
+    row: &rusqlite::Row<'_>,
+    view: TraceView,
+) -> rusqlite::Result<TraceViewRecord> {
+    let event_type: String = row.get(2)?;
+    let detail: String = row.get(3)?;
+    let masked = match view {
+        TraceView::Full => None,
+        TraceView::Redacted => redact_trace_detail(&event_type, &detail),
+    };
+    Ok(TraceViewRecord {
+        id: row.get(0)?,
+        goal_id: row.get(1)?,
+        redacted: masked.is_some(),
+        detail: masked.unwrap_or(detail),
+        event_type,
+        risk_mode: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+fn outbox_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<OutboxRecord> {
+    Ok(OutboxRecord {
+        id: row.get(0)?,
+        operator: row.get(1)?,
+        event_kind: row.get(2)?,
+        subject_id: row.get(3)?,
+        channel: row.get(4)?,
+        target: row.get(5)?,
+        message: row.get(6)?,
+        status: row.get(7)?,
+        attempts: row.get(8)?,
+        next_attempt_at_ms: row.get(9)?,
+        last_error: row.get(10)?,
+        created_at_ms: row.get(11)?,
+        delivered_at_ms: row.get(12)?,
+    })
+}
+
+fn webhook_delivery_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<WebhookDeliveryRecord> {
+    Ok(WebhookDeliveryRecord {
+        id: row.get(0)?,
+        event_id: row.get(1)?,
+        webhook: row.get(2)?,
+        status: row.get(3)?,
+        attempts: row.get(4)?,
+        next_attempt_at_ms: row.get(5)?,
+        last_error: row.get(6)?,
+        last_status_code: row.get(7)?,
+        updated_at_ms: row.get(8)?,
+    })
+}
+
+fn record_approval_decision(
+    conn: &Connection,
+    approval_id: &str,
+    approver: &str,
+    approved: bool,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO approval_decisions (approval_id, approver, decision, created_at_ms)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(approval_id, approver) DO UPDATE SET
+           decision = excluded.decision,
+           created_at_ms = excluded.created_at_ms",
+        params![
+            approval_id,
+            approver,
+            if approved { "approve" } else { "deny" },
+            now_epoch_ms()
+        ],
+    )?;
+    Ok(())
 }
 
 fn is_job_due(job: &JobRecord, now_ms: i64) -> Result<bool> {
@@ -2010,6 +7877,13 @@ fn persist_pending_approval(
     };
     let approval_id = Uuid::new_v4().to_string();
     let nonce = Uuid::new_v4().to_string();
+    let input = if pending.tool_name == CANARY_CONTINUE_TOOL {
+        approval_payload::encode_approval_payload(&approval_payload::CanaryContinuePayload {
+            canary_output: pending.input.clone().unwrap_or_default(),
+        })?
+    } else {
+        pending.input.clone().unwrap_or_default()
+    };
     tx.execute(
         "INSERT INTO approval_requests
          (id, nonce, goal_id, tool_name, capability, input, status, requested_by, expires_at_ms)
@@ -2020,18 +7894,27 @@ fn persist_pending_approval(
             goal_id,
             pending.tool_name,
             pending.capability,
-            pending.input.clone().unwrap_or_default(),
+            input,
             requested_by.or(Some(source)),
             expires_at_ms
         ],
     )?;
-    tx.execute(
-        "INSERT INTO trace_events (goal_id, event_type, detail, risk_mode) VALUES (?1, 'approval_queued', ?2, ?3)",
-        params![
+    record_lifecycle_event(
+        tx,
+        "approval_created",
+        &approval_id,
+        &format!(
+            "goal_id={goal_id} tool={} capability={}",
+            pending.tool_name, pending.capability
+        ),
+    )?;
+    insert_trace(
+        tx,
+        &TraceEvent::new(
             goal_id,
+            "approval_queued",
             format!("approval_id={} tool={}", approval_id, pending.tool_name),
-            "secure"
-        ],
+        ),
     )?;
     Ok(Some(approval_id))
 }
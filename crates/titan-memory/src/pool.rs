@@ -0,0 +1,122 @@
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+
+use crate::MemoryStore;
+
+/// Open stores kept for reuse when none is asked for.
+pub const DEFAULT_POOL_MAX_IDLE: usize = 8;
+
+/// Open stores for one database, shared by request handlers instead of opening a connection
+/// per request. Cloning is cheap and every clone shares the same stores.
+///
+/// The first store opened runs migrations and switches the database to WAL, so readers do
+/// not wait on the writer. Checking out an idle store never blocks on I/O.
+#[derive(Clone)]
+pub struct MemoryStorePool {
+    inner: Arc<PoolInner>,
+}
+
+struct PoolInner {
+    db_path: PathBuf,
+    max_idle: usize,
+    idle: Mutex<Vec<MemoryStore>>,
+    prepared: AtomicBool,
+}
+
+impl MemoryStorePool {
+    /// Opens nothing until the first [`Self::get`].
+    pub fn new(db_path: &Path) -> Self {
+        Self::with_max_idle(db_path, DEFAULT_POOL_MAX_IDLE)
+    }
+
+    pub fn with_max_idle(db_path: &Path, max_idle: usize) -> Self {
+        Self {
+            inner: Arc::new(PoolInner {
+                db_path: db_path.to_path_buf(),
+                max_idle,
+                idle: Mutex::new(Vec::new()),
+                prepared: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    pub fn db_path(&self) -> &Path {
+        &self.inner.db_path
+    }
+
+    /// An idle store, or a newly opened one when all are checked out. It goes back to the
+    /// pool when the returned guard is dropped.
+    pub fn get(&self) -> Result<PooledStore> {
+        let idle = self
+            .inner
+            .idle
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pop();
+        let store = match idle {
+            Some(store) => store,
+            None if self.inner.prepared.load(Ordering::Acquire) => {
+                MemoryStore::connect(&self.inner.db_path)?
+            }
+            None => {
+                let store = MemoryStore::open(&self.inner.db_path)?;
+                store.enable_wal()?;
+                self.inner.prepared.store(true, Ordering::Release);
+                store
+            }
+        };
+        Ok(PooledStore {
+            store: Some(store),
+            pool: Arc::clone(&self.inner),
+        })
+    }
+
+    /// Stores waiting to be checked out.
+    pub fn idle_count(&self) -> usize {
+        self.inner
+            .idle
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .len()
+    }
+}
+
+/// A store checked out of a [`MemoryStorePool`].
+pub struct PooledStore {
+    store: Option<MemoryStore>,
+    pool: Arc<PoolInner>,
+}
+
+impl Deref for PooledStore {
+    type Target = MemoryStore;
+
+    fn deref(&self) -> &MemoryStore {
+        self.store.as_ref().expect("store is present until drop")
+    }
+}
+
+impl DerefMut for PooledStore {
+    fn deref_mut(&mut self) -> &mut MemoryStore {
+        self.store.as_mut().expect("store is present until drop")
+    }
+}
+
+impl Drop for PooledStore {
+    fn drop(&mut self) {
+        let Some(store) = self.store.take() else {
+            return;
+        };
+        // A store left inside a transaction is closed instead of handed to the next caller.
+        if !store.is_autocommit() {
+            return;
+        }
+        let mut idle = self.pool.idle.lock().unwrap_or_else(|e| e.into_inner());
+        if idle.len() < self.pool.max_idle {
+            idle.push(store);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use anyhow::Result;
+use rusqlite::{Connection, Params, params};
+use titan_core::{GoalStatus, TraceEvent};
+
+use crate::{MemoryStore, insert_trace, record_goal_lifecycle};
+
+/// Rows of one kind that point at something missing, and what `--fix` does about them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairFinding {
+    pub check: &'static str,
+    pub action: &'static str,
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RepairReport {
+    /// One entry per check, in the order they ran, including checks that found nothing.
+    pub findings: Vec<RepairFinding>,
+    /// Whether the fixes were applied; a dry run leaves the database untouched.
+    pub fixed: bool,
+}
+
+impl RepairReport {
+    pub fn total(&self) -> usize {
+        self.findings.iter().map(|finding| finding.ids.len()).sum()
+    }
+}
+
+impl MemoryStore {
+    /// Looks for referential drift: tool runs naming a missing approval, goals left in
+    /// `planning` without a plan, steps whose plan is gone, and sessions that never got a
+    /// message. Goals and sessions only count once untouched since `stale_before_ms`, so runs
+    /// in flight are left alone. With `fix`, every repair is applied in one transaction;
+    /// otherwise nothing is written.
+    pub fn repair_references(&self, stale_before_ms: i64, fix: bool) -> Result<RepairReport> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut findings = Vec::new();
+
+        let runs = select_ids(
+            &tx,
+            "SELECT id FROM tool_runs
+             WHERE approval_id IS NOT NULL
+               AND approval_id NOT IN (SELECT id FROM approval_requests)
+             ORDER BY id",
+            [],
+        )?;
+        if fix {
+            for id in &runs {
+                tx.execute(
+                    "UPDATE tool_runs SET approval_id = NULL WHERE id = ?1",
+                    params![id],
+                )?;
+            }
+        }
+        findings.push(RepairFinding {
+            check: "tool_run_missing_approval",
+            action: "clear approval_id",
+            ids: runs,
+        });
+
+        // A goal waiting on a pending approval sits in `planning` with no plan by design.
+        let goals = select_ids(
+            &tx,
+            "SELECT id FROM goals g
+             WHERE status = 'planning'
+               AND updated_at < datetime(?1 / 1000, 'unixepoch')
+               AND NOT EXISTS (SELECT 1 FROM run_plans p WHERE p.goal_id = g.id)
+               AND NOT EXISTS (
+                 SELECT 1 FROM approval_requests a
+                 WHERE a.goal_id = g.id AND a.status = 'pending'
+               )
+             ORDER BY id",
+            params![stale_before_ms],
+        )?;
+        if fix {
+            for id in &goals {
+                tx.execute(
+                    "UPDATE goals SET status = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+                    params![GoalStatus::Failed.as_str(), id],
+                )?;
+                record_goal_lifecycle(&tx, id, &GoalStatus::Failed)?;
+                insert_trace(
+                    &tx,
+                    &TraceEvent::new(
+                        id.clone(),
+                        "goal_repaired",
+                        "failed: stuck in planning with no plan",
+                    ),
+                )?;
+            }
+        }
+        findings.push(RepairFinding {
+            check: "goal_stuck_planning",
+            action: "mark failed",
+            ids: goals,
+        });
+
+        let steps = select_ids(
+            &tx,
+            "SELECT id FROM run_steps
+             WHERE plan_id NOT IN (SELECT id FROM run_plans)
+             ORDER BY id",
+            [],
+        )?;
+        if fix {
+            for id in &steps {
+                tx.execute("DELETE FROM run_steps WHERE id = ?1", params![id])?;
+            }
+        }
+        findings.push(RepairFinding {
+            check: "step_missing_plan",
+            action: "delete step",
+            ids: steps,
+        });
+
+        // An empty session still open as the peer's newest one is simply waiting for its
+        // first message.
+        let sessions = select_ids(
+            &tx,
+            "SELECT id FROM sessions s
+             WHERE NOT EXISTS (SELECT 1 FROM session_messages m WHERE m.session_id = s.id)
+               AND NOT EXISTS (SELECT 1 FROM goals g WHERE g.session_id = s.id)
+               AND updated_at < datetime(?1 / 1000, 'unixepoch')
+               AND (
+                 closed_at_ms IS NOT NULL
+                 OR EXISTS (
+                   SELECT 1 FROM sessions newer
+                   WHERE newer.channel = s.channel AND newer.peer_id = s.peer_id
+                     AND newer.rowid > s.rowid
+                 )
+               )
+             ORDER BY id",
+            params![stale_before_ms],
+        )?;
+        if fix && !sessions.is_empty() {
+            let session_tables = tables_with_column(&tx, "session_id")?;
+            for id in &sessions {
+                for table in &session_tables {
+                    tx.execute(
+                        &format!("DELETE FROM {table} WHERE session_id = ?1"),
+                        params![id],
+                    )?;
+                }
+                tx.execute("DELETE FROM sessions WHERE id = ?1", params![id])?;
+            }
+        }
+        findings.push(RepairFinding {
+            check: "session_without_messages",
+            action: "delete session",
+            ids: sessions,
+        });
+
+        let report = RepairReport {
+            findings,
+            fixed: fix,
+        };
+        if fix && report.total() > 0 {
+            tx.commit()?;
+        }
+        Ok(report)
+    }
+}
+
+fn select_ids(conn: &Connection, sql: &str, params: impl Params) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(sql)?;
+    let ids = stmt
+        .query_map(params, |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(ids)
+}
+
+/// Every table other than `goals` and `sessions` with a `column` column.
+fn tables_with_column(conn: &Connection, column: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT m.name FROM sqlite_master m, pragma_table_info(m.name) p
+         WHERE m.type = 'table' AND p.name = ?1 AND m.name NOT IN ('goals', 'sessions')
+         ORDER BY m.name",
+    )?;
+    let tables = stmt
+        .query_map(params![column], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(tables)
+}
@@ -0,0 +1,238 @@
+use anyhow::{Context, Result, bail};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+/// Access keys for an S3-compatible endpoint.
+#[derive(Clone)]
+pub struct S3Credentials {
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+/// One object returned by [`S3Client::list`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Object {
+    pub key: String,
+    pub size_bytes: u64,
+}
+
+/// A minimal client for one bucket of an S3-compatible service, addressed path-style and
+/// signed with AWS Signature Version 4.
+#[derive(Debug, Clone)]
+pub struct S3Client {
+    endpoint: String,
+    bucket: String,
+    region: String,
+}
+
+impl S3Client {
+    pub fn new(endpoint: &str, bucket: &str, region: &str) -> Self {
+        Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket: bucket.to_string(),
+            region: region.to_string(),
+        }
+    }
+
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    pub fn put(&self, credentials: &S3Credentials, key: &str, body: &[u8]) -> Result<()> {
+        self.send(credentials, "PUT", key, &[], body).map(|_| ())
+    }
+
+    pub fn get(&self, credentials: &S3Credentials, key: &str) -> Result<Vec<u8>> {
+        self.send(credentials, "GET", key, &[], &[])
+    }
+
+    pub fn delete(&self, credentials: &S3Credentials, key: &str) -> Result<()> {
+        self.send(credentials, "DELETE", key, &[], &[]).map(|_| ())
+    }
+
+    /// Every object under `prefix`, in key order, following continuation tokens.
+    pub fn list(&self, credentials: &S3Credentials, prefix: &str) -> Result<Vec<S3Object>> {
+        let mut objects = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("list-type".to_string(), "2".to_string()),
+                ("prefix".to_string(), prefix.to_string()),
+            ];
+            if let Some(token) = &token {
+                query.push(("continuation-token".to_string(), token.clone()));
+            }
+            let body = self.send(credentials, "GET", "", &query, &[])?;
+            let body = String::from_utf8_lossy(&body);
+            for entry in xml_values(&body, "Contents") {
+                let Some(key) = xml_values(&entry, "Key").first().map(|k| xml_unescape(k)) else {
+                    continue;
+                };
+                let size_bytes = xml_values(&entry, "Size")
+                    .into_iter()
+                    .next()
+                    .and_then(|size| size.parse().ok())
+                    .unwrap_or(0);
+                objects.push(S3Object { key, size_bytes });
+            }
+            let truncated = xml_values(&body, "IsTruncated")
+                .first()
+                .is_some_and(|value| value == "true");
+            token = xml_values(&body, "NextContinuationToken")
+                .first()
+                .map(|value| xml_unescape(value));
+            if !truncated || token.is_none() {
+                break;
+            }
+        }
+        objects.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(objects)
+    }
+
+    fn send(
+        &self,
+        credentials: &S3Credentials,
+        method: &str,
+        key: &str,
+        query: &[(String, String)],
+        body: &[u8],
+    ) -> Result<Vec<u8>> {
+        let host = self
+            .endpoint
+            .split_once("://")
+            .map_or(self.endpoint.as_str(), |(_, rest)| rest)
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        let path = if key.is_empty() {
+            format!("/{}", uri_encode(&self.bucket, false))
+        } else {
+            format!(
+                "/{}/{}",
+                uri_encode(&self.bucket, false),
+                uri_encode(key, false)
+            )
+        };
+        let mut pairs: Vec<(String, String)> = query
+            .iter()
+            .map(|(name, value)| (uri_encode(name, true), uri_encode(value, true)))
+            .collect();
+        pairs.sort();
+        let canonical_query = pairs
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("&");
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = sha256_hex(body);
+        let canonical = format!(
+            "{method}\n{path}\n{canonical_query}\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\nhost;x-amz-content-sha256;x-amz-date\n{payload_hash}"
+        );
+        let scope = format!("{date}/{}/s3/aws4_request", self.region);
+        let to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            sha256_hex(canonical.as_bytes())
+        );
+        let mut signing_key = format!("AWS4{}", credentials.secret_key).into_bytes();
+        for part in [date.as_str(), self.region.as_str(), "s3", "aws4_request"] {
+            signing_key = hmac_sha256(&signing_key, part.as_bytes());
+        }
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+            credentials.access_key,
+            hex(&hmac_sha256(&signing_key, to_sign.as_bytes()))
+        );
+        let url = if canonical_query.is_empty() {
+            format!("{}{path}", self.endpoint)
+        } else {
+            format!("{}{path}?{canonical_query}", self.endpoint)
+        };
+        let method = reqwest::Method::from_bytes(method.as_bytes())?;
+        // The blocking client cannot run on a tokio worker, and web handlers read blobs there.
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| -> Result<Vec<u8>> {
+                    let response = reqwest::blocking::Client::builder()
+                        .timeout(std::time::Duration::from_secs(30))
+                        .build()?
+                        .request(method, &url)
+                        .header("x-amz-date", &amz_date)
+                        .header("x-amz-content-sha256", &payload_hash)
+                        .header("authorization", authorization)
+                        .body(body.to_vec())
+                        .send()
+                        .with_context(|| format!("s3 request to {} failed", self.endpoint))?;
+                    if !response.status().is_success() {
+                        let target = if key.is_empty() { &self.bucket } else { key };
+                        bail!("s3 request for {target} failed: {}", response.status());
+                    }
+                    Ok(response.bytes()?.to_vec())
+                })
+                .join()
+                .unwrap_or_else(|_| bail!("s3 request thread panicked"))
+        })
+    }
+}
+
+/// Splits `s3://<bucket>/<key>` into its bucket and key.
+pub fn parse_s3_url(url: &str) -> Option<(&str, &str)> {
+    let (bucket, key) = url.strip_prefix("s3://")?.split_once('/')?;
+    (!bucket.is_empty() && !key.is_empty()).then_some((bucket, key))
+}
+
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+// SigV4 encoding: unreserved characters pass through, `/` too when encoding a path.
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut out = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            b'/' if !encode_slash => out.push('/'),
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
+
+fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+// Raw inner text of every `<tag>` element; enough for ListObjectsV2 responses.
+fn xml_values(xml: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    let mut values = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let after = &rest[start + open.len()..];
+        let Some(end) = after.find(&close) else {
+            break;
+        };
+        values.push(after[..end].to_string());
+        rest = &after[end + close.len()..];
+    }
+    values
+}
@@ -0,0 +1,276 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use rusqlite::params;
+
+use crate::MemoryStore;
+
+/// What one goal run recorded: its latest plan choice, every step, the outcome, and how long
+/// the goal took from creation to its last status change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoalRunSummary {
+    pub goal_id: String,
+    pub description: String,
+    pub status: String,
+    /// The scheduled job that started this goal, if any.
+    pub job_id: Option<String>,
+    pub intent: Option<String>,
+    pub selected_candidate_id: Option<String>,
+    pub selected_score: Option<f64>,
+    pub duration_secs: i64,
+    pub steps: Vec<RunStepOutcome>,
+    /// Trace events per type, so a diff can point at e.g. an `execution_failed` only one run hit.
+    pub event_counts: BTreeMap<String, i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunStepOutcome {
+    pub step_id: String,
+    pub tool_name: String,
+    pub status: String,
+    pub output: String,
+}
+
+/// One row of the step alignment. Steps pair up by tool along the longest common sequence;
+/// a step the other run never took has `None` on that side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepDiff {
+    pub a: Option<RunStepOutcome>,
+    pub b: Option<RunStepOutcome>,
+}
+
+impl StepDiff {
+    /// `same`, `changed` (status or output differ), `only_a`, or `only_b`.
+    pub fn kind(&self) -> &'static str {
+        match (&self.a, &self.b) {
+            (Some(a), Some(b)) if a.status == b.status && a.output == b.output => "same",
+            (Some(_), Some(_)) => "changed",
+            (Some(_), None) => "only_a",
+            _ => "only_b",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoalRunDiff {
+    pub a: GoalRunSummary,
+    pub b: GoalRunSummary,
+    pub steps: Vec<StepDiff>,
+}
+
+impl GoalRunDiff {
+    /// Both goals came from the same scheduled job, or share a description and planned intent.
+    pub fn same_family(&self) -> bool {
+        match (&self.a.job_id, &self.b.job_id) {
+            (Some(a), Some(b)) => a == b,
+            _ => {
+                self.a.description == self.b.description
+                    && self.a.intent.is_some()
+                    && self.a.intent == self.b.intent
+            }
+        }
+    }
+
+    pub fn plan_changed(&self) -> bool {
+        self.a.intent != self.b.intent
+            || self.a.selected_candidate_id != self.b.selected_candidate_id
+    }
+
+    pub fn outcome_changed(&self) -> bool {
+        self.a.status != self.b.status
+    }
+
+    pub fn duration_delta_secs(&self) -> i64 {
+        self.b.duration_secs - self.a.duration_secs
+    }
+
+    /// Event types whose counts differ, as `(type, count in a, count in b)`.
+    pub fn event_count_changes(&self) -> Vec<(String, i64, i64)> {
+        let mut types: Vec<&String> = self
+            .a
+            .event_counts
+            .keys()
+            .chain(self.b.event_counts.keys())
+            .collect();
+        types.sort();
+        types.dedup();
+        types
+            .into_iter()
+            .filter_map(|event_type| {
+                let a = self.a.event_counts.get(event_type).copied().unwrap_or(0);
+                let b = self.b.event_counts.get(event_type).copied().unwrap_or(0);
+                (a != b).then(|| (event_type.clone(), a, b))
+            })
+            .collect()
+    }
+}
+
+impl MemoryStore {
+    /// `None` when the goal does not exist. Offloaded step outputs are read back from blobs.
+    pub fn goal_run_summary(&self, goal_id: &str) -> Result<Option<GoalRunSummary>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, description, status,
+                    (SELECT job_id FROM job_runs WHERE goal_id = goals.id LIMIT 1),
+                    CAST(strftime('%s', updated_at) AS INTEGER)
+                      - CAST(strftime('%s', created_at) AS INTEGER)
+             FROM goals WHERE id = ?1",
+        )?;
+        let mut rows = stmt.query(params![goal_id])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+        let mut summary = GoalRunSummary {
+            goal_id: row.get(0)?,
+            description: row.get(1)?,
+            status: row.get(2)?,
+            job_id: row.get(3)?,
+            intent: None,
+            selected_candidate_id: None,
+            selected_score: None,
+            duration_secs: row.get::<_, Option<i64>>(4)?.unwrap_or(0),
+            steps: Vec::new(),
+            event_counts: BTreeMap::new(),
+        };
+
+        let mut stmt = self.conn.prepare(
+            "SELECT intent, selected_candidate_id, selected_score
+             FROM run_plans WHERE goal_id = ?1
+             ORDER BY created_at DESC, rowid DESC
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query(params![goal_id])?;
+        if let Some(row) = rows.next()? {
+            summary.intent = Some(row.get(0)?);
+            summary.selected_candidate_id = Some(row.get(1)?);
+            summary.selected_score = Some(row.get(2)?);
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT step_id, tool_name, status, output
+             FROM run_steps WHERE goal_id = ?1
+             ORDER BY rowid ASC",
+        )?;
+        let steps = stmt
+            .query_map(params![goal_id], |row| {
+                Ok(RunStepOutcome {
+                    step_id: row.get(0)?,
+                    tool_name: row.get(1)?,
+                    status: row.get(2)?,
+                    output: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for mut step in steps {
+            step.output = self.resolve_output(&step.output)?;
+            summary.steps.push(step);
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT event_type, COUNT(*) FROM trace_events WHERE goal_id = ?1 GROUP BY event_type",
+        )?;
+        summary.event_counts = stmt
+            .query_map(params![goal_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<BTreeMap<String, i64>>>()?;
+        Ok(Some(summary))
+    }
+
+    /// Lines up two runs, typically of the same recurring goal, for `titan trace diff`.
+    /// `None` when either goal does not exist.
+    pub fn diff_goal_runs(&self, goal_a: &str, goal_b: &str) -> Result<Option<GoalRunDiff>> {
+        let (Some(a), Some(b)) = (
+            self.goal_run_summary(goal_a)?,
+            self.goal_run_summary(goal_b)?,
+        ) else {
+            return Ok(None);
+        };
+        let steps = align_steps(&a.steps, &b.steps);
+        Ok(Some(GoalRunDiff { a, b, steps }))
+    }
+}
+
+/// Longest common subsequence on tool names; unmatched steps keep their run order.
+fn align_steps(a: &[RunStepOutcome], b: &[RunStepOutcome]) -> Vec<StepDiff> {
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i].tool_name == b[j].tool_name {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut aligned = Vec::with_capacity(a.len().max(b.len()));
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i].tool_name == b[j].tool_name {
+            aligned.push(StepDiff {
+                a: Some(a[i].clone()),
+                b: Some(b[j].clone()),
+            });
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            aligned.push(StepDiff {
+                a: Some(a[i].clone()),
+                b: None,
+            });
+            i += 1;
+        } else {
+            aligned.push(StepDiff {
+                a: None,
+                b: Some(b[j].clone()),
+            });
+            j += 1;
+        }
+    }
+    aligned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(step_id: &str, tool_name: &str, status: &str) -> RunStepOutcome {
+        RunStepOutcome {
+            step_id: step_id.to_string(),
+            tool_name: tool_name.to_string(),
+            status: status.to_string(),
+            output: String::new(),
+        }
+    }
+
+    #[test]
+    fn aligns_shared_tools_and_keeps_extra_steps_in_order() {
+        let a = [
+            step("s1", "list_dir", "executed"),
+            step("s2", "read_file", "executed"),
+            step("s3", "write_file", "executed"),
+        ];
+        let b = [
+            step("s1", "list_dir", "executed"),
+            step("s2", "search_text", "executed"),
+            step("s3", "read_file", "skipped"),
+        ];
+        let rows = align_steps(&a, &b);
+        let kinds: Vec<(&str, Option<&str>, Option<&str>)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.kind(),
+                    row.a.as_ref().map(|step| step.tool_name.as_str()),
+                    row.b.as_ref().map(|step| step.tool_name.as_str()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("same", Some("list_dir"), Some("list_dir")),
+                ("only_b", None, Some("search_text")),
+                ("changed", Some("read_file"), Some("read_file")),
+                ("only_a", Some("write_file"), None),
+            ]
+        );
+    }
+}
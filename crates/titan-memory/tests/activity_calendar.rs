@@ -0,0 +1,86 @@
+use chrono::{Datelike, Utc};
+use tempfile::tempdir;
+use titan_core::{Goal, GoalStatus};
+use titan_memory::{MemoryStore, NewJobRecord, parse_calendar_month};
+
+#[test]
+fn calendar_buckets_runs_triggers_and_projected_jobs_by_day() {
+    let tmp = tempdir().expect("tempdir");
+    let db = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db).expect("open store");
+    let today = Utc::now().date_naive();
+    let month = today.with_day(1).expect("month");
+
+    store
+        .add_job(NewJobRecord {
+            job_id: "nightly",
+            name: "nightly",
+            schedule_kind: "cron",
+            schedule_value: "0 0 9 * * *",
+            goal_template: "nightly report",
+            mode: "supervised",
+            allowed_scopes: "read",
+        })
+        .expect("job");
+    let run_id = store.start_job_run("nightly").expect("job run");
+    let done = Goal::new("nightly report");
+    store.create_goal(&done).expect("goal");
+    store
+        .update_goal_status(&done.id, GoalStatus::Completed)
+        .expect("complete");
+    store
+        .finish_job_run(&run_id, "completed", Some(&done.id), None)
+        .expect("finish run");
+    store
+        .add_connector("gh", "github", "GitHub", "{}")
+        .expect("connector");
+    store
+        .record_connector_tool_usage("gh", "webhook:push", Some(&done.id))
+        .expect("webhook");
+    store
+        .record_connector_tool_usage("gh", "list_issues", None)
+        .expect("tool call");
+
+    let calendar = store
+        .activity_calendar(month, Utc::now().timestamp_millis())
+        .expect("calendar");
+    assert_eq!(calendar.month, month.format("%Y-%m").to_string());
+    assert_eq!(
+        calendar.days.first().expect("first").date,
+        month.to_string()
+    );
+    let day = calendar
+        .days
+        .iter()
+        .find(|day| day.date == today.to_string())
+        .expect("today");
+    assert_eq!(
+        (
+            day.job_runs,
+            day.webhook_triggers,
+            day.completed,
+            day.failed
+        ),
+        (1, 1, 1, 0)
+    );
+    let scheduled: i64 = calendar.days.iter().map(|day| day.scheduled).sum();
+    let remaining_days = calendar
+        .days
+        .iter()
+        .filter(|day| day.date > today.to_string())
+        .count() as i64;
+    assert!(
+        (remaining_days..=remaining_days + 1).contains(&scheduled),
+        "one projected run per remaining day, got {scheduled}"
+    );
+
+    let past = store
+        .activity_calendar(
+            parse_calendar_month("2001-02").expect("month"),
+            Utc::now().timestamp_millis(),
+        )
+        .expect("past calendar");
+    assert_eq!(past.days.len(), 28);
+    assert!(past.days.iter().all(|day| day.total() == 0));
+    assert!(parse_calendar_month("2001-13").is_err());
+}
@@ -0,0 +1,46 @@
+use titan_common::ActivationMode;
+use titan_memory::approval_payload::{
+    AllowlistAction, ConfigActivationPayload, ConfigAllowlistPayload, SkillExecGrantPayload,
+    decode_approval_payload, encode_approval_payload,
+};
+
+#[test]
+fn round_trips_and_reads_legacy_strings() {
+    let payload = ConfigAllowlistPayload {
+        action: AllowlistAction::Remove,
+        id: "u:42".to_string(),
+    };
+    let encoded = encode_approval_payload(&payload).expect("encode");
+    assert!(encoded.contains(r#""kind":"config_allowlist""#));
+    assert_eq!(
+        decode_approval_payload::<ConfigAllowlistPayload>(&encoded).expect("decode"),
+        payload
+    );
+    let legacy: ConfigAllowlistPayload = decode_approval_payload("add:discord:1").expect("legacy");
+    assert_eq!(legacy.action, AllowlistAction::Add);
+    assert_eq!(legacy.id, "discord:1");
+
+    let mode: ConfigActivationPayload = decode_approval_payload("mention").expect("legacy");
+    assert!(matches!(mode.mode, ActivationMode::Mention));
+}
+
+#[test]
+fn rejects_wrong_kind_newer_versions_and_unknown_fields() {
+    let grant = encode_approval_payload(&SkillExecGrantPayload {
+        slug: "deploy".to_string(),
+    })
+    .expect("encode");
+    let err = decode_approval_payload::<ConfigAllowlistPayload>(&grant).expect_err("kind");
+    assert!(err.to_string().contains("expected 'config_allowlist'"));
+
+    let newer = r#"{"kind":"skill_exec_grant","version":2,"payload":{"slug":"x"}}"#;
+    let err = decode_approval_payload::<SkillExecGrantPayload>(newer).expect_err("newer");
+    assert!(err.to_string().contains("newer than this build"));
+
+    let extra = r#"{"kind":"skill_exec_grant","version":1,"payload":{"slug":"x","y":1}}"#;
+    let err = decode_approval_payload::<SkillExecGrantPayload>(extra).expect_err("strict");
+    assert!(err.to_string().contains("unknown field"));
+
+    let err = decode_approval_payload::<ConfigAllowlistPayload>("nonsense").expect_err("bad");
+    assert!(err.to_string().contains("not action:id"));
+}
@@ -0,0 +1,53 @@
+use tempfile::tempdir;
+use titan_memory::MemoryStore;
+use titan_memory::blob::{BLOB_REF_PREFIX, blob_key};
+
+#[test]
+fn large_outputs_are_stored_as_shared_blob_references() {
+    let tmp = tempdir().expect("tempdir");
+    let db_path = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db_path).expect("store");
+    let large = "x".repeat(64 * 1024 + 1);
+
+    store
+        .record_tool_run(None, "read_file", "success", &large)
+        .expect("first run");
+    store
+        .record_tool_run(None, "read_file", "success", &large)
+        .expect("second run");
+    store
+        .record_tool_run(None, "read_file", "success", "small")
+        .expect("small run");
+
+    let stored: Vec<String> = {
+        let conn = rusqlite::Connection::open(&db_path).expect("conn");
+        let mut stmt = conn
+            .prepare("SELECT output FROM tool_runs ORDER BY rowid")
+            .expect("prepare");
+        stmt.query_map([], |row| row.get(0))
+            .expect("query")
+            .collect::<Result<_, _>>()
+            .expect("rows")
+    };
+    let key = blob_key(large.as_bytes());
+    let reference = format!("{BLOB_REF_PREFIX}{key}");
+    assert_eq!(
+        stored,
+        vec![reference.clone(), reference.clone(), "small".to_string()]
+    );
+    assert!(tmp.path().join("blobs").join(&key[..2]).join(&key).exists());
+    assert_eq!(store.resolve_output(&reference).expect("resolve"), large);
+    assert_eq!(store.resolve_output("small").expect("plain"), "small");
+    assert_eq!(
+        store.blob(&key).expect("blob").map(|bytes| bytes.len()),
+        Some(large.len())
+    );
+    assert!(store.blob(&blob_key(b"unknown")).expect("blob").is_none());
+
+    // A failed lookup is an error, not an unknown key.
+    rusqlite::Connection::open(&db_path)
+        .expect("conn")
+        .execute_batch("DROP TABLE blobs")
+        .expect("drop blobs");
+    assert!(store.blob(&key).is_err());
+}
@@ -0,0 +1,141 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tempfile::tempdir;
+use titan_core::{Goal, GoalStatus};
+use titan_memory::MemoryStore;
+
+#[test]
+fn repair_reports_drift_then_fixes_it_without_touching_runs_in_flight() {
+    let tmp = tempdir().expect("tempdir");
+    let db = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db).expect("open store");
+
+    let stuck = Goal::new("stuck in planning");
+    store.create_goal(&stuck).expect("stuck goal");
+    let waiting = Goal::new("waiting on approval");
+    store.create_goal(&waiting).expect("waiting goal");
+    store
+        .create_approval_request_for_goal(
+            Some(&waiting.id),
+            "email.send",
+            "write",
+            "{}",
+            Some("test"),
+            60_000,
+        )
+        .expect("pending approval");
+    let abandoned = store
+        .get_or_create_active_session("discord", "u1")
+        .expect("abandoned session");
+    let active = store
+        .create_session("discord", "u1", None)
+        .expect("active session");
+
+    // Drift only gets in with foreign keys off, as with hand edits and older databases.
+    let raw = rusqlite::Connection::open(&db).expect("second connection");
+    raw.execute_batch("PRAGMA foreign_keys = OFF")
+        .expect("foreign keys off");
+    raw.execute(
+        "INSERT INTO tool_runs (id, approval_id, tool_name, status, output)
+         VALUES ('dangling-run', 'missing-approval', 'run_command', 'success', 'ok')",
+        [],
+    )
+    .expect("dangling tool run");
+    raw.execute(
+        "INSERT INTO run_steps (id, goal_id, plan_id, step_id, tool_name, permission, status, output)
+         VALUES ('orphan-step', ?1, 'missing-plan', 's1', 'list_dir', 'read', 'success', '')",
+        [&stuck.id],
+    )
+    .expect("orphan step");
+    raw.execute(
+        "UPDATE goals SET status = 'planning', updated_at = datetime('now', '-2 hours')",
+        [],
+    )
+    .expect("age goals");
+    raw.execute(
+        "UPDATE sessions SET updated_at = datetime('now', '-2 hours')",
+        [],
+    )
+    .expect("age sessions");
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock")
+        .as_millis() as i64;
+    let cutoff = now_ms - 60 * 60_000;
+
+    let dry_run = store.repair_references(cutoff, false).expect("dry run");
+    assert!(!dry_run.fixed);
+    let found: Vec<(&str, Vec<String>)> = dry_run
+        .findings
+        .iter()
+        .map(|finding| (finding.check, finding.ids.clone()))
+        .collect();
+    assert_eq!(
+        found[0],
+        (
+            "tool_run_missing_approval",
+            vec!["dangling-run".to_string()]
+        )
+    );
+    assert_eq!(
+        found[1],
+        ("goal_stuck_planning", vec![stuck.id.clone()]),
+        "a goal with a pending approval is still in flight"
+    );
+    assert_eq!(
+        found[2],
+        ("step_missing_plan", vec!["orphan-step".to_string()])
+    );
+    assert_eq!(
+        found[3],
+        ("session_without_messages", vec![abandoned.id.clone()]),
+        "the peer's newest session is still open"
+    );
+    assert_eq!(dry_run.total(), 4);
+    assert_eq!(
+        store
+            .get_goal(&stuck.id)
+            .expect("goal")
+            .expect("exists")
+            .status,
+        GoalStatus::Planning.as_str()
+    );
+    assert!(store.get_session(&abandoned.id).expect("lookup").is_some());
+
+    let fixed = store.repair_references(cutoff, true).expect("fix");
+    assert!(fixed.fixed);
+    assert_eq!(fixed.total(), 4);
+    assert_eq!(
+        store
+            .get_goal(&stuck.id)
+            .expect("goal")
+            .expect("exists")
+            .status,
+        GoalStatus::Failed.as_str()
+    );
+    assert!(
+        store
+            .get_traces(&stuck.id)
+            .expect("traces")
+            .iter()
+            .any(|trace| trace.event_type == "goal_repaired")
+    );
+    assert_eq!(
+        store
+            .get_goal(&waiting.id)
+            .expect("goal")
+            .expect("exists")
+            .status,
+        GoalStatus::Planning.as_str()
+    );
+    assert!(store.get_session(&abandoned.id).expect("lookup").is_none());
+    assert!(store.get_session(&active.id).expect("lookup").is_some());
+
+    assert_eq!(
+        store
+            .repair_references(cutoff, false)
+            .expect("rerun")
+            .total(),
+        0
+    );
+}
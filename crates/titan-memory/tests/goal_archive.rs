@@ -0,0 +1,98 @@
+use tempfile::tempdir;
+use titan_common::{ArchiveConfig, ArchiveTarget};
+use titan_core::{Goal, GoalStatus, TraceEvent};
+use titan_memory::MemoryStore;
+
+fn finished_goal(store: &MemoryStore, description: &str, status: GoalStatus) -> Goal {
+    let goal = Goal::new(description);
+    store.create_goal(&goal).expect("goal");
+    for event_type in ["goal_submitted", "execution_started"] {
+        store
+            .add_trace_event(&TraceEvent::new(&goal.id, event_type, description))
+            .expect("trace");
+    }
+    store.update_goal_status(&goal.id, status).expect("status");
+    goal
+}
+
+#[test]
+fn old_finished_goals_move_to_archives_and_leave_stub_rows() {
+    let tmp = tempdir().expect("tempdir");
+    let db = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db).expect("open store");
+    let old = finished_goal(&store, "old scan", GoalStatus::Completed);
+    let running = finished_goal(&store, "still running", GoalStatus::Executing);
+    let recent = finished_goal(&store, "recent scan", GoalStatus::Failed);
+    rusqlite::Connection::open(&db)
+        .expect("second connection")
+        .execute(
+            "UPDATE goals SET updated_at = datetime('now', '-120 days') WHERE id IN (?1, ?2)",
+            [&old.id, &running.id],
+        )
+        .expect("age goals");
+    let cutoff_ms = chrono::Utc::now().timestamp_millis() - 90 * 86_400_000;
+    let config = ArchiveConfig {
+        dir: Some(tmp.path().join("cold")),
+        ..ArchiveConfig::default()
+    };
+
+    let report = store.archive_goals(cutoff_ms, &config).expect("archive");
+    assert_eq!(report.goals, 1, "only finished goals past the cutoff");
+    assert!(report.rows >= 2, "traces and their sampling counters");
+    let archive_ref = report.archive_ref.expect("archive written");
+    assert!(archive_ref.starts_with(&tmp.path().join("cold").display().to_string()));
+    assert!(archive_ref.ends_with(".jsonl.zst"));
+
+    let stub = store
+        .get_goal(&old.id)
+        .expect("get")
+        .expect("stub row kept");
+    assert_eq!(stub.status, "completed");
+    assert!(store.get_traces(&old.id).expect("traces").is_empty());
+    assert_eq!(store.get_traces(&recent.id).expect("traces").len(), 2);
+    assert_eq!(
+        store.goal_archive_ref(&old.id).expect("ref"),
+        Some(archive_ref)
+    );
+    assert_eq!(store.goal_archive_ref(&recent.id).expect("ref"), None);
+    let archived = store
+        .archived_goal(&old.id)
+        .expect("read archive")
+        .expect("archived");
+    assert_eq!(archived["goal"]["description"], "old scan");
+    let traces = archived["tables"]["trace_events"]
+        .as_array()
+        .expect("traces");
+    assert_eq!(traces.len(), 2);
+    assert_eq!(traces[0]["event_type"], "goal_submitted");
+
+    let again = store.archive_goals(cutoff_ms, &config).expect("archive");
+    assert_eq!(again.goals, 0);
+    assert!(again.archive_ref.is_none());
+}
+
+#[test]
+fn archives_can_go_to_the_blob_store() {
+    let tmp = tempdir().expect("tempdir");
+    let db = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db).expect("open store");
+    let goal = finished_goal(&store, "cancelled scan", GoalStatus::Cancelled);
+    let config = ArchiveConfig {
+        target: ArchiveTarget::BlobStore,
+        ..ArchiveConfig::default()
+    };
+
+    let report = store
+        .archive_goals(chrono::Utc::now().timestamp_millis() + 60_000, &config)
+        .expect("archive");
+    let archive_ref = report.archive_ref.expect("archive written");
+    let key = archive_ref
+        .strip_prefix("blob:sha256:")
+        .expect("blob reference");
+    assert!(store.blob(key).expect("blob").is_some());
+    let archived = store
+        .archived_goal(&goal.id)
+        .expect("read archive")
+        .expect("archived");
+    assert_eq!(archived["goal"]["status"], "cancelled");
+}
@@ -0,0 +1,123 @@
+use tempfile::tempdir;
+use titan_core::{Goal, GoalStatus};
+use titan_memory::{GOAL_LABEL_AUTO, GOAL_LABEL_USER, GoalFilter, MemoryStore};
+
+#[test]
+fn goals_filter_by_every_label_and_status_and_presets_are_per_operator() {
+    let tmp = tempdir().expect("tempdir");
+    let store = MemoryStore::open(&tmp.path().join("titan.db")).expect("open store");
+    let discord = Goal::new("scan workspace");
+    let slack = Goal::new("update readme");
+    store.create_goal(&discord).expect("goal");
+    store.create_goal(&slack).expect("goal");
+    store
+        .add_goal_labels(
+            &discord.id,
+            &[
+                "channel:discord".to_string(),
+                "Intent:scan_workspace".to_string(),
+            ],
+            GOAL_LABEL_AUTO,
+        )
+        .expect("labels");
+    store
+        .add_goal_labels(&slack.id, &["channel:slack".to_string()], GOAL_LABEL_AUTO)
+        .expect("labels");
+    let added = store
+        .add_goal_labels(
+            &discord.id,
+            &["release".to_string(), "channel:discord".to_string()],
+            GOAL_LABEL_USER,
+        )
+        .expect("user labels");
+    assert_eq!(added, 1, "an existing label is not added twice");
+    assert_eq!(
+        store.goal_labels(&discord.id).expect("labels"),
+        vec!["channel:discord", "intent:scan_workspace", "release"]
+    );
+    assert!(
+        store
+            .add_goal_labels(&discord.id, &["two words".to_string()], GOAL_LABEL_USER)
+            .is_err()
+    );
+
+    let ids = |filter: &GoalFilter| -> Vec<String> {
+        store
+            .list_goals_filtered(filter, 10)
+            .expect("filtered goals")
+            .into_iter()
+            .map(|goal| goal.id)
+            .collect()
+    };
+    assert_eq!(ids(&GoalFilter::default()).len(), 2);
+    let release = GoalFilter::parse("label=release label=channel:discord").expect("filter");
+    assert_eq!(ids(&release), vec![discord.id.clone()]);
+    assert!(
+        ids(&GoalFilter::parse("label=release label=channel:slack").expect("filter")).is_empty()
+    );
+    store
+        .update_goal_status(&slack.id, GoalStatus::Failed)
+        .expect("status");
+    assert_eq!(
+        ids(&GoalFilter::parse("status=failed").expect("filter")),
+        vec![slack.id.clone()]
+    );
+    assert!(GoalFilter::parse("status=lost").is_err());
+    assert!(GoalFilter::parse("release").is_err());
+
+    assert!(
+        store
+            .remove_goal_label(&discord.id, "RELEASE")
+            .expect("remove")
+    );
+    assert!(
+        !store
+            .remove_goal_label(&discord.id, "release")
+            .expect("remove")
+    );
+    assert!(ids(&release).is_empty());
+
+    store
+        .save_goal_filter(
+            "alice",
+            "failures",
+            &GoalFilter::parse("status=failed").expect("filter"),
+        )
+        .expect("save");
+    store
+        .save_goal_filter("alice", "releases", &release)
+        .expect("save");
+    assert!(
+        store
+            .save_goal_filter("alice", "two words", &release)
+            .is_err()
+    );
+    let saved = store.list_goal_filters("alice").expect("presets");
+    assert_eq!(
+        saved
+            .iter()
+            .map(|preset| (preset.name.as_str(), preset.filter.terms()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("failures", "status=failed".to_string()),
+            (
+                "releases",
+                "label=release label=channel:discord".to_string()
+            ),
+        ]
+    );
+    assert!(store.list_goal_filters("bob").expect("presets").is_empty());
+    assert_eq!(
+        store.get_goal_filter("alice", "releases").expect("preset"),
+        Some(release)
+    );
+    assert!(
+        store
+            .remove_goal_filter("alice", "releases")
+            .expect("remove")
+    );
+    assert_eq!(
+        store.get_goal_filter("alice", "releases").expect("preset"),
+        None
+    );
+}
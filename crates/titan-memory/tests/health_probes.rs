@@ -0,0 +1,37 @@
+use tempfile::tempdir;
+use titan_memory::{LATEST_SCHEMA_VERSION, MemoryStore};
+
+#[test]
+fn fresh_store_is_at_latest_schema_and_writable() {
+    let tmp = tempdir().expect("tempdir");
+    let db = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db).expect("open store");
+    assert_eq!(
+        store.schema_version().expect("version"),
+        LATEST_SCHEMA_VERSION
+    );
+    store.probe_write().expect("write probe");
+    assert_eq!(store.latest_recorded_at_ms().expect("latest"), None);
+
+    store
+        .record_worker_heartbeat("scheduler", "pid1", 1_000)
+        .expect("beat");
+    store
+        .record_worker_heartbeat("scheduler", "pid2", 2_000)
+        .expect("takeover beat");
+    let beats = store.list_worker_heartbeats().expect("beats");
+    assert_eq!(beats.len(), 1);
+    assert_eq!(beats[0].holder, "pid2");
+    assert!(!beats[0].is_stale(beats[0].beat_at_ms + 15_000));
+    assert!(beats[0].is_stale(beats[0].beat_at_ms + 15_001));
+    assert_eq!(
+        store.latest_recorded_at_ms().expect("latest"),
+        Some(beats[0].beat_at_ms)
+    );
+
+    // The probe leaves no transaction open behind it.
+    store.probe_write().expect("second probe");
+    store
+        .record_worker_heartbeat("outbox", "pid2", 5_000)
+        .expect("write after probe");
+}
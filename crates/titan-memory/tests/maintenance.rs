@@ -0,0 +1,56 @@
+use tempfile::tempdir;
+use titan_core::{Goal, TraceEvent};
+use titan_memory::MemoryStore;
+
+#[test]
+fn maintenance_vacuums_freed_pages_and_records_the_run() {
+    let tmp = tempdir().expect("tempdir");
+    let db = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db).expect("open store");
+    assert!(
+        store
+            .maintenance_due(0, 1, 1.0)
+            .expect("due before first run")
+    );
+
+    let goal = Goal::new("bulk trace writer");
+    store.create_goal(&goal).expect("goal");
+    let filler = "x".repeat(2048);
+    for i in 0..400 {
+        store
+            .add_trace_event(&TraceEvent::new(
+                &goal.id,
+                "execution_failed",
+                format!("{i}{filler}"),
+            ))
+            .expect("trace");
+    }
+    store
+        .run_maintenance("test", false, 1.0)
+        .expect("baseline run");
+    rusqlite::Connection::open(&db)
+        .expect("second connection")
+        .execute("DELETE FROM trace_events", [])
+        .expect("delete traces");
+    assert!(store.size_stats().expect("stats").freelist_pages > 0);
+
+    let report = store
+        .run_maintenance("test", false, 0.2)
+        .expect("maintenance");
+    assert!(report.vacuumed);
+    assert!(report.reclaimed_bytes() > 0);
+    assert_eq!(report.after.freelist_pages, 0);
+    assert_eq!(report.integrity, vec!["ok".to_string()]);
+    assert!(report.index_count > 0);
+
+    let last = store
+        .last_maintenance_at_ms()
+        .expect("last run")
+        .expect("recorded");
+    assert!(!store.maintenance_due(last, 60_000, 0.2).expect("not due"));
+    assert!(
+        store
+            .maintenance_due(last + 60_000, 60_000, 0.2)
+            .expect("interval elapsed")
+    );
+}
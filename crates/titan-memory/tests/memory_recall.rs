@@ -0,0 +1,223 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+use tempfile::tempdir;
+use titan_core::Goal;
+use titan_memory::MemoryStore;
+use titan_memory::embedding::{Embedder, HashingEmbedder, OpenAiEmbedder};
+
+#[test]
+fn recall_similar_ranks_facts_and_memories_by_meaning() {
+    let tmp = tempdir().expect("tempdir");
+    let store = MemoryStore::open(&tmp.path().join("titan.db")).expect("open store");
+    let goal = Goal::new("rotate the deploy key");
+    store.create_goal(&goal).expect("goal");
+    store
+        .add_episodic_memory(
+            &goal.id,
+            "Rotating the deploy key needs the vault token",
+            "reflection",
+        )
+        .expect("memory");
+    store
+        .add_episodic_memory(&goal.id, "Listed the docs folder", "reflection")
+        .expect("memory");
+    store
+        .upsert_semantic_fact("repo", "deploy_branch", "main", "user")
+        .expect("fact");
+    store
+        .upsert_semantic_fact("repo", "deploy_branch", "release", "user")
+        .expect("fact");
+
+    let recalled = store
+        .recall_similar("rotate deploy key", 2)
+        .expect("recall");
+    assert_eq!(recalled.len(), 2);
+    assert_eq!(recalled[0].kind, "episodic");
+    assert!(recalled[0].text.contains("deploy key"));
+    assert!(recalled[0].score > recalled[1].score);
+
+    let facts = store
+        .recall_similar("repo deploy branch", 5)
+        .expect("recall");
+    let fact_texts: Vec<&str> = facts
+        .iter()
+        .filter(|hit| hit.kind == "semantic")
+        .map(|hit| hit.text.as_str())
+        .collect();
+    assert_eq!(fact_texts, vec!["repo.deploy_branch: release"]);
+    assert!(
+        store
+            .recall_similar("anything", 0)
+            .expect("recall")
+            .is_empty()
+    );
+}
+
+struct FixedEmbedder;
+
+impl Embedder for FixedEmbedder {
+    fn id(&self) -> String {
+        "fixed".to_string()
+    }
+
+    fn embed(&self, text: &str) -> anyhow::Result<Vec<f32>> {
+        Ok(if text.contains("vault") {
+            vec![1.0, 0.0]
+        } else {
+            vec![0.0, 1.0]
+        })
+    }
+}
+
+#[test]
+fn recall_uses_the_plugged_in_embedder_and_keeps_vectors_apart() {
+    let tmp = tempdir().expect("tempdir");
+    let db_path = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db_path).expect("open store");
+    let goal = Goal::new("g");
+    store.create_goal(&goal).expect("goal");
+    store
+        .add_episodic_memory(&goal.id, "vault unsealed", "reflection")
+        .expect("memory");
+    store
+        .add_episodic_memory(&goal.id, "docs listed", "reflection")
+        .expect("memory");
+    let hashed = store.recall_similar("docs", 1).expect("hashing recall");
+    assert_eq!(hashed[0].text, "docs listed");
+
+    let store = MemoryStore::open(&db_path)
+        .expect("reopen")
+        .with_embedder(Arc::new(FixedEmbedder));
+    let recalled = store
+        .recall_similar("where is the vault", 1)
+        .expect("recall");
+    assert_eq!(recalled[0].text, "vault unsealed");
+    assert!((recalled[0].score - 1.0).abs() < 1e-6);
+
+    let a = HashingEmbedder::new(64).embed("Deploy key").expect("embed");
+    let b = HashingEmbedder::new(64).embed("deploy KEY").expect("embed");
+    assert_eq!(a, b);
+}
+
+struct CountingEmbedder {
+    dimensions: usize,
+    batches: Mutex<Vec<usize>>,
+}
+
+impl Embedder for CountingEmbedder {
+    fn id(&self) -> String {
+        "counting".to_string()
+    }
+
+    fn embed(&self, _text: &str) -> anyhow::Result<Vec<f32>> {
+        Ok(vec![1.0; self.dimensions])
+    }
+
+    fn embed_batch(&self, texts: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        self.batches.lock().expect("batches").push(texts.len());
+        texts.iter().map(|text| self.embed(text)).collect()
+    }
+}
+
+#[test]
+fn pending_memories_are_embedded_in_batches_and_sizes_are_kept_per_namespace() {
+    let tmp = tempdir().expect("tempdir");
+    let db_path = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db_path).expect("open store");
+    let goal = Goal::new("g");
+    store.create_goal(&goal).expect("goal");
+    store
+        .add_episodic_memory(&goal.id, "vault unsealed", "reflection")
+        .expect("memory");
+    store
+        .upsert_semantic_fact("repo", "deploy_branch", "main", "user")
+        .expect("fact");
+    store
+        .upsert_semantic_fact("user", "timezone", "UTC", "user")
+        .expect("fact");
+
+    let embedder = Arc::new(CountingEmbedder {
+        dimensions: 3,
+        batches: Mutex::new(Vec::new()),
+    });
+    let store = MemoryStore::open(&db_path)
+        .expect("reopen")
+        .with_embedder(embedder.clone());
+    assert_eq!(store.embed_pending_memories().expect("embed"), 3);
+    assert_eq!(*embedder.batches.lock().expect("batches"), vec![3]);
+    assert_eq!(store.embed_pending_memories().expect("nothing left"), 0);
+    let namespaces: Vec<(String, String, usize)> = store
+        .embedding_namespaces()
+        .expect("namespaces")
+        .into_iter()
+        .map(|ns| (ns.namespace, ns.embedder, ns.dimensions))
+        .collect();
+    assert_eq!(
+        namespaces,
+        vec![
+            ("episodic".to_string(), "counting".to_string(), 3),
+            ("repo".to_string(), "counting".to_string(), 3),
+            ("user".to_string(), "counting".to_string(), 3),
+        ]
+    );
+
+    store
+        .add_episodic_memory(&goal.id, "docs listed", "reflection")
+        .expect("memory");
+    let resized = MemoryStore::open(&db_path)
+        .expect("reopen")
+        .with_embedder(Arc::new(CountingEmbedder {
+            dimensions: 4,
+            batches: Mutex::new(Vec::new()),
+        }));
+    let err = resized
+        .recall_similar("docs", 1)
+        .expect_err("a size change under the same id is rejected");
+    assert!(err.to_string().contains("returned 4 dimensions"));
+}
+
+#[test]
+fn openai_batches_come_back_in_input_order() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let endpoint = format!("http://{}", listener.local_addr().expect("addr"));
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().expect("accept");
+        let mut reader = BufReader::new(stream.try_clone().expect("clone"));
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).expect("header");
+            if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                length = value.trim().parse().expect("length");
+            }
+            if line == "\r\n" {
+                break;
+            }
+        }
+        let mut request = vec![0; length];
+        reader.read_exact(&mut request).expect("body");
+        let body =
+            r#"{"data":[{"index":1,"embedding":[0.0,2.0]},{"index":0,"embedding":[3.0,0.0]}]}"#;
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+            body.len()
+        )
+        .expect("respond");
+        String::from_utf8(request).expect("utf8")
+    });
+    unsafe { std::env::set_var("TITAN_TEST_EMBEDDINGS_KEY", "sk-test") };
+    let embedder = OpenAiEmbedder {
+        endpoint,
+        model: "text-embedding-3-small".to_string(),
+        api_key_env: "TITAN_TEST_EMBEDDINGS_KEY".to_string(),
+    };
+    let vectors = embedder
+        .embed_batch(&["first".to_string(), "second".to_string()])
+        .expect("batch");
+    assert_eq!(vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+    let request = server.join().expect("server");
+    assert!(request.contains(r#""input":["first","second"]"#));
+}
@@ -0,0 +1,71 @@
+use tempfile::tempdir;
+use titan_core::{Goal, TraceEvent};
+use titan_memory::{MemoryKind, MemoryStore};
+
+#[test]
+fn search_memory_ranks_hits_across_traces_memories_and_messages() {
+    let tmp = tempdir().expect("tempdir");
+    let store = MemoryStore::open(&tmp.path().join("titan.db")).expect("open store");
+    let goal = Goal::new("rotate the deploy key");
+    store.create_goal(&goal).expect("goal");
+    store
+        .add_trace_event(&TraceEvent::new(
+            &goal.id,
+            "tool_executed",
+            "deploy key rotated; deploy key fingerprint updated",
+        ))
+        .expect("trace");
+    store
+        .add_trace_event(&TraceEvent::new(
+            &goal.id,
+            "tool_executed",
+            "listed the workspace and found one key file among many unrelated files",
+        ))
+        .expect("trace");
+    store
+        .add_episodic_memory(
+            &goal.id,
+            "Rotating the deploy key needs the vault",
+            "reflection",
+        )
+        .expect("memory");
+    let session = store
+        .get_or_create_active_session("webchat", "u1")
+        .expect("session");
+    store
+        .add_session_message(&session.id, "user", "please rotate the deploy key", false)
+        .expect("message");
+
+    let hits = store
+        .search_memory("deploy key", &[], 10)
+        .expect("search all");
+    assert_eq!(hits.len(), 3);
+    assert!(hits.windows(2).all(|pair| pair[0].score >= pair[1].score));
+    assert_eq!(hits[0].kind, MemoryKind::Trace);
+    assert!(hits[0].snippet.contains("[deploy] [key]"));
+    let message = hits
+        .iter()
+        .find(|hit| hit.kind == MemoryKind::Message)
+        .expect("message hit");
+    assert_eq!(message.owner_id, session.id);
+
+    let memories = store
+        .search_memory("vault", &[MemoryKind::Episodic], 10)
+        .expect("episodic only");
+    assert_eq!(memories.len(), 1);
+    assert_eq!(memories[0].owner_id, goal.id);
+    assert!(
+        store
+            .search_memory("vault", &[MemoryKind::Trace], 10)
+            .expect("traces only")
+            .is_empty()
+    );
+
+    // Query syntax is searched literally instead of failing to parse.
+    assert!(
+        store
+            .search_memory("key\" OR (NEAR", &[], 10)
+            .expect("quoted terms")
+            .is_empty()
+    );
+}
@@ -0,0 +1,118 @@
+use std::sync::{Arc, Barrier};
+use std::time::{Duration, Instant};
+
+use tempfile::tempdir;
+use titan_memory::{LATEST_SCHEMA_VERSION, MemoryStore};
+
+#[test]
+fn concurrent_opens_of_a_fresh_database_migrate_once() {
+    let tmp = tempdir().expect("tempdir");
+    let db = tmp.path().join("titan.db");
+    let barrier = Arc::new(Barrier::new(6));
+    let handles: Vec<_> = (0..6)
+        .map(|_| {
+            let db = db.clone();
+            let barrier = barrier.clone();
+            std::thread::spawn(move || {
+                barrier.wait();
+                MemoryStore::open(&db).map(|store| store.schema_version())
+            })
+        })
+        .collect();
+    for handle in handles {
+        let version = handle
+            .join()
+            .expect("thread")
+            .expect("open")
+            .expect("version");
+        assert_eq!(version, LATEST_SCHEMA_VERSION);
+    }
+
+    let conn = rusqlite::Connection::open(&db).expect("conn");
+    let applied: i64 = conn
+        .query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| {
+            row.get(0)
+        })
+        .expect("count");
+    assert_eq!(applied, LATEST_SCHEMA_VERSION);
+    let locks: i64 = conn
+        .query_row("SELECT COUNT(*) FROM migration_lock", [], |row| row.get(0))
+        .expect("locks");
+    assert_eq!(locks, 0, "the migrating process releases the lock");
+}
+
+#[test]
+fn open_waits_for_a_held_lock_and_takes_it_over_once_expired() {
+    let tmp = tempdir().expect("tempdir");
+    let db = tmp.path().join("titan.db");
+    drop(MemoryStore::open(&db).expect("initial open"));
+
+    // Simulate a pending migration while another process holds the lock.
+    let conn = rusqlite::Connection::open(&db).expect("conn");
+    conn.execute(
+        "DELETE FROM schema_migrations WHERE version = ?1",
+        [LATEST_SCHEMA_VERSION],
+    )
+    .expect("unapply latest");
+    let expires_at_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("clock")
+        .as_millis() as i64
+        + 300;
+    conn.execute(
+        "INSERT INTO migration_lock (id, holder, acquired_at_ms, expires_at_ms)
+         VALUES (1, 'crashed-process', 0, ?1)",
+        [expires_at_ms],
+    )
+    .expect("hold lock");
+
+    let started = Instant::now();
+    let store = MemoryStore::open(&db).expect("open after expiry");
+    assert!(started.elapsed() >= Duration::from_millis(250));
+    assert_eq!(
+        store.schema_version().expect("version"),
+        LATEST_SCHEMA_VERSION
+    );
+}
+
+#[test]
+fn a_run_that_loses_the_lock_stops_before_the_next_migration() {
+    let tmp = tempdir().expect("tempdir");
+    let db = tmp.path().join("titan.db");
+    drop(MemoryStore::open(&db).expect("initial open"));
+
+    // Another process takes the lock over right after migration 53 commits.
+    let conn = rusqlite::Connection::open(&db).expect("conn");
+    conn.execute_batch(&format!(
+        "DELETE FROM schema_migrations WHERE version >= {};
+         CREATE TRIGGER steal_migration_lock AFTER INSERT ON schema_migrations
+         WHEN NEW.version = {} BEGIN
+           UPDATE migration_lock SET holder = 'other-process';
+         END;",
+        LATEST_SCHEMA_VERSION - 1,
+        LATEST_SCHEMA_VERSION - 1
+    ))
+    .expect("arrange takeover");
+
+    let err = MemoryStore::open(&db)
+        .err()
+        .expect("open stops once the lock is lost");
+    assert!(
+        format!("{err:#}").contains("migration lock lost"),
+        "{err:#}"
+    );
+    let latest: i64 = conn
+        .query_row("SELECT MAX(version) FROM schema_migrations", [], |row| {
+            row.get(0)
+        })
+        .expect("version");
+    assert_eq!(latest, LATEST_SCHEMA_VERSION - 1);
+    let holder: String = conn
+        .query_row(
+            "SELECT holder FROM migration_lock WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )
+        .expect("holder");
+    assert_eq!(holder, "other-process", "the new holder keeps its lock");
+}
@@ -0,0 +1,46 @@
+use tempfile::tempdir;
+use titan_memory::{MemoryStore, model_cache_key};
+
+#[test]
+fn cached_replies_expire_and_keys_cover_the_model_identity() {
+    let tmp = tempdir().expect("tempdir");
+    let db_path = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db_path).expect("open store");
+    let key = model_cache_key("ollama|llama3|http://127.0.0.1:11434", "plan this");
+    assert_ne!(
+        key,
+        model_cache_key("ollama|qwen3|http://127.0.0.1:11434", "plan this")
+    );
+
+    store
+        .cache_model_response(&key, "ollama|llama3", "{\"candidates\":[]}", 1_000, 61_000)
+        .expect("cache");
+    assert_eq!(
+        store.cached_model_response(&key, 30_000).expect("lookup"),
+        Some("{\"candidates\":[]}".to_string())
+    );
+    assert_eq!(
+        store.cached_model_response(&key, 61_000).expect("lookup"),
+        None
+    );
+    let stats = store.model_cache_stats(30_000).expect("stats");
+    assert_eq!((stats.entries, stats.live, stats.hits), (1, 1, 1));
+
+    // Storing again after expiry drops the stale entry and starts a fresh count.
+    let other = model_cache_key("ollama|llama3|http://127.0.0.1:11434", "reflect");
+    store
+        .cache_model_response(&other, "ollama|llama3", "{}", 70_000, 130_000)
+        .expect("cache");
+    let stats = store.model_cache_stats(70_000).expect("stats");
+    assert_eq!((stats.entries, stats.live, stats.hits), (1, 1, 0));
+
+    // A hit that cannot be counted is an error, not a miss.
+    rusqlite::Connection::open(&db_path)
+        .expect("conn")
+        .execute_batch(
+            "CREATE TRIGGER refuse_hits BEFORE UPDATE ON model_response_cache
+             BEGIN SELECT RAISE(ABORT, 'write refused'); END;",
+        )
+        .expect("trigger");
+    assert!(store.cached_model_response(&other, 80_000).is_err());
+}
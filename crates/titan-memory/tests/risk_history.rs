@@ -0,0 +1,36 @@
+use tempfile::tempdir;
+use titan_memory::{MemoryStore, RiskMode};
+
+#[test]
+fn every_arm_enable_disable_and_expiry_appends_history() {
+    let tmp = tempdir().expect("tempdir");
+    let store = MemoryStore::open(&tmp.path().join("titan.db")).expect("open store");
+    store
+        .arm_yolo("cli", Some("incident 42 cleanup"))
+        .expect("arm");
+    store.enable_yolo("cli", 10, None).expect("enable");
+    store
+        .set_risk_mode_secure("ops", Some("cleanup done"))
+        .expect("disable");
+    store.arm_yolo("cli", None).expect("arm again");
+    store.enable_yolo("cli", 10, None).expect("enable again");
+    store.set_yolo_expiry_at_ms(0).expect("backdate expiry");
+    assert!(store.apply_yolo_expiry("run_loop").expect("expire"));
+    assert!(!store.apply_yolo_expiry("run_loop").expect("already secure"));
+
+    let history = store.list_risk_state_history(10).expect("history");
+    let actions: Vec<_> = history.iter().map(|row| row.action.as_str()).collect();
+    assert_eq!(
+        actions,
+        vec!["expire", "enable", "arm", "disable", "enable", "arm"]
+    );
+    assert_eq!(history[0].actor, "run_loop");
+    assert_eq!(history[0].risk_mode, RiskMode::Secure);
+    assert!(history[0].reason.as_deref().unwrap_or("").contains("ttl"));
+    assert_eq!(history[1].risk_mode, RiskMode::Yolo);
+    assert!(history[1].expires_at_ms.is_some());
+    assert_eq!(history[3].actor, "ops");
+    assert_eq!(history[3].reason.as_deref(), Some("cleanup done"));
+    assert_eq!(history[5].risk_mode, RiskMode::Secure);
+    assert_eq!(history[5].reason.as_deref(), Some("incident 42 cleanup"));
+}
@@ -0,0 +1,99 @@
+use tempfile::tempdir;
+use titan_core::{Goal, GoalStatus};
+use titan_memory::{MemoryStore, RiskMode};
+
+fn finish_goal(store: &MemoryStore, status: GoalStatus) {
+    let goal = Goal::new("breaker probe");
+    store.create_goal(&goal).expect("goal");
+    store.update_goal_status(&goal.id, status).expect("status");
+}
+
+#[test]
+fn consecutive_failures_trip_the_breaker_until_an_operator_resumes() {
+    let tmp = tempdir().expect("tempdir");
+    let store = MemoryStore::open(&tmp.path().join("titan.db")).expect("open store");
+    store.enable_yolo("cli", 15, None).expect("yolo");
+
+    finish_goal(&store, GoalStatus::Failed);
+    finish_goal(&store, GoalStatus::Completed);
+    finish_goal(&store, GoalStatus::Failed);
+    finish_goal(&store, GoalStatus::Failed);
+    assert_eq!(store.consecutive_failed_goals().expect("streak"), 2);
+    assert!(
+        store
+            .evaluate_safe_mode(3, "gateway")
+            .expect("check")
+            .is_none()
+    );
+    assert!(
+        store
+            .evaluate_safe_mode(0, "gateway")
+            .expect("disabled")
+            .is_none()
+    );
+
+    finish_goal(&store, GoalStatus::Failed);
+    let reason = store
+        .evaluate_safe_mode(3, "gateway")
+        .expect("check")
+        .expect("tripped");
+    assert_eq!(reason, "3 consecutive goals failed");
+    let state = store.safe_mode_state().expect("state");
+    assert!(state.active);
+    assert_eq!(state.changed_by.as_deref(), Some("gateway"));
+    assert_eq!(
+        store.get_runtime_risk_state().expect("risk").risk_mode,
+        RiskMode::Secure
+    );
+    assert_eq!(
+        store.list_risk_state_history(1).expect("history")[0].action,
+        "safe_mode"
+    );
+    let err = store.arm_yolo("cli", None).expect_err("yolo refused");
+    assert!(err.to_string().contains("safe mode is active"));
+
+    assert!(
+        store
+            .resume_from_safe_mode("cli", Some("fixed"))
+            .expect("resume")
+    );
+    assert!(
+        !store
+            .resume_from_safe_mode("cli", None)
+            .expect("already normal")
+    );
+    assert_eq!(store.consecutive_failed_goals().expect("streak"), 0);
+    assert!(
+        store
+            .evaluate_safe_mode(3, "gateway")
+            .expect("check")
+            .is_none()
+    );
+    store.arm_yolo("cli", None).expect("yolo allowed again");
+}
+
+#[test]
+fn a_recorded_panic_boots_into_safe_mode_once() {
+    let tmp = tempdir().expect("tempdir");
+    let store = MemoryStore::open(&tmp.path().join("titan.db")).expect("open store");
+    store
+        .record_runtime_panic("thread 'main' panicked at src/lib.rs:1")
+        .expect("panic");
+    let reason = store
+        .evaluate_safe_mode(5, "startup")
+        .expect("check")
+        .expect("tripped");
+    assert!(reason.contains("panicked at src/lib.rs:1"));
+
+    store.resume_from_safe_mode("cli", None).expect("resume");
+    let state = store.safe_mode_state().expect("state");
+    assert!(!state.active);
+    assert!(!state.panic_pending);
+    assert!(state.last_panic_detail.is_some());
+    assert!(
+        store
+            .evaluate_safe_mode(5, "startup")
+            .expect("check")
+            .is_none()
+    );
+}
@@ -0,0 +1,111 @@
+use tempfile::tempdir;
+use titan_core::Goal;
+use titan_memory::MemoryStore;
+
+#[test]
+fn idle_sessions_are_compacted_closed_and_summarized() {
+    let tmp = tempdir().expect("tempdir");
+    let db = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db).expect("open store");
+    let idle = store
+        .get_or_create_active_session("discord", "u1")
+        .expect("session");
+    let goal = Goal::new("[discord] scan workspace");
+    store
+        .create_goal_for_session(&goal, Some(&idle.id))
+        .expect("goal");
+    for (role, content) in [
+        ("user", "scan workspace"),
+        ("assistant", "found 3 files"),
+        ("user", "read the readme"),
+        ("assistant", "readme says hello"),
+    ] {
+        store
+            .add_session_message(&idle.id, role, content, false)
+            .expect("message");
+    }
+    let quiet = store
+        .get_or_create_active_session("discord", "u2")
+        .expect("session");
+    let busy = store
+        .get_or_create_active_session("discord", "u3")
+        .expect("session");
+    store.set_session_queue_depth(&busy.id, 1).expect("busy");
+    let recent = store
+        .get_or_create_active_session("discord", "u4")
+        .expect("session");
+    rusqlite::Connection::open(&db)
+        .expect("second connection")
+        .execute(
+            "UPDATE sessions SET updated_at = datetime('now', '-10 days') WHERE id != ?1",
+            [&recent.id],
+        )
+        .expect("age sessions");
+
+    let cutoff_ms = chrono::Utc::now().timestamp_millis() - 7 * 86_400_000;
+    let report = store.close_idle_sessions(cutoff_ms).expect("close");
+    let mut closed = report.closed.clone();
+    closed.sort();
+    let mut expected = vec![idle.id.clone(), quiet.id.clone()];
+    expected.sort();
+    assert_eq!(closed, expected, "busy and recent sessions stay open");
+    assert_eq!(
+        report.summarized, 1,
+        "only sessions with a goal are summarized"
+    );
+
+    let closed_idle = store.get_session(&idle.id).expect("get").expect("session");
+    assert!(closed_idle.closed_at_ms.is_some());
+    assert_eq!(closed_idle.compactions_count, 1);
+    let memory = &store.list_episodic_memory(1).expect("memories")[0];
+    assert_eq!(memory.goal_id, goal.id);
+    assert_eq!(memory.source, "session_close");
+    assert!(memory.summary.contains("scan workspace"));
+    assert!(memory.summary.contains("readme says hello"));
+
+    let fresh = store
+        .get_or_create_active_session("discord", "u1")
+        .expect("session");
+    assert_ne!(fresh.id, idle.id, "a closed session is never resumed");
+    assert_eq!(
+        store
+            .get_or_create_active_session("discord", "u3")
+            .expect("session")
+            .id,
+        busy.id
+    );
+    assert!(
+        store
+            .close_idle_sessions(cutoff_ms)
+            .expect("close")
+            .closed
+            .is_empty()
+    );
+}
+
+#[test]
+fn a_failed_goal_lookup_leaves_the_session_open() {
+    let tmp = tempdir().expect("tempdir");
+    let db = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db).expect("open store");
+    let idle = store
+        .get_or_create_active_session("discord", "u1")
+        .expect("session");
+    let conn = rusqlite::Connection::open(&db).expect("second connection");
+    conn.execute(
+        "INSERT INTO goals (id, description, status, session_id)
+         VALUES (X'00', 'unreadable id', 'completed', ?1)",
+        [&idle.id],
+    )
+    .expect("goal with an unreadable id");
+    conn.execute(
+        "UPDATE sessions SET updated_at = datetime('now', '-10 days')",
+        [],
+    )
+    .expect("age sessions");
+
+    let cutoff_ms = chrono::Utc::now().timestamp_millis() - 7 * 86_400_000;
+    assert!(store.close_idle_sessions(cutoff_ms).is_err());
+    let session = store.get_session(&idle.id).expect("get").expect("session");
+    assert!(session.closed_at_ms.is_none());
+}
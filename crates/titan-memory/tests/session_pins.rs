@@ -0,0 +1,75 @@
+use tempfile::tempdir;
+use titan_memory::MemoryStore;
+
+#[test]
+fn pins_survive_compaction_and_lead_the_session_context() {
+    let tmp = tempdir().expect("tempdir");
+    let store = MemoryStore::open(&tmp.path().join("titan.db")).expect("open store");
+    let session = store
+        .get_or_create_active_session("discord", "u1")
+        .expect("session");
+    let other = store.create_session("discord", "u2", None).expect("other");
+    for i in 0..5 {
+        store
+            .add_session_message(&session.id, "user", &format!("message {i}"), false)
+            .expect("message");
+    }
+    let first = store
+        .list_session_messages(&session.id, 10)
+        .expect("messages")
+        .into_iter()
+        .find(|row| row.content == "message 0")
+        .expect("first message");
+
+    let message_pin = store
+        .pin_session_item(&session.id, &format!("#{}", first.id), "u1")
+        .expect("pin message");
+    assert_eq!(message_pin.message_id, Some(first.id));
+    assert_eq!(message_pin.content, "message 0");
+    let fact_pin = store
+        .pin_session_item(&session.id, "deploys go through staging", "u1")
+        .expect("pin fact");
+    assert_eq!(fact_pin.message_id, None);
+    let foreign = store
+        .pin_session_item(&other.id, &first.id.to_string(), "u2")
+        .expect("pin in other session");
+    assert_eq!(
+        foreign.message_id, None,
+        "another session's message id is pinned as text"
+    );
+    assert!(store.pin_session_item(&session.id, "  ", "u1").is_err());
+
+    assert_eq!(
+        store.compact_session(&session.id, None).expect("compact"),
+        3
+    );
+    let context = store.session_context(&session.id, 20).expect("context");
+    assert_eq!(
+        context
+            .pins
+            .iter()
+            .map(|pin| pin.content.as_str())
+            .collect::<Vec<_>>(),
+        vec!["message 0", "deploys go through staging"]
+    );
+    assert_eq!(
+        context
+            .messages
+            .iter()
+            .map(|row| row.role.as_str())
+            .collect::<Vec<_>>(),
+        vec!["summary", "user", "user"]
+    );
+
+    assert!(
+        !store
+            .unpin_session_item(&other.id, fact_pin.id)
+            .expect("wrong session")
+    );
+    assert!(
+        store
+            .unpin_session_item(&session.id, fact_pin.id)
+            .expect("unpin")
+    );
+    assert_eq!(store.list_session_pins(&session.id).expect("pins").len(), 1);
+}
@@ -0,0 +1,106 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tempfile::tempdir;
+use titan_core::{Goal, GoalStatus};
+use titan_memory::{MemoryStore, RiskMode};
+
+#[test]
+fn snapshot_replays_messages_goals_approvals_and_risk_mode_at_a_timestamp() {
+    let tmp = tempdir().expect("tempdir");
+    let db = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db).expect("open store");
+    let session = store
+        .get_or_create_active_session("discord", "u1")
+        .expect("session");
+    store
+        .add_session_message(&session.id, "user", "delete the old logs", false)
+        .expect("before");
+    let open = Goal::new("delete old logs");
+    store
+        .create_goal_for_session(&open, Some(&session.id))
+        .expect("open goal");
+    let done = Goal::new("list logs");
+    store
+        .create_goal_for_session(&done, Some(&session.id))
+        .expect("done goal");
+    store
+        .update_goal_status(&done.id, GoalStatus::Completed)
+        .expect("complete");
+    let approval = store
+        .create_approval_request_for_goal(
+            Some(&open.id),
+            "run_command",
+            "exec",
+            "rm logs/*.old",
+            Some("u1"),
+            600_000,
+        )
+        .expect("approval");
+    store.get_runtime_risk_state().expect("risk state");
+    store.enable_yolo("u1", 30, None).expect("yolo");
+
+    // Everything so far happened two minutes ago; what follows happens after `as_of`.
+    let raw = rusqlite::Connection::open(&db).expect("second connection");
+    let two_minutes_ago = "datetime('now', '-120 seconds')";
+    for table in ["session_messages", "goals", "approval_requests"] {
+        raw.execute(
+            &format!("UPDATE {table} SET created_at = {two_minutes_ago}"),
+            [],
+        )
+        .expect("backdate");
+    }
+    raw.execute(
+        &format!("UPDATE goals SET updated_at = {two_minutes_ago}"),
+        [],
+    )
+    .expect("backdate goal updates");
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock")
+        .as_millis() as i64;
+    raw.execute(
+        "UPDATE risk_state_history SET created_at_ms = ?1",
+        [now_ms - 120_000],
+    )
+    .expect("backdate risk change");
+    store
+        .add_session_message(&session.id, "assistant", "done", false)
+        .expect("after");
+    store
+        .resolve_approval_request(&approval.id, true, Some("u1"), None, None)
+        .expect("approve");
+    store
+        .update_goal_status(&open.id, GoalStatus::Completed)
+        .expect("complete later");
+    store.set_risk_mode_secure("u1", None).expect("secure");
+
+    let as_of = now_ms - 60_000;
+    let snapshot = store
+        .session_snapshot(&session.id, as_of)
+        .expect("snapshot");
+    assert_eq!(snapshot.risk_mode, RiskMode::Yolo);
+    let contents: Vec<_> = snapshot
+        .messages
+        .iter()
+        .map(|m| m.content.as_str())
+        .collect();
+    assert_eq!(contents, vec!["delete the old logs"]);
+    let goals: Vec<_> = snapshot.open_goals.iter().map(|g| g.id.as_str()).collect();
+    assert_eq!(goals, vec![open.id.as_str()]);
+    assert_eq!(snapshot.open_approvals.len(), 1);
+    assert_eq!(snapshot.open_approvals[0].id, approval.id);
+
+    let current = store
+        .session_snapshot(&session.id, now_ms + 5_000)
+        .expect("current");
+    assert_eq!(current.risk_mode, RiskMode::Secure);
+    assert_eq!(current.messages.len(), 2);
+    assert!(current.open_goals.is_empty());
+    assert!(current.open_approvals.is_empty());
+
+    let before = store
+        .session_snapshot(&session.id, now_ms - 600_000)
+        .expect("before session");
+    assert_eq!(before.risk_mode, RiskMode::Secure);
+    assert!(before.messages.is_empty() && before.open_goals.is_empty());
+}
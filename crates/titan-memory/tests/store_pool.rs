@@ -0,0 +1,42 @@
+use std::thread;
+
+use tempfile::tempdir;
+use titan_core::Goal;
+use titan_memory::{MemoryStore, MemoryStorePool};
+
+#[test]
+fn pool_reuses_stores_and_switches_the_database_to_wal() {
+    let tmp = tempdir().expect("tempdir");
+    let db_path = tmp.path().join("titan.db");
+    let pool = MemoryStorePool::with_max_idle(&db_path, 2);
+    assert_eq!(pool.idle_count(), 0);
+    {
+        let store = pool.get().expect("first store");
+        store.create_goal(&Goal::new("pooled")).expect("goal");
+        assert_eq!(store.size_stats().expect("stats").journal_mode, "wal");
+    }
+    assert_eq!(pool.idle_count(), 1);
+    let reused = pool.get().expect("reused");
+    assert_eq!(pool.idle_count(), 0);
+    let second = pool.get().expect("second");
+    let third = pool.get().expect("third");
+    drop((reused, second, third));
+    assert_eq!(pool.idle_count(), 2, "idle stores are capped at max_idle");
+
+    let handles: Vec<_> = (0..4)
+        .map(|n| {
+            let pool = pool.clone();
+            thread::spawn(move || {
+                let store = pool.get().expect("store");
+                store
+                    .create_goal(&Goal::new(format!("goal {n}")))
+                    .expect("goal");
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().expect("thread");
+    }
+    let store = MemoryStore::open(&db_path).expect("plain open");
+    assert_eq!(store.list_goals(10).expect("goals").len(), 5);
+}
@@ -0,0 +1,64 @@
+use tempfile::tempdir;
+use titan_common::StorageConfig;
+use titan_memory::MemoryStore;
+use titan_memory::blob::{BLOB_REF_PREFIX, truncate_output};
+
+#[test]
+fn oversized_outputs_are_truncated_and_offloaded_outputs_rehydrate() {
+    MemoryStore::configure_blob_storage(&StorageConfig {
+        blob_threshold_bytes: 1024,
+        max_output_bytes: Some(4096),
+        ..StorageConfig::default()
+    });
+    let tmp = tempdir().expect("tempdir");
+    let db_path = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db_path).expect("store");
+
+    let small = store
+        .record_tool_run(None, "run_command", "success", "ok")
+        .expect("small run");
+    let medium_output = "m".repeat(2048);
+    let medium = store
+        .record_tool_run(None, "run_command", "success", &medium_output)
+        .expect("medium run");
+    let huge = store
+        .record_tool_run(None, "run_command", "success", &"h".repeat(1 << 20))
+        .expect("huge run");
+
+    let stored: String = rusqlite::Connection::open(&db_path)
+        .expect("conn")
+        .query_row(
+            "SELECT output FROM tool_runs WHERE id = ?1",
+            [&medium.id],
+            |row| row.get(0),
+        )
+        .expect("row");
+    assert!(
+        stored.starts_with(BLOB_REF_PREFIX),
+        "pointer, not the output"
+    );
+
+    let get = |id: &str| store.get_tool_run_output(id).expect("output");
+    assert_eq!(get(&small.id).as_deref(), Some("ok"));
+    assert_eq!(get(&medium.id), Some(medium_output));
+    let huge_output = get(&huge.id).expect("huge output");
+    assert!(huge_output.starts_with(&"h".repeat(4096)));
+    assert!(huge_output.ends_with("[output truncated: kept 4096 of 1048576 bytes]"));
+    assert_eq!(get("missing"), None);
+
+    // A row that cannot be read is an error, not a missing run.
+    rusqlite::Connection::open(&db_path)
+        .expect("conn")
+        .execute(
+            "UPDATE tool_runs SET output = X'00' WHERE id = ?1",
+            [&small.id],
+        )
+        .expect("corrupt row");
+    assert!(store.get_tool_run_output(&small.id).is_err());
+
+    assert_eq!(
+        truncate_output("héllo", 2),
+        "h\n[output truncated: kept 1 of 6 bytes]"
+    );
+    assert_eq!(truncate_output("hello", 5), "hello");
+}
@@ -0,0 +1,93 @@
+use tempfile::tempdir;
+use titan_core::{Goal, TraceEvent};
+use titan_memory::MemoryStore;
+
+fn record_run(conn: &rusqlite::Connection, goal_id: &str, candidate: &str, steps: &[(&str, &str)]) {
+    let plan_id = format!("plan-{goal_id}");
+    conn.execute(
+        "INSERT INTO run_plans (id, goal_id, intent, selected_candidate_id, selected_score)
+         VALUES (?1, ?2, 'Inspect', ?3, 0.8)",
+        [&plan_id, goal_id, candidate],
+    )
+    .expect("plan");
+    for (index, (tool_name, output)) in steps.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO run_steps (id, goal_id, plan_id, step_id, tool_name, permission, status, output)
+             VALUES (?1, ?2, ?3, ?4, ?5, 'read', 'executed', ?6)",
+            [
+                &format!("{goal_id}-{index}"),
+                goal_id,
+                &plan_id,
+                &format!("s{}", index + 1),
+                tool_name,
+                output,
+            ],
+        )
+        .expect("step");
+    }
+}
+
+#[test]
+fn diff_aligns_recurring_runs_and_flags_what_changed() {
+    let tmp = tempdir().expect("tempdir");
+    let db = tmp.path().join("titan.db");
+    let store = MemoryStore::open(&db).expect("open store");
+    let passing = Goal::new("nightly report");
+    let failing = Goal::new("nightly report");
+    store.create_goal(&passing).expect("passing goal");
+    store.create_goal(&failing).expect("failing goal");
+
+    let conn = rusqlite::Connection::open(&db).expect("second connection");
+    record_run(
+        &conn,
+        &passing.id,
+        "direct",
+        &[("list_dir", "a.md"), ("read_file", "# report")],
+    );
+    record_run(
+        &conn,
+        &failing.id,
+        "search_first",
+        &[
+            ("list_dir", "a.md"),
+            ("search_text", "no matches"),
+            ("read_file", "missing file"),
+        ],
+    );
+    conn.execute(
+        "UPDATE goals SET status = 'failed' WHERE id = ?1",
+        [&failing.id],
+    )
+    .expect("fail goal");
+    store
+        .add_trace_event(&TraceEvent::new(
+            failing.id.clone(),
+            "execution_failed",
+            "read_file: not found",
+        ))
+        .expect("trace");
+
+    let diff = store
+        .diff_goal_runs(&passing.id, &failing.id)
+        .expect("diff")
+        .expect("both goals exist");
+    assert!(
+        diff.same_family(),
+        "same description and intent, even without a job tying them"
+    );
+    assert!(diff.plan_changed());
+    assert!(diff.outcome_changed());
+    let kinds: Vec<&str> = diff.steps.iter().map(|row| row.kind()).collect();
+    assert_eq!(kinds, vec!["same", "only_b", "changed"]);
+    assert_eq!(
+        diff.event_count_changes(),
+        vec![("execution_failed".to_string(), 0, 1)]
+    );
+
+    assert!(
+        store
+            .diff_goal_runs(&passing.id, "missing")
+            .expect("diff")
+            .is_none()
+    );
+}
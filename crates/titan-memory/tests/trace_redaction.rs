@@ -0,0 +1,81 @@
+use tempfile::tempdir;
+use titan_core::{Goal, TraceEvent, TraceVerbosity};
+use titan_memory::{MemoryStore, TraceView};
+
+#[test]
+fn redacted_view_masks_payloads_but_keeps_types_and_times() {
+    let tmp = tempdir().expect("tempdir");
+    let store = MemoryStore::open(&tmp.path().join("titan.db")).expect("open store");
+    store
+        .set_trace_level("default", TraceVerbosity::Debug)
+        .expect("debug traces");
+    let goal = Goal::new("read secrets file");
+    store.create_goal(&goal).expect("goal");
+    for (event_type, detail) in [
+        ("goal_submitted", "read ./secrets.env"),
+        ("execution_started", "Executing selected plan p1"),
+        ("tool_executed", "API_KEY=hunter2"),
+        ("connector_tool_result", r#"{"token":"hunter2"}"#),
+    ] {
+        store
+            .add_trace_event(&TraceEvent::new(&goal.id, event_type, detail))
+            .expect("trace");
+    }
+
+    let full = store
+        .trace_view_for_goal(&goal.id, TraceView::Full)
+        .expect("full view");
+    assert!(full.iter().all(|trace| !trace.redacted));
+    assert!(full.iter().any(|trace| trace.detail.contains("hunter2")));
+
+    let redacted = store
+        .trace_view_for_goal(&goal.id, TraceView::Redacted)
+        .expect("redacted view");
+    assert_eq!(
+        redacted
+            .iter()
+            .map(|trace| trace.event_type.as_str())
+            .collect::<Vec<_>>(),
+        vec![
+            "goal_submitted",
+            "execution_started",
+            "tool_executed",
+            "connector_tool_result"
+        ]
+    );
+    assert!(redacted.iter().all(|trace| !trace.created_at.is_empty()));
+    assert!(
+        redacted
+            .iter()
+            .all(|trace| !trace.detail.contains("hunter2"))
+    );
+    assert_eq!(redacted[1].detail, "Executing selected plan p1");
+    assert_eq!(redacted[2].detail, "[redacted 15 bytes]");
+    assert!(redacted[2].redacted);
+
+    let recent = store
+        .recent_trace_view(10, TraceView::Redacted)
+        .expect("recent");
+    assert!(recent.iter().all(|trace| !trace.detail.contains("hunter2")));
+    assert_eq!(
+        store
+            .search_trace_view("hunter2", 10, TraceView::Full)
+            .expect("full search")
+            .len(),
+        2
+    );
+    assert!(
+        store
+            .search_trace_view("hunter2", 10, TraceView::Redacted)
+            .expect("redacted search")
+            .is_empty(),
+        "redacted search must not match masked details"
+    );
+    assert_eq!(
+        store
+            .search_trace_view("tool_exec", 10, TraceView::Redacted)
+            .expect("type search")
+            .len(),
+        1
+    );
+}
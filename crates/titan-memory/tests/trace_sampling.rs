@@ -0,0 +1,73 @@
+use tempfile::tempdir;
+use titan_core::{Goal, TraceEvent, TraceVerbosity};
+use titan_memory::MemoryStore;
+
+fn count(store: &MemoryStore, goal_id: &str, event_type: &str) -> usize {
+    store
+        .get_traces(goal_id)
+        .expect("traces")
+        .into_iter()
+        .filter(|trace| trace.event_type == event_type)
+        .count()
+}
+
+#[test]
+fn verbosity_resolves_goal_then_channel_then_default_and_keeps_protected_events() {
+    let tmp = tempdir().expect("tempdir");
+    let store = MemoryStore::open(&tmp.path().join("titan.db")).expect("open store");
+    let session = store
+        .get_or_create_active_session("discord", "u1")
+        .expect("session");
+    let goal = Goal::new("noisy autonomous run");
+    store
+        .create_goal_for_session(&goal, Some(&session.id))
+        .expect("goal");
+
+    assert_eq!(
+        store.trace_verbosity_for_goal(&goal.id).expect("level"),
+        TraceVerbosity::Normal
+    );
+    store
+        .set_trace_level("default", TraceVerbosity::Debug)
+        .expect("default");
+    store
+        .set_trace_level("channel:discord", TraceVerbosity::Minimal)
+        .expect("channel");
+    assert_eq!(
+        store.trace_verbosity_for_goal(&goal.id).expect("level"),
+        TraceVerbosity::Minimal
+    );
+
+    for i in 0..5 {
+        store
+            .add_trace_event(&TraceEvent::new(&goal.id, "tool_executed", format!("{i}")))
+            .expect("detail");
+        store
+            .add_trace_event(&TraceEvent::new(
+                &goal.id,
+                "approval_required",
+                format!("{i}"),
+            ))
+            .expect("protected");
+    }
+    store
+        .add_trace_event(&TraceEvent::new(&goal.id, "execution_failed", "boom"))
+        .expect("lifecycle");
+    assert_eq!(count(&store, &goal.id, "tool_executed"), 0);
+    assert_eq!(count(&store, &goal.id, "approval_required"), 5);
+    assert_eq!(count(&store, &goal.id, "execution_failed"), 1);
+
+    store
+        .set_trace_level(&format!("goal:{}", goal.id), TraceVerbosity::Normal)
+        .expect("goal override");
+    for i in 5..100 {
+        store
+            .add_trace_event(&TraceEvent::new(&goal.id, "tool_executed", format!("{i}")))
+            .expect("detail");
+    }
+    // Seen 6..=100 at Normal: 15 more inside the burst, then every 10th (30..=100).
+    assert_eq!(count(&store, &goal.id, "tool_executed"), 15 + 8);
+    let stats = store.trace_sampling_stats(&goal.id).expect("stats");
+    assert!(stats.contains(&("tool_executed".to_string(), 100, 23)));
+    assert!(stats.contains(&("approval_required".to_string(), 5, 5)));
+}
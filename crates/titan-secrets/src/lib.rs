@@ -11,6 +11,7 @@ use rand::RngCore;
 use serde::{Deserialize, Serialize};
 
 const DEFAULT_SECRETS_FILE: &str = ".titan/secrets.enc";
+const SEALED_MAGIC: &[u8; 8] = b"TITANSL1";
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SecretsStatus {
@@ -150,6 +151,48 @@ impl SecretsStore {
     }
 }
 
+/// Encrypts `plaintext` under a key derived from `passphrase` with a fresh salt. The output
+/// is self-describing: magic, salt, nonce, then ciphertext.
+pub fn seal_with_passphrase(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+    if passphrase.trim().is_empty() {
+        bail!("passphrase cannot be empty");
+    }
+    let mut salt = [0_u8; 16];
+    rand::rng().fill_bytes(&mut salt);
+    let mut nonce = [0_u8; 24];
+    rand::rng().fill_bytes(&mut nonce);
+    let key = derive_key(passphrase, &salt)?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), plaintext)
+        .map_err(|_| anyhow!("failed to encrypt payload"))?;
+    let mut sealed = Vec::with_capacity(SEALED_MAGIC.len() + 40 + ciphertext.len());
+    sealed.extend_from_slice(SEALED_MAGIC);
+    sealed.extend_from_slice(&salt);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// Reverses [`seal_with_passphrase`].
+pub fn open_with_passphrase(passphrase: &str, sealed: &[u8]) -> Result<Vec<u8>> {
+    let Some(rest) = sealed.strip_prefix(SEALED_MAGIC.as_slice()) else {
+        bail!("payload is not sealed by titan");
+    };
+    if rest.len() < 40 {
+        bail!("sealed payload is truncated");
+    }
+    let (salt, rest) = rest.split_at(16);
+    let (nonce, ciphertext) = rest.split_at(24);
+    let mut salt_bytes = [0_u8; 16];
+    salt_bytes.copy_from_slice(salt);
+    let key = derive_key(passphrase, &salt_bytes)?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    cipher
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("failed to decrypt sealed payload (wrong passphrase?)"))
+}
+
 fn validate_key_id(key_id: &str) -> Result<()> {
     if key_id.trim().is_empty() {
         bail!("key_id cannot be empty");
@@ -1,5 +1,5 @@
 use tempfile::tempdir;
-use titan_secrets::{SecretsStatus, SecretsStore};
+use titan_secrets::{SecretsStatus, SecretsStore, open_with_passphrase, seal_with_passphrase};
 
 #[test]
 fn roundtrip_secrets_store_encrypts_payload() {
@@ -45,3 +45,15 @@ fn wrong_passphrase_fails_to_unlock() {
     let err = fresh.unlock("wrong-pass").expect_err("must fail");
     assert!(err.to_string().contains("failed to decrypt"));
 }
+
+#[test]
+fn sealed_payloads_open_only_with_their_passphrase() {
+    let sealed = seal_with_passphrase("backup-pass", b"bundle bytes").expect("seal");
+    assert!(!sealed.windows(6).any(|w| w == b"bundle"));
+    assert_eq!(
+        open_with_passphrase("backup-pass", &sealed).expect("open"),
+        b"bundle bytes"
+    );
+    assert!(open_with_passphrase("other-pass", &sealed).is_err());
+    assert!(open_with_passphrase("backup-pass", b"plain").is_err());
+}
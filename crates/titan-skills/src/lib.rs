@@ -8,6 +8,9 @@ use base64::Engine;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use titan_common::path_guard::canonicalize_existing_dir;
+use titan_memory::approval_payload::{
+    ApprovalPayload, SkillExecGrantPayload, decode_approval_payload, encode_approval_payload,
+};
 use wait_timeout::ChildExt;
 use wasmparser::{Validator, WasmFeatures};
 
@@ -18,6 +21,43 @@ pub struct SkillManifest {
     pub entrypoint: String,
     #[serde(default)]
     pub capabilities: SkillCapabilities,
+    #[serde(default)]
+    pub limits: WasmLimits,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasmLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fuel: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_bytes: Option<u64>,
+}
+
+impl WasmLimits {
+    pub fn is_unset(&self) -> bool {
+        self.fuel.is_none() && self.max_memory_bytes.is_none()
+    }
+
+    pub fn from_config(config: &titan_common::SkillsConfig) -> Self {
+        Self {
+            fuel: config.wasm_fuel,
+            max_memory_bytes: config.wasm_max_memory_bytes,
+        }
+    }
+
+    // A manifest may tighten the runtime limits but never loosen them.
+    pub fn effective(self, manifest: WasmLimits) -> Self {
+        fn tightest(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        }
+        Self {
+            fuel: tightest(self.fuel, manifest.fuel),
+            max_memory_bytes: tightest(self.max_memory_bytes, manifest.max_memory_bytes),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
@@ -41,6 +81,7 @@ pub struct SkillPackage {
 pub struct SkillRuntime {
     pub workspace_root: PathBuf,
     pub timeout_ms: u64,
+    pub limits: WasmLimits,
 }
 
 #[derive(Debug, Clone)]
@@ -129,9 +170,19 @@ impl SkillRuntime {
         // - only whitelisted env vars passed through
         // - workspace directory mounted explicitly
         // - process timeout enforced by TITAN runtime
+        let limits = self.limits.effective(package.manifest.limits);
         let mut cmd = Command::new("wasmtime");
-        cmd.arg("run")
-            .arg(format!("--dir={}", workspace_root.display()))
+        cmd.arg("run");
+        if let Some(fuel) = limits.fuel {
+            cmd.arg("-W").arg(format!("fuel={fuel}"));
+        }
+        if let Some(bytes) = limits.max_memory_bytes {
+            cmd.arg("-W")
+                .arg(format!("max-memory-size={bytes}"))
+                .arg("-W")
+                .arg("trap-on-grow-failure=y");
+        }
+        cmd.arg(format!("--dir={}", workspace_root.display()))
             .arg(&package.wasm_path);
         for arg in args {
             cmd.arg(arg);
@@ -174,11 +225,11 @@ impl SkillRuntime {
             merged.push_str(&String::from_utf8_lossy(&output.stderr));
         }
 
-        let status = if output.status.success() {
-            "success".to_string()
-        } else {
-            format!("failed({})", output.status.code().unwrap_or(-1))
-        };
+        let status = wasm_run_status(
+            output.status.success(),
+            output.status.code(),
+            &String::from_utf8_lossy(&output.stderr),
+        );
         Ok(SkillRunResult {
             status,
             output: merged,
@@ -186,6 +237,28 @@ impl SkillRuntime {
     }
 }
 
+impl SkillRunResult {
+    pub fn limit_exceeded(&self) -> bool {
+        self.status.starts_with("limit_exceeded")
+    }
+}
+
+// Maps a wasmtime exit to a run status. Fuel and memory traps become `limit_exceeded(..)`
+// so they are not confused with a skill's own failures.
+pub fn wasm_run_status(success: bool, code: Option<i32>, stderr: &str) -> String {
+    if success {
+        return "success".to_string();
+    }
+    let stderr = stderr.to_ascii_lowercase();
+    if stderr.contains("all fuel consumed") {
+        return "limit_exceeded(fuel)".to_string();
+    }
+    if stderr.contains("growing memory") || stderr.contains("memory growth") {
+        return "limit_exceeded(memory)".to_string();
+    }
+    format!("failed({})", code.unwrap_or(-1))
+}
+
 pub fn validate_wasm_binary(path: &Path) -> Result<()> {
     let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
     let mut validator = Validator::new_with_features(WasmFeatures::default());
@@ -561,6 +634,9 @@ pub struct SkillManifestV1 {
     pub permissions: SkillManifestPermissionsV1,
     #[serde(default)]
     pub signature: Option<SkillSignatureV1>,
+    /// Tightens `skills.wasm_fuel` / `skills.wasm_max_memory_bytes` for wasm entrypoints.
+    #[serde(default, skip_serializing_if = "WasmLimits::is_unset")]
+    pub limits: WasmLimits,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -600,6 +676,11 @@ pub trait SkillRegistryAdapter {
         resolved: &ResolvedSkillVersion,
         staging_dir: &Path,
     ) -> Result<PathBuf>;
+    // Cheap fingerprint of the current index (commit id, HTTP ETag) so caches can skip
+    // a full fetch. `None` means the adapter cannot tell without fetching.
+    fn index_etag(&self) -> Result<Option<String>> {
+        Ok(None)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -708,6 +789,22 @@ impl SkillRegistryAdapter for GitRegistryAdapter {
         copy_dir_recursive(&src, staging_dir)?;
         Ok(staging_dir.to_path_buf())
     }
+
+    fn index_etag(&self) -> Result<Option<String>> {
+        let output = std::process::Command::new("git")
+            .arg("ls-remote")
+            .arg(&self.repo_url)
+            .arg("HEAD")
+            .output()
+            .with_context(|| "failed to run git ls-remote for skill registry")?;
+        if !output.status.success() {
+            bail!("git ls-remote failed for {}", self.repo_url);
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .next()
+            .map(str::to_string))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -750,6 +847,188 @@ impl SkillRegistryAdapter for HttpRegistryAdapter {
         }
         bail!("http registry bundle unpack is not implemented for non-file URLs in v1")
     }
+
+    fn index_etag(&self) -> Result<Option<String>> {
+        let response = reqwest::blocking::Client::new()
+            .head(&self.index_url)
+            .send()
+            .with_context(|| format!("failed to HEAD {}", self.index_url))?
+            .error_for_status()
+            .with_context(|| format!("registry returned error for {}", self.index_url))?;
+        Ok(response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string))
+    }
+}
+
+// `local`, `local:<path>`, `git:<url>`, or `http:<url>`. Remote sources are wrapped in the
+// on-disk registry cache.
+pub fn registry_adapter_from_source(
+    workspace_root: &Path,
+    source: &str,
+) -> Result<Box<dyn SkillRegistryAdapter>> {
+    let trimmed = source.trim();
+    if trimmed.eq_ignore_ascii_case("local") {
+        return Ok(Box::new(LocalRegistryAdapter::new(default_registry_root())));
+    }
+    if let Some(path) = trimmed.strip_prefix("local:") {
+        return Ok(Box::new(LocalRegistryAdapter::new(PathBuf::from(path))));
+    }
+    let remote: Box<dyn SkillRegistryAdapter> = if let Some(url) = trimmed.strip_prefix("git:") {
+        Box::new(GitRegistryAdapter::new(url))
+    } else if let Some(url) = trimmed.strip_prefix("http:") {
+        Box::new(HttpRegistryAdapter::new(url))
+    } else {
+        bail!("unsupported skill registry source: {source}");
+    };
+    Ok(Box::new(CachedRegistryAdapter::new(
+        remote,
+        &registry_cache_root(workspace_root),
+        trimmed,
+    )))
+}
+
+// Builds the configured registries; with none configured this is the single `local` registry.
+pub fn configured_registries(
+    registries: &[titan_common::SkillRegistryConfig],
+    workspace_root: &Path,
+) -> Result<Vec<NamedRegistry>> {
+    if registries.is_empty() {
+        return Ok(vec![NamedRegistry {
+            name: "local".to_string(),
+            priority: 0,
+            adapter: registry_adapter_from_source(workspace_root, "local")?,
+        }]);
+    }
+    registries
+        .iter()
+        .map(|registry| {
+            Ok(NamedRegistry {
+                name: registry.name.clone(),
+                priority: registry.priority,
+                adapter: registry_adapter_from_source(workspace_root, &registry.source)?,
+            })
+        })
+        .collect()
+}
+
+pub fn registry_cache_root(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(".titan/registry/cache")
+}
+
+// Wraps a remote adapter with an on-disk mirror of its index and bundles. Bundles are
+// served from the cache only when their hash still matches the index, and a cached index
+// is used when the registry is unreachable so previously-seen versions install offline.
+pub struct CachedRegistryAdapter {
+    inner: Box<dyn SkillRegistryAdapter>,
+    cache_dir: PathBuf,
+}
+
+impl CachedRegistryAdapter {
+    pub fn new(inner: Box<dyn SkillRegistryAdapter>, cache_root: &Path, source: &str) -> Self {
+        let key = format!("{:x}", Sha256::digest(source.trim().as_bytes()));
+        Self {
+            inner,
+            cache_dir: cache_root.join(&key[..16]),
+        }
+    }
+
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    fn cached_index(&self) -> Result<Option<(RegistryIndexV1, String)>> {
+        let index_path = self.cache_dir.join("index.json");
+        if !index_path.exists() {
+            return Ok(None);
+        }
+        let raw = fs::read_to_string(&index_path)?;
+        let etag = fs::read_to_string(self.cache_dir.join("index.etag")).unwrap_or_default();
+        let index = serde_json::from_str(&raw)
+            .with_context(|| format!("failed to parse {}", index_path.display()))?;
+        Ok(Some((index, etag.trim().to_string())))
+    }
+
+    fn store_index(&self, index: &RegistryIndexV1, etag: Option<String>) -> Result<()> {
+        fs::create_dir_all(&self.cache_dir)?;
+        let raw = serde_json::to_string_pretty(index)?;
+        let etag = etag.unwrap_or_else(|| format!("{:x}", Sha256::digest(raw.as_bytes())));
+        let tmp_path = self.cache_dir.join("index.json.tmp");
+        fs::write(&tmp_path, raw)?;
+        fs::rename(&tmp_path, self.cache_dir.join("index.json"))?;
+        fs::write(self.cache_dir.join("index.etag"), etag)?;
+        Ok(())
+    }
+
+    fn cached_bundle_path(&self, resolved: &ResolvedSkillVersion) -> PathBuf {
+        self.cache_dir
+            .join("bundles")
+            .join(format!("{}-{}", resolved.slug, resolved.version))
+    }
+}
+
+impl SkillRegistryAdapter for CachedRegistryAdapter {
+    fn id(&self) -> &str {
+        self.inner.id()
+    }
+
+    fn fetch_index(&self) -> Result<RegistryIndexV1> {
+        let cached = self.cached_index()?;
+        let etag = match self.inner.index_etag() {
+            Ok(etag) => etag,
+            Err(err) => {
+                return match cached {
+                    Some((index, _)) => Ok(index),
+                    None => Err(err),
+                };
+            }
+        };
+        if let (Some(etag), Some((index, cached_etag))) = (etag.as_deref(), cached.as_ref())
+            && etag == cached_etag
+        {
+            return Ok(index.clone());
+        }
+        match self.inner.fetch_index() {
+            Ok(index) => {
+                self.store_index(&index, etag)?;
+                Ok(index)
+            }
+            Err(err) => match cached {
+                Some((index, _)) => Ok(index),
+                None => Err(err),
+            },
+        }
+    }
+
+    fn fetch_bundle_to_dir(
+        &self,
+        resolved: &ResolvedSkillVersion,
+        staging_dir: &Path,
+    ) -> Result<PathBuf> {
+        let expected = resolved.sha256.to_ascii_lowercase();
+        let cached = self.cached_bundle_path(resolved);
+        if cached.is_dir() && compute_bundle_hash(&cached)? == expected {
+            if staging_dir.exists() {
+                fs::remove_dir_all(staging_dir)?;
+            }
+            copy_dir_recursive(&cached, staging_dir)?;
+            return Ok(staging_dir.to_path_buf());
+        }
+        let fetched = self.inner.fetch_bundle_to_dir(resolved, staging_dir)?;
+        if compute_bundle_hash(&fetched)? == expected {
+            if cached.exists() {
+                fs::remove_dir_all(&cached)?;
+            }
+            copy_dir_recursive(&fetched, &cached)?;
+        }
+        Ok(fetched)
+    }
+
+    fn index_etag(&self) -> Result<Option<String>> {
+        self.inner.index_etag()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -785,9 +1064,11 @@ pub struct StagedSkillInstall {
     pub staging_dir: PathBuf,
     pub target_dir: PathBuf,
     pub lock_path: PathBuf,
+    pub previous_source: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct SkillApprovalPayload {
     pub slug: String,
     pub version: String,
@@ -800,6 +1081,8 @@ pub struct SkillApprovalPayload {
     pub staging_dir: PathBuf,
     pub target_dir: PathBuf,
     pub lock_path: PathBuf,
+    #[serde(default)]
+    pub previous_source: Option<String>,
 }
 
 pub fn skills_lock_path(workspace_root: &Path) -> PathBuf {
@@ -960,9 +1243,81 @@ pub fn stage_install_v1_with_trust_root(
         staging_dir: materialized_dir,
         target_dir,
         lock_path,
+        previous_source: None,
     })
 }
 
+pub struct NamedRegistry {
+    pub name: String,
+    pub priority: i32,
+    pub adapter: Box<dyn SkillRegistryAdapter>,
+}
+
+// Walks registries in ascending priority order. A skill already in skills.lock stays on the
+// registry it was installed from; moving it elsewhere needs `allow_source_switch`, and the
+// staged install then records the old source so the approval shows the switch.
+pub fn stage_install_from_registries(
+    registries: &[NamedRegistry],
+    workspace_root: &Path,
+    slug: &str,
+    requested_version: Option<&str>,
+    force: bool,
+    allow_source_switch: bool,
+    trust_root: &Path,
+) -> Result<StagedSkillInstall> {
+    let mut ordered: Vec<&NamedRegistry> = registries.iter().collect();
+    ordered.sort_by_key(|registry| registry.priority);
+    let provides = |registry: &NamedRegistry| -> bool {
+        registry
+            .adapter
+            .fetch_index()
+            .ok()
+            .and_then(|index| resolve_skill_version(&index, slug, requested_version).ok())
+            .is_some()
+    };
+    let lock = load_skills_lock_v1(&skills_lock_path(workspace_root))?;
+    let pinned = lock
+        .entries
+        .iter()
+        .find(|entry| entry.slug == slug)
+        .map(|entry| entry.source.clone());
+    let (selected, previous_source) = match pinned {
+        Some(pinned) => match ordered
+            .iter()
+            .find(|registry| registry.name == pinned && provides(registry))
+        {
+            Some(registry) => (*registry, None),
+            None => {
+                let Some(other) = ordered.iter().find(|registry| provides(registry)) else {
+                    bail!("skill not found in any registry: {slug}");
+                };
+                if !allow_source_switch {
+                    bail!(
+                        "{slug} is pinned to registry {pinned} in skills.lock; {} provides it but switching sources requires --allow-source-switch",
+                        other.name
+                    );
+                }
+                (*other, Some(pinned))
+            }
+        },
+        None => match ordered.iter().find(|registry| provides(registry)) {
+            Some(registry) => (*registry, None),
+            None => bail!("skill not found in any registry: {slug}"),
+        },
+    };
+    let mut staged = stage_install_v1_with_trust_root(
+        selected.adapter.as_ref(),
+        workspace_root,
+        slug,
+        requested_version,
+        force || previous_source.is_some(),
+        trust_root,
+    )?;
+    staged.source = selected.name.clone();
+    staged.previous_source = previous_source;
+    Ok(staged)
+}
+
 pub fn deny_unsigned_risky_install(staged: &StagedSkillInstall) -> Result<()> {
     let unsigned = staged.signature_status != "verified";
     if !unsigned {
@@ -1015,15 +1370,21 @@ pub fn approval_payload_for_stage(stage: &StagedSkillInstall) -> SkillApprovalPa
         staging_dir: stage.staging_dir.clone(),
         target_dir: stage.target_dir.clone(),
         lock_path: stage.lock_path.clone(),
+        previous_source: stage.previous_source.clone(),
     }
 }
 
+impl ApprovalPayload for SkillApprovalPayload {
+    const KIND: &'static str = "skill_install";
+    const VERSION: u32 = 1;
+}
+
 pub fn serialize_approval_payload(payload: &SkillApprovalPayload) -> Result<String> {
-    Ok(serde_json::to_string(payload)?)
+    encode_approval_payload(payload)
 }
 
 pub fn deserialize_approval_payload(input: &str) -> Result<SkillApprovalPayload> {
-    Ok(serde_json::from_str(input)?)
+    decode_approval_payload(input)
 }
 
 pub fn finalize_install_from_payload(payload: &SkillApprovalPayload) -> Result<InstalledSkillV1> {
@@ -1230,6 +1591,345 @@ fn canonical_json(value: &serde_json::Value) -> String {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct SkillScaffoldOptions {
+    pub slug: String,
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub entrypoint_type: SkillEntrypointType,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScaffoldedSkill {
+    pub bundle_dir: PathBuf,
+    pub manifest: SkillManifestV1,
+    pub hash: String,
+}
+
+// Smallest valid module (magic + version) so `validate_wasm_binary` accepts the stub.
+const WASM_STUB_MODULE: [u8; 8] = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+
+pub fn scaffold_skill_v1(
+    target_dir: &Path,
+    options: &SkillScaffoldOptions,
+) -> Result<ScaffoldedSkill> {
+    validate_skill_slug(&options.slug)?;
+    if target_dir.exists() && fs::read_dir(target_dir)?.next().is_some() {
+        bail!("scaffold target is not empty: {}", target_dir.display());
+    }
+    fs::create_dir_all(target_dir)?;
+    let (entrypoint, permissions) = match options.entrypoint_type {
+        SkillEntrypointType::Prompt => (
+            "tool:list_dir {{input}}".to_string(),
+            SkillManifestPermissionsV1 {
+                scopes: vec![SkillScope::Read],
+                allowed_paths: vec![".".to_string()],
+                allowed_hosts: Vec::new(),
+            },
+        ),
+        SkillEntrypointType::Http => (
+            "https://api.example.com/run?q={{input}}".to_string(),
+            SkillManifestPermissionsV1 {
+                scopes: vec![SkillScope::Net],
+                allowed_paths: Vec::new(),
+                allowed_hosts: vec!["api.example.com".to_string()],
+            },
+        ),
+        SkillEntrypointType::Wasm => {
+            fs::write(target_dir.join("skill.wasm"), WASM_STUB_MODULE)?;
+            (
+                "skill.wasm".to_string(),
+                SkillManifestPermissionsV1 {
+                    scopes: vec![SkillScope::Read],
+                    allowed_paths: vec![".".to_string()],
+                    allowed_hosts: Vec::new(),
+                },
+            )
+        }
+        SkillEntrypointType::ScriptStub => {
+            bail!("scaffolding supports prompt, http, and wasm entrypoints")
+        }
+    };
+    let manifest = SkillManifestV1 {
+        name: options.name.clone(),
+        slug: options.slug.clone(),
+        version: options.version.clone(),
+        description: options.description.clone(),
+        author: None,
+        license: None,
+        entrypoint_type: options.entrypoint_type.clone(),
+        entrypoint,
+        permissions,
+        signature: None,
+        limits: WasmLimits::default(),
+    };
+    fs::write(
+        target_dir.join("skill.toml"),
+        toml::to_string_pretty(&manifest)?,
+    )?;
+    fs::write(
+        target_dir.join("SKILL.md"),
+        format!(
+            "# {}\n\n{}\n\n## Usage\n\n`titan skill run {} --input <text>`\n",
+            manifest.name, manifest.description, manifest.slug
+        ),
+    )?;
+    let hash = compute_bundle_hash(target_dir)?;
+    Ok(ScaffoldedSkill {
+        bundle_dir: target_dir.to_path_buf(),
+        manifest,
+        hash,
+    })
+}
+
+// Signs a bundle in place with an ed25519 secret key (base64, 32 bytes) and returns the
+// signature written into skill.toml.
+pub fn sign_skill_bundle_v1(
+    bundle_dir: &Path,
+    key_id: &str,
+    secret_key_base64: &str,
+) -> Result<SkillSignatureV1> {
+    use ed25519_dalek::Signer;
+
+    let secret = base64::prelude::BASE64_STANDARD
+        .decode(secret_key_base64.trim())
+        .with_context(|| "invalid base64 signing key")?;
+    let signing_key = ed25519_dalek::SigningKey::from_bytes(
+        &secret
+            .as_slice()
+            .try_into()
+            .map_err(|_| anyhow!("invalid signing key length"))?,
+    );
+    let manifest_path = bundle_dir.join("skill.toml");
+    let mut manifest = load_skill_manifest_v1(&manifest_path)?;
+    manifest.signature = None;
+    let payload = signature_payload(&manifest, &compute_signature_hash_v1(bundle_dir)?)?;
+    let signature = SkillSignatureV1 {
+        public_key_id: key_id.to_string(),
+        ed25519_sig_base64: base64::prelude::BASE64_STANDARD
+            .encode(signing_key.sign(payload.as_bytes()).to_bytes()),
+    };
+    manifest.signature = Some(signature.clone());
+    fs::write(&manifest_path, toml::to_string_pretty(&manifest)?)?;
+    Ok(signature)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PublishOptions {
+    pub copy_bundle: bool,
+    pub overwrite: bool,
+}
+
+impl Default for PublishOptions {
+    fn default() -> Self {
+        Self {
+            copy_bundle: true,
+            overwrite: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PublishedSkill {
+    pub slug: String,
+    pub entry: RegistrySkillVersionV1,
+    pub latest: String,
+    pub replaced: bool,
+}
+
+// Publishes a bundle into a local registry. With `copy_bundle` the bundle lands in
+// `<registry_root>/bundles/<slug>-<version>`; otherwise the index points at the bundle in place.
+pub fn publish_to_local_registry(
+    registry_root: &Path,
+    bundle_dir: &Path,
+    options: &PublishOptions,
+) -> Result<PublishedSkill> {
+    let manifest = load_skill_manifest_v1(&bundle_dir.join("skill.toml"))?;
+    validate_publishable_bundle(bundle_dir, &manifest)?;
+    let hash = compute_bundle_hash(bundle_dir)?;
+    fs::create_dir_all(registry_root)?;
+    let index_path = registry_root.join("index.json");
+    let mut index = if index_path.exists() {
+        let raw = fs::read_to_string(&index_path)?;
+        serde_json::from_str::<RegistryIndexV1>(&raw)
+            .with_context(|| format!("failed to parse {}", index_path.display()))?
+    } else {
+        RegistryIndexV1 { skills: Vec::new() }
+    };
+    let existing = index
+        .skills
+        .iter()
+        .find(|skill| skill.slug == manifest.slug)
+        .and_then(|skill| {
+            skill
+                .versions
+                .iter()
+                .find(|item| item.version == manifest.version)
+        });
+    if let Some(existing) = existing
+        && existing.sha256 != hash
+        && !options.overwrite
+    {
+        bail!(
+            "{}@{} is already published with a different sha256 (use overwrite to replace)",
+            manifest.slug,
+            manifest.version
+        );
+    }
+    let replaced = existing.is_some();
+
+    let download_url = if options.copy_bundle {
+        let download_url = format!("bundles/{}-{}", manifest.slug, manifest.version);
+        let target = registry_root.join(&download_url);
+        if target.exists() {
+            fs::remove_dir_all(&target)?;
+        }
+        copy_dir_recursive(bundle_dir, &target)?;
+        download_url
+    } else {
+        fs::canonicalize(bundle_dir)?.to_string_lossy().to_string()
+    };
+    let entry = RegistrySkillVersionV1 {
+        version: manifest.version.clone(),
+        download_url,
+        sha256: hash,
+    };
+    let skill = match index
+        .skills
+        .iter_mut()
+        .position(|skill| skill.slug == manifest.slug)
+    {
+        Some(pos) => &mut index.skills[pos],
+        None => {
+            index.skills.push(RegistrySkillEntryV1 {
+                slug: manifest.slug.clone(),
+                name: manifest.name.clone(),
+                latest: entry.version.clone(),
+                versions: Vec::new(),
+            });
+            index.skills.last_mut().expect("skill entry just pushed")
+        }
+    };
+    skill.name = manifest.name.clone();
+    skill.versions.retain(|item| item.version != entry.version);
+    skill.versions.push(entry.clone());
+    skill
+        .versions
+        .sort_by(|a, b| compare_versions(&a.version, &b.version));
+    skill.latest = skill
+        .versions
+        .last()
+        .map(|item| item.version.clone())
+        .unwrap_or_default();
+    let latest = skill.latest.clone();
+    index.skills.sort_by(|a, b| a.slug.cmp(&b.slug));
+
+    let tmp_path = registry_root.join("index.json.tmp");
+    fs::write(&tmp_path, serde_json::to_string_pretty(&index)?)?;
+    fs::rename(&tmp_path, &index_path)
+        .with_context(|| format!("failed to replace {}", index_path.display()))?;
+    Ok(PublishedSkill {
+        slug: manifest.slug,
+        entry,
+        latest,
+        replaced,
+    })
+}
+
+fn validate_publishable_bundle(bundle_dir: &Path, manifest: &SkillManifestV1) -> Result<()> {
+    validate_skill_slug(&manifest.slug)?;
+    if manifest.version.trim().is_empty() {
+        bail!("skill version is required");
+    }
+    if manifest.entrypoint.trim().is_empty() {
+        bail!("skill entrypoint is required");
+    }
+    if manifest.permissions.scopes.is_empty() {
+        bail!("skill must declare at least one permission scope");
+    }
+    if !bundle_dir.join("SKILL.md").exists() {
+        bail!("missing required SKILL.md for {}", manifest.slug);
+    }
+    let has_net = manifest
+        .permissions
+        .scopes
+        .iter()
+        .any(|scope| matches!(scope, SkillScope::Net));
+    if has_net && manifest.permissions.allowed_hosts.is_empty() {
+        bail!("NET skills must declare allowed_hosts");
+    }
+    if matches!(manifest.entrypoint_type, SkillEntrypointType::Wasm) {
+        validate_wasm_binary(&bundle_dir.join(&manifest.entrypoint))?;
+    }
+    if matches!(manifest.entrypoint_type, SkillEntrypointType::Http) {
+        validate_http_entrypoint(manifest)?;
+    }
+    Ok(())
+}
+
+// An http entrypoint is an https URL template; `{{input}}` may appear anywhere after the
+// host, and the host itself must be one of the manifest's allowed_hosts.
+fn validate_http_entrypoint(manifest: &SkillManifestV1) -> Result<()> {
+    if !manifest
+        .permissions
+        .scopes
+        .iter()
+        .any(|scope| matches!(scope, SkillScope::Net))
+    {
+        bail!("http entrypoints require the NET scope");
+    }
+    let template = manifest.entrypoint.trim();
+    let url = url::Url::parse(&template.replace("{{input}}", "input"))
+        .with_context(|| format!("http entrypoint is not a URL template: {template}"))?;
+    if url.scheme() != "https" {
+        bail!("http entrypoints must use https");
+    }
+    let host = url.host_str().unwrap_or_default();
+    if url::Url::parse(&template.replace("{{input}}", "other"))?.host_str() != Some(host) {
+        bail!("http entrypoint input cannot change the host");
+    }
+    let allowed_hosts = &manifest.permissions.allowed_hosts;
+    if !allowed_hosts.iter().any(|item| item == "*" || item == host) {
+        bail!("http entrypoint host '{host}' is not in allowed_hosts");
+    }
+    Ok(())
+}
+
+// Orders dotted versions numerically where both parts are numbers ("1.10.0" > "1.9.0").
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let mut left = a.split(['.', '-']);
+    let mut right = b.split(['.', '-']);
+    loop {
+        match (left.next(), right.next()) {
+            (None, None) => return std::cmp::Ordering::Equal,
+            (None, Some(_)) => return std::cmp::Ordering::Less,
+            (Some(_), None) => return std::cmp::Ordering::Greater,
+            (Some(x), Some(y)) => {
+                let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(x), Ok(y)) => x.cmp(&y),
+                    _ => x.cmp(y),
+                };
+                if ordering != std::cmp::Ordering::Equal {
+                    return ordering;
+                }
+            }
+        }
+    }
+}
+
+fn validate_skill_slug(slug: &str) -> Result<()> {
+    let valid = !slug.is_empty()
+        && slug
+            .chars()
+            .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-')
+        && !slug.starts_with('-');
+    if !valid {
+        bail!("skill slug must be lowercase letters, digits, and dashes: {slug}");
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub enum SkillRunState {
     Completed,
@@ -1243,13 +1943,105 @@ pub struct SkillRunOutcome {
     pub output: String,
 }
 
+/// A skill run awaiting approval, or a planned `skill_run` step.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SkillRunPayload {
+    pub slug: String,
+    #[serde(default)]
+    pub input: Option<String>,
+}
+
+impl ApprovalPayload for SkillRunPayload {
+    const KIND: &'static str = "skill_run";
+    const VERSION: u32 = 1;
+
+    fn upgrade(version: u32, value: serde_json::Value) -> Result<Self> {
+        // Before versioning: "slug=<slug> input=<input>".
+        let legacy = value
+            .as_str()
+            .filter(|_| version == 0)
+            .ok_or_else(|| anyhow!("skill_run payload version {version} is unknown"))?;
+        let body = legacy
+            .trim()
+            .strip_prefix("slug=")
+            .ok_or_else(|| anyhow!("legacy skill_run payload does not start with slug="))?;
+        let (slug, input) = body.split_once(" input=").unwrap_or((body, ""));
+        Ok(Self {
+            slug: slug.trim().to_string(),
+            input: Some(input.trim())
+                .filter(|value| !value.is_empty())
+                .map(str::to_string),
+        })
+    }
+}
+
+pub fn skill_run_payload(slug: &str, input: Option<&str>) -> Result<String> {
+    encode_approval_payload(&SkillRunPayload {
+        slug: slug.to_string(),
+        input: input.map(str::to_string),
+    })
+}
+
+pub fn parse_skill_run_payload(payload: &str) -> Result<(String, Option<String>)> {
+    let payload: SkillRunPayload = decode_approval_payload(payload)?;
+    if payload.slug.trim().is_empty() {
+        bail!("invalid skill_run payload: missing slug");
+    }
+    Ok((payload.slug, payload.input))
+}
+
+// `limits` are the runtime's wasm limits, usually `WasmLimits::from_config(&cfg.skills)`; a
+// skill's manifest `[limits]` can only tighten them.
 pub fn run_skill_v1(
     store: &titan_memory::MemoryStore,
     workspace_root: &Path,
+    limits: WasmLimits,
     mode: titan_common::AutonomyMode,
     actor_id: &str,
     slug: &str,
     input: Option<&str>,
+) -> Result<SkillRunOutcome> {
+    run_skill_v1_inner(
+        store,
+        workspace_root,
+        limits,
+        Some(mode),
+        actor_id,
+        slug,
+        input,
+    )
+}
+
+// Runs a skill whose `skill_run` approval was already granted; only the EXEC grant gate
+// still applies.
+pub fn run_approved_skill_v1(
+    store: &titan_memory::MemoryStore,
+    workspace_root: &Path,
+    limits: WasmLimits,
+    actor_id: &str,
+    payload: &str,
+) -> Result<SkillRunOutcome> {
+    let (slug, input) = parse_skill_run_payload(payload)?;
+    run_skill_v1_inner(
+        store,
+        workspace_root,
+        limits,
+        None,
+        actor_id,
+        &slug,
+        input.as_deref(),
+    )
+}
+
+fn run_skill_v1_inner(
+    store: &titan_memory::MemoryStore,
+    workspace_root: &Path,
+    limits: WasmLimits,
+    mode: Option<titan_common::AutonomyMode>,
+    actor_id: &str,
+    slug: &str,
+    input: Option<&str>,
 ) -> Result<SkillRunOutcome> {
     let skill = select_installed_skill(workspace_root, slug)?
         .ok_or_else(|| anyhow!("skill not installed: {slug}"))?;
@@ -1264,17 +2056,64 @@ pub fn run_skill_v1(
         ),
     ))?;
 
+    let goal_id = goal.id.clone();
+    let started = std::time::Instant::now();
+    let outcome = execute_skill_run(
+        store,
+        workspace_root,
+        limits,
+        mode,
+        actor_id,
+        &skill,
+        goal,
+        input,
+    );
+    let duration_ms = started.elapsed().as_millis() as i64;
+    let (status, output_bytes) = match &outcome {
+        Ok(run) => match run.state {
+            SkillRunState::Completed => ("completed", run.output.len() as i64),
+            SkillRunState::PendingApproval(_) => ("pending_approval", 0),
+        },
+        Err(_) => ("failed", 0),
+    };
+    store.record_skill_run(slug, Some(&goal_id), status, duration_ms, output_bytes)?;
+    outcome
+}
+
+#[allow(clippy::too_many_arguments)]
+fn execute_skill_run(
+    store: &titan_memory::MemoryStore,
+    workspace_root: &Path,
+    limits: WasmLimits,
+    mode: Option<titan_common::AutonomyMode>,
+    actor_id: &str,
+    skill: &InstalledSkillV1,
+    goal: titan_core::Goal,
+    input: Option<&str>,
+) -> Result<SkillRunOutcome> {
+    let slug = skill.manifest.slug.as_str();
+    if let Some(quarantine) = store.get_skill_quarantine(slug)? {
+        store.update_goal_status(&goal.id, titan_core::GoalStatus::Failed)?;
+        bail!(
+            "skill {slug} is quarantined after {} scope violations: {}",
+            quarantine.violation_count,
+            quarantine.reason
+        );
+    }
     let scopes = &skill.manifest.permissions.scopes;
     for scope in scopes {
         let class = scope.as_capability_class();
-        if titan_tools::PolicyEngine::requires_approval(mode.clone(), class) {
+        if mode
+            .clone()
+            .is_some_and(|mode| titan_tools::PolicyEngine::requires_approval(mode, class))
+        {
             let approval = store.create_approval_request_for_goal(
                 Some(goal.id.as_str()),
                 "skill_run",
                 class.as_str(),
-                &format!("slug={} input={}", slug, input.unwrap_or_default()),
+                &skill_run_payload(slug, input)?,
                 Some(actor_id),
-                300_000,
+                titan_tools::PolicyEngine::approval_ttl_ms(class.as_str()),
             )?;
             store.add_trace_event(&titan_core::TraceEvent::new(
                 goal.id.clone(),
@@ -1296,9 +2135,11 @@ pub fn run_skill_v1(
             Some(goal.id.as_str()),
             "skill_exec_grant",
             "exec",
-            &skill.manifest.slug,
+            &encode_approval_payload(&SkillExecGrantPayload {
+                slug: skill.manifest.slug.clone(),
+            })?,
             Some(actor_id),
-            300_000,
+            titan_tools::PolicyEngine::approval_ttl_ms("exec"),
         )?;
         store.add_trace_event(&titan_core::TraceEvent::new(
             goal.id.clone(),
@@ -1312,7 +2153,12 @@ pub fn run_skill_v1(
         });
     }
 
-    let (tool_name, tool_input) = resolve_prompt_tool_call(&skill, input)?;
+    if skill.manifest.entrypoint_type == SkillEntrypointType::Wasm {
+        let limits = limits.effective(skill.manifest.limits);
+        return execute_wasm_skill_run(store, workspace_root, limits, skill, goal, input);
+    }
+
+    let (tool_name, tool_input) = resolve_prompt_tool_call(skill, input)?;
     enforce_allowed_paths(
         &skill.manifest,
         workspace_root,
@@ -1324,9 +2170,30 @@ pub fn run_skill_v1(
     let tool = registry
         .get(&tool_name)
         .ok_or_else(|| anyhow!("skill references unknown tool: {tool_name}"))?;
-    let exec_ctx =
+    attest_skill_tool_call(store, skill, &goal.id, tool)?;
+    let mut exec_ctx =
         titan_tools::ToolExecutionContext::default_for_workspace(workspace_root.to_path_buf());
-    let result = titan_tools::ToolExecutor::execute(tool, tool_input.as_deref(), &exec_ctx)?;
+    if skill.manifest.entrypoint_type == SkillEntrypointType::Http {
+        exec_ctx.timeout_ms = HTTP_SKILL_TIMEOUT_MS;
+        exec_ctx.max_output_bytes = HTTP_SKILL_MAX_RESPONSE_BYTES;
+    }
+    let result = titan_tools::ToolExecutor::execute(tool, tool_input.as_deref(), &exec_ctx)
+        .inspect_err(|err| {
+            if let Some(blocked) = err.downcast_ref::<titan_tools::EgressBlocked>() {
+                let _ = store.add_trace_event(&titan_core::TraceEvent::new(
+                    goal.id.clone(),
+                    "egress_blocked",
+                    blocked.trace_detail(&tool_name),
+                ));
+            }
+            if let Some(blocked) = err.downcast_ref::<titan_tools::SecretWriteBlocked>() {
+                let _ = store.add_trace_event(&titan_core::TraceEvent::new(
+                    goal.id.clone(),
+                    "secret_write_blocked",
+                    blocked.trace_detail(&tool_name),
+                ));
+            }
+        })?;
     store.record_tool_run(None, &tool_name, &result.status, &result.output)?;
     store.update_goal_status(&goal.id, titan_core::GoalStatus::Completed)?;
     store.add_trace_event(&titan_core::TraceEvent::new(
@@ -1347,6 +2214,392 @@ pub fn run_skill_v1(
     })
 }
 
+pub const HTTP_SKILL_TIMEOUT_MS: u64 = 5_000;
+pub const HTTP_SKILL_MAX_RESPONSE_BYTES: usize = 32 * 1024;
+
+// Defaults for in-process wasm runs when neither config nor the manifest sets a limit.
+pub const V1_WASM_FUEL: u64 = 1_000_000_000;
+pub const V1_WASM_MAX_MEMORY_BYTES: usize = 256 * 1024 * 1024;
+pub const V1_WASM_TIMEOUT_MS: u64 = 10_000;
+const V1_WASM_OUTPUT_BYTES: usize = 1024 * 1024;
+
+fn execute_wasm_skill_run(
+    store: &titan_memory::MemoryStore,
+    workspace_root: &Path,
+    limits: WasmLimits,
+    skill: &InstalledSkillV1,
+    goal: titan_core::Goal,
+    input: Option<&str>,
+) -> Result<SkillRunOutcome> {
+    let result = run_wasm_entrypoint(skill, workspace_root, limits, input)?;
+    let run = store.record_tool_run(None, "skill_wasm", &result.status, &result.output)?;
+    store.add_trace_event(&titan_core::TraceEvent::new(
+        goal.id.clone(),
+        "skill_wasm_result",
+        format!("run_id={} status={}", run.id, result.status),
+    ))?;
+    store.set_skill_last_run_goal(&skill.manifest.slug, &goal.id)?;
+    if result.status != "success" {
+        store.update_goal_status(&goal.id, titan_core::GoalStatus::Failed)?;
+        bail!(
+            "wasm skill {} finished with status {}",
+            skill.manifest.slug,
+            result.status
+        );
+    }
+    store.update_goal_status(&goal.id, titan_core::GoalStatus::Completed)?;
+    store.add_episodic_memory(
+        &goal.id,
+        &format!("Skill {} executed via wasm", skill.manifest.slug),
+        "skill",
+    )?;
+    Ok(SkillRunOutcome {
+        state: SkillRunState::Completed,
+        goal_id: goal.id,
+        output: result.output,
+    })
+}
+
+struct WasmSkillState {
+    wasi: wasmtime_wasi::preview1::WasiP1Ctx,
+    limits: wasmtime::StoreLimits,
+}
+
+// Runs a v1 wasm entrypoint in-process. The module sees only the manifest's
+// `allowed_paths` as WASI preopens (read-only unless the skill holds WRITE), gets the
+// input as argv[1], and has no network or environment. Fuel and memory from `limits` (the
+// V1_WASM_* defaults where unset) and a wall-clock epoch deadline bound the run; stdout and
+// stderr are captured for the tool run record.
+fn run_wasm_entrypoint(
+    skill: &InstalledSkillV1,
+    workspace_root: &Path,
+    limits: WasmLimits,
+    input: Option<&str>,
+) -> Result<SkillRunResult> {
+    use wasmtime_wasi::pipe::MemoryOutputPipe;
+    use wasmtime_wasi::{DirPerms, FilePerms, I32Exit, WasiCtxBuilder};
+
+    let wasm_path = titan_common::path_guard::resolve_existing_path_within(
+        &skill.root,
+        &skill.manifest.entrypoint,
+    )?;
+    let root = canonicalize_existing_dir(workspace_root)?;
+    let writable = skill
+        .manifest
+        .permissions
+        .scopes
+        .iter()
+        .any(|scope| matches!(scope, SkillScope::Write));
+    let (dir_perms, file_perms) = if writable {
+        (DirPerms::all(), FilePerms::all())
+    } else {
+        (DirPerms::READ, FilePerms::READ)
+    };
+
+    let stdout = MemoryOutputPipe::new(V1_WASM_OUTPUT_BYTES);
+    let stderr = MemoryOutputPipe::new(V1_WASM_OUTPUT_BYTES);
+    let mut wasi = WasiCtxBuilder::new();
+    wasi.arg(&skill.manifest.slug)
+        .stdout(stdout.clone())
+        .stderr(stderr.clone());
+    if let Some(input) = input {
+        wasi.arg(input);
+    }
+    for allowed_path in &skill.manifest.permissions.allowed_paths {
+        let normalized = allowed_path.trim().trim_start_matches("./");
+        let guest = if normalized.is_empty() || normalized == "." {
+            "."
+        } else {
+            normalized
+        };
+        let host = titan_common::path_guard::resolve_existing_path_within(&root, guest)
+            .with_context(|| format!("allowed path is not a workspace directory: {guest}"))?;
+        if !host.is_dir() {
+            bail!("allowed path is not a directory: {guest}");
+        }
+        wasi.preopened_dir(&host, guest, dir_perms, file_perms)?;
+    }
+
+    let mut config = wasmtime::Config::new();
+    config.consume_fuel(true).epoch_interruption(true);
+    let engine = wasmtime::Engine::new(&config)?;
+    let module = wasmtime::Module::from_file(&engine, &wasm_path)
+        .with_context(|| format!("failed to load {}", wasm_path.display()))?;
+    let mut linker = wasmtime::Linker::<WasmSkillState>::new(&engine);
+    wasmtime_wasi::preview1::add_to_linker_sync(&mut linker, |state| &mut state.wasi)?;
+    let mut wasm_store = wasmtime::Store::new(
+        &engine,
+        WasmSkillState {
+            wasi: wasi.build_p1(),
+            limits: wasmtime::StoreLimitsBuilder::new()
+                .memory_size(
+                    limits
+                        .max_memory_bytes
+                        .map_or(V1_WASM_MAX_MEMORY_BYTES, |bytes| {
+                            usize::try_from(bytes).unwrap_or(usize::MAX)
+                        }),
+                )
+                .trap_on_grow_failure(true)
+                .build(),
+        },
+    );
+    wasm_store.limiter(|state| &mut state.limits);
+    wasm_store.set_fuel(limits.fuel.unwrap_or(V1_WASM_FUEL))?;
+    wasm_store.set_epoch_deadline(1);
+
+    // The watchdog bumps the epoch once the deadline passes; dropping `done` releases it
+    // early when the module finishes first.
+    let (done, finished) = std::sync::mpsc::channel::<()>();
+    let watchdog = {
+        let engine = engine.clone();
+        std::thread::spawn(move || {
+            if let Err(std::sync::mpsc::RecvTimeoutError::Timeout) =
+                finished.recv_timeout(Duration::from_millis(V1_WASM_TIMEOUT_MS))
+            {
+                engine.increment_epoch();
+            }
+        })
+    };
+    let outcome = linker
+        .instantiate(&mut wasm_store, &module)
+        .and_then(|instance| instance.get_typed_func::<(), ()>(&mut wasm_store, "_start"))
+        .and_then(|start| start.call(&mut wasm_store, ()));
+    drop(done);
+    let _ = watchdog.join();
+
+    let status = match &outcome {
+        Ok(()) => "success".to_string(),
+        Err(err) => match (
+            err.downcast_ref::<I32Exit>(),
+            err.downcast_ref::<wasmtime::Trap>(),
+        ) {
+            (Some(I32Exit(0)), _) => "success".to_string(),
+            (Some(I32Exit(code)), _) => format!("failed({code})"),
+            (_, Some(wasmtime::Trap::OutOfFuel)) => "limit_exceeded(fuel)".to_string(),
+            (_, Some(wasmtime::Trap::Interrupt)) => "limit_exceeded(timeout)".to_string(),
+            _ => wasm_run_status(false, None, &format!("{err:?}")),
+        },
+    };
+    let mut output = String::from_utf8_lossy(&stdout.contents()).into_owned();
+    let mut errors = String::from_utf8_lossy(&stderr.contents()).into_owned();
+    if let Err(err) = &outcome
+        && status.starts_with("failed")
+        && err.downcast_ref::<I32Exit>().is_none()
+    {
+        errors.push_str(&format!("{err:#}"));
+    }
+    if !errors.is_empty() {
+        output.push_str("\n--- stderr ---\n");
+        output.push_str(&errors);
+    }
+    Ok(SkillRunResult { status, output })
+}
+
+pub const SKILL_QUARANTINE_THRESHOLD: i64 = 3;
+
+// Runtime capability monitor: every tool call a skill makes is traced, and a call whose
+// capability class the manifest does not declare is blocked and counted. Reaching
+// SKILL_QUARANTINE_THRESHOLD uncleared violations quarantines the skill.
+fn attest_skill_tool_call(
+    store: &titan_memory::MemoryStore,
+    skill: &InstalledSkillV1,
+    goal_id: &str,
+    tool: &titan_tools::ToolDescriptor,
+) -> Result<()> {
+    let slug = skill.manifest.slug.as_str();
+    let declared = skill
+        .manifest
+        .permissions
+        .scopes
+        .iter()
+        .any(|scope| scope.as_capability_class() == tool.class);
+    store.add_trace_event(&titan_core::TraceEvent::new(
+        goal_id.to_string(),
+        "skill_tool_call",
+        format!(
+            "slug={} tool={} capability={} declared={}",
+            slug,
+            tool.name,
+            tool.class.as_str(),
+            declared
+        ),
+    ))?;
+    if declared {
+        return Ok(());
+    }
+    let violations =
+        store.record_skill_scope_violation(slug, Some(goal_id), &tool.name, tool.class.as_str())?;
+    store.add_trace_event(&titan_core::TraceEvent::new(
+        goal_id.to_string(),
+        "scope_violation",
+        format!(
+            "slug={} tool={} capability={} violations={}",
+            slug,
+            tool.name,
+            tool.class.as_str(),
+            violations
+        ),
+    ))?;
+    if violations >= SKILL_QUARANTINE_THRESHOLD {
+        let reason = format!(
+            "undeclared {} capability via {}",
+            tool.class.as_str(),
+            tool.name
+        );
+        store.quarantine_skill(slug, &reason, violations)?;
+        store.add_trace_event(&titan_core::TraceEvent::new(
+            goal_id.to_string(),
+            "skill_quarantined",
+            format!("slug={} violations={}", slug, violations),
+        ))?;
+    }
+    store.update_goal_status(goal_id, titan_core::GoalStatus::Failed)?;
+    bail!(
+        "skill {slug} blocked: tool '{}' needs {} scope which the manifest does not declare",
+        tool.name,
+        tool.class.as_str()
+    )
+}
+
+#[derive(Debug, Clone)]
+pub struct SkillTestReport {
+    pub slug: String,
+    pub version: String,
+    pub tool_name: String,
+    pub tool_input: Option<String>,
+    pub status: String,
+    pub output: String,
+    pub violations: Vec<String>,
+    pub changed_paths: Vec<String>,
+    pub sandbox_root: Option<PathBuf>,
+}
+
+impl SkillTestReport {
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty() && self.status != "failed"
+    }
+}
+
+// Dry-runs an installed skill against a throwaway copy of the workspace. Policy problems
+// are collected as violations instead of aborting so authors see every gap at once.
+pub fn test_skill_v1(
+    workspace_root: &Path,
+    slug: &str,
+    input: Option<&str>,
+    keep_sandbox: bool,
+) -> Result<SkillTestReport> {
+    let skill = select_installed_skill(workspace_root, slug)?
+        .ok_or_else(|| anyhow!("skill not installed: {slug}"))?;
+    let (tool_name, tool_input) = resolve_prompt_tool_call(&skill, input)?;
+    let sandbox = tempfile::Builder::new()
+        .prefix("titan-skill-test-")
+        .tempdir()?;
+    let sandbox_root = canonicalize_existing_dir(sandbox.path())?;
+    copy_workspace_for_test(workspace_root, &sandbox_root)?;
+
+    let mut violations = Vec::new();
+    let registry = titan_tools::ToolRegistry::with_defaults();
+    let tool = registry
+        .get(&tool_name)
+        .ok_or_else(|| anyhow!("skill references unknown tool: {tool_name}"))?;
+    if !skill
+        .manifest
+        .permissions
+        .scopes
+        .iter()
+        .any(|scope| scope.as_capability_class() == tool.class)
+    {
+        violations.push(format!(
+            "tool '{}' needs {} scope which the manifest does not declare",
+            tool_name,
+            tool.class.as_str()
+        ));
+    }
+    if let Err(err) = enforce_allowed_paths(
+        &skill.manifest,
+        &sandbox_root,
+        &tool_name,
+        tool_input.as_deref(),
+    ) {
+        violations.push(err.to_string());
+    }
+    if let Err(err) = enforce_allowed_hosts(&skill.manifest, &tool_name, tool_input.as_deref()) {
+        violations.push(err.to_string());
+    }
+    if tool.class == titan_tools::CapabilityClass::Net
+        && let Some(target) = tool_input.as_deref()
+        && let Err(err) = titan_tools::PolicyEngine::check_egress(target)
+    {
+        violations.push(err.to_string());
+    }
+
+    let (status, output) = if tool.class == titan_tools::CapabilityClass::Net {
+        // Host checks above are the useful signal; never reach the network from a test.
+        ("skipped_net".to_string(), String::new())
+    } else {
+        let exec_ctx =
+            titan_tools::ToolExecutionContext::default_for_workspace(sandbox_root.clone());
+        match titan_tools::ToolExecutor::execute(tool, tool_input.as_deref(), &exec_ctx) {
+            Ok(result) => (result.status, result.output),
+            Err(err) => ("failed".to_string(), err.to_string()),
+        }
+    };
+    let changed_paths = sandbox_changes(workspace_root, &sandbox_root)?;
+    let kept_root = keep_sandbox.then(|| sandbox.keep());
+
+    Ok(SkillTestReport {
+        slug: skill.manifest.slug.clone(),
+        version: skill.manifest.version.clone(),
+        tool_name,
+        tool_input,
+        status,
+        output,
+        violations,
+        changed_paths,
+        sandbox_root: kept_root,
+    })
+}
+
+fn copy_workspace_for_test(workspace_root: &Path, sandbox_root: &Path) -> Result<()> {
+    let walker = walkdir::WalkDir::new(workspace_root)
+        .into_iter()
+        .filter_entry(|entry| {
+            !matches!(
+                entry.file_name().to_str(),
+                Some(".titan" | ".git" | "target")
+            )
+        });
+    for entry in walker {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(workspace_root)?;
+        let target = sandbox_root.join(relative);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else if entry.file_type().is_file() {
+            fs::copy(entry.path(), &target)
+                .with_context(|| format!("failed to copy {}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
+
+fn sandbox_changes(workspace_root: &Path, sandbox_root: &Path) -> Result<Vec<String>> {
+    let mut changed = Vec::new();
+    for entry in walkdir::WalkDir::new(sandbox_root) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(sandbox_root)?;
+        let original = fs::read(workspace_root.join(relative)).ok();
+        if original.as_deref() != Some(fs::read(entry.path())?.as_slice()) {
+            changed.push(relative.display().to_string());
+        }
+    }
+    changed.sort();
+    Ok(changed)
+}
+
 fn select_installed_skill(workspace_root: &Path, slug: &str) -> Result<Option<InstalledSkillV1>> {
     let mut matches = list_installed_skills_v1(workspace_root)?
         .into_iter()
@@ -1384,8 +2637,19 @@ fn resolve_prompt_tool_call(
             };
             Ok((name.to_string(), arg))
         }
-        SkillEntrypointType::Http => bail!("http entrypoint is not implemented in v1"),
-        SkillEntrypointType::Wasm => bail!("wasm entrypoint is not implemented in v1"),
+        SkillEntrypointType::Http => {
+            // The input is percent-encoded so it can only fill a path or query slot; the
+            // resolved URL still goes through allowed_hosts and the http_get egress checks.
+            let encoded: String =
+                url::form_urlencoded::byte_serialize(input.unwrap_or("").as_bytes()).collect();
+            let url = skill
+                .manifest
+                .entrypoint
+                .trim()
+                .replace("{{input}}", &encoded);
+            Ok(("http_get".to_string(), Some(url)))
+        }
+        SkillEntrypointType::Wasm => bail!("wasm entrypoint runs in the wasm runtime, not a tool"),
         SkillEntrypointType::ScriptStub => bail!("script_stub entrypoint is not implemented in v1"),
     }
 }
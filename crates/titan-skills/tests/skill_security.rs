@@ -1,7 +1,7 @@
 use std::fs;
 
 use tempfile::tempdir;
-use titan_skills::{SkillPackage, SkillRuntime};
+use titan_skills::{SkillPackage, SkillRuntime, WasmLimits, wasm_run_status};
 
 #[test]
 fn invalid_wasm_is_rejected() {
@@ -52,9 +52,60 @@ environment = []
     let runtime = SkillRuntime {
         workspace_root: fake_workspace,
         timeout_ms: 1000,
+        limits: WasmLimits::default(),
     };
     let err = runtime
         .run(&pkg, &[])
         .expect_err("workspace file should fail");
     assert!(err.to_string().to_lowercase().contains("workspace root"));
 }
+
+#[test]
+fn manifest_limits_only_tighten_runtime_limits() {
+    let dir = tempdir().expect("tempdir");
+    fs::write(
+        dir.path().join("manifest.toml"),
+        r#"
+name = "limited-skill"
+version = "0.1.0"
+entrypoint = "tiny.wasm"
+
+[limits]
+fuel = 5000
+max_memory_bytes = 1048576000
+"#,
+    )
+    .expect("write manifest");
+    fs::write(dir.path().join("tiny.wasm"), b"\0asm\x01\0\0\0").expect("write wasm header");
+
+    let pkg = SkillPackage::load(dir.path()).expect("package should load");
+    let runtime_limits = WasmLimits {
+        fuel: Some(1_000_000),
+        max_memory_bytes: Some(64 * 1024 * 1024),
+    };
+    let effective = runtime_limits.effective(pkg.manifest.limits);
+    assert_eq!(effective.fuel, Some(5000));
+    assert_eq!(effective.max_memory_bytes, Some(64 * 1024 * 1024));
+    assert_eq!(
+        WasmLimits::default().effective(pkg.manifest.limits).fuel,
+        Some(5000)
+    );
+}
+
+#[test]
+fn limit_traps_are_reported_apart_from_failures() {
+    assert_eq!(
+        wasm_run_status(false, Some(134), "Error: all fuel consumed by WebAssembly"),
+        "limit_exceeded(fuel)"
+    );
+    assert_eq!(
+        wasm_run_status(
+            false,
+            Some(134),
+            "wasm trap: forcing trap when growing memory to 2097152 bytes"
+        ),
+        "limit_exceeded(memory)"
+    );
+    assert_eq!(wasm_run_status(false, Some(1), "panic"), "failed(1)");
+    assert_eq!(wasm_run_status(true, Some(0), ""), "success");
+}
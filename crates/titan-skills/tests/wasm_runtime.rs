@@ -0,0 +1,279 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use tempfile::{TempDir, tempdir};
+use titan_common::AutonomyMode;
+use titan_memory::MemoryStore;
+use titan_skills::{
+    SkillEntrypointType, SkillManifestPermissionsV1, SkillManifestV1, SkillRunState, SkillScope,
+    WasmLimits, compute_bundle_hash, run_skill_v1,
+};
+
+// Echoes argv[1], then appends `note.txt` read through the first preopened directory.
+// Exits with 7 when no directory was preopened.
+const ECHO_NOTE_WAT: &str = r#"
+(module
+  (import "wasi_snapshot_preview1" "args_sizes_get" (func $args_sizes_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "args_get" (func $args_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 1100) "note.txt")
+  (func (export "_start")
+    (drop (call $args_sizes_get (i32.const 0) (i32.const 4)))
+    (drop (call $args_get (i32.const 16) (i32.const 64)))
+    (i32.store (i32.const 1024) (i32.load (i32.const 20)))
+    (i32.store (i32.const 1028)
+      (i32.sub (i32.sub (i32.add (i32.load (i32.const 4)) (i32.const 64)) (i32.load (i32.const 20))) (i32.const 1)))
+    (drop (call $fd_write (i32.const 1) (i32.const 1024) (i32.const 1) (i32.const 1032)))
+    (if (i32.ne (call $path_open (i32.const 3) (i32.const 0) (i32.const 1100) (i32.const 8) (i32.const 0)
+                  (i64.const 2) (i64.const 0) (i32.const 0) (i32.const 1200)) (i32.const 0))
+      (then (call $proc_exit (i32.const 7))))
+    (i32.store (i32.const 1024) (i32.const 2048))
+    (i32.store (i32.const 1028) (i32.const 256))
+    (drop (call $fd_read (i32.load (i32.const 1200)) (i32.const 1024) (i32.const 1) (i32.const 1208)))
+    (i32.store (i32.const 1028) (i32.load (i32.const 1208)))
+    (drop (call $fd_write (i32.const 1) (i32.const 1024) (i32.const 1) (i32.const 1032)))))
+"#;
+
+const SPIN_WAT: &str = r#"
+(module
+  (func (export "_start")
+    (loop $spin (br $spin))))
+"#;
+
+// Counts to 100_000 and exits; well inside the default fuel budget.
+const COUNT_WAT: &str = r#"
+(module
+  (func (export "_start")
+    (local $i i32)
+    (loop $count
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br_if $count (i32.lt_u (local.get $i) (i32.const 100000))))))
+"#;
+
+#[test]
+fn wasm_skill_reads_allowed_paths_and_records_stdout() -> Result<()> {
+    let env = TestEnv::new()?;
+    fs::create_dir_all(env.workspace_root.join("docs"))?;
+    fs::write(env.workspace_root.join("docs/note.txt"), " from docs")?;
+    env.install_wasm_skill("echo-note", ECHO_NOTE_WAT, vec!["docs".to_string()])?;
+    let store = MemoryStore::open(&env.db_path)?;
+
+    let outcome = run_skill_v1(
+        &store,
+        &env.workspace_root,
+        WasmLimits::default(),
+        AutonomyMode::Autonomous,
+        "tester",
+        "echo-note",
+        Some("hello"),
+    )?;
+    assert!(matches!(outcome.state, SkillRunState::Completed));
+    assert_eq!(outcome.output, "hello from docs");
+
+    let traces = store.get_traces(&outcome.goal_id)?;
+    let result = traces
+        .iter()
+        .find(|t| t.event_type == "skill_wasm_result")
+        .expect("wasm result trace");
+    assert!(
+        result.detail.ends_with("status=success"),
+        "{}",
+        result.detail
+    );
+    let run_id = result
+        .detail
+        .strip_prefix("run_id=")
+        .and_then(|rest| rest.split(' ').next())
+        .expect("run id");
+    assert_eq!(
+        store.get_tool_run_output(run_id)?.as_deref(),
+        Some("hello from docs")
+    );
+    Ok(())
+}
+
+#[test]
+fn wasm_skill_without_allowed_paths_gets_no_preopens() -> Result<()> {
+    let env = TestEnv::new()?;
+    fs::create_dir_all(env.workspace_root.join("docs"))?;
+    fs::write(env.workspace_root.join("docs/note.txt"), "secret")?;
+    env.install_wasm_skill("no-fs", ECHO_NOTE_WAT, Vec::new())?;
+    let store = MemoryStore::open(&env.db_path)?;
+
+    let err = run_skill_v1(
+        &store,
+        &env.workspace_root,
+        WasmLimits::default(),
+        AutonomyMode::Autonomous,
+        "tester",
+        "no-fs",
+        Some("hi"),
+    )
+    .expect_err("module cannot open files without a preopen");
+    assert!(err.to_string().contains("failed(7)"), "{err}");
+    let runs = store.list_skill_runs("no-fs", 10)?;
+    assert_eq!(runs[0].status, "failed");
+    Ok(())
+}
+
+#[test]
+fn runaway_wasm_skill_is_stopped_by_fuel() -> Result<()> {
+    let env = TestEnv::new()?;
+    env.install_wasm_skill("spin", SPIN_WAT, Vec::new())?;
+    let store = MemoryStore::open(&env.db_path)?;
+
+    let err = run_skill_v1(
+        &store,
+        &env.workspace_root,
+        WasmLimits::default(),
+        AutonomyMode::Autonomous,
+        "tester",
+        "spin",
+        None,
+    )
+    .expect_err("spinning module must be stopped");
+    assert!(err.to_string().contains("limit_exceeded("), "{err}");
+    Ok(())
+}
+
+#[test]
+fn manifest_and_config_fuel_caps_apply_to_wasm_runs() -> Result<()> {
+    let env = TestEnv::new()?;
+    env.install_wasm_skill("count", COUNT_WAT, Vec::new())?;
+    let store = MemoryStore::open(&env.db_path)?;
+    let run = |slug: &str, limits: WasmLimits| {
+        run_skill_v1(
+            &store,
+            &env.workspace_root,
+            limits,
+            AutonomyMode::Autonomous,
+            "tester",
+            slug,
+            None,
+        )
+    };
+
+    let outcome = run("count", WasmLimits::default())?;
+    assert!(matches!(outcome.state, SkillRunState::Completed));
+    let err = run(
+        "count",
+        WasmLimits {
+            fuel: Some(1_000),
+            max_memory_bytes: None,
+        },
+    )
+    .expect_err("config fuel cap stops the loop");
+    assert!(err.to_string().contains("limit_exceeded(fuel)"), "{err}");
+
+    env.install_wasm_skill_with_limits(
+        "count-capped",
+        COUNT_WAT,
+        Vec::new(),
+        WasmLimits {
+            fuel: Some(1_000),
+            max_memory_bytes: None,
+        },
+    )?;
+    let err =
+        run("count-capped", WasmLimits::default()).expect_err("manifest fuel cap stops the loop");
+    assert!(err.to_string().contains("limit_exceeded(fuel)"), "{err}");
+    // A manifest cannot raise the operator's cap.
+    env.install_wasm_skill_with_limits(
+        "count-loose",
+        COUNT_WAT,
+        Vec::new(),
+        WasmLimits {
+            fuel: Some(u64::MAX),
+            max_memory_bytes: None,
+        },
+    )?;
+    let err = run(
+        "count-loose",
+        WasmLimits {
+            fuel: Some(1_000),
+            max_memory_bytes: None,
+        },
+    )
+    .expect_err("config cap still applies");
+    assert!(err.to_string().contains("limit_exceeded(fuel)"), "{err}");
+    Ok(())
+}
+
+struct TestEnv {
+    _guard: TempDir,
+    workspace_root: PathBuf,
+    db_path: PathBuf,
+}
+
+impl TestEnv {
+    fn new() -> Result<Self> {
+        let guard = tempdir()?;
+        let workspace_root = guard.path().join("workspace");
+        fs::create_dir_all(&workspace_root)?;
+        let db_path = workspace_root.join("titan.db");
+        Ok(Self {
+            _guard: guard,
+            workspace_root,
+            db_path,
+        })
+    }
+
+    fn install_wasm_skill(&self, slug: &str, wat: &str, allowed_paths: Vec<String>) -> Result<()> {
+        self.install_wasm_skill_with_limits(slug, wat, allowed_paths, WasmLimits::default())
+    }
+
+    fn install_wasm_skill_with_limits(
+        &self,
+        slug: &str,
+        wat: &str,
+        allowed_paths: Vec<String>,
+        limits: WasmLimits,
+    ) -> Result<()> {
+        let install_dir = self.workspace_root.join("skills").join(slug).join("1.0.0");
+        fs::create_dir_all(&install_dir)?;
+        fs::write(install_dir.join("SKILL.md"), "# wasm test skill\n")?;
+        fs::write(install_dir.join("skill.wasm"), wat::parse_str(wat)?)?;
+        let manifest = SkillManifestV1 {
+            name: slug.to_string(),
+            slug: slug.to_string(),
+            version: "1.0.0".to_string(),
+            description: "wasm test".to_string(),
+            author: None,
+            license: None,
+            entrypoint_type: SkillEntrypointType::Wasm,
+            entrypoint: "skill.wasm".to_string(),
+            permissions: SkillManifestPermissionsV1 {
+                scopes: vec![SkillScope::Read],
+                allowed_paths: allowed_paths.clone(),
+                allowed_hosts: Vec::new(),
+            },
+            signature: None,
+            limits,
+        };
+        fs::write(
+            install_dir.join("skill.toml"),
+            toml::to_string_pretty(&manifest)?,
+        )?;
+        let hash = compute_bundle_hash(&install_dir)?;
+        let store = MemoryStore::open(&self.db_path)?;
+        store.upsert_installed_skill(&titan_memory::InstalledSkillRecord {
+            slug: slug.to_string(),
+            name: slug.to_string(),
+            version: "1.0.0".to_string(),
+            description: "wasm test".to_string(),
+            source: "test".to_string(),
+            hash,
+            signature_status: "unsigned".to_string(),
+            scopes: "READ".to_string(),
+            allowed_paths: allowed_paths.join(","),
+            allowed_hosts: String::new(),
+            last_run_goal_id: None,
+        })?;
+        Ok(())
+    }
+}
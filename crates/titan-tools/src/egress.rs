@@ -0,0 +1,335 @@
+use std::fmt;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use titan_common::EgressConfig;
+use url::Url;
+
+/// Why a network tool was refused; callers downcast to it to record `egress_blocked` traces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EgressBlocked {
+    pub host: String,
+    pub reason: String,
+}
+
+impl EgressBlocked {
+    /// Detail of the `egress_blocked` trace for a call to `tool_name`.
+    pub fn trace_detail(&self, tool_name: &str) -> String {
+        format!("tool={tool_name} host={} reason={}", self.host, self.reason)
+    }
+}
+
+impl fmt::Display for EgressBlocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "egress to {} blocked: {}", self.host, self.reason)
+    }
+}
+
+impl std::error::Error for EgressBlocked {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum EgressRule {
+    Any,
+    Host(String),
+    /// `*.example.com`: subdomains only, not the apex.
+    Suffix(String),
+    Cidr(IpAddr, u8),
+}
+
+impl EgressRule {
+    fn parse(raw: &str) -> Result<Self> {
+        let rule = raw.trim().to_lowercase();
+        if rule == "*" {
+            return Ok(Self::Any);
+        }
+        if let Some((addr, bits)) = rule.split_once('/') {
+            let addr: IpAddr = addr
+                .parse()
+                .map_err(|_| anyhow!("invalid egress CIDR '{raw}'"))?;
+            let max = if addr.is_ipv4() { 32 } else { 128 };
+            let bits: u8 = bits
+                .parse()
+                .ok()
+                .filter(|bits| *bits <= max)
+                .ok_or_else(|| anyhow!("invalid egress CIDR prefix '{raw}'"))?;
+            return Ok(Self::Cidr(addr, bits));
+        }
+        if let Ok(addr) = rule.parse::<IpAddr>() {
+            return Ok(Self::Cidr(addr, if addr.is_ipv4() { 32 } else { 128 }));
+        }
+        let (suffix, host) = match rule.strip_prefix("*.") {
+            Some(host) => (true, host),
+            None => (false, rule.as_str()),
+        };
+        if host.is_empty()
+            || host.contains('*')
+            || host.contains('/')
+            || host.contains(':')
+            || host.starts_with('.')
+        {
+            return Err(anyhow!("invalid egress rule '{raw}'"));
+        }
+        Ok(if suffix {
+            Self::Suffix(host.to_string())
+        } else {
+            Self::Host(host.to_string())
+        })
+    }
+
+    fn matches(&self, host: &str, addrs: &[IpAddr]) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Host(name) => host == name,
+            Self::Suffix(name) => host
+                .strip_suffix(name.as_str())
+                .is_some_and(|rest| rest.ends_with('.')),
+            Self::Cidr(network, bits) => addrs.iter().any(|addr| in_cidr(*addr, *network, *bits)),
+        }
+    }
+}
+
+fn in_cidr(addr: IpAddr, network: IpAddr, bits: u8) -> bool {
+    let (addr, network, width) = match (addr, network) {
+        (IpAddr::V4(a), IpAddr::V4(n)) => (u32::from(a) as u128, u32::from(n) as u128, 32),
+        (IpAddr::V6(a), IpAddr::V6(n)) => (u128::from(a), u128::from(n), 128),
+        (IpAddr::V6(a), IpAddr::V4(n)) => match a.to_ipv4_mapped() {
+            Some(a) => (u32::from(a) as u128, u32::from(n) as u128, 32),
+            None => return false,
+        },
+        (IpAddr::V4(_), IpAddr::V6(_)) => return false,
+    };
+    if bits == 0 {
+        return true;
+    }
+    let shift = width - u32::from(bits);
+    addr >> shift == network >> shift
+}
+
+/// `[egress]` parsed once: deny rules win, and a non-empty allow list blocks everything it
+/// does not match. CIDR rules are checked against every address the host resolves to.
+#[derive(Debug, Clone, Default)]
+pub struct EgressPolicy {
+    allow: Vec<EgressRule>,
+    deny: Vec<EgressRule>,
+    allow_private: Vec<EgressRule>,
+}
+
+impl EgressPolicy {
+    pub fn from_config(config: &EgressConfig) -> Result<Self> {
+        let parse = |rules: &[String]| {
+            rules
+                .iter()
+                .map(|rule| EgressRule::parse(rule))
+                .collect::<Result<Vec<_>>>()
+        };
+        let allow_private = parse(&config.allow_private)?;
+        if allow_private
+            .iter()
+            .any(|rule| !matches!(rule, EgressRule::Cidr(..)))
+        {
+            return Err(anyhow!(
+                "egress.allow_private takes IP addresses or CIDR ranges"
+            ));
+        }
+        Ok(Self {
+            allow: parse(&config.allow)?,
+            deny: parse(&config.deny)?,
+            allow_private,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// Resolves `url`'s host once, checks the policy and every address against private
+    /// ranges, and returns the address the request must use.
+    pub fn resolve(&self, url: &Url) -> Result<SocketAddr> {
+        let host = url
+            .host_str()
+            .ok_or_else(|| anyhow!("URL must include a host"))?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_lowercase();
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| anyhow!("URL must include a port"))?;
+        // Name rules can refuse the host before it is looked up at all.
+        if let Some(rule) = self.deny.iter().find(|rule| rule.matches(&host, &[])) {
+            return Err(anyhow::Error::new(EgressBlocked {
+                host,
+                reason: format!("matches deny rule {}", rule_label(rule)),
+            }));
+        }
+        let addrs: Vec<IpAddr> = match host.parse::<IpAddr>() {
+            Ok(addr) => vec![addr],
+            Err(_) => (host.as_str(), port)
+                .to_socket_addrs()
+                .with_context(|| format!("failed to resolve {host}"))?
+                .map(|addr| addr.ip())
+                .collect(),
+        };
+        if addrs.is_empty() {
+            bail!("{host} did not resolve to any address");
+        }
+        self.check_resolved(&host, &addrs)?;
+        // Refuse the host if any answer is private: a rebinding server can hand out a public
+        // address first and a private one on the next lookup.
+        if let Some(addr) = addrs.iter().find(|addr| !self.reachable(**addr)) {
+            return Err(anyhow::Error::new(EgressBlocked {
+                host: host.clone(),
+                reason: format!("resolves to private address {addr}"),
+            }));
+        }
+        Ok(SocketAddr::new(addrs[0], port))
+    }
+
+    /// Checks the host of `target`, which may be a URL or a bare host name.
+    pub fn check(&self, target: &str) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let (host, port) = match Url::parse(target) {
+            Ok(url) if url.host_str().is_some() => (
+                url.host_str().unwrap_or_default().to_string(),
+                url.port_or_known_default().unwrap_or(443),
+            ),
+            _ => (target.trim().to_string(), 443),
+        };
+        let host = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_lowercase();
+        let addrs = self.addresses(&host, port);
+        self.check_resolved(&host, &addrs)
+    }
+
+    fn check_resolved(&self, host: &str, addrs: &[IpAddr]) -> Result<()> {
+        let blocked = |reason: String| {
+            anyhow::Error::new(EgressBlocked {
+                host: host.to_string(),
+                reason,
+            })
+        };
+        if let Some(rule) = self.deny.iter().find(|rule| rule.matches(host, addrs)) {
+            return Err(blocked(format!("matches deny rule {}", rule_label(rule))));
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|rule| rule.matches(host, addrs)) {
+            return Err(blocked("not in egress allow list".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Whether `addr` is public, or a private address listed in `allow_private`.
+    fn reachable(&self, addr: IpAddr) -> bool {
+        !is_private(addr)
+            || self
+                .allow_private
+                .iter()
+                .any(|rule| rule.matches("", &[addr]))
+    }
+
+    // Names are only resolved when a CIDR rule needs addresses to compare against.
+    fn addresses(&self, host: &str, port: u16) -> Vec<IpAddr> {
+        if let Ok(addr) = host.parse::<IpAddr>() {
+            return vec![addr];
+        }
+        let needs_lookup = self
+            .allow
+            .iter()
+            .chain(&self.deny)
+            .any(|rule| matches!(rule, EgressRule::Cidr(..)));
+        if !needs_lookup {
+            return Vec::new();
+        }
+        (host, port)
+            .to_socket_addrs()
+            .map(|addrs| addrs.map(|addr| addr.ip()).collect())
+            .unwrap_or_default()
+    }
+}
+
+fn is_private(addr: IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || (a == 100 && (64..128).contains(&b))
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_private(IpAddr::V4(v4)),
+            None => {
+                v6.is_loopback()
+                    || v6.is_unspecified()
+                    || v6.is_unique_local()
+                    || v6.is_unicast_link_local()
+            }
+        },
+    }
+}
+
+fn rule_label(rule: &EgressRule) -> String {
+    match rule {
+        EgressRule::Any => "*".to_string(),
+        EgressRule::Host(host) => host.clone(),
+        EgressRule::Suffix(host) => format!("*.{host}"),
+        EgressRule::Cidr(addr, bits) => format!("{addr}/{bits}"),
+    }
+}
+
+static EGRESS_POLICY: RwLock<Option<EgressPolicy>> = RwLock::new(None);
+
+pub(crate) fn install(config: &EgressConfig) -> Result<()> {
+    let policy = EgressPolicy::from_config(config)?;
+    let mut guard = EGRESS_POLICY.write().unwrap_or_else(|e| e.into_inner());
+    *guard = Some(policy);
+    Ok(())
+}
+
+// Processes that never load a config have no egress rules.
+pub(crate) fn check(target: &str) -> Result<()> {
+    let guard = EGRESS_POLICY.read().unwrap_or_else(|e| e.into_inner());
+    match guard.as_ref() {
+        Some(policy) => policy.check(target),
+        None => Ok(()),
+    }
+}
+
+/// The address a non-HTTP connection to `url`'s host must use, checked like [`pinned_client`].
+pub fn pinned_addr(url: &Url) -> Result<SocketAddr> {
+    EGRESS_POLICY
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_default()
+        .resolve(url)
+}
+
+/// A blocking client for `url` whose host was resolved once, checked against `[egress]` and
+/// private ranges, and pinned to that address. Redirects are followed only on the same host,
+/// so they stay on the pinned address too.
+pub fn pinned_client(url: &Url, timeout: Duration) -> Result<reqwest::blocking::Client> {
+    let addr = pinned_addr(url)?;
+    let host = url.host_str().unwrap_or_default().to_string();
+    let pinned_host = host.clone();
+    let redirects = reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() >= 5 || attempt.url().host_str() != Some(pinned_host.as_str()) {
+            attempt.stop()
+        } else {
+            attempt.follow()
+        }
+    });
+    let mut builder = reqwest::blocking::Client::builder()
+        .timeout(timeout)
+        .redirect(redirects);
+    if url.domain().is_some() {
+        builder = builder.resolve(&host, addr);
+    }
+    Ok(builder.build()?)
+}
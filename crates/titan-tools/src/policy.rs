@@ -0,0 +1,639 @@
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use titan_common::{ApprovalsConfig, AutonomyMode, TitanConfig};
+
+use crate::{CapabilityClass, ToolRegistry, ToolRiskMode};
+
+pub const POLICY_FILE_VERSION: u32 = 1;
+const MAX_QUORUM: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyCapability {
+    Read,
+    Write,
+    Exec,
+    Net,
+}
+
+impl PolicyCapability {
+    fn matches(self, class: CapabilityClass) -> bool {
+        matches!(
+            (self, class),
+            (Self::Read, CapabilityClass::Read)
+                | (Self::Write, CapabilityClass::Write)
+                | (Self::Exec, CapabilityClass::Exec)
+                | (Self::Net, CapabilityClass::Net)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyMode {
+    Supervised,
+    Collaborative,
+    Autonomous,
+}
+
+impl PolicyMode {
+    fn matches(self, mode: &AutonomyMode) -> bool {
+        matches!(
+            (self, mode),
+            (Self::Supervised, AutonomyMode::Supervised)
+                | (Self::Collaborative, AutonomyMode::Collaborative)
+                | (Self::Autonomous, AutonomyMode::Autonomous)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleDecision {
+    Allow,
+    Approve,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyRule {
+    pub name: String,
+    pub capability: PolicyCapability,
+    pub tool: Option<String>,
+    #[serde(default)]
+    pub modes: Vec<PolicyMode>,
+    pub decision: RuleDecision,
+    pub quorum: Option<u32>,
+}
+
+impl PolicyRule {
+    fn matches(&self, mode: &AutonomyMode, class: CapabilityClass, tool: Option<&str>) -> bool {
+        self.capability.matches(class)
+            && (self.modes.is_empty() || self.modes.iter().any(|m| m.matches(mode)))
+            && self
+                .tool
+                .as_deref()
+                .is_none_or(|wanted| tool == Some(wanted))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StandingGrant {
+    pub name: String,
+    pub tool: String,
+    pub reason: String,
+    /// Last day (UTC, `YYYY-MM-DD`) on which the grant applies.
+    pub expires: Option<String>,
+}
+
+impl StandingGrant {
+    fn active_on(&self, today: i64) -> bool {
+        match self.expires.as_deref().map(parse_day) {
+            Some(Ok(last_day)) => today <= last_day,
+            Some(Err(_)) => false,
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct YoloGuardrails {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub max_minutes: Option<u64>,
+    /// Capabilities that still need approval while YOLO is active.
+    #[serde(default)]
+    pub always_approve: Vec<PolicyCapability>,
+}
+
+impl Default for YoloGuardrails {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_minutes: None,
+            always_approve: Vec::new(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyFile {
+    pub version: u32,
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+    #[serde(default)]
+    pub standing_grants: Vec<StandingGrant>,
+    #[serde(default)]
+    pub yolo: YoloGuardrails,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyDecision {
+    Allow,
+    RequireApproval { quorum: u32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct LintIssue {
+    pub level: LintLevel,
+    pub message: String,
+}
+
+impl PolicyFile {
+    pub fn parse(raw: &str) -> Result<Self> {
+        Ok(toml::from_str(raw)?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read policy {}", path.display()))?;
+        Self::parse(&raw).with_context(|| format!("invalid policy {}", path.display()))
+    }
+
+    pub fn lint(&self) -> Vec<LintIssue> {
+        let mut issues = Vec::new();
+        let mut error = |message: String| {
+            issues.push(LintIssue {
+                level: LintLevel::Error,
+                message,
+            })
+        };
+        if self.version != POLICY_FILE_VERSION {
+            error(format!(
+                "unsupported policy version {} (expected {POLICY_FILE_VERSION})",
+                self.version
+            ));
+        }
+        let mut names = std::collections::HashSet::new();
+        for rule in &self.rules {
+            if rule.name.trim().is_empty() {
+                error("rule with empty name".to_string());
+            } else if !names.insert(rule.name.as_str()) {
+                error(format!("duplicate rule name '{}'", rule.name));
+            }
+            match (rule.decision, rule.quorum) {
+                (RuleDecision::Allow, Some(_)) => error(format!(
+                    "rule '{}': quorum needs decision = \"approve\"",
+                    rule.name
+                )),
+                (_, Some(q)) if q == 0 || q > MAX_QUORUM => error(format!(
+                    "rule '{}': quorum must be between 1 and {MAX_QUORUM}",
+                    rule.name
+                )),
+                _ => {}
+            }
+        }
+        for grant in &self.standing_grants {
+            if grant.reason.trim().is_empty() {
+                error(format!("standing grant '{}' needs a reason", grant.name));
+            }
+            if let Some(expires) = grant.expires.as_deref()
+                && parse_day(expires).is_err()
+            {
+                error(format!(
+                    "standing grant '{}': expires must be YYYY-MM-DD",
+                    grant.name
+                ));
+            }
+        }
+        if self.yolo.max_minutes == Some(0) {
+            error("yolo.max_minutes must be greater than 0".to_string());
+        }
+
+        let known = ToolRegistry::with_defaults();
+        let mut warning = |message: String| {
+            issues.push(LintIssue {
+                level: LintLevel::Warning,
+                message,
+            })
+        };
+        for rule in &self.rules {
+            if let Some(tool) = rule.tool.as_deref() {
+                match known.get(tool) {
+                    Some(descriptor) if !rule.capability.matches(descriptor.class) => {
+                        warning(format!(
+                            "rule '{}': tool '{tool}' is {}, so this rule never matches it",
+                            rule.name,
+                            descriptor.class.as_str()
+                        ))
+                    }
+                    None => warning(format!(
+                        "rule '{}': '{tool}' is not a built-in tool; check connector or skill names",
+                        rule.name
+                    )),
+                    _ => {}
+                }
+            }
+        }
+        let today = today_utc();
+        for grant in &self.standing_grants {
+            if known.get(&grant.tool).is_none() {
+                warning(format!(
+                    "standing grant '{}': '{}' is not a built-in tool",
+                    grant.name, grant.tool
+                ));
+            }
+            if grant
+                .expires
+                .as_deref()
+                .is_some_and(|e| parse_day(e).is_ok())
+                && !grant.active_on(today)
+            {
+                warning(format!("standing grant '{}' has expired", grant.name));
+            }
+        }
+        issues
+    }
+
+    // Order: YOLO, then standing grants, then the first matching rule, then the built-in
+    // mode defaults. Rules can only tighten or loosen approval; nothing here blocks a tool.
+    pub fn evaluate(
+        &self,
+        mode: &AutonomyMode,
+        risk_mode: ToolRiskMode,
+        class: CapabilityClass,
+        tool: Option<&str>,
+    ) -> PolicyDecision {
+        if matches!(risk_mode, ToolRiskMode::Yolo)
+            && self.yolo.enabled
+            && !self.yolo.always_approve.iter().any(|c| c.matches(class))
+        {
+            return PolicyDecision::Allow;
+        }
+        let today = today_utc();
+        if let Some(tool) = tool
+            && self
+                .standing_grants
+                .iter()
+                .any(|grant| grant.tool == tool && grant.active_on(today))
+        {
+            return PolicyDecision::Allow;
+        }
+        if let Some(rule) = self.rules.iter().find(|r| r.matches(mode, class, tool)) {
+            return match rule.decision {
+                RuleDecision::Allow => PolicyDecision::Allow,
+                RuleDecision::Approve => PolicyDecision::RequireApproval {
+                    quorum: rule.quorum.unwrap_or(1),
+                },
+            };
+        }
+        builtin_decision(mode, class)
+    }
+}
+
+pub(crate) fn builtin_decision(mode: &AutonomyMode, class: CapabilityClass) -> PolicyDecision {
+    let needs_approval = match mode {
+        AutonomyMode::Supervised => true,
+        AutonomyMode::Collaborative => !matches!(class, CapabilityClass::Read),
+        AutonomyMode::Autonomous => false,
+    };
+    if needs_approval {
+        PolicyDecision::RequireApproval { quorum: 1 }
+    } else {
+        PolicyDecision::Allow
+    }
+}
+
+struct ActivePolicy {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    policy: PolicyFile,
+    last_reload_error: Option<String>,
+}
+
+static ACTIVE_POLICY: RwLock<Option<ActivePolicy>> = RwLock::new(None);
+
+/// `policy.toml` next to the config file unless `security.policy_file` points elsewhere.
+pub fn policy_path_for(config: &TitanConfig) -> PathBuf {
+    config.security.policy_file.clone().unwrap_or_else(|| {
+        TitanConfig::resolve_path()
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("policy.toml")
+    })
+}
+
+fn load_checked(path: &Path) -> Result<PolicyFile> {
+    let policy = PolicyFile::load(path)?;
+    let errors: Vec<String> = policy
+        .lint()
+        .into_iter()
+        .filter(|issue| issue.level == LintLevel::Error)
+        .map(|issue| issue.message)
+        .collect();
+    if !errors.is_empty() {
+        bail!(
+            "policy {} is invalid: {}",
+            path.display(),
+            errors.join("; ")
+        );
+    }
+    Ok(policy)
+}
+
+pub(crate) fn install(path: &Path) -> Result<()> {
+    let policy = load_checked(path)?;
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut guard = ACTIVE_POLICY.write().unwrap_or_else(|e| e.into_inner());
+    *guard = Some(ActivePolicy {
+        path: path.to_path_buf(),
+        modified,
+        policy,
+        last_reload_error: None,
+    });
+    Ok(())
+}
+
+pub(crate) fn clear() {
+    let mut guard = ACTIVE_POLICY.write().unwrap_or_else(|e| e.into_inner());
+    *guard = None;
+}
+
+// Hot reload: a changed file is re-read on the next decision. An invalid edit keeps the
+// last good policy in force and is reported through `PolicyStatus::last_reload_error`.
+fn reload_if_changed() {
+    let (path, known) = {
+        let guard = ACTIVE_POLICY.read().unwrap_or_else(|e| e.into_inner());
+        match guard.as_ref() {
+            Some(active) => (active.path.clone(), active.modified),
+            None => return,
+        }
+    };
+    let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+    if modified == known {
+        return;
+    }
+    let reloaded = load_checked(&path);
+    let mut guard = ACTIVE_POLICY.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(active) = guard.as_mut() {
+        active.modified = modified;
+        match reloaded {
+            Ok(policy) => {
+                active.policy = policy;
+                active.last_reload_error = None;
+            }
+            Err(err) => active.last_reload_error = Some(err.to_string()),
+        }
+    }
+}
+
+pub(crate) fn with_active<T>(f: impl FnOnce(Option<&PolicyFile>) -> T) -> T {
+    reload_if_changed();
+    let guard = ACTIVE_POLICY.read().unwrap_or_else(|e| e.into_inner());
+    f(guard.as_ref().map(|active| &active.policy))
+}
+
+static APPROVALS: RwLock<Option<ApprovalsConfig>> = RwLock::new(None);
+
+pub(crate) fn install_approvals(config: &ApprovalsConfig) {
+    let mut guard = APPROVALS.write().unwrap_or_else(|e| e.into_inner());
+    *guard = Some(config.clone());
+}
+
+// Processes that never load a config use the `[approvals]` defaults: five-minute TTLs, any
+// approver, and no two-person rule.
+pub(crate) fn approval_ttl_ms(capability: &str) -> u64 {
+    with_approvals(|config| config.ttl_ms_for(capability))
+}
+
+pub(crate) fn with_approvals<T>(f: impl FnOnce(&ApprovalsConfig) -> T) -> T {
+    let guard = APPROVALS.read().unwrap_or_else(|e| e.into_inner());
+    match guard.as_ref() {
+        Some(config) => f(config),
+        None => f(&ApprovalsConfig::default()),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PolicyStatus {
+    pub path: PathBuf,
+    pub rules: usize,
+    pub standing_grants: usize,
+    pub last_reload_error: Option<String>,
+}
+
+pub(crate) fn status() -> Option<PolicyStatus> {
+    reload_if_changed();
+    let guard = ACTIVE_POLICY.read().unwrap_or_else(|e| e.into_inner());
+    guard.as_ref().map(|active| PolicyStatus {
+        path: active.path.clone(),
+        rules: active.policy.rules.len(),
+        standing_grants: active.policy.standing_grants.len(),
+        last_reload_error: active.last_reload_error.clone(),
+    })
+}
+
+fn today_utc() -> i64 {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    (secs / 86_400) as i64
+}
+
+// Days since 1970-01-01 for a proleptic Gregorian `YYYY-MM-DD`.
+fn parse_day(value: &str) -> Result<i64> {
+    let parts: Vec<&str> = value.trim().split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        bail!("expected YYYY-MM-DD, got '{value}'");
+    };
+    let (year, month, day): (i64, i64, i64) = (year.parse()?, month.parse()?, day.parse()?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        bail!("date out of range: '{value}'");
+    }
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Ok(era * 146_097 + doe - 719_468)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+        version = 1
+
+        [[rules]]
+        name = "two-person-exec"
+        capability = "exec"
+        decision = "approve"
+        quorum = 2
+
+        [[rules]]
+        name = "collab-net-ok"
+        capability = "net"
+        modes = ["collaborative"]
+        decision = "allow"
+
+        [[standing_grants]]
+        name = "docs-writer"
+        tool = "write_file"
+        reason = "docs bot"
+
+        [yolo]
+        always_approve = ["exec"]
+    "#;
+
+    #[test]
+    fn rules_grants_and_yolo_guardrails_drive_decisions() {
+        let policy = PolicyFile::parse(SAMPLE).expect("parse");
+        assert!(policy.lint().is_empty());
+        let collab = AutonomyMode::Collaborative;
+        assert_eq!(
+            policy.evaluate(&collab, ToolRiskMode::Secure, CapabilityClass::Exec, None),
+            PolicyDecision::RequireApproval { quorum: 2 }
+        );
+        assert_eq!(
+            policy.evaluate(&collab, ToolRiskMode::Yolo, CapabilityClass::Exec, None),
+            PolicyDecision::RequireApproval { quorum: 2 }
+        );
+        assert_eq!(
+            policy.evaluate(&collab, ToolRiskMode::Secure, CapabilityClass::Net, None),
+            PolicyDecision::Allow
+        );
+        assert_eq!(
+            policy.evaluate(
+                &AutonomyMode::Supervised,
+                ToolRiskMode::Secure,
+                CapabilityClass::Net,
+                None
+            ),
+            PolicyDecision::RequireApproval { quorum: 1 }
+        );
+        assert_eq!(
+            policy.evaluate(
+                &collab,
+                ToolRiskMode::Secure,
+                CapabilityClass::Write,
+                Some("write_file")
+            ),
+            PolicyDecision::Allow
+        );
+    }
+
+    #[test]
+    fn approval_ttls_use_capability_overrides_then_the_default() {
+        assert_eq!(approval_ttl_ms("exec"), 300_000);
+        let config: ApprovalsConfig = toml::from_str(
+            r#"
+            default_ttl_ms = 120000
+
+            [ttl_ms]
+            exec = 600000
+            write = 3600000
+            "#,
+        )
+        .expect("parse");
+        install_approvals(&config);
+        assert_eq!(approval_ttl_ms("exec"), 600_000);
+        assert_eq!(approval_ttl_ms("write"), 3_600_000);
+        assert_eq!(approval_ttl_ms("net"), 120_000);
+    }
+
+    #[test]
+    fn lint_reports_schema_problems() {
+        let policy = PolicyFile::parse(
+            r#"
+            version = 2
+
+            [[rules]]
+            name = "dup"
+            capability = "write"
+            decision = "allow"
+            quorum = 2
+
+            [[rules]]
+            name = "dup"
+            capability = "read"
+            tool = "write_file"
+            decision = "approve"
+
+            [[standing_grants]]
+            name = "old"
+            tool = "write_file"
+            reason = "legacy"
+            expires = "2020-01-01"
+            "#,
+        )
+        .expect("parse");
+        let issues = policy.lint();
+        let errors = issues
+            .iter()
+            .filter(|i| i.level == LintLevel::Error)
+            .count();
+        let warnings = issues
+            .iter()
+            .filter(|i| i.level == LintLevel::Warning)
+            .count();
+        assert_eq!(errors, 3);
+        assert_eq!(warnings, 2);
+        assert!(
+            PolicyFile::parse(
+                "version = 1\n[[rules]]\nname = \"x\"\ncapability = \"root\"\ndecision = \"allow\""
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn edited_policy_file_is_hot_reloaded() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("policy.toml");
+        std::fs::write(&path, "version = 1\n").expect("write");
+        install(&path).expect("install");
+        let collab = AutonomyMode::Collaborative;
+        let decide = || {
+            with_active(|p| {
+                p.expect("active").evaluate(
+                    &collab,
+                    ToolRiskMode::Secure,
+                    CapabilityClass::Read,
+                    None,
+                )
+            })
+        };
+        assert_eq!(decide(), PolicyDecision::Allow);
+
+        let strict = "version = 1\n[[rules]]\nname = \"reads\"\ncapability = \"read\"\ndecision = \"approve\"\n";
+        std::fs::write(&path, strict).expect("write");
+        let bump = SystemTime::now() + std::time::Duration::from_secs(5);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|f| f.set_modified(bump))
+            .expect("mtime");
+        assert_eq!(decide(), PolicyDecision::RequireApproval { quorum: 1 });
+
+        std::fs::write(&path, "version = 9\n").expect("write");
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|f| f.set_modified(bump + std::time::Duration::from_secs(5)))
+            .expect("mtime");
+        assert_eq!(decide(), PolicyDecision::RequireApproval { quorum: 1 });
+        assert!(status().expect("status").last_reload_error.is_some());
+        clear();
+    }
+}
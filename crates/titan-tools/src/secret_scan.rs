@@ -0,0 +1,144 @@
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// A credential-looking match in content about to be written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretFinding {
+    /// Pattern name, e.g. `aws_access_key` or `private_key`.
+    pub kind: &'static str,
+    /// 1-based line of the match.
+    pub line: usize,
+}
+
+/// Why a write was refused; callers downcast to it to record `secret_write_blocked` traces.
+/// The matched text itself is never kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretWriteBlocked {
+    /// File path for `write_file`, or the command for `git commit`.
+    pub target: String,
+    pub findings: Vec<SecretFinding>,
+}
+
+impl SecretWriteBlocked {
+    /// Detail of the `secret_write_blocked` trace for a call to `tool_name`.
+    pub fn trace_detail(&self, tool_name: &str) -> String {
+        format!(
+            "tool={tool_name} target={} findings={}",
+            self.target,
+            self.findings_summary()
+        )
+    }
+
+    fn findings_summary(&self) -> String {
+        self.findings
+            .iter()
+            .map(|finding| format!("{}@{}", finding.kind, finding.line))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+impl fmt::Display for SecretWriteBlocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "write to {} blocked: possible secrets ({}); approve an override to write it anyway",
+            self.target,
+            self.findings_summary()
+        )
+    }
+}
+
+impl std::error::Error for SecretWriteBlocked {}
+
+fn patterns() -> &'static [(&'static str, Regex)] {
+    static PATTERNS: OnceLock<Vec<(&'static str, Regex)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            ("private_key", r"-----BEGIN (?:[A-Z0-9]+ )*PRIVATE KEY-----"),
+            ("aws_access_key", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+            (
+                "github_token",
+                r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})",
+            ),
+            ("slack_token", r"\bxox[abposr]-[A-Za-z0-9-]{10,}"),
+            ("openai_key", r"\bsk-(?:proj-)?[A-Za-z0-9_-]{20,}"),
+            ("stripe_key", r"\b[rs]k_live_[A-Za-z0-9]{16,}"),
+            ("google_api_key", r"\bAIza[0-9A-Za-z_-]{35}"),
+            (
+                "generic_secret",
+                r#"(?i)\b(?:api[_-]?key|secret[_-]?key|client[_-]?secret|password|passwd|access[_-]?token|auth[_-]?token)\b["']?\s*[:=]\s*["']?[A-Za-z0-9/+_\-]{16,}"#,
+            ),
+        ]
+        .into_iter()
+        .map(|(kind, pattern)| (kind, Regex::new(pattern).expect("valid secret pattern")))
+        .collect()
+    })
+}
+
+/// Lines of `content` matching a known credential pattern, at most one finding per pattern
+/// and line.
+pub fn scan_for_secrets(content: &str) -> Vec<SecretFinding> {
+    let mut findings = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        for (kind, pattern) in patterns() {
+            if pattern.is_match(line) {
+                findings.push(SecretFinding {
+                    kind,
+                    line: index + 1,
+                });
+            }
+        }
+    }
+    findings
+}
+
+/// Checks what a `write_file` or `git commit` call would write. `input` is the tool input as
+/// the executor sees it; `root` is the workspace the command runs in.
+pub fn check_secret_write(tool_name: &str, input: &str, root: &Path) -> Option<SecretWriteBlocked> {
+    let (target, content) = match tool_name {
+        "write_file" => {
+            let (path, content) = input.split_once("::")?;
+            (path.trim().to_string(), content.to_string())
+        }
+        "run_command" => (input.trim().to_string(), commit_content(input, root)?),
+        _ => return None,
+    };
+    let findings = scan_for_secrets(&content);
+    (!findings.is_empty()).then_some(SecretWriteBlocked { target, findings })
+}
+
+// Added lines a `git commit` would record: the index, or the whole working tree for `-a`.
+fn commit_content(command: &str, root: &Path) -> Option<String> {
+    let args = shlex::split(command)?;
+    if args.first().map(String::as_str) != Some("git")
+        || args.get(1).map(String::as_str) != Some("commit")
+    {
+        return None;
+    }
+    let all = args[2..].iter().any(|arg| {
+        arg == "--all" || (arg.starts_with('-') && !arg.starts_with("--") && arg.contains('a'))
+    });
+    let diff_args: &[&str] = if all {
+        &["diff", "--no-color", "-U0", "HEAD"]
+    } else {
+        &["diff", "--no-color", "-U0", "--cached"]
+    };
+    let output = Command::new("git")
+        .args(diff_args)
+        .current_dir(root)
+        .output()
+        .ok()?;
+    let diff = String::from_utf8_lossy(&output.stdout);
+    Some(
+        diff.lines()
+            .filter(|line| line.starts_with('+') && !line.starts_with("+++"))
+            .map(|line| &line[1..])
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}